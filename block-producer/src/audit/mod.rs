@@ -0,0 +1,202 @@
+//! Audit of the store's state, before the block producer accepts transactions.
+//!
+//! The handshake with the store only verifies the chain the store serves, see
+//! [NodeInfo::verify]. On startup, the block producer also verifies the latest block of that chain
+//! can be built upon: the store's in-memory state must agree with its latest header, the header
+//! must carry the protocol parameters of the genesis block, and the block producer's clock must not
+//! be behind it. A misconfigured node is refused before any transaction is accepted, instead of
+//! failing on its first block.
+use std::fmt::{Display, Formatter};
+
+use miden_node_proto::{
+    domain::NodeInfo,
+    errors::ParseError,
+    responses::{GetChainCountersResponse, GetStartupAuditResponse},
+};
+use miden_objects::{BlockHeader, Digest};
+
+use crate::errors::StartupAuditError;
+
+#[cfg(test)]
+mod tests;
+
+// CHAIN COUNTERS
+// ================================================================================================
+
+/// Totals of the chain reported by the store, as of its latest block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainCounters {
+    pub block_num: u32,
+    pub block_hash: Digest,
+    /// Root of the account tree held by the store.
+    pub account_root: Digest,
+    pub num_accounts: u64,
+    pub num_notes: u64,
+    pub num_nullifiers: u64,
+    /// Number of the suspicious blocks applied since the store started.
+    pub num_suspicious_blocks: u64,
+    pub num_block_anomalies: u64,
+    /// Block number and digest of the latest state commitment published by the store, if any.
+    pub commitment: Option<(u32, Digest)>,
+    /// Number of the state commitments whose roots diverged from their block since the store
+    /// started.
+    pub num_diverged_commitments: u64,
+}
+
+impl TryFrom<GetChainCountersResponse> for ChainCounters {
+    type Error = ParseError;
+
+    fn try_from(counters: GetChainCountersResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block_num: counters.block_num,
+            block_hash: counters.block_hash.ok_or(ParseError::ProtobufMissingData)?.try_into()?,
+            account_root: counters
+                .account_root
+                .ok_or(ParseError::ProtobufMissingData)?
+                .try_into()?,
+            num_accounts: counters.num_accounts,
+            num_notes: counters.num_notes,
+            num_nullifiers: counters.num_nullifiers,
+            num_suspicious_blocks: counters.num_suspicious_blocks,
+            num_block_anomalies: counters.num_block_anomalies,
+            commitment: match counters.commitment {
+                Some(commitment) => Some((counters.commitment_block_num, commitment.try_into()?)),
+                None => None,
+            },
+            num_diverged_commitments: counters.num_diverged_commitments,
+        })
+    }
+}
+
+impl From<ChainCounters> for GetChainCountersResponse {
+    fn from(counters: ChainCounters) -> Self {
+        Self {
+            block_num: counters.block_num,
+            block_hash: Some(counters.block_hash.into()),
+            account_root: Some(counters.account_root.into()),
+            num_accounts: counters.num_accounts,
+            num_notes: counters.num_notes,
+            num_nullifiers: counters.num_nullifiers,
+            num_suspicious_blocks: counters.num_suspicious_blocks,
+            num_block_anomalies: counters.num_block_anomalies,
+            commitment_block_num: counters.commitment.map_or(0, |(block_num, _)| block_num),
+            commitment: counters.commitment.map(|(_, digest)| digest.into()),
+            num_diverged_commitments: counters.num_diverged_commitments,
+        }
+    }
+}
+
+// STARTUP AUDIT
+// ================================================================================================
+
+/// Summary of the store's state the block producer started with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartupAudit {
+    /// Latest block of the chain.
+    pub chain_tip: BlockHeader,
+    /// Counters of the store, `None` if they could not be read, see
+    /// [crate::store::DefaultStore::audit].
+    pub counters: Option<ChainCounters>,
+    /// Time at which the audit passed, in seconds since the UNIX epoch.
+    pub audited_at: u64,
+}
+
+impl StartupAudit {
+    /// Audits the latest block of the chain `chain_tip` and the store's `counters`, against the
+    /// `genesis` block of the chain described by `node_info`. The store's state is only checked
+    /// against `chain_tip` if the `counters` are given.
+    ///
+    /// `now` is the current time of the block producer, in seconds since the UNIX epoch.
+    pub fn new(
+        node_info: &NodeInfo,
+        genesis: &BlockHeader,
+        chain_tip: BlockHeader,
+        counters: Option<ChainCounters>,
+        now: u64,
+    ) -> Result<Self, StartupAuditError> {
+        if genesis.hash() != node_info.genesis_hash {
+            return Err(StartupAuditError::GenesisHashMismatch {
+                expected: node_info.genesis_hash,
+                actual: genesis.hash(),
+            });
+        }
+
+        // the block producer builds every block with the version of its parent
+        if chain_tip.version() != genesis.version() {
+            return Err(StartupAuditError::BlockVersionMismatch {
+                block_num: chain_tip.block_num(),
+                genesis_version: genesis.version().as_int(),
+                version: chain_tip.version().as_int(),
+            });
+        }
+
+        if let Some(counters) = &counters {
+            // the block producer is the only writer of the store, no block is applied in between
+            if counters.block_num != chain_tip.block_num()
+                || counters.block_hash != chain_tip.hash()
+            {
+                return Err(StartupAuditError::ChainTipMismatch {
+                    header_block_num: chain_tip.block_num(),
+                    counters_block_num: counters.block_num,
+                });
+            }
+
+            if counters.account_root != chain_tip.account_root() {
+                return Err(StartupAuditError::AccountRootMismatch {
+                    block_num: chain_tip.block_num(),
+                    header_root: chain_tip.account_root(),
+                    store_root: counters.account_root,
+                });
+            }
+        }
+
+        // blocks are timestamped with the clock of the block producer, and must not go back in
+        // time
+        let timestamp = chain_tip.timestamp().as_int();
+        if timestamp > now {
+            return Err(StartupAuditError::ClockBehindChainTip {
+                block_num: chain_tip.block_num(),
+                timestamp,
+                now,
+            });
+        }
+
+        Ok(Self {
+            chain_tip,
+            counters,
+            audited_at: now,
+        })
+    }
+}
+
+impl Display for StartupAudit {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ block_num: {}, block_hash: {}, account_root: {}, version: {}",
+            self.chain_tip.block_num(),
+            self.chain_tip.hash(),
+            self.chain_tip.account_root(),
+            self.chain_tip.version(),
+        ))?;
+        match &self.counters {
+            Some(counters) => f.write_fmt(format_args!(
+                ", num_accounts: {}, num_notes: {}, num_nullifiers: {} }}",
+                counters.num_accounts, counters.num_notes, counters.num_nullifiers,
+            )),
+            None => f.write_str(", counters: None }"),
+        }
+    }
+}
+
+impl From<&StartupAudit> for GetStartupAuditResponse {
+    fn from(audit: &StartupAudit) -> Self {
+        Self {
+            chain_tip: Some(audit.chain_tip.into()),
+            counters: audit.counters.map(Into::into),
+            audited_at: audit.audited_at,
+        }
+    }
+}
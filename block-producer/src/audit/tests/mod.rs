@@ -0,0 +1,184 @@
+use miden_node_proto::PROTOCOL_VERSION;
+use miden_objects::{Felt, ONE, ZERO};
+
+use super::*;
+
+// HELPERS
+// ================================================================================================
+
+const GENESIS_TIMESTAMP: u64 = 1_700_000_000;
+
+fn header(
+    prev: Option<&BlockHeader>,
+    account_root: Digest,
+    version: Felt,
+    timestamp: u64,
+) -> BlockHeader {
+    BlockHeader::new(
+        prev.map(BlockHeader::hash).unwrap_or_default(),
+        prev.map(|prev| prev.block_num() + 1).unwrap_or_default(),
+        Digest::default(),
+        account_root,
+        Digest::default(),
+        Digest::default(),
+        Digest::default(),
+        Digest::default(),
+        version,
+        timestamp.into(),
+    )
+}
+
+fn node_info(genesis: &BlockHeader) -> NodeInfo {
+    NodeInfo {
+        protocol_version: PROTOCOL_VERSION,
+        genesis_hash: genesis.hash(),
+        version: "0.1.0".to_string(),
+        chain_id: 1,
+        tag_policy: Default::default(),
+    }
+}
+
+fn counters(chain_tip: &BlockHeader) -> ChainCounters {
+    ChainCounters {
+        block_num: chain_tip.block_num(),
+        block_hash: chain_tip.hash(),
+        account_root: chain_tip.account_root(),
+        num_accounts: 2,
+        num_notes: 5,
+        num_nullifiers: 3,
+        num_suspicious_blocks: 0,
+        num_block_anomalies: 0,
+        commitment: None,
+        num_diverged_commitments: 0,
+    }
+}
+
+// TESTS
+// ================================================================================================
+
+/// Tests that a chain tip consistent with the store and the genesis block passes the audit
+#[test]
+fn test_audit_succeeds() {
+    let genesis = header(None, Digest::default(), ONE, GENESIS_TIMESTAMP);
+    let account_root = Digest::new([ONE, ZERO, ZERO, ZERO]);
+    let chain_tip = header(Some(&genesis), account_root, ONE, GENESIS_TIMESTAMP + 10);
+
+    let audit = StartupAudit::new(
+        &node_info(&genesis),
+        &genesis,
+        chain_tip,
+        Some(counters(&chain_tip)),
+        GENESIS_TIMESTAMP + 20,
+    )
+    .unwrap();
+
+    assert_eq!(audit.chain_tip, chain_tip);
+    assert_eq!(audit.counters.unwrap().num_nullifiers, 3);
+    assert_eq!(audit.audited_at, GENESIS_TIMESTAMP + 20);
+
+    // the genesis block alone is a valid chain
+    StartupAudit::new(
+        &node_info(&genesis),
+        &genesis,
+        genesis,
+        Some(counters(&genesis)),
+        GENESIS_TIMESTAMP,
+    )
+    .unwrap();
+
+    // the counters of a remote store can't be read without an operator key, the chain tip is
+    // audited alone
+    let audit =
+        StartupAudit::new(&node_info(&genesis), &genesis, chain_tip, None, GENESIS_TIMESTAMP + 20)
+            .unwrap();
+    assert_eq!(audit.counters, None);
+}
+
+/// Tests that a chain tip diverging from the genesis block, or from the store's in-memory state,
+/// fails the audit
+#[test]
+fn test_audit_detects_mismatches() {
+    let genesis = header(None, Digest::default(), ONE, GENESIS_TIMESTAMP);
+    let chain_tip = header(Some(&genesis), Digest::default(), ONE, GENESIS_TIMESTAMP + 10);
+    let now = GENESIS_TIMESTAMP + 20;
+
+    // the store reports another genesis block than the one of its chain
+    let other_genesis = header(None, Digest::default(), ONE, GENESIS_TIMESTAMP + 1);
+    assert_eq!(
+        StartupAudit::new(
+            &node_info(&other_genesis),
+            &genesis,
+            chain_tip,
+            Some(counters(&chain_tip)),
+            now
+        ),
+        Err(StartupAuditError::GenesisHashMismatch {
+            expected: other_genesis.hash(),
+            actual: genesis.hash(),
+        })
+    );
+
+    // the chain tip was built with another version of the block header
+    let upgraded_tip = header(Some(&genesis), Digest::default(), Felt::new(2), GENESIS_TIMESTAMP);
+    assert_eq!(
+        StartupAudit::new(
+            &node_info(&genesis),
+            &genesis,
+            upgraded_tip,
+            Some(counters(&upgraded_tip)),
+            now
+        ),
+        Err(StartupAuditError::BlockVersionMismatch {
+            block_num: 1,
+            genesis_version: 1,
+            version: 2,
+        })
+    );
+
+    // the counters of the store are taken at another block
+    assert_eq!(
+        StartupAudit::new(&node_info(&genesis), &genesis, chain_tip, Some(counters(&genesis)), now),
+        Err(StartupAuditError::ChainTipMismatch {
+            header_block_num: 1,
+            counters_block_num: 0,
+        })
+    );
+
+    // the in-memory account tree of the store diverged from its database
+    let store_root = Digest::new([ONE, ONE, ZERO, ZERO]);
+    let diverged = ChainCounters {
+        account_root: store_root,
+        ..counters(&chain_tip)
+    };
+    assert_eq!(
+        StartupAudit::new(&node_info(&genesis), &genesis, chain_tip, Some(diverged), now),
+        Err(StartupAuditError::AccountRootMismatch {
+            block_num: 1,
+            header_root: Digest::default(),
+            store_root,
+        })
+    );
+}
+
+/// Tests that a block producer whose clock is behind the latest block fails the audit, its blocks
+/// would go back in time
+#[test]
+fn test_audit_detects_clock_behind_chain_tip() {
+    let genesis = header(None, Digest::default(), ONE, GENESIS_TIMESTAMP);
+    let chain_tip = header(Some(&genesis), Digest::default(), ONE, GENESIS_TIMESTAMP + 10);
+
+    assert_eq!(
+        StartupAudit::new(
+            &node_info(&genesis),
+            &genesis,
+            chain_tip,
+            Some(counters(&chain_tip)),
+            GENESIS_TIMESTAMP + 5
+        ),
+        Err(StartupAuditError::ClockBehindChainTip {
+            block_num: 1,
+            timestamp: GENESIS_TIMESTAMP + 10,
+            now: GENESIS_TIMESTAMP + 5,
+        })
+    );
+}
@@ -1,12 +1,14 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use miden_crypto::hash::blake::{Blake3Digest, Blake3_256};
-use miden_objects::{accounts::AccountId, notes::NoteEnvelope, Digest};
+use miden_node_utils::formatting::format_array;
+use miden_objects::{accounts::AccountId, notes::NoteEnvelope, transaction::TransactionId, Digest};
 use miden_vm::crypto::SimpleSmt;
-use tracing::instrument;
+use tracing::{instrument, warn};
 
+use super::prover::BatchProof;
 use crate::{
-    errors::BuildBatchError, ProvenTransaction, CREATED_NOTES_SMT_DEPTH,
+    errors::BuildBatchError, ProvenTransaction, COMPONENT, CREATED_NOTES_SMT_DEPTH,
     MAX_NUM_CREATED_NOTES_PER_BATCH,
 };
 
@@ -15,18 +17,31 @@ pub type BatchId = Blake3Digest<32>;
 // TRANSACTION BATCH
 // ================================================================================================
 
-/// A batch of transactions that share a common proof. For any given account, at most 1 transaction
-/// in the batch must be addressing that account (issue: #186).
+/// A batch of transactions that share a common proof.
 ///
-/// Note: Until recursive proofs are available in the Miden VM, we don't include the common proof.
+/// Several transactions of an account may be part of the batch, each one starting from the final
+/// state of the previous one. The batch then updates the account from the initial state of the
+/// first transaction to the final state of the last one.
+///
+/// The batch is proven once built if the batch builder has a
+/// [BatchProver](super::prover::BatchProver), see the [prover](super::prover) module.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TransactionBatch {
     id: BatchId,
+    tx_ids: Vec<TransactionId>,
     updated_accounts: BTreeMap<AccountId, AccountStates>,
     produced_nullifiers: Vec<Digest>,
     created_notes_smt: SimpleSmt<CREATED_NOTES_SMT_DEPTH>,
     /// The notes stored `created_notes_smt`
     created_notes: Vec<NoteEnvelope>,
+    /// Versions of the transaction kernels the transactions were proven with
+    tx_kernel_versions: BTreeSet<u32>,
+    /// Public notes consumed by the transactions, as `(note_hash, nullifier)` links
+    consumed_notes: Vec<(Digest, Digest)>,
+    /// Hashes of the created notes whose details are private, see [Self::with_private_notes]
+    private_notes: Vec<Digest>,
+    /// Proof of the batch, if it was proven
+    proof: Option<BatchProof>,
 }
 
 impl TransactionBatch {
@@ -37,25 +52,31 @@ impl TransactionBatch {
     ///
     /// # Errors
     /// Returns an error if:
-    /// - The number of created notes across all transactions exceeds 4096.
+    /// - A transaction consumes or creates the same note as a previous transaction in `txs`, or
+    ///   doesn't start from the final state of the previous transaction of its account. The
+    ///   conflicting transactions are rejected, the error contains the remaining ones.
+    /// - The number of created notes across all transactions exceeds
+    ///   [MAX_NUM_CREATED_NOTES_PER_BATCH], the notes would not fit in the created notes tree.
     ///
     /// TODO: enforce limit on the number of created nullifiers.
     #[instrument(target = "miden-block-producer", name = "new_batch", skip_all, err)]
     pub fn new(txs: Vec<ProvenTransaction>) -> Result<Self, BuildBatchError> {
+        let txs = Self::reject_conflicting_transactions(txs)?;
+
         let id = Self::compute_id(&txs);
+        let tx_ids = txs.iter().map(|tx| tx.id()).collect();
 
-        let updated_accounts = txs
-            .iter()
-            .map(|tx| {
-                (
-                    tx.account_id(),
-                    AccountStates {
-                        initial_state: tx.initial_account_hash(),
-                        final_state: tx.final_account_hash(),
-                    },
-                )
-            })
-            .collect();
+        // the transactions of an account are chained, see `reject_conflicting_transactions`
+        let mut updated_accounts: BTreeMap<AccountId, AccountStates> = BTreeMap::new();
+        for tx in txs.iter() {
+            updated_accounts
+                .entry(tx.account_id())
+                .and_modify(|states| states.final_state = tx.final_account_hash())
+                .or_insert(AccountStates {
+                    initial_state: tx.initial_account_hash(),
+                    final_state: tx.final_account_hash(),
+                });
+        }
 
         let produced_nullifiers = txs
             .iter()
@@ -67,31 +88,91 @@ impl TransactionBatch {
             let created_notes: Vec<NoteEnvelope> =
                 txs.iter().flat_map(|tx| tx.output_notes().iter()).cloned().collect();
 
-            if created_notes.len() > MAX_NUM_CREATED_NOTES_PER_BATCH {
-                return Err(BuildBatchError::TooManyNotesCreated(created_notes.len(), txs));
+            // every note takes two leaves, the bound is checked on the leaf indices themselves so
+            // no key out of the tree can reach it
+            let mut leaves = Vec::with_capacity(2 * created_notes.len());
+            for (note_index, note_envelope) in created_notes.iter().enumerate() {
+                let Some((id_leaf, metadata_leaf)) = created_note_leaf_indices(note_index) else {
+                    return Err(BuildBatchError::TooManyNotesCreated(created_notes.len(), txs));
+                };
+                leaves.push((id_leaf, note_envelope.note_id().into()));
+                leaves.push((metadata_leaf, note_envelope.metadata().into()));
             }
 
-            // TODO: document under what circumstances SMT creating can fail
-            (
-                created_notes.clone(),
-                SimpleSmt::<CREATED_NOTES_SMT_DEPTH>::with_contiguous_leaves(
-                    created_notes.into_iter().flat_map(|note_envelope| {
-                        [note_envelope.note_id().into(), note_envelope.metadata().into()]
-                    }),
-                )
-                .map_err(|e| BuildBatchError::NotesSmtError(e, txs))?,
-            )
+            // the leaf indices are unique and within the tree, see `created_note_leaf_indices`
+            let created_notes_smt = SimpleSmt::<CREATED_NOTES_SMT_DEPTH>::with_leaves(leaves)
+                .map_err(|e| BuildBatchError::NotesSmtError(e, txs))?;
+
+            (created_notes, created_notes_smt)
         };
 
         Ok(Self {
             id,
+            tx_ids,
             updated_accounts,
             produced_nullifiers,
             created_notes_smt,
             created_notes,
+            tx_kernel_versions: BTreeSet::new(),
+            consumed_notes: Vec::new(),
+            private_notes: Vec::new(),
+            proof: None,
         })
     }
 
+    /// Records the versions of the transaction kernels the transactions of the batch were proven
+    /// with.
+    pub fn with_tx_kernel_versions(
+        mut self,
+        tx_kernel_versions: BTreeSet<u32>,
+    ) -> Self {
+        self.tx_kernel_versions = tx_kernel_versions;
+        self
+    }
+
+    /// Links the public notes consumed by the transactions of the batch to their nullifiers, as
+    /// `(note_hash, nullifier)` pairs.
+    ///
+    /// The links whose nullifier isn't produced by the batch are dropped, e.g. the ones of the
+    /// transactions rejected when the batch was built.
+    pub fn with_consumed_notes(
+        mut self,
+        consumed_notes: Vec<(Digest, Digest)>,
+    ) -> Self {
+        self.consumed_notes = consumed_notes
+            .into_iter()
+            .filter(|(_, nullifier)| self.produced_nullifiers.contains(nullifier))
+            .collect();
+        self
+    }
+
+    /// Marks the created notes whose hashes are `private_notes` as private, their details are
+    /// then never served by the store.
+    ///
+    /// The hashes of the notes which aren't created by the batch are dropped, e.g. the ones of the
+    /// transactions rejected when the batch was built.
+    pub fn with_private_notes(
+        mut self,
+        private_notes: Vec<Digest>,
+    ) -> Self {
+        self.private_notes = private_notes
+            .into_iter()
+            .filter(|note_hash| {
+                self.created_notes.iter().any(|note| note.note_id().inner() == *note_hash)
+            })
+            .collect();
+        self
+    }
+
+    /// Attaches the `proof` of the batch.
+    pub fn with_proof(
+        mut self,
+        proof: BatchProof,
+    ) -> Self {
+        self.proof = Some(proof);
+        self
+    }
+
     // PUBLIC ACCESSORS
     // --------------------------------------------------------------------------------------------
 
@@ -100,6 +181,11 @@ impl TransactionBatch {
         self.id
     }
 
+    /// Returns an iterator over the IDs of the transactions in this batch.
+    pub fn transaction_ids(&self) -> impl Iterator<Item = TransactionId> + '_ {
+        self.tx_ids.iter().copied()
+    }
+
     /// Returns an iterator over (account_id, init_state_hash) tuples for accounts that were
     /// modified in this transaction batch.
     pub fn account_initial_states(&self) -> impl Iterator<Item = (AccountId, Digest)> + '_ {
@@ -131,9 +217,119 @@ impl TransactionBatch {
         self.created_notes_smt.root()
     }
 
+    /// Returns the versions of the transaction kernels the transactions were proven with.
+    pub fn tx_kernel_versions(&self) -> impl Iterator<Item = u32> + '_ {
+        self.tx_kernel_versions.iter().copied()
+    }
+
+    /// Returns the `(note_hash, nullifier)` links of the public notes consumed by the batch.
+    pub fn consumed_notes(&self) -> impl Iterator<Item = (Digest, Digest)> + '_ {
+        self.consumed_notes.iter().copied()
+    }
+
+    /// Returns the hashes of the private notes created by the batch.
+    pub fn private_notes(&self) -> impl Iterator<Item = Digest> + '_ {
+        self.private_notes.iter().copied()
+    }
+
+    /// Returns the proof of the batch, or `None` if it wasn't proven.
+    pub fn proof(&self) -> Option<&BatchProof> {
+        self.proof.as_ref()
+    }
+
+    /// Returns `true` if `self` updates an account to the state `other` starts from, `other` must
+    /// then follow `self`, see [order_transitions].
+    pub fn precedes(
+        &self,
+        other: &TransactionBatch,
+    ) -> bool {
+        self.updated_accounts.iter().any(|(account_id, states)| {
+            states.initial_state != states.final_state
+                && other
+                    .updated_accounts
+                    .get(account_id)
+                    .is_some_and(|other_states| other_states.initial_state == states.final_state)
+        })
+    }
+
     // HELPER FUNCTIONS
     // --------------------------------------------------------------------------------------------
 
+    /// Ensures no two transactions consume the same note, nor create the same note, and that the
+    /// transactions of an account start from the final state of the previous one.
+    ///
+    /// Transactions are kept in order, and a transaction conflicting with a previously kept one is
+    /// rejected, so the same transactions are always rejected for a given ordering.
+    fn reject_conflicting_transactions(
+        txs: Vec<ProvenTransaction>
+    ) -> Result<Vec<ProvenTransaction>, BuildBatchError> {
+        let mut nullifiers = BTreeSet::new();
+        let mut note_ids = BTreeSet::new();
+        let mut duplicate_nullifiers = Vec::new();
+        let mut duplicate_notes = Vec::new();
+        let mut account_states: BTreeMap<AccountId, Digest> = BTreeMap::new();
+        let mut broken_chains = Vec::new();
+        let mut accepted = Vec::with_capacity(txs.len());
+        let mut rejected = Vec::new();
+
+        for tx in txs {
+            let tx_nullifiers: Vec<Digest> =
+                tx.input_notes().iter().map(|nullifier| nullifier.inner()).collect();
+            let tx_note_ids: Vec<Digest> =
+                tx.output_notes().iter().map(|note| note.note_id().inner()).collect();
+
+            let tx_duplicate_nullifiers: Vec<Digest> = tx_nullifiers
+                .iter()
+                .filter(|nullifier| nullifiers.contains(*nullifier))
+                .copied()
+                .collect();
+            let tx_duplicate_notes: Vec<Digest> = tx_note_ids
+                .iter()
+                .filter(|note_id| note_ids.contains(*note_id))
+                .copied()
+                .collect();
+
+            let breaks_chain = account_states
+                .get(&tx.account_id())
+                .is_some_and(|&final_state| final_state != tx.initial_account_hash());
+
+            if tx_duplicate_nullifiers.is_empty() && tx_duplicate_notes.is_empty() && !breaks_chain
+            {
+                nullifiers.extend(tx_nullifiers);
+                note_ids.extend(tx_note_ids);
+                account_states.insert(tx.account_id(), tx.final_account_hash());
+                accepted.push(tx);
+            } else {
+                warn!(
+                    target: COMPONENT,
+                    tx_id = %tx.id().to_hex(),
+                    duplicate_nullifiers = %format_array(&tx_duplicate_nullifiers),
+                    duplicate_notes = %format_array(&tx_duplicate_notes),
+                    breaks_chain,
+                    "Transaction rejected, it conflicts with a previous transaction of the batch"
+                );
+                duplicate_nullifiers.extend(tx_duplicate_nullifiers);
+                duplicate_notes.extend(tx_duplicate_notes);
+                if breaks_chain {
+                    broken_chains.push(tx.account_id());
+                }
+                rejected.push(tx);
+            }
+        }
+
+        if !rejected.is_empty() {
+            return Err(BuildBatchError::ConflictingTransactions {
+                duplicate_nullifiers,
+                duplicate_notes,
+                broken_chains,
+                rejected,
+                txs: accepted,
+            });
+        }
+
+        Ok(accepted)
+    }
+
     fn compute_id(txs: &[ProvenTransaction]) -> BatchId {
         let mut buf = Vec::with_capacity(32 * txs.len());
         for tx in txs {
@@ -143,8 +339,59 @@ impl TransactionBatch {
     }
 }
 
-/// Stores the initial state (before the transaction) and final state (after the transaction) of an
-/// account.
+/// Returns the indices of the leaves of the created notes tree of a batch holding the ID and the
+/// metadata of the `note_index`-th note of the batch, or `None` if the note doesn't fit in the
+/// tree, i.e. the batch creates more than [MAX_NUM_CREATED_NOTES_PER_BATCH] notes.
+pub(crate) fn created_note_leaf_indices(note_index: usize) -> Option<(u64, u64)> {
+    if note_index >= MAX_NUM_CREATED_NOTES_PER_BATCH {
+        return None;
+    }
+
+    let id_leaf = 2 * note_index as u64;
+    Some((id_leaf, id_leaf + 1))
+}
+
+/// Returns `true` if `tx` updates its account to the state `other` starts from, `other` must then
+/// follow `tx`, see [order_transitions].
+///
+/// A transaction which leaves its account unchanged precedes no other transaction.
+pub(crate) fn tx_precedes(
+    tx: &ProvenTransaction,
+    other: &ProvenTransaction,
+) -> bool {
+    tx.account_id() == other.account_id()
+        && tx.initial_account_hash() != tx.final_account_hash()
+        && tx.final_account_hash() == other.initial_account_hash()
+}
+
+/// Moves the items of `items` which must follow another item after it, as told by `precedes`, the
+/// order of `items` being kept otherwise.
+///
+/// The transactions of an account are applied in the order of the state transitions they make, be
+/// they ordered by arrival or by id. Items preceding each other in a cycle are kept in order.
+pub(crate) fn order_transitions<T>(
+    items: Vec<T>,
+    precedes: impl Fn(&T, &T) -> bool,
+) -> Vec<T> {
+    let mut remaining = items;
+    let mut ordered = Vec::with_capacity(remaining.len());
+    while !remaining.is_empty() {
+        let next = (0..remaining.len())
+            .find(|&index| {
+                !remaining
+                    .iter()
+                    .enumerate()
+                    .any(|(other, item)| other != index && precedes(item, &remaining[index]))
+            })
+            .unwrap_or(0);
+        ordered.push(remaining.remove(next));
+    }
+
+    ordered
+}
+
+/// Stores the initial state (before the transactions) and final state (after the transactions) of
+/// an account.
 ///
 /// TODO: should this be moved into domain objects?
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
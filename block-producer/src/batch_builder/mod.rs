@@ -1,19 +1,33 @@
-use std::{cmp::min, sync::Arc, time::Duration};
+use std::{
+    cmp::min,
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
+use miden_objects::Digest;
 use tokio::{sync::RwLock, time};
-use tracing::{debug, info, instrument, Span};
+use tracing::{debug, info, instrument, warn, Span};
 
-use crate::{block_builder::BlockBuilder, ProvenTransaction, SharedRwVec, COMPONENT};
+use crate::{
+    block_builder::BlockBuilder, latency::LatencyTracker, restart::BlockProduction,
+    ProvenTransaction, SharedRwVec, COMPONENT,
+};
 
 #[cfg(test)]
 mod tests;
 
 pub mod batch;
 pub use batch::TransactionBatch;
+pub mod prover;
 use miden_node_utils::formatting::{format_array, format_blake3_digest};
 
-use crate::errors::BuildBatchError;
+use self::prover::BatchProver;
+use crate::errors::{ApplyBlockError, BuildBatchError, BuildBlockError};
 
 // BATCH BUILDER
 // ================================================================================================
@@ -29,10 +43,28 @@ use crate::errors::BuildBatchError;
 /// - distributed prover on another machine
 #[async_trait]
 pub trait BatchBuilder: Send + Sync + 'static {
-    /// Start proving of a new batch.
+    /// Start proving of a new batch of `txs`, proven with the transaction kernels of
+    /// `tx_kernel_versions`, whose consumed public notes are linked to their nullifiers by
+    /// `consumed_notes`, see [TransactionBatch::with_consumed_notes], and whose created notes
+    /// with the hashes `private_notes` are private, see [TransactionBatch::with_private_notes].
     async fn build_batch(
         &self,
         txs: Vec<ProvenTransaction>,
+        tx_kernel_versions: BTreeSet<u32>,
+        consumed_notes: Vec<(Digest, Digest)>,
+        private_notes: Vec<Digest>,
+    ) -> Result<(), BuildBatchError>;
+
+    /// Adds `batch`, built and proven by an external batch producer from `txs`, to the batches
+    /// ready to be included in a block.
+    ///
+    /// The batch is rejected as a whole if the proof of one of `txs` is invalid. It is refused with
+    /// [BuildBatchError::ReadyBatchesFull] while the ready batches fill a block, so that the
+    /// external batches wait in the transaction queue rather than piling up here.
+    async fn add_batch(
+        &self,
+        batch: TransactionBatch,
+        txs: Vec<ProvenTransaction>,
     ) -> Result<(), BuildBatchError>;
 }
 
@@ -46,6 +78,9 @@ pub struct DefaultBatchBuilderOptions {
 
     /// Maximum number of batches in any given block
     pub max_batches_per_block: usize,
+
+    /// The time no block is built for once the store refused a block for its disk usage
+    pub read_only_store_pause: Duration,
 }
 
 pub struct DefaultBatchBuilder<BB> {
@@ -54,7 +89,22 @@ pub struct DefaultBatchBuilder<BB> {
 
     block_builder: Arc<BB>,
 
+    latency: Arc<LatencyTracker>,
+
     options: DefaultBatchBuilderOptions,
+
+    /// Time until which no block is built, set when the store is read-only
+    paused_until: Mutex<Option<Instant>>,
+
+    /// Held while a block is built, so that block production stops in between two blocks
+    building: tokio::sync::Mutex<()>,
+
+    /// Set once block production stopped, before the block producer exits
+    stopped: AtomicBool,
+
+    /// Prover of the batches, which also verifies the proofs of their transactions. The batches
+    /// are added to the blocks unproven if missing
+    prover: Option<Arc<dyn BatchProver>>,
 }
 
 impl<BB> DefaultBatchBuilder<BB>
@@ -67,20 +117,41 @@ where
     /// specified options.
     pub fn new(
         block_builder: Arc<BB>,
+        latency: Arc<LatencyTracker>,
         options: DefaultBatchBuilderOptions,
     ) -> Self {
         Self {
             ready_batches: Arc::new(RwLock::new(Vec::new())),
             block_builder,
+            latency,
             options,
+            paused_until: Mutex::new(None),
+            building: tokio::sync::Mutex::new(()),
+            stopped: AtomicBool::new(false),
+            prover: None,
         }
     }
 
+    /// Proves the batches with `prover` before they are added to the batches ready to be included
+    /// in a block.
+    pub fn with_prover(
+        mut self,
+        prover: Arc<dyn BatchProver>,
+    ) -> Self {
+        self.prover = Some(prover);
+        self
+    }
+
+    /// Returns the number of batches waiting to be included in a block.
+    pub async fn num_ready_batches(&self) -> usize {
+        self.ready_batches.read().await.len()
+    }
+
     // BATCH BUILDER STARTER
     // --------------------------------------------------------------------------------------------
 
     /// TODO: add comments
-    #[instrument(target = "miden-block-producer", name = "block_producer" skip_all)]
+    #[instrument(target = "miden-block-producer", name = "batch_builder", skip_all)]
     pub async fn run(self: Arc<Self>) {
         let mut interval = time::interval(self.options.block_frequency);
 
@@ -99,6 +170,25 @@ where
     /// A call to an empty `build_block()` indicates that an empty block should be created.
     #[instrument(target = "miden-block-producer", skip_all)]
     async fn try_build_block(&self) {
+        let _building = self.building.lock().await;
+        if self.stopped.load(Ordering::Relaxed) {
+            return;
+        }
+
+        // the batches wait in the queue while the store is read-only, rather than being proven
+        // into blocks it would refuse
+        {
+            let mut paused_until = self.paused_until.lock().expect("lock poisoned");
+            match *paused_until {
+                Some(until) if Instant::now() < until => return,
+                Some(_) => {
+                    info!(target: COMPONENT, "Resuming block production");
+                    *paused_until = None;
+                },
+                None => (),
+            }
+        }
+
         let mut batches_in_block: Vec<TransactionBatch> = {
             let mut locked_ready_batches = self.ready_batches.write().await;
 
@@ -112,12 +202,84 @@ where
             Ok(_) => {
                 // block successfully built, do nothing
             },
-            Err(_) => {
+            Err(err) => {
+                if let BuildBlockError::ApplyBlockFailed(ApplyBlockError::StoreReadOnly(_)) = err {
+                    warn!(
+                        target: COMPONENT,
+                        pause_s = self.options.read_only_store_pause.as_secs(),
+                        "Store is read-only, pausing block production"
+                    );
+                    *self.paused_until.lock().expect("lock poisoned") =
+                        Some(Instant::now() + self.options.read_only_store_pause);
+                }
+
                 // Block building failed; add back the batches at the end of the queue
                 self.ready_batches.write().await.append(&mut batches_in_block);
             },
         }
     }
+
+    /// Verifies the proofs of `txs` with `prover`, returning them along with the versions of the
+    /// transaction kernels they were proven with, or failing with the transactions whose proof is
+    /// invalid and the remaining ones.
+    async fn verify_tx_proofs(
+        &self,
+        prover: &dyn BatchProver,
+        txs: Vec<ProvenTransaction>,
+    ) -> Result<(Vec<ProvenTransaction>, BTreeSet<u32>), BuildBatchError> {
+        let invalid = match prover.verify_tx_proofs(&txs).await {
+            Ok(tx_kernel_versions) => return Ok((txs, tx_kernel_versions)),
+            Err(invalid) => invalid,
+        };
+
+        let (rejected, txs): (Vec<_>, Vec<_>) =
+            txs.into_iter().partition(|tx| invalid.contains(&tx.id()));
+        for tx in &rejected {
+            self.latency.rejected(tx.id());
+        }
+
+        Err(BuildBatchError::InvalidTransactionProofs { rejected, txs })
+    }
+
+    /// Adds `batch` to the batches ready to be included in a block.
+    async fn push_ready_batch(
+        &self,
+        batch: TransactionBatch,
+    ) {
+        self.latency.batched(batch.transaction_ids());
+
+        let num_batches = {
+            let mut write_guard = self.ready_batches.write().await;
+            write_guard.push(batch);
+            write_guard.len()
+        };
+
+        info!(target: COMPONENT, num_batches, "Transaction batch added to the batch queue");
+    }
+}
+
+#[async_trait]
+impl<BB> BlockProduction for DefaultBatchBuilder<BB>
+where
+    BB: BlockBuilder,
+{
+    async fn num_ready_batches(&self) -> usize {
+        DefaultBatchBuilder::num_ready_batches(self).await
+    }
+
+    async fn stop_block_production(&self) {
+        // waits for the block being built, if any, to be applied by the store
+        let _building = self.building.lock().await;
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    fn resume_block_production(&self) {
+        self.stopped.store(false, Ordering::Relaxed);
+    }
+
+    fn is_block_production_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
 }
 
 #[async_trait]
@@ -130,24 +292,87 @@ where
     async fn build_batch(
         &self,
         txs: Vec<ProvenTransaction>,
+        tx_kernel_versions: BTreeSet<u32>,
+        consumed_notes: Vec<(Digest, Digest)>,
+        private_notes: Vec<Digest>,
     ) -> Result<(), BuildBatchError> {
         let num_txs = txs.len();
 
         info!(target: COMPONENT, num_txs, "Building a transaction batch");
         debug!(target: COMPONENT, txs = %format_array(txs.iter().map(|tx| tx.id().to_hex())));
 
-        let batch = TransactionBatch::new(txs)?;
+        // the transactions with an invalid proof are rejected, the others are re-queued. Once
+        // verified, the transactions are recorded with the kernels their proofs were verified
+        // against rather than the ones their submissions declared
+        let (txs, tx_kernel_versions) = match &self.prover {
+            Some(prover) => self.verify_tx_proofs(prover.as_ref(), txs).await?,
+            None => (txs, tx_kernel_versions),
+        };
+
+        // the transactions are re-queued if proving the batch fails
+        let proven_txs = self.prover.as_ref().map(|_| txs.clone());
+
+        let mut batch = TransactionBatch::new(txs)
+            .map_err(|err| {
+                if let BuildBatchError::ConflictingTransactions { rejected, .. } = &err {
+                    for tx in rejected {
+                        self.latency.rejected(tx.id());
+                    }
+                }
+                err
+            })?
+            .with_tx_kernel_versions(tx_kernel_versions)
+            .with_consumed_notes(consumed_notes)
+            .with_private_notes(private_notes);
 
         info!(target: COMPONENT, "Transaction batch built");
         Span::current().record("batch_id", format_blake3_digest(batch.id()));
 
+        if let Some(prover) = &self.prover {
+            let proof = prover.prove_batch(&batch).await.map_err(|err| {
+                BuildBatchError::ProvingFailed(err, proven_txs.unwrap_or_default())
+            })?;
+            info!(target: COMPONENT, commitment = %proof.commitment().to_hex(), "Transaction batch proven");
+            batch = batch.with_proof(proof);
+        }
+
+        self.push_ready_batch(batch).await;
+
+        Ok(())
+    }
+
+    async fn add_batch(
+        &self,
+        mut batch: TransactionBatch,
+        txs: Vec<ProvenTransaction>,
+    ) -> Result<(), BuildBatchError> {
+        if self.ready_batches.read().await.len() >= self.options.max_batches_per_block {
+            return Err(BuildBatchError::ReadyBatchesFull(txs));
+        }
+
+        // the proof of the batch doesn't cover the proofs of its transactions, which are verified
+        // as the ones of the batches built here. The external batches are refused by the block
+        // producer without a prover, see `BlockProducerApi::with_proven_batches`
+        let txs = match &self.prover {
+            Some(prover) => {
+                let (txs, tx_kernel_versions) = self.verify_tx_proofs(prover.as_ref(), txs).await?;
+                batch = batch.with_tx_kernel_versions(tx_kernel_versions);
+                txs
+            },
+            None => txs,
+        };
+
         let num_batches = {
             let mut write_guard = self.ready_batches.write().await;
+            if write_guard.len() >= self.options.max_batches_per_block {
+                return Err(BuildBatchError::ReadyBatchesFull(txs));
+            }
+            self.latency.batched(batch.transaction_ids());
             write_guard.push(batch);
             write_guard.len()
         };
 
-        info!(target: COMPONENT, num_batches, "Transaction batch added to the batch queue");
+        info!(target: COMPONENT, num_batches, "External batch added to the batch queue");
 
         Ok(())
     }
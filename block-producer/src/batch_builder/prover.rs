@@ -0,0 +1,370 @@
+//! Proving of the transaction batches.
+//!
+//! A batch is proven by executing the batch kernel over the ids of its transactions and the root of
+//! its created notes, which outputs the commitment of the batch. The proof of that execution is
+//! attached to the batch, and the block commits to the proven batches in its batch root and proof
+//! hash, see [batch_root] and [proof_hash]. The store verifies the proofs of the batches before
+//! applying the block, and checks the notes they cover are the notes of the block, see
+//! [miden_node_store::batch_kernel].
+//!
+//! Note: A batch proof only attests the commitment of the batch to its transaction ids and created
+//! notes, it is not a recursive proof of the transactions. Until recursive proofs are available in
+//! the Miden VM, the proofs of the transactions are verified by the prover before the batch is
+//! built instead, see [BatchProver::verify_tx_proofs].
+use std::{collections::BTreeSet, sync::Arc};
+
+use async_trait::async_trait;
+use miden_air::ExecutionOptions;
+use miden_node_proto::TX_KERNEL_VERSION;
+use miden_node_store::batch_kernel::{
+    self, batch_kernel, kernel_stack_inputs, BatchKernelVerifier,
+};
+use miden_objects::{transaction::TransactionId, Digest, Felt, Word};
+use miden_tx::TransactionVerifier;
+use miden_vm::{
+    execute, prove, AdviceInputs, DefaultHost, MemAdviceProvider, Program, ProvingOptions,
+    StackInputs,
+};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use tokio::sync::oneshot;
+use tracing::warn;
+
+use crate::{
+    block_builder::prover::lower_thread_priority, config::ProverPriority, errors::BatchProverError,
+    ProvenTransaction, TransactionBatch, COMPONENT,
+};
+
+/// Security level the proofs of the transactions must reach, in bits.
+const TX_PROOF_SECURITY_LEVEL: u32 = 96;
+
+// BATCH PROOF
+// ================================================================================================
+
+/// Proof of the execution of the batch kernel over a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchProof {
+    /// Commitment to the transactions and created notes of the batch, output by the batch kernel
+    commitment: Digest,
+
+    /// Serialized proof of the batch kernel execution
+    proof: Vec<u8>,
+}
+
+impl BatchProof {
+    /// Returns the proof of a batch whose batch kernel execution output `commitment`, e.g. as
+    /// submitted by an external batch producer, see [BatchProofVerifier].
+    pub fn new(
+        commitment: Digest,
+        proof: Vec<u8>,
+    ) -> Self {
+        Self { commitment, proof }
+    }
+
+    pub fn commitment(&self) -> Digest {
+        self.commitment
+    }
+
+    pub fn proof(&self) -> &[u8] {
+        &self.proof
+    }
+
+    /// Returns the hash of the serialized proof, which the block commits to.
+    pub fn proof_hash(&self) -> Digest {
+        batch_kernel::batch_proof_hash(&self.proof)
+    }
+}
+
+/// Returns the batch root of the block made of `batches`, committing to the commitments and proof
+/// hashes of its proven batches, see [batch_kernel::batch_root].
+pub fn batch_root(batches: &[TransactionBatch]) -> Digest {
+    batch_kernel::batch_root(
+        batches
+            .iter()
+            .filter_map(TransactionBatch::proof)
+            .map(|proof| (proof.commitment(), proof.proof_hash())),
+    )
+}
+
+/// Returns the proof hash of the block made of `batches`, the hash of the proof hashes of its
+/// proven batches, see [batch_kernel::proof_hash].
+pub fn proof_hash(batches: &[TransactionBatch]) -> Digest {
+    batch_kernel::proof_hash(
+        batches.iter().filter_map(TransactionBatch::proof).map(BatchProof::proof_hash),
+    )
+}
+
+/// Returns the proofs of the proven `batches`, as verified by the store, along with their index in
+/// the block.
+pub fn block_batch_proofs(batches: &[TransactionBatch]) -> Vec<batch_kernel::BatchProof> {
+    batches
+        .iter()
+        .enumerate()
+        .filter_map(|(batch_index, batch)| {
+            batch.proof().map(|proof| batch_kernel::BatchProof {
+                commitment: proof.commitment(),
+                num_txs: batch.transaction_ids().count(),
+                created_notes_root: batch.created_notes_root(),
+                proof: proof.proof().to_vec(),
+                batch_index: batch_index as u32,
+            })
+        })
+        .collect()
+}
+
+// BATCH PROVER
+// ================================================================================================
+
+/// Abstraction over the proving of the batches, the proofs being produced in process or by a
+/// remote prover.
+#[async_trait]
+pub trait BatchProver: Send + Sync + 'static {
+    /// Verifies the proofs of `txs`, returning the versions of the transaction kernels they were
+    /// proven with, or the ids of the transactions whose proof is invalid.
+    async fn verify_tx_proofs(
+        &self,
+        txs: &[ProvenTransaction],
+    ) -> Result<BTreeSet<u32>, Vec<TransactionId>>;
+
+    async fn prove_batch(
+        &self,
+        batch: &TransactionBatch,
+    ) -> Result<BatchProof, BatchProverError>;
+}
+
+/// Verifier of the proofs of the transactions, before they are batched.
+pub trait TxProofVerifier: Send + Sync + 'static {
+    /// Verifies the proof of `tx`, returning the version of the transaction kernel it was proven
+    /// with.
+    fn verify_tx_proof(
+        &self,
+        tx: &ProvenTransaction,
+    ) -> Result<u32, String>;
+}
+
+/// Verifies the proofs of the transactions against the transaction kernel, whose version is
+/// [TX_KERNEL_VERSION].
+pub struct KernelTxProofVerifier {
+    verifier: TransactionVerifier,
+}
+
+impl KernelTxProofVerifier {
+    pub fn new() -> Self {
+        Self {
+            verifier: TransactionVerifier::new(TX_PROOF_SECURITY_LEVEL),
+        }
+    }
+}
+
+impl Default for KernelTxProofVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TxProofVerifier for KernelTxProofVerifier {
+    fn verify_tx_proof(
+        &self,
+        tx: &ProvenTransaction,
+    ) -> Result<u32, String> {
+        self.verifier.verify(tx.clone()).map_err(|err| err.to_string())?;
+        Ok(TX_KERNEL_VERSION)
+    }
+}
+
+/// Proves the batches on a pool of threads, keeping proving off the async runtime so the batches
+/// keep being assembled while others are proven.
+pub struct LocalBatchProver {
+    kernel: Arc<Program>,
+    proving_pool: ThreadPool,
+    tx_verifier: Arc<dyn TxProofVerifier>,
+}
+
+impl LocalBatchProver {
+    /// Returns a prover of the batches proving them on `workers` threads, run with
+    /// `prover_priority`.
+    pub fn new(
+        workers: usize,
+        prover_priority: ProverPriority,
+    ) -> Self {
+        let proving_pool = ThreadPoolBuilder::new()
+            .num_threads(workers)
+            .thread_name(|idx| format!("batch-prover-{idx}"))
+            .start_handler(move |_| {
+                if prover_priority == ProverPriority::Low {
+                    lower_thread_priority();
+                }
+            })
+            .build()
+            .expect("failed to create the batch proving threads");
+
+        Self {
+            kernel: Arc::new(batch_kernel()),
+            proving_pool,
+            tx_verifier: Arc::new(KernelTxProofVerifier::new()),
+        }
+    }
+
+    /// Verifies the proofs of the transactions with `tx_verifier`, instead of against the
+    /// transaction kernel.
+    pub fn with_tx_verifier(
+        mut self,
+        tx_verifier: Arc<dyn TxProofVerifier>,
+    ) -> Self {
+        self.tx_verifier = tx_verifier;
+        self
+    }
+}
+
+#[async_trait]
+impl BatchProver for LocalBatchProver {
+    async fn verify_tx_proofs(
+        &self,
+        txs: &[ProvenTransaction],
+    ) -> Result<BTreeSet<u32>, Vec<TransactionId>> {
+        let (sender, receiver) = oneshot::channel();
+        let txs = txs.to_vec();
+        let tx_verifier = self.tx_verifier.clone();
+        self.proving_pool.spawn(move || {
+            let mut versions = BTreeSet::new();
+            let mut invalid = Vec::new();
+            for tx in &txs {
+                match tx_verifier.verify_tx_proof(tx) {
+                    Ok(version) => {
+                        versions.insert(version);
+                    },
+                    Err(err) => {
+                        warn!(target: COMPONENT, tx_id = %tx.id().to_hex(), %err, "Invalid transaction proof");
+                        invalid.push(tx.id());
+                    },
+                }
+            }
+            let _ = sender.send(if invalid.is_empty() { Ok(versions) } else { Err(invalid) });
+        });
+
+        // the transactions are all rejected if the verification task failed
+        receiver.await.unwrap_or_else(|_| Err(txs.iter().map(|tx| tx.id()).collect()))
+    }
+
+    async fn prove_batch(
+        &self,
+        batch: &TransactionBatch,
+    ) -> Result<BatchProof, BatchProverError> {
+        let (stack_inputs, advice_inputs) = kernel_inputs(batch);
+
+        let (sender, receiver) = oneshot::channel();
+        let kernel = self.kernel.clone();
+        self.proving_pool.spawn(move || {
+            let _ = sender.send(prove_kernel(&kernel, stack_inputs, advice_inputs));
+        });
+
+        receiver.await.map_err(|_| BatchProverError::ProvingTaskFailed)?
+    }
+}
+
+// BATCH PROOF VERIFIER
+// ================================================================================================
+
+/// Verifies the proofs of the batches built and proven by external batch producers.
+///
+/// The batch kernel takes the ids of the transactions as advice, so a proof alone doesn't attest
+/// which transactions it covers. The commitment of the batch is obtained by executing the kernel
+/// over its transactions, and the proof must output that commitment.
+pub struct BatchProofVerifier {
+    kernel: Program,
+    verifier: BatchKernelVerifier,
+}
+
+impl BatchProofVerifier {
+    pub fn new() -> Self {
+        Self {
+            kernel: batch_kernel(),
+            verifier: BatchKernelVerifier::new(),
+        }
+    }
+
+    /// Returns the commitment output by the batch kernel executed over `batch`.
+    pub fn commitment(
+        &self,
+        batch: &TransactionBatch,
+    ) -> Result<Digest, BatchProverError> {
+        let (stack_inputs, advice_inputs) = kernel_inputs(batch);
+        let host = DefaultHost::new(MemAdviceProvider::from(advice_inputs));
+
+        let trace = execute(&self.kernel, stack_inputs, host, ExecutionOptions::default())
+            .map_err(BatchProverError::ExecutionFailed)?;
+        let commitment = trace
+            .stack_outputs()
+            .get_stack_word(0)
+            .ok_or(BatchProverError::InvalidCommitmentOutput)?;
+
+        Ok(commitment.into())
+    }
+
+    /// Verifies `proof` is a proof of the batch kernel executed over `batch`, failing with the
+    /// reason it isn't.
+    pub fn verify(
+        &self,
+        batch: &TransactionBatch,
+        proof: &BatchProof,
+    ) -> Result<(), String> {
+        let commitment = self.commitment(batch).map_err(|err| err.to_string())?;
+        if proof.commitment != commitment {
+            return Err(format!(
+                "commitment {} is not the commitment {} of the batch",
+                proof.commitment.to_hex(),
+                commitment.to_hex()
+            ));
+        }
+
+        self.verifier
+            .verify(&batch_kernel::BatchProof {
+                commitment,
+                num_txs: batch.transaction_ids().count(),
+                created_notes_root: batch.created_notes_root(),
+                proof: proof.proof.clone(),
+                // the batch isn't part of a block yet
+                batch_index: 0,
+            })
+            .map_err(|err| err.to_string())
+    }
+}
+
+impl Default for BatchProofVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Returns the inputs of the batch kernel proving `batch`.
+fn kernel_inputs(batch: &TransactionBatch) -> (StackInputs, AdviceInputs) {
+    let stack_inputs =
+        kernel_stack_inputs(batch.transaction_ids().count(), batch.created_notes_root());
+
+    let advice_stack: Vec<Felt> =
+        batch.transaction_ids().flat_map(|tx_id| Word::from(tx_id.inner())).collect();
+
+    (stack_inputs, AdviceInputs::default().with_stack(advice_stack))
+}
+
+fn prove_kernel(
+    kernel: &Program,
+    stack_inputs: StackInputs,
+    advice_inputs: AdviceInputs,
+) -> Result<BatchProof, BatchProverError> {
+    let host = DefaultHost::new(MemAdviceProvider::from(advice_inputs));
+
+    let (stack_outputs, proof) = prove(kernel, stack_inputs, host, ProvingOptions::default())
+        .map_err(BatchProverError::ProvingFailed)?;
+
+    let commitment = stack_outputs
+        .get_stack_word(0)
+        .ok_or(BatchProverError::InvalidCommitmentOutput)?;
+
+    Ok(BatchProof {
+        commitment: commitment.into(),
+        proof: proof.to_bytes(),
+    })
+}
@@ -1,5 +1,28 @@
-use super::*;
-use crate::{errors::BuildBlockError, test_utils::DummyProvenTxGenerator, TransactionBatch};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use miden_node_proto::TX_KERNEL_VERSION;
+use miden_objects::{
+    accounts::AccountId,
+    notes::{NoteEnvelope, NoteMetadata},
+    transaction::{InputNotes, OutputNotes},
+    Hasher, ONE,
+};
+use miden_vm::crypto::SimpleSmt;
+use proptest::prelude::*;
+
+use super::{
+    batch::{created_note_leaf_indices, order_transitions, tx_precedes},
+    prover::{LocalBatchProver, TxProofVerifier},
+    *,
+};
+use crate::{
+    config::ProverPriority,
+    errors::{ApplyBlockError, BuildBlockError},
+    test_utils::{
+        nullifier_by_index, DummyProvenTxGenerator, MockPrivateAccount, MockProvenTxBuilder,
+    },
+    TransactionBatch, CREATED_NOTES_SMT_DEPTH, MAX_NUM_CREATED_NOTES_PER_BATCH,
+};
 
 // STRUCTS
 // ================================================================================================
@@ -26,6 +49,23 @@ impl BlockBuilder for BlockBuilderSuccess {
     }
 }
 
+/// Rejects the proofs of the transactions of a single account, accepting the other ones
+struct TxProofVerifierRejecting {
+    account_id: Option<AccountId>,
+}
+
+impl TxProofVerifier for TxProofVerifierRejecting {
+    fn verify_tx_proof(
+        &self,
+        tx: &ProvenTransaction,
+    ) -> Result<u32, String> {
+        if Some(tx.account_id()) == self.account_id {
+            return Err("invalid proof".to_string());
+        }
+        Ok(TX_KERNEL_VERSION)
+    }
+}
+
 #[derive(Default)]
 struct BlockBuilderFailure;
 
@@ -39,6 +79,23 @@ impl BlockBuilder for BlockBuilderFailure {
     }
 }
 
+/// Fails every block as a store whose disk usage exceeds its limits does
+#[derive(Default)]
+struct BlockBuilderReadOnlyStore {
+    num_blocks: AtomicUsize,
+}
+
+#[async_trait]
+impl BlockBuilder for BlockBuilderReadOnlyStore {
+    async fn build_block(
+        &self,
+        _batches: &[TransactionBatch],
+    ) -> Result<(), BuildBlockError> {
+        self.num_blocks.fetch_add(1, Ordering::Relaxed);
+        Err(ApplyBlockError::StoreReadOnly(String::new()).into())
+    }
+}
+
 // TESTS
 // ================================================================================================
 
@@ -52,9 +109,11 @@ async fn test_block_size_doesnt_exceed_limit() {
 
     let batch_builder = Arc::new(DefaultBatchBuilder::new(
         block_builder.clone(),
+        Arc::new(LatencyTracker::new(10)),
         DefaultBatchBuilderOptions {
             block_frequency,
             max_batches_per_block,
+            read_only_store_pause: Duration::ZERO,
         },
     ));
 
@@ -97,9 +156,11 @@ async fn test_build_block_called_when_no_batches() {
 
     let batch_builder = Arc::new(DefaultBatchBuilder::new(
         block_builder.clone(),
+        Arc::new(LatencyTracker::new(10)),
         DefaultBatchBuilderOptions {
             block_frequency,
             max_batches_per_block,
+            read_only_store_pause: Duration::ZERO,
         },
     ));
 
@@ -124,9 +185,11 @@ async fn test_batches_added_back_to_queue_on_block_build_failure() {
 
     let batch_builder = Arc::new(DefaultBatchBuilder::new(
         block_builder.clone(),
+        Arc::new(LatencyTracker::new(10)),
         DefaultBatchBuilderOptions {
             block_frequency,
             max_batches_per_block,
+            read_only_store_pause: Duration::ZERO,
         },
     ));
 
@@ -155,6 +218,352 @@ async fn test_batches_added_back_to_queue_on_block_build_failure() {
     assert_eq!(internal_ready_batches.read().await.len(), 3);
 }
 
+/// Tests that no block is built for a while once the store is read-only, and that the batches are
+/// kept on the queue meanwhile
+#[tokio::test]
+async fn test_block_production_paused_while_store_read_only() {
+    let block_frequency = Duration::from_millis(20);
+
+    let block_builder = Arc::new(BlockBuilderReadOnlyStore::default());
+
+    let batch_builder = Arc::new(DefaultBatchBuilder::new(
+        block_builder.clone(),
+        Arc::new(LatencyTracker::new(10)),
+        DefaultBatchBuilderOptions {
+            block_frequency,
+            max_batches_per_block: 2,
+            read_only_store_pause: Duration::from_secs(3600),
+        },
+    ));
+
+    let internal_ready_batches = batch_builder.ready_batches.clone();
+    {
+        let tx_gen = DummyProvenTxGenerator::new();
+        batch_builder.ready_batches.write().await.push(dummy_tx_batch(&tx_gen, 2));
+    }
+
+    tokio::spawn(batch_builder.run());
+
+    // Wait for 4 blocks to be due
+    time::sleep(block_frequency * 4 + (block_frequency / 2)).await;
+
+    assert_eq!(block_builder.num_blocks.load(Ordering::Relaxed), 1);
+    assert_eq!(internal_ready_batches.read().await.len(), 1);
+}
+
+/// Tests that no block is built once block production stopped, and that it resumes afterwards
+#[tokio::test]
+async fn test_block_production_stopped() {
+    let block_frequency = Duration::from_millis(20);
+
+    let block_builder = Arc::new(BlockBuilderSuccess::default());
+
+    let batch_builder = Arc::new(DefaultBatchBuilder::new(
+        block_builder.clone(),
+        Arc::new(LatencyTracker::new(10)),
+        DefaultBatchBuilderOptions {
+            block_frequency,
+            max_batches_per_block: 2,
+            read_only_store_pause: Duration::ZERO,
+        },
+    ));
+
+    batch_builder.stop_block_production().await;
+    assert!(batch_builder.is_block_production_stopped());
+    {
+        let tx_gen = DummyProvenTxGenerator::new();
+        batch_builder.ready_batches.write().await.push(dummy_tx_batch(&tx_gen, 2));
+    }
+
+    tokio::spawn(batch_builder.clone().run());
+
+    // Wait for 2 blocks to be due
+    time::sleep(block_frequency * 2 + (block_frequency / 2)).await;
+
+    assert!(block_builder.batch_groups.read().await.is_empty());
+    assert_eq!(*block_builder.num_empty_batches_received.read().await, 0);
+    assert_eq!(batch_builder.num_ready_batches().await, 1);
+
+    batch_builder.resume_block_production();
+    time::sleep(block_frequency * 2).await;
+
+    assert_eq!(block_builder.batch_groups.read().await.len(), 1);
+}
+
+/// Tests that transactions consuming or creating the same notes as a previous transaction of the
+/// batch are rejected, and that the remaining transactions are returned to be batched again
+#[test]
+fn test_conflicting_transactions_are_rejected() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let accounts: Vec<MockPrivateAccount> = (0..4).map(MockPrivateAccount::from).collect();
+
+    let note = |account: &MockPrivateAccount, index: u8| {
+        NoteEnvelope::new(Hasher::hash(&[index]).into(), NoteMetadata::new(account.id, ONE))
+    };
+    let tx = |account: &MockPrivateAccount, nullifiers, notes| {
+        tx_gen.dummy_proven_tx_with_params(
+            account.id,
+            account.states[0],
+            account.states[1],
+            InputNotes::new(nullifiers).unwrap(),
+            OutputNotes::new(notes).unwrap(),
+        )
+    };
+
+    let txs = vec![
+        tx(&accounts[0], vec![nullifier_by_index(0)], vec![note(&accounts[0], 0)]),
+        // consumes the same note as the first transaction
+        tx(&accounts[1], vec![nullifier_by_index(0), nullifier_by_index(1)], Vec::new()),
+        // creates the same note as the first transaction
+        tx(&accounts[2], Vec::new(), vec![note(&accounts[0], 0)]),
+        // consumes the note of the rejected second transaction, which is not part of the batch
+        tx(&accounts[3], vec![nullifier_by_index(1)], vec![note(&accounts[3], 1)]),
+    ];
+    let tx_ids: Vec<_> = txs.iter().map(|tx| tx.id()).collect();
+
+    let Err(BuildBatchError::ConflictingTransactions {
+        duplicate_nullifiers,
+        duplicate_notes,
+        broken_chains,
+        rejected,
+        txs,
+    }) = TransactionBatch::new(txs)
+    else {
+        panic!("conflicting transactions must be rejected");
+    };
+
+    assert_eq!(duplicate_nullifiers, vec![nullifier_by_index(0).inner()]);
+    assert_eq!(duplicate_notes, vec![note(&accounts[0], 0).note_id().inner()]);
+    assert!(broken_chains.is_empty());
+    assert_eq!(
+        rejected.iter().map(|tx| tx.id()).collect::<Vec<_>>(),
+        vec![tx_ids[1], tx_ids[2]]
+    );
+    assert_eq!(txs.iter().map(|tx| tx.id()).collect::<Vec<_>>(), vec![tx_ids[0], tx_ids[3]]);
+
+    // the remaining transactions can be batched
+    let batch = TransactionBatch::new(txs).unwrap();
+    assert_eq!(batch.produced_nullifiers().count(), 2);
+    assert_eq!(batch.created_notes().count(), 2);
+}
+
+/// Tests that the chained transactions of an account are ordered and merged into a single update
+/// of the account, and that a transaction breaking the chain is rejected
+#[test]
+fn test_chained_transactions_are_merged() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account: MockPrivateAccount = MockPrivateAccount::from(0);
+
+    let tx = |initial: usize, last: usize| {
+        tx_gen.dummy_proven_tx_with_params(
+            account.id,
+            account.states[initial],
+            account.states[last],
+            InputNotes::new(Vec::new()).unwrap(),
+            OutputNotes::new(Vec::new()).unwrap(),
+        )
+    };
+
+    // the second transaction follows the first one, whichever the order they arrived in
+    let txs = order_transitions(vec![tx(1, 2), tx(0, 1)], tx_precedes);
+    assert_eq!(
+        txs.iter().map(|tx| tx.id()).collect::<Vec<_>>(),
+        vec![tx(0, 1).id(), tx(1, 2).id()]
+    );
+
+    // starts from the state the first transaction already modified
+    let conflicting = tx(0, 2);
+    let Err(BuildBatchError::ConflictingTransactions {
+        broken_chains,
+        rejected,
+        txs,
+        ..
+    }) = TransactionBatch::new(vec![txs[0].clone(), conflicting.clone(), txs[1].clone()])
+    else {
+        panic!("transactions breaking the chain of their account must be rejected");
+    };
+    assert_eq!(broken_chains, vec![account.id]);
+    assert_eq!(rejected.iter().map(|tx| tx.id()).collect::<Vec<_>>(), vec![conflicting.id()]);
+
+    let batch = TransactionBatch::new(txs).unwrap();
+    assert_eq!(
+        batch.account_initial_states().collect::<Vec<_>>(),
+        vec![(account.id, account.states[0])]
+    );
+    assert_eq!(
+        batch.updated_accounts().collect::<Vec<_>>(),
+        vec![(account.id, account.states[2])]
+    );
+}
+
+/// Tests that the batches are proven once built, the commitment of a batch only depending on its
+/// transactions, and that the batches record the kernel versions the transaction proofs were
+/// verified against rather than the declared ones
+#[tokio::test]
+async fn test_batches_are_proven() {
+    // the dummy transaction proofs are accepted
+    let prover = Arc::new(
+        LocalBatchProver::new(1, ProverPriority::Normal)
+            .with_tx_verifier(Arc::new(TxProofVerifierRejecting { account_id: None })),
+    );
+    let batch_builder = DefaultBatchBuilder::new(
+        Arc::new(BlockBuilderSuccess::default()),
+        Arc::new(LatencyTracker::new(10)),
+        DefaultBatchBuilderOptions {
+            block_frequency: Duration::from_millis(20),
+            max_batches_per_block: 1,
+            read_only_store_pause: Duration::ZERO,
+        },
+    )
+    .with_prover(prover.clone());
+
+    let tx_gen = DummyProvenTxGenerator::new();
+    let txs: Vec<_> = (0..2).map(|_| tx_gen.dummy_proven_tx()).collect();
+    let declared_versions = BTreeSet::from([TX_KERNEL_VERSION + 1]);
+    batch_builder
+        .build_batch(txs.clone(), declared_versions, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+
+    let batch = batch_builder.ready_batches.read().await[0].clone();
+    assert_eq!(batch.tx_kernel_versions().collect::<Vec<_>>(), vec![TX_KERNEL_VERSION]);
+    let proof = batch.proof().expect("built batches must be proven");
+    assert!(!proof.proof().is_empty());
+
+    let same_batch = TransactionBatch::new(txs).unwrap();
+    assert_eq!(prover.prove_batch(&same_batch).await.unwrap().commitment(), proof.commitment());
+
+    let other_batch = dummy_tx_batch(&tx_gen, 2);
+    assert_ne!(prover.prove_batch(&other_batch).await.unwrap().commitment(), proof.commitment());
+}
+
+/// Tests that the transactions with an invalid proof are rejected when their batch is built, and
+/// that a submitted batch holding one is rejected as a whole
+#[tokio::test]
+async fn test_invalid_tx_proofs_are_rejected() {
+    let txs: Vec<_> = (0..3).map(|_| MockProvenTxBuilder::new().build()).collect();
+    let prover = LocalBatchProver::new(1, ProverPriority::Normal).with_tx_verifier(Arc::new(
+        TxProofVerifierRejecting {
+            account_id: Some(txs[1].account_id()),
+        },
+    ));
+    let batch_builder = DefaultBatchBuilder::new(
+        Arc::new(BlockBuilderSuccess::default()),
+        Arc::new(LatencyTracker::new(10)),
+        DefaultBatchBuilderOptions {
+            block_frequency: Duration::from_millis(20),
+            max_batches_per_block: 1,
+            read_only_store_pause: Duration::ZERO,
+        },
+    )
+    .with_prover(Arc::new(prover));
+
+    let Err(BuildBatchError::InvalidTransactionProofs {
+        rejected,
+        txs: remaining,
+    }) = batch_builder
+        .build_batch(txs.clone(), BTreeSet::new(), Vec::new(), Vec::new())
+        .await
+    else {
+        panic!("a transaction with an invalid proof must be rejected");
+    };
+    assert_eq!(rejected, vec![txs[1].clone()]);
+    assert_eq!(remaining, vec![txs[0].clone(), txs[2].clone()]);
+
+    let batch = TransactionBatch::new(txs.clone()).unwrap();
+    let result = batch_builder.add_batch(batch, txs).await;
+    assert!(matches!(result, Err(BuildBatchError::InvalidTransactionProofs { .. })));
+    assert_eq!(batch_builder.num_ready_batches().await, 0);
+}
+
+/// Tests that the external batches are refused while the ready batches fill a block, and accepted
+/// again once a block was built
+#[tokio::test]
+async fn test_external_batches_wait_for_a_block() {
+    let batch_builder = DefaultBatchBuilder::new(
+        Arc::new(BlockBuilderSuccess::default()),
+        Arc::new(LatencyTracker::new(10)),
+        DefaultBatchBuilderOptions {
+            block_frequency: Duration::from_millis(20),
+            max_batches_per_block: 2,
+            read_only_store_pause: Duration::ZERO,
+        },
+    );
+    let tx_gen = DummyProvenTxGenerator::new();
+    let txs: Vec<_> = (0..3).map(|_| tx_gen.dummy_proven_tx()).collect();
+
+    batch_builder
+        .build_batch(vec![txs[0].clone()], BTreeSet::new(), Vec::new(), Vec::new())
+        .await
+        .unwrap();
+    let batch = TransactionBatch::new(vec![txs[1].clone()]).unwrap();
+    batch_builder.add_batch(batch, vec![txs[1].clone()]).await.unwrap();
+
+    let batch = TransactionBatch::new(vec![txs[2].clone()]).unwrap();
+    let result = batch_builder.add_batch(batch.clone(), vec![txs[2].clone()]).await;
+    let Err(BuildBatchError::ReadyBatchesFull(refused)) = result else {
+        panic!("the batch must wait while the ready batches fill a block, got {result:?}");
+    };
+    assert_eq!(refused, vec![txs[2].clone()]);
+    assert_eq!(batch_builder.num_ready_batches().await, 2);
+
+    batch_builder.try_build_block().await;
+    batch_builder.add_batch(batch, vec![txs[2].clone()]).await.unwrap();
+    assert_eq!(batch_builder.num_ready_batches().await, 1);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    /// Tests that the two leaves of every note of a batch are its own, and within the created
+    /// notes tree
+    #[test]
+    fn test_created_note_leaf_indices(note_index in 0..2 * MAX_NUM_CREATED_NOTES_PER_BATCH) {
+        match created_note_leaf_indices(note_index) {
+            Some((id_leaf, metadata_leaf)) => {
+                prop_assert!(note_index < MAX_NUM_CREATED_NOTES_PER_BATCH);
+                prop_assert_eq!(id_leaf, 2 * note_index as u64);
+                prop_assert_eq!(metadata_leaf, id_leaf + 1);
+                prop_assert!(metadata_leaf < 1 << CREATED_NOTES_SMT_DEPTH);
+            },
+            None => prop_assert!(note_index >= MAX_NUM_CREATED_NOTES_PER_BATCH),
+        }
+    }
+
+    /// Tests that random batches either fit their notes in the created notes tree, laid out as two
+    /// contiguous leaves per note, or are rejected for creating too many notes
+    #[test]
+    fn test_random_batches_created_notes_bounds(
+        num_notes in prop::collection::vec(0..(MAX_NUM_CREATED_NOTES_PER_BATCH as u64 / 2), 1..4)
+    ) {
+        let txs: Vec<_> = num_notes
+            .iter()
+            .map(|&num_notes| MockProvenTxBuilder::new().num_notes_created(num_notes).build())
+            .collect();
+        let total_notes = num_notes.iter().sum::<u64>() as usize;
+
+        match TransactionBatch::new(txs) {
+            Ok(batch) => {
+                prop_assert!(total_notes <= MAX_NUM_CREATED_NOTES_PER_BATCH);
+                prop_assert_eq!(batch.created_notes().count(), total_notes);
+
+                let expected = SimpleSmt::<CREATED_NOTES_SMT_DEPTH>::with_contiguous_leaves(
+                    batch.created_notes().flat_map(|note_envelope| {
+                        [note_envelope.note_id().into(), note_envelope.metadata().into()]
+                    }),
+                )
+                .unwrap();
+                prop_assert_eq!(batch.created_notes_root(), expected.root());
+            },
+            Err(BuildBatchError::TooManyNotesCreated(num_created, _)) => {
+                prop_assert!(total_notes > MAX_NUM_CREATED_NOTES_PER_BATCH);
+                prop_assert_eq!(num_created, total_notes);
+            },
+            Err(err) => prop_assert!(false, "unexpected error: {}", err),
+        }
+    }
+}
+
 // HELPERS
 // ================================================================================================
 
@@ -0,0 +1,39 @@
+//! Generates the test vectors of the block headers, see
+//! [miden_node_block_producer::test_utils::vectors].
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use miden_node_block_producer::test_utils::vectors::{generate, Script};
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Parser)]
+#[command(version, about = "Generates the test vectors of the block headers", long_about = None)]
+pub struct Cli {
+    /// Script of the blocks to build
+    #[arg(short, long, value_name = "FILE", default_value = "test-vectors.toml")]
+    pub script: PathBuf,
+
+    /// File the vectors are written to, as JSON, instead of the standard output
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let script = fs::read_to_string(&cli.script)
+        .with_context(|| format!("Failed to read script {}", cli.script.display()))?;
+    let script: Script = toml::from_str(&script).context("Failed to parse script")?;
+
+    let vectors = generate(&script).await?;
+    let vectors = serde_json::to_string_pretty(&vectors)?;
+
+    match cli.output {
+        Some(output) => fs::write(&output, vectors)
+            .with_context(|| format!("Failed to write vectors to {}", output.display()))?,
+        None => println!("{vectors}"),
+    }
+
+    Ok(())
+}
@@ -1,15 +1,42 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
-use miden_objects::{accounts::AccountId, notes::NoteEnvelope, BlockHeader, Digest};
+use miden_node_proto::domain::{BlockSignature, TimestampAttestation};
+use miden_node_store::batch_kernel::BatchProof;
+use miden_objects::{
+    accounts::{Account, AccountId},
+    notes::NoteEnvelope,
+    BlockHeader, Digest,
+};
+
+use crate::config::TxOrdering;
 
 #[derive(Debug, Clone)]
 pub struct Block {
     pub header: BlockHeader,
+    /// Identifier of the chain of the block, checked by the store
+    pub chain_id: u32,
     pub updated_accounts: Vec<(AccountId, Digest)>,
+    /// States of the public accounts of `updated_accounts` known to the block producer, published
+    /// by the store
+    pub account_details: Vec<Account>,
     pub created_notes: BTreeMap<u64, NoteEnvelope>,
     pub produced_nullifiers: Vec<Digest>,
+    /// Public notes consumed by the block, as `(note_hash, nullifier)` links
+    pub consumed_notes: Vec<(Digest, Digest)>,
+    /// Hashes of the notes of `created_notes` whose details are private
+    pub private_notes: BTreeSet<Digest>,
+    /// Versions of the transaction kernels the transactions of the block were proven with
+    pub tx_kernel_versions: BTreeSet<u32>,
+    /// Signature of the header by the block producer's identity key, if it has one
+    pub signature: Option<BlockSignature>,
+    /// Attestation of the time the previous block was produced by the time authority, if it
+    /// answered in time
+    pub timestamp_attestation: Option<TimestampAttestation>,
+    /// Order of the transactions within the block
+    pub tx_ordering: TxOrdering,
+    /// Proofs of the proven batches of the block, which the header commits to
+    pub batch_proofs: Vec<BatchProof>,
     // TODO:
-    // - full states for updated public accounts
     // - full states for created public notes
     // - zk proof
 }
@@ -1,20 +1,37 @@
-use std::sync::Arc;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
+use miden_crypto::dsa::rpo_falcon512::KeyPair;
+use miden_node_proto::domain::{BlockSignature, TimestampAttestation};
 use miden_node_utils::formatting::{format_array, format_blake3_digest};
-use miden_objects::{accounts::AccountId, Digest};
-use tracing::{debug, info, instrument};
+use miden_objects::{accounts::AccountId, BlockHeader, Digest};
+use tracing::{debug, info, instrument, warn};
 
 use crate::{
-    batch_builder::batch::TransactionBatch,
+    batch_builder::{
+        batch::{order_transitions, TransactionBatch},
+        prover::block_batch_proofs,
+    },
     block::Block,
-    errors::BuildBlockError,
+    config::{ProverPriority, TxOrdering},
+    dashboard::{BlockRecord, Dashboard},
+    errors::{ApplyBlockError, BuildBlockError},
+    journal::{now_ms, BlockJournal, JournalEntry},
+    latency::LatencyTracker,
+    notifier::{Event, Notifier},
+    quarantine::{BlockDiagnostics, BlockQuarantine},
     store::{ApplyBlock, Store},
+    time_authority::TimeAuthority,
+    txqueue::TransactionLog,
     COMPONENT, MAX_NUM_CREATED_NOTES_PER_BATCH,
 };
 
 pub(crate) mod prover;
-use self::prover::{block_witness::BlockWitness, BlockProver};
+use self::prover::{block_witness::BlockWitness, current_timestamp, BlockProver, LocalBlockProver};
 
 #[cfg(test)]
 mod tests;
@@ -35,11 +52,61 @@ pub trait BlockBuilder: Send + Sync + 'static {
     ) -> Result<(), BuildBlockError>;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefaultBlockBuilderOptions {
+    /// Number of threads dedicated to the block kernel execution and proving, zero uses one
+    /// thread per CPU
+    pub prover_threads: usize,
+
+    /// Scheduling priority of the proving threads
+    pub prover_priority: ProverPriority,
+
+    /// Identifier of the chain of the blocks, sent along with them to the store, and which their
+    /// signatures are bound to
+    pub chain_id: u32,
+}
+
 pub struct DefaultBlockBuilder<S, A> {
     store: Arc<S>,
     state_view: Arc<A>,
-    block_kernel: BlockProver,
+
+    /// Prover of the blocks, proving them on local threads unless a remote prover is configured
+    prover: Arc<dyn BlockProver>,
+
+    /// Diagnostics of the blocks which failed to be built
+    quarantine: Arc<BlockQuarantine>,
+
+    /// Time spent by the transactions in every stage until their block is committed
+    latency: Arc<LatencyTracker>,
+
+    /// Dashboard recording the committed and failed blocks, if enabled
+    dashboard: Option<Arc<Dashboard>>,
+
+    /// Identity key of the block producer signing the blocks, they are not signed if missing
+    identity_key: Option<Arc<KeyPair>>,
+
+    /// Identifier of the chain of the blocks
+    chain_id: u32,
+
+    /// Time authority attesting the time the blocks were produced, they are not attested if
+    /// missing
+    time_authority: Option<Arc<TimeAuthority>>,
+
+    /// Attestation of the latest applied block along with its hash, requested in the background
+    /// once the block was applied and carried by the next block
+    attestation: Arc<Mutex<Option<(Digest, TimestampAttestation)>>>,
+
+    /// Webhooks notified of the applied blocks and committed transactions, if enabled
+    notifier: Option<Arc<Notifier>>,
+
+    /// Order of the batches within the blocks
+    tx_ordering: TxOrdering,
+
+    /// Journal of the production attempts, if enabled
+    journal: Option<Arc<BlockJournal>>,
+
+    /// Log of the accepted transactions, whose committed transactions are finished, if enabled
+    tx_log: Option<Arc<TransactionLog>>,
 }
 
 impl<S, A> DefaultBlockBuilder<S, A>
@@ -50,35 +117,190 @@ where
     pub fn new(
         store: Arc<S>,
         state_view: Arc<A>,
+        quarantine: Arc<BlockQuarantine>,
+        latency: Arc<LatencyTracker>,
+        options: DefaultBlockBuilderOptions,
     ) -> Self {
         Self {
             store,
             state_view,
-            block_kernel: BlockProver::new(),
+            prover: Arc::new(LocalBlockProver::new(
+                options.prover_threads,
+                options.prover_priority,
+            )),
+            quarantine,
+            latency,
+            dashboard: None,
+            identity_key: None,
+            chain_id: options.chain_id,
+            time_authority: None,
+            attestation: Arc::new(Mutex::new(None)),
+            notifier: None,
+            tx_ordering: TxOrdering::default(),
+            journal: None,
+            tx_log: None,
         }
     }
-}
 
-#[async_trait]
-impl<S, A> BlockBuilder for DefaultBlockBuilder<S, A>
-where
-    S: Store,
-    A: ApplyBlock,
-{
-    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
-    #[instrument(target = "miden-block-producer", skip_all, err)]
-    async fn build_block(
+    /// Returns the prover of the blocks, proving them on the prover threads unless replaced with
+    /// [DefaultBlockBuilder::with_prover].
+    pub fn prover(&self) -> Arc<dyn BlockProver> {
+        self.prover.clone()
+    }
+
+    /// Proves the blocks with `prover`, e.g. a remote prover, instead of on the prover threads.
+    pub fn with_prover(
+        mut self,
+        prover: Arc<dyn BlockProver>,
+    ) -> Self {
+        self.prover = prover;
+        self
+    }
+
+    /// Orders the batches of the blocks as set by `tx_ordering`, instead of as they were built,
+    /// and records it along with the blocks.
+    pub fn with_tx_ordering(
+        mut self,
+        tx_ordering: TxOrdering,
+    ) -> Self {
+        self.tx_ordering = tx_ordering;
+        self
+    }
+
+    /// Signs the blocks with the block producer's `identity_key`.
+    pub fn with_identity_key(
+        mut self,
+        identity_key: Arc<KeyPair>,
+    ) -> Self {
+        self.identity_key = Some(identity_key);
+        self
+    }
+
+    /// Has the time the blocks were produced attested by the `time_authority`.
+    pub fn with_time_authority(
+        mut self,
+        time_authority: Arc<TimeAuthority>,
+    ) -> Self {
+        self.time_authority = Some(time_authority);
+        self
+    }
+
+    /// Records the committed and failed blocks on the `dashboard`.
+    pub fn with_dashboard(
+        mut self,
+        dashboard: Arc<Dashboard>,
+    ) -> Self {
+        self.dashboard = Some(dashboard);
+        self
+    }
+
+    /// Notifies the webhooks of the `notifier` of the applied blocks and committed transactions.
+    pub fn with_notifier(
+        mut self,
+        notifier: Arc<Notifier>,
+    ) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Records every production attempt in the `journal`.
+    pub fn with_journal(
+        mut self,
+        journal: Arc<BlockJournal>,
+    ) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Marks the transactions of the applied blocks finished in `tx_log`, they are no longer
+    /// admitted again on startup.
+    pub fn with_transaction_log(
+        mut self,
+        tx_log: Arc<TransactionLog>,
+    ) -> Self {
+        self.tx_log = Some(tx_log);
+        self
+    }
+
+    /// Proves the block with the prover, and waits for the result along with the time spent
+    /// proving.
+    async fn prove_block(
         &self,
-        batches: &[TransactionBatch],
-    ) -> Result<(), BuildBlockError> {
+        witness: BlockWitness,
+    ) -> Result<(BlockHeader, Duration), BuildBlockError> {
+        let start = Instant::now();
+        let result = self.prover.prove_block(witness, current_timestamp()).await;
+        let proving_time = start.elapsed();
+
         info!(
             target: COMPONENT,
-            num_batches = batches.len(),
-            batches = %format_array(batches.iter().map(|batch| format_blake3_digest(batch.id()))),
+            proving_time_ms = proving_time.as_millis(),
+            success = result.is_ok(),
+            "block kernel executed"
         );
 
-        let account_updates: Vec<(AccountId, Digest)> =
-            batches.iter().flat_map(|batch| batch.updated_accounts()).collect();
+        result.map(|header| (header, proving_time))
+    }
+
+    /// Requests the attestation of the applied block `block_hash` from the time authority, if
+    /// any, in the background.
+    ///
+    /// The attestation is optional, the next block is applied without it if the request failed or
+    /// is still pending.
+    fn request_attestation(
+        &self,
+        block_hash: Digest,
+    ) {
+        let Some(time_authority) = self.time_authority.clone() else {
+            return;
+        };
+        let attestation = self.attestation.clone();
+        tokio::spawn(async move {
+            match time_authority.attest(block_hash).await {
+                Ok(attested) => {
+                    *attestation.lock().expect("attestation lock poisoned") =
+                        Some((block_hash, attested))
+                },
+                Err(err) => {
+                    warn!(target: COMPONENT, %block_hash, %err, "Block timestamp not attested")
+                },
+            }
+        });
+    }
+
+    /// Returns the attestation of the block `prev_hash` a new block is built upon, if received.
+    fn parent_attestation(
+        &self,
+        prev_hash: Digest,
+    ) -> Option<TimestampAttestation> {
+        match &*self.attestation.lock().expect("attestation lock poisoned") {
+            Some((block_hash, attestation)) if *block_hash == prev_hash => {
+                Some(attestation.clone())
+            },
+            _ => None,
+        }
+    }
+
+    /// Builds a block out of `batches`, and sends it to the store.
+    ///
+    /// The data gathered along the way is kept in `diagnostics`, to be quarantined on failure.
+    async fn try_build_block(
+        &self,
+        batches: &[TransactionBatch],
+        diagnostics: &mut BlockDiagnostics,
+    ) -> Result<(), BuildBlockError> {
+        // an account updated by several batches ends in the final state of the last one
+        let mut account_updates: Vec<(AccountId, Digest)> = Vec::new();
+        let mut account_positions: BTreeMap<AccountId, usize> = BTreeMap::new();
+        for (account_id, final_state) in batches.iter().flat_map(|batch| batch.updated_accounts()) {
+            match account_positions.get(&account_id) {
+                Some(&position) => account_updates[position].1 = final_state,
+                None => {
+                    account_positions.insert(account_id, account_updates.len());
+                    account_updates.push((account_id, final_state));
+                },
+            }
+        }
         let created_notes = batches
             .iter()
             .enumerate()
@@ -92,6 +314,13 @@ where
             .collect();
         let produced_nullifiers: Vec<Digest> =
             batches.iter().flat_map(|batch| batch.produced_nullifiers()).collect();
+        let tx_kernel_versions =
+            batches.iter().flat_map(|batch| batch.tx_kernel_versions()).collect();
+        let consumed_notes = batches.iter().flat_map(|batch| batch.consumed_notes()).collect();
+        let private_notes = batches.iter().flat_map(|batch| batch.private_notes()).collect();
+        // TODO: accumulate the fees paid by the transactions, and credit them to the operator's
+        // account in the block, once transactions pay fees. Neither `ProvenTransaction` nor
+        // `BlockHeader` have a field for them yet.
 
         let block_inputs = self
             .store
@@ -100,18 +329,38 @@ where
                 produced_nullifiers.iter(),
             )
             .await?;
+        diagnostics.block_inputs = Some(block_inputs.clone());
 
         let block_header_witness = BlockWitness::new(block_inputs, batches)?;
+        diagnostics.witness = Some(block_header_witness.clone());
 
-        let new_block_header = self.block_kernel.prove(block_header_witness)?;
+        let (new_block_header, proving_time) = self.prove_block(block_header_witness).await?;
+        self.latency.proven(batches.iter().flat_map(|batch| batch.transaction_ids()));
 
         let block_num = new_block_header.block_num();
+        let signature = self
+            .identity_key
+            .as_ref()
+            .map(|keypair| BlockSignature::sign(keypair, self.chain_id, new_block_header.hash()))
+            .transpose()
+            .map_err(|err| BuildBlockError::SigningFailed(format!("{err:?}")))?;
+        let timestamp_attestation = self.parent_attestation(new_block_header.prev_hash());
 
         let block = Block {
             header: new_block_header,
+            chain_id: self.chain_id,
             updated_accounts: account_updates,
+            // filled in by the state view, which tracks the states of the accounts in flight
+            account_details: Vec::new(),
             created_notes,
             produced_nullifiers,
+            consumed_notes,
+            private_notes,
+            tx_kernel_versions,
+            signature,
+            timestamp_attestation,
+            tx_ordering: self.tx_ordering,
+            batch_proofs: block_batch_proofs(batches),
         };
 
         // TODO: Change to block.hash(), once it implemented
@@ -120,10 +369,129 @@ where
         info!(target: COMPONENT, block_num, %block_hash, "block built");
         debug!(target: COMPONENT, ?block);
 
+        diagnostics.block = Some(block.clone());
+        if let Some(journal) = &self.journal {
+            journal
+                .record(&JournalEntry::new(diagnostics.started_at, batches, diagnostics, None))
+                .await;
+        }
         self.state_view.apply_block(block).await?;
 
         info!(target: COMPONENT, block_num, %block_hash, "block committed");
+        self.request_attestation(block_hash);
+
+        if let Some(tx_log) = &self.tx_log {
+            tx_log.finished(batches.iter().flat_map(|batch| batch.transaction_ids())).await;
+        }
+
+        if let Some(dashboard) = &self.dashboard {
+            dashboard.record_committed_block(
+                block_num,
+                batches.len(),
+                num_transactions(batches),
+                proving_time,
+            );
+        }
+
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(Event::BlockApplied {
+                block_num,
+                block_hash: block_hash.to_hex(),
+                num_transactions: num_transactions(batches),
+            });
+            for tx_id in batches.iter().flat_map(|batch| batch.transaction_ids()) {
+                notifier.notify(Event::TransactionCommitted {
+                    tx_id: tx_id.to_hex(),
+                    block_num,
+                });
+            }
+        }
+
+        self.latency.committed(batches.iter().flat_map(|batch| batch.transaction_ids()));
+        let latency = self.latency.report();
+        info!(
+            target: COMPONENT,
+            num_samples = latency.num_samples,
+            num_in_flight = latency.num_in_flight,
+            p50_ms = latency.end_to_end.p50.as_millis() as u64,
+            p95_ms = latency.end_to_end.p95.as_millis() as u64,
+            p99_ms = latency.end_to_end.p99.as_millis() as u64,
+            "transaction latency"
+        );
+        if let Some(dashboard) = &self.dashboard {
+            dashboard.record_latency(latency);
+        }
 
         Ok(())
     }
 }
+
+#[async_trait]
+impl<S, A> BlockBuilder for DefaultBlockBuilder<S, A>
+where
+    S: Store,
+    A: ApplyBlock,
+{
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-block-producer", skip_all, err)]
+    async fn build_block(
+        &self,
+        batches: &[TransactionBatch],
+    ) -> Result<(), BuildBlockError> {
+        info!(
+            target: COMPONENT,
+            num_batches = batches.len(),
+            batches = %format_array(batches.iter().map(|batch| format_blake3_digest(batch.id()))),
+        );
+
+        // the batches updating an account follow the batch updating it to the state they start
+        // from, whichever the ordering
+        let mut ordered_batches = batches.to_vec();
+        if self.tx_ordering == TxOrdering::Canonical {
+            ordered_batches.sort_by_key(|batch| batch.id());
+        }
+        let ordered_batches = order_transitions(ordered_batches, TransactionBatch::precedes);
+        let batches = &ordered_batches;
+
+        let mut diagnostics = BlockDiagnostics {
+            started_at: now_ms(),
+            ..Default::default()
+        };
+        let result = self.try_build_block(batches, &mut diagnostics).await;
+
+        if let Some(journal) = &self.journal {
+            journal
+                .record(&JournalEntry::new(
+                    diagnostics.started_at,
+                    batches,
+                    &diagnostics,
+                    Some(&result),
+                ))
+                .await;
+        }
+
+        // a block refused by a read-only store is not quarantined, it would only fill its disk
+        let store_read_only = matches!(
+            result,
+            Err(BuildBlockError::ApplyBlockFailed(ApplyBlockError::StoreReadOnly(_)))
+        );
+        if let Err(err) = &result {
+            if !store_read_only {
+                let failure = self.quarantine.record(batches, diagnostics, err).await;
+                if let Some(dashboard) = &self.dashboard {
+                    dashboard
+                        .record_block(BlockRecord::failed(&failure, num_transactions(batches)));
+                }
+            }
+        }
+
+        result
+    }
+}
+
+// HELPERS
+// =================================================================================================
+
+fn num_transactions(batches: &[TransactionBatch]) -> usize {
+    batches.iter().map(|batch| batch.transaction_ids().count()).sum()
+}
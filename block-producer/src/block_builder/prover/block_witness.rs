@@ -1,16 +1,23 @@
 use std::collections::{BTreeMap, BTreeSet};
 
 use miden_crypto::ZERO;
-use miden_node_proto::domain::BlockInputs;
+use miden_node_proto::{
+    conversion::{convert, felt_from_u64, try_convert},
+    domain::BlockInputs,
+    errors::ParseError,
+    requests::{BlockWitnessAccount, BlockWitnessNotesRoot, ProveBlockRequest},
+};
 use miden_objects::{
     accounts::AccountId,
-    crypto::merkle::{EmptySubtreeRoots, MerkleStore, MmrPeaks},
-    BlockHeader, Digest, Felt,
+    crypto::merkle::{EmptySubtreeRoots, MerkleStore, MmrPeaks, NodeIndex, PartialMmr, TieredSmt},
+    BlockHeader, Digest, Felt, ACCOUNT_TREE_DEPTH,
 };
 use miden_vm::{crypto::MerklePath, AdviceInputs, StackInputs};
 
 use crate::{
+    batch_builder::prover::{batch_root, proof_hash},
     errors::{BlockProverError, BuildBlockError},
+    journal::WitnessSummary,
     TransactionBatch, CREATED_NOTES_SMT_DEPTH, CREATED_NOTES_TREE_INSERTION_DEPTH,
 };
 
@@ -28,39 +35,40 @@ pub(crate) const MAX_BATCHES_PER_BLOCK: usize =
 // =================================================================================================
 
 /// Provides inputs to the `BlockKernel` so that it can generate the new header.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BlockWitness {
     pub(super) updated_accounts: BTreeMap<AccountId, AccountUpdate>,
     /// (batch_index, created_notes_root) for batches that contain notes
     pub(super) batch_created_notes_roots: BTreeMap<usize, Digest>,
     pub(super) chain_peaks: MmrPeaks,
     pub(super) prev_header: BlockHeader,
+    /// Commitment to the proofs of the batches, see [batch_root]
+    pub(super) batch_root: Digest,
+    /// Hash of the proofs of the batches, see [proof_hash]
+    pub(super) proof_hash: Digest,
 }
 
 impl BlockWitness {
+    /// Returns the witness of the block made of `batches`, in the order they are applied.
+    ///
+    /// An account updated by several batches goes from the initial state of the first of them to
+    /// the final state of the last one.
     pub fn new(
         block_inputs: BlockInputs,
         batches: &[TransactionBatch],
     ) -> Result<Self, BuildBlockError> {
-        Self::validate_inputs(&block_inputs, batches)?;
+        let account_states = Self::validate_inputs(&block_inputs, batches)?;
 
         let updated_accounts = {
-            let mut account_initial_states: BTreeMap<AccountId, Digest> =
-                batches.iter().flat_map(|batch| batch.account_initial_states()).collect();
-
             let mut account_merkle_proofs: BTreeMap<AccountId, MerklePath> = block_inputs
                 .account_states
                 .into_iter()
                 .map(|record| (record.account_id, record.proof))
                 .collect();
 
-            batches
-                .iter()
-                .flat_map(|batch| batch.updated_accounts())
-                .map(|(account_id, final_state_hash)| {
-                    let initial_state_hash = account_initial_states
-                        .remove(&account_id)
-                        .expect("already validated that key exists");
+            account_states
+                .into_iter()
+                .map(|(account_id, (initial_state_hash, final_state_hash))| {
                     let proof = account_merkle_proofs
                         .remove(&account_id)
                         .expect("already validated that key exists");
@@ -92,11 +100,171 @@ impl BlockWitness {
         Ok(Self {
             updated_accounts,
             batch_created_notes_roots,
+            batch_root: batch_root(batches),
+            proof_hash: proof_hash(batches),
             chain_peaks: block_inputs.chain_peaks,
             prev_header: block_inputs.block_header,
         })
     }
 
+    /// Returns the roots the block is built upon, as recorded by the journal.
+    pub fn summary(&self) -> WitnessSummary {
+        WitnessSummary {
+            prev_block_num: self.prev_header.block_num(),
+            prev_block_hash: self.prev_header.hash().to_hex(),
+            chain_root: Digest::from(self.chain_peaks.hash_peaks()).to_hex(),
+            account_root: self.prev_header.account_root().to_hex(),
+            nullifier_root: self.prev_header.nullifier_root().to_hex(),
+            num_updated_accounts: self.updated_accounts.len(),
+            num_note_batches: self.batch_created_notes_roots.len(),
+        }
+    }
+
+    /// Returns the header of the block timestamped with `timestamp`, its roots being computed
+    /// natively instead of by the block kernel.
+    ///
+    /// This is the header the block kernel outputs for the witness, against which the headers
+    /// proven by a remote prover are checked. Computing the roots natively is much cheaper than
+    /// executing the kernel.
+    pub fn header(
+        &self,
+        timestamp: Felt,
+    ) -> Result<BlockHeader, BlockProverError> {
+        let mut merkle_store = MerkleStore::default();
+        merkle_store
+            .add_merkle_paths(self.updated_accounts.iter().map(|(&account_id, update)| {
+                (u64::from(account_id), update.initial_state_hash, update.proof.clone())
+            }))
+            .map_err(BlockProverError::InvalidMerklePaths)?;
+
+        let mut account_root = self.prev_header.account_root();
+        for (&account_id, update) in &self.updated_accounts {
+            let index = NodeIndex::new(ACCOUNT_TREE_DEPTH, u64::from(account_id))
+                .map_err(BlockProverError::InvalidMerklePaths)?;
+            account_root = merkle_store
+                .set_node(account_root, index, update.final_state_hash)
+                .map_err(BlockProverError::InvalidMerklePaths)?
+                .root;
+        }
+
+        // the store holds the roots of the empty subtrees, which are the leaves of the batches
+        // without notes
+        let mut note_root = *EmptySubtreeRoots::entry(CREATED_NOTES_TREE_DEPTH, 0);
+        for (&batch_index, &created_notes_root) in &self.batch_created_notes_roots {
+            let index = NodeIndex::new(CREATED_NOTES_TREE_INSERTION_DEPTH, batch_index as u64)
+                .map_err(BlockProverError::InvalidMerklePaths)?;
+            note_root = merkle_store
+                .set_node(note_root, index, created_notes_root)
+                .map_err(BlockProverError::InvalidMerklePaths)?
+                .root;
+        }
+
+        let mut chain_mmr = PartialMmr::from_peaks(self.chain_peaks.clone());
+        chain_mmr.add(self.prev_header.hash(), false);
+        let chain_root = Digest::from(chain_mmr.peaks().hash_peaks());
+
+        Ok(BlockHeader::new(
+            self.prev_header.hash(),
+            self.prev_header.block_num() + 1,
+            chain_root,
+            account_root,
+            TieredSmt::default().root(),
+            note_root,
+            self.batch_root,
+            self.proof_hash,
+            self.prev_header.version(),
+            timestamp,
+        ))
+    }
+
+    /// Returns the request proving the block timestamped with `timestamp` by a remote prover.
+    pub fn to_request(
+        &self,
+        timestamp: Felt,
+    ) -> ProveBlockRequest {
+        ProveBlockRequest {
+            prev_header: Some(self.prev_header.into()),
+            chain_peaks: convert(self.chain_peaks.peaks().iter()),
+            updated_accounts: self
+                .updated_accounts
+                .iter()
+                .map(|(&account_id, update)| BlockWitnessAccount {
+                    account_id: Some(account_id.into()),
+                    initial_state_hash: Some(update.initial_state_hash.into()),
+                    final_state_hash: Some(update.final_state_hash.into()),
+                    proof: Some(update.proof.clone().into()),
+                })
+                .collect(),
+            batch_created_notes_roots: self
+                .batch_created_notes_roots
+                .iter()
+                .map(|(&batch_index, created_notes_root)| BlockWitnessNotesRoot {
+                    batch_index: batch_index as u32,
+                    created_notes_root: Some(created_notes_root.into()),
+                })
+                .collect(),
+            batch_root: Some(self.batch_root.into()),
+            timestamp: timestamp.as_int(),
+            proof_hash: Some(self.proof_hash.into()),
+        }
+    }
+
+    /// Returns the witness of the block proven by `request`, along with the timestamp of the
+    /// block.
+    pub fn from_request(request: ProveBlockRequest) -> Result<(Self, Felt), ParseError> {
+        let prev_header: BlockHeader =
+            request.prev_header.ok_or(ParseError::ProtobufMissingData)?.try_into()?;
+        // the chain MMR of the previous block holds the blocks before it
+        let chain_peaks =
+            MmrPeaks::new(prev_header.block_num() as usize, try_convert(request.chain_peaks)?)
+                .map_err(ParseError::MmrPeaksError)?;
+
+        let updated_accounts = request
+            .updated_accounts
+            .into_iter()
+            .map(|account| {
+                let account_id: AccountId =
+                    account.account_id.ok_or(ParseError::ProtobufMissingData)?.try_into()?;
+                let update = AccountUpdate {
+                    initial_state_hash: account
+                        .initial_state_hash
+                        .ok_or(ParseError::ProtobufMissingData)?
+                        .try_into()?,
+                    final_state_hash: account
+                        .final_state_hash
+                        .ok_or(ParseError::ProtobufMissingData)?
+                        .try_into()?,
+                    proof: account.proof.ok_or(ParseError::ProtobufMissingData)?.try_into()?,
+                };
+                Ok((account_id, update))
+            })
+            .collect::<Result<_, ParseError>>()?;
+
+        let batch_created_notes_roots = request
+            .batch_created_notes_roots
+            .into_iter()
+            .map(|notes_root| {
+                let created_notes_root = notes_root
+                    .created_notes_root
+                    .ok_or(ParseError::ProtobufMissingData)?
+                    .try_into()?;
+                Ok((notes_root.batch_index as usize, created_notes_root))
+            })
+            .collect::<Result<_, ParseError>>()?;
+
+        let timestamp = felt_from_u64(request.timestamp)?;
+        let witness = Self {
+            updated_accounts,
+            batch_created_notes_roots,
+            chain_peaks,
+            prev_header,
+            batch_root: request.batch_root.ok_or(ParseError::ProtobufMissingData)?.try_into()?,
+            proof_hash: request.proof_hash.ok_or(ParseError::ProtobufMissingData)?.try_into()?,
+        };
+
+        Ok((witness, timestamp))
+    }
+
     pub(super) fn into_program_inputs(
         self
     ) -> Result<(AdviceInputs, StackInputs), BlockProverError> {
@@ -177,10 +345,12 @@ impl BlockWitness {
     // HELPERS
     // ---------------------------------------------------------------------------------------------
 
+    /// Validates the inputs, and returns the (initial, final) states of the accounts updated by
+    /// the batches.
     fn validate_inputs(
         block_inputs: &BlockInputs,
         batches: &[TransactionBatch],
-    ) -> Result<(), BuildBlockError> {
+    ) -> Result<BTreeMap<AccountId, (Digest, Digest)>, BuildBlockError> {
         // TODO:
         // - Block height returned for each nullifier is 0.
 
@@ -188,19 +358,57 @@ impl BlockWitness {
             return Err(BuildBlockError::TooManyBatchesInBlock(batches.len()));
         }
 
-        Self::validate_account_states(block_inputs, batches)?;
+        let account_states = Self::merge_account_states(batches)?;
+        Self::validate_account_states(block_inputs, &account_states)?;
 
-        Ok(())
+        Ok(account_states)
+    }
+
+    /// Returns the state of the accounts before the first batch updating them, and after the last
+    /// one, ensuring each batch updates an account from the final state of the previous one.
+    fn merge_account_states(
+        batches: &[TransactionBatch]
+    ) -> Result<BTreeMap<AccountId, (Digest, Digest)>, BuildBlockError> {
+        let mut account_states: BTreeMap<AccountId, (Digest, Digest)> = BTreeMap::new();
+        let mut discontinuous_accounts = BTreeSet::new();
+
+        for batch in batches {
+            for (account_id, initial_state) in batch.account_initial_states() {
+                account_states
+                    .entry(account_id)
+                    .and_modify(|(_, final_state)| {
+                        if *final_state != initial_state {
+                            discontinuous_accounts.insert(account_id);
+                        }
+                    })
+                    .or_insert((initial_state, initial_state));
+            }
+            for (account_id, final_state) in batch.updated_accounts() {
+                if let Some(states) = account_states.get_mut(&account_id) {
+                    states.1 = final_state;
+                }
+            }
+        }
+
+        if discontinuous_accounts.is_empty() {
+            Ok(account_states)
+        } else {
+            Err(BuildBlockError::DiscontinuousAccountStates(
+                discontinuous_accounts.into_iter().collect(),
+            ))
+        }
     }
 
     /// Validate that initial account states coming from the batches are the same as the account
     /// states returned from the store
     fn validate_account_states(
         block_inputs: &BlockInputs,
-        batches: &[TransactionBatch],
+        account_states: &BTreeMap<AccountId, (Digest, Digest)>,
     ) -> Result<(), BuildBlockError> {
-        let batches_initial_states: BTreeMap<AccountId, Digest> =
-            batches.iter().flat_map(|batch| batch.account_initial_states()).collect();
+        let batches_initial_states: BTreeMap<AccountId, Digest> = account_states
+            .iter()
+            .map(|(account_id, (initial_state, _))| (*account_id, *initial_state))
+            .collect();
 
         let accounts_in_batches: BTreeSet<AccountId> =
             batches_initial_states.keys().cloned().collect();
@@ -248,7 +456,7 @@ impl BlockWitness {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(super) struct AccountUpdate {
     pub initial_state_hash: Digest,
     pub final_state_hash: Digest,
@@ -1,13 +1,24 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
+use async_trait::async_trait;
 use miden_air::{ExecutionOptions, Felt};
 use miden_crypto::merkle::TieredSmt;
 use miden_objects::{assembly::Assembler, BlockHeader, Digest};
 use miden_stdlib::StdLibrary;
 use miden_vm::{execute, DefaultHost, MemAdviceProvider, Program};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use tokio::sync::oneshot;
+use tracing::warn;
 
 use self::block_witness::BlockWitness;
-use crate::errors::{BlockProverError, BuildBlockError};
+use crate::{
+    config::ProverPriority,
+    errors::{BlockProverError, BuildBlockError},
+    COMPONENT,
+};
 
 /// The index of the word at which the account root is stored on the output stack.
 pub const ACCOUNT_ROOT_WORD_IDX: usize = 0;
@@ -19,10 +30,14 @@ pub const NOTE_ROOT_WORD_IDX: usize = 4;
 pub const CHAIN_MMR_ROOT_WORD_IDX: usize = 8;
 
 pub mod block_witness;
+pub mod remote;
 
 #[cfg(test)]
 mod tests;
 
+/// Niceness of the block proving threads configured with [crate::config::ProverPriority::Low].
+const LOW_PRIORITY_NICENESS: i32 = 10;
+
 /// Note: For now, the "block kernel" only computes the account root. Eventually, it will compute
 /// the entire block header.
 ///
@@ -149,12 +164,80 @@ begin
 end
 ";
 
+// BLOCK PROVER
+// ================================================================================================
+
+/// Abstraction over the proving of the blocks, in process or by a remote proving service, see
+/// [remote::RemoteBlockProver].
+#[async_trait]
+pub trait BlockProver: Send + Sync + 'static {
+    /// Returns the header of the block proven from `witness`, timestamped with `timestamp`.
+    async fn prove_block(
+        &self,
+        witness: BlockWitness,
+        timestamp: Felt,
+    ) -> Result<BlockHeader, BuildBlockError>;
+}
+
+/// Proves the blocks on a pool of threads, keeping proving off the async runtime and bounding its
+/// CPU usage.
+pub struct LocalBlockProver {
+    kernel: Arc<BlockKernel>,
+    proving_pool: ThreadPool,
+}
+
+impl LocalBlockProver {
+    /// Returns a prover of the blocks proving them on `threads` threads, one per CPU if zero, run
+    /// with `prover_priority`.
+    pub fn new(
+        threads: usize,
+        prover_priority: ProverPriority,
+    ) -> Self {
+        let proving_pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|idx| format!("block-prover-{idx}"))
+            .start_handler(move |_| {
+                if prover_priority == ProverPriority::Low {
+                    lower_thread_priority();
+                }
+            })
+            .build()
+            .expect("failed to create the block proving threads");
+
+        Self {
+            kernel: Arc::new(BlockKernel::new()),
+            proving_pool,
+        }
+    }
+}
+
+#[async_trait]
+impl BlockProver for LocalBlockProver {
+    async fn prove_block(
+        &self,
+        witness: BlockWitness,
+        timestamp: Felt,
+    ) -> Result<BlockHeader, BuildBlockError> {
+        let (sender, receiver) = oneshot::channel();
+        let kernel = self.kernel.clone();
+        self.proving_pool.spawn(move || {
+            let _ = sender.send(kernel.prove_at(witness, timestamp));
+        });
+
+        receiver.await.map_err(|_| BuildBlockError::ProvingTaskFailed)?
+    }
+}
+
+// BLOCK KERNEL
+// ================================================================================================
+
+/// Executes the block kernel, computing the header of a block from its witness.
 #[derive(Debug)]
-pub(crate) struct BlockProver {
+pub(crate) struct BlockKernel {
     kernel: Program,
 }
 
-impl BlockProver {
+impl BlockKernel {
     pub fn new() -> Self {
         let account_program = {
             let assembler = Assembler::default()
@@ -175,21 +258,25 @@ impl BlockProver {
     pub fn prove(
         &self,
         witness: BlockWitness,
+    ) -> Result<BlockHeader, BuildBlockError> {
+        self.prove_at(witness, current_timestamp())
+    }
+
+    /// Proves the block, timestamped with `timestamp` instead of the current time.
+    pub fn prove_at(
+        &self,
+        witness: BlockWitness,
+        timestamp: Felt,
     ) -> Result<BlockHeader, BuildBlockError> {
         let prev_hash = witness.prev_header.hash();
         let block_num = witness.prev_header.block_num() + 1;
         let version = witness.prev_header.version();
+        let batch_root = witness.batch_root;
+        let proof_hash = witness.proof_hash;
 
         let (account_root, note_root, chain_root) = self.compute_roots(witness)?;
 
         let nullifier_root = TieredSmt::default().root();
-        let batch_root = Digest::default();
-        let proof_hash = Digest::default();
-        let timestamp: Felt = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("today is expected to be before 1970")
-            .as_millis()
-            .into();
 
         Ok(BlockHeader::new(
             prev_hash,
@@ -238,3 +325,41 @@ impl BlockProver {
         Ok((new_account_root.into(), new_note_root.into(), new_chain_mmr_root.into()))
     }
 }
+
+/// Returns the timestamp of a block produced now, in seconds since the UNIX epoch as the genesis
+/// block timestamp.
+pub(crate) fn current_timestamp() -> Felt {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("today is expected to be after 1970")
+        .as_secs()
+        .into()
+}
+
+// PROVING THREADS
+// ================================================================================================
+
+/// Lowers the scheduling priority of the calling thread, so proving yields the CPU to the other
+/// tasks of the node.
+#[cfg(target_os = "linux")]
+pub(crate) fn lower_thread_priority() {
+    // Safety: on Linux `setpriority` with `who = 0` only changes the niceness of the calling thread
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, LOW_PRIORITY_NICENESS) };
+    if result != 0 {
+        warn!(
+            target: COMPONENT,
+            err = %std::io::Error::last_os_error(),
+            "Failed to lower the block prover thread priority"
+        );
+    }
+}
+
+/// Lowers the scheduling priority of the calling thread, only supported on Linux.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn lower_thread_priority() {
+    warn!(
+        target: COMPONENT,
+        niceness = LOW_PRIORITY_NICENESS,
+        "Block prover thread priority can only be lowered on Linux"
+    );
+}
@@ -0,0 +1,197 @@
+//! Proving of the blocks by a remote proving service.
+//!
+//! The witness of a block is sent to a service serving the `block_prover.Api` gRPC API, e.g.
+//! [BlockProverApi], which answers with the header of the proven block. A block the service failed
+//! to prove, or didn't prove within the timeout, is proven locally if the fallback is enabled, and
+//! fails otherwise, its batches being included in a later block.
+//!
+//! The block kernel doesn't output a proof yet, so every field of a header returned by the service
+//! is checked against the header computed natively from the witness, see [BlockWitness::header]. A
+//! header which doesn't match is rejected as a failure of the service.
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use miden_air::Felt;
+use miden_node_proto::{
+    block_prover::{api_client::ApiClient, api_server},
+    errors::ParseError,
+    requests::ProveBlockRequest,
+    responses::ProveBlockResponse,
+};
+use miden_node_utils::grpc;
+use miden_objects::BlockHeader;
+use tokio::time;
+use tonic::{transport::Channel, Request, Response, Status};
+use tracing::{info, warn};
+
+use super::{block_witness::BlockWitness, BlockProver};
+use crate::{
+    errors::{BlockProverError, BuildBlockError},
+    COMPONENT,
+};
+
+// REMOTE BLOCK PROVER
+// ================================================================================================
+
+/// Client of the remote proving service, see the [module](self) documentation.
+pub struct RemoteBlockProver {
+    url: String,
+    client: ApiClient<Channel>,
+
+    /// Time after which a proving request fails
+    timeout: Duration,
+
+    /// Prover of the blocks the remote prover failed to prove, they fail if missing
+    fallback: Option<Arc<dyn BlockProver>>,
+}
+
+impl RemoteBlockProver {
+    /// Returns a client of the proving service at `url`, which is connected to on the first block.
+    pub fn new(
+        url: &str,
+        timeout: Duration,
+    ) -> Result<Self, tonic::transport::Error> {
+        Ok(Self {
+            url: url.to_string(),
+            client: ApiClient::new(grpc::connect_lazy(url)?),
+            timeout,
+            fallback: None,
+        })
+    }
+
+    /// Proves the blocks the remote prover failed to prove with `fallback`.
+    pub fn with_fallback(
+        mut self,
+        fallback: Arc<dyn BlockProver>,
+    ) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// Requests the proof of the block from the proving service.
+    ///
+    /// The header returned by the service must match the header computed from the witness, with
+    /// the requested timestamp.
+    async fn prove_remotely(
+        &self,
+        witness: &BlockWitness,
+        timestamp: Felt,
+    ) -> Result<BlockHeader, BlockProverError> {
+        let request = witness.to_request(timestamp);
+        let response = time::timeout(self.timeout, self.client.clone().prove_block(request))
+            .await
+            .map_err(|_| BlockProverError::RemoteProvingTimedOut(self.timeout.as_millis() as u64))?
+            .map_err(|status| {
+                BlockProverError::RemoteProvingFailed(status.message().to_string())
+            })?;
+
+        let block_header: BlockHeader = response
+            .into_inner()
+            .block_header
+            .ok_or(ParseError::ProtobufMissingData)
+            .and_then(BlockHeader::try_from)
+            .map_err(|err| {
+                BlockProverError::RemoteProvingFailed(format!("invalid block header: {err}"))
+            })?;
+
+        let expected_header = witness.header(timestamp)?;
+        if block_header != expected_header {
+            return Err(BlockProverError::RemoteProvingFailed(format!(
+                "block {} doesn't match its witness, mismatched fields: {}",
+                block_header.hash(),
+                mismatched_fields(&block_header, &expected_header).join(", ")
+            )));
+        }
+
+        Ok(block_header)
+    }
+}
+
+#[async_trait]
+impl BlockProver for RemoteBlockProver {
+    async fn prove_block(
+        &self,
+        witness: BlockWitness,
+        timestamp: Felt,
+    ) -> Result<BlockHeader, BuildBlockError> {
+        let err = match self.prove_remotely(&witness, timestamp).await {
+            Ok(block_header) => return Ok(block_header),
+            Err(err) => err,
+        };
+
+        match &self.fallback {
+            Some(fallback) => {
+                warn!(target: COMPONENT, url = %self.url, %err, "Remote block proving failed, proving locally");
+                fallback.prove_block(witness, timestamp).await
+            },
+            None => Err(err.into()),
+        }
+    }
+}
+
+// BLOCK PROVER SERVER
+// ================================================================================================
+
+/// Implementation of the `block_prover.Api` gRPC API, proving the blocks of remote block producers
+/// with `prover`.
+pub struct BlockProverApi {
+    prover: Arc<dyn BlockProver>,
+}
+
+impl BlockProverApi {
+    pub fn new(prover: Arc<dyn BlockProver>) -> Self {
+        Self { prover }
+    }
+}
+
+#[tonic::async_trait]
+impl api_server::Api for BlockProverApi {
+    async fn prove_block(
+        &self,
+        request: Request<ProveBlockRequest>,
+    ) -> Result<Response<ProveBlockResponse>, Status> {
+        let (witness, timestamp) = BlockWitness::from_request(request.into_inner())
+            .map_err(|err| Status::invalid_argument(format!("Invalid block witness: {err}")))?;
+        let block_num = witness.prev_header.block_num() + 1;
+
+        let block_header =
+            self.prover.prove_block(witness, timestamp).await.map_err(|err| {
+                Status::internal(format!("Failed to prove block {block_num}: {err}"))
+            })?;
+        info!(target: COMPONENT, block_num, block_hash = %block_header.hash(), "Block proven");
+
+        Ok(Response::new(ProveBlockResponse {
+            block_header: Some(block_header.into()),
+        }))
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Returns the names of the fields of `block_header` which differ from `expected_header`.
+fn mismatched_fields(
+    block_header: &BlockHeader,
+    expected_header: &BlockHeader,
+) -> Vec<&'static str> {
+    let fields = [
+        ("prev_hash", block_header.prev_hash() != expected_header.prev_hash()),
+        ("block_num", block_header.block_num() != expected_header.block_num()),
+        ("chain_root", block_header.chain_root() != expected_header.chain_root()),
+        ("account_root", block_header.account_root() != expected_header.account_root()),
+        (
+            "nullifier_root",
+            block_header.nullifier_root() != expected_header.nullifier_root(),
+        ),
+        ("note_root", block_header.note_root() != expected_header.note_root()),
+        ("batch_root", block_header.batch_root() != expected_header.batch_root()),
+        ("proof_hash", block_header.proof_hash() != expected_header.proof_hash()),
+        ("version", block_header.version() != expected_header.version()),
+        ("timestamp", block_header.timestamp() != expected_header.timestamp()),
+    ];
+
+    fields
+        .into_iter()
+        .filter_map(|(name, mismatched)| mismatched.then_some(name))
+        .collect()
+}
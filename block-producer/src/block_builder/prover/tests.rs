@@ -1,22 +1,39 @@
+use std::{collections::BTreeMap, time::Duration};
+
 use miden_crypto::{merkle::Mmr, ONE};
 use miden_mock::mock::block::mock_block_header;
-use miden_node_proto::domain::{AccountInputRecord, BlockInputs};
+use miden_node_proto::{
+    block_prover::{api_client::ApiClient, api_server::ApiServer},
+    domain::{AccountInputRecord, BlockInputs},
+    requests::ProveBlockRequest,
+};
+use miden_node_store::batch_kernel::{self, BatchKernelVerifier};
 use miden_objects::{
     accounts::AccountId,
     crypto::merkle::{EmptySubtreeRoots, MmrPeaks},
     notes::{NoteEnvelope, NoteMetadata},
     transaction::{InputNotes, OutputNotes},
-    ZERO,
+    Hasher, Word, ZERO,
 };
 use miden_vm::crypto::{MerklePath, SimpleSmt};
+use tokio::net::TcpListener;
+use tonic::{
+    transport::{server::TcpIncoming, Server},
+    Code,
+};
 
 use super::*;
 use crate::{
-    block_builder::prover::block_witness::CREATED_NOTES_TREE_DEPTH,
+    batch_builder::prover::{block_batch_proofs, BatchProver, LocalBatchProver},
+    block_builder::prover::{
+        block_witness::{AccountUpdate, CREATED_NOTES_TREE_DEPTH},
+        remote::{BlockProverApi, RemoteBlockProver},
+    },
+    config::ProverPriority,
     store::Store,
     test_utils::{
-        block::{build_actual_block_header, build_expected_block_header, MockBlockBuilder},
-        DummyProvenTxGenerator, MockStoreSuccessBuilder,
+        build_actual_block_header, build_expected_block_header, DummyProvenTxGenerator,
+        MockBlockBuilder, MockStoreSuccessBuilder,
     },
     TransactionBatch,
 };
@@ -172,10 +189,58 @@ fn test_block_witness_validation_inconsistent_account_hashes() {
     );
 }
 
+/// Tests that an account updated by several batches goes from the initial state of the first batch
+/// to the final state of the last one, and that `BlockWitness` constructor fails if a batch doesn't
+/// start from the final state of the previous one.
+#[test]
+fn test_block_witness_chained_account_updates() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account_id = AccountId::new_unchecked(ONE);
+    let states: Vec<Digest> =
+        (0..4).map(|index| Digest::new([Felt::new(index), ZERO, ZERO, ZERO])).collect();
+
+    let block_inputs_from_store = BlockInputs {
+        block_header: mock_block_header(0, None, None, &[]),
+        chain_peaks: MmrPeaks::new(0, Vec::new()).unwrap(),
+        account_states: vec![AccountInputRecord {
+            account_id,
+            account_hash: states[0],
+            proof: MerklePath::default(),
+        }],
+        nullifiers: Vec::new(),
+    };
+    let batch = |initial: usize, last: usize| {
+        let tx = tx_gen.dummy_proven_tx_with_params(
+            account_id,
+            states[initial],
+            states[last],
+            InputNotes::new(Vec::new()).unwrap(),
+            OutputNotes::new(Vec::new()).unwrap(),
+        );
+
+        TransactionBatch::new(vec![tx]).unwrap()
+    };
+
+    let block_witness =
+        BlockWitness::new(block_inputs_from_store.clone(), &[batch(0, 1), batch(1, 2)]).unwrap();
+    let account_update = &block_witness.updated_accounts[&account_id];
+    assert_eq!(
+        (account_update.initial_state_hash, account_update.final_state_hash),
+        (states[0], states[2])
+    );
+
+    let block_witness_result =
+        BlockWitness::new(block_inputs_from_store, &[batch(0, 1), batch(2, 3)]);
+    assert_eq!(
+        block_witness_result,
+        Err(BuildBlockError::DiscontinuousAccountStates(vec![account_id]))
+    );
+}
+
 // ACCOUNT ROOT TESTS
 // =================================================================================================
 
-/// Tests that the `BlockProver` computes the proper account root.
+/// Tests that the `BlockKernel` computes the proper account root.
 ///
 /// We assume an initial store with 5 accounts, and all will be updated.
 #[tokio::test]
@@ -250,7 +315,7 @@ async fn test_compute_account_root_success() {
 
     let block_witness = BlockWitness::new(block_inputs_from_store, &batches).unwrap();
 
-    let block_prover = BlockProver::new();
+    let block_prover = BlockKernel::new();
     let block_header = block_prover.prove(block_witness).unwrap();
 
     // Update SMT by hand to get new root
@@ -314,7 +379,7 @@ async fn test_compute_account_root_empty_batches() {
     let batches = Vec::new();
     let block_witness = BlockWitness::new(block_inputs_from_store, &batches).unwrap();
 
-    let block_prover = BlockProver::new();
+    let block_prover = BlockKernel::new();
     let block_header = block_prover.prove(block_witness).unwrap();
 
     // Compare roots
@@ -345,7 +410,7 @@ async fn test_compute_note_root_empty_batches_success() {
 
     let block_witness = BlockWitness::new(block_inputs_from_store, &batches).unwrap();
 
-    let block_prover = BlockProver::new();
+    let block_prover = BlockKernel::new();
     let block_header = block_prover.prove(block_witness).unwrap();
 
     // Compare roots
@@ -377,7 +442,7 @@ async fn test_compute_note_root_empty_notes_success() {
 
     let block_witness = BlockWitness::new(block_inputs_from_store, &batches).unwrap();
 
-    let block_prover = BlockProver::new();
+    let block_prover = BlockKernel::new();
     let block_header = block_prover.prove(block_witness).unwrap();
 
     // Compare roots
@@ -445,7 +510,7 @@ async fn test_compute_note_root_success() {
 
     let block_witness = BlockWitness::new(block_inputs_from_store, &batches).unwrap();
 
-    let block_prover = BlockProver::new();
+    let block_prover = BlockKernel::new();
     let block_header = block_prover.prove(block_witness).unwrap();
 
     // Create SMT by hand to get new root
@@ -526,3 +591,289 @@ async fn test_compute_chain_mmr_root_mmr_17_peaks() {
 
     assert_eq!(actual_block_header.chain_root(), expected_block_header.chain_root());
 }
+
+// BATCH ROOT TESTS
+// =================================================================================================
+
+/// Tests that the batch root and proof hash commit to the proofs of the batches, in order, and are
+/// the default digest if none of them is proven, and that the store verifies them.
+#[tokio::test]
+async fn test_compute_batch_root() {
+    let store = MockStoreSuccessBuilder::new().build();
+
+    let batch = TransactionBatch::new(Vec::new()).unwrap();
+    let proof = LocalBatchProver::new(1, ProverPriority::Normal)
+        .prove_batch(&batch)
+        .await
+        .unwrap();
+
+    let prove_block = |batches: Vec<TransactionBatch>| {
+        let store = &store;
+        async move {
+            let block_inputs: BlockInputs =
+                store.get_block_inputs(std::iter::empty(), std::iter::empty()).await.unwrap();
+            let block_witness = BlockWitness::new(block_inputs, &batches).unwrap();
+
+            BlockKernel::new().prove(block_witness).unwrap()
+        }
+    };
+
+    let unproven_header = prove_block(vec![batch.clone(), batch.clone()]).await;
+    assert_eq!(unproven_header.batch_root(), Digest::default());
+    assert_eq!(unproven_header.proof_hash(), Digest::default());
+
+    let proven_batch = batch.with_proof(proof.clone());
+    let proven_header = prove_block(vec![proven_batch.clone(), proven_batch.clone()]).await;
+    let expected_batch_root = {
+        let mut elements = Vec::new();
+        for _ in 0..2 {
+            elements.extend(Word::from(proof.commitment()));
+            elements.extend(Word::from(proof.proof_hash()));
+        }
+        Hasher::hash_elements(&elements)
+    };
+    assert_eq!(proven_header.batch_root(), expected_batch_root);
+    assert_eq!(proof.proof_hash(), Hasher::hash(proof.proof()));
+    assert_eq!(
+        proven_header.proof_hash(),
+        Hasher::hash_elements(
+            &[Word::from(proof.proof_hash()), Word::from(proof.proof_hash())].concat()
+        )
+    );
+
+    // the store verifies the proofs of the batches against the header
+    let batch_proofs = block_batch_proofs(&[proven_batch.clone(), proven_batch]);
+    assert_eq!(batch_proofs.iter().map(|batch| batch.batch_index).collect::<Vec<_>>(), [0, 1]);
+    let verifier = BatchKernelVerifier::new();
+    verifier
+        .verify_block(proven_header.batch_root(), proven_header.proof_hash(), &batch_proofs)
+        .unwrap();
+    assert!(verifier
+        .verify_block(unproven_header.batch_root(), unproven_header.proof_hash(), &batch_proofs)
+        .is_err());
+
+    // a proof of a batch of another number of transactions doesn't verify
+    let tampered = vec![batch_kernel::BatchProof {
+        num_txs: 1,
+        ..batch_proofs[0].clone()
+    }];
+    let batch_root = batch_kernel::batch_root(
+        tampered.iter().map(|batch| (batch.commitment, batch.proof_hash())),
+    );
+    let proof_hash = batch_kernel::proof_hash(tampered.iter().map(|batch| batch.proof_hash()));
+    assert!(verifier.verify_block(batch_root, proof_hash, &tampered).is_err());
+}
+
+// REMOTE PROVER TESTS
+// =================================================================================================
+
+/// Tests that the witness of a block is sent to a remote prover unaltered, along with the timestamp
+/// of the block.
+#[test]
+fn test_block_witness_request() {
+    let digest = |value: u64| Digest::new([Felt::new(value), ZERO, ZERO, ZERO]);
+    let chain_mmr = {
+        let mut mmr = Mmr::new();
+        mmr.add(digest(1));
+        mmr.add(digest(2));
+
+        mmr
+    };
+
+    let block_witness = BlockWitness {
+        updated_accounts: BTreeMap::from([(
+            AccountId::new_unchecked(ONE),
+            AccountUpdate {
+                initial_state_hash: digest(3),
+                final_state_hash: digest(4),
+                proof: MerklePath::new(vec![digest(5)]),
+            },
+        )]),
+        batch_created_notes_roots: BTreeMap::from([(1, digest(6))]),
+        chain_peaks: chain_mmr.peaks(chain_mmr.forest()).unwrap(),
+        prev_header: mock_block_header(2, None, None, &[]),
+        batch_root: digest(7),
+        proof_hash: digest(8),
+    };
+    let timestamp = Felt::new(1_700_000_000);
+
+    let request = block_witness.to_request(timestamp);
+    assert_eq!(request.timestamp, 1_700_000_000);
+    assert_eq!(BlockWitness::from_request(request).unwrap(), (block_witness, timestamp));
+}
+
+/// Tests that a block the remote prover failed to prove is proven locally if the fallback is
+/// enabled, and fails otherwise.
+#[tokio::test]
+async fn test_remote_prover_fallback() {
+    let store = MockStoreSuccessBuilder::new().build();
+    let block_inputs: BlockInputs =
+        store.get_block_inputs(std::iter::empty(), std::iter::empty()).await.unwrap();
+    let block_witness = BlockWitness::new(block_inputs, &[]).unwrap();
+    let timestamp = Felt::new(1_700_000_000);
+
+    // nothing listens on the port, the requests fail right away
+    let remote_prover =
+        || RemoteBlockProver::new("http://127.0.0.1:1", Duration::from_secs(5)).unwrap();
+
+    let result = remote_prover().prove_block(block_witness.clone(), timestamp).await;
+    assert!(matches!(
+        result,
+        Err(BuildBlockError::BlockProverFailed(BlockProverError::RemoteProvingFailed(_)))
+    ));
+
+    let block_header = remote_prover()
+        .with_fallback(Arc::new(LocalBlockProver::new(1, ProverPriority::Normal)))
+        .prove_block(block_witness.clone(), timestamp)
+        .await
+        .unwrap();
+    assert_eq!(block_header, BlockKernel::new().prove_at(block_witness, timestamp).unwrap());
+}
+
+/// Tests that the header of a block computed natively from its witness is the one output by the
+/// block kernel, which the headers of the remote provers are checked against.
+#[tokio::test]
+async fn test_block_witness_header() {
+    let block_witness = block_witness_with_updates().await;
+    let timestamp = Felt::new(1_700_000_000);
+
+    assert_eq!(
+        block_witness.header(timestamp).unwrap(),
+        BlockKernel::new().prove_at(block_witness, timestamp).unwrap()
+    );
+}
+
+/// Tests that the blocks proven by a `block_prover.Api` server are accepted, and that a header
+/// which doesn't match the witness is rejected, the block being proven locally if the fallback is
+/// enabled.
+#[tokio::test]
+async fn test_remote_prover_server() {
+    let block_witness = block_witness_with_updates().await;
+    let timestamp = Felt::new(1_700_000_000);
+    let expected_header = BlockKernel::new().prove_at(block_witness.clone(), timestamp).unwrap();
+    let local_prover = Arc::new(LocalBlockProver::new(1, ProverPriority::Normal));
+
+    let url = serve_block_prover(local_prover.clone()).await;
+    let block_header = RemoteBlockProver::new(&url, Duration::from_secs(5))
+        .unwrap()
+        .prove_block(block_witness.clone(), timestamp)
+        .await
+        .unwrap();
+    assert_eq!(block_header, expected_header);
+
+    // the server rejects the requests whose witness can't be parsed
+    let status = ApiClient::connect(url)
+        .await
+        .unwrap()
+        .prove_block(ProveBlockRequest::default())
+        .await;
+    assert_eq!(status.unwrap_err().code(), Code::InvalidArgument);
+
+    let url = serve_block_prover(Arc::new(BlockProverTampering(local_prover.clone()))).await;
+    let remote_prover = || RemoteBlockProver::new(&url, Duration::from_secs(5)).unwrap();
+
+    let result = remote_prover().prove_block(block_witness.clone(), timestamp).await;
+    let Err(BuildBlockError::BlockProverFailed(BlockProverError::RemoteProvingFailed(err))) =
+        result
+    else {
+        panic!("Tampered header should have been rejected, got {result:?}");
+    };
+    assert!(err.ends_with("mismatched fields: account_root"), "{err}");
+
+    let block_header = remote_prover()
+        .with_fallback(local_prover)
+        .prove_block(block_witness, timestamp)
+        .await
+        .unwrap();
+    assert_eq!(block_header, expected_header);
+}
+
+/// Proves the blocks with the wrapped prover, then tampers with the account root of their header
+struct BlockProverTampering(Arc<dyn BlockProver>);
+
+#[async_trait]
+impl BlockProver for BlockProverTampering {
+    async fn prove_block(
+        &self,
+        witness: BlockWitness,
+        timestamp: Felt,
+    ) -> Result<BlockHeader, BuildBlockError> {
+        let header = self.0.prove_block(witness, timestamp).await?;
+
+        Ok(BlockHeader::new(
+            header.prev_hash(),
+            header.block_num(),
+            header.chain_root(),
+            Digest::default(),
+            header.nullifier_root(),
+            header.note_root(),
+            header.batch_root(),
+            header.proof_hash(),
+            header.version(),
+            header.timestamp(),
+        ))
+    }
+}
+
+/// Serves the `block_prover.Api` API proving the blocks with `prover` on a local port, and returns
+/// its URL.
+async fn serve_block_prover(prover: Arc<dyn BlockProver>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+
+    tokio::spawn(
+        Server::builder()
+            .add_service(ApiServer::new(BlockProverApi::new(prover)))
+            .serve_with_incoming(incoming),
+    );
+
+    url
+}
+
+/// Returns the witness of a block updating two accounts, and creating notes in two batches, on top
+/// of a chain of three blocks.
+async fn block_witness_with_updates() -> BlockWitness {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let digest = |value: u64| Digest::new([Felt::new(value), ZERO, ZERO, ZERO]);
+    let account_ids = [
+        AccountId::new_unchecked(Felt::from(0b0000_1111_0000_0000u64)),
+        AccountId::new_unchecked(Felt::from(0b1111_0000_1111_0000u64)),
+    ];
+
+    let store = MockStoreSuccessBuilder::new()
+        .initial_accounts(account_ids.iter().map(|&account_id| (account_id, digest(1))))
+        .initial_chain_mmr({
+            let mut mmr = Mmr::new();
+            for value in 0..3 {
+                mmr.add(digest(value));
+            }
+
+            mmr
+        })
+        .build();
+    let block_inputs: BlockInputs =
+        store.get_block_inputs(account_ids.iter(), std::iter::empty()).await.unwrap();
+
+    let batches: Vec<TransactionBatch> = account_ids
+        .iter()
+        .enumerate()
+        .map(|(idx, &account_id)| {
+            let note = NoteEnvelope::new(
+                digest(10 + idx as u64).into(),
+                NoteMetadata::new(account_id, Felt::from(1u64)),
+            );
+            let tx = tx_gen.dummy_proven_tx_with_params(
+                account_id,
+                digest(1),
+                digest(2 + idx as u64),
+                InputNotes::new(Vec::new()).unwrap(),
+                OutputNotes::new(vec![note]).unwrap(),
+            );
+
+            TransactionBatch::new(vec![tx]).unwrap()
+        })
+        .collect();
+
+    BlockWitness::new(block_inputs, &batches).unwrap()
+}
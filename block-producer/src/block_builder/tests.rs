@@ -1,11 +1,28 @@
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use figment::Jail;
 use miden_air::Felt;
+use miden_crypto::utils::Serializable;
+use miden_node_proto::{
+    requests::SyncStateRequest, responses::ConsumedNoteUpdate, store::api_server::Api,
+};
+use miden_node_store::{config::StoreConfig, db::Db, genesis::GenesisState, server::StoreApi};
 use miden_objects::transaction::{InputNotes, OutputNotes};
+use tonic::Request;
 
 // block builder tests (higher level)
 // 1. `apply_block()` is called
 use super::*;
 use crate::{
-    test_utils::{DummyProvenTxGenerator, MockStoreFailure, MockStoreSuccessBuilder},
+    config::{QuarantineConfig, TxOrdering},
+    store::DefaultStore,
+    test_utils::{
+        note_by_index, DummyProvenTxGenerator, Fault, FaultyStore, MockStoreFailure,
+        MockStoreSuccessBuilder, StoreCall, TransactionBatchConstructor,
+    },
     TransactionBatch,
 };
 
@@ -22,7 +39,13 @@ async fn test_apply_block_called_nonempty_batches() {
             .build(),
     );
 
-    let block_builder = DefaultBlockBuilder::new(store.clone(), store.clone());
+    let block_builder = DefaultBlockBuilder::new(
+        store.clone(),
+        store.clone(),
+        Arc::new(BlockQuarantine::new(disabled_quarantine())),
+        Arc::new(LatencyTracker::new(10)),
+        block_builder_options(),
+    );
 
     let batches: Vec<TransactionBatch> = {
         let batch_1 = {
@@ -57,7 +80,13 @@ async fn test_apply_block_called_empty_batches() {
             .build(),
     );
 
-    let block_builder = DefaultBlockBuilder::new(store.clone(), store.clone());
+    let block_builder = DefaultBlockBuilder::new(
+        store.clone(),
+        store.clone(),
+        Arc::new(BlockQuarantine::new(disabled_quarantine())),
+        Arc::new(LatencyTracker::new(10)),
+        block_builder_options(),
+    );
 
     block_builder.build_block(&Vec::new()).await.unwrap();
 
@@ -70,10 +99,210 @@ async fn test_apply_block_called_empty_batches() {
 async fn test_build_block_failure() {
     let store = Arc::new(MockStoreFailure);
 
-    let block_builder = DefaultBlockBuilder::new(store.clone(), store.clone());
+    let quarantine = Arc::new(BlockQuarantine::new(disabled_quarantine()));
+    let block_builder = DefaultBlockBuilder::new(
+        store.clone(),
+        store.clone(),
+        quarantine.clone(),
+        Arc::new(LatencyTracker::new(10)),
+        block_builder_options(),
+    );
 
     let result = block_builder.build_block(&Vec::new()).await;
 
     // Ensure that the store's `apply_block()` was called
     assert!(matches!(result, Err(BuildBlockError::GetBlockInputsFailed(_))));
+
+    // Ensure that the failure was recorded, without a dump
+    let failure = quarantine.last_failure().expect("failure must be recorded");
+    assert_eq!(failure.block_num, None);
+    assert_eq!(failure.num_batches, 0);
+    assert_eq!(failure.error, result.unwrap_err().to_string());
+    assert_eq!(failure.dump_path, None);
+}
+
+/// Tests that the block builder recovers once the store's failures stop
+#[tokio::test]
+async fn test_build_block_recovers_from_store_failures() {
+    let store = Arc::new(FaultyStore::new(MockStoreSuccessBuilder::new().build()));
+    store.inject(StoreCall::GetBlockInputs, Fault::Fail);
+    store.inject(StoreCall::ApplyBlock, Fault::Fail);
+
+    let block_builder = DefaultBlockBuilder::new(
+        store.clone(),
+        store.clone(),
+        Arc::new(BlockQuarantine::new(disabled_quarantine())),
+        Arc::new(LatencyTracker::new(10)),
+        block_builder_options(),
+    );
+
+    let result = block_builder.build_block(&Vec::new()).await;
+    assert!(matches!(result, Err(BuildBlockError::GetBlockInputsFailed(_))));
+    assert_eq!(store.num_calls(StoreCall::ApplyBlock), 0);
+
+    let result = block_builder.build_block(&Vec::new()).await;
+    assert!(matches!(result, Err(BuildBlockError::ApplyBlockFailed(_))));
+    assert_eq!(*store.inner().num_apply_block_called.read().await, 0);
+
+    block_builder.build_block(&Vec::new()).await.unwrap();
+    assert_eq!(store.num_calls(StoreCall::GetBlockInputs), 3);
+    assert_eq!(*store.inner().num_apply_block_called.read().await, 1);
+}
+
+/// Tests that the block builder refuses block inputs inconsistent with the transactions
+#[tokio::test]
+async fn test_build_block_with_corrupted_inputs() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account_id = AccountId::new_unchecked(42u64.into());
+    let account_initial_hash: Digest =
+        [Felt::from(1u64), Felt::from(1u64), Felt::from(1u64), Felt::from(1u64)].into();
+    let store = Arc::new(FaultyStore::new(
+        MockStoreSuccessBuilder::new()
+            .initial_accounts(std::iter::once((account_id, account_initial_hash)))
+            .build(),
+    ));
+    store.inject(StoreCall::GetBlockInputs, Fault::Corrupt);
+
+    let block_builder = DefaultBlockBuilder::new(
+        store.clone(),
+        store.clone(),
+        Arc::new(BlockQuarantine::new(disabled_quarantine())),
+        Arc::new(LatencyTracker::new(10)),
+        block_builder_options(),
+    );
+
+    let tx = tx_gen.dummy_proven_tx_with_params(
+        account_id,
+        account_initial_hash,
+        [Felt::from(2u64), Felt::from(2u64), Felt::from(2u64), Felt::from(2u64)].into(),
+        InputNotes::new(Vec::new()).unwrap(),
+        OutputNotes::new(Vec::new()).unwrap(),
+    );
+    let batches = vec![TransactionBatch::new(vec![tx]).unwrap()];
+
+    let result = block_builder.build_block(&batches).await;
+    assert_eq!(result, Err(BuildBlockError::InconsistentAccountStates(vec![account_id])));
+
+    // the inputs are sound again on the next attempt
+    block_builder.build_block(&batches).await.unwrap();
+    assert_eq!(*store.inner().num_apply_block_called.read().await, 1);
+}
+
+/// Tests that the canonical ordering builds the same block out of the same batches whatever their
+/// order, unlike the arrival ordering
+#[tokio::test]
+async fn test_build_block_tx_ordering() {
+    let batches = vec![
+        TransactionBatch::from_notes_created(&[1]),
+        TransactionBatch::from_notes_created(&[2]),
+    ];
+    let reversed: Vec<_> = batches.iter().rev().cloned().collect();
+
+    for tx_ordering in [TxOrdering::Arrival, TxOrdering::Canonical] {
+        let mut note_roots = Vec::new();
+        for batches in [&batches, &reversed] {
+            let store = Arc::new(
+                MockStoreSuccessBuilder::new()
+                    .initial_accounts(
+                        batches.iter().flat_map(|batch| batch.account_initial_states()),
+                    )
+                    .build(),
+            );
+            let block_builder = DefaultBlockBuilder::new(
+                store.clone(),
+                store.clone(),
+                Arc::new(BlockQuarantine::new(disabled_quarantine())),
+                Arc::new(LatencyTracker::new(10)),
+                block_builder_options(),
+            )
+            .with_tx_ordering(tx_ordering);
+
+            block_builder.build_block(batches).await.unwrap();
+            note_roots.push(store.last_block_header.read().await.note_root());
+        }
+
+        match tx_ordering {
+            TxOrdering::Arrival => assert_ne!(note_roots[0], note_roots[1]),
+            TxOrdering::Canonical => assert_eq!(note_roots[0], note_roots[1]),
+        }
+    }
+}
+
+/// Tests that the public notes consumed by a block built by the block builder are linked to their
+/// nullifiers by the store the block is applied to
+#[test]
+fn test_consumed_notes_are_linked_by_the_store() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let config = StoreConfig {
+                data_directory: "store".into(),
+                ..Default::default()
+            };
+            let data_directory = config.data_directory();
+            let genesis_timestamp =
+                SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() - 3600;
+            let genesis = GenesisState::new(Vec::new(), 1, genesis_timestamp, config.chain_id);
+            fs::create_dir_all(data_directory.root()).unwrap();
+            fs::write(data_directory.genesis_filepath(), genesis.to_bytes()).unwrap();
+
+            let db = Db::setup(config.clone()).await.unwrap();
+            let store_api = Arc::new(StoreApi::load(&config, db).await.unwrap());
+            let store = Arc::new(DefaultStore::new(store_api.clone()));
+
+            let block_builder = DefaultBlockBuilder::new(
+                store.clone(),
+                store.clone(),
+                Arc::new(BlockQuarantine::new(disabled_quarantine())),
+                Arc::new(LatencyTracker::new(10)),
+                block_builder_options(),
+            );
+
+            // the first note is submitted along with its transaction, the second one isn't
+            let notes: Vec<_> = (0..2).map(note_by_index).collect();
+            let tx = DummyProvenTxGenerator::new().dummy_proven_tx_with_params(
+                AccountId::new_unchecked(42u64.into()),
+                Digest::default(),
+                [Felt::from(2u64), Felt::from(2u64), Felt::from(2u64), Felt::from(2u64)].into(),
+                InputNotes::new(notes.iter().map(|note| note.nullifier()).collect()).unwrap(),
+                OutputNotes::new(Vec::new()).unwrap(),
+            );
+            let batch = TransactionBatch::new(vec![tx])
+                .unwrap()
+                .with_consumed_notes(vec![(notes[0].id().inner(), notes[0].nullifier().inner())]);
+            block_builder.build_block(&[batch]).await.unwrap();
+
+            let request = Request::new(SyncStateRequest {
+                block_num: 0,
+                note_hashes: notes.iter().map(|note| note.id().inner().into()).collect(),
+                ..Default::default()
+            });
+            let response = Api::sync_state(store_api.as_ref(), request).await.unwrap().into_inner();
+            assert_eq!(
+                response.consumed_notes,
+                vec![ConsumedNoteUpdate {
+                    note_hash: Some(notes[0].id().inner().into()),
+                    nullifier: Some(notes[0].nullifier().inner().into()),
+                    block_num: 1,
+                }]
+            );
+        });
+
+        Ok(())
+    });
+}
+
+fn block_builder_options() -> DefaultBlockBuilderOptions {
+    DefaultBlockBuilderOptions {
+        prover_threads: 1,
+        prover_priority: ProverPriority::Normal,
+        chain_id: 1,
+    }
+}
+
+fn disabled_quarantine() -> QuarantineConfig {
+    QuarantineConfig {
+        directory: "quarantine".into(),
+        max_size_mb: 0,
+    }
 }
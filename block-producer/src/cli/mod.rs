@@ -1,8 +1,8 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 
-use crate::config;
+use crate::config::{self, BatchingConfig};
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Parser)]
 #[command(version, about, long_about = None)]
@@ -10,6 +10,9 @@ pub struct Cli {
     #[arg(short, long, value_name = "FILE", default_value = config::CONFIG_FILENAME)]
     pub config: PathBuf,
 
+    #[command(flatten)]
+    pub batching: BatchingArgs,
+
     #[command(subcommand)]
     pub command: Command,
 }
@@ -17,4 +20,52 @@ pub struct Cli {
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Subcommand)]
 pub enum Command {
     Serve,
+
+    /// Prints the journal of the block production attempts, from the oldest to the latest.
+    DumpJournal {
+        /// Prints the entries as they are stored, one JSON object per line.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Overrides of the `batching` table of the config file.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Args)]
+pub struct BatchingArgs {
+    /// Overrides the number of transactions per batch.
+    #[arg(long, value_name = "NUM")]
+    pub batch_size: Option<usize>,
+
+    /// Overrides the interval between two blocks.
+    #[arg(long, value_name = "MILLISECONDS")]
+    pub block_frequency_ms: Option<u64>,
+
+    /// Overrides the interval at which the queue checks for batches ready to be sealed.
+    #[arg(long, value_name = "MILLISECONDS")]
+    pub build_batch_frequency_ms: Option<u64>,
+
+    /// Overrides the number of batches included in a block at most.
+    #[arg(long, value_name = "NUM")]
+    pub max_batches_per_block: Option<usize>,
+}
+
+impl BatchingArgs {
+    /// Overrides the settings of `config` given on the command line.
+    pub fn apply(
+        &self,
+        config: &mut BatchingConfig,
+    ) {
+        if let Some(batch_size) = self.batch_size {
+            config.batch_size = batch_size;
+        }
+        if let Some(block_frequency_ms) = self.block_frequency_ms {
+            config.block_frequency_ms = block_frequency_ms;
+        }
+        if let Some(build_batch_frequency_ms) = self.build_batch_frequency_ms {
+            config.build_batch_frequency_ms = build_batch_frequency_ms;
+        }
+        if let Some(max_batches_per_block) = self.max_batches_per_block {
+            config.max_batches_per_block = max_batches_per_block;
+        }
+    }
 }
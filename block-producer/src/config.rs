@@ -1,20 +1,162 @@
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    path::PathBuf,
+    time::Duration,
+};
 
-use miden_node_utils::config::Endpoint;
+use miden_node_proto::TX_KERNEL_VERSION;
+use miden_node_utils::{
+    config::{Endpoint, GrpcServerConfig, ListenAddress},
+    documented_config,
+    formatting::{format_array, format_opt},
+};
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    errors::BatchingConfigError, CREATED_NOTES_TREE_INSERTION_DEPTH,
+    MAX_NUM_CREATED_NOTES_PER_BATCH,
+};
+
 pub const CONFIG_FILENAME: &str = "miden-block-producer.toml";
 
+/// Number of batches whose notes fit in the notes tree of a block
+const MAX_BATCHES_PER_BLOCK: usize = 1 << CREATED_NOTES_TREE_INSERTION_DEPTH;
+
 // Main config
 // ================================================================================================
 
-/// Block producer specific configuration
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
-pub struct BlockProducerConfig {
-    pub endpoint: Endpoint,
+documented_config! {
+    /// Block producer specific configuration
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    pub struct BlockProducerConfig {
+        /// Endpoint of the block producer's gRPC server.
+        pub endpoint: Endpoint => table,
+
+        /// Additional addresses to listen on, e.g. IPv6 or Unix domain sockets, as in
+        /// `[{ host = "::1", port = 48046 }, { unix_socket = "/run/miden/block-producer.sock" }]`.
+        #[serde(default)]
+        pub listen: Vec<ListenAddress>,
+
+        /// Store gRPC endpoint in the format `http://<host>[:<port>]`, or `unix://<path>` for a
+        /// Unix domain socket.
+        pub store_url: String,
+
+        /// Calls the store of this node directly instead of over gRPC, `store_url` is then unused.
+        /// Only available when the block producer is started by the node, along with the store.
+        #[serde(default)]
+        pub embedded_store: bool,
+
+        /// File holding the hex encoded seed of one of the store's operator keys, signing the
+        /// requests reading the store's counters. The counters of a remote store are not audited
+        /// at startup if missing.
+        #[serde(default)]
+        pub store_operator_key_file: Option<PathBuf> => "\"./store-operator-key.hex\"",
+
+        /// Identifier of the chain the blocks are built for, must match the chain id of the
+        /// genesis file.
+        pub chain_id: u32,
+
+        /// Hex encoded hash of the genesis block, the block producer refuses to start against a
+        /// store of another genesis block. Any genesis block is accepted if missing.
+        #[serde(default)]
+        pub genesis_hash: Option<String> => "\"0x...\"",
+
+        /// Number of threads the block kernel execution and proving may use.
+        pub prover_threads: usize,
+
+        /// Scheduling priority of the proving threads, `"normal"` or `"low"`, only supported on
+        /// Linux.
+        pub prover_priority: ProverPriority,
+
+        /// Beyond any of these limits new transactions are rejected and fewer batches are built
+        /// concurrently, until the load decreases. `0` disables a limit.
+        pub load_shedding: LoadSheddingConfig => table,
+
+        /// Diagnostics of the blocks which failed to be built.
+        pub quarantine: QuarantineConfig => table,
+
+        /// Prioritization of the transactions of the queue, by lane.
+        pub lanes: LanesConfig => table,
+
+        /// Capacity of the batches reserved for the transactions signed by partners.
+        #[serde(default)]
+        pub reservations: ReservationsConfig => table,
+
+        /// Signing of the receipts of the accepted transactions.
+        #[serde(default)]
+        pub receipts: ReceiptsConfig => table,
+
+        /// Identity of the block producer, attributing the blocks it produces to it.
+        #[serde(default)]
+        pub identity: IdentityConfig => table,
+
+        /// Capacity of the transaction queue, and what to drop once it is full.
+        #[serde(default)]
+        pub eviction: EvictionConfig => table,
+
+        /// Versions of the transaction kernel accepted, both included. Widen the window ahead of a
+        /// kernel upgrade, and raise `min_version` once the previous kernel is retired.
+        #[serde(default)]
+        pub tx_kernel: TxKernelConfig => table,
+
+        /// Size of the batches, and frequency of the batches and blocks. Larger batches and blocks
+        /// raise the throughput, more frequent ones lower the latency.
+        #[serde(default)]
+        pub batching: BatchingConfig => table,
+
+        /// Slowdown of the batch building while the block prover falls behind.
+        #[serde(default)]
+        pub adaptive_batching: AdaptiveBatchingConfig => table,
+
+        /// Order of the transactions within the blocks, `"arrival"` or `"canonical"` (by id),
+        /// recorded along with every block.
+        #[serde(default)]
+        pub tx_ordering: TxOrdering,
+
+        /// Tuning of the gRPC server, every option left to `0` keeps the default of tonic.
+        #[serde(default)]
+        pub grpc: GrpcServerConfig => table,
+
+        /// Web dashboard of the queue depth, latest blocks, rejected transactions and proving
+        /// times, served over HTTP without authentication.
+        #[serde(default)]
+        pub dashboard: DashboardConfig => table,
 
-    /// Store gRPC endpoint in the format `http://<host>[:<port>]`.
-    pub store_url: String,
+        /// Webhooks notified of the applied blocks, and of the committed and rejected
+        /// transactions.
+        #[serde(default)]
+        pub webhooks: WebhooksConfig => table,
+
+        /// Journal of the block production attempts, printed by
+        /// `miden-node-block-producer dump-journal`.
+        #[serde(default)]
+        pub journal: JournalConfig => table,
+
+        /// Hex encoded public keys of the operators allowed to sign the admin operations, e.g.
+        /// those of `miden-node restart-producer`. The admin operations are disabled if empty.
+        #[serde(default)]
+        pub operator_keys: Vec<String>,
+
+        /// Restart of the block producer by `miden-node restart-producer`.
+        #[serde(default)]
+        pub restart: RestartConfig => table,
+
+        /// Persistence of the transaction queue.
+        #[serde(default)]
+        pub mempool: MempoolConfig => table,
+
+        /// Time authority attesting the time the blocks were produced.
+        #[serde(default)]
+        pub time_authority: TimeAuthorityConfig => table,
+
+        /// Proving of the transaction batches, committed to by the batch root of the blocks.
+        #[serde(default)]
+        pub batch_proving: BatchProvingConfig => table,
+
+        /// Proving of the blocks by a remote proving service, instead of on the prover threads.
+        #[serde(default)]
+        pub remote_prover: RemoteProverConfig => table,
+    }
 }
 
 impl BlockProducerConfig {
@@ -29,8 +171,786 @@ impl Display for BlockProducerConfig {
         f: &mut Formatter<'_>,
     ) -> std::fmt::Result {
         f.write_fmt(format_args!(
-            "{{ endpoint: \"{}\", store_url: \"{}\" }}",
-            self.endpoint, self.store_url
+            "{{ endpoint: \"{}\", listen: {}, store_url: \"{}\", embedded_store: {}, store_operator_key_file: {:?}, chain_id: {}, genesis_hash: {:?}, prover_threads: {}, prover_priority: {}, load_shedding: {}, quarantine: {}, lanes: {}, reservations: {}, receipts: {}, identity: {}, eviction: {}, tx_kernel: {}, batching: {}, adaptive_batching: {}, tx_ordering: {}, grpc: {}, dashboard: {}, webhooks: {}, journal: {}, operator_keys: {:?}, restart: {}, mempool: {}, time_authority: {}, batch_proving: {}, remote_prover: {} }}",
+            self.endpoint, format_array(&self.listen), self.store_url, self.embedded_store, self.store_operator_key_file, self.chain_id, self.genesis_hash, self.prover_threads, self.prover_priority, self.load_shedding, self.quarantine, self.lanes, self.reservations, self.receipts, self.identity, self.eviction, self.tx_kernel, self.batching, self.adaptive_batching, self.tx_ordering, self.grpc, self.dashboard, self.webhooks, self.journal, self.operator_keys, self.restart, self.mempool, self.time_authority, self.batch_proving, self.remote_prover
+        ))
+    }
+}
+
+impl Default for BlockProducerConfig {
+    fn default() -> Self {
+        Self {
+            // port defined as:
+            // sum(ord(c)**p for (p, c) in enumerate('miden-block-producer', 1)) % 2**16
+            endpoint: Endpoint {
+                host: "localhost".to_string(),
+                port: 48046,
+            },
+            listen: Vec::new(),
+            store_url: "http://localhost:28943".to_string(),
+            embedded_store: false,
+            store_operator_key_file: None,
+            chain_id: 1,
+            genesis_hash: None,
+            prover_threads: 1,
+            prover_priority: ProverPriority::default(),
+            load_shedding: LoadSheddingConfig {
+                max_rss_mb: 8192,
+                max_queued_transactions: 10_000,
+                max_ready_batches: 64,
+            },
+            quarantine: QuarantineConfig {
+                directory: "quarantine".into(),
+                max_size_mb: 512,
+            },
+            lanes: LanesConfig {
+                reserved_consuming_percent: 25,
+            },
+            reservations: ReservationsConfig::default(),
+            receipts: ReceiptsConfig::default(),
+            identity: IdentityConfig::default(),
+            eviction: EvictionConfig::default(),
+            tx_kernel: TxKernelConfig::default(),
+            batching: BatchingConfig::default(),
+            adaptive_batching: AdaptiveBatchingConfig::default(),
+            tx_ordering: TxOrdering::default(),
+            grpc: GrpcServerConfig::default(),
+            dashboard: DashboardConfig::default(),
+            webhooks: WebhooksConfig::default(),
+            journal: JournalConfig::default(),
+            operator_keys: Vec::new(),
+            restart: RestartConfig::default(),
+            mempool: MempoolConfig::default(),
+            time_authority: TimeAuthorityConfig::default(),
+            batch_proving: BatchProvingConfig::default(),
+            remote_prover: RemoteProverConfig::default(),
+        }
+    }
+}
+
+/// Scheduling priority of the block prover threads
+#[derive(
+    Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ProverPriority {
+    /// Same priority as the other tasks of the block producer.
+    #[default]
+    Normal,
+
+    /// Lower priority than the other tasks, so proving doesn't starve the gRPC and DB work on
+    /// shared hardware. Only supported on Linux.
+    Low,
+}
+
+impl Display for ProverPriority {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            ProverPriority::Normal => f.write_str("normal"),
+            ProverPriority::Low => f.write_str("low"),
+        }
+    }
+}
+
+/// Order of the transactions within the blocks
+#[derive(
+    Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TxOrdering {
+    /// The transactions are ordered as they arrived, as selected from the transaction queue.
+    #[default]
+    Arrival,
+
+    /// The transactions of a batch are ordered by id, and the batches of a block by id, so a block
+    /// is replayed identically from the same set of batches whatever their arrival order.
+    ///
+    /// With either ordering, the transactions of an account follow the one they start from.
+    Canonical,
+}
+
+impl Display for TxOrdering {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            TxOrdering::Arrival => f.write_str("arrival"),
+            TxOrdering::Canonical => f.write_str("canonical"),
+        }
+    }
+}
+
+documented_config! {
+    /// Limits on the resources used by the block producer, a limit of `0` disables the
+    /// corresponding check
+    #[derive(
+        Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+    )]
+    pub struct LoadSheddingConfig {
+        /// Resident set size of the process in MiB, only measured on Linux.
+        pub max_rss_mb: u64,
+
+        /// Number of transactions waiting to be batched.
+        pub max_queued_transactions: usize,
+
+        /// Number of batches waiting to be included in a block.
+        pub max_ready_batches: usize,
+    }
+}
+
+impl Display for LoadSheddingConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ max_rss_mb: {}, max_queued_transactions: {}, max_ready_batches: {} }}",
+            self.max_rss_mb, self.max_queued_transactions, self.max_ready_batches
+        ))
+    }
+}
+
+documented_config! {
+    /// Quarantine of the blocks which failed to be built
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    pub struct QuarantineConfig {
+        /// Directory in which a diagnostic dump is written for every failed block.
+        pub directory: PathBuf,
+
+        /// Total size of the dumps in MiB, the oldest dumps are removed beyond it. `0` disables
+        /// the dumps.
+        pub max_size_mb: u64,
+    }
+}
+
+impl Display for QuarantineConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ directory: {:?}, max_size_mb: {} }}",
+            self.directory, self.max_size_mb
+        ))
+    }
+}
+
+documented_config! {
+    /// Prioritization of the transactions of the queue, by lane
+    #[derive(
+        Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+    )]
+    pub struct LanesConfig {
+        /// Percentage of the transactions selected to be batched reserved for transactions
+        /// consuming notes, so they are not starved by transactions which only create notes.
+        /// Reserved capacity not used by them is given to the other transactions, `0` selects
+        /// transactions in arrival order.
+        pub reserved_consuming_percent: u8,
+    }
+}
+
+impl Display for LanesConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ reserved_consuming_percent: {} }}",
+            self.reserved_consuming_percent
+        ))
+    }
+}
+
+documented_config! {
+    /// Capacity of the batches reserved for the transactions signed by partners of the block
+    /// producer, e.g. exchanges and bridges, see [crate::txqueue::Reservations]
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    pub struct ReservationsConfig {
+        /// Partners signing their transactions with the hex encoded public `key` get
+        /// `reserved_percent` of the transactions selected to be batched, e.g.
+        /// `[{ name = "exchange", key = "0x...", reserved_percent = 10 }]`. The reservations must
+        /// add up to 100% at most, unused reservations go to the other transactions.
+        pub partners: Vec<PartnerConfig>,
+    }
+}
+
+impl Display for ReservationsConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!("{{ partners: {} }}", format_array(&self.partners)))
+    }
+}
+
+/// A partner of the block producer, and its reservation
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+pub struct PartnerConfig {
+    /// Name of the partner, as reported in the logs.
+    pub name: String,
+
+    /// Hex encoded public key the partner signs its transactions with.
+    pub key: String,
+
+    /// Percentage of the transactions selected to be batched which is reserved for the
+    /// transactions signed by the partner. Reserved capacity not used by them expires at the end of
+    /// the selection, and is given to the other transactions.
+    pub reserved_percent: u8,
+}
+
+impl Display for PartnerConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ name: \"{}\", key: \"{}\", reserved_percent: {} }}",
+            self.name, self.key, self.reserved_percent
+        ))
+    }
+}
+
+documented_config! {
+    /// Signing of the receipts of the accepted transactions
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    pub struct ReceiptsConfig {
+        /// File holding the hex encoded seed of the RPO Falcon512 key signing the receipts, e.g.
+        /// created with `openssl rand -hex 40`. No receipt is returned if missing.
+        pub key_file: Option<PathBuf> => "\"./receipt-key.hex\"",
+    }
+}
+
+impl Display for ReceiptsConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!("{{ key_file: {:?} }}", self.key_file))
+    }
+}
+
+documented_config! {
+    /// Identity of the block producer
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    pub struct IdentityConfig {
+        /// File holding the hex encoded seed of the RPO Falcon512 key signing the block headers.
+        /// The blocks are not signed if missing.
+        pub key_file: Option<PathBuf> => "\"./identity-key.hex\"",
+    }
+}
+
+impl Display for IdentityConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!("{{ key_file: {:?} }}", self.key_file))
+    }
+}
+
+/// Policy choosing what to drop when the transaction queue is full, see
+/// [crate::txqueue::EvictionPolicy]
+#[derive(
+    Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicyKind {
+    /// New transactions are rejected.
+    #[default]
+    RejectNewest,
+
+    /// The transaction which waited the longest is evicted.
+    OldestFirst,
+
+    /// The newest transaction of the account with the most queued transactions is evicted, if it
+    /// has more than `max_per_account` of them. New transactions are rejected otherwise.
+    AccountCap,
+
+    /// The transaction paying the lowest fee is evicted, if the new transaction pays a higher
+    /// fee. New transactions are rejected otherwise.
+    LowestFeeFirst,
+}
+
+impl Display for EvictionPolicyKind {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            EvictionPolicyKind::RejectNewest => f.write_str("reject_newest"),
+            EvictionPolicyKind::OldestFirst => f.write_str("oldest_first"),
+            EvictionPolicyKind::AccountCap => f.write_str("account_cap"),
+            EvictionPolicyKind::LowestFeeFirst => f.write_str("lowest_fee_first"),
+        }
+    }
+}
+
+documented_config! {
+    /// Capacity of the transaction queue
+    #[derive(
+        Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+    )]
+    #[serde(default)]
+    pub struct EvictionConfig {
+        /// Maximum number of transactions waiting to be batched, `0` leaves the queue unbounded.
+        pub max_queued_transactions: usize,
+        /// What to drop when a transaction arrives while the queue is full: the new transaction
+        /// (`"reject_newest"`), the oldest queued one (`"oldest_first"`), the newest one of the
+        /// account with the most queued transactions, if over `max_per_account`
+        /// (`"account_cap"`), or the one paying the lowest fee, if lower than the new one's
+        /// (`"lowest_fee_first"`).
+        pub policy: EvictionPolicyKind,
+        /// Number of queued transactions an account may have, only used by the `"account_cap"`
+        /// policy.
+        pub max_per_account: usize,
+        /// Number of blocks applied while a transaction waits in the queue after which it is
+        /// dropped, `0` keeps the transactions until they are batched.
+        pub ttl_blocks: u32,
+    }
+}
+
+impl Display for EvictionConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ max_queued_transactions: {}, policy: {}, max_per_account: {}, ttl_blocks: {} }}",
+            self.max_queued_transactions, self.policy, self.max_per_account, self.ttl_blocks
+        ))
+    }
+}
+
+documented_config! {
+    /// Versions of the transaction kernel accepted by the block producer, both bounds included
+    ///
+    /// Widening the window ahead of a kernel upgrade lets the clients adopt the new kernel while
+    /// the previous one is still accepted, raising `min_version` retires the previous one.
+    #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct TxKernelConfig {
+        /// Oldest version of the transaction kernel accepted.
+        pub min_version: u32,
+        /// Newest version of the transaction kernel accepted.
+        pub max_version: u32,
+    }
+}
+
+impl TxKernelConfig {
+    /// Returns `true` if transactions proven with the kernel `version` are accepted.
+    pub fn supports(
+        &self,
+        version: u32,
+    ) -> bool {
+        self.min_version <= version && version <= self.max_version
+    }
+}
+
+impl Default for TxKernelConfig {
+    fn default() -> Self {
+        Self {
+            min_version: TX_KERNEL_VERSION,
+            max_version: TX_KERNEL_VERSION,
+        }
+    }
+}
+
+impl Display for TxKernelConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ min_version: {}, max_version: {} }}",
+            self.min_version, self.max_version
+        ))
+    }
+}
+
+documented_config! {
+    /// Size of the batches, and frequency at which the batches and blocks are built
+    ///
+    /// Larger batches and blocks raise the throughput of the block producer, more frequent ones
+    /// lower the latency of the transactions.
+    #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct BatchingConfig {
+        /// Number of transactions per batch, the batches are sealed when full.
+        pub batch_size: usize,
+
+        /// Interval between two blocks, in milliseconds.
+        pub block_frequency_ms: u64,
+
+        /// Interval at which the transaction queue checks for batches ready to be sealed, in
+        /// milliseconds.
+        pub build_batch_frequency_ms: u64,
+
+        /// Number of batches included in a block at most.
+        pub max_batches_per_block: usize,
+    }
+}
+
+impl BatchingConfig {
+    /// Ensures the batches and blocks are not empty, the batches fit a note per transaction and
+    /// the blocks fit the batches in their notes trees, and the batches are checked for at least
+    /// once per block.
+    pub fn validate(&self) -> Result<(), BatchingConfigError> {
+        if self.batch_size == 0 {
+            return Err(BatchingConfigError::EmptyBatches);
+        }
+        if self.batch_size > MAX_NUM_CREATED_NOTES_PER_BATCH {
+            return Err(BatchingConfigError::TooManyTransactionsPerBatch(self.batch_size));
+        }
+        if self.max_batches_per_block == 0 {
+            return Err(BatchingConfigError::EmptyBlocks);
+        }
+        if self.max_batches_per_block > MAX_BATCHES_PER_BLOCK {
+            return Err(BatchingConfigError::TooManyBatchesPerBlock(self.max_batches_per_block));
+        }
+        if self.build_batch_frequency_ms == 0 {
+            return Err(BatchingConfigError::ZeroFrequency("build_batch_frequency_ms"));
+        }
+        if self.block_frequency_ms == 0 {
+            return Err(BatchingConfigError::ZeroFrequency("block_frequency_ms"));
+        }
+        if self.build_batch_frequency_ms > self.block_frequency_ms {
+            return Err(BatchingConfigError::BatchesSlowerThanBlocks {
+                build_batch_frequency_ms: self.build_batch_frequency_ms,
+                block_frequency_ms: self.block_frequency_ms,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn block_frequency(&self) -> Duration {
+        Duration::from_millis(self.block_frequency_ms)
+    }
+
+    pub fn build_batch_frequency(&self) -> Duration {
+        Duration::from_millis(self.build_batch_frequency_ms)
+    }
+}
+
+impl Default for BatchingConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 2,
+            block_frequency_ms: 10_000,
+            build_batch_frequency_ms: 100,
+            max_batches_per_block: 4,
+        }
+    }
+}
+
+impl Display for BatchingConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ batch_size: {}, block_frequency_ms: {}, build_batch_frequency_ms: {}, max_batches_per_block: {} }}",
+            self.batch_size, self.block_frequency_ms, self.build_batch_frequency_ms, self.max_batches_per_block
+        ))
+    }
+}
+
+documented_config! {
+    /// Slowdown of the batch building while blocks are proven slower than batches are built
+    ///
+    /// Once more than `target_backlog_blocks` blocks worth of batches wait to be proven, batches
+    /// are sealed half as often, down to `max_slowdown` times less often than normal, and twice as
+    /// often again once the backlog is back under the target.
+    #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct AdaptiveBatchingConfig {
+        /// Number of blocks worth of batches waiting to be proven beyond which batches are sealed
+        /// less and less often, `0` disables the slowdown.
+        pub target_backlog_blocks: usize,
+        /// Factor by which the batches are sealed less often at most.
+        pub max_slowdown: u32,
+    }
+}
+
+impl Default for AdaptiveBatchingConfig {
+    fn default() -> Self {
+        Self {
+            target_backlog_blocks: 2,
+            max_slowdown: 8,
+        }
+    }
+}
+
+impl Display for AdaptiveBatchingConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ target_backlog_blocks: {}, max_slowdown: {} }}",
+            self.target_backlog_blocks, self.max_slowdown
+        ))
+    }
+}
+
+documented_config! {
+    /// Web dashboard showing the depth of the transaction queue over time, the latest blocks, the
+    /// rejected transactions and the proving times
+    ///
+    /// The dashboard has no authentication, it should only be reachable by the operator.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    pub struct DashboardConfig {
+        /// Endpoint of the dashboard's HTTP server, the dashboard is disabled if missing.
+        pub endpoint: Option<Endpoint> => "{ host = \"localhost\", port = 48047 }",
+    }
+}
+
+impl Display for DashboardConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!("{{ endpoint: {} }}", format_opt(self.endpoint.as_ref())))
+    }
+}
+
+documented_config! {
+    /// Webhooks the events of the block producer are posted to, see [crate::notifier]
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct WebhooksConfig {
+        /// URLs the events are posted to, the notifications are disabled if empty.
+        pub urls: Vec<String>,
+
+        /// File holding the secret the bodies are signed with, they are not signed if missing.
+        pub secret_file: Option<PathBuf> => "\"./webhook-secret\"",
+
+        /// Number of times a failed delivery is retried.
+        pub max_retries: u32,
+
+        /// Time waited before the first retry of a delivery, and twice as long before every
+        /// following one, in milliseconds.
+        pub initial_backoff_ms: u64,
+    }
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            secret_file: None,
+            max_retries: 5,
+            initial_backoff_ms: 1000,
+        }
+    }
+}
+
+impl Display for WebhooksConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ urls: {:?}, secret_file: {:?}, max_retries: {}, initial_backoff_ms: {} }}",
+            self.urls, self.secret_file, self.max_retries, self.initial_backoff_ms
+        ))
+    }
+}
+
+documented_config! {
+    /// Journal the block production attempts are appended to, see [crate::journal]
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct JournalConfig {
+        /// File every block production attempt is appended to.
+        pub path: PathBuf,
+
+        /// Size of the journal in MiB past which it is rotated, the journal is disabled if `0`.
+        pub max_size_mb: u64,
+
+        /// Number of rotated journals kept along with the current one.
+        pub max_files: u32,
+    }
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            path: "block-journal.jsonl".into(),
+            max_size_mb: 0,
+            max_files: 4,
+        }
+    }
+}
+
+impl Display for JournalConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ path: {:?}, max_size_mb: {}, max_files: {} }}",
+            self.path, self.max_size_mb, self.max_files
+        ))
+    }
+}
+
+documented_config! {
+    /// Restart of the block producer orchestrated by `miden-node restart-producer`, see
+    /// [crate::restart]
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct RestartConfig {
+        /// File the queued transactions are saved to by `miden-node restart-producer --snapshot`,
+        /// and admitted again from once the block producer started.
+        pub snapshot_path: PathBuf,
+    }
+}
+
+impl Default for RestartConfig {
+    fn default() -> Self {
+        Self {
+            snapshot_path: "queue-snapshot.bin".into(),
+        }
+    }
+}
+
+impl Display for RestartConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!("{{ snapshot_path: {:?} }}", self.snapshot_path))
+    }
+}
+
+documented_config! {
+    /// Persistence of the transaction queue, see [crate::txqueue::TransactionLog]
+    #[derive(Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct MempoolConfig {
+        /// File the accepted transactions are logged to until they are included in a block or
+        /// dropped, and admitted again from once the block producer started. The queue is kept in
+        /// memory only if missing.
+        pub wal_path: Option<PathBuf> => "\"./mempool.wal\"",
+    }
+}
+
+impl Display for MempoolConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!("{{ wal_path: {:?} }}", self.wal_path))
+    }
+}
+
+documented_config! {
+    /// Time authority attesting the time the blocks were produced, see [crate::time_authority]
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct TimeAuthorityConfig {
+        /// URL the hash of every block is posted to, the blocks are not attested if missing.
+        pub url: Option<String> => "\"https://time.example.com/attest\"",
+
+        /// Hex encoded public key of the time authority, the attestations signed by any other key
+        /// are discarded. Required along with `url`.
+        pub authority_key: Option<String> => "\"0x...\"",
+
+        /// Time in milliseconds after which an attestation request fails, the block being applied
+        /// without one.
+        pub timeout_ms: u64,
+    }
+}
+
+impl Default for TimeAuthorityConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            authority_key: None,
+            timeout_ms: 2000,
+        }
+    }
+}
+
+impl Display for TimeAuthorityConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ url: {:?}, authority_key: {:?}, timeout_ms: {} }}",
+            self.url, self.authority_key, self.timeout_ms
+        ))
+    }
+}
+
+documented_config! {
+    /// Proving of the transaction batches, see [crate::batch_builder::prover]
+    #[derive(Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct BatchProvingConfig {
+        /// Number of threads proving the batches, run with `prover_priority`. The batches are not
+        /// proven if `0`.
+        pub workers: usize,
+    }
+}
+
+impl Display for BatchProvingConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!("{{ workers: {} }}", self.workers))
+    }
+}
+
+documented_config! {
+    /// Remote prover of the blocks, see [crate::block_builder::prover::remote]
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct RemoteProverConfig {
+        /// URL of the proving service, in the format `http://<host>[:<port>]` or `unix://<path>`.
+        /// The blocks are proven on the prover threads if missing.
+        pub url: Option<String> => "\"http://localhost:50051\"",
+
+        /// Time in milliseconds after which a proving request fails.
+        pub timeout_ms: u64,
+
+        /// Whether the blocks the proving service failed to prove in time are proven on the prover
+        /// threads, rather than failing.
+        pub fallback_to_local: bool,
+    }
+}
+
+impl RemoteProverConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+impl Default for RemoteProverConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            timeout_ms: 30_000,
+            fallback_to_local: true,
+        }
+    }
+}
+
+impl Display for RemoteProverConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ url: {:?}, timeout_ms: {}, fallback_to_local: {} }}",
+            self.url, self.timeout_ms, self.fallback_to_local
         ))
     }
 }
@@ -49,10 +969,17 @@ mod tests {
     use std::path::PathBuf;
 
     use figment::Jail;
-    use miden_node_utils::config::{load_config, Endpoint};
+    use miden_node_utils::config::{load_config, Endpoint, GrpcServerConfig};
 
-    use super::{BlockProducerConfig, BlockProducerTopLevelConfig};
-    use crate::config::CONFIG_FILENAME;
+    use super::{
+        AdaptiveBatchingConfig, BatchProvingConfig, BatchingConfig, BlockProducerConfig,
+        BlockProducerTopLevelConfig, DashboardConfig, EvictionConfig, EvictionPolicyKind,
+        IdentityConfig, JournalConfig, LanesConfig, LoadSheddingConfig, MempoolConfig,
+        PartnerConfig, ProverPriority, QuarantineConfig, ReceiptsConfig, RemoteProverConfig,
+        ReservationsConfig, RestartConfig, TimeAuthorityConfig, TxKernelConfig, TxOrdering,
+        WebhooksConfig,
+    };
+    use crate::{config::CONFIG_FILENAME, errors::BatchingConfigError};
 
     #[test]
     fn test_block_producer_config() {
@@ -62,10 +989,88 @@ mod tests {
                 r#"
                     [block_producer]
                     store_url = "http://store:8000"
+                    chain_id = 1
+                    prover_threads = 2
+                    prover_priority = "low"
+                    tx_ordering = "canonical"
+                    operator_keys = ["0x01"]
 
                     [block_producer.endpoint]
                     host = "127.0.0.1"
                     port = 8080
+
+                    [block_producer.load_shedding]
+                    max_rss_mb = 4096
+                    max_queued_transactions = 1000
+                    max_ready_batches = 0
+
+                    [block_producer.quarantine]
+                    directory = "quarantine"
+                    max_size_mb = 256
+
+                    [block_producer.lanes]
+                    reserved_consuming_percent = 25
+
+                    [[block_producer.reservations.partners]]
+                    name = "exchange"
+                    key = "0x0102"
+                    reserved_percent = 10
+
+                    [block_producer.receipts]
+                    key_file = "receipt-key.hex"
+
+                    [block_producer.identity]
+                    key_file = "identity-key.hex"
+
+                    [block_producer.eviction]
+                    max_queued_transactions = 5000
+                    policy = "account_cap"
+                    max_per_account = 8
+                    ttl_blocks = 20
+
+                    [block_producer.tx_kernel]
+                    max_version = 2
+
+                    [block_producer.batching]
+                    batch_size = 16
+                    block_frequency_ms = 5000
+
+                    [block_producer.adaptive_batching]
+                    target_backlog_blocks = 3
+
+                    [block_producer.grpc]
+                    max_connections = 64
+                    initial_stream_window_size = 1048576
+
+                    [block_producer.dashboard.endpoint]
+                    host = "127.0.0.1"
+                    port = 8081
+
+                    [block_producer.webhooks]
+                    urls = ["https://example.com/miden"]
+                    secret_file = "webhook-secret"
+                    max_retries = 3
+
+                    [block_producer.journal]
+                    path = "journal/blocks.jsonl"
+                    max_size_mb = 64
+
+                    [block_producer.restart]
+                    snapshot_path = "snapshots/queue.bin"
+
+                    [block_producer.mempool]
+                    wal_path = "wal/mempool.wal"
+
+                    [block_producer.time_authority]
+                    url = "https://time.example.com/attest"
+                    authority_key = "0x0304"
+
+                    [block_producer.batch_proving]
+                    workers = 2
+
+                    [block_producer.remote_prover]
+                    url = "http://prover:50051"
+                    fallback_to_local = false
                 "#,
             )?;
 
@@ -80,7 +1085,100 @@ mod tests {
                             host: "127.0.0.1".to_string(),
                             port: 8080,
                         },
+                        listen: Vec::new(),
                         store_url: "http://store:8000".to_string(),
+                        embedded_store: false,
+                        store_operator_key_file: None,
+                        chain_id: 1,
+                        genesis_hash: None,
+                        prover_threads: 2,
+                        prover_priority: ProverPriority::Low,
+                        load_shedding: LoadSheddingConfig {
+                            max_rss_mb: 4096,
+                            max_queued_transactions: 1000,
+                            max_ready_batches: 0,
+                        },
+                        quarantine: QuarantineConfig {
+                            directory: "quarantine".into(),
+                            max_size_mb: 256,
+                        },
+                        lanes: LanesConfig {
+                            reserved_consuming_percent: 25,
+                        },
+                        reservations: ReservationsConfig {
+                            partners: vec![PartnerConfig {
+                                name: "exchange".to_string(),
+                                key: "0x0102".to_string(),
+                                reserved_percent: 10,
+                            }],
+                        },
+                        receipts: ReceiptsConfig {
+                            key_file: Some("receipt-key.hex".into()),
+                        },
+                        identity: IdentityConfig {
+                            key_file: Some("identity-key.hex".into()),
+                        },
+                        eviction: EvictionConfig {
+                            max_queued_transactions: 5000,
+                            policy: EvictionPolicyKind::AccountCap,
+                            max_per_account: 8,
+                            ttl_blocks: 20,
+                        },
+                        tx_kernel: TxKernelConfig {
+                            min_version: 1,
+                            max_version: 2,
+                        },
+                        batching: BatchingConfig {
+                            batch_size: 16,
+                            block_frequency_ms: 5000,
+                            build_batch_frequency_ms: 100,
+                            max_batches_per_block: 4,
+                        },
+                        adaptive_batching: AdaptiveBatchingConfig {
+                            target_backlog_blocks: 3,
+                            max_slowdown: 8,
+                        },
+                        tx_ordering: TxOrdering::Canonical,
+                        grpc: GrpcServerConfig {
+                            max_connections: 64,
+                            initial_stream_window_size: 1048576,
+                            ..Default::default()
+                        },
+                        dashboard: DashboardConfig {
+                            endpoint: Some(Endpoint {
+                                host: "127.0.0.1".to_string(),
+                                port: 8081,
+                            }),
+                        },
+                        webhooks: WebhooksConfig {
+                            urls: vec!["https://example.com/miden".to_string()],
+                            secret_file: Some("webhook-secret".into()),
+                            max_retries: 3,
+                            initial_backoff_ms: 1000,
+                        },
+                        journal: JournalConfig {
+                            path: "journal/blocks.jsonl".into(),
+                            max_size_mb: 64,
+                            max_files: 4,
+                        },
+                        operator_keys: vec!["0x01".to_string()],
+                        restart: RestartConfig {
+                            snapshot_path: "snapshots/queue.bin".into(),
+                        },
+                        mempool: MempoolConfig {
+                            wal_path: Some("wal/mempool.wal".into()),
+                        },
+                        time_authority: TimeAuthorityConfig {
+                            url: Some("https://time.example.com/attest".to_string()),
+                            authority_key: Some("0x0304".to_string()),
+                            timeout_ms: 2000,
+                        },
+                        batch_proving: BatchProvingConfig { workers: 2 },
+                        remote_prover: RemoteProverConfig {
+                            url: Some("http://prover:50051".to_string()),
+                            timeout_ms: 30_000,
+                            fallback_to_local: false,
+                        },
                     }
                 }
             );
@@ -88,4 +1186,61 @@ mod tests {
             Ok(())
         });
     }
+
+    #[test]
+    fn test_batching_config_validation() {
+        assert_eq!(BatchingConfig::default().validate(), Ok(()));
+
+        let invalid = [
+            (
+                BatchingConfig {
+                    batch_size: 0,
+                    ..Default::default()
+                },
+                BatchingConfigError::EmptyBatches,
+            ),
+            (
+                BatchingConfig {
+                    batch_size: 4097,
+                    ..Default::default()
+                },
+                BatchingConfigError::TooManyTransactionsPerBatch(4097),
+            ),
+            (
+                BatchingConfig {
+                    max_batches_per_block: 0,
+                    ..Default::default()
+                },
+                BatchingConfigError::EmptyBlocks,
+            ),
+            (
+                BatchingConfig {
+                    max_batches_per_block: 257,
+                    ..Default::default()
+                },
+                BatchingConfigError::TooManyBatchesPerBlock(257),
+            ),
+            (
+                BatchingConfig {
+                    block_frequency_ms: 0,
+                    build_batch_frequency_ms: 0,
+                    ..Default::default()
+                },
+                BatchingConfigError::ZeroFrequency("build_batch_frequency_ms"),
+            ),
+            (
+                BatchingConfig {
+                    block_frequency_ms: 50,
+                    ..Default::default()
+                },
+                BatchingConfigError::BatchesSlowerThanBlocks {
+                    build_batch_frequency_ms: 100,
+                    block_frequency_ms: 50,
+                },
+            ),
+        ];
+        for (config, error) in invalid {
+            assert_eq!(config.validate(), Err(error));
+        }
+    }
 }
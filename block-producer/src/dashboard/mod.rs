@@ -0,0 +1,326 @@
+//! Web dashboard of the block producer's activity, for operators who don't run a metrics stack.
+//!
+//! The [Dashboard] keeps a bounded history of the depth of the transaction queue, of the latest
+//! blocks and of the rejected transactions. The history is served as JSON, along with a static
+//! page rendering it, see [server].
+use std::{
+    collections::{BTreeMap, VecDeque},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tokio::time;
+use tracing::info;
+
+use crate::{
+    latency::{LatencyPercentiles, LatencyReport},
+    quarantine::BlockFailure,
+    COMPONENT,
+};
+
+pub mod server;
+
+#[cfg(test)]
+mod tests;
+
+// RECORDS
+// ================================================================================================
+
+/// Number of queued transactions in every lane, at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct QueueDepthSample {
+    /// Time of the sample, in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+    pub consuming: usize,
+    pub creating: usize,
+}
+
+/// A block committed by the store, or which failed to be built.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockRecord {
+    /// Time at which the block was committed or failed, in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+
+    /// Number of the block, `None` if the failure happened before the chain tip was retrieved
+    pub block_num: Option<u32>,
+
+    pub num_batches: usize,
+
+    pub num_transactions: usize,
+
+    /// Time spent proving the block, `None` if the block failed before it was proven
+    pub proving_time_ms: Option<u64>,
+
+    /// Error which made the block fail, `None` if it was committed
+    pub error: Option<String>,
+}
+
+impl BlockRecord {
+    pub fn failed(
+        failure: &BlockFailure,
+        num_transactions: usize,
+    ) -> Self {
+        Self {
+            timestamp: failure.timestamp,
+            block_num: failure.block_num,
+            num_batches: failure.num_batches,
+            num_transactions,
+            proving_time_ms: None,
+            error: Some(failure.error.clone()),
+        }
+    }
+}
+
+/// A transaction rejected on submission.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rejection {
+    /// Time of the rejection, in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+
+    /// Hex encoded id of the transaction
+    pub tx_id: String,
+
+    /// Reason of the rejection, see [crate::errors::AddTransactionError::reason]
+    pub reason: &'static str,
+
+    pub error: String,
+}
+
+/// The rejected transactions, counted by reason since the start of the block producer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RejectionStats {
+    pub counts: BTreeMap<&'static str, u64>,
+
+    /// Latest rejections, the oldest first
+    pub recent: VecDeque<Rejection>,
+}
+
+/// Proving times of the latest committed blocks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ProverTimings {
+    /// Number of blocks the timings are computed over
+    pub num_blocks: usize,
+    pub last_ms: u64,
+    pub mean_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Percentiles of the durations of a stage of the transactions, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LatencyPercentilesMs {
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+}
+
+impl From<LatencyPercentiles> for LatencyPercentilesMs {
+    fn from(percentiles: LatencyPercentiles) -> Self {
+        Self {
+            p50_ms: percentiles.p50.as_millis() as u64,
+            p95_ms: percentiles.p95.as_millis() as u64,
+            p99_ms: percentiles.p99.as_millis() as u64,
+        }
+    }
+}
+
+/// Latency of the latest committed transactions as of the latest committed block, see
+/// [LatencyReport].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LatencyRecord {
+    pub num_samples: usize,
+
+    /// Number of transactions tracked until they are committed or dropped
+    pub num_in_flight: usize,
+
+    pub end_to_end: LatencyPercentilesMs,
+    pub verify: LatencyPercentilesMs,
+    pub queue_wait: LatencyPercentilesMs,
+    pub batch: LatencyPercentilesMs,
+    pub prove: LatencyPercentilesMs,
+    pub apply: LatencyPercentilesMs,
+}
+
+impl From<LatencyReport> for LatencyRecord {
+    fn from(report: LatencyReport) -> Self {
+        Self {
+            num_samples: report.num_samples,
+            num_in_flight: report.num_in_flight,
+            end_to_end: report.end_to_end.into(),
+            verify: report.verify.into(),
+            queue_wait: report.queue_wait.into(),
+            batch: report.batch.into(),
+            prove: report.prove.into(),
+            apply: report.apply.into(),
+        }
+    }
+}
+
+// DASHBOARD
+// ================================================================================================
+
+/// History of the block producer's activity, keeping the latest `history` entries of every kind.
+pub struct Dashboard {
+    history: usize,
+    queue_depth: Mutex<VecDeque<QueueDepthSample>>,
+    blocks: Mutex<VecDeque<BlockRecord>>,
+    rejections: Mutex<RejectionStats>,
+    latency: Mutex<LatencyRecord>,
+}
+
+impl Dashboard {
+    pub fn new(history: usize) -> Self {
+        Self {
+            history,
+            queue_depth: Mutex::new(VecDeque::with_capacity(history)),
+            blocks: Mutex::new(VecDeque::with_capacity(history)),
+            rejections: Mutex::new(RejectionStats::default()),
+            latency: Mutex::new(LatencyRecord::default()),
+        }
+    }
+
+    /// Samples the depth of the transaction queue every `frequency`, `sample` returns the number
+    /// of queued transactions in the consuming and creating lanes.
+    pub async fn sample_queue_depth<S, F>(
+        self: Arc<Self>,
+        frequency: Duration,
+        sample: S,
+    ) where
+        S: Fn() -> F,
+        F: Future<Output = (usize, usize)>,
+    {
+        let mut interval = time::interval(frequency);
+
+        info!(target: COMPONENT, period_ms = interval.period().as_millis(), "Dashboard sampler started");
+
+        loop {
+            interval.tick().await;
+            let (consuming, creating) = sample().await;
+            self.record_queue_depth(QueueDepthSample {
+                timestamp: now_ms(),
+                consuming,
+                creating,
+            });
+        }
+    }
+
+    pub fn record_queue_depth(
+        &self,
+        sample: QueueDepthSample,
+    ) {
+        push_bounded(&mut self.queue_depth.lock().expect("poisoned lock"), sample, self.history);
+    }
+
+    /// Records a block committed by the store, which took `proving_time` to be proven.
+    pub fn record_committed_block(
+        &self,
+        block_num: u32,
+        num_batches: usize,
+        num_transactions: usize,
+        proving_time: Duration,
+    ) {
+        self.record_block(BlockRecord {
+            timestamp: now_ms(),
+            block_num: Some(block_num),
+            num_batches,
+            num_transactions,
+            proving_time_ms: Some(proving_time.as_millis() as u64),
+            error: None,
+        });
+    }
+
+    pub fn record_block(
+        &self,
+        block: BlockRecord,
+    ) {
+        push_bounded(&mut self.blocks.lock().expect("poisoned lock"), block, self.history);
+    }
+
+    /// Records the rejection of the transaction `tx_id`, for `reason`.
+    pub fn record_rejection(
+        &self,
+        tx_id: String,
+        reason: &'static str,
+        error: String,
+    ) {
+        let mut rejections = self.rejections.lock().expect("poisoned lock");
+        *rejections.counts.entry(reason).or_default() += 1;
+        let rejection = Rejection {
+            timestamp: now_ms(),
+            tx_id,
+            reason,
+            error,
+        };
+        push_bounded(&mut rejections.recent, rejection, self.history);
+    }
+
+    /// Records the latency of the transactions, as of the latest committed block.
+    pub fn record_latency(
+        &self,
+        report: LatencyReport,
+    ) {
+        *self.latency.lock().expect("poisoned lock") = report.into();
+    }
+
+    /// Returns the samples of the queue depth, the oldest first.
+    pub fn queue_depth(&self) -> Vec<QueueDepthSample> {
+        self.queue_depth.lock().expect("poisoned lock").iter().copied().collect()
+    }
+
+    /// Returns the latest blocks, the oldest first.
+    pub fn blocks(&self) -> Vec<BlockRecord> {
+        self.blocks.lock().expect("poisoned lock").iter().cloned().collect()
+    }
+
+    pub fn rejections(&self) -> RejectionStats {
+        self.rejections.lock().expect("poisoned lock").clone()
+    }
+
+    pub fn latency(&self) -> LatencyRecord {
+        *self.latency.lock().expect("poisoned lock")
+    }
+
+    /// Returns the proving times of the latest committed blocks.
+    pub fn prover_timings(&self) -> ProverTimings {
+        let blocks = self.blocks.lock().expect("poisoned lock");
+        let proving_times: Vec<u64> =
+            blocks.iter().filter_map(|block| block.proving_time_ms).collect();
+
+        let Some(&last_ms) = proving_times.last() else {
+            return ProverTimings::default();
+        };
+
+        ProverTimings {
+            num_blocks: proving_times.len(),
+            last_ms,
+            mean_ms: proving_times.iter().sum::<u64>() / proving_times.len() as u64,
+            max_ms: proving_times.iter().copied().max().unwrap_or_default(),
+        }
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Appends `entry` to `entries`, dropping the oldest ones beyond `capacity`.
+fn push_bounded<T>(
+    entries: &mut VecDeque<T>,
+    entry: T,
+    capacity: usize,
+) {
+    if capacity == 0 {
+        return;
+    }
+    while entries.len() >= capacity {
+        entries.pop_front();
+    }
+    entries.push_back(entry);
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,79 @@
+//! HTTP server of the [Dashboard], serving its page and the JSON endpoints the page polls.
+use std::{convert::Infallible, future::Future, net::SocketAddr, sync::Arc};
+
+use anyhow::{Context, Result};
+use hyper::{
+    header::{ALLOW, CONTENT_TYPE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::Serialize;
+
+use super::Dashboard;
+
+/// Page of the dashboard, rendering the JSON endpoints.
+const INDEX_HTML: &str = include_str!("index.html");
+
+/// Binds the dashboard's HTTP server to `addr`, the returned future serves it until an error
+/// happens.
+///
+/// Binding eagerly lets the block producer fail at startup if the address is unavailable.
+pub fn bind(
+    dashboard: Arc<Dashboard>,
+    addr: SocketAddr,
+) -> Result<impl Future<Output = Result<()>>> {
+    let make_service = make_service_fn(move |_| {
+        let dashboard = dashboard.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let response = route(&dashboard, &request);
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&addr)
+        .with_context(|| format!("Failed to bind the dashboard to {addr}"))?
+        .serve(make_service);
+
+    Ok(async move { server.await.context("Dashboard server failed") })
+}
+
+/// Answers the `request` from the `dashboard`'s history.
+pub(super) fn route(
+    dashboard: &Dashboard,
+    request: &Request<Body>,
+) -> Response<Body> {
+    if request.method() != Method::GET {
+        return Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header(ALLOW, "GET")
+            .body(Body::empty())
+            .expect("the response is valid");
+    }
+
+    match request.uri().path() {
+        "/" => Response::builder()
+            .header(CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(Body::from(INDEX_HTML))
+            .expect("the response is valid"),
+        "/api/queue-depth" => json(&dashboard.queue_depth()),
+        "/api/blocks" => json(&dashboard.blocks()),
+        "/api/rejections" => json(&dashboard.rejections()),
+        "/api/prover" => json(&dashboard.prover_timings()),
+        "/api/latency" => json(&dashboard.latency()),
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .expect("the response is valid"),
+    }
+}
+
+fn json<T: Serialize>(value: &T) -> Response<Body> {
+    let body = serde_json::to_vec(value).expect("the dashboard records serialize to JSON");
+
+    Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .expect("the response is valid")
+}
@@ -0,0 +1,135 @@
+use hyper::{body, Body, Method, Request, StatusCode};
+
+use super::{server::route, *};
+
+// HELPERS
+// ================================================================================================
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+/// Sends a `method` request for `path` to the dashboard, returning the status and body.
+async fn request(
+    dashboard: &Dashboard,
+    method: Method,
+    path: &str,
+) -> (StatusCode, String) {
+    let request = Request::builder().method(method).uri(path).body(Body::empty()).unwrap();
+    let response = route(dashboard, &request);
+    let status = response.status();
+    let body = body::to_bytes(response.into_body()).await.unwrap();
+
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+// TESTS
+// ================================================================================================
+
+/// Tests that only the latest entries of every kind are kept
+#[test]
+fn test_history_is_bounded() {
+    let dashboard = Dashboard::new(2);
+
+    for block_num in 1..=3 {
+        dashboard.record_committed_block(block_num, 1, 2, ms(10));
+        dashboard.record_rejection(format!("0x{block_num}"), "queue_full", "full".to_string());
+    }
+
+    let blocks: Vec<_> = dashboard.blocks().iter().map(|block| block.block_num).collect();
+    assert_eq!(blocks, vec![Some(2), Some(3)]);
+
+    let rejections = dashboard.rejections();
+    assert_eq!(rejections.counts.get("queue_full"), Some(&3));
+    let tx_ids: Vec<_> =
+        rejections.recent.iter().map(|rejection| rejection.tx_id.as_str()).collect();
+    assert_eq!(tx_ids, vec!["0x2", "0x3"]);
+}
+
+/// Tests that the prover timings are computed over the committed blocks only
+#[test]
+fn test_prover_timings() {
+    let dashboard = Dashboard::new(10);
+    assert_eq!(dashboard.prover_timings(), ProverTimings::default());
+
+    dashboard.record_committed_block(1, 1, 1, ms(30));
+    dashboard.record_committed_block(2, 1, 1, ms(10));
+    dashboard.record_block(BlockRecord {
+        timestamp: 0,
+        block_num: Some(3),
+        num_batches: 1,
+        num_transactions: 1,
+        proving_time_ms: None,
+        error: Some("store unavailable".to_string()),
+    });
+
+    assert_eq!(
+        dashboard.prover_timings(),
+        ProverTimings {
+            num_blocks: 2,
+            last_ms: 10,
+            mean_ms: 20,
+            max_ms: 30,
+        }
+    );
+}
+
+/// Tests that the queue depth is sampled at the configured frequency
+#[tokio::test(start_paused = true)]
+async fn test_queue_depth_sampling() {
+    let dashboard = Arc::new(Dashboard::new(10));
+
+    tokio::spawn(dashboard.clone().sample_queue_depth(ms(100), || async { (3, 5) }));
+    time::sleep(ms(250)).await;
+
+    let samples = dashboard.queue_depth();
+    assert_eq!(samples.len(), 3);
+    assert!(samples.iter().all(|sample| sample.consuming == 3 && sample.creating == 5));
+}
+
+/// Tests that the page and the JSON endpoints are served, and other requests refused
+#[tokio::test]
+async fn test_routes() {
+    let dashboard = Dashboard::new(10);
+    dashboard.record_committed_block(7, 2, 4, ms(15));
+    dashboard.record_rejection("0x1".to_string(), "overloaded", "overloaded".to_string());
+    dashboard.record_latency(LatencyReport {
+        num_samples: 3,
+        num_in_flight: 2,
+        end_to_end: LatencyPercentiles {
+            p50: ms(40),
+            p95: ms(90),
+            p99: ms(120),
+        },
+        ..Default::default()
+    });
+
+    let (status, page) = request(&dashboard, Method::GET, "/").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("/api/queue-depth"));
+
+    let (status, blocks) = request(&dashboard, Method::GET, "/api/blocks").await;
+    assert_eq!(status, StatusCode::OK);
+    let blocks: serde_json::Value = serde_json::from_str(&blocks).unwrap();
+    assert_eq!(blocks[0]["block_num"], 7);
+    assert_eq!(blocks[0]["proving_time_ms"], 15);
+
+    let (_, rejections) = request(&dashboard, Method::GET, "/api/rejections").await;
+    let rejections: serde_json::Value = serde_json::from_str(&rejections).unwrap();
+    assert_eq!(rejections["counts"]["overloaded"], 1);
+
+    let (_, prover) = request(&dashboard, Method::GET, "/api/prover").await;
+    let prover: serde_json::Value = serde_json::from_str(&prover).unwrap();
+    assert_eq!(prover["last_ms"], 15);
+
+    let (_, latency) = request(&dashboard, Method::GET, "/api/latency").await;
+    let latency: serde_json::Value = serde_json::from_str(&latency).unwrap();
+    assert_eq!(latency["num_in_flight"], 2);
+    assert_eq!(latency["end_to_end"]["p95_ms"], 90);
+
+    let (status, _) = request(&dashboard, Method::GET, "/api/unknown").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = request(&dashboard, Method::POST, "/api/blocks").await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+}
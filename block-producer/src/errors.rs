@@ -1,11 +1,11 @@
 use miden_air::trace::chiplets::hasher::Digest;
 use miden_crypto::merkle::MerkleError;
 use miden_node_proto::errors::ParseError;
-use miden_node_utils::formatting::format_opt;
+use miden_node_utils::formatting::{format_array, format_opt};
 use miden_objects::{
-    accounts::AccountId,
+    accounts::{AccountId, AccountType},
     notes::Nullifier,
-    transaction::{InputNotes, ProvenTransaction},
+    transaction::{InputNotes, ProvenTransaction, TransactionId},
     TransactionInputError,
 };
 use miden_vm::ExecutionError;
@@ -18,8 +18,9 @@ use crate::{CREATED_NOTES_TREE_INSERTION_DEPTH, MAX_NUM_CREATED_NOTES_PER_BATCH}
 
 #[derive(Error, Debug, PartialEq)]
 pub enum VerifyTxError {
-    /// The account that the transaction modifies has already been modified and isn't yet committed
-    /// to a block
+    /// The account that the transaction modifies has already been modified by transactions which
+    /// aren't yet committed to a block, and the transaction doesn't start from the final state of
+    /// the last of them
     #[error("Account {0} was already modified by other transaction")]
     AccountAlreadyModifiedByOtherTx(AccountId),
 
@@ -34,6 +35,80 @@ pub enum VerifyTxError {
         store_account_hash: Option<Digest>,
     },
 
+    /// A note created by the transaction is sent by another account than the one executing the
+    /// transaction
+    ///
+    /// Only faucets mint assets, so a note claiming a faucet as sender must have been created by a
+    /// transaction of that faucet.
+    #[error("Output note {note_id} is sent by {sender_type:?} account {sender}, but the transaction was executed by {account_type:?} account {account_id}")]
+    OutputNoteSenderMismatch {
+        account_id: AccountId,
+        account_type: AccountType,
+        note_id: Digest,
+        sender: AccountId,
+        sender_type: AccountType,
+    },
+
+    /// The transaction modifies a public account but doesn't include the changes it made to the
+    /// account's state, which must be published
+    #[error("Transaction of public account {0} doesn't include the account delta")]
+    PublicAccountDeltaMissing(AccountId),
+
+    /// The transaction modifies a private account but includes an account delta, private accounts
+    /// only commit to the hash of their state
+    #[error("Transaction of private account {0} includes an account delta")]
+    PrivateAccountDeltaProvided(AccountId),
+
+    /// The account delta of the transaction doesn't apply to the state of the account the
+    /// transaction starts from
+    #[error("Account delta of account {account_id} doesn't apply to its initial state: {error}")]
+    InvalidAccountDelta {
+        account_id: AccountId,
+        error: String,
+    },
+
+    /// The account delta of the transaction doesn't update the account to the final state the
+    /// transaction commits to, the published state of the account would not match its hash
+    #[error("Account delta of account {account_id} updates it to state {delta_account_hash}, but the transaction commits to state {tx_final_account_hash}")]
+    AccountDeltaMismatch {
+        account_id: AccountId,
+        tx_final_account_hash: Digest,
+        delta_account_hash: Digest,
+    },
+
+    /// A note created by the transaction has a tag which is not accepted by the store's tag policy
+    #[error("Output note {note_id} has tag {tag}, which is not allowed by the tag policy")]
+    OutputNoteTagNotAllowed { note_id: Digest, tag: u64 },
+
+    /// The transaction was executed against a block older than the store's pruning horizon, the
+    /// notes it consumes can no longer be authenticated
+    #[error("Transaction was executed against block {block_ref_num}, which is older than the pruning horizon of the store (block {pruning_horizon})")]
+    ReferenceBlockPruned {
+        block_ref: Digest,
+        block_ref_num: u32,
+        pruning_horizon: u32,
+    },
+
+    /// The transaction was executed against a block unknown to the store while the store prunes
+    /// its data, the block may be older than the pruning horizon
+    #[error("Transaction was executed against block {block_ref}, which is unknown to the store whose pruning horizon is block {pruning_horizon}")]
+    ReferenceBlockUnknown {
+        block_ref: Digest,
+        pruning_horizon: u32,
+    },
+
+    /// The transaction of a regular account adds assets to the vault of the account without
+    /// consuming any note
+    ///
+    /// Only faucets mint assets: the assets added to the vault of a regular account must come from
+    /// the notes consumed by the transaction.
+    #[error("Transaction of {account_type:?} account {account_id} adds assets of faucet {faucet_id} to its vault without consuming any note")]
+    AssetsMintedByRegularAccount {
+        account_id: AccountId,
+        account_type: AccountType,
+        faucet_id: AccountId,
+    },
+
     /// Failed to retrieve transaction inputs from the store
     ///
     /// TODO: Make this an "internal error". Q: Should we have a single `InternalError` enum for all
@@ -45,6 +120,29 @@ pub enum VerifyTxError {
     TransactionInputError(#[from] TransactionInputError),
 }
 
+impl VerifyTxError {
+    /// Returns a short name of the error, stable across the values of its fields.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            VerifyTxError::AccountAlreadyModifiedByOtherTx(_) => "account_already_modified",
+            VerifyTxError::InputNotesAlreadyConsumed(_) => "input_notes_already_consumed",
+            VerifyTxError::IncorrectAccountInitialHash { .. } => "incorrect_account_initial_hash",
+            VerifyTxError::OutputNoteSenderMismatch { .. } => "output_note_sender_mismatch",
+            VerifyTxError::AssetsMintedByRegularAccount { .. } => {
+                "assets_minted_by_regular_account"
+            },
+            VerifyTxError::PublicAccountDeltaMissing(_) => "public_account_delta_missing",
+            VerifyTxError::PrivateAccountDeltaProvided(_) => "private_account_delta_provided",
+            VerifyTxError::InvalidAccountDelta { .. } => "invalid_account_delta",
+            VerifyTxError::AccountDeltaMismatch { .. } => "account_delta_mismatch",
+            VerifyTxError::OutputNoteTagNotAllowed { .. } => "output_note_tag_not_allowed",
+            VerifyTxError::ReferenceBlockPruned { .. } => "reference_block_pruned",
+            VerifyTxError::StoreConnectionFailed(_) => "store_connection_failed",
+            VerifyTxError::TransactionInputError(_) => "transaction_input_error",
+        }
+    }
+}
+
 // Transaction adding errors
 // =================================================================================================
 
@@ -52,6 +150,129 @@ pub enum VerifyTxError {
 pub enum AddTransactionError {
     #[error("Transaction verification failed: {0}")]
     VerificationFailed(#[from] VerifyTxError),
+    #[error("Block producer is overloaded, transaction rejected")]
+    Overloaded,
+    #[error("Transaction queue is full, transaction rejected")]
+    QueueFull,
+    #[error("Transaction kernel version {version} is not supported, expected a version between {min_version} and {max_version}")]
+    UnsupportedTxKernelVersion {
+        version: u32,
+        min_version: u32,
+        max_version: u32,
+    },
+    #[error("Transaction is signed by {0}, which is not a partner of the block producer")]
+    UnknownPartner(Digest),
+    #[error("Partner signature is not a signature of the transaction by {0}")]
+    InvalidPartnerSignature(Digest),
+    #[error("Transaction could not be persisted: {0}")]
+    PersistenceFailed(String),
+    #[error("Note {0} is not consumed by the transaction")]
+    NoteNotConsumed(Digest),
+    #[error("Note {0} is not created by the transaction")]
+    NoteNotCreated(Digest),
+}
+
+impl AddTransactionError {
+    /// Returns a short name of the error, the reason of a verification failure for the
+    /// [VerifyTxError]s.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            AddTransactionError::VerificationFailed(err) => err.reason(),
+            AddTransactionError::Overloaded => "overloaded",
+            AddTransactionError::QueueFull => "queue_full",
+            AddTransactionError::UnsupportedTxKernelVersion { .. } => {
+                "unsupported_tx_kernel_version"
+            },
+            AddTransactionError::UnknownPartner(_) => "unknown_partner",
+            AddTransactionError::InvalidPartnerSignature(_) => "invalid_partner_signature",
+            AddTransactionError::PersistenceFailed(_) => "persistence_failed",
+            AddTransactionError::NoteNotConsumed(_) => "note_not_consumed",
+            AddTransactionError::NoteNotCreated(_) => "note_not_created",
+        }
+    }
+}
+
+// Batch adding errors
+// =================================================================================================
+
+/// Error rejecting a batch built by an external batch producer, none of its transactions is
+/// admitted.
+#[derive(Error, Debug)]
+pub enum AddBatchError {
+    #[error("Batch has no transaction")]
+    EmptyBatch,
+    #[error("Batch has {0} transactions, more than the batch size of {1}")]
+    TooManyTransactions(usize, usize),
+    #[error("Block producer is overloaded, batch rejected")]
+    Overloaded,
+    #[error("Transaction queue is full, batch rejected")]
+    QueueFull,
+    #[error("Invalid batch proof: {0}")]
+    InvalidProof(String),
+    #[error("Invalid batch: {0}")]
+    InvalidBatch(#[from] BuildBatchError),
+    #[error("Transaction {} of the batch rejected: {error}", .tx_id.to_hex())]
+    TransactionRejected {
+        tx_id: TransactionId,
+        error: AddTransactionError,
+    },
+}
+
+impl AddBatchError {
+    /// Returns a short name of the error, the reason of the rejected transaction for
+    /// [AddBatchError::TransactionRejected].
+    pub fn reason(&self) -> &'static str {
+        match self {
+            AddBatchError::EmptyBatch => "empty_batch",
+            AddBatchError::TooManyTransactions(..) => "too_many_transactions",
+            AddBatchError::Overloaded => "overloaded",
+            AddBatchError::QueueFull => "queue_full",
+            AddBatchError::InvalidProof(_) => "invalid_proof",
+            AddBatchError::InvalidBatch(_) => "invalid_batch",
+            AddBatchError::TransactionRejected { error, .. } => error.reason(),
+        }
+    }
+}
+
+// Reservation errors
+// =================================================================================================
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum ReservationError {
+    #[error("key {key} of partner {name} is not a valid hex encoded public key")]
+    InvalidKey { name: String, key: String },
+    #[error("key {key} is listed for several partners")]
+    DuplicateKey { key: String },
+    #[error("reservations of the partners add up to {0}%, more than 100%")]
+    OverReserved(u32),
+}
+
+// Batching configuration errors
+// =================================================================================================
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum BatchingConfigError {
+    #[error("batch_size must be at least 1")]
+    EmptyBatches,
+    #[error(
+        "batch_size is {0}, but the notes of a batch of more than {} transactions may not fit in its notes tree",
+        MAX_NUM_CREATED_NOTES_PER_BATCH
+    )]
+    TooManyTransactionsPerBatch(usize),
+    #[error("max_batches_per_block must be at least 1")]
+    EmptyBlocks,
+    #[error(
+        "max_batches_per_block is {0}, but the notes of at most 2^{} batches fit in a block",
+        CREATED_NOTES_TREE_INSERTION_DEPTH
+    )]
+    TooManyBatchesPerBlock(usize),
+    #[error("{0} must be greater than 0")]
+    ZeroFrequency(&'static str),
+    #[error("build_batch_frequency_ms ({build_batch_frequency_ms}) must not exceed block_frequency_ms ({block_frequency_ms}), batches would be missing from the blocks")]
+    BatchesSlowerThanBlocks {
+        build_batch_frequency_ms: u64,
+        block_frequency_ms: u64,
+    },
 }
 
 // Batch building errors
@@ -72,6 +293,49 @@ pub enum BuildBatchError {
 
     #[error("failed to create notes SMT: {0}")]
     NotesSmtError(MerkleError, Vec<ProvenTransaction>),
+
+    /// Some transactions consume or create the same notes as transactions before them in the
+    /// batch, or don't start from the final state of the previous transaction of their account.
+    /// These transactions are rejected, only the remaining ones are re-queued.
+    ///
+    /// TODO: release the accounts and nullifiers of the rejected transactions from the in-flight
+    /// state of the [TransactionVerifier](crate::txqueue::TransactionVerifier).
+    #[error(
+        "{} transactions conflict with previous transactions of the batch, duplicate nullifiers: {}, duplicate notes: {}, broken chains: {}",
+        .rejected.len(),
+        format_array(.duplicate_nullifiers),
+        format_array(.duplicate_notes),
+        format_array(.broken_chains)
+    )]
+    ConflictingTransactions {
+        duplicate_nullifiers: Vec<Digest>,
+        duplicate_notes: Vec<Digest>,
+        /// Accounts of the rejected transactions which don't start from the final state of the
+        /// previous transaction of their account
+        broken_chains: Vec<AccountId>,
+        rejected: Vec<ProvenTransaction>,
+        txs: Vec<ProvenTransaction>,
+    },
+
+    /// The proofs of some transactions are invalid. These transactions are rejected, only the
+    /// remaining ones are re-queued.
+    #[error(
+        "{} transactions have an invalid proof: {}",
+        .rejected.len(),
+        format_array(.rejected.iter().map(|tx| tx.id().to_hex()))
+    )]
+    InvalidTransactionProofs {
+        rejected: Vec<ProvenTransaction>,
+        txs: Vec<ProvenTransaction>,
+    },
+
+    #[error("failed to prove the batch: {0}")]
+    ProvingFailed(BatchProverError, Vec<ProvenTransaction>),
+
+    /// The batches ready to be included in a block already fill a block, the batch is kept by the
+    /// transaction queue until the next block is built.
+    #[error("the ready batches already fill a block")]
+    ReadyBatchesFull(Vec<ProvenTransaction>),
 }
 
 impl BuildBatchError {
@@ -79,10 +343,29 @@ impl BuildBatchError {
         match self {
             BuildBatchError::TooManyNotesCreated(_, txs) => txs,
             BuildBatchError::NotesSmtError(_, txs) => txs,
+            BuildBatchError::ConflictingTransactions { txs, .. } => txs,
+            BuildBatchError::InvalidTransactionProofs { txs, .. } => txs,
+            BuildBatchError::ProvingFailed(_, txs) => txs,
+            BuildBatchError::ReadyBatchesFull(txs) => txs,
         }
     }
 }
 
+// Batch prover errors
+// =================================================================================================
+
+#[derive(Error, Debug, PartialEq)]
+pub enum BatchProverError {
+    #[error("batch kernel proving failed: {0}")]
+    ProvingFailed(ExecutionError),
+    #[error("batch kernel execution failed: {0}")]
+    ExecutionFailed(ExecutionError),
+    #[error("failed to retrieve the batch commitment from the stack outputs")]
+    InvalidCommitmentOutput,
+    #[error("batch proving task failed")]
+    ProvingTaskFailed,
+}
+
 // Block prover errors
 // =================================================================================================
 
@@ -94,6 +377,10 @@ pub enum BlockProverError {
     ProgramExecutionFailed(ExecutionError),
     #[error("failed to retrieve {0} root from stack outputs")]
     InvalidRootOutput(String),
+    #[error("remote block prover failed: {0}")]
+    RemoteProvingFailed(String),
+    #[error("remote block prover didn't answer within {0} ms")]
+    RemoteProvingTimedOut(u64),
 }
 
 // Block inputs errors
@@ -114,6 +401,16 @@ pub enum BlockInputsError {
 pub enum ApplyBlockError {
     #[error("gRPC client failed with error: {0}")]
     GrpcClientError(String),
+    #[error("block rejected by the store: {0}")]
+    BlockRejected(String),
+    #[error("store is read-only, its disk usage exceeds its limits: {0}")]
+    StoreReadOnly(String),
+    #[error("store is busy applying another block: {0}")]
+    StoreBusy(String),
+    #[error("block proposal expired before it was committed: {0}")]
+    ProposalExpired(String),
+    #[error("block proposal not found by the store: {0}")]
+    ProposalNotFound(String),
 }
 
 // Block building errors
@@ -131,11 +428,37 @@ pub enum BuildBlockError {
     InconsistentAccountIds(Vec<AccountId>),
     #[error("transaction batches and store contain different hashes for some accounts. Offending accounts: {0:?}")]
     InconsistentAccountStates(Vec<AccountId>),
+    #[error("transaction batches update some accounts from another state than the final state of the previous batch. Offending accounts: {0:?}")]
+    DiscontinuousAccountStates(Vec<AccountId>),
     #[error(
         "too many batches in block. Got: {0}, max: 2^{}",
         CREATED_NOTES_TREE_INSERTION_DEPTH
     )]
     TooManyBatchesInBlock(usize),
+    #[error("block proving task stopped before returning a result")]
+    ProvingTaskFailed,
+    #[error("failed to sign block: {0}")]
+    SigningFailed(String),
+}
+
+// Time attestation errors
+// =================================================================================================
+
+/// Error of a time authority attesting a block, the block is applied without attestation.
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum TimeAttestationError {
+    #[error("request to the time authority failed: {0}")]
+    RequestFailed(String),
+    #[error("time authority didn't answer in time")]
+    TimedOut,
+    #[error("time authority answered with status {0}")]
+    UnexpectedStatus(u16),
+    #[error("malformed response from the time authority: {0}")]
+    InvalidResponse(String),
+    #[error("attestation is signed by {0}, which is not the configured time authority")]
+    UnexpectedKey(Digest),
+    #[error("attestation signature is not a signature of the block by the time authority")]
+    InvalidSignature,
 }
 
 // Transaction inputs errors
@@ -152,3 +475,45 @@ pub enum TxInputsError {
     #[error("dummy")]
     Dummy,
 }
+
+// Startup audit errors
+// =================================================================================================
+
+#[derive(Debug, PartialEq, Error)]
+pub enum StartupAuditError {
+    #[error("gRPC client failed with error: {0}")]
+    GrpcClientError(String),
+    #[error("malformed response from store: {0}")]
+    MalformedResponse(String),
+    #[error("failed to parse protobuf message: {0}")]
+    ParseError(#[from] ParseError),
+    #[error(
+        "genesis block hash mismatch, store reports {expected} but its genesis block is {actual}"
+    )]
+    GenesisHashMismatch { expected: Digest, actual: Digest },
+    #[error("block {block_num} has version {version}, but the genesis block has version {genesis_version}")]
+    BlockVersionMismatch {
+        block_num: u32,
+        genesis_version: u64,
+        version: u64,
+    },
+    #[error("latest block header of the store is block {header_block_num}, but its counters are at block {counters_block_num}")]
+    ChainTipMismatch {
+        header_block_num: u32,
+        counters_block_num: u32,
+    },
+    #[error("account root of block {block_num} is {header_root}, but the store's account tree has root {store_root}")]
+    AccountRootMismatch {
+        block_num: u32,
+        header_root: Digest,
+        store_root: Digest,
+    },
+    #[error(
+        "block {block_num} is timestamped {timestamp}, ahead of the block producer's clock ({now})"
+    )]
+    ClockBehindChainTip {
+        block_num: u32,
+        timestamp: u64,
+        now: u64,
+    },
+}
@@ -0,0 +1,322 @@
+use std::{
+    ffi::OsString,
+    fmt::{Display, Formatter},
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use miden_node_utils::formatting::{format_array, format_blake3_digest, format_opt};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    config::JournalConfig, errors::BuildBlockError, quarantine::BlockDiagnostics, TransactionBatch,
+    COMPONENT,
+};
+
+#[cfg(test)]
+mod tests;
+
+// JOURNAL ENTRY
+// ================================================================================================
+
+/// Roots the block kernel was executed upon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessSummary {
+    pub prev_block_num: u32,
+    pub prev_block_hash: String,
+    pub chain_root: String,
+    pub account_root: String,
+    pub nullifier_root: String,
+    pub num_updated_accounts: usize,
+
+    /// Number of batches of the block which created notes
+    pub num_note_batches: usize,
+}
+
+impl Display for WitnessSummary {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ prev_block_num: {}, prev_block_hash: {}, chain_root: {}, account_root: {}, nullifier_root: {}, num_updated_accounts: {}, num_note_batches: {} }}",
+            self.prev_block_num,
+            self.prev_block_hash,
+            self.chain_root,
+            self.account_root,
+            self.nullifier_root,
+            self.num_updated_accounts,
+            self.num_note_batches
+        ))
+    }
+}
+
+/// Outcome of a step of a block production attempt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepOutcome {
+    Succeeded,
+    Failed(String),
+}
+
+impl Display for StepOutcome {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            StepOutcome::Succeeded => f.write_str("succeeded"),
+            StepOutcome::Failed(error) => write!(f, "failed ({error})"),
+        }
+    }
+}
+
+/// State of a block production attempt, appended to the journal as the attempt progresses.
+///
+/// A proven block is recorded before it is sent to the store, and every attempt is recorded again
+/// once it completed. An attempt whose latest entry has a proven block but no `apply` outcome was
+/// interrupted while the store applied the block, which the store may hold or not.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Time the attempt started, in milliseconds since the UNIX epoch, identifies the attempt
+    pub started_at: u64,
+
+    /// Time the entry was recorded, in milliseconds since the UNIX epoch
+    pub recorded_at: u64,
+
+    /// Number of the block, `None` if the attempt failed before the chain tip was retrieved
+    pub block_num: Option<u32>,
+
+    /// Ids of the batches of the block
+    pub batches: Vec<String>,
+
+    pub witness: Option<WitnessSummary>,
+
+    /// Hash of the proven block
+    pub block_hash: Option<String>,
+
+    /// Outcome of the proving of the block, `None` if the attempt failed before
+    pub prove: Option<StepOutcome>,
+
+    /// Outcome of the application of the block by the store, `None` if it is in progress or the
+    /// attempt failed before
+    pub apply: Option<StepOutcome>,
+}
+
+impl JournalEntry {
+    /// Returns the entry of the attempt building a block out of `batches`, which gathered
+    /// `diagnostics` so far, and completed with `result` if any.
+    pub fn new(
+        started_at: u64,
+        batches: &[TransactionBatch],
+        diagnostics: &BlockDiagnostics,
+        result: Option<&Result<(), BuildBlockError>>,
+    ) -> Self {
+        let error = match result {
+            Some(Err(err)) => Some(err.to_string()),
+            _ => None,
+        };
+
+        // the block is only known once proven, a failure before it happened while preparing or
+        // proving the block
+        let (prove, apply) = match (&diagnostics.block, result) {
+            (Some(_), Some(_)) => (
+                Some(StepOutcome::Succeeded),
+                Some(error.map_or(StepOutcome::Succeeded, StepOutcome::Failed)),
+            ),
+            (Some(_), None) => (Some(StepOutcome::Succeeded), None),
+            (None, _) if diagnostics.witness.is_some() => (error.map(StepOutcome::Failed), None),
+            (None, _) => (None, None),
+        };
+
+        Self {
+            started_at,
+            recorded_at: now_ms(),
+            block_num: diagnostics
+                .block_inputs
+                .as_ref()
+                .map(|inputs| inputs.block_header.block_num() + 1),
+            batches: batches.iter().map(|batch| format_blake3_digest(batch.id())).collect(),
+            witness: diagnostics.witness.as_ref().map(|witness| witness.summary()),
+            block_hash: diagnostics.block.as_ref().map(|block| block.header.hash().to_hex()),
+            prove,
+            apply,
+        }
+    }
+}
+
+impl Display for JournalEntry {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ started_at: {}, recorded_at: {}, block_num: {}, batches: {}, witness: {}, block_hash: {}, prove: {}, apply: {} }}",
+            self.started_at,
+            self.recorded_at,
+            format_opt(self.block_num.as_ref()),
+            format_array(&self.batches),
+            format_opt(self.witness.as_ref()),
+            format_opt(self.block_hash.as_ref()),
+            format_opt(self.prove.as_ref()),
+            format_opt(self.apply.as_ref()),
+        ))
+    }
+}
+
+// BLOCK JOURNAL
+// ================================================================================================
+
+/// Appends the block production attempts to a local file, one JSON entry per line, so a block
+/// which was produced but never made it to the store can be investigated after the fact.
+///
+/// Once the journal exceeds the configured size it is renamed to `<path>.1`, the previous
+/// `<path>.1` to `<path>.2` and so on, the oldest beyond the configured number of files being
+/// removed.
+pub struct BlockJournal {
+    config: Arc<JournalConfig>,
+    file: Arc<Mutex<Option<File>>>,
+}
+
+impl BlockJournal {
+    pub fn new(config: JournalConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            file: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Appends `entry` to the journal, and syncs it to the disk off the async runtime.
+    ///
+    /// Failing to write the entry is logged, and doesn't affect the production of the block.
+    pub async fn record(
+        &self,
+        entry: &JournalEntry,
+    ) {
+        let mut line = match serde_json::to_vec(entry) {
+            Ok(line) => line,
+            Err(err) => {
+                warn!(target: COMPONENT, %err, "Failed to serialize the block journal entry");
+                return;
+            },
+        };
+        line.push(b'\n');
+
+        let config = self.config.clone();
+        let file = self.file.clone();
+        let appended = tokio::task::spawn_blocking(move || append(&config, &file, &line))
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+            .and_then(|appended| appended);
+        if let Err(err) = appended {
+            warn!(target: COMPONENT, path = ?self.config.path, %err, "Failed to write the block journal");
+        }
+    }
+}
+
+/// Reads the entries of the journal configured by `config`, the rotated journals first, from the
+/// oldest entry to the latest.
+///
+/// A line which is not an entry, e.g. the last line of a journal whose writer crashed, is skipped.
+pub fn read_entries(config: &JournalConfig) -> io::Result<Vec<JournalEntry>> {
+    let mut entries = Vec::new();
+    let paths = (1..=config.max_files)
+        .rev()
+        .map(|index| rotated_path(&config.path, index))
+        .chain([config.path.clone()]);
+    for path in paths {
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        for line in BufReader::new(file).lines() {
+            match serde_json::from_str(&line?) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => warn!(target: COMPONENT, ?path, %err, "Invalid journal entry skipped"),
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Appends `line` to the current journal `file`, rotated beforehand if the line doesn't fit, and
+/// syncs it so that a crash can't lose or tear it.
+fn append(
+    config: &JournalConfig,
+    file: &Mutex<Option<File>>,
+    line: &[u8],
+) -> io::Result<()> {
+    let mut file = file.lock().expect("journal lock poisoned");
+    if file.is_none() {
+        *file = Some(open(config)?);
+    }
+
+    // the journal is rotated before it exceeds its size, unless a single entry does
+    let size = file.as_ref().expect("journal opened above").metadata()?.len();
+    if size > 0 && size + line.len() as u64 > config.max_size_mb * 1024 * 1024 {
+        *file = None;
+        rotate(config)?;
+        *file = Some(open(config)?);
+    }
+
+    let file = file.as_mut().expect("journal opened above");
+    file.write_all(line)?;
+    file.sync_data()
+}
+
+/// Opens the current journal, created along with its directory if missing.
+fn open(config: &JournalConfig) -> io::Result<File> {
+    if let Some(parent) = config.path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    OpenOptions::new().create(true).append(true).open(&config.path)
+}
+
+/// Shifts every rotated journal by one, and rotates the current journal to `<path>.1`.
+fn rotate(config: &JournalConfig) -> io::Result<()> {
+    if config.max_files == 0 {
+        return remove_if_exists(&config.path);
+    }
+
+    remove_if_exists(&rotated_path(&config.path, config.max_files))?;
+    for index in (1..config.max_files).rev() {
+        let path = rotated_path(&config.path, index);
+        if path.exists() {
+            fs::rename(&path, rotated_path(&config.path, index + 1))?;
+        }
+    }
+    fs::rename(&config.path, rotated_path(&config.path, 1))
+}
+
+fn rotated_path(
+    path: &Path,
+    index: u32,
+) -> PathBuf {
+    let mut rotated = OsString::from(path);
+    rotated.push(format!(".{index}"));
+    rotated.into()
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the current time in milliseconds since the UNIX epoch.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
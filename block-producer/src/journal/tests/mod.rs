@@ -0,0 +1,102 @@
+use figment::Jail;
+
+use super::*;
+use crate::errors::BlockInputsError;
+
+// HELPERS
+// ================================================================================================
+
+fn journal_config(max_files: u32) -> JournalConfig {
+    JournalConfig {
+        path: "journal/blocks.jsonl".into(),
+        max_size_mb: 1,
+        max_files,
+    }
+}
+
+fn entry(block_num: u32) -> JournalEntry {
+    JournalEntry {
+        started_at: 1000 + block_num as u64,
+        recorded_at: 2000 + block_num as u64,
+        block_num: Some(block_num),
+        batches: vec!["00".repeat(32)],
+        witness: None,
+        block_hash: Some("0x01".to_string()),
+        prove: Some(StepOutcome::Succeeded),
+        apply: Some(StepOutcome::Failed("block rejected".to_string())),
+    }
+}
+
+// TESTS
+// ================================================================================================
+
+/// Tests that the recorded entries are read back in order, skipping the lines which aren't entries
+#[test]
+fn test_entries_are_read_back() {
+    Jail::expect_with(|_jail| {
+        let config = journal_config(4);
+        assert_eq!(read_entries(&config).unwrap(), Vec::new());
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let journal = BlockJournal::new(config.clone());
+        runtime.block_on(journal.record(&entry(1)));
+        // e.g. the last line written by a crashed block producer
+        OpenOptions::new()
+            .append(true)
+            .open(&config.path)
+            .unwrap()
+            .write_all(b"{\"started_at\":\n")
+            .unwrap();
+        runtime.block_on(journal.record(&entry(2)));
+
+        assert_eq!(read_entries(&config).unwrap(), vec![entry(1), entry(2)]);
+
+        Ok(())
+    });
+}
+
+/// Tests that the journal is rotated once it exceeds its size, keeping the configured number of
+/// rotated journals
+#[test]
+fn test_journal_is_rotated() {
+    Jail::expect_with(|_jail| {
+        let config = journal_config(1);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let journal = BlockJournal::new(config.clone());
+        runtime.block_on(journal.record(&entry(1)));
+
+        // the next entry doesn't fit the journal anymore
+        let mut file = OpenOptions::new().append(true).open(&config.path).unwrap();
+        file.write_all(&vec![b' '; 1024 * 1024]).unwrap();
+        runtime.block_on(journal.record(&entry(2)));
+
+        assert!(rotated_path(&config.path, 1).exists());
+        assert_eq!(read_entries(&config).unwrap(), vec![entry(1), entry(2)]);
+
+        // the oldest journal is removed beyond `max_files`
+        file = OpenOptions::new().append(true).open(&config.path).unwrap();
+        file.write_all(&vec![b' '; 1024 * 1024]).unwrap();
+        runtime.block_on(journal.record(&entry(3)));
+
+        assert!(!rotated_path(&config.path, 2).exists());
+        assert_eq!(read_entries(&config).unwrap(), vec![entry(2), entry(3)]);
+
+        Ok(())
+    });
+}
+
+/// Tests that an attempt failing before the block was proven records no outcome for the steps it
+/// didn't reach
+#[test]
+fn test_entry_of_failed_attempt() {
+    let error = BuildBlockError::GetBlockInputsFailed(BlockInputsError::GrpcClientError(
+        "store unavailable".to_string(),
+    ));
+    let entry = JournalEntry::new(1000, &[], &BlockDiagnostics::default(), Some(&Err(error)));
+
+    assert_eq!(entry.started_at, 1000);
+    assert_eq!(entry.block_num, None);
+    assert_eq!(entry.witness, None);
+    assert_eq!(entry.prove, None);
+    assert_eq!(entry.apply, None);
+}
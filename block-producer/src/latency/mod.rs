@@ -0,0 +1,309 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Mutex,
+    time::Duration,
+};
+
+use miden_objects::transaction::TransactionId;
+use tokio::time::Instant;
+
+#[cfg(test)]
+mod tests;
+
+// LATENCY REPORT
+// ================================================================================================
+
+/// Percentiles of the durations of a stage, over the latest committed transactions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyPercentiles {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+
+impl LatencyPercentiles {
+    /// Computes the nearest-rank percentiles of `durations`, all zero if there are none.
+    fn new(mut durations: Vec<Duration>) -> Self {
+        if durations.is_empty() {
+            return Self::default();
+        }
+        durations.sort_unstable();
+
+        let percentile = |p: usize| {
+            let rank = (p * durations.len()).div_ceil(100);
+            durations[rank.saturating_sub(1)]
+        };
+
+        Self {
+            p50: percentile(50),
+            p95: percentile(95),
+            p99: percentile(99),
+        }
+    }
+}
+
+/// Latency of the latest committed transactions, end-to-end and per stage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyReport {
+    /// Number of transactions the percentiles are computed over
+    pub num_samples: usize,
+
+    /// Number of transactions tracked until they are committed or dropped
+    pub num_in_flight: usize,
+
+    /// From the submission of the transaction until its block is applied by the store
+    pub end_to_end: LatencyPercentiles,
+
+    /// Verification of the transaction against the state of the chain
+    pub verify: LatencyPercentiles,
+
+    /// Wait in the transaction queue, until the transaction is sent to the batch builder
+    pub queue_wait: LatencyPercentiles,
+
+    /// Building of the batch
+    pub batch: LatencyPercentiles,
+
+    /// Wait for a block, and proving of the block
+    pub prove: LatencyPercentiles,
+
+    /// Application of the block by the store
+    pub apply: LatencyPercentiles,
+}
+
+// LATENCY TRACKER
+// ================================================================================================
+
+/// Time at which a transaction reached every stage so far.
+#[derive(Debug, Clone, Copy)]
+struct Timeline {
+    submitted_at: Instant,
+    verified_at: Option<Instant>,
+    dequeued_at: Option<Instant>,
+    batched_at: Option<Instant>,
+    proven_at: Option<Instant>,
+}
+
+/// Durations of the stages of a committed transaction.
+#[derive(Debug, Clone, Copy)]
+struct StageDurations {
+    verify: Duration,
+    queue_wait: Duration,
+    batch: Duration,
+    prove: Duration,
+    apply: Duration,
+}
+
+impl StageDurations {
+    fn end_to_end(&self) -> Duration {
+        self.verify + self.queue_wait + self.batch + self.prove + self.apply
+    }
+}
+
+/// Tracks the time transactions spend in every stage of the block producer, from their submission
+/// until their block is applied by the store.
+///
+/// The stages of the transactions in flight are stamped as they progress, and the durations of the
+/// latest `window` committed transactions are kept to compute the percentiles. A transaction which
+/// goes through a stage again, e.g. because its batch failed to be built, keeps the time of its
+/// latest attempt.
+pub struct LatencyTracker {
+    window: usize,
+    in_flight: Mutex<BTreeMap<TransactionId, Timeline>>,
+    committed: Mutex<VecDeque<StageDurations>>,
+}
+
+impl LatencyTracker {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            in_flight: Mutex::new(BTreeMap::new()),
+            committed: Mutex::new(VecDeque::with_capacity(window)),
+        }
+    }
+
+    /// Stamps the submission of the transactions `tx_ids`.
+    ///
+    /// The transactions stop being tracked once the returned [Admission] is dropped, unless they
+    /// were admitted to the queue, so they are forgotten on every path rejecting them, including
+    /// the cancellation of their submission.
+    pub fn submitted(
+        &self,
+        tx_ids: impl IntoIterator<Item = TransactionId>,
+    ) -> Admission<'_> {
+        let timeline = Timeline {
+            submitted_at: Instant::now(),
+            verified_at: None,
+            dequeued_at: None,
+            batched_at: None,
+            proven_at: None,
+        };
+        let tx_ids: Vec<TransactionId> = tx_ids.into_iter().collect();
+        let mut in_flight = self.in_flight.lock().expect("latency lock poisoned");
+        for tx_id in &tx_ids {
+            in_flight.insert(*tx_id, timeline);
+        }
+
+        Admission {
+            tracker: self,
+            tx_ids,
+        }
+    }
+
+    /// Stamps the successful verification of the transaction `tx_id`.
+    pub fn verified(
+        &self,
+        tx_id: TransactionId,
+    ) {
+        self.stamp([tx_id], |timeline, now| timeline.verified_at = Some(now));
+    }
+
+    /// Stops tracking the transaction `tx_id`, which was rejected.
+    pub fn rejected(
+        &self,
+        tx_id: TransactionId,
+    ) {
+        self.in_flight.lock().expect("latency lock poisoned").remove(&tx_id);
+    }
+
+    /// Stops tracking the transactions `tx_ids`, which were saved to a snapshot of the queue to be
+    /// admitted again after a restart.
+    pub fn snapshotted(
+        &self,
+        tx_ids: impl IntoIterator<Item = TransactionId>,
+    ) {
+        self.forget(tx_ids);
+    }
+
+    /// Stamps the transactions `tx_ids` as sent to the batch builder.
+    pub fn dequeued(
+        &self,
+        tx_ids: impl IntoIterator<Item = TransactionId>,
+    ) {
+        self.stamp(tx_ids, |timeline, now| timeline.dequeued_at = Some(now));
+    }
+
+    /// Stamps the transactions `tx_ids` as part of a batch ready to be included in a block.
+    pub fn batched(
+        &self,
+        tx_ids: impl IntoIterator<Item = TransactionId>,
+    ) {
+        self.stamp(tx_ids, |timeline, now| timeline.batched_at = Some(now));
+    }
+
+    /// Stamps the transactions `tx_ids` as part of a proven block.
+    pub fn proven(
+        &self,
+        tx_ids: impl IntoIterator<Item = TransactionId>,
+    ) {
+        self.stamp(tx_ids, |timeline, now| timeline.proven_at = Some(now));
+    }
+
+    /// Records the durations of the transactions `tx_ids`, whose block was applied by the store.
+    ///
+    /// Transactions which missed a stage, e.g. submitted before the tracker was created, are
+    /// ignored.
+    pub fn committed(
+        &self,
+        tx_ids: impl IntoIterator<Item = TransactionId>,
+    ) {
+        let now = Instant::now();
+
+        let durations: Vec<StageDurations> = {
+            let mut in_flight = self.in_flight.lock().expect("latency lock poisoned");
+            tx_ids
+                .into_iter()
+                .filter_map(|tx_id| in_flight.remove(&tx_id))
+                .filter_map(|timeline| {
+                    let verified_at = timeline.verified_at?;
+                    let dequeued_at = timeline.dequeued_at?;
+                    let batched_at = timeline.batched_at?;
+                    let proven_at = timeline.proven_at?;
+
+                    Some(StageDurations {
+                        verify: verified_at.saturating_duration_since(timeline.submitted_at),
+                        queue_wait: dequeued_at.saturating_duration_since(verified_at),
+                        batch: batched_at.saturating_duration_since(dequeued_at),
+                        prove: proven_at.saturating_duration_since(batched_at),
+                        apply: now.saturating_duration_since(proven_at),
+                    })
+                })
+                .collect()
+        };
+
+        let mut committed = self.committed.lock().expect("latency lock poisoned");
+        committed.extend(durations);
+        while committed.len() > self.window {
+            committed.pop_front();
+        }
+    }
+
+    /// Returns the latency percentiles over the latest committed transactions.
+    pub fn report(&self) -> LatencyReport {
+        let committed = self.committed.lock().expect("latency lock poisoned");
+        let percentiles = |stage: fn(&StageDurations) -> Duration| {
+            LatencyPercentiles::new(committed.iter().map(stage).collect())
+        };
+
+        LatencyReport {
+            num_samples: committed.len(),
+            num_in_flight: self.in_flight.lock().expect("latency lock poisoned").len(),
+            end_to_end: percentiles(StageDurations::end_to_end),
+            verify: percentiles(|durations| durations.verify),
+            queue_wait: percentiles(|durations| durations.queue_wait),
+            batch: percentiles(|durations| durations.batch),
+            prove: percentiles(|durations| durations.prove),
+            apply: percentiles(|durations| durations.apply),
+        }
+    }
+
+    // HELPERS
+    // --------------------------------------------------------------------------------------------
+
+    fn forget(
+        &self,
+        tx_ids: impl IntoIterator<Item = TransactionId>,
+    ) {
+        let mut in_flight = self.in_flight.lock().expect("latency lock poisoned");
+        for tx_id in tx_ids {
+            in_flight.remove(&tx_id);
+        }
+    }
+
+    fn stamp(
+        &self,
+        tx_ids: impl IntoIterator<Item = TransactionId>,
+        update: impl Fn(&mut Timeline, Instant),
+    ) {
+        let now = Instant::now();
+        let mut in_flight = self.in_flight.lock().expect("latency lock poisoned");
+        for tx_id in tx_ids {
+            if let Some(timeline) = in_flight.get_mut(&tx_id) {
+                update(timeline, now);
+            }
+        }
+    }
+}
+
+// ADMISSION
+// ================================================================================================
+
+/// Transactions being admitted to the queue, which stop being tracked when dropped unless they
+/// were admitted.
+#[must_use]
+pub struct Admission<'a> {
+    tracker: &'a LatencyTracker,
+    tx_ids: Vec<TransactionId>,
+}
+
+impl Admission<'_> {
+    /// Keeps tracking the transactions, which were admitted to the queue.
+    pub fn admitted(mut self) {
+        self.tx_ids.clear();
+    }
+}
+
+impl Drop for Admission<'_> {
+    fn drop(&mut self) {
+        self.tracker.forget(self.tx_ids.drain(..));
+    }
+}
@@ -0,0 +1,120 @@
+use tokio::time;
+
+use super::*;
+use crate::test_utils::MockProvenTxBuilder;
+
+// HELPERS
+// ================================================================================================
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+/// Walks the transaction `tx_id` through every stage, each stage lasting `stage_duration`.
+async fn commit_transaction(
+    tracker: &LatencyTracker,
+    tx_id: TransactionId,
+    stage_duration: Duration,
+) {
+    tracker.submitted([tx_id]).admitted();
+    time::advance(stage_duration).await;
+    tracker.verified(tx_id);
+    time::advance(stage_duration).await;
+    tracker.dequeued([tx_id]);
+    time::advance(stage_duration).await;
+    tracker.batched([tx_id]);
+    time::advance(stage_duration).await;
+    tracker.proven([tx_id]);
+    time::advance(stage_duration).await;
+    tracker.committed([tx_id]);
+}
+
+// TESTS
+// ================================================================================================
+
+/// Tests that the durations of every stage are measured, and the end-to-end latency is their sum
+#[tokio::test(start_paused = true)]
+async fn test_stage_durations() {
+    let tracker = LatencyTracker::new(10);
+    assert_eq!(tracker.report(), LatencyReport::default());
+
+    let tx_id = MockProvenTxBuilder::new().build().id();
+    commit_transaction(&tracker, tx_id, ms(10)).await;
+
+    let report = tracker.report();
+    assert_eq!(report.num_samples, 1);
+    for stage in [report.verify, report.queue_wait, report.batch, report.prove, report.apply] {
+        assert_eq!(stage.p50, ms(10));
+    }
+    assert_eq!(report.end_to_end.p50, ms(50));
+    assert_eq!(report.end_to_end.p99, ms(50));
+}
+
+/// Tests the percentiles, and that only the latest committed transactions are kept
+#[tokio::test(start_paused = true)]
+async fn test_percentiles_over_window() {
+    let tracker = LatencyTracker::new(100);
+
+    for stage_millis in 1..=200 {
+        let tx_id = MockProvenTxBuilder::new().build().id();
+        commit_transaction(&tracker, tx_id, ms(stage_millis)).await;
+    }
+
+    // only the transactions with stages of 101ms to 200ms are kept
+    let report = tracker.report();
+    assert_eq!(report.num_samples, 100);
+    assert_eq!(
+        report.verify,
+        LatencyPercentiles {
+            p50: ms(150),
+            p95: ms(195),
+            p99: ms(199),
+        }
+    );
+    assert_eq!(report.end_to_end.p50, ms(750));
+}
+
+/// Tests that rejected transactions, and transactions which missed a stage, aren't reported
+#[tokio::test(start_paused = true)]
+async fn test_incomplete_transactions_are_ignored() {
+    let tracker = LatencyTracker::new(10);
+
+    let rejected_tx = MockProvenTxBuilder::new().build().id();
+    tracker.submitted([rejected_tx]).admitted();
+    tracker.rejected(rejected_tx);
+    tracker.verified(rejected_tx);
+    tracker.dequeued([rejected_tx]);
+    tracker.batched([rejected_tx]);
+    tracker.proven([rejected_tx]);
+    tracker.committed([rejected_tx]);
+
+    let unverified_tx = MockProvenTxBuilder::new().build().id();
+    tracker.submitted([unverified_tx]).admitted();
+    tracker.dequeued([unverified_tx]);
+    tracker.batched([unverified_tx]);
+    tracker.proven([unverified_tx]);
+    tracker.committed([unverified_tx]);
+
+    assert_eq!(tracker.report().num_samples, 0);
+    assert!(tracker.in_flight.lock().unwrap().is_empty());
+}
+
+/// Tests that the transactions whose admission was dropped, e.g. rejected or cancelled, stop being
+/// tracked, and that the admitted ones are tracked until committed
+#[tokio::test(start_paused = true)]
+async fn test_dropped_admissions_are_forgotten() {
+    let tracker = LatencyTracker::new(10);
+
+    let rejected_txs =
+        [MockProvenTxBuilder::new().build().id(), MockProvenTxBuilder::new().build().id()];
+    let admission = tracker.submitted(rejected_txs);
+    assert_eq!(tracker.report().num_in_flight, 2);
+    drop(admission);
+    assert_eq!(tracker.report().num_in_flight, 0);
+
+    let admitted_tx = MockProvenTxBuilder::new().build().id();
+    tracker.submitted([admitted_tx]).admitted();
+    assert_eq!(tracker.report().num_in_flight, 1);
+    tracker.committed([admitted_tx]);
+    assert_eq!(tracker.report().num_in_flight, 0);
+}
@@ -4,14 +4,24 @@ use batch_builder::batch::TransactionBatch;
 use miden_objects::transaction::ProvenTransaction;
 use tokio::sync::RwLock;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "testing"))]
 pub mod test_utils;
 
+mod audit;
 mod batch_builder;
 mod block_builder;
+mod dashboard;
 mod errors;
+pub mod journal;
+mod latency;
+mod load_shedder;
+mod notifier;
+mod pacer;
+mod quarantine;
+pub mod restart;
 mod state_view;
 mod store;
+mod time_authority;
 mod txqueue;
 
 pub mod block;
@@ -31,6 +41,9 @@ pub(crate) type SharedRwVec<T> = Arc<RwLock<Vec<T>>>;
 /// The name of the block producer component
 pub const COMPONENT: &str = "miden-block-producer";
 
+/// Operation signed by an operator to read the latest failure to build a block.
+pub const GET_LAST_BLOCK_FAILURE_OPERATION: &str = "GetLastBlockFailure";
+
 /// The depth of the SMT for created notes
 const CREATED_NOTES_SMT_DEPTH: u8 = 13;
 
@@ -41,17 +54,50 @@ const CREATED_NOTES_SMT_DEPTH: u8 = 13;
 /// hash of level 13, where both the `note_hash()` and metadata are stored (one per node).
 const MAX_NUM_CREATED_NOTES_PER_BATCH: usize = 2_usize.pow((CREATED_NOTES_SMT_DEPTH - 1) as u32);
 
-/// The number of transactions per batch
-const SERVER_BATCH_SIZE: usize = 2;
+// the two leaves of every note fill the created notes tree exactly
+const _: () = assert!(2 * MAX_NUM_CREATED_NOTES_PER_BATCH == 1 << CREATED_NOTES_SMT_DEPTH);
+
+/// The maximum time a transaction waits in the queue before a partial batch is sealed
+const SERVER_MAX_BATCH_LATENCY: Duration = Duration::from_secs(2);
+
+/// The time the production of blocks is paused for once the store refuses them for its disk usage
+const SERVER_READ_ONLY_STORE_PAUSE: Duration = Duration::from_secs(60);
+
+/// The number of failed batches a transaction is part of before it is dropped
+const SERVER_MAX_BATCH_ATTEMPTS: u32 = 3;
+
+/// The number of dropped transactions reported to their submitters
+const SERVER_DROPPED_TX_HISTORY: usize = 10_000;
+
+/// The frequency at which the store's genesis block hash and protocol version are verified
+const SERVER_NODE_INFO_CHECK_FREQUENCY: Duration = Duration::from_secs(60);
+
+/// The frequency at which the load shedder samples the load of the block producer
+const SERVER_LOAD_CHECK_FREQUENCY: Duration = Duration::from_secs(1);
+
+/// The maximum number of batches built concurrently while shedding load
+const SERVER_SHEDDING_CONCURRENT_BATCHES: usize = 1;
+
+/// The number of latest committed transactions over which the latency percentiles are computed
+const SERVER_LATENCY_WINDOW: usize = 1000;
+
+/// The maximum number of accounts whose latest known hash is cached by the state view
+const SERVER_ACCOUNT_CACHE_CAPACITY: usize = 100_000;
+
+/// The number of entries of every kind kept by the dashboard, e.g. queue depth samples
+const SERVER_DASHBOARD_HISTORY: usize = 360;
+
+/// The frequency at which the dashboard samples the depth of the transaction queue
+const SERVER_DASHBOARD_SAMPLE_FREQUENCY: Duration = Duration::from_secs(10);
 
-/// The frequency at which blocks are produced
-const SERVER_BLOCK_FREQUENCY: Duration = Duration::from_secs(10);
+/// The maximum number of webhook deliveries in progress, further notifications are dropped
+const SERVER_WEBHOOK_MAX_IN_FLIGHT: usize = 10_000;
 
-/// The frequency at which batches are built
-const SERVER_BUILD_BATCH_FREQUENCY: Duration = Duration::from_secs(2);
+/// The time after which a webhook delivery attempt fails
+const SERVER_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
 
-/// Maximum number of batches per block
-const SERVER_MAX_BATCHES_PER_BLOCK: usize = 4;
+/// The maximum delay between two webhook delivery attempts
+const SERVER_WEBHOOK_MAX_BACKOFF: Duration = Duration::from_secs(300);
 
 /// The depth at which we insert roots from the batches.
 const CREATED_NOTES_TREE_INSERTION_DEPTH: u8 = 8;
@@ -0,0 +1,199 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::time;
+use tracing::{debug, info, instrument, warn};
+
+use crate::{config::LoadSheddingConfig, COMPONENT};
+
+#[cfg(test)]
+mod tests;
+
+// LOAD SAMPLE
+// ================================================================================================
+
+/// Resource usage of the block producer at a point in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadSample {
+    /// Resident set size of the process in bytes, `None` if it can't be measured.
+    pub rss_bytes: Option<u64>,
+
+    /// Number of transactions waiting to be batched.
+    pub queued_transactions: usize,
+
+    /// Number of batches waiting to be included in a block.
+    pub ready_batches: usize,
+}
+
+// LOAD SHEDDER
+// ================================================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadShedderOptions {
+    /// The frequency at which the load is sampled
+    pub check_frequency: Duration,
+
+    /// The maximum number of batches built concurrently
+    pub max_concurrent_batches: usize,
+
+    /// The maximum number of batches built concurrently while shedding load
+    pub shedding_concurrent_batches: usize,
+}
+
+/// Watchdog protecting the block producer against memory exhaustion.
+///
+/// Once any of the configured limits is exceeded, the block producer sheds load: new transactions
+/// are rejected, and fewer batches are built concurrently, until the queued work drains. Shedding
+/// only stops once every measure is back under 90% of its limit, so the block producer doesn't
+/// flap around a limit.
+pub struct LoadShedder {
+    limits: LoadSheddingConfig,
+    options: LoadShedderOptions,
+    shedding: AtomicBool,
+}
+
+impl LoadShedder {
+    pub fn new(
+        limits: LoadSheddingConfig,
+        options: LoadShedderOptions,
+    ) -> Self {
+        Self {
+            limits,
+            options,
+            shedding: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns true if new transactions must be rejected.
+    pub fn is_shedding(&self) -> bool {
+        self.shedding.load(Ordering::Relaxed)
+    }
+
+    /// Returns the maximum number of batches which may currently be built concurrently.
+    pub fn max_concurrent_batches(&self) -> usize {
+        if self.is_shedding() {
+            self.options.shedding_concurrent_batches
+        } else {
+            self.options.max_concurrent_batches
+        }
+    }
+
+    /// Periodically samples the load with `sample`, and updates the shedding state accordingly.
+    #[instrument(target = "miden-block-producer", name = "load_shedder", skip_all)]
+    pub async fn run<S, F>(
+        self: Arc<Self>,
+        sample: S,
+    ) where
+        S: Fn() -> F,
+        F: Future<Output = LoadSample>,
+    {
+        if self.limits.max_rss_mb != 0 && resident_set_size().is_none() {
+            warn!(
+                target: COMPONENT,
+                max_rss_mb = self.limits.max_rss_mb,
+                "Resident set size can't be measured on this platform, memory limit is ignored"
+            );
+        }
+
+        let mut interval = time::interval(self.options.check_frequency);
+
+        info!(target: COMPONENT, period_ms = interval.period().as_millis(), limits = %self.limits, "Load shedder started");
+
+        loop {
+            interval.tick().await;
+            self.update(&sample().await);
+        }
+    }
+
+    /// Updates the shedding state from the latest `sample`, returns true if shedding load.
+    pub fn update(
+        &self,
+        sample: &LoadSample,
+    ) -> bool {
+        let was_shedding = self.is_shedding();
+
+        let rss_mb = sample.rss_bytes.map(|rss_bytes| rss_bytes / (1024 * 1024));
+        let overloaded = rss_mb
+            .is_some_and(|rss_mb| exceeds(rss_mb, self.limits.max_rss_mb, was_shedding))
+            || exceeds(
+                sample.queued_transactions as u64,
+                self.limits.max_queued_transactions as u64,
+                was_shedding,
+            )
+            || exceeds(
+                sample.ready_batches as u64,
+                self.limits.max_ready_batches as u64,
+                was_shedding,
+            );
+
+        self.shedding.store(overloaded, Ordering::Relaxed);
+
+        match (was_shedding, overloaded) {
+            (false, true) => warn!(
+                target: COMPONENT,
+                rss_mb,
+                queued_transactions = sample.queued_transactions,
+                ready_batches = sample.ready_batches,
+                limits = %self.limits,
+                max_concurrent_batches = self.options.shedding_concurrent_batches,
+                "Load limit exceeded, rejecting new transactions"
+            ),
+            (true, false) => info!(
+                target: COMPONENT,
+                rss_mb,
+                queued_transactions = sample.queued_transactions,
+                ready_batches = sample.ready_batches,
+                "Load back under the limits, accepting new transactions"
+            ),
+            _ => debug!(
+                target: COMPONENT,
+                rss_mb,
+                queued_transactions = sample.queued_transactions,
+                ready_batches = sample.ready_batches,
+                shedding = overloaded,
+            ),
+        }
+
+        overloaded
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Returns true if `value` is over `limit`, or over 90% of `limit` if already `shedding`.
+///
+/// A `limit` of `0` is disabled.
+fn exceeds(
+    value: u64,
+    limit: u64,
+    shedding: bool,
+) -> bool {
+    let threshold = if shedding { limit - limit / 10 } else { limit };
+    limit != 0 && value > threshold
+}
+
+/// Returns the resident set size of the process in bytes, only supported on Linux.
+#[cfg(target_os = "linux")]
+pub fn resident_set_size() -> Option<u64> {
+    // the second field is the number of resident pages
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let resident_pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+
+    // Safety: `sysconf` has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+
+    Some(resident_pages * u64::try_from(page_size).ok()?)
+}
+
+/// Returns the resident set size of the process in bytes, only supported on Linux.
+#[cfg(not(target_os = "linux"))]
+pub fn resident_set_size() -> Option<u64> {
+    None
+}
@@ -0,0 +1,99 @@
+use super::*;
+
+// HELPERS
+// ================================================================================================
+
+fn new_load_shedder(limits: LoadSheddingConfig) -> LoadShedder {
+    LoadShedder::new(
+        limits,
+        LoadShedderOptions {
+            check_frequency: Duration::from_millis(10),
+            max_concurrent_batches: 4,
+            shedding_concurrent_batches: 1,
+        },
+    )
+}
+
+// TESTS
+// ================================================================================================
+
+/// Tests that exceeding any limit starts shedding, and that shedding stops only once every measure
+/// is back under 90% of its limit
+#[test]
+fn test_shedding_hysteresis() {
+    let load_shedder = new_load_shedder(LoadSheddingConfig {
+        max_rss_mb: 100,
+        max_queued_transactions: 50,
+        max_ready_batches: 10,
+    });
+    let mib = 1024 * 1024;
+
+    let under_limits = LoadSample {
+        rss_bytes: Some(50 * mib),
+        queued_transactions: 10,
+        ready_batches: 2,
+    };
+    assert!(!load_shedder.update(&under_limits));
+    assert_eq!(load_shedder.max_concurrent_batches(), 4);
+
+    // at the limit is still accepted
+    assert!(!load_shedder.update(&LoadSample {
+        rss_bytes: Some(100 * mib),
+        ..under_limits
+    }));
+
+    assert!(load_shedder.update(&LoadSample {
+        rss_bytes: Some(101 * mib),
+        ..under_limits
+    }));
+    assert!(load_shedder.is_shedding());
+    assert_eq!(load_shedder.max_concurrent_batches(), 1);
+
+    // the memory is back under the limit, but not under 90% of it, and the ready batches exceed
+    // theirs
+    assert!(load_shedder.update(&LoadSample {
+        rss_bytes: Some(95 * mib),
+        ready_batches: 11,
+        ..under_limits
+    }));
+    assert!(load_shedder.update(&LoadSample {
+        rss_bytes: Some(90 * mib),
+        ready_batches: 10,
+        ..under_limits
+    }));
+
+    assert!(!load_shedder.update(&under_limits));
+    assert!(!load_shedder.is_shedding());
+    assert_eq!(load_shedder.max_concurrent_batches(), 4);
+}
+
+/// Tests that limits set to `0` and unmeasured memory never trigger shedding
+#[test]
+fn test_disabled_limits() {
+    let load_shedder = new_load_shedder(LoadSheddingConfig::default());
+
+    assert!(!load_shedder.update(&LoadSample {
+        rss_bytes: Some(u64::MAX),
+        queued_transactions: usize::MAX,
+        ready_batches: usize::MAX,
+    }));
+
+    let load_shedder = new_load_shedder(LoadSheddingConfig {
+        max_rss_mb: 1,
+        max_queued_transactions: 0,
+        max_ready_batches: 0,
+    });
+
+    assert!(!load_shedder.update(&LoadSample {
+        rss_bytes: None,
+        queued_transactions: 0,
+        ready_batches: 0,
+    }));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_resident_set_size() {
+    let rss_bytes = resident_set_size().expect("RSS must be measured on Linux");
+    assert!(rss_bytes > 0);
+}
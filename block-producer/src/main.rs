@@ -3,7 +3,7 @@ use clap::Parser;
 use miden_node_block_producer::{
     cli::{Cli, Command},
     config::BlockProducerTopLevelConfig,
-    server,
+    journal, server,
 };
 use miden_node_utils::config::load_config;
 
@@ -12,12 +12,22 @@ async fn main() -> Result<()> {
     miden_node_utils::logging::setup_logging()?;
 
     let cli = Cli::parse();
-    let config: BlockProducerTopLevelConfig = load_config(cli.config.as_path()).extract()?;
+    let mut config: BlockProducerTopLevelConfig = load_config(cli.config.as_path()).extract()?;
+    cli.batching.apply(&mut config.block_producer.batching);
 
     match cli.command {
         Command::Serve { .. } => {
             server::serve(config.block_producer).await?;
         },
+        Command::DumpJournal { json } => {
+            for entry in journal::read_entries(&config.block_producer.journal)? {
+                if json {
+                    println!("{}", serde_json::to_string(&entry)?);
+                } else {
+                    println!("{entry}");
+                }
+            }
+        },
     }
 
     Ok(())
@@ -0,0 +1,295 @@
+//! Webhook notifications of the block producer's activity, for integrators who don't run gRPC
+//! stream consumers.
+//!
+//! Every [Event] is posted as JSON to each configured URL. When a secret is configured, the body
+//! is signed with HMAC-SHA256, and the signature sent in the [SIGNATURE_HEADER] header. Failed
+//! deliveries are retried with an exponential backoff. The events are not persisted, the ones
+//! still undelivered when the block producer stops are lost.
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use hyper::{
+    client::HttpConnector,
+    header::{CONTENT_TYPE, USER_AGENT},
+    Body, Client, Method, Request, StatusCode, Uri,
+};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::{sync::Semaphore, time};
+use tracing::{debug, warn};
+
+use crate::{config::WebhooksConfig, COMPONENT};
+
+#[cfg(test)]
+mod tests;
+
+/// Header holding the signature of the body, `sha256=` followed by the hex encoded HMAC.
+pub const SIGNATURE_HEADER: &str = "x-miden-signature";
+
+// EVENTS
+// ================================================================================================
+
+/// Activity of the block producer an integrator may react to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A block was built and applied to the store.
+    BlockApplied {
+        block_num: u32,
+        /// Hex encoded hash of the block header
+        block_hash: String,
+        num_transactions: usize,
+    },
+
+    /// A transaction was included in the block `block_num`, applied to the store.
+    TransactionCommitted {
+        /// Hex encoded id of the transaction
+        tx_id: String,
+        block_num: u32,
+    },
+
+    /// A transaction was rejected on submission.
+    TransactionRejected {
+        /// Hex encoded id of the transaction
+        tx_id: String,
+        /// Reason of the rejection, see [crate::errors::AddTransactionError::reason]
+        reason: &'static str,
+        error: String,
+    },
+
+    /// A transaction accepted on submission was dropped before being batched, it must be submitted
+    /// again to be included in a block.
+    TransactionDropped {
+        /// Hex encoded id of the transaction
+        tx_id: String,
+        /// Reason the transaction was dropped, see [crate::txqueue::DropReason]
+        reason: &'static str,
+    },
+}
+
+/// An [Event], as posted to the webhooks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Notification {
+    /// Sequence number of the notification since the start of the block producer, the
+    /// notifications may be delivered out of order
+    pub id: u64,
+
+    /// Time of the event, in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+// NOTIFIER
+// ================================================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotifierOptions {
+    /// The maximum number of deliveries in progress, including the ones waiting for a retry. The
+    /// events beyond it are dropped.
+    pub max_in_flight: usize,
+
+    /// The time after which a delivery attempt fails
+    pub timeout: Duration,
+
+    /// The maximum delay between two delivery attempts
+    pub max_backoff: Duration,
+}
+
+/// Posts the events to the configured webhooks.
+pub struct Notifier {
+    urls: Vec<Uri>,
+    secret: Option<Vec<u8>>,
+    max_retries: u32,
+    initial_backoff: Duration,
+    options: NotifierOptions,
+    next_id: AtomicU64,
+    in_flight: Arc<Semaphore>,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl Notifier {
+    /// Returns a notifier posting to the webhooks of `config`, signing the bodies with `secret`.
+    pub fn new(
+        config: &WebhooksConfig,
+        secret: Option<Vec<u8>>,
+        options: NotifierOptions,
+    ) -> Result<Self> {
+        let urls = config
+            .urls
+            .iter()
+            .map(|url| url.parse().with_context(|| format!("Invalid webhook URL {url}")))
+            .collect::<Result<_>>()?;
+        let connector = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Ok(Self {
+            urls,
+            secret,
+            max_retries: config.max_retries,
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            in_flight: Arc::new(Semaphore::new(options.max_in_flight)),
+            options,
+            next_id: AtomicU64::new(0),
+            client: Client::builder().build(connector),
+        })
+    }
+
+    /// Posts `event` to every webhook in the background.
+    pub fn notify(
+        &self,
+        event: Event,
+    ) {
+        let notification = Notification {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            timestamp: now_ms(),
+            event,
+        };
+        let body = serde_json::to_vec(&notification).expect("the notifications serialize to JSON");
+        let signature = self.secret.as_ref().map(|secret| sign(secret, &body));
+
+        for url in &self.urls {
+            let Ok(permit) = self.in_flight.clone().try_acquire_owned() else {
+                warn!(target: COMPONENT, %url, id = notification.id, "Too many webhook deliveries in progress, dropping notification");
+                continue;
+            };
+
+            let delivery = Delivery {
+                client: self.client.clone(),
+                url: url.clone(),
+                body: body.clone(),
+                signature: signature.clone(),
+                timeout: self.options.timeout,
+            };
+            let retry_delays =
+                retry_delays(self.initial_backoff, self.options.max_backoff, self.max_retries);
+            let id = notification.id;
+            tokio::spawn(async move {
+                delivery.run(id, retry_delays).await;
+                drop(permit);
+            });
+        }
+    }
+}
+
+// DELIVERY
+// ================================================================================================
+
+/// A notification to post to a webhook.
+struct Delivery {
+    client: Client<HttpsConnector<HttpConnector>>,
+    url: Uri,
+    body: Vec<u8>,
+    signature: Option<String>,
+    timeout: Duration,
+}
+
+/// Outcome of a delivery attempt.
+enum Attempt {
+    Delivered,
+    /// The webhook refused the notification, sending it again wouldn't help
+    Refused(StatusCode),
+    Failed(String),
+}
+
+impl Delivery {
+    /// Posts the notification `id`, retrying after each of `retry_delays` until it is delivered.
+    async fn run(
+        self,
+        id: u64,
+        retry_delays: impl IntoIterator<Item = Duration>,
+    ) {
+        let mut retry_delays = retry_delays.into_iter();
+
+        loop {
+            match self.attempt().await {
+                Attempt::Delivered => {
+                    debug!(target: COMPONENT, url = %self.url, id, "Notification delivered");
+                    return;
+                },
+                Attempt::Refused(status) => {
+                    warn!(target: COMPONENT, url = %self.url, id, %status, "Webhook refused notification");
+                    return;
+                },
+                Attempt::Failed(err) => match retry_delays.next() {
+                    Some(delay) => {
+                        debug!(target: COMPONENT, url = %self.url, id, err, retry_in_ms = delay.as_millis() as u64, "Notification delivery failed");
+                        time::sleep(delay).await;
+                    },
+                    None => {
+                        warn!(target: COMPONENT, url = %self.url, id, err, "Notification delivery failed, giving up");
+                        return;
+                    },
+                },
+            }
+        }
+    }
+
+    async fn attempt(&self) -> Attempt {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(self.url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(USER_AGENT, concat!("miden-node-block-producer/", env!("CARGO_PKG_VERSION")));
+        if let Some(signature) = &self.signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+        let request = request.body(Body::from(self.body.clone())).expect("the request is valid");
+
+        match time::timeout(self.timeout, self.client.request(request)).await {
+            Ok(Ok(response)) if response.status().is_success() => Attempt::Delivered,
+            // the webhook may accept the notification later
+            Ok(Ok(response))
+                if response.status().is_server_error()
+                    || response.status() == StatusCode::TOO_MANY_REQUESTS =>
+            {
+                Attempt::Failed(format!("status {}", response.status()))
+            },
+            Ok(Ok(response)) => Attempt::Refused(response.status()),
+            Ok(Err(err)) => Attempt::Failed(err.to_string()),
+            Err(_) => Attempt::Failed("timed out".to_string()),
+        }
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Returns the signature of `body` with `secret`, as sent in the [SIGNATURE_HEADER] header.
+pub fn sign(
+    secret: &[u8],
+    body: &[u8],
+) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Returns the delays before each of the `max_retries` retries, starting at `initial` and doubled
+/// after every retry, up to `max`.
+fn retry_delays(
+    initial: Duration,
+    max: Duration,
+    max_retries: u32,
+) -> impl Iterator<Item = Duration> {
+    (0..max_retries).map(move |retry| initial.saturating_mul(2u32.saturating_pow(retry)).min(max))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
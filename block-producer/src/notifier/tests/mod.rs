@@ -0,0 +1,152 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Mutex};
+
+use hyper::{
+    body,
+    service::{make_service_fn, service_fn},
+    Response, Server,
+};
+
+use super::*;
+
+// HELPERS
+// ================================================================================================
+
+/// A request received by a [webhook].
+struct Received {
+    signature: Option<String>,
+    body: Vec<u8>,
+}
+
+/// Serves a webhook answering the requests with `statuses` in order, then with `200 OK`, and
+/// returns its address along with the requests it received.
+fn webhook(statuses: Vec<StatusCode>) -> (SocketAddr, Arc<Mutex<Vec<Received>>>) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let statuses = Arc::new(Mutex::new(statuses.into_iter()));
+
+    let make_service = {
+        let received = received.clone();
+        make_service_fn(move |_| {
+            let received = received.clone();
+            let statuses = statuses.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let received = received.clone();
+                    let statuses = statuses.clone();
+                    async move {
+                        let signature = request
+                            .headers()
+                            .get(SIGNATURE_HEADER)
+                            .map(|value| value.to_str().unwrap().to_string());
+                        let body = body::to_bytes(request.into_body()).await.unwrap().to_vec();
+                        received.lock().unwrap().push(Received { signature, body });
+
+                        let status = statuses.lock().unwrap().next().unwrap_or(StatusCode::OK);
+                        Ok::<_, Infallible>(
+                            Response::builder().status(status).body(Body::empty()).unwrap(),
+                        )
+                    }
+                }))
+            }
+        })
+    };
+
+    let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+
+    (addr, received)
+}
+
+fn notifier(
+    addr: SocketAddr,
+    secret: Option<&[u8]>,
+) -> Notifier {
+    let config = WebhooksConfig {
+        urls: vec![format!("http://{addr}/events")],
+        secret_file: None,
+        max_retries: 3,
+        initial_backoff_ms: 10,
+    };
+    let options = NotifierOptions {
+        max_in_flight: 10,
+        timeout: Duration::from_secs(5),
+        max_backoff: Duration::from_millis(20),
+    };
+
+    Notifier::new(&config, secret.map(<[u8]>::to_vec), options).unwrap()
+}
+
+/// Waits until the webhook received `count` requests.
+async fn wait_for_requests(
+    received: &Mutex<Vec<Received>>,
+    count: usize,
+) {
+    for _ in 0..200 {
+        if received.lock().unwrap().len() >= count {
+            return;
+        }
+        time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the webhook didn't receive {count} requests");
+}
+
+// TESTS
+// ================================================================================================
+
+/// Tests the signature against the HMAC-SHA256 test vector of RFC 4231
+#[test]
+fn test_sign() {
+    assert_eq!(
+        sign(b"Jefe", b"what do ya want for nothing?"),
+        "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+/// Tests that the delay doubles after every retry, up to the maximum
+#[test]
+fn test_retry_delays() {
+    let delays: Vec<_> = retry_delays(Duration::from_secs(1), Duration::from_secs(5), 5).collect();
+    assert_eq!(delays, [1, 2, 4, 5, 5].into_iter().map(Duration::from_secs).collect::<Vec<_>>());
+}
+
+/// Tests that a failed delivery is retried, with the same signed body
+#[tokio::test]
+async fn test_delivery_is_retried() {
+    let (addr, received) = webhook(vec![StatusCode::SERVICE_UNAVAILABLE]);
+    let notifier = notifier(addr, Some(b"secret"));
+
+    notifier.notify(Event::TransactionCommitted {
+        tx_id: "0x01".to_string(),
+        block_num: 7,
+    });
+    wait_for_requests(&received, 2).await;
+
+    let received = received.lock().unwrap();
+    assert_eq!(received[0].body, received[1].body);
+    assert_eq!(received[1].signature, Some(sign(b"secret", &received[1].body)));
+
+    let notification: serde_json::Value = serde_json::from_slice(&received[1].body).unwrap();
+    assert_eq!(notification["id"], 0);
+    assert_eq!(notification["event"], "transaction_committed");
+    assert_eq!(notification["tx_id"], "0x01");
+    assert_eq!(notification["block_num"], 7);
+}
+
+/// Tests that a notification refused by the webhook is not retried, and unsigned without secret
+#[tokio::test]
+async fn test_refused_delivery_is_not_retried() {
+    let (addr, received) = webhook(vec![StatusCode::BAD_REQUEST]);
+    let notifier = notifier(addr, None);
+
+    notifier.notify(Event::TransactionRejected {
+        tx_id: "0x02".to_string(),
+        reason: "queue_full",
+        error: "full".to_string(),
+    });
+    wait_for_requests(&received, 1).await;
+    time::sleep(Duration::from_millis(100)).await;
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].signature, None);
+}
@@ -0,0 +1,202 @@
+use std::{
+    cmp::Ordering as CmpOrdering,
+    future::Future,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use tokio::time::{self, Instant};
+use tracing::{debug, info, instrument, warn};
+
+use crate::{config::AdaptiveBatchingConfig, COMPONENT};
+
+#[cfg(test)]
+mod tests;
+
+// PACER METRICS
+// ================================================================================================
+
+/// Decisions taken by the [BatchPacer] since the block producer started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacerMetrics {
+    /// Current slowdown of the batch building, `1` at the normal pace.
+    pub slowdown: u32,
+
+    /// Number of times the batch building was slowed down.
+    pub num_slowdowns: u64,
+
+    /// Number of times the batch building was sped up.
+    pub num_speedups: u64,
+
+    /// Number of rounds of the transaction queue skipped to slow down the batch building.
+    pub skipped_rounds: u64,
+}
+
+// BATCH PACER
+// ================================================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchPacerOptions {
+    /// The frequency at which the backlog of the block prover is sampled
+    pub check_frequency: Duration,
+
+    /// The frequency at which the transaction queue seals batches at the normal pace
+    pub build_batch_frequency: Duration,
+
+    /// Maximum number of batches in any given block
+    pub max_batches_per_block: usize,
+}
+
+/// Adapts the pace at which batches are built to the pace at which blocks are proven.
+///
+/// When the block prover falls behind, batches are built faster than blocks include them, and the
+/// batches waiting for a block grow without bound. The backlog of the prover is the number of
+/// blocks needed to include every waiting batch. Every time it exceeds its target, the transaction
+/// queue seals batches half as often, down to the configured maximum slowdown, and every time it
+/// falls under its target, twice as often again, until the normal pace is restored.
+pub struct BatchPacer {
+    config: AdaptiveBatchingConfig,
+    options: BatchPacerOptions,
+    slowdown: AtomicU32,
+    last_round: Mutex<Option<Instant>>,
+    num_slowdowns: AtomicU64,
+    num_speedups: AtomicU64,
+    skipped_rounds: AtomicU64,
+}
+
+impl BatchPacer {
+    pub fn new(
+        config: AdaptiveBatchingConfig,
+        options: BatchPacerOptions,
+    ) -> Self {
+        Self {
+            config,
+            options,
+            slowdown: AtomicU32::new(1),
+            last_round: Mutex::new(None),
+            num_slowdowns: AtomicU64::new(0),
+            num_speedups: AtomicU64::new(0),
+            skipped_rounds: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns how many times less often than normal batches are currently sealed.
+    pub fn slowdown(&self) -> u32 {
+        self.slowdown.load(Ordering::Relaxed)
+    }
+
+    /// Returns true if the transaction queue may seal batches now.
+    ///
+    /// At the normal pace every round may seal batches. Once slowed down, a round may only seal
+    /// batches if the last round which did was at least `slowdown` normal intervals ago.
+    pub fn start_round(&self) -> bool {
+        let slowdown = self.slowdown();
+        let mut last_round = self.last_round.lock().expect("pacer lock poisoned");
+
+        let now = Instant::now();
+        let due = slowdown <= 1
+            || last_round.map_or(true, |last_round| {
+                now.duration_since(last_round) >= self.options.build_batch_frequency * slowdown
+            });
+
+        if due {
+            *last_round = Some(now);
+        } else {
+            self.skipped_rounds.fetch_add(1, Ordering::Relaxed);
+        }
+
+        due
+    }
+
+    /// Periodically samples the number of batches waiting for a block with `ready_batches`, and
+    /// updates the slowdown accordingly.
+    #[instrument(target = "miden-block-producer", name = "batch_pacer", skip_all)]
+    pub async fn run<S, F>(
+        self: Arc<Self>,
+        ready_batches: S,
+    ) where
+        S: Fn() -> F,
+        F: Future<Output = usize>,
+    {
+        if self.config.target_backlog_blocks == 0 {
+            info!(target: COMPONENT, "Adaptive batching disabled");
+            return;
+        }
+
+        let mut interval = time::interval(self.options.check_frequency);
+
+        info!(target: COMPONENT, period_ms = interval.period().as_millis(), config = %self.config, "Batch pacer started");
+
+        loop {
+            interval.tick().await;
+            self.update(ready_batches().await);
+        }
+    }
+
+    /// Updates the slowdown from the number of batches waiting for a block, returns the new
+    /// slowdown.
+    pub fn update(
+        &self,
+        ready_batches: usize,
+    ) -> u32 {
+        let target = self.config.target_backlog_blocks;
+        if target == 0 {
+            return 1;
+        }
+
+        let backlog_blocks = ready_batches.div_ceil(self.options.max_batches_per_block.max(1));
+        let slowdown = self.slowdown();
+        let new_slowdown = match backlog_blocks.cmp(&target) {
+            CmpOrdering::Greater => slowdown.saturating_mul(2).min(self.config.max_slowdown.max(1)),
+            CmpOrdering::Less => (slowdown / 2).max(1),
+            CmpOrdering::Equal => slowdown,
+        };
+        self.slowdown.store(new_slowdown, Ordering::Relaxed);
+
+        match new_slowdown.cmp(&slowdown) {
+            CmpOrdering::Greater => {
+                self.num_slowdowns.fetch_add(1, Ordering::Relaxed);
+                warn!(
+                    target: COMPONENT,
+                    ready_batches,
+                    backlog_blocks,
+                    slowdown = new_slowdown,
+                    metrics = ?self.metrics(),
+                    "Block prover falling behind, slowing down the batch building"
+                );
+            },
+            CmpOrdering::Less => {
+                self.num_speedups.fetch_add(1, Ordering::Relaxed);
+                info!(
+                    target: COMPONENT,
+                    ready_batches,
+                    backlog_blocks,
+                    slowdown = new_slowdown,
+                    metrics = ?self.metrics(),
+                    "Block prover catching up, speeding up the batch building"
+                );
+            },
+            CmpOrdering::Equal => debug!(
+                target: COMPONENT,
+                ready_batches,
+                backlog_blocks,
+                slowdown = new_slowdown,
+            ),
+        }
+
+        new_slowdown
+    }
+
+    /// Returns the decisions taken so far.
+    pub fn metrics(&self) -> PacerMetrics {
+        PacerMetrics {
+            slowdown: self.slowdown(),
+            num_slowdowns: self.num_slowdowns.load(Ordering::Relaxed),
+            num_speedups: self.num_speedups.load(Ordering::Relaxed),
+            skipped_rounds: self.skipped_rounds.load(Ordering::Relaxed),
+        }
+    }
+}
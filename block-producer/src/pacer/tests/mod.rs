@@ -0,0 +1,75 @@
+use super::*;
+
+// HELPERS
+// ================================================================================================
+
+fn new_pacer(target_backlog_blocks: usize) -> BatchPacer {
+    BatchPacer::new(
+        AdaptiveBatchingConfig {
+            target_backlog_blocks,
+            max_slowdown: 4,
+        },
+        BatchPacerOptions {
+            check_frequency: Duration::from_millis(10),
+            build_batch_frequency: Duration::from_millis(100),
+            max_batches_per_block: 2,
+        },
+    )
+}
+
+// TESTS
+// ================================================================================================
+
+/// Tests that the slowdown doubles while the backlog exceeds its target, up to the maximum, and
+/// halves once the backlog is back under the target
+#[test]
+fn test_pacer_slowdown() {
+    let pacer = new_pacer(2);
+    assert_eq!(pacer.slowdown(), 1);
+
+    // 4 batches are 2 blocks worth of batches, at the target
+    assert_eq!(pacer.update(4), 1);
+
+    assert_eq!(pacer.update(5), 2);
+    assert_eq!(pacer.update(10), 4);
+    assert_eq!(pacer.update(10), 4, "the slowdown is capped");
+
+    // at the target the pace is kept
+    assert_eq!(pacer.update(3), 4);
+
+    assert_eq!(pacer.update(2), 2);
+    assert_eq!(pacer.update(0), 1);
+    assert_eq!(pacer.update(0), 1);
+
+    let metrics = pacer.metrics();
+    assert_eq!(metrics.slowdown, 1);
+    assert_eq!(metrics.num_slowdowns, 2);
+    assert_eq!(metrics.num_speedups, 2);
+
+    // a target of 0 disables the slowdown
+    let disabled = new_pacer(0);
+    assert_eq!(disabled.update(100), 1);
+    assert_eq!(disabled.metrics().num_slowdowns, 0);
+}
+
+/// Tests that once slowed down, batches are only sealed every `slowdown` normal intervals
+#[tokio::test(start_paused = true)]
+async fn test_pacer_rounds() {
+    let pacer = new_pacer(1);
+
+    // at the normal pace every round seals batches
+    assert!(pacer.start_round());
+    assert!(pacer.start_round());
+
+    assert_eq!(pacer.update(3), 2);
+    assert!(!pacer.start_round());
+
+    time::advance(Duration::from_millis(100)).await;
+    assert!(!pacer.start_round());
+
+    time::advance(Duration::from_millis(100)).await;
+    assert!(pacer.start_round());
+    assert!(!pacer.start_round());
+
+    assert_eq!(pacer.metrics().skipped_rounds, 3);
+}
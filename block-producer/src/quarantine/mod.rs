@@ -0,0 +1,249 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use miden_node_proto::domain::BlockInputs;
+use tracing::{info, warn};
+
+use crate::{
+    block::Block, block_builder::prover::block_witness::BlockWitness, config::QuarantineConfig,
+    errors::BuildBlockError, TransactionBatch, COMPONENT,
+};
+
+#[cfg(test)]
+mod tests;
+
+// BLOCK DIAGNOSTICS
+// ================================================================================================
+
+/// Data gathered while building a block, dumped to the quarantine if building the block fails.
+///
+/// Every field is filled in once the corresponding step succeeded, so the dump shows how far the
+/// block got.
+#[derive(Debug, Default)]
+pub struct BlockDiagnostics {
+    /// Time the attempt to build the block started, in milliseconds since the UNIX epoch
+    pub started_at: u64,
+
+    /// Response of the store to the block inputs request
+    pub block_inputs: Option<BlockInputs>,
+
+    /// Inputs of the block kernel
+    pub witness: Option<BlockWitness>,
+
+    /// Proven block, which was sent to the store
+    pub block: Option<Block>,
+}
+
+// BLOCK FAILURE
+// ================================================================================================
+
+/// Summary of a failure to build a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockFailure {
+    /// Number of the block, `None` if the failure happened before the chain tip was retrieved
+    pub block_num: Option<u32>,
+
+    pub error: String,
+
+    pub num_batches: usize,
+
+    /// Time of the failure, in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+
+    /// Directory of the diagnostic dump, `None` if it wasn't written or was already removed
+    pub dump_path: Option<PathBuf>,
+}
+
+// BLOCK QUARANTINE
+// ================================================================================================
+
+/// Keeps the diagnostics of the blocks which failed to be built, so the failures can be reproduced
+/// offline.
+///
+/// Every failure is dumped to its own directory, named after the block number and the time of the
+/// failure:
+///
+/// ```text
+/// <directory>/block-<block_num>-<timestamp>/
+/// ├── error.txt          error which caused the failure
+/// ├── batches.txt        batches included in the block
+/// ├── block_inputs.txt   store response to the block inputs request, if received
+/// ├── witness.txt        block kernel inputs, if built
+/// └── block.txt          proven block, if the store rejected it
+/// ```
+///
+/// The oldest dumps are removed once their total size exceeds the configured limit.
+pub struct BlockQuarantine {
+    config: QuarantineConfig,
+    last_failure: Mutex<Option<BlockFailure>>,
+}
+
+impl BlockQuarantine {
+    pub fn new(config: QuarantineConfig) -> Self {
+        Self {
+            config,
+            last_failure: Mutex::new(None),
+        }
+    }
+
+    /// Returns the latest recorded failure, if any.
+    pub fn last_failure(&self) -> Option<BlockFailure> {
+        self.last_failure.lock().expect("quarantine lock poisoned").clone()
+    }
+
+    /// Records the failure to build a block out of `batches`, dumping the `diagnostics` gathered
+    /// until the `error` happened.
+    ///
+    /// The dump is written off the async runtime. Failing to write it is logged, and doesn't
+    /// prevent the failure from being recorded.
+    pub async fn record(
+        &self,
+        batches: &[TransactionBatch],
+        diagnostics: BlockDiagnostics,
+        error: &BuildBlockError,
+    ) -> BlockFailure {
+        let block_num = diagnostics
+            .block_inputs
+            .as_ref()
+            .map(|inputs| inputs.block_header.block_num() + 1);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default();
+
+        let dump = (self.config.max_size_mb != 0).then(|| {
+            let block_name = block_num.map_or("unknown".to_string(), |num| num.to_string());
+            let dump_path = self.config.directory.join(format!("block-{block_name}-{timestamp}"));
+            (dump_path, dump_files(batches, &diagnostics, error))
+        });
+        let config = self.config.clone();
+        let dump_path = tokio::task::spawn_blocking(move || quarantine(&config, block_num, dump))
+            .await
+            .unwrap_or_else(|err| {
+                warn!(target: COMPONENT, block_num, %err, "Failed to quarantine block");
+                None
+            });
+
+        let failure = BlockFailure {
+            block_num,
+            error: error.to_string(),
+            num_batches: batches.len(),
+            timestamp,
+            dump_path,
+        };
+
+        *self.last_failure.lock().expect("quarantine lock poisoned") = Some(failure.clone());
+
+        failure
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Writes the `dump` of a failed block, if any, then removes the oldest dumps of the quarantine
+/// exceeding its size limit.
+///
+/// Returns the directory of the dump, `None` if it wasn't written or was already removed.
+fn quarantine(
+    config: &QuarantineConfig,
+    block_num: Option<u32>,
+    dump: Option<(PathBuf, Vec<(&'static str, String)>)>,
+) -> Option<PathBuf> {
+    let dump_path = dump.and_then(|(dump_path, files)| match write_dump(&dump_path, files) {
+        Ok(()) => {
+            info!(target: COMPONENT, block_num, ?dump_path, "Failed block quarantined");
+            Some(dump_path)
+        },
+        Err(err) => {
+            warn!(target: COMPONENT, block_num, ?dump_path, %err, "Failed to quarantine block");
+            let _ = fs::remove_dir_all(&dump_path);
+            None
+        },
+    });
+
+    if let Err(err) = enforce_size_limit(config) {
+        warn!(target: COMPONENT, directory = ?config.directory, %err, "Failed to prune quarantine");
+    }
+
+    dump_path.filter(|path| path.exists())
+}
+
+/// Removes the oldest dumps until their total size fits the configured limit.
+fn enforce_size_limit(config: &QuarantineConfig) -> io::Result<()> {
+    if config.max_size_mb == 0 || !config.directory.exists() {
+        return Ok(());
+    }
+    let max_size = config.max_size_mb * 1024 * 1024;
+
+    let mut dumps = Vec::new();
+    for entry in fs::read_dir(&config.directory)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            let modified = entry.metadata()?.modified()?;
+            dumps.push((modified, directory_size(&entry.path())?, entry.path()));
+        }
+    }
+    dumps.sort();
+
+    let mut total_size: u64 = dumps.iter().map(|(_, size, _)| size).sum();
+    for (_, size, path) in dumps {
+        if total_size <= max_size {
+            break;
+        }
+        fs::remove_dir_all(&path)?;
+        total_size -= size;
+        info!(target: COMPONENT, dump_path = ?path, "Quarantined block removed");
+    }
+
+    Ok(())
+}
+
+/// Renders the diagnostics of a failed block, as the name and the contents of every file of its
+/// dump.
+fn dump_files(
+    batches: &[TransactionBatch],
+    diagnostics: &BlockDiagnostics,
+    error: &BuildBlockError,
+) -> Vec<(&'static str, String)> {
+    let mut files = vec![
+        ("error.txt", format!("{error}\n\n{error:#?}\n")),
+        ("batches.txt", format!("{batches:#?}\n")),
+    ];
+    if let Some(block_inputs) = &diagnostics.block_inputs {
+        files.push(("block_inputs.txt", format!("{block_inputs:#?}\n")));
+    }
+    if let Some(witness) = &diagnostics.witness {
+        files.push(("witness.txt", format!("{witness:#?}\n")));
+    }
+    if let Some(block) = &diagnostics.block {
+        files.push(("block.txt", format!("{block:#?}\n")));
+    }
+    files
+}
+
+/// Writes the `files` of the dump of a failed block to the directory `path`.
+fn write_dump(
+    path: &Path,
+    files: Vec<(&'static str, String)>,
+) -> io::Result<()> {
+    fs::create_dir_all(path)?;
+    for (name, contents) in files {
+        fs::write(path.join(name), contents)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the total size of the files in the directory `path`, which has no subdirectories.
+fn directory_size(path: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        size += entry?.metadata()?.len();
+    }
+    Ok(size)
+}
@@ -0,0 +1,70 @@
+use std::{thread, time::Duration};
+
+use figment::Jail;
+
+use super::*;
+use crate::errors::BlockInputsError;
+
+// TESTS
+// ================================================================================================
+
+/// Tests that a failure is dumped to its own directory, and reported as the last failure
+#[test]
+fn test_failed_block_is_dumped() {
+    Jail::expect_with(|_jail| {
+        let quarantine = BlockQuarantine::new(QuarantineConfig {
+            directory: "quarantine".into(),
+            max_size_mb: 1,
+        });
+        assert_eq!(quarantine.last_failure(), None);
+
+        let error = BuildBlockError::GetBlockInputsFailed(BlockInputsError::GrpcClientError(
+            "store unavailable".to_string(),
+        ));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let failure = runtime.block_on(quarantine.record(&[], BlockDiagnostics::default(), &error));
+
+        let dump_path = failure.dump_path.clone().expect("dump must be kept");
+        assert!(dump_path.starts_with("quarantine"));
+        assert!(fs::read_to_string(dump_path.join("error.txt"))
+            .unwrap()
+            .contains("store unavailable"));
+        assert!(dump_path.join("batches.txt").exists());
+        assert!(!dump_path.join("block_inputs.txt").exists());
+
+        assert_eq!(failure.block_num, None);
+        assert_eq!(failure.error, error.to_string());
+        assert_eq!(quarantine.last_failure(), Some(failure));
+
+        Ok(())
+    });
+}
+
+/// Tests that the oldest dumps are removed once the quarantine exceeds its size limit
+#[test]
+fn test_oldest_dumps_are_removed() {
+    Jail::expect_with(|_jail| {
+        let old_dump = PathBuf::from("quarantine/block-1-0");
+        fs::create_dir_all(&old_dump).unwrap();
+        fs::write(old_dump.join("batches.txt"), vec![0u8; 1024 * 1024]).unwrap();
+
+        // make sure the new dump is strictly more recent
+        thread::sleep(Duration::from_millis(20));
+
+        let quarantine = BlockQuarantine::new(QuarantineConfig {
+            directory: "quarantine".into(),
+            max_size_mb: 1,
+        });
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let failure = runtime.block_on(quarantine.record(
+            &[],
+            BlockDiagnostics::default(),
+            &BuildBlockError::ProvingTaskFailed,
+        ));
+
+        assert!(!old_dump.exists());
+        assert!(failure.dump_path.expect("new dump must be kept").exists());
+
+        Ok(())
+    });
+}
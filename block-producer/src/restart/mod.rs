@@ -0,0 +1,184 @@
+//! Restart of the block producer without losing its transactions.
+//!
+//! Killing the block producer drops the transactions it queued, and may interrupt the store while
+//! it applies a block. Its operator restarts it safely through the admin endpoints instead, as
+//! `miden-node restart-producer` does:
+//!
+//! 1. the intake is paused, new transactions are refused as unavailable,
+//! 2. the queue is either drained into blocks, or saved to a snapshot admitted again once the block
+//!    producer started,
+//! 3. once the batches in flight are included in a block, block production is stopped after the
+//!    block being applied, if any,
+//! 4. the server exits, to be started again by its supervisor.
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use async_trait::async_trait;
+use miden_node_proto::requests::SubmitProvenTransactionRequest;
+use prost::Message;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::{config::RestartConfig, txqueue::QueuedSubmission, COMPONENT};
+
+#[cfg(test)]
+mod tests;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Operation signed by an operator to pause or resume the intake of transactions.
+pub const PAUSE_INTAKE_OPERATION: &str = "PauseIntake";
+
+/// Operation signed by an operator to save the queue to the snapshot.
+pub const SNAPSHOT_QUEUE_OPERATION: &str = "SnapshotQueue";
+
+/// Operation signed by an operator to stop the block producer.
+pub const SHUTDOWN_OPERATION: &str = "Shutdown";
+
+// BLOCK PRODUCTION
+// ================================================================================================
+
+/// Production of the blocks out of the built batches, stopped before the block producer exits.
+#[async_trait]
+pub trait BlockProduction: Send + Sync + 'static {
+    /// Returns the number of batches waiting to be included in a block.
+    async fn num_ready_batches(&self) -> usize;
+
+    /// Stops producing blocks, once the block being produced if any was applied by the store.
+    async fn stop_block_production(&self);
+
+    /// Produces blocks again, after [BlockProduction::stop_block_production].
+    fn resume_block_production(&self);
+
+    fn is_block_production_stopped(&self) -> bool;
+}
+
+// RESTART CONTROL
+// ================================================================================================
+
+/// State of a restart of the block producer, driven by the admin endpoints.
+pub struct RestartControl {
+    intake_paused: AtomicBool,
+    block_production: Arc<dyn BlockProduction>,
+    snapshot_path: PathBuf,
+    exit: Notify,
+}
+
+impl RestartControl {
+    pub fn new(
+        config: &RestartConfig,
+        block_production: Arc<dyn BlockProduction>,
+    ) -> Self {
+        Self {
+            intake_paused: AtomicBool::new(false),
+            block_production,
+            snapshot_path: config.snapshot_path.clone(),
+            exit: Notify::new(),
+        }
+    }
+
+    /// Refuses the new transactions if `paused`, admits them again otherwise.
+    pub fn pause_intake(
+        &self,
+        paused: bool,
+    ) {
+        if self.intake_paused.swap(paused, Ordering::Relaxed) != paused {
+            info!(target: COMPONENT, paused, "Transaction intake paused or resumed");
+        }
+    }
+
+    pub fn is_intake_paused(&self) -> bool {
+        self.intake_paused.load(Ordering::Relaxed)
+    }
+
+    pub fn block_production(&self) -> &dyn BlockProduction {
+        self.block_production.as_ref()
+    }
+
+    /// Appends `submissions` to the snapshot, which is synced to disk once they are written.
+    ///
+    /// The snapshot is appended to rather than replaced, the transactions of a failed batch which
+    /// went back to the queue after a first snapshot being saved by the next one.
+    pub fn save_snapshot(
+        &self,
+        submissions: &[QueuedSubmission],
+    ) -> io::Result<()> {
+        if let Some(parent) = self.snapshot_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut bytes = Vec::new();
+        for submission in submissions {
+            submission.to_proto().encode_length_delimited(&mut bytes)?;
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.snapshot_path)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+
+        info!(target: COMPONENT, path = ?self.snapshot_path, num_txs = submissions.len(), "Queue snapshot saved");
+
+        Ok(())
+    }
+
+    /// Stops block production, then makes the server exit, see [RestartControl::exit_requested].
+    pub async fn shutdown(&self) {
+        self.block_production.stop_block_production().await;
+        info!(target: COMPONENT, "Block production stopped, exiting");
+        self.exit.notify_one();
+    }
+
+    /// Completes once the block producer was shut down.
+    pub async fn exit_requested(&self) {
+        self.exit.notified().await
+    }
+}
+
+// SNAPSHOT
+// ================================================================================================
+
+/// Reads the transactions saved to the snapshot at `path`, in the order they were queued, or
+/// `None` if there is no snapshot.
+///
+/// The snapshot ends at its first record which can't be decoded, e.g. the one written by a block
+/// producer killed while saving it.
+pub fn load_snapshot(path: &Path) -> io::Result<Option<Vec<QueuedSubmission>>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    let mut submissions = Vec::new();
+    let mut remaining = bytes.as_slice();
+    while !remaining.is_empty() {
+        let submission = SubmitProvenTransactionRequest::decode_length_delimited(&mut remaining)
+            .map_err(|err| err.to_string())
+            .and_then(QueuedSubmission::from_proto);
+        match submission {
+            Ok(submission) => submissions.push(submission),
+            Err(err) => {
+                warn!(target: COMPONENT, ?path, %err, "Truncated queue snapshot, remaining transactions skipped");
+                break;
+            },
+        }
+    }
+
+    Ok(Some(submissions))
+}
+
+/// Removes the snapshot at `path` once its transactions were admitted again.
+pub fn remove_snapshot(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
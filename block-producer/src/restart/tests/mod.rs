@@ -0,0 +1,153 @@
+use figment::Jail;
+use miden_crypto::dsa::rpo_falcon512::KeyPair;
+use miden_node_proto::domain::PartnerSignature;
+use miden_objects::transaction::{InputNotes, OutputNotes};
+
+use super::*;
+use crate::test_utils::{DummyProvenTxGenerator, MockPrivateAccount};
+
+// STRUCTS
+// ================================================================================================
+
+#[derive(Default)]
+struct BlockProductionStub {
+    stopped: AtomicBool,
+}
+
+#[async_trait]
+impl BlockProduction for BlockProductionStub {
+    async fn num_ready_batches(&self) -> usize {
+        0
+    }
+
+    async fn stop_block_production(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    fn resume_block_production(&self) {
+        self.stopped.store(false, Ordering::Relaxed);
+    }
+
+    fn is_block_production_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+fn restart_control() -> RestartControl {
+    let config = RestartConfig {
+        snapshot_path: "snapshots/queue.bin".into(),
+    };
+    RestartControl::new(&config, Arc::new(BlockProductionStub::default()))
+}
+
+fn submissions(
+    tx_gen: &DummyProvenTxGenerator,
+    accounts: std::ops::Range<u32>,
+) -> Vec<QueuedSubmission> {
+    accounts
+        .map(|index| {
+            let account = MockPrivateAccount::<3>::from(index);
+            let tx = tx_gen.dummy_proven_tx_with_params(
+                account.id,
+                account.states[0],
+                account.states[1],
+                InputNotes::new(Vec::new()).unwrap(),
+                OutputNotes::new(Vec::new()).unwrap(),
+            );
+            QueuedSubmission {
+                tx,
+                tx_kernel_version: index + 1,
+                account_delta: None,
+                consumed_notes: Vec::new(),
+                private_notes: Vec::new(),
+                partner_signature: None,
+            }
+        })
+        .collect()
+}
+
+fn summary(submissions: &[QueuedSubmission]) -> Vec<(String, u32)> {
+    submissions
+        .iter()
+        .map(|submission| (submission.tx.id().to_hex(), submission.tx_kernel_version))
+        .collect()
+}
+
+// TESTS
+// ================================================================================================
+
+/// Tests that the snapshots are read back in order, every snapshot being appended to the previous
+/// ones, with the signatures of the partner transactions
+#[test]
+fn test_snapshot_is_read_back() {
+    Jail::expect_with(|_jail| {
+        let restart = restart_control();
+        let path = restart.snapshot_path.clone();
+        assert!(load_snapshot(&path).unwrap().is_none());
+
+        let tx_gen = DummyProvenTxGenerator::new();
+        let mut first = submissions(&tx_gen, 0..2);
+        let second = submissions(&tx_gen, 2..3);
+        let partner_key = KeyPair::new().unwrap();
+        let signature = PartnerSignature::sign(&partner_key, first[1].tx.id().inner()).unwrap();
+        first[1].partner_signature = Some(signature.clone());
+        restart.save_snapshot(&first).unwrap();
+        restart.save_snapshot(&second).unwrap();
+
+        let loaded = load_snapshot(&path).unwrap().unwrap();
+        assert_eq!(summary(&loaded), summary(&[first, second].concat()));
+        let signatures: Vec<_> =
+            loaded.iter().map(|submission| submission.partner_signature.clone()).collect();
+        assert_eq!(signatures, vec![None, Some(signature), None]);
+
+        remove_snapshot(&path).unwrap();
+        assert!(load_snapshot(&path).unwrap().is_none());
+        remove_snapshot(&path).unwrap();
+
+        Ok(())
+    });
+}
+
+/// Tests that a snapshot whose last transaction was partially written is read up to that
+/// transaction
+#[test]
+fn test_truncated_snapshot() {
+    Jail::expect_with(|_jail| {
+        let restart = restart_control();
+        let path = restart.snapshot_path.clone();
+
+        let tx_gen = DummyProvenTxGenerator::new();
+        let saved = submissions(&tx_gen, 0..2);
+        restart.save_snapshot(&saved).unwrap();
+
+        // e.g. a block producer killed while saving the snapshot
+        let len = fs::metadata(&path).unwrap().len();
+        OpenOptions::new().write(true).open(&path).unwrap().set_len(len - 10).unwrap();
+
+        let loaded = load_snapshot(&path).unwrap().unwrap();
+        assert_eq!(summary(&loaded), summary(&saved[..1]));
+
+        Ok(())
+    });
+}
+
+/// Tests that the shutdown stops block production before the exit is requested
+#[tokio::test]
+async fn test_shutdown() {
+    let restart = restart_control();
+    assert!(!restart.is_intake_paused());
+
+    restart.pause_intake(true);
+    assert!(restart.is_intake_paused());
+
+    restart.shutdown().await;
+    assert!(restart.block_production().is_block_production_stopped());
+
+    // the exit is requested even though the server started waiting for it after the shutdown
+    tokio::time::timeout(std::time::Duration::from_secs(1), restart.exit_requested())
+        .await
+        .unwrap();
+}
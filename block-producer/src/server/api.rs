@@ -1,20 +1,58 @@
-use std::sync::Arc;
+use std::{
+    collections::BTreeSet,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::Result;
-use miden_crypto::utils::Deserializable;
+use miden_crypto::{dsa::rpo_falcon512::KeyPair, utils::Deserializable};
 use miden_node_proto::{
-    block_producer::api_server, requests::SubmitProvenTransactionRequest,
-    responses::SubmitProvenTransactionResponse,
+    block_producer::api_server,
+    domain::{PartnerSignature, SubmissionReceipt},
+    requests::{
+        GetLastBlockFailureRequest, GetMempoolStatusRequest,
+        GetPendingTransactionsByAccountRequest, GetQueueDepthRequest, GetRestartStatusRequest,
+        GetStartupAuditRequest, GetTransactionLatencyRequest, PauseIntakeRequest, ShutdownRequest,
+        SnapshotQueueRequest, SubmitProvenBatchRequest, SubmitProvenTransactionRequest,
+    },
+    responses::{
+        self, BlockFailure, DroppedTransaction, GetLastBlockFailureResponse,
+        GetMempoolStatusResponse, GetPendingTransactionsByAccountResponse, GetQueueDepthResponse,
+        GetRestartStatusResponse, GetStartupAuditResponse, GetTransactionLatencyResponse,
+        PauseIntakeResponse, PendingTransaction, ShutdownResponse, SnapshotQueueResponse,
+        SubmitProvenBatchResponse, SubmitProvenTransactionResponse,
+    },
+    FIRST_TX_KERNEL_VERSION, SUBMIT_PROVEN_BATCH_OPERATION,
+};
+use miden_node_utils::{
+    formatting::{format_blake3_digest, format_input_notes, format_opt, format_output_notes},
+    operator_auth::require_operator,
+};
+use miden_objects::{
+    accounts::{AccountDelta, AccountId},
+    transaction::ProvenTransaction,
+    Digest,
 };
-use miden_node_utils::formatting::{format_input_notes, format_opt, format_output_notes};
-use miden_objects::transaction::ProvenTransaction;
 use tonic::Status;
-use tracing::{debug, info, instrument};
+use tracing::{debug, info, instrument, warn};
 
 use crate::{
-    batch_builder::BatchBuilder,
-    txqueue::{TransactionQueue, TransactionVerifier},
-    COMPONENT,
+    audit::StartupAudit,
+    batch_builder::{prover::BatchProof, BatchBuilder},
+    dashboard::Dashboard,
+    errors::{AddBatchError, AddTransactionError},
+    latency::{LatencyPercentiles, LatencyTracker},
+    notifier::{Event, Notifier},
+    quarantine::BlockQuarantine,
+    restart::{
+        BlockProduction, RestartControl, PAUSE_INTAKE_OPERATION, SHUTDOWN_OPERATION,
+        SNAPSHOT_QUEUE_OPERATION,
+    },
+    txqueue::{
+        parse_consumed_notes, parse_private_notes, Lane, QueuedSubmission, TransactionQueue,
+        TransactionVerifier,
+    },
+    COMPONENT, GET_LAST_BLOCK_FAILURE_OPERATION,
 };
 
 // BLOCK PRODUCER
@@ -22,11 +60,91 @@ use crate::{
 
 pub struct BlockProducerApi<BB, TV> {
     queue: Arc<TransactionQueue<BB, TV>>,
+    quarantine: Arc<BlockQuarantine>,
+    latency: Arc<LatencyTracker>,
+    /// Key signing the receipts of the accepted transactions, none are returned if missing.
+    receipt_key: Option<Arc<KeyPair>>,
+    /// Identifier of the chain the receipts are signed for.
+    chain_id: u32,
+    /// State of the store the block producer started with.
+    audit: Arc<StartupAudit>,
+    /// Production of the blocks out of the batches built from the queue.
+    block_production: Arc<dyn BlockProduction>,
+    /// Dashboard recording the rejected transactions, if enabled.
+    dashboard: Option<Arc<Dashboard>>,
+    /// Webhooks notified of the rejected transactions, if enabled.
+    notifier: Option<Arc<Notifier>>,
+    /// Restart of the block producer by its operators, refused if missing.
+    restart: Option<Arc<RestartControl>>,
+    /// Whether the batches of external batch producers are accepted, which requires the batches to
+    /// be proven, the proofs of their transactions being verified by the batch prover.
+    proven_batches: bool,
 }
 
 impl<BB, TV> BlockProducerApi<BB, TV> {
-    pub fn new(queue: Arc<TransactionQueue<BB, TV>>) -> Self {
-        Self { queue }
+    pub fn new(
+        queue: Arc<TransactionQueue<BB, TV>>,
+        quarantine: Arc<BlockQuarantine>,
+        latency: Arc<LatencyTracker>,
+        receipt_key: Option<Arc<KeyPair>>,
+        chain_id: u32,
+        audit: Arc<StartupAudit>,
+        block_production: Arc<dyn BlockProduction>,
+    ) -> Self {
+        Self {
+            queue,
+            quarantine,
+            latency,
+            receipt_key,
+            chain_id,
+            audit,
+            block_production,
+            dashboard: None,
+            notifier: None,
+            restart: None,
+            proven_batches: false,
+        }
+    }
+
+    /// Records the rejected transactions on the `dashboard`.
+    pub fn with_dashboard(
+        mut self,
+        dashboard: Arc<Dashboard>,
+    ) -> Self {
+        self.dashboard = Some(dashboard);
+        self
+    }
+
+    /// Notifies the webhooks of the `notifier` of the rejected transactions.
+    pub fn with_notifier(
+        mut self,
+        notifier: Arc<Notifier>,
+    ) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Lets the operators restart the block producer through `restart`, see [crate::restart].
+    pub fn with_restart(
+        mut self,
+        restart: Arc<RestartControl>,
+    ) -> Self {
+        self.restart = Some(restart);
+        self
+    }
+
+    /// Accepts the batches built and proven by external batch producers, see
+    /// [TransactionQueue::add_batch]. The batch builder must prove the batches, the proofs of the
+    /// transactions of the external batches being verified by its prover only.
+    pub fn with_proven_batches(mut self) -> Self {
+        self.proven_batches = true;
+        self
+    }
+
+    fn restart_control(&self) -> Result<&Arc<RestartControl>, Status> {
+        self.restart
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("Restarts are not supported"))
     }
 }
 
@@ -47,11 +165,32 @@ where
         &self,
         request: tonic::Request<SubmitProvenTransactionRequest>,
     ) -> Result<tonic::Response<SubmitProvenTransactionResponse>, Status> {
+        let received_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("current time is after the UNIX epoch")
+            .as_millis() as u64;
+
+        // the client may retry once the block producer restarted
+        if self.restart.as_ref().is_some_and(|restart| restart.is_intake_paused()) {
+            return Err(Status::unavailable(
+                "Transaction intake paused, the block producer is restarting",
+            ));
+        }
+
         let request = request.into_inner();
         debug!(target: COMPONENT, ?request);
 
         let tx = ProvenTransaction::read_from_bytes(&request.transaction)
             .map_err(|_| Status::invalid_argument("Invalid transaction"))?;
+        let account_delta: Option<AccountDelta> = request
+            .account_delta
+            .map(TryInto::try_into)
+            .transpose()
+            .map_err(|err| Status::invalid_argument(format!("Invalid account delta: {err}")))?;
+        let consumed_notes =
+            parse_consumed_notes(&request.consumed_notes).map_err(Status::invalid_argument)?;
+        let private_notes =
+            parse_private_notes(&request.private_notes).map_err(Status::invalid_argument)?;
 
         info!(
             target: COMPONENT,
@@ -67,11 +206,512 @@ where
         );
         debug!(target: COMPONENT, proof = ?tx.proof());
 
-        self.queue
-            .add_transaction(tx)
+        // submissions predating the kernel versions were proven with the first kernel
+        let tx_kernel_version = match request.tx_kernel_version {
+            0 => FIRST_TX_KERNEL_VERSION,
+            version => version,
+        };
+
+        let partner_signature: Option<PartnerSignature> =
+            request.partner_signature.as_ref().map(TryInto::try_into).transpose().map_err(
+                |err| Status::invalid_argument(format!("Invalid partner signature: {err}")),
+            )?;
+
+        let tx_id = tx.id();
+        let result = match &partner_signature {
+            Some(signature) => {
+                self.queue
+                    .add_partner_transaction(
+                        tx,
+                        tx_kernel_version,
+                        account_delta,
+                        consumed_notes,
+                        private_notes,
+                        signature,
+                    )
+                    .await
+            },
+            None => {
+                self.queue
+                    .add_transaction(
+                        tx,
+                        tx_kernel_version,
+                        account_delta,
+                        consumed_notes,
+                        private_notes,
+                    )
+                    .await
+            },
+        };
+        let queue_position = match result {
+            Ok(queue_position) => queue_position,
+            Err(err) => {
+                if let Some(dashboard) = &self.dashboard {
+                    dashboard.record_rejection(tx_id.to_hex(), err.reason(), err.to_string());
+                }
+                if let Some(notifier) = &self.notifier {
+                    notifier.notify(Event::TransactionRejected {
+                        tx_id: tx_id.to_hex(),
+                        reason: err.reason(),
+                        error: err.to_string(),
+                    });
+                }
+                return Err(add_transaction_status(&err));
+            },
+        };
+
+        let receipt = self
+            .receipt_key
+            .as_ref()
+            .map(|keypair| {
+                SubmissionReceipt::sign(
+                    keypair,
+                    self.chain_id,
+                    tx_id.inner(),
+                    received_at,
+                    queue_position as u32,
+                )
+            })
+            .transpose()
+            .map_err(|err| Status::internal(format!("Failed to sign receipt: {err:?}")))?;
+
+        Ok(tonic::Response::new(SubmitProvenTransactionResponse {
+            receipt: receipt.as_ref().map(Into::into),
+        }))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-block-producer",
+        name = "block_producer:submit_proven_batch",
+        skip_all,
+        err
+    )]
+    async fn submit_proven_batch(
+        &self,
+        request: tonic::Request<SubmitProvenBatchRequest>,
+    ) -> Result<tonic::Response<SubmitProvenBatchResponse>, Status> {
+        // the batches of unknown batch producers are refused before they are parsed
+        let operator = require_operator(&request, SUBMIT_PROVEN_BATCH_OPERATION)?;
+
+        // the batch proof doesn't cover the proofs of the transactions, which only the batch
+        // prover verifies
+        if !self.proven_batches {
+            return Err(Status::failed_precondition(
+                "Batch proving is disabled, the proofs of the transactions of the batch can't be verified",
+            ));
+        }
+
+        // the batch producer may retry once the block producer restarted
+        if self.restart.as_ref().is_some_and(|restart| restart.is_intake_paused()) {
+            return Err(Status::unavailable(
+                "Transaction intake paused, the block producer is restarting",
+            ));
+        }
+
+        let request = request.into_inner();
+        debug!(target: COMPONENT, num_txs = request.transactions.len(), operator = %operator.key.to_hex());
+
+        let commitment: Digest = request
+            .commitment
+            .ok_or(Status::invalid_argument("Batch commitment is missing"))?
+            .try_into()
+            .map_err(|_| Status::invalid_argument("Invalid batch commitment"))?;
+        if request.proof.is_empty() {
+            return Err(Status::invalid_argument("Batch proof is missing"));
+        }
+        let proof = BatchProof::new(commitment, request.proof);
+
+        let mut submissions = Vec::with_capacity(request.transactions.len());
+        for (position, mut transaction) in request.transactions.into_iter().enumerate() {
+            if transaction.partner_signature.is_some() {
+                return Err(Status::invalid_argument(format!(
+                    "Transaction {position} of the batch is signed by a partner, batches don't use the partner reservations"
+                )));
+            }
+            // submissions predating the kernel versions were proven with the first kernel
+            if transaction.tx_kernel_version == 0 {
+                transaction.tx_kernel_version = FIRST_TX_KERNEL_VERSION;
+            }
+            let submission = QueuedSubmission::from_proto(transaction).map_err(|err| {
+                Status::invalid_argument(format!("Transaction {position} of the batch: {err}"))
+            })?;
+            submissions.push(submission);
+        }
+        let tx_ids: Vec<_> = submissions.iter().map(|submission| submission.tx.id()).collect();
+
+        let batch = match self.queue.add_batch(submissions, proof).await {
+            Ok(batch) => batch,
+            Err(err) => {
+                for tx_id in &tx_ids {
+                    if let Some(dashboard) = &self.dashboard {
+                        dashboard.record_rejection(tx_id.to_hex(), err.reason(), err.to_string());
+                    }
+                    if let Some(notifier) = &self.notifier {
+                        notifier.notify(Event::TransactionRejected {
+                            tx_id: tx_id.to_hex(),
+                            reason: err.reason(),
+                            error: err.to_string(),
+                        });
+                    }
+                }
+                return Err(match &err {
+                    AddBatchError::Overloaded | AddBatchError::QueueFull => {
+                        Status::unavailable(err.to_string())
+                    },
+                    AddBatchError::TransactionRejected { error, .. } => {
+                        Status::new(add_transaction_status(error).code(), err.to_string())
+                    },
+                    _ => Status::invalid_argument(err.to_string()),
+                });
+            },
+        };
+
+        info!(target: COMPONENT, batch_id = %format_blake3_digest(batch.id()), num_txs = tx_ids.len(), "Batch accepted");
+
+        Ok(tonic::Response::new(SubmitProvenBatchResponse {
+            batch_id: batch.id().as_bytes().to_vec(),
+            created_notes_root: Some(batch.created_notes_root().into()),
+        }))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-block-producer",
+        name = "block_producer:get_pending_transactions_by_account",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_pending_transactions_by_account(
+        &self,
+        request: tonic::Request<GetPendingTransactionsByAccountRequest>,
+    ) -> Result<tonic::Response<GetPendingTransactionsByAccountResponse>, Status> {
+        let request = request.into_inner();
+        debug!(target: COMPONENT, ?request);
+
+        let account_id: AccountId = request
+            .account_id
+            .ok_or(Status::invalid_argument("Account id is missing"))?
+            .try_into()
+            .map_err(|_| Status::invalid_argument("Invalid account id"))?;
+
+        let transactions = self
+            .queue
+            .get_pending_transactions_by_account(account_id)
             .await
-            .map_err(|err| Status::invalid_argument(format!("{:?}", err)))?;
+            .into_iter()
+            .map(|tx| PendingTransaction {
+                tx_id: Some(tx.tx_id.into()),
+                initial_account_hash: Some(tx.initial_account_hash.into()),
+                final_account_hash: Some(tx.final_account_hash.into()),
+                queue_position: tx.queue_position as u32,
+            })
+            .collect();
+        let dropped_transactions = self
+            .queue
+            .get_dropped_transactions_by_account(account_id)
+            .into_iter()
+            .map(|tx| DroppedTransaction {
+                tx_id: Some(tx.tx_id.into()),
+                reason: tx.reason.to_string(),
+                dropped_at: tx.dropped_at,
+            })
+            .collect();
+
+        Ok(tonic::Response::new(GetPendingTransactionsByAccountResponse {
+            transactions,
+            dropped_transactions,
+        }))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-block-producer",
+        name = "block_producer:get_last_block_failure",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_last_block_failure(
+        &self,
+        request: tonic::Request<GetLastBlockFailureRequest>,
+    ) -> Result<tonic::Response<GetLastBlockFailureResponse>, Status> {
+        require_operator(&request, GET_LAST_BLOCK_FAILURE_OPERATION)?;
+        debug!(target: COMPONENT, request = ?request.into_inner());
+
+        let failure = self.quarantine.last_failure().map(|failure| BlockFailure {
+            block_num: failure.block_num,
+            error: failure.error,
+            num_batches: failure.num_batches as u32,
+            timestamp: failure.timestamp,
+            dump_path: failure.dump_path.map(|path| path.display().to_string()),
+        });
+
+        Ok(tonic::Response::new(GetLastBlockFailureResponse { failure }))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-block-producer",
+        name = "block_producer:get_transaction_latency",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_transaction_latency(
+        &self,
+        request: tonic::Request<GetTransactionLatencyRequest>,
+    ) -> Result<tonic::Response<GetTransactionLatencyResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.into_inner());
+
+        let report = self.latency.report();
+
+        Ok(tonic::Response::new(GetTransactionLatencyResponse {
+            num_samples: report.num_samples as u32,
+            num_in_flight: report.num_in_flight as u32,
+            end_to_end: Some(percentiles_to_proto(report.end_to_end)),
+            verify: Some(percentiles_to_proto(report.verify)),
+            queue_wait: Some(percentiles_to_proto(report.queue_wait)),
+            batch: Some(percentiles_to_proto(report.batch)),
+            prove: Some(percentiles_to_proto(report.prove)),
+            apply: Some(percentiles_to_proto(report.apply)),
+        }))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-block-producer",
+        name = "block_producer:get_queue_depth",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_queue_depth(
+        &self,
+        request: tonic::Request<GetQueueDepthRequest>,
+    ) -> Result<tonic::Response<GetQueueDepthResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.into_inner());
+
+        Ok(tonic::Response::new(GetQueueDepthResponse {
+            consuming: self.queue.num_queued_transactions_in_lane(Lane::Consuming).await as u32,
+            creating: self.queue.num_queued_transactions_in_lane(Lane::Creating).await as u32,
+        }))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-block-producer",
+        name = "block_producer:get_mempool_status",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_mempool_status(
+        &self,
+        request: tonic::Request<GetMempoolStatusRequest>,
+    ) -> Result<tonic::Response<GetMempoolStatusResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.into_inner());
+
+        let ready_batches = self.block_production.num_ready_batches().await;
+        let in_flight = self.queue.in_flight().await;
+
+        Ok(tonic::Response::new(GetMempoolStatusResponse {
+            queued_transactions: self.queue.num_queued_transactions().await as u32,
+            batches_in_flight: self.queue.num_batches_in_flight() as u32,
+            ready_batches: ready_batches as u32,
+            locked_accounts: in_flight.num_accounts as u32,
+            reserved_nullifiers: in_flight.num_nullifiers as u32,
+        }))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-block-producer",
+        name = "block_producer:get_startup_audit",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_startup_audit(
+        &self,
+        request: tonic::Request<GetStartupAuditRequest>,
+    ) -> Result<tonic::Response<GetStartupAuditResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.into_inner());
+
+        Ok(tonic::Response::new(self.audit.as_ref().into()))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-block-producer",
+        name = "block_producer:get_restart_status",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_restart_status(
+        &self,
+        request: tonic::Request<GetRestartStatusRequest>,
+    ) -> Result<tonic::Response<GetRestartStatusResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.into_inner());
+
+        let restart = self.restart_control()?;
+
+        Ok(tonic::Response::new(GetRestartStatusResponse {
+            intake_paused: restart.is_intake_paused(),
+            queued_transactions: self.queue.num_queued_transactions().await as u32,
+            batches_in_flight: self.queue.num_batches_in_flight() as u32,
+            ready_batches: restart.block_production().num_ready_batches().await as u32,
+            block_production_stopped: restart.block_production().is_block_production_stopped(),
+        }))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-block-producer",
+        name = "block_producer:pause_intake",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn pause_intake(
+        &self,
+        request: tonic::Request<PauseIntakeRequest>,
+    ) -> Result<tonic::Response<PauseIntakeResponse>, Status> {
+        let operator = require_operator(&request, PAUSE_INTAKE_OPERATION)?;
+        let request = request.into_inner();
+        debug!(target: COMPONENT, ?request);
+
+        let restart = self.restart_control()?;
+        if !request.paused && restart.block_production().is_block_production_stopped() {
+            return Err(Status::failed_precondition(
+                "Block production stopped, the block producer is exiting",
+            ));
+        }
+
+        info!(target: COMPONENT, operator = %operator.key.to_hex(), paused = request.paused, "Transaction intake paused or resumed by operator");
+        restart.pause_intake(request.paused);
+
+        Ok(tonic::Response::new(PauseIntakeResponse {}))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-block-producer",
+        name = "block_producer:snapshot_queue",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn snapshot_queue(
+        &self,
+        request: tonic::Request<SnapshotQueueRequest>,
+    ) -> Result<tonic::Response<SnapshotQueueResponse>, Status> {
+        let operator = require_operator(&request, SNAPSHOT_QUEUE_OPERATION)?;
+        debug!(target: COMPONENT, request = ?request.into_inner());
+
+        let restart = self.restart_control()?;
+        if !restart.is_intake_paused() {
+            return Err(Status::failed_precondition("The intake must be paused first"));
+        }
+
+        // the transactions are removed from the queue only once saved, the ones batched in the
+        // meantime are both in the snapshot and in a block, and rejected once admitted again. The
+        // snapshot is written and synced on a blocking thread
+        let submissions = self.queue.queued_submissions().await;
+        let submissions = tokio::task::spawn_blocking({
+            let restart = Arc::clone(restart);
+            move || restart.save_snapshot(&submissions).map(|()| submissions)
+        })
+        .await
+        .map_err(|err| Status::internal(format!("Queue snapshot task failed: {err}")))?
+        .map_err(|err| Status::internal(format!("Failed to save the queue snapshot: {err}")))?;
+        let tx_ids: BTreeSet<_> = submissions.iter().map(|submission| submission.tx.id()).collect();
+        let num_removed = self.queue.remove_transactions(&tx_ids).await;
+        if num_removed < submissions.len() {
+            warn!(target: COMPONENT, num_batched = submissions.len() - num_removed, "Snapshotted transactions batched before they were removed from the queue");
+        }
+
+        info!(target: COMPONENT, operator = %operator.key.to_hex(), num_txs = submissions.len(), "Queue snapshotted by operator");
+
+        Ok(tonic::Response::new(SnapshotQueueResponse {
+            num_transactions: submissions.len() as u32,
+        }))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-block-producer",
+        name = "block_producer:shutdown",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn shutdown(
+        &self,
+        request: tonic::Request<ShutdownRequest>,
+    ) -> Result<tonic::Response<ShutdownResponse>, Status> {
+        let operator = require_operator(&request, SHUTDOWN_OPERATION)?;
+        debug!(target: COMPONENT, request = ?request.into_inner());
+
+        let restart = self.restart_control()?;
+        if !restart.is_intake_paused() {
+            return Err(Status::failed_precondition("The intake must be paused first"));
+        }
+
+        // exiting now would lose the transactions which are not in a block yet
+        let block_production = restart.block_production();
+        let num_queued = self.queue.num_queued_transactions().await;
+        let num_in_flight = self.queue.num_batches_in_flight();
+        let num_ready = block_production.num_ready_batches().await;
+        if num_queued + num_in_flight + num_ready > 0 {
+            return Err(Status::failed_precondition(format!(
+                "{num_queued} queued transactions, {num_in_flight} batches in flight and {num_ready} ready batches, drain or snapshot the queue first"
+            )));
+        }
+
+        // the block being built when block production stopped may have failed, its batches
+        // going back to be included in the next block
+        block_production.stop_block_production().await;
+        let num_ready = block_production.num_ready_batches().await;
+        if num_ready > 0 {
+            block_production.resume_block_production();
+            return Err(Status::failed_precondition(format!(
+                "The latest block failed, {num_ready} batches to be included in a block again"
+            )));
+        }
+
+        info!(target: COMPONENT, operator = %operator.key.to_hex(), "Block producer shut down by operator");
+        restart.shutdown().await;
+
+        Ok(tonic::Response::new(ShutdownResponse {}))
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Returns the status of a request whose transaction was rejected with `err`.
+fn add_transaction_status(err: &AddTransactionError) -> Status {
+    match err {
+        // the client may retry once the load decreases, or the disk is writable again
+        AddTransactionError::Overloaded
+        | AddTransactionError::QueueFull
+        | AddTransactionError::PersistenceFailed(_) => Status::unavailable(err.to_string()),
+        // the client must prove the transaction again with a supported kernel
+        AddTransactionError::UnsupportedTxKernelVersion { .. } => {
+            Status::failed_precondition(err.to_string())
+        },
+        AddTransactionError::UnknownPartner(_) => Status::permission_denied(err.to_string()),
+        _ => Status::invalid_argument(format!("{:?}", err)),
+    }
+}
 
-        Ok(tonic::Response::new(SubmitProvenTransactionResponse {}))
+fn percentiles_to_proto(percentiles: LatencyPercentiles) -> responses::LatencyPercentiles {
+    responses::LatencyPercentiles {
+        p50: percentiles.p50.as_micros() as u64,
+        p95: percentiles.p95.as_micros() as u64,
+        p99: percentiles.p99.as_micros() as u64,
     }
 }
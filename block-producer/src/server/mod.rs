@@ -1,19 +1,60 @@
-use std::{net::ToSocketAddrs, sync::Arc};
+use std::{
+    fs,
+    net::ToSocketAddrs,
+    path::Path,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use anyhow::{anyhow, Result};
-use miden_node_proto::{block_producer::api_server, store::api_client as store_client};
-use tonic::transport::Server;
-use tracing::{info, instrument};
+use anyhow::{anyhow, Context, Result};
+use miden_crypto::dsa::rpo_falcon512::KeyPair;
+use miden_node_proto::{
+    block_producer::api_server,
+    chain_id::{ChainIdInterceptor, ChainIdValidator},
+    errors::NodeInfoError,
+    store::api_client as store_client,
+};
+use miden_node_store::server::StoreApi;
+use miden_node_utils::{
+    config::parse_genesis_hash,
+    grpc,
+    operator_auth::{format_key, OperatorAuthenticator, OperatorKeys},
+};
+use miden_objects::Digest;
+use tokio::{task::JoinSet, time};
+use tonic::service::Interceptor;
+use tracing::{error, info, instrument, warn};
 
 use crate::{
-    batch_builder::{DefaultBatchBuilder, DefaultBatchBuilderOptions},
-    block_builder::DefaultBlockBuilder,
+    batch_builder::{
+        prover::LocalBatchProver, BatchBuilder, DefaultBatchBuilder, DefaultBatchBuilderOptions,
+    },
+    block_builder::{
+        prover::remote::RemoteBlockProver, DefaultBlockBuilder, DefaultBlockBuilderOptions,
+    },
     config::BlockProducerConfig,
+    dashboard::{self, Dashboard},
+    errors::AddTransactionError,
+    journal::BlockJournal,
+    latency::LatencyTracker,
+    load_shedder::{resident_set_size, LoadSample, LoadShedder, LoadShedderOptions},
+    notifier::{Notifier, NotifierOptions},
+    pacer::{BatchPacer, BatchPacerOptions},
+    quarantine::BlockQuarantine,
+    restart::{self, RestartControl},
     state_view::DefaultStateView,
-    store::DefaultStore,
-    txqueue::{TransactionQueue, TransactionQueueOptions},
-    COMPONENT, SERVER_BATCH_SIZE, SERVER_BLOCK_FREQUENCY, SERVER_BUILD_BATCH_FREQUENCY,
-    SERVER_MAX_BATCHES_PER_BLOCK,
+    store::{DefaultStore, StoreEndpoints},
+    time_authority::TimeAuthority,
+    txqueue::{
+        policy_from_config, Lane, QueuedSubmission, Reservations, TransactionLog, TransactionQueue,
+        TransactionQueueOptions, TransactionVerifier,
+    },
+    COMPONENT, SERVER_ACCOUNT_CACHE_CAPACITY, SERVER_DASHBOARD_HISTORY,
+    SERVER_DASHBOARD_SAMPLE_FREQUENCY, SERVER_DROPPED_TX_HISTORY, SERVER_LATENCY_WINDOW,
+    SERVER_LOAD_CHECK_FREQUENCY, SERVER_MAX_BATCH_ATTEMPTS, SERVER_MAX_BATCH_LATENCY,
+    SERVER_NODE_INFO_CHECK_FREQUENCY, SERVER_READ_ONLY_STORE_PAUSE,
+    SERVER_SHEDDING_CONCURRENT_BATCHES, SERVER_WEBHOOK_MAX_BACKOFF, SERVER_WEBHOOK_MAX_IN_FLIGHT,
+    SERVER_WEBHOOK_TIMEOUT,
 };
 
 // TODO: does this need to be public?
@@ -22,47 +63,472 @@ pub mod api;
 // BLOCK PRODUCER INITIALIZER
 // ================================================================================================
 
-/// TODO: add comments
-#[instrument(target = "miden-block-producer", name = "block_producer", skip_all)]
+/// Serves the block producer, against the store at `config.store_url`.
 pub async fn serve(config: BlockProducerConfig) -> Result<()> {
-    info!(target: COMPONENT, %config, "Initializing server");
+    if config.embedded_store {
+        return Err(anyhow!(
+            "The embedded store is only available when the block producer is started by the node"
+        ));
+    }
 
-    let store = Arc::new(DefaultStore::new(
-        store_client::ApiClient::connect(config.store_url.to_string()).await?,
+    let store_channel = grpc::connect(&config.store_url).await?;
+    let mut store = DefaultStore::new(store_client::ApiClient::with_interceptor(
+        store_channel,
+        ChainIdInterceptor::new(config.chain_id),
     ));
-    let state_view = Arc::new(DefaultStateView::new(store.clone()));
+    if let Some(key_file) = &config.store_operator_key_file {
+        store = store.with_operator_key(Arc::new(load_key(key_file)?), config.chain_id);
+    }
+
+    serve_with_store(config, store).await
+}
+
+/// Serves the block producer, against `store` running in the same process.
+pub async fn serve_embedded(
+    config: BlockProducerConfig,
+    store: Arc<StoreApi>,
+) -> Result<()> {
+    serve_with_store(config, DefaultStore::new(store)).await
+}
+
+#[instrument(target = "miden-block-producer", name = "block_producer", skip_all)]
+async fn serve_with_store<S: StoreEndpoints>(
+    config: BlockProducerConfig,
+    store: DefaultStore<S>,
+) -> Result<()> {
+    info!(target: COMPONENT, %config, "Initializing server");
+
+    config.batching.validate()?;
+    let operator_keys = OperatorKeys::from_hex(&config.operator_keys)?;
+    if operator_keys.is_empty() {
+        info!(target: COMPONENT, "No operator key configured, admin operations are disabled");
+    }
+
+    let store = Arc::new(store);
+
+    // Refuse to produce blocks for a chain the store doesn't serve.
+    let genesis_hash = parse_genesis_hash(config.genesis_hash.as_deref())?;
+    let node_info = store.verify_node_info(config.chain_id, genesis_hash).await?;
+    info!(
+        target: COMPONENT,
+        genesis_hash = %node_info.genesis_hash,
+        store_version = node_info.version,
+        "Store handshake succeeded"
+    );
+
+    // Refuse to build on top of a chain tip which doesn't match the store's state or the block
+    // producer's configuration.
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let audit = Arc::new(store.audit(&node_info, now).await?);
+    info!(target: COMPONENT, %audit, "Store audit succeeded");
 
-    let block_builder = DefaultBlockBuilder::new(store.clone(), state_view.clone());
+    let state_view = Arc::new(
+        DefaultStateView::new(store.clone())
+            .with_tag_policy(node_info.tag_policy.clone())
+            .with_account_cache(SERVER_ACCOUNT_CACHE_CAPACITY),
+    );
+
+    let dashboard = config
+        .dashboard
+        .endpoint
+        .as_ref()
+        .map(|_| Arc::new(Dashboard::new(SERVER_DASHBOARD_HISTORY)));
+
+    let notifier = if config.webhooks.urls.is_empty() {
+        None
+    } else {
+        let secret = config.webhooks.secret_file.as_deref().map(load_secret).transpose()?;
+        let options = NotifierOptions {
+            max_in_flight: SERVER_WEBHOOK_MAX_IN_FLIGHT,
+            timeout: SERVER_WEBHOOK_TIMEOUT,
+            max_backoff: SERVER_WEBHOOK_MAX_BACKOFF,
+        };
+        info!(target: COMPONENT, urls = ?config.webhooks.urls, signed = secret.is_some(), "Notifying webhooks");
+        Some(Arc::new(Notifier::new(&config.webhooks, secret, options)?))
+    };
+
+    let (tx_log, logged_submissions) = match &config.mempool.wal_path {
+        Some(path) => {
+            // the log is read and compacted off the runtime, as it is written afterwards
+            let opened = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || TransactionLog::open(&path)
+            })
+            .await?;
+            let (tx_log, submissions) = opened.with_context(|| {
+                format!("Failed to open the transaction log {}", path.display())
+            })?;
+            (Some(Arc::new(tx_log)), submissions)
+        },
+        None => (None, Vec::new()),
+    };
+
+    let block_builder_options = DefaultBlockBuilderOptions {
+        prover_threads: config.prover_threads,
+        prover_priority: config.prover_priority,
+        chain_id: config.chain_id,
+    };
+    let quarantine = Arc::new(BlockQuarantine::new(config.quarantine.clone()));
+    let latency = Arc::new(LatencyTracker::new(SERVER_LATENCY_WINDOW));
+    let mut block_builder = DefaultBlockBuilder::new(
+        store.clone(),
+        state_view.clone(),
+        quarantine.clone(),
+        latency.clone(),
+        block_builder_options,
+    )
+    .with_tx_ordering(config.tx_ordering);
+    if let Some(dashboard) = &dashboard {
+        block_builder = block_builder.with_dashboard(dashboard.clone());
+    }
+    if let Some(notifier) = &notifier {
+        block_builder = block_builder.with_notifier(notifier.clone());
+    }
+    if let Some(key_file) = &config.identity.key_file {
+        let keypair = load_key(key_file)?;
+        info!(target: COMPONENT, key = format_key(keypair.public_key()), "Signing blocks");
+        block_builder = block_builder.with_identity_key(Arc::new(keypair));
+    }
+    if let Some(url) = &config.remote_prover.url {
+        info!(target: COMPONENT, url, fallback_to_local = config.remote_prover.fallback_to_local, "Proving blocks remotely");
+        let mut remote_prover = RemoteBlockProver::new(url, config.remote_prover.timeout())?;
+        if config.remote_prover.fallback_to_local {
+            remote_prover = remote_prover.with_fallback(block_builder.prover());
+        }
+        block_builder = block_builder.with_prover(Arc::new(remote_prover));
+    }
+    if let Some(time_authority) =
+        TimeAuthority::from_config(&config.time_authority, config.chain_id)?
+    {
+        info!(target: COMPONENT, url = ?config.time_authority.url, authority_key = %time_authority.authority_key(), "Attesting block timestamps");
+        block_builder = block_builder.with_time_authority(Arc::new(time_authority));
+    }
+    if config.journal.max_size_mb > 0 {
+        info!(target: COMPONENT, path = ?config.journal.path, "Journaling block production");
+        block_builder =
+            block_builder.with_journal(Arc::new(BlockJournal::new(config.journal.clone())));
+    }
+    if let Some(tx_log) = &tx_log {
+        block_builder = block_builder.with_transaction_log(tx_log.clone());
+    }
+    let batching = config.batching;
     let batch_builder_options = DefaultBatchBuilderOptions {
-        block_frequency: SERVER_BLOCK_FREQUENCY,
-        max_batches_per_block: SERVER_MAX_BATCHES_PER_BLOCK,
+        block_frequency: batching.block_frequency(),
+        max_batches_per_block: batching.max_batches_per_block,
+        read_only_store_pause: SERVER_READ_ONLY_STORE_PAUSE,
     };
-    let batch_builder =
-        Arc::new(DefaultBatchBuilder::new(Arc::new(block_builder), batch_builder_options));
+    let mut batch_builder =
+        DefaultBatchBuilder::new(Arc::new(block_builder), latency.clone(), batch_builder_options);
+    if config.batch_proving.workers > 0 {
+        info!(target: COMPONENT, workers = config.batch_proving.workers, "Proving batches");
+        batch_builder = batch_builder.with_prover(Arc::new(LocalBatchProver::new(
+            config.batch_proving.workers,
+            config.prover_priority,
+        )));
+    }
+    let batch_builder = Arc::new(batch_builder);
+
+    let load_shedder_options = LoadShedderOptions {
+        check_frequency: SERVER_LOAD_CHECK_FREQUENCY,
+        // the batches of the next block are built while the current one is
+        max_concurrent_batches: 2 * batching.max_batches_per_block,
+        shedding_concurrent_batches: SERVER_SHEDDING_CONCURRENT_BATCHES,
+    };
+    let load_shedder = Arc::new(LoadShedder::new(config.load_shedding, load_shedder_options));
+
+    let pacer = Arc::new(BatchPacer::new(
+        config.adaptive_batching,
+        BatchPacerOptions {
+            check_frequency: batching.block_frequency(),
+            build_batch_frequency: batching.build_batch_frequency(),
+            max_batches_per_block: batching.max_batches_per_block,
+        },
+    ));
+
+    let reservations = Reservations::from_config(&config.reservations)?;
+    if !reservations.is_empty() {
+        info!(target: COMPONENT, reservations = %config.reservations, "Reserving block space for partners");
+    }
 
     let transaction_queue_options = TransactionQueueOptions {
-        build_batch_frequency: SERVER_BUILD_BATCH_FREQUENCY,
-        batch_size: SERVER_BATCH_SIZE,
+        build_batch_frequency: batching.build_batch_frequency(),
+        batch_size: batching.batch_size,
+        max_batch_latency: SERVER_MAX_BATCH_LATENCY,
+        reserved_consuming_percent: config.lanes.reserved_consuming_percent,
+        max_batch_attempts: SERVER_MAX_BATCH_ATTEMPTS,
+        dropped_history: SERVER_DROPPED_TX_HISTORY,
     };
-    let queue = Arc::new(TransactionQueue::new(
+    let mut queue = TransactionQueue::new(
         state_view,
         batch_builder.clone(),
+        load_shedder.clone(),
+        latency.clone(),
         transaction_queue_options,
-    ));
+    )
+    .with_eviction(config.eviction.max_queued_transactions, policy_from_config(&config.eviction))
+    .with_ttl(config.eviction.ttl_blocks)
+    .with_tx_kernel(config.tx_kernel)
+    .with_tx_ordering(config.tx_ordering)
+    .with_pacer(pacer.clone())
+    .with_reservations(reservations);
+    if let Some(notifier) = &notifier {
+        queue = queue.with_notifier(notifier.clone());
+    }
+    if let Some(tx_log) = &tx_log {
+        queue = queue.with_transaction_log(tx_log.clone());
+    }
+    let queue = Arc::new(queue);
+    // the snapshotted transactions were finished in the log, they are only admitted once
+    if let Some(tx_log) = &tx_log {
+        replay_transaction_log(&queue, tx_log, logged_submissions).await;
+    }
+    restore_snapshot(&queue, &config.restart.snapshot_path).await?;
+    let restart = Arc::new(RestartControl::new(&config.restart, batch_builder.clone()));
 
-    let block_producer = api_server::ApiServer::new(api::BlockProducerApi::new(queue.clone()));
+    let receipt_key = match &config.receipts.key_file {
+        Some(key_file) => {
+            let keypair = load_key(key_file)?;
+            info!(target: COMPONENT, key = format_key(keypair.public_key()), "Signing receipts");
+            Some(Arc::new(keypair))
+        },
+        None => None,
+    };
 
-    tokio::spawn(async move { queue.run().await });
-    tokio::spawn(async move { batch_builder.run().await });
+    let mut block_producer_api = api::BlockProducerApi::new(
+        queue.clone(),
+        quarantine,
+        latency,
+        receipt_key,
+        config.chain_id,
+        audit,
+        batch_builder.clone(),
+    );
+    if let Some(dashboard) = &dashboard {
+        block_producer_api = block_producer_api.with_dashboard(dashboard.clone());
+    }
+    if let Some(notifier) = notifier {
+        block_producer_api = block_producer_api.with_notifier(notifier);
+    }
+    block_producer_api = block_producer_api.with_restart(restart.clone());
+    if config.batch_proving.workers > 0 {
+        block_producer_api = block_producer_api.with_proven_batches();
+    }
+    let mut chain_id_validator = ChainIdValidator::new(config.chain_id);
+    let mut operator_authenticator = OperatorAuthenticator::new(config.chain_id, operator_keys);
+    let block_producer =
+        api_server::ApiServer::with_interceptor(block_producer_api, move |request| {
+            operator_authenticator.call(chain_id_validator.call(request)?)
+        });
+
+    // the background tasks stop along with the server, so the block producer can be started again
+    // in the same process, see `restart-producer`
+    let mut tasks = JoinSet::new();
+    if let (Some(dashboard), Some(endpoint)) = (dashboard, &config.dashboard.endpoint) {
+        let addr = endpoint
+            .to_socket_addrs()?
+            .next()
+            .ok_or(anyhow!("Couldn't resolve dashboard address"))?;
+        let dashboard_server = dashboard::server::bind(dashboard.clone(), addr)?;
+
+        let queue = queue.clone();
+        tasks.spawn(dashboard.sample_queue_depth(SERVER_DASHBOARD_SAMPLE_FREQUENCY, move || {
+            let queue = queue.clone();
+            async move {
+                (
+                    queue.num_queued_transactions_in_lane(Lane::Consuming).await,
+                    queue.num_queued_transactions_in_lane(Lane::Creating).await,
+                )
+            }
+        }));
+        tasks.spawn(async move {
+            if let Err(err) = dashboard_server.await {
+                error!(target: COMPONENT, %err, "Dashboard stopped");
+            }
+        });
+
+        info!(target: COMPONENT, %endpoint, "Dashboard served");
+    }
+
+    {
+        let queue = queue.clone();
+        let batch_builder = batch_builder.clone();
+        tasks.spawn(load_shedder.run(move || {
+            let queue = queue.clone();
+            let batch_builder = batch_builder.clone();
+            async move {
+                LoadSample {
+                    rss_bytes: resident_set_size(),
+                    queued_transactions: queue.num_queued_transactions().await,
+                    ready_batches: batch_builder.num_ready_batches().await,
+                }
+            }
+        }));
+    }
+    {
+        let batch_builder = batch_builder.clone();
+        tasks.spawn(pacer.run(move || {
+            let batch_builder = batch_builder.clone();
+            async move { batch_builder.num_ready_batches().await }
+        }));
+    }
+    tasks.spawn(async move { queue.run().await });
+    tasks.spawn(async move { batch_builder.run().await });
 
     info!(target: COMPONENT, "Server initialized");
 
-    let addr = config
-        .endpoint
-        .to_socket_addrs()?
-        .next()
-        .ok_or(anyhow!("Couldn't resolve server address"))?;
-    Server::builder().add_service(block_producer).serve(addr).await?;
+    let incoming = config.grpc.incoming(&config.endpoint, &config.listen)?;
+    let server = config.grpc.server_builder().add_service(block_producer);
+
+    tokio::select! {
+        result = server.serve_with_incoming_shutdown(incoming, restart.exit_requested()) => result?,
+        err = watch_node_info(store, config.chain_id, node_info.genesis_hash) => {
+            return Err(err.into())
+        },
+    }
+
+    info!(target: COMPONENT, "Server exited to be restarted");
+
+    Ok(())
+}
+
+/// Admits again the transactions of the snapshot at `snapshot_path` saved before the latest
+/// restart, if any, then removes the snapshot.
+///
+/// The transactions which are no longer valid, e.g. the ones included in a block after the
+/// snapshot was saved, are dropped.
+async fn restore_snapshot<BB: BatchBuilder, TV: TransactionVerifier>(
+    queue: &TransactionQueue<BB, TV>,
+    snapshot_path: &Path,
+) -> Result<()> {
+    let Some(submissions) = restart::load_snapshot(snapshot_path).with_context(|| {
+        format!("Failed to read the queue snapshot {}", snapshot_path.display())
+    })?
+    else {
+        return Ok(());
+    };
+
+    let num_txs = submissions.len();
+    let mut num_restored = 0;
+    for submission in submissions {
+        let tx_id = submission.tx.id();
+        match readmit(queue, submission).await {
+            Ok(_) => num_restored += 1,
+            Err(err) => {
+                warn!(target: COMPONENT, tx_id = %tx_id.to_hex(), %err, "Snapshotted transaction dropped");
+            },
+        }
+    }
+    restart::remove_snapshot(snapshot_path).with_context(|| {
+        format!("Failed to remove the queue snapshot {}", snapshot_path.display())
+    })?;
+
+    info!(target: COMPONENT, path = ?snapshot_path, num_txs, num_restored, "Queue snapshot restored");
 
     Ok(())
 }
+
+/// Admits again the `submissions` of the transaction log which were neither included in a block
+/// nor dropped before the block producer stopped.
+///
+/// The transactions which are no longer valid, e.g. the ones included in a block whose finished
+/// records were lost, are dropped and finished in the log.
+async fn replay_transaction_log<BB: BatchBuilder, TV: TransactionVerifier>(
+    queue: &TransactionQueue<BB, TV>,
+    tx_log: &TransactionLog,
+    submissions: Vec<QueuedSubmission>,
+) {
+    let num_txs = submissions.len();
+    let mut num_replayed = 0;
+    for submission in submissions {
+        let tx_id = submission.tx.id();
+        match readmit(queue, submission).await {
+            Ok(_) => num_replayed += 1,
+            Err(err) => {
+                warn!(target: COMPONENT, tx_id = %tx_id.to_hex(), %err, "Logged transaction dropped");
+                tx_log.finished([tx_id]).await;
+            },
+        }
+    }
+
+    info!(target: COMPONENT, num_txs, num_replayed, "Transaction log replayed");
+}
+
+/// Admits `submission` again, against the reservation of the partner which signed it if any.
+async fn readmit<BB: BatchBuilder, TV: TransactionVerifier>(
+    queue: &TransactionQueue<BB, TV>,
+    submission: QueuedSubmission,
+) -> Result<usize, AddTransactionError> {
+    match &submission.partner_signature {
+        Some(signature) => {
+            queue
+                .add_partner_transaction(
+                    submission.tx,
+                    submission.tx_kernel_version,
+                    submission.account_delta,
+                    submission.consumed_notes,
+                    submission.private_notes,
+                    signature,
+                )
+                .await
+        },
+        None => {
+            queue
+                .add_transaction(
+                    submission.tx,
+                    submission.tx_kernel_version,
+                    submission.account_delta,
+                    submission.consumed_notes,
+                    submission.private_notes,
+                )
+                .await
+        },
+    }
+}
+
+/// Loads a signing key from `key_file`, which holds the hex encoded seed of the key.
+fn load_key(key_file: &Path) -> Result<KeyPair> {
+    let seed = fs::read_to_string(key_file)
+        .with_context(|| format!("Failed to read key file {}", key_file.display()))?;
+    let seed = seed.trim();
+    let seed = hex::decode(seed.strip_prefix("0x").unwrap_or(seed))
+        .with_context(|| format!("Key file {} is not valid hex", key_file.display()))?;
+
+    KeyPair::from_seed(&seed)
+        .map_err(|err| anyhow!("Invalid key seed in {}: {err:?}", key_file.display()))
+}
+
+/// Loads the secret signing the webhook notifications from `secret_file`, ignoring the surrounding
+/// whitespace.
+fn load_secret(secret_file: &Path) -> Result<Vec<u8>> {
+    let secret = fs::read_to_string(secret_file)
+        .with_context(|| format!("Failed to read secret file {}", secret_file.display()))?;
+    let secret = secret.trim();
+    if secret.is_empty() {
+        return Err(anyhow!("Secret file {} is empty", secret_file.display()));
+    }
+
+    Ok(secret.as_bytes().to_vec())
+}
+
+/// Periodically verifies the store still serves the chain `chain_id` starting at `genesis_hash`.
+///
+/// Returns only once the verification failed, failures to reach the store are retried.
+async fn watch_node_info<S: StoreEndpoints>(
+    store: Arc<DefaultStore<S>>,
+    chain_id: u32,
+    genesis_hash: Digest,
+) -> NodeInfoError {
+    let mut interval = time::interval(SERVER_NODE_INFO_CHECK_FREQUENCY);
+
+    loop {
+        interval.tick().await;
+
+        match store.verify_node_info(chain_id, Some(genesis_hash)).await {
+            Ok(_) => (),
+            Err(NodeInfoError::GrpcClientError(err)) => {
+                warn!(target: COMPONENT, %err, "Failed to retrieve node info from the store");
+            },
+            Err(err) => return err,
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use miden_objects::{accounts::AccountId, Digest};
+
+// ACCOUNT HASH CACHE
+// ================================================================================================
+
+/// The latest known hash of the accounts, so a transaction executed against a stale state of its
+/// account is rejected without querying the store.
+///
+/// The hashes are learnt from the store when verifying a transaction, and from the blocks applied
+/// by the block producer, which is the only writer of the store. A hash read from the store may
+/// be outdated by the time it is cached, if a block updating the account was applied in between:
+/// every applied block starts a new epoch, and the hashes read during a previous epoch are
+/// discarded.
+///
+/// Once the cache is full, the oldest cached accounts are evicted first.
+#[derive(Debug)]
+pub struct AccountHashCache {
+    /// Map account id -> (account hash, sequence number of the insertion)
+    hashes: BTreeMap<AccountId, (Digest, u64)>,
+
+    /// The insertions in order, the entries whose sequence number doesn't match the one of the
+    /// account anymore were overwritten
+    insertions: VecDeque<(AccountId, u64)>,
+
+    /// Sequence number of the next insertion
+    next_seq: u64,
+
+    /// Maximum number of cached accounts
+    capacity: usize,
+
+    /// Number of blocks applied, or invalidated, since the creation of the cache
+    epoch: u64,
+}
+
+impl AccountHashCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            hashes: BTreeMap::new(),
+            insertions: VecDeque::new(),
+            next_seq: 0,
+            capacity,
+            epoch: 0,
+        }
+    }
+
+    /// Returns the latest known hash of the account, if cached. The hash of an account which isn't
+    /// cached must be read from the store, see [AccountHashCache::insert_from_store].
+    pub fn get(
+        &self,
+        account_id: &AccountId,
+    ) -> Option<Digest> {
+        self.hashes.get(account_id).map(|&(hash, _)| hash)
+    }
+
+    /// Returns the current epoch, to be given to [AccountHashCache::insert_from_store] along with
+    /// the hashes read from the store after this call.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Caches the hash of the account read from the store during `epoch`, the hash is discarded
+    /// if a block was applied since.
+    pub fn insert_from_store(
+        &mut self,
+        account_id: AccountId,
+        account_hash: Digest,
+        epoch: u64,
+    ) {
+        if epoch == self.epoch {
+            self.insert(account_id, account_hash);
+        }
+    }
+
+    /// Caches the final hashes of the accounts updated by an applied block, and starts a new
+    /// epoch.
+    pub fn apply_block<'a>(
+        &mut self,
+        updated_accounts: impl IntoIterator<Item = &'a (AccountId, Digest)>,
+    ) {
+        self.epoch += 1;
+        for &(account_id, account_hash) in updated_accounts {
+            self.insert(account_id, account_hash);
+        }
+    }
+
+    /// Drops the cached hashes of the accounts, and starts a new epoch.
+    ///
+    /// Used when the state of the accounts in the store is unknown, e.g. a block updating them
+    /// failed to be applied.
+    pub fn invalidate<'a>(
+        &mut self,
+        account_ids: impl IntoIterator<Item = &'a AccountId>,
+    ) {
+        self.epoch += 1;
+        for account_id in account_ids {
+            self.hashes.remove(account_id);
+        }
+    }
+
+    fn insert(
+        &mut self,
+        account_id: AccountId,
+        account_hash: Digest,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.hashes.insert(account_id, (account_hash, seq));
+        self.insertions.push_back((account_id, seq));
+
+        // evicts the oldest accounts, skipping the insertions which were overwritten since
+        while self.hashes.len() > self.capacity {
+            let Some((account_id, seq)) = self.insertions.pop_front() else {
+                break;
+            };
+            if self.hashes.get(&account_id).is_some_and(|&(_, current)| current == seq) {
+                self.hashes.remove(&account_id);
+            }
+        }
+
+        // the overwritten insertions are otherwise only dropped on eviction, an account updated
+        // by every block would grow the queue forever
+        if self.insertions.len() > 2 * self.capacity {
+            let hashes = &self.hashes;
+            self.insertions.retain(|(account_id, seq)| {
+                hashes.get(account_id).is_some_and(|&(_, current)| current == *seq)
+            });
+        }
+    }
+}
@@ -1,8 +1,20 @@
-use std::{collections::BTreeSet, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
 
 use async_trait::async_trait;
-use miden_node_utils::formatting::format_array;
-use miden_objects::{accounts::AccountId, notes::Nullifier, transaction::InputNotes, Digest};
+use miden_crypto::StarkField;
+use miden_node_utils::{formatting::format_array, tag_policy::TagPolicy};
+use miden_objects::{
+    accounts::{Account, AccountDelta, AccountId},
+    notes::Nullifier,
+    transaction::{InputNotes, TransactionId},
+    Digest,
+};
 use tokio::sync::RwLock;
 use tracing::{debug, instrument};
 
@@ -10,22 +22,37 @@ use crate::{
     block::Block,
     errors::VerifyTxError,
     store::{ApplyBlock, ApplyBlockError, Store, TxInputs},
-    txqueue::TransactionVerifier,
+    txqueue::{InFlight, TransactionVerifier},
     ProvenTransaction, COMPONENT,
 };
 
+mod account_cache;
+use account_cache::AccountHashCache;
+
 #[cfg(test)]
 mod tests;
 
 pub struct DefaultStateView<S> {
     store: Arc<S>,
 
-    /// The account ID of accounts being modified by transactions currently in the block production
-    /// pipeline. We currently ensure that only 1 tx/block modifies any given account (issue: #186).
-    accounts_in_flight: Arc<RwLock<BTreeSet<AccountId>>>,
+    /// The accounts being modified by transactions currently in the block production pipeline,
+    /// with these transactions in the order they were verified.
+    ///
+    /// The transactions of an account form a chain, each one starting from the final state of the
+    /// previous one, the last final hash being the state the next transaction must start from.
+    accounts_in_flight: Arc<RwLock<BTreeMap<AccountId, Vec<TxInFlight>>>>,
 
     /// The nullifiers of notes consumed by transactions currently in the block production pipeline.
     nullifiers_in_flight: Arc<RwLock<BTreeSet<Digest>>>,
+
+    /// Policy of the tags of the notes created by the transactions, as reported by the store.
+    tag_policy: TagPolicy,
+
+    /// The latest known hash of the accounts, if enabled, see [AccountHashCache].
+    account_hashes: Option<Arc<RwLock<AccountHashCache>>>,
+
+    /// Number of blocks applied since the state view was created.
+    num_applied_blocks: AtomicU32,
 }
 
 impl<S> DefaultStateView<S>
@@ -35,10 +62,46 @@ where
     pub fn new(store: Arc<S>) -> Self {
         Self {
             store,
-            accounts_in_flight: Arc::new(RwLock::new(BTreeSet::new())),
+            accounts_in_flight: Arc::new(RwLock::new(BTreeMap::new())),
             nullifiers_in_flight: Arc::new(RwLock::new(BTreeSet::new())),
+            tag_policy: TagPolicy::default(),
+            account_hashes: None,
+            num_applied_blocks: AtomicU32::new(0),
         }
     }
+
+    /// Rejects the transactions creating notes with tags outside of `tag_policy`, every tag is
+    /// accepted otherwise.
+    pub fn with_tag_policy(
+        mut self,
+        tag_policy: TagPolicy,
+    ) -> Self {
+        self.tag_policy = tag_policy;
+        self
+    }
+
+    /// Caches the latest known hash of up to `capacity` accounts, the transactions executed
+    /// against a stale state of their account are then rejected without querying the store.
+    pub fn with_account_cache(
+        mut self,
+        capacity: usize,
+    ) -> Self {
+        self.account_hashes = Some(Arc::new(RwLock::new(AccountHashCache::new(capacity))));
+        self
+    }
+}
+
+/// A transaction in the block production pipeline, modifying the state of its account.
+#[derive(Debug, Clone)]
+struct TxInFlight {
+    tx_id: TransactionId,
+
+    /// Hash of the state of the account once the transaction is applied
+    final_account_hash: Digest,
+
+    /// State of the account once the transaction is applied, if the account is public and the
+    /// state the transaction started from is known, see [ensure_account_delta_constraints]
+    final_account: Option<Account>,
 }
 
 #[async_trait]
@@ -52,38 +115,97 @@ where
     async fn verify_tx(
         &self,
         candidate_tx: &ProvenTransaction,
+        account_delta: Option<&AccountDelta>,
     ) -> Result<(), VerifyTxError> {
-        // 1. soft-check if `tx` violates in-flight requirements.
+        // 1. check the structural constraints of the accounts involved in `tx`, which don't depend
+        //    on any state
+        ensure_account_constraints(candidate_tx)?;
+        ensure_asset_constraints(candidate_tx, account_delta)?;
+        ensure_account_storage_constraints(candidate_tx, account_delta)?;
+        ensure_note_tag_constraints(candidate_tx, &self.tag_policy)?;
+
+        // 2. soft-check if `tx` violates in-flight requirements.
         //
         // This is a "soft" check, because we'll need to redo it at the end. We do this soft check
         // to quickly reject clearly infracting transactions before hitting the store (slow).
-        ensure_in_flight_constraints(
+        let in_flight_account_hash = ensure_in_flight_constraints(
             candidate_tx,
             &*self.accounts_in_flight.read().await,
             &*self.nullifiers_in_flight.read().await,
         )?;
 
-        // 2. Fetch the transaction inputs from the store, and check tx input constraints
-        let tx_inputs = self.store.get_tx_inputs(candidate_tx).await?;
+        // 3. check the initial account hash against the cached one, if any, unless the transaction
+        //    follows one in flight.
+        //
+        // The epoch is read before querying the store, so the hash read from it is only cached if
+        // no block was applied in between.
+        let cache_epoch = match &self.account_hashes {
+            Some(account_hashes) => {
+                let locked_account_hashes = account_hashes.read().await;
+                if in_flight_account_hash.is_none() {
+                    ensure_cached_account_constraints(candidate_tx, &locked_account_hashes)?;
+                }
+                Some(locked_account_hashes.epoch())
+            },
+            None => None,
+        };
+
+        // 4. Fetch the transaction inputs from the store, and check tx input constraints
+        //
+        // An account missing from the cache, e.g. evicted or unknown since a restart, falls back to
+        // the hash of the store, which is cached whether the transaction is accepted or not.
+        let mut tx_inputs = self.store.get_tx_inputs(candidate_tx).await?;
+        let store_account_hash = tx_inputs.account_hash;
+        let store_account = tx_inputs.account_details.take();
+
+        if let (Some(account_hashes), Some(epoch), Some(account_hash)) =
+            (&self.account_hashes, cache_epoch, store_account_hash)
+        {
+            account_hashes.write().await.insert_from_store(
+                candidate_tx.account_id(),
+                account_hash,
+                epoch,
+            );
+        }
+
         ensure_tx_inputs_constraints(candidate_tx, tx_inputs)?;
 
-        // 3. Re-check in-flight transaction constraints, and if verification passes, register
-        //    transaction
+        // 5. Re-check in-flight transaction constraints, check the account delta against the state
+        //    the transaction starts from, and if verification passes, register transaction
         //
         // Note: We need to re-check these constraints because we dropped the locks since we last
-        // checked
+        // checked. The transaction is checked against the account hash of the store only if it
+        // doesn't follow a transaction in flight, whose final state the store doesn't know yet.
         {
             let mut locked_accounts_in_flight = self.accounts_in_flight.write().await;
             let mut locked_nullifiers_in_flight = self.nullifiers_in_flight.write().await;
 
-            ensure_in_flight_constraints(
+            let in_flight_account_hash = ensure_in_flight_constraints(
                 candidate_tx,
                 &locked_accounts_in_flight,
                 &locked_nullifiers_in_flight,
             )?;
+            let initial_account = match in_flight_account_hash {
+                Some(_) => locked_accounts_in_flight
+                    .get(&candidate_tx.account_id())
+                    .and_then(|txs_in_flight| txs_in_flight.last())
+                    .and_then(|tx_in_flight| tx_in_flight.final_account.clone()),
+                None => {
+                    ensure_account_hash_constraints(candidate_tx, store_account_hash)?;
+                    store_account
+                },
+            };
+            let final_account =
+                ensure_account_delta_constraints(candidate_tx, account_delta, initial_account)?;
 
             // Success! Register transaction as successfully verified
-            locked_accounts_in_flight.insert(candidate_tx.account_id());
+            locked_accounts_in_flight.entry(candidate_tx.account_id()).or_default().push(
+                TxInFlight {
+                    tx_id: candidate_tx.id(),
+                    final_account_hash: candidate_tx.final_account_hash(),
+                    final_account,
+                },
+            );
 
             let mut nullifiers_in_tx: BTreeSet<_> =
                 candidate_tx.input_notes().iter().map(|nullifier| nullifier.inner()).collect();
@@ -92,6 +214,41 @@ where
 
         Ok(())
     }
+
+    async fn release_tx(
+        &self,
+        tx: &ProvenTransaction,
+    ) {
+        let mut locked_accounts_in_flight = self.accounts_in_flight.write().await;
+        let mut locked_nullifiers_in_flight = self.nullifiers_in_flight.write().await;
+
+        // the transactions following `tx` can't be applied without it, the next transaction of the
+        // account must start from the state `tx` started from
+        if let Some(txs_in_flight) = locked_accounts_in_flight.get_mut(&tx.account_id()) {
+            if let Some(position) =
+                txs_in_flight.iter().position(|tx_in_flight| tx_in_flight.tx_id == tx.id())
+            {
+                txs_in_flight.truncate(position);
+            }
+            if txs_in_flight.is_empty() {
+                locked_accounts_in_flight.remove(&tx.account_id());
+            }
+        }
+        for nullifier in tx.input_notes().iter() {
+            locked_nullifiers_in_flight.remove(&nullifier.inner());
+        }
+    }
+
+    fn num_applied_blocks(&self) -> u32 {
+        self.num_applied_blocks.load(Ordering::Relaxed)
+    }
+
+    async fn in_flight(&self) -> InFlight {
+        InFlight {
+            num_accounts: self.accounts_in_flight.read().await.len(),
+            num_nullifiers: self.nullifiers_in_flight.read().await.len(),
+        }
+    }
 }
 
 #[async_trait]
@@ -103,22 +260,62 @@ where
     #[instrument(target = "miden-block-producer", skip_all, err)]
     async fn apply_block(
         &self,
-        block: Block,
+        mut block: Block,
     ) -> Result<(), ApplyBlockError> {
-        self.store.apply_block(block.clone()).await?;
+        // the states of the public accounts updated by the block are published by the store, the
+        // transactions of the block are still in flight until it is applied
+        block.account_details = {
+            let locked_accounts_in_flight = self.accounts_in_flight.read().await;
+            block
+                .updated_accounts
+                .iter()
+                .filter_map(|(account_id, final_account_hash)| {
+                    locked_accounts_in_flight
+                        .get(account_id)?
+                        .iter()
+                        .find(|tx_in_flight| {
+                            tx_in_flight.final_account_hash == *final_account_hash
+                        })?
+                        .final_account
+                        .clone()
+                })
+                .collect()
+        };
+
+        if let Err(err) = self.store.apply_block(block.clone()).await {
+            // the block may have been applied by the store regardless, the state of its accounts is
+            // unknown
+            if let Some(account_hashes) = &self.account_hashes {
+                account_hashes
+                    .write()
+                    .await
+                    .invalidate(block.updated_accounts.iter().map(|(account_id, _)| account_id));
+            }
+            return Err(err);
+        }
+
+        if let Some(account_hashes) = &self.account_hashes {
+            account_hashes.write().await.apply_block(&block.updated_accounts);
+        }
 
         let mut locked_accounts_in_flight = self.accounts_in_flight.write().await;
         let mut locked_nullifiers_in_flight = self.nullifiers_in_flight.write().await;
 
-        // 1. Remove account ids of transactions in block
-        let account_ids_in_block = block
-            .updated_accounts
-            .iter()
-            .map(|(account_id, _final_account_hash)| account_id);
-
-        for account_id in account_ids_in_block {
-            let was_in_flight = locked_accounts_in_flight.remove(account_id);
-            debug_assert!(was_in_flight);
+        // 1. Remove the transactions in block, up to the one which updated the account to its final
+        //    state. The transactions following it are still in flight.
+        for (account_id, final_account_hash) in block.updated_accounts.iter() {
+            let Some(txs_in_flight) = locked_accounts_in_flight.get_mut(account_id) else {
+                debug_assert!(false, "account of the block not in flight");
+                continue;
+            };
+            let position = txs_in_flight
+                .iter()
+                .position(|tx_in_flight| tx_in_flight.final_account_hash == *final_account_hash);
+            debug_assert!(position.is_some());
+            txs_in_flight.drain(..=position.unwrap_or(txs_in_flight.len() - 1));
+            if txs_in_flight.is_empty() {
+                locked_accounts_in_flight.remove(account_id);
+            }
         }
 
         // 2. Remove new nullifiers of transactions in block
@@ -127,6 +324,8 @@ where
             debug_assert!(was_in_flight);
         }
 
+        self.num_applied_blocks.fetch_add(1, Ordering::Relaxed);
+
         Ok(())
     }
 }
@@ -134,21 +333,120 @@ where
 // HELPERS
 // -------------------------------------------------------------------------------------------------
 
+/// Ensures the constraints related to the type of the accounts involved in the transaction:
+/// 1. all the notes created by the candidate transaction are sent by the account executing it. In
+///    particular, notes can't claim to be sent by a faucet unless the faucet created them.
+///
+/// That only faucets mint assets is checked by [ensure_asset_constraints].
+#[instrument(target = "miden-block-producer", skip_all, err)]
+fn ensure_account_constraints(candidate_tx: &ProvenTransaction) -> Result<(), VerifyTxError> {
+    let account_id = candidate_tx.account_id();
+
+    if let Some(note) = candidate_tx
+        .output_notes()
+        .iter()
+        .find(|note| note.metadata().sender() != account_id)
+    {
+        let sender = note.metadata().sender();
+        return Err(VerifyTxError::OutputNoteSenderMismatch {
+            account_id,
+            account_type: account_id.account_type(),
+            note_id: note.note_id().inner(),
+            sender,
+            sender_type: sender.account_type(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Ensures only faucets mint assets:
+/// 1. a transaction of a regular account consuming no note doesn't add assets to the vault of the
+///    account, the assets it receives can only come from the notes it consumes
+///
+/// The assets of the notes, consumed or created, are not part of the [ProvenTransaction]: only the
+/// vault of the public accounts is checked, through their `account_delta`.
+#[instrument(target = "miden-block-producer", skip_all, err)]
+fn ensure_asset_constraints(
+    candidate_tx: &ProvenTransaction,
+    account_delta: Option<&AccountDelta>,
+) -> Result<(), VerifyTxError> {
+    let account_id = candidate_tx.account_id();
+    if account_id.is_faucet() || !candidate_tx.input_notes().is_empty() {
+        return Ok(());
+    }
+
+    match account_delta.and_then(|delta| delta.vault().added_assets.first()) {
+        Some(asset) => Err(VerifyTxError::AssetsMintedByRegularAccount {
+            account_id,
+            account_type: account_id.account_type(),
+            faucet_id: asset.faucet_id(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Ensures the constraints related to the storage mode of the account executing the transaction,
+/// which is encoded in its id:
+/// 1. transactions of public accounts include the account delta, so the state of the account can
+///    be published
+/// 2. transactions of private accounts don't include one, their account is only tracked by the
+///    hash of its state
+#[instrument(target = "miden-block-producer", skip_all, err)]
+fn ensure_account_storage_constraints(
+    candidate_tx: &ProvenTransaction,
+    account_delta: Option<&AccountDelta>,
+) -> Result<(), VerifyTxError> {
+    let account_id = candidate_tx.account_id();
+
+    match (account_id.is_on_chain(), account_delta) {
+        (true, None) => Err(VerifyTxError::PublicAccountDeltaMissing(account_id)),
+        (false, Some(_)) => Err(VerifyTxError::PrivateAccountDeltaProvided(account_id)),
+        _ => Ok(()),
+    }
+}
+
+/// Ensures the tags of the notes created by the candidate transaction are accepted by the
+/// `tag_policy` of the store, which would otherwise reject the block.
+#[instrument(target = "miden-block-producer", skip_all, err)]
+fn ensure_note_tag_constraints(
+    candidate_tx: &ProvenTransaction,
+    tag_policy: &TagPolicy,
+) -> Result<(), VerifyTxError> {
+    for note in candidate_tx.output_notes().iter() {
+        let tag = note.metadata().tag().as_int();
+        if tag_policy.check(tag).is_err() {
+            return Err(VerifyTxError::OutputNoteTagNotAllowed {
+                note_id: note.note_id().inner(),
+                tag,
+            });
+        }
+    }
+
+    Ok(())
+}
+
 /// Ensures the constraints related to in-flight transactions:
-/// 1. the candidate transaction doesn't modify the same account as an existing in-flight
-///    transaction (issue: #186)
+/// 1. if the account of the candidate transaction is modified by in-flight transactions, the
+///    candidate transaction starts from the final state of the last of them
 /// 2. no consumed note's nullifier in candidate tx's consumed notes is already contained in
 ///    `already_consumed_nullifiers`
+///
+/// Returns the final account hash of the last in-flight transaction of the account, if any.
 #[instrument(target = "miden-block-producer", skip_all, err)]
 fn ensure_in_flight_constraints(
     candidate_tx: &ProvenTransaction,
-    accounts_in_flight: &BTreeSet<AccountId>,
+    accounts_in_flight: &BTreeMap<AccountId, Vec<TxInFlight>>,
     already_consumed_nullifiers: &BTreeSet<Digest>,
-) -> Result<(), VerifyTxError> {
-    debug!(target: COMPONENT, accounts_in_flight = %format_array(accounts_in_flight), already_consumed_nullifiers = %format_array(already_consumed_nullifiers));
+) -> Result<Option<Digest>, VerifyTxError> {
+    debug!(target: COMPONENT, accounts_in_flight = %format_array(accounts_in_flight.keys()), already_consumed_nullifiers = %format_array(already_consumed_nullifiers));
 
-    // 1. Check account id hasn't been modified yet
-    if accounts_in_flight.contains(&candidate_tx.account_id()) {
+    // 1. Check the transaction follows the in-flight transactions of its account
+    let in_flight_account_hash = accounts_in_flight
+        .get(&candidate_tx.account_id())
+        .and_then(|txs_in_flight| txs_in_flight.last())
+        .map(|tx_in_flight| tx_in_flight.final_account_hash);
+    if in_flight_account_hash.is_some_and(|hash| hash != candidate_tx.initial_account_hash()) {
         return Err(VerifyTxError::AccountAlreadyModifiedByOtherTx(candidate_tx.account_id()));
     }
 
@@ -170,7 +468,25 @@ fn ensure_in_flight_constraints(
         )?));
     }
 
-    Ok(())
+    Ok(in_flight_account_hash)
+}
+
+/// Ensures the initial account hash of the candidate transaction matches the latest known hash of
+/// its account, if cached.
+#[instrument(target = "miden-block-producer", skip_all, err)]
+fn ensure_cached_account_constraints(
+    candidate_tx: &ProvenTransaction,
+    account_hashes: &AccountHashCache,
+) -> Result<(), VerifyTxError> {
+    match account_hashes.get(&candidate_tx.account_id()) {
+        Some(cached_account_hash) if cached_account_hash != candidate_tx.initial_account_hash() => {
+            Err(VerifyTxError::IncorrectAccountInitialHash {
+                tx_initial_account_hash: candidate_tx.initial_account_hash(),
+                store_account_hash: Some(cached_account_hash),
+            })
+        },
+        _ => Ok(()),
+    }
 }
 
 #[instrument(target = "miden-block-producer", skip_all, err)]
@@ -180,21 +496,24 @@ fn ensure_tx_inputs_constraints(
 ) -> Result<(), VerifyTxError> {
     debug!(target: COMPONENT, %tx_inputs);
 
-    match tx_inputs.account_hash {
-        // if the account is present in the Store, make sure that the account state hash
-        // from the received transaction is the same as the one from the Store
-        Some(store_account_hash) => {
-            if candidate_tx.initial_account_hash() != store_account_hash {
-                return Err(VerifyTxError::IncorrectAccountInitialHash {
-                    tx_initial_account_hash: candidate_tx.initial_account_hash(),
-                    store_account_hash: Some(store_account_hash),
-                });
-            }
+    // the notes consumed by the transaction are at most as recent as the block it was executed
+    // against, if the block is older than the pruning horizon so are the notes. A block unknown to
+    // a store retaining the whole chain can't have been pruned
+    match tx_inputs.block_ref_num {
+        Some(block_ref_num) if block_ref_num < tx_inputs.pruning_horizon => {
+            return Err(VerifyTxError::ReferenceBlockPruned {
+                block_ref: candidate_tx.block_ref(),
+                block_ref_num,
+                pruning_horizon: tx_inputs.pruning_horizon,
+            });
         },
-        // if the account is not present in the Store, it must be a new account
-        None => {
-            // TODO: check if the transaction was executed against new account
+        None if tx_inputs.pruning_horizon > 0 => {
+            return Err(VerifyTxError::ReferenceBlockUnknown {
+                block_ref: candidate_tx.block_ref(),
+                pruning_horizon: tx_inputs.pruning_horizon,
+            });
         },
+        _ => (),
     }
 
     let infracting_nullifiers: Vec<Nullifier> = tx_inputs
@@ -212,3 +531,72 @@ fn ensure_tx_inputs_constraints(
 
     Ok(())
 }
+
+/// Ensures the account delta of the candidate transaction, if any, updates the state its account
+/// starts from, `initial_account`, to the final state the transaction commits to:
+/// 1. the delta applies to the initial state of the account
+/// 2. the hash of the updated state is the final account hash of the transaction
+///
+/// Returns the final state of the account, which the store publishes once the transaction is in a
+/// block. The delta can't be checked if the initial state of the account is unknown, e.g. for a
+/// public account created after the genesis block, the account is then only tracked by its hash.
+#[instrument(target = "miden-block-producer", skip_all, err)]
+fn ensure_account_delta_constraints(
+    candidate_tx: &ProvenTransaction,
+    account_delta: Option<&AccountDelta>,
+    initial_account: Option<Account>,
+) -> Result<Option<Account>, VerifyTxError> {
+    let account_id = candidate_tx.account_id();
+    let Some(account_delta) = account_delta else {
+        return Ok(None);
+    };
+    let Some(mut account) =
+        initial_account.filter(|account| account.hash() == candidate_tx.initial_account_hash())
+    else {
+        debug!(target: COMPONENT, %account_id, "Initial state of the account unknown, account delta not checked");
+        return Ok(None);
+    };
+
+    account
+        .apply_delta(account_delta)
+        .map_err(|err| VerifyTxError::InvalidAccountDelta {
+            account_id,
+            error: format!("{err:?}"),
+        })?;
+    if account.hash() != candidate_tx.final_account_hash() {
+        return Err(VerifyTxError::AccountDeltaMismatch {
+            account_id,
+            tx_final_account_hash: candidate_tx.final_account_hash(),
+            delta_account_hash: account.hash(),
+        });
+    }
+
+    Ok(Some(account))
+}
+
+/// Ensures the initial account hash of the candidate transaction matches the hash of its account
+/// in the store, as returned with the transaction inputs.
+#[instrument(target = "miden-block-producer", skip_all, err)]
+fn ensure_account_hash_constraints(
+    candidate_tx: &ProvenTransaction,
+    store_account_hash: Option<Digest>,
+) -> Result<(), VerifyTxError> {
+    match store_account_hash {
+        // if the account is present in the Store, make sure that the account state hash
+        // from the received transaction is the same as the one from the Store
+        Some(store_account_hash) => {
+            if candidate_tx.initial_account_hash() != store_account_hash {
+                return Err(VerifyTxError::IncorrectAccountInitialHash {
+                    tx_initial_account_hash: candidate_tx.initial_account_hash(),
+                    store_account_hash: Some(store_account_hash),
+                });
+            }
+        },
+        // if the account is not present in the Store, it must be a new account
+        None => {
+            // TODO: check if the transaction was executed against new account
+        },
+    }
+
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Requirements for the account hash cache:
+//!
+//! AC1: a transaction executed against a stale state of a cached account is rejected without
+//!      querying the store
+//! AC2: the hashes read from the store before a block was applied are not cached
+//! AC3: the cached hashes of the accounts of a block which failed to be applied are dropped
+//! AC4: once full, the oldest cached accounts are evicted first
+//! AC5: a transaction of an account missing from the cache, e.g. evicted, is checked against the
+//!      store, whose hash is cached
+
+use std::iter;
+
+use miden_objects::transaction::{InputNotes, OutputNotes};
+
+use super::*;
+use crate::test_utils::{MockBlockBuilder, MockStoreSuccessBuilder};
+
+/// Tests requirement AC1
+#[tokio::test]
+async fn test_account_cache_ac1() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account: MockPrivateAccount<3> = MockPrivateAccount::from(0);
+
+    let store = Arc::new(
+        MockStoreSuccessBuilder::new()
+            .initial_accounts(iter::once((account.id, account.states[0])))
+            .build(),
+    );
+    let state_view = DefaultStateView::new(store.clone()).with_account_cache(16);
+
+    let tx = |from: usize| {
+        tx_gen.dummy_proven_tx_with_params(
+            account.id,
+            account.states[from],
+            account.states[from + 1],
+            InputNotes::new(Vec::new()).unwrap(),
+            OutputNotes::new(Vec::new()).unwrap(),
+        )
+    };
+
+    state_view.verify_tx(&tx(0), None).await.unwrap();
+    assert_eq!(*store.num_get_tx_inputs_called.read().await, 1);
+
+    let block = MockBlockBuilder::new(&store)
+        .await
+        .account_updates(vec![(account.id, account.states[1])])
+        .build();
+    state_view.apply_block(block).await.unwrap();
+
+    // the same transaction, submitted again once applied, is rejected by the cache
+    let verify_tx_result = state_view.verify_tx(&tx(0), None).await;
+    assert_eq!(
+        verify_tx_result,
+        Err(VerifyTxError::IncorrectAccountInitialHash {
+            tx_initial_account_hash: account.states[0],
+            store_account_hash: Some(account.states[1]),
+        })
+    );
+    assert_eq!(*store.num_get_tx_inputs_called.read().await, 1);
+
+    // the next transaction of the account still goes through the store
+    state_view.verify_tx(&tx(1), None).await.unwrap();
+    assert_eq!(*store.num_get_tx_inputs_called.read().await, 2);
+}
+
+/// Tests requirement AC2
+#[test]
+fn test_account_cache_ac2() {
+    let account: MockPrivateAccount<3> = MockPrivateAccount::from(0);
+    let mut cache = AccountHashCache::new(16);
+
+    // the hash is read from the store, while a block updating the account is applied
+    let epoch = cache.epoch();
+    cache.apply_block(&[(account.id, account.states[1])]);
+    cache.insert_from_store(account.id, account.states[0], epoch);
+
+    assert_eq!(cache.get(&account.id), Some(account.states[1]));
+
+    // a hash read after the block was applied is cached
+    let epoch = cache.epoch();
+    cache.insert_from_store(account.id, account.states[2], epoch);
+
+    assert_eq!(cache.get(&account.id), Some(account.states[2]));
+}
+
+/// Tests requirement AC3
+#[test]
+fn test_account_cache_ac3() {
+    let accounts: Vec<MockPrivateAccount<3>> = (0..2).map(MockPrivateAccount::from).collect();
+    let mut cache = AccountHashCache::new(16);
+
+    cache.apply_block(&[(accounts[0].id, accounts[0].states[0])]);
+    cache.apply_block(&[(accounts[1].id, accounts[1].states[0])]);
+
+    // the hash read from the store before the invalidation may predate the failed block
+    let epoch = cache.epoch();
+    cache.invalidate(iter::once(&accounts[0].id));
+    cache.insert_from_store(accounts[0].id, accounts[0].states[0], epoch);
+
+    assert_eq!(cache.get(&accounts[0].id), None);
+    assert_eq!(cache.get(&accounts[1].id), Some(accounts[1].states[0]));
+}
+
+/// Tests requirement AC4
+#[test]
+fn test_account_cache_ac4() {
+    let accounts: Vec<MockPrivateAccount<3>> = (0..3).map(MockPrivateAccount::from).collect();
+    let mut cache = AccountHashCache::new(2);
+
+    cache.apply_block(&[(accounts[0].id, accounts[0].states[0])]);
+    cache.apply_block(&[(accounts[1].id, accounts[1].states[0])]);
+
+    // updating the first account makes the second one the oldest
+    cache.apply_block(&[(accounts[0].id, accounts[0].states[1])]);
+    cache.apply_block(&[(accounts[2].id, accounts[2].states[0])]);
+
+    assert_eq!(cache.get(&accounts[0].id), Some(accounts[0].states[1]));
+    assert_eq!(cache.get(&accounts[1].id), None);
+    assert_eq!(cache.get(&accounts[2].id), Some(accounts[2].states[0]));
+}
+
+/// Tests requirement AC5
+#[tokio::test]
+async fn test_account_cache_ac5() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let accounts: Vec<MockPrivateAccount<3>> = (0..2).map(MockPrivateAccount::from).collect();
+
+    let store = Arc::new(
+        MockStoreSuccessBuilder::new()
+            .initial_accounts(accounts.iter().map(|account| (account.id, account.states[0])))
+            .build(),
+    );
+    let state_view = DefaultStateView::new(store.clone()).with_account_cache(1);
+
+    let tx = |account: &MockPrivateAccount<3>, from: usize| {
+        tx_gen.dummy_proven_tx_with_params(
+            account.id,
+            account.states[from],
+            account.states[from + 1],
+            InputNotes::new(Vec::new()).unwrap(),
+            OutputNotes::new(Vec::new()).unwrap(),
+        )
+    };
+
+    // the second block evicts the first account from the cache
+    for account in &accounts {
+        state_view.verify_tx(&tx(account, 0), None).await.unwrap();
+        let block = MockBlockBuilder::new(&store)
+            .await
+            .account_updates(vec![(account.id, account.states[1])])
+            .build();
+        state_view.apply_block(block).await.unwrap();
+    }
+    assert_eq!(*store.num_get_tx_inputs_called.read().await, 2);
+
+    // a stale transaction of the evicted account is rejected by the store
+    let stale_tx = tx(&accounts[0], 0);
+    let expected_err = Err(VerifyTxError::IncorrectAccountInitialHash {
+        tx_initial_account_hash: accounts[0].states[0],
+        store_account_hash: Some(accounts[0].states[1]),
+    });
+    assert_eq!(state_view.verify_tx(&stale_tx, None).await, expected_err);
+    assert_eq!(*store.num_get_tx_inputs_called.read().await, 3);
+
+    // the hash read from the store is cached
+    assert_eq!(state_view.verify_tx(&stale_tx, None).await, expected_err);
+    assert_eq!(*store.num_get_tx_inputs_called.read().await, 3);
+}
@@ -3,13 +3,14 @@
 //! AB1: the internal store's `apply_block` is called once
 //! AB2: All accounts modified by transactions in the block are removed from the internal state
 //! AB3: All consumed notes by some transaction in the block are still not consumable after `apply_block`
+//! AB4: The transactions following the ones in the block for their account are still in flight
 
 use std::iter;
 
 use miden_objects::transaction::{InputNotes, OutputNotes};
 
 use super::*;
-use crate::test_utils::{block::MockBlockBuilder, MockStoreSuccessBuilder};
+use crate::test_utils::{MockBlockBuilder, MockStoreSuccessBuilder};
 
 /// Tests requirement AB1
 #[tokio::test]
@@ -34,8 +35,9 @@ async fn test_apply_block_ab1() {
     let state_view = DefaultStateView::new(store.clone());
 
     // Verify transaction so it can be tracked in state view
-    let verify_tx_res = state_view.verify_tx(&tx).await;
+    let verify_tx_res = state_view.verify_tx(&tx, None).await;
     assert!(verify_tx_res.is_ok());
+    assert_eq!(state_view.in_flight().await.num_accounts, 1);
 
     let block = MockBlockBuilder::new(&store)
         .await
@@ -50,6 +52,8 @@ async fn test_apply_block_ab1() {
     assert!(apply_block_res.is_ok());
 
     assert_eq!(*store.num_apply_block_called.read().await, 1);
+    assert_eq!(state_view.num_applied_blocks(), 1);
+    assert_eq!(state_view.in_flight().await, InFlight::default());
 }
 
 /// Tests requirement AB2
@@ -74,7 +78,7 @@ async fn test_apply_block_ab2() {
 
     // Verify transactions so it can be tracked in state view
     for tx in txs {
-        let verify_tx_res = state_view.verify_tx(&tx).await;
+        let verify_tx_res = state_view.verify_tx(&tx, None).await;
         assert!(verify_tx_res.is_ok());
     }
 
@@ -98,7 +102,7 @@ async fn test_apply_block_ab2() {
 
     // Only the first account should still be in flight
     assert_eq!(accounts_still_in_flight.len(), 1);
-    assert!(accounts_still_in_flight.contains(&accounts[0].id));
+    assert!(accounts_still_in_flight.contains_key(&accounts[0].id));
 }
 
 /// Tests requirement AB3
@@ -123,7 +127,7 @@ async fn test_apply_block_ab3() {
 
     // Verify transactions so it can be tracked in state view
     for tx in txs.clone() {
-        let verify_tx_res = state_view.verify_tx(&tx).await;
+        let verify_tx_res = state_view.verify_tx(&tx, None).await;
         assert!(verify_tx_res.is_ok());
     }
 
@@ -151,9 +155,60 @@ async fn test_apply_block_ab3() {
         OutputNotes::new(Vec::new()).unwrap(),
     );
 
-    let verify_tx_res = state_view.verify_tx(&tx_new).await;
+    let verify_tx_res = state_view.verify_tx(&tx_new, None).await;
     assert_eq!(
         verify_tx_res,
         Err(VerifyTxError::InputNotesAlreadyConsumed(txs[0].input_notes().clone()))
     );
 }
+
+/// Tests requirement AB4
+#[tokio::test]
+async fn test_apply_block_ab4() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account: MockPrivateAccount<3> = MockPrivateAccount::from(0);
+
+    let store = Arc::new(
+        MockStoreSuccessBuilder::new()
+            .initial_accounts(iter::once((account.id, account.states[0])))
+            .build(),
+    );
+
+    let tx1 = tx_gen.dummy_proven_tx_with_params(
+        account.id,
+        account.states[0],
+        account.states[1],
+        InputNotes::new(Vec::new()).unwrap(),
+        OutputNotes::new(Vec::new()).unwrap(),
+    );
+    let tx2 = tx_gen.dummy_proven_tx_with_params(
+        account.id,
+        account.states[1],
+        account.states[2],
+        InputNotes::new(Vec::new()).unwrap(),
+        OutputNotes::new(Vec::new()).unwrap(),
+    );
+
+    let state_view = DefaultStateView::new(store.clone());
+    assert!(state_view.verify_tx(&tx1, None).await.is_ok());
+    assert!(state_view.verify_tx(&tx2, None).await.is_ok());
+
+    // Only tx1 goes into the block
+    let block = MockBlockBuilder::new(&store)
+        .await
+        .account_updates(vec![(account.id, account.states[1])])
+        .build();
+
+    let apply_block_res = state_view.apply_block(block).await;
+    assert!(apply_block_res.is_ok());
+
+    let accounts_still_in_flight = state_view.accounts_in_flight.read().await;
+    let txs_still_in_flight: Option<Vec<_>> =
+        accounts_still_in_flight.get(&account.id).map(|txs_in_flight| {
+            txs_in_flight
+                .iter()
+                .map(|tx_in_flight| (tx_in_flight.tx_id, tx_in_flight.final_account_hash))
+                .collect()
+        });
+    assert_eq!(txs_still_in_flight, Some(vec![(tx2.id(), account.states[2])]));
+}
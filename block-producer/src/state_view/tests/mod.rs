@@ -1,29 +1,15 @@
-use miden_crypto::ZERO;
-use miden_objects::{notes::Nullifier, transaction::OutputNotes, Hasher, EMPTY_WORD};
+use miden_objects::transaction::OutputNotes;
 
 use super::*;
-use crate::test_utils::{DummyProvenTxGenerator, MockPrivateAccount};
+use crate::test_utils::{nullifier_by_index, DummyProvenTxGenerator, MockPrivateAccount};
 
+mod account_cache;
 mod apply_block;
 mod verify_tx;
 
 // HELPERS
 // -------------------------------------------------------------------------------------------------
 
-pub fn nullifier_by_index(index: u32) -> Nullifier {
-    Nullifier::new(
-        Hasher::hash(&index.to_be_bytes()),
-        Hasher::hash(
-            &[index.to_be_bytes(), index.to_be_bytes()]
-                .into_iter()
-                .flatten()
-                .collect::<Vec<_>>(),
-        ),
-        EMPTY_WORD.into(),
-        [ZERO, ZERO, ZERO, index.into()],
-    )
-}
-
 /// Returns `num` transactions, and the corresponding account they modify.
 /// The transactions each consume a single different note
 pub fn get_txs_and_accounts(
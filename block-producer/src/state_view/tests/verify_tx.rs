@@ -6,17 +6,55 @@
 //! VT3: If `tx` consumes an already-consumed note in the store, `verify_tx` must fail
 //!
 //! in-flight related requirements
-//! VT4: In each block, at most 1 transaction is allowed to modify any given account
+//! VT4: A transaction modifying an account already modified by transactions in flight must start
+//!      from the final state of the last of them
 //! VT5: `verify_tx(tx)` must fail if a previous transaction, not yet in the block, consumed a note
 //!      that `tx` is also consuming
+//!
+//! Account-related requirements
+//! VT6: `verify_tx(tx)` must fail if a note created by `tx` is sent by another account than
+//!      `tx.account_id`, e.g. a regular account pretending to distribute a faucet's assets
+//!
+//! Retention-related requirements
+//! VT7: `verify_tx(tx)` must fail if `tx` was executed against a block older than the store's
+//!      pruning horizon, since the notes it consumes can no longer be authenticated
+//!
+//! Note-related requirements
+//! VT8: `verify_tx(tx)` must fail if a note created by `tx` has a tag outside of the store's tag
+//!      policy
+//!
+//! Storage mode related requirements
+//! VT9: `verify_tx(tx)` must fail if `tx` modifies a public account without including the account
+//!      delta, or a private account with an account delta
+//! VT10: `verify_tx(tx)` must fail if the account delta of `tx` doesn't update the state of the
+//!       account `tx` starts from, as known to the store or following the transactions in flight,
+//!       to the final state of `tx`
+//!
+//! Asset-related requirements
+//! VT11: `verify_tx(tx)` must fail if `tx` of a regular account adds assets to the vault of the
+//!       account without consuming any note, only faucets mint assets
 
 use std::iter;
 
-use miden_objects::transaction::{InputNotes, OutputNotes};
+use miden_lib::transaction::TransactionKernel;
+use miden_mock::{
+    constants::{
+        ACCOUNT_ID_FUNGIBLE_FAUCET_ON_CHAIN, ACCOUNT_ID_REGULAR_ACCOUNT_UPDATABLE_CODE_ON_CHAIN,
+    },
+    mock::account::mock_account,
+};
+use miden_node_utils::tag_policy::{TagPolicy, TagRange};
+use miden_objects::{
+    accounts::{AccountStorageDelta, AccountType, AccountVaultDelta},
+    assets::FungibleAsset,
+    notes::{NoteEnvelope, NoteMetadata},
+    transaction::{InputNotes, OutputNotes},
+    Felt, Hasher, ONE,
+};
 use tokio::task::JoinSet;
 
 use super::*;
-use crate::test_utils::MockStoreSuccessBuilder;
+use crate::test_utils::{MockBlockBuilder, MockProvenTxBuilder, MockStoreSuccessBuilder};
 
 /// Tests the happy path where 3 transactions who modify different accounts and consume different
 /// notes all verify successfully
@@ -39,8 +77,15 @@ async fn test_verify_tx_happy_path() {
     let state_view = DefaultStateView::new(store);
 
     for tx in txs {
-        state_view.verify_tx(&tx).await.unwrap();
+        state_view.verify_tx(&tx, None).await.unwrap();
     }
+    assert_eq!(
+        state_view.in_flight().await,
+        InFlight {
+            num_accounts: 3,
+            num_nullifiers: 3
+        }
+    );
 }
 
 /// Tests the happy path where 3 transactions who modify different accounts and consume different
@@ -69,7 +114,7 @@ async fn test_verify_tx_happy_path_concurrent() {
 
     for tx in txs {
         let state_view = state_view.clone();
-        set.spawn(async move { state_view.verify_tx(&tx).await });
+        set.spawn(async move { state_view.verify_tx(&tx, None).await });
     }
 
     while let Some(res) = set.join_next().await {
@@ -102,7 +147,7 @@ async fn test_verify_tx_vt1() {
 
     let state_view = DefaultStateView::new(store);
 
-    let verify_tx_result = state_view.verify_tx(&tx).await;
+    let verify_tx_result = state_view.verify_tx(&tx, None).await;
 
     assert_eq!(
         verify_tx_result,
@@ -134,7 +179,7 @@ async fn test_verify_tx_vt2() {
 
     let state_view = DefaultStateView::new(store);
 
-    let verify_tx_result = state_view.verify_tx(&tx).await;
+    let verify_tx_result = state_view.verify_tx(&tx, None).await;
 
     assert!(verify_tx_result.is_ok());
 }
@@ -166,7 +211,7 @@ async fn test_verify_tx_vt3() {
 
     let state_view = DefaultStateView::new(store);
 
-    let verify_tx_result = state_view.verify_tx(&tx).await;
+    let verify_tx_result = state_view.verify_tx(&tx, None).await;
 
     assert_eq!(
         verify_tx_result,
@@ -197,8 +242,7 @@ async fn test_verify_tx_vt4() {
         OutputNotes::new(Vec::new()).unwrap(),
     );
 
-    // Notice: tx2 modifies the same account as tx1, even though from a different initial state,
-    // which is currently disallowed
+    // Notice: tx2 starts from the final state of tx1, which is still in flight
     let tx2 = tx_gen.dummy_proven_tx_with_params(
         account.id,
         account.states[1],
@@ -207,16 +251,33 @@ async fn test_verify_tx_vt4() {
         OutputNotes::new(Vec::new()).unwrap(),
     );
 
+    // Notice: tx3 starts from the state of the account in the store, which tx1 already modified
+    let tx3 = tx_gen.dummy_proven_tx_with_params(
+        account.id,
+        account.states[0],
+        account.states[2],
+        InputNotes::new(Vec::new()).unwrap(),
+        OutputNotes::new(Vec::new()).unwrap(),
+    );
+
     let state_view = DefaultStateView::new(store);
 
-    let verify_tx1_result = state_view.verify_tx(&tx1).await;
+    let verify_tx1_result = state_view.verify_tx(&tx1, None).await;
     assert!(verify_tx1_result.is_ok());
 
-    let verify_tx2_result = state_view.verify_tx(&tx2).await;
+    let verify_tx2_result = state_view.verify_tx(&tx2, None).await;
+    assert!(verify_tx2_result.is_ok());
+
+    let verify_tx3_result = state_view.verify_tx(&tx3, None).await;
     assert_eq!(
-        verify_tx2_result,
+        verify_tx3_result,
         Err(VerifyTxError::AccountAlreadyModifiedByOtherTx(account.id))
     );
+
+    // once tx1 is released, so is tx2 which follows it, and tx3 starts from the latest state
+    state_view.release_tx(&tx1).await;
+    let verify_tx3_result = state_view.verify_tx(&tx3, None).await;
+    assert!(verify_tx3_result.is_ok());
 }
 
 /// Verifies requirement VT5
@@ -259,10 +320,10 @@ async fn test_verify_tx_vt5() {
 
     let state_view = DefaultStateView::new(store);
 
-    let verify_tx1_result = state_view.verify_tx(&tx1).await;
+    let verify_tx1_result = state_view.verify_tx(&tx1, None).await;
     assert!(verify_tx1_result.is_ok());
 
-    let verify_tx2_result = state_view.verify_tx(&tx2).await;
+    let verify_tx2_result = state_view.verify_tx(&tx2, None).await;
     assert_eq!(
         verify_tx2_result,
         Err(VerifyTxError::InputNotesAlreadyConsumed(
@@ -270,3 +331,301 @@ async fn test_verify_tx_vt5() {
         ))
     );
 }
+
+/// Verifies requirement VT6
+#[tokio::test]
+async fn test_verify_tx_vt6() {
+    let tx_gen = DummyProvenTxGenerator::new();
+
+    let account: MockPrivateAccount<3> = MockPrivateAccount::from(0);
+    let faucet_id = AccountId::try_from(ACCOUNT_ID_FUNGIBLE_FAUCET_ON_CHAIN).unwrap();
+
+    let store = Arc::new(
+        MockStoreSuccessBuilder::new()
+            .initial_accounts(iter::once((account.id, account.states[0])))
+            .build(),
+    );
+
+    let own_note =
+        NoteEnvelope::new(Hasher::hash(&[1u8]).into(), NoteMetadata::new(account.id, ONE));
+    let faucet_note =
+        NoteEnvelope::new(Hasher::hash(&[2u8]).into(), NoteMetadata::new(faucet_id, ONE));
+
+    // Notice: the regular account creates a note claiming to be sent by the faucet
+    let tx = tx_gen.dummy_proven_tx_with_params(
+        account.id,
+        account.states[0],
+        account.states[1],
+        InputNotes::new(Vec::new()).unwrap(),
+        OutputNotes::new(vec![own_note, faucet_note]).unwrap(),
+    );
+
+    let state_view = DefaultStateView::new(store);
+
+    let verify_tx_result = state_view.verify_tx(&tx, None).await;
+    assert_eq!(
+        verify_tx_result,
+        Err(VerifyTxError::OutputNoteSenderMismatch {
+            account_id: account.id,
+            account_type: AccountType::RegularAccountUpdatableCode,
+            note_id: faucet_note.note_id().inner(),
+            sender: faucet_id,
+            sender_type: AccountType::FungibleFaucet,
+        })
+    );
+
+    // the rejected transaction is not in flight, the account can still be modified
+    let tx = tx_gen.dummy_proven_tx_with_params(
+        account.id,
+        account.states[0],
+        account.states[1],
+        InputNotes::new(Vec::new()).unwrap(),
+        OutputNotes::new(vec![own_note]).unwrap(),
+    );
+    assert!(state_view.verify_tx(&tx, None).await.is_ok());
+}
+
+/// Verifies requirement VT7
+#[tokio::test]
+async fn test_verify_tx_vt7() {
+    let store = Arc::new(MockStoreSuccessBuilder::new().pruning_horizon(1).build());
+    let genesis_hash = store.last_block_header.read().await.hash();
+
+    let state_view = DefaultStateView::new(store);
+
+    let tx = MockProvenTxBuilder::new().block_ref(genesis_hash).build();
+    let verify_tx_result = state_view.verify_tx(&tx, None).await;
+    assert_eq!(
+        verify_tx_result,
+        Err(VerifyTxError::ReferenceBlockPruned {
+            block_ref: genesis_hash,
+            block_ref_num: 0,
+            pruning_horizon: 1,
+        })
+    );
+
+    // a block unknown to a pruned store may be older than its pruning horizon
+    let tx = MockProvenTxBuilder::new().build();
+    let verify_tx_result = state_view.verify_tx(&tx, None).await;
+    assert_eq!(
+        verify_tx_result,
+        Err(VerifyTxError::ReferenceBlockUnknown {
+            block_ref: Digest::default(),
+            pruning_horizon: 1,
+        })
+    );
+
+    // transactions executed against blocks unknown to a store retaining the whole chain are not
+    // checked
+    let store = Arc::new(MockStoreSuccessBuilder::new().build());
+    let state_view = DefaultStateView::new(store);
+    assert!(state_view.verify_tx(&tx, None).await.is_ok());
+}
+
+/// Verifies requirement VT8
+#[tokio::test]
+async fn test_verify_tx_vt8() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account: MockPrivateAccount<3> = MockPrivateAccount::from(0);
+
+    let store = Arc::new(
+        MockStoreSuccessBuilder::new()
+            .initial_accounts(iter::once((account.id, account.states[0])))
+            .build(),
+    );
+
+    let allowed_note =
+        NoteEnvelope::new(Hasher::hash(&[1u8]).into(), NoteMetadata::new(account.id, ONE));
+    let rejected_note = NoteEnvelope::new(
+        Hasher::hash(&[2u8]).into(),
+        NoteMetadata::new(account.id, Felt::new(50)),
+    );
+
+    let tx = tx_gen.dummy_proven_tx_with_params(
+        account.id,
+        account.states[0],
+        account.states[1],
+        InputNotes::new(Vec::new()).unwrap(),
+        OutputNotes::new(vec![allowed_note, rejected_note]).unwrap(),
+    );
+
+    let tag_policy = TagPolicy {
+        local: vec![TagRange { start: 0, end: 10 }],
+        ..Default::default()
+    };
+    let state_view = DefaultStateView::new(store).with_tag_policy(tag_policy);
+
+    let verify_tx_result = state_view.verify_tx(&tx, None).await;
+    assert_eq!(
+        verify_tx_result,
+        Err(VerifyTxError::OutputNoteTagNotAllowed {
+            note_id: rejected_note.note_id().inner(),
+            tag: 50,
+        })
+    );
+}
+
+/// Verifies requirement VT9
+#[tokio::test]
+async fn test_verify_tx_vt9() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account_delta =
+        AccountDelta::new(AccountStorageDelta::default(), AccountVaultDelta::default(), None)
+            .unwrap();
+
+    let store = Arc::new(MockStoreSuccessBuilder::new().build());
+    let state_view = DefaultStateView::new(store);
+
+    // the transactions of public accounts must include the account delta
+    let public_tx = tx_gen.dummy_proven_tx();
+    assert!(public_tx.account_id().is_on_chain());
+    assert_eq!(
+        state_view.verify_tx(&public_tx, None).await,
+        Err(VerifyTxError::PublicAccountDeltaMissing(public_tx.account_id()))
+    );
+    assert!(state_view.verify_tx(&public_tx, Some(&account_delta)).await.is_ok());
+
+    // the transactions of private accounts must not
+    let private_tx = MockProvenTxBuilder::new().build();
+    assert!(!private_tx.account_id().is_on_chain());
+    assert_eq!(
+        state_view.verify_tx(&private_tx, Some(&account_delta)).await,
+        Err(VerifyTxError::PrivateAccountDeltaProvided(private_tx.account_id()))
+    );
+    assert!(state_view.verify_tx(&private_tx, None).await.is_ok());
+}
+
+/// Verifies requirement VT10
+#[tokio::test]
+async fn test_verify_tx_vt10() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account = mock_account(
+        Some(ACCOUNT_ID_REGULAR_ACCOUNT_UPDATABLE_CODE_ON_CHAIN),
+        ONE,
+        None,
+        &TransactionKernel::assembler(),
+    );
+    let account_id = account.id();
+    assert!(account_id.is_on_chain());
+
+    // the deltas only update the nonce of the account
+    let delta = |nonce: u64| {
+        AccountDelta::new(
+            AccountStorageDelta::default(),
+            AccountVaultDelta::default(),
+            Some(Felt::new(nonce)),
+        )
+        .unwrap()
+    };
+    let updated = |account: &Account, nonce: u64| {
+        let mut account = account.clone();
+        account.apply_delta(&delta(nonce)).unwrap();
+        account
+    };
+    let account_tx = |initial_account: &Account, final_account: &Account| {
+        tx_gen.dummy_proven_tx_with_params(
+            account_id,
+            initial_account.hash(),
+            final_account.hash(),
+            InputNotes::new(Vec::new()).unwrap(),
+            OutputNotes::new(Vec::new()).unwrap(),
+        )
+    };
+
+    let store = Arc::new(
+        MockStoreSuccessBuilder::new()
+            .initial_accounts(iter::once((account_id, account.hash())))
+            .initial_account_details(iter::once(account.clone()))
+            .build(),
+    );
+    let state_view = DefaultStateView::new(store.clone());
+
+    // the delta is checked against the state of the account in the store
+    let state_1 = updated(&account, 2);
+    let tx_1 = account_tx(&account, &state_1);
+    assert_eq!(
+        state_view.verify_tx(&tx_1, Some(&delta(3))).await,
+        Err(VerifyTxError::AccountDeltaMismatch {
+            account_id,
+            tx_final_account_hash: state_1.hash(),
+            delta_account_hash: updated(&account, 3).hash(),
+        })
+    );
+    assert!(matches!(
+        state_view.verify_tx(&tx_1, Some(&delta(1))).await,
+        Err(VerifyTxError::InvalidAccountDelta { account_id: id, .. }) if id == account_id
+    ));
+    assert!(state_view.verify_tx(&tx_1, Some(&delta(2))).await.is_ok());
+
+    // and against the final state of the transaction in flight the next transaction follows
+    let state_2 = updated(&state_1, 3);
+    let tx_2 = account_tx(&state_1, &state_2);
+    assert_eq!(
+        state_view.verify_tx(&tx_2, Some(&delta(4))).await,
+        Err(VerifyTxError::AccountDeltaMismatch {
+            account_id,
+            tx_final_account_hash: state_2.hash(),
+            delta_account_hash: updated(&state_1, 4).hash(),
+        })
+    );
+    assert!(state_view.verify_tx(&tx_2, Some(&delta(3))).await.is_ok());
+
+    // the final state of the account is published along with the block
+    let block = MockBlockBuilder::new(&store)
+        .await
+        .account_updates(vec![(account_id, state_2.hash())])
+        .build();
+    state_view.apply_block(block).await.unwrap();
+    assert_eq!(store.account_details.read().await.get(&account_id), Some(&state_2));
+}
+
+/// Verifies requirement VT11
+#[tokio::test]
+async fn test_verify_tx_vt11() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account_id =
+        AccountId::try_from(ACCOUNT_ID_REGULAR_ACCOUNT_UPDATABLE_CODE_ON_CHAIN).unwrap();
+    let faucet_id = AccountId::try_from(ACCOUNT_ID_FUNGIBLE_FAUCET_ON_CHAIN).unwrap();
+
+    // the delta adds 100 units of the faucet's asset to the vault of the account
+    let account_delta = AccountDelta::new(
+        AccountStorageDelta::default(),
+        AccountVaultDelta {
+            added_assets: vec![FungibleAsset::new(faucet_id, 100).unwrap().into()],
+            removed_assets: Vec::new(),
+        },
+        Some(Felt::new(2)),
+    )
+    .unwrap();
+    let tx = |account_id: AccountId, input_notes: Vec<Nullifier>| {
+        tx_gen.dummy_proven_tx_with_params(
+            account_id,
+            Hasher::hash(&[1u8]),
+            Hasher::hash(&[2u8]),
+            InputNotes::new(input_notes).unwrap(),
+            OutputNotes::new(Vec::new()).unwrap(),
+        )
+    };
+
+    // the accounts are not in the store, their delta can't be checked against their state
+    let store = Arc::new(MockStoreSuccessBuilder::new().build());
+    let state_view = DefaultStateView::new(store);
+
+    // Notice: the regular account receives assets out of nothing
+    assert_eq!(
+        state_view.verify_tx(&tx(account_id, Vec::new()), Some(&account_delta)).await,
+        Err(VerifyTxError::AssetsMintedByRegularAccount {
+            account_id,
+            account_type: AccountType::RegularAccountUpdatableCode,
+            faucet_id,
+        })
+    );
+
+    // the assets may come from the notes it consumes
+    let consuming_tx = tx(account_id, vec![nullifier_by_index(0)]);
+    assert!(state_view.verify_tx(&consuming_tx, Some(&account_delta)).await.is_ok());
+
+    // faucets mint assets without consuming any note
+    let faucet_tx = tx(faucet_id, Vec::new());
+    assert!(state_view.verify_tx(&faucet_tx, Some(&account_delta)).await.is_ok());
+}
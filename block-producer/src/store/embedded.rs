@@ -0,0 +1,90 @@
+//! Store running in the same process as the block producer.
+//!
+//! Single-node deployments run the store and the block producer side by side. Instead of going
+//! through the store's gRPC server, the block producer then calls the store's endpoints directly,
+//! skipping the encoding of the messages and the network round trip. The requests still go through
+//! the same endpoints as remote ones, so both modes validate the blocks the same way.
+//!
+//! The chain id interceptor of the store's server is skipped, the block producer verifies the
+//! chain id of the store once connected, see [super::DefaultStore::verify_node_info]. So is the
+//! operator authentication: the counters of the chain are read without an operator signature, the
+//! block producer being the store's own process.
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use miden_node_proto::{
+    requests::{
+        ApplyBlockRequest, CommitBlockRequest, GetBlockHeaderByNumberRequest,
+        GetBlockInputsRequest, GetChainCountersRequest, GetNodeInfoRequest,
+        GetPruningHorizonRequest, GetTransactionInputsRequest,
+    },
+    responses::{
+        CommitBlockResponse, GetBlockHeaderByNumberResponse, GetBlockInputsResponse,
+        GetChainCountersResponse, GetNodeInfoResponse, GetPruningHorizonResponse,
+        GetTransactionInputsResponse, ProposeBlockResponse,
+    },
+    store::api_server::Api,
+};
+use miden_node_store::server::StoreApi;
+use tonic::{Request, Response, Status};
+
+use super::StoreEndpoints;
+
+#[async_trait]
+impl StoreEndpoints for Arc<StoreApi> {
+    async fn get_transaction_inputs(
+        &self,
+        request: Request<GetTransactionInputsRequest>,
+    ) -> Result<Response<GetTransactionInputsResponse>, Status> {
+        Api::get_transaction_inputs(self.as_ref(), request).await
+    }
+
+    async fn get_pruning_horizon(
+        &self,
+        request: Request<GetPruningHorizonRequest>,
+    ) -> Result<Response<GetPruningHorizonResponse>, Status> {
+        Api::get_pruning_horizon(self.as_ref(), request).await
+    }
+
+    async fn get_block_inputs(
+        &self,
+        request: Request<GetBlockInputsRequest>,
+    ) -> Result<Response<GetBlockInputsResponse>, Status> {
+        Api::get_block_inputs(self.as_ref(), request).await
+    }
+
+    async fn propose_block(
+        &self,
+        request: Request<ApplyBlockRequest>,
+    ) -> Result<Response<ProposeBlockResponse>, Status> {
+        Api::propose_block(self.as_ref(), request).await
+    }
+
+    async fn commit_block(
+        &self,
+        request: Request<CommitBlockRequest>,
+    ) -> Result<Response<CommitBlockResponse>, Status> {
+        Api::commit_block(self.as_ref(), request).await
+    }
+
+    async fn get_node_info(
+        &self,
+        request: Request<GetNodeInfoRequest>,
+    ) -> Result<Response<GetNodeInfoResponse>, Status> {
+        Api::get_node_info(self.as_ref(), request).await
+    }
+
+    async fn get_block_header_by_number(
+        &self,
+        request: Request<GetBlockHeaderByNumberRequest>,
+    ) -> Result<Response<GetBlockHeaderByNumberResponse>, Status> {
+        Api::get_block_header_by_number(self.as_ref(), request).await
+    }
+
+    async fn get_chain_counters(
+        &self,
+        _request: Request<GetChainCountersRequest>,
+    ) -> Result<Response<GetChainCountersResponse>, Status> {
+        Ok(Response::new(self.chain_counters().await?))
+    }
+}
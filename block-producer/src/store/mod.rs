@@ -1,24 +1,60 @@
 use std::{
     collections::BTreeMap,
     fmt::{Display, Formatter},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use async_trait::async_trait;
+use miden_crypto::dsa::rpo_falcon512::KeyPair;
 use miden_node_proto::{
     account,
+    chain_id::ChainIdInterceptor,
     conversion::convert,
     digest,
-    domain::BlockInputs,
-    requests::{ApplyBlockRequest, GetBlockInputsRequest, GetTransactionInputsRequest},
+    domain::{BlockInputs, NodeInfo},
+    errors::NodeInfoError,
+    note::{NoteCreated, NoteNullifier},
+    requests::{
+        AccountUpdate, ApplyBlockRequest, BatchProof, CommitBlockRequest,
+        GetBlockHeaderByNumberRequest, GetBlockInputsRequest, GetChainCountersRequest,
+        GetNodeInfoRequest, GetPruningHorizonRequest, GetTransactionInputsRequest,
+    },
+    responses::{
+        CommitBlockResponse, GetBlockHeaderByNumberResponse, GetBlockInputsResponse,
+        GetChainCountersResponse, GetNodeInfoResponse, GetPruningHorizonResponse,
+        GetTransactionInputsResponse, ProposeBlockResponse,
+    },
     store::api_client as store_client,
 };
-use miden_node_utils::formatting::{format_map, format_opt};
-use miden_objects::{accounts::AccountId, Digest};
-use tonic::transport::Channel;
-use tracing::{debug, info, instrument};
+use miden_node_store::GET_CHAIN_COUNTERS_OPERATION;
+use miden_node_utils::{
+    formatting::{format_map, format_opt},
+    operator_auth::sign_request,
+};
+use miden_objects::{
+    accounts::{Account, AccountId},
+    utils::serde::{Deserializable, Serializable},
+    BlockHeader, Digest,
+};
+use tonic::{
+    service::interceptor::InterceptedService, transport::Channel, Code, Request, Response, Status,
+};
+use tracing::{debug, info, instrument, warn};
+
+pub use crate::errors::{ApplyBlockError, BlockInputsError, StartupAuditError, TxInputsError};
 
-pub use crate::errors::{ApplyBlockError, BlockInputsError, TxInputsError};
-use crate::{block::Block, ProvenTransaction, COMPONENT};
+pub mod embedded;
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    audit::{ChainCounters, StartupAudit},
+    block::Block,
+    config::TxOrdering,
+    ProvenTransaction, COMPONENT,
+};
 
 // STORE TRAIT
 // ================================================================================================
@@ -53,8 +89,18 @@ pub struct TxInputs {
     /// The account hash in the store corresponding to tx's account ID
     pub account_hash: Option<Digest>,
 
+    /// The state of the account whose hash is `account_hash`, if the account is public and its
+    /// state is known to the store
+    pub account_details: Option<Account>,
+
     /// Maps each consumed notes' nullifier to whether the note is already consumed
     pub nullifiers: BTreeMap<Digest, bool>,
+
+    /// The number of the block the tx was executed against, `None` if the store doesn't know it
+    pub block_ref_num: Option<u32>,
+
+    /// The oldest block whose notes are retained by the store
+    pub pruning_horizon: u32,
 }
 
 impl Display for TxInputs {
@@ -63,55 +109,404 @@ impl Display for TxInputs {
         f: &mut Formatter<'_>,
     ) -> std::fmt::Result {
         f.write_fmt(format_args!(
-            "{{ account_hash: {}, nullifiers: {} }}",
+            "{{ account_hash: {}, account_details: {}, nullifiers: {}, block_ref_num: {}, pruning_horizon: {} }}",
             format_opt(self.account_hash.as_ref()),
-            format_map(&self.nullifiers)
+            self.account_details.is_some(),
+            format_map(&self.nullifiers),
+            format_opt(self.block_ref_num.as_ref()),
+            self.pruning_horizon,
         ))
     }
 }
 
+// STORE ENDPOINTS
+// ================================================================================================
+
+/// The endpoints of the store used by the block producer.
+///
+/// Implemented by the gRPC client of a remote store, and by the store itself when it runs in the
+/// same process, see [embedded].
+#[async_trait]
+pub trait StoreEndpoints: Send + Sync + 'static {
+    async fn get_transaction_inputs(
+        &self,
+        request: Request<GetTransactionInputsRequest>,
+    ) -> Result<Response<GetTransactionInputsResponse>, Status>;
+
+    async fn get_pruning_horizon(
+        &self,
+        request: Request<GetPruningHorizonRequest>,
+    ) -> Result<Response<GetPruningHorizonResponse>, Status>;
+
+    async fn get_block_inputs(
+        &self,
+        request: Request<GetBlockInputsRequest>,
+    ) -> Result<Response<GetBlockInputsResponse>, Status>;
+
+    async fn propose_block(
+        &self,
+        request: Request<ApplyBlockRequest>,
+    ) -> Result<Response<ProposeBlockResponse>, Status>;
+
+    async fn commit_block(
+        &self,
+        request: Request<CommitBlockRequest>,
+    ) -> Result<Response<CommitBlockResponse>, Status>;
+
+    async fn get_node_info(
+        &self,
+        request: Request<GetNodeInfoRequest>,
+    ) -> Result<Response<GetNodeInfoResponse>, Status>;
+
+    async fn get_block_header_by_number(
+        &self,
+        request: Request<GetBlockHeaderByNumberRequest>,
+    ) -> Result<Response<GetBlockHeaderByNumberResponse>, Status>;
+
+    async fn get_chain_counters(
+        &self,
+        request: Request<GetChainCountersRequest>,
+    ) -> Result<Response<GetChainCountersResponse>, Status>;
+}
+
+/// Client to the store, which attaches the block producer's chain id to every request.
+pub type StoreClient = store_client::ApiClient<InterceptedService<Channel, ChainIdInterceptor>>;
+
+#[async_trait]
+impl StoreEndpoints for StoreClient {
+    async fn get_transaction_inputs(
+        &self,
+        request: Request<GetTransactionInputsRequest>,
+    ) -> Result<Response<GetTransactionInputsResponse>, Status> {
+        self.clone().get_transaction_inputs(request).await
+    }
+
+    async fn get_pruning_horizon(
+        &self,
+        request: Request<GetPruningHorizonRequest>,
+    ) -> Result<Response<GetPruningHorizonResponse>, Status> {
+        self.clone().get_pruning_horizon(request).await
+    }
+
+    async fn get_block_inputs(
+        &self,
+        request: Request<GetBlockInputsRequest>,
+    ) -> Result<Response<GetBlockInputsResponse>, Status> {
+        self.clone().get_block_inputs(request).await
+    }
+
+    async fn propose_block(
+        &self,
+        request: Request<ApplyBlockRequest>,
+    ) -> Result<Response<ProposeBlockResponse>, Status> {
+        self.clone().propose_block(request).await
+    }
+
+    async fn commit_block(
+        &self,
+        request: Request<CommitBlockRequest>,
+    ) -> Result<Response<CommitBlockResponse>, Status> {
+        self.clone().commit_block(request).await
+    }
+
+    async fn get_node_info(
+        &self,
+        request: Request<GetNodeInfoRequest>,
+    ) -> Result<Response<GetNodeInfoResponse>, Status> {
+        self.clone().get_node_info(request).await
+    }
+
+    async fn get_block_header_by_number(
+        &self,
+        request: Request<GetBlockHeaderByNumberRequest>,
+    ) -> Result<Response<GetBlockHeaderByNumberResponse>, Status> {
+        self.clone().get_block_header_by_number(request).await
+    }
+
+    async fn get_chain_counters(
+        &self,
+        request: Request<GetChainCountersRequest>,
+    ) -> Result<Response<GetChainCountersResponse>, Status> {
+        self.clone().get_chain_counters(request).await
+    }
+}
+
 // DEFAULT STORE IMPLEMENTATION
 // ================================================================================================
 
-pub struct DefaultStore {
-    store: store_client::ApiClient<Channel>,
+/// Number of times the proposal of a block, then its commit, is attempted when the store is busy
+/// or can't be reached.
+const APPLY_BLOCK_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry of a proposal or a commit, doubled after every retry.
+const APPLY_BLOCK_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+pub struct DefaultStore<S = StoreClient> {
+    store: S,
+    /// Operator key of the store signing the admin requests, along with the chain id they are
+    /// signed for
+    operator_key: Option<(Arc<KeyPair>, u32)>,
+    /// Pruning horizon of the store, cached until the next block is applied
+    pruning_horizon: Mutex<Option<u32>>,
 }
 
-impl DefaultStore {
+impl<S: StoreEndpoints> DefaultStore<S> {
     /// TODO: this should probably take store connection string and create a connection internally
-    pub fn new(store: store_client::ApiClient<Channel>) -> Self {
-        Self { store }
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            operator_key: None,
+            pruning_horizon: Mutex::new(None),
+        }
+    }
+
+    /// Signs the admin requests to the store, e.g. reading its counters, with `operator_key`, one
+    /// of the store's operator keys, for the chain `chain_id`.
+    pub fn with_operator_key(
+        mut self,
+        operator_key: Arc<KeyPair>,
+        chain_id: u32,
+    ) -> Self {
+        self.operator_key = Some((operator_key, chain_id));
+        self
+    }
+
+    /// Retrieves the store's [NodeInfo], and verifies the block producer can operate against it.
+    ///
+    /// See [NodeInfo::verify] for the meaning of `chain_id` and `genesis_hash`.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-block-producer", skip_all, err)]
+    pub async fn verify_node_info(
+        &self,
+        chain_id: u32,
+        genesis_hash: Option<Digest>,
+    ) -> Result<NodeInfo, NodeInfoError> {
+        let node_info: NodeInfo = self
+            .store
+            .get_node_info(tonic::Request::new(GetNodeInfoRequest {}))
+            .await
+            .map_err(|status| NodeInfoError::GrpcClientError(status.message().to_string()))?
+            .into_inner()
+            .try_into()?;
+
+        node_info.verify(chain_id, genesis_hash)?;
+
+        Ok(node_info)
+    }
+
+    /// Audits the latest block of the store against the genesis block of the chain described by
+    /// `node_info`, see [StartupAudit::new].
+    ///
+    /// The counters of a remote store are only read by its operators: without an operator key
+    /// they are not audited.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-block-producer", skip_all, err)]
+    pub async fn audit(
+        &self,
+        node_info: &NodeInfo,
+        now: u64,
+    ) -> Result<StartupAudit, StartupAuditError> {
+        let genesis = self.get_block_header(Some(0)).await?;
+        let chain_tip = self.get_block_header(None).await?;
+        let mut request = tonic::Request::new(GetChainCountersRequest {});
+        if let Some((operator_key, chain_id)) = &self.operator_key {
+            sign_request(&mut request, operator_key, *chain_id, GET_CHAIN_COUNTERS_OPERATION)
+                .map_err(|err| StartupAuditError::GrpcClientError(err.to_string()))?;
+        }
+        let counters: Option<ChainCounters> = match self.store.get_chain_counters(request).await {
+            Ok(response) => Some(response.into_inner().try_into()?),
+            Err(status)
+                if status.code() == Code::Unauthenticated && self.operator_key.is_none() =>
+            {
+                warn!(target: COMPONENT, "No store operator key configured, the chain counters are not audited");
+                None
+            },
+            Err(status) => {
+                return Err(StartupAuditError::GrpcClientError(status.message().to_string()))
+            },
+        };
+
+        StartupAudit::new(node_info, &genesis, chain_tip, counters, now)
+    }
+
+    /// Returns the pruning horizon of the store, which only moves when a block is applied: it is
+    /// fetched once per block instead of once per transaction.
+    async fn pruning_horizon(&self) -> Result<u32, TxInputsError> {
+        if let Some(pruning_horizon) =
+            *self.pruning_horizon.lock().expect("pruning horizon lock poisoned")
+        {
+            return Ok(pruning_horizon);
+        }
+
+        let pruning_horizon = self
+            .store
+            .get_pruning_horizon(tonic::Request::new(GetPruningHorizonRequest {}))
+            .await
+            .map_err(|status| TxInputsError::GrpcClientError(status.message().to_string()))?
+            .into_inner()
+            .block_num;
+        *self.pruning_horizon.lock().expect("pruning horizon lock poisoned") =
+            Some(pruning_horizon);
+
+        Ok(pruning_horizon)
+    }
+
+    /// Retrieves the header of the block `block_num`, or of the latest block if `None`.
+    async fn get_block_header(
+        &self,
+        block_num: Option<u32>,
+    ) -> Result<BlockHeader, StartupAuditError> {
+        let request = tonic::Request::new(GetBlockHeaderByNumberRequest {
+            block_num,
+            ..Default::default()
+        });
+        let block_header = self
+            .store
+            .get_block_header_by_number(request)
+            .await
+            .map_err(|status| StartupAuditError::GrpcClientError(status.message().to_string()))?
+            .into_inner()
+            .block_header
+            .ok_or(StartupAuditError::MalformedResponse(format!(
+                "block header {} missing",
+                format_opt(block_num.as_ref())
+            )))?;
+
+        Ok(block_header.try_into()?)
     }
 }
 
 #[async_trait]
-impl ApplyBlock for DefaultStore {
+impl<S: StoreEndpoints> ApplyBlock for DefaultStore<S> {
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(target = "miden-block-producer", skip_all, err)]
     async fn apply_block(
         &self,
         block: Block,
     ) -> Result<(), ApplyBlockError> {
-        let request = tonic::Request::new(ApplyBlockRequest {
+        // The block is first validated and staged by the store, a rejected block is known not to
+        // be part of the chain. Proposing the staged block again replaces it, and committing it is
+        // idempotent, so both are retried while the store is busy or its answer is unknown.
+        let request = ApplyBlockRequest {
             block: Some(block.header.into()),
-            accounts: convert(block.updated_accounts),
+            chain_id: block.chain_id,
+            accounts: block
+                .updated_accounts
+                .into_iter()
+                .map(|(account_id, account_hash)| AccountUpdate {
+                    account_id: Some(account_id.into()),
+                    account_hash: Some(account_hash.into()),
+                    details: block
+                        .account_details
+                        .iter()
+                        .find(|account| account.id() == account_id)
+                        .map(Serializable::to_bytes)
+                        .unwrap_or_default(),
+                })
+                .collect(),
             nullifiers: convert(block.produced_nullifiers),
-            notes: convert(block.created_notes),
-        });
+            notes: block
+                .created_notes
+                .into_iter()
+                .map(|(note_idx, note)| NoteCreated {
+                    private: block.private_notes.contains(&note.note_id().inner()),
+                    ..NoteCreated::from((note_idx, note))
+                })
+                .collect(),
+            consumed_notes: block
+                .consumed_notes
+                .into_iter()
+                .map(|(note_hash, nullifier)| NoteNullifier {
+                    note_hash: Some(note_hash.into()),
+                    nullifier: Some(nullifier.into()),
+                })
+                .collect(),
+            tx_kernel_versions: block.tx_kernel_versions.into_iter().collect(),
+            signature: block.signature.as_ref().map(Into::into),
+            canonical_tx_ordering: block.tx_ordering == TxOrdering::Canonical,
+            timestamp_attestation: block.timestamp_attestation.as_ref().map(Into::into),
+            batch_proofs: block
+                .batch_proofs
+                .into_iter()
+                .map(|batch_proof| BatchProof {
+                    commitment: Some(batch_proof.commitment.into()),
+                    num_txs: batch_proof.num_txs as u32,
+                    created_notes_root: Some(batch_proof.created_notes_root.into()),
+                    proof: batch_proof.proof,
+                    batch_index: batch_proof.batch_index,
+                })
+                .collect(),
+        };
 
-        let _ = self
-            .store
-            .clone()
-            .apply_block(request)
-            .await
-            .map_err(|status| ApplyBlockError::GrpcClientError(status.message().to_string()))?;
+        let block_hash = with_retries("propose block", || {
+            self.store.propose_block(tonic::Request::new(request.clone()))
+        })
+        .await?
+        .into_inner()
+        .block_hash;
+
+        let committed = with_retries("commit block", || {
+            self.store.commit_block(tonic::Request::new(CommitBlockRequest {
+                block_hash: block_hash.clone(),
+            }))
+        })
+        .await;
+
+        // the block may have moved the pruning horizon, even if the answer of its commit is unknown
+        *self.pruning_horizon.lock().expect("pruning horizon lock poisoned") = None;
+
+        let block_num = committed?.into_inner().block_num;
+        debug!(target: COMPONENT, block_num);
 
         Ok(())
     }
 }
 
+/// Calls `call` until it succeeds or fails for another reason than the store being busy or
+/// unreachable, up to [APPLY_BLOCK_ATTEMPTS] times, backing off between the attempts.
+async fn with_retries<T, F, Fut>(
+    operation: &str,
+    mut call: F,
+) -> Result<T, ApplyBlockError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Status>>,
+{
+    let mut backoff = APPLY_BLOCK_INITIAL_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Ok(response) => return Ok(response),
+            Err(status)
+                if matches!(status.code(), Code::Aborted | Code::Unavailable | Code::Unknown)
+                    && attempt < APPLY_BLOCK_ATTEMPTS =>
+            {
+                warn!(target: COMPONENT, attempt, %status, "Failed to {operation}, retrying");
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            },
+            Err(status) => return Err(apply_block_error(status)),
+        }
+    }
+}
+
+/// Maps a failure of the store to propose or commit a block to an error, telling blocks rejected
+/// by the store apart from failures to reach it.
+fn apply_block_error(status: Status) -> ApplyBlockError {
+    let message = status.message().to_string();
+    match status.code() {
+        Code::InvalidArgument => ApplyBlockError::BlockRejected(message),
+        Code::ResourceExhausted => ApplyBlockError::StoreReadOnly(message),
+        Code::Aborted => ApplyBlockError::StoreBusy(message),
+        Code::DeadlineExceeded => ApplyBlockError::ProposalExpired(message),
+        Code::NotFound => ApplyBlockError::ProposalNotFound(message),
+        _ => ApplyBlockError::GrpcClientError(message),
+    }
+}
+
 #[async_trait]
-impl Store for DefaultStore {
+impl<S: StoreEndpoints> Store for DefaultStore<S> {
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(target = "miden-block-producer", skip_all, err)]
     async fn get_tx_inputs(
@@ -125,6 +520,7 @@ impl Store for DefaultStore {
                 .iter()
                 .map(|nullifier| (*nullifier).into())
                 .collect(),
+            block_ref: Some(proven_tx.block_ref().into()),
         };
 
         info!(target: COMPONENT, tx_id = %proven_tx.id().to_hex());
@@ -133,7 +529,6 @@ impl Store for DefaultStore {
         let request = tonic::Request::new(message);
         let response = self
             .store
-            .clone()
             .get_transaction_inputs(request)
             .await
             .map_err(|status| TxInputsError::GrpcClientError(status.message().to_string()))?
@@ -141,7 +536,9 @@ impl Store for DefaultStore {
 
         debug!(target: COMPONENT, ?response);
 
-        let account_hash = {
+        let pruning_horizon = self.pruning_horizon().await?;
+
+        let (account_hash, account_details) = {
             let account_state = response
                 .account_state
                 .ok_or(TxInputsError::MalformedResponse("account_states empty".to_string()))?;
@@ -160,7 +557,19 @@ impl Store for DefaultStore {
                 )));
             }
 
-            account_state.account_hash.clone().map(Digest::try_from).transpose()?
+            let account_details = if account_state.details.is_empty() {
+                None
+            } else {
+                let account = Account::read_from_bytes(&account_state.details).map_err(|err| {
+                    TxInputsError::MalformedResponse(format!("malformed account details: {err}"))
+                })?;
+                Some(account)
+            };
+
+            (
+                account_state.account_hash.clone().map(Digest::try_from).transpose()?,
+                account_details,
+            )
         };
 
         let nullifiers = {
@@ -201,7 +610,10 @@ impl Store for DefaultStore {
 
         let tx_inputs = TxInputs {
             account_hash,
+            account_details,
             nullifiers,
+            block_ref_num: response.block_ref_num,
+            pruning_horizon,
         };
 
         debug!(target: COMPONENT, %tx_inputs);
@@ -223,7 +635,6 @@ impl Store for DefaultStore {
 
         let store_response = self
             .store
-            .clone()
             .get_block_inputs(request)
             .await
             .map_err(|err| BlockInputsError::GrpcClientError(err.message().to_string()))?
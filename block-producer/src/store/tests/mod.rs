@@ -0,0 +1,228 @@
+use std::{
+    collections::{BTreeSet, VecDeque},
+    sync::Mutex,
+};
+
+use miden_objects::ONE;
+
+use super::*;
+
+// HELPERS
+// ================================================================================================
+
+/// Store failing the proposals and commits with scripted statuses, then accepting them.
+#[derive(Default)]
+struct ScriptedStore {
+    propose_failures: Mutex<VecDeque<Status>>,
+    commit_failures: Mutex<VecDeque<Status>>,
+    num_proposals: Mutex<usize>,
+    num_commits: Mutex<usize>,
+    num_pruning_horizons: Mutex<usize>,
+}
+
+impl ScriptedStore {
+    fn new(
+        propose_failures: impl IntoIterator<Item = Status>,
+        commit_failures: impl IntoIterator<Item = Status>,
+    ) -> Self {
+        Self {
+            propose_failures: Mutex::new(propose_failures.into_iter().collect()),
+            commit_failures: Mutex::new(commit_failures.into_iter().collect()),
+            ..Default::default()
+        }
+    }
+
+    fn calls(&self) -> (usize, usize) {
+        (*self.num_proposals.lock().unwrap(), *self.num_commits.lock().unwrap())
+    }
+}
+
+#[async_trait]
+impl StoreEndpoints for ScriptedStore {
+    async fn get_transaction_inputs(
+        &self,
+        _request: Request<GetTransactionInputsRequest>,
+    ) -> Result<Response<GetTransactionInputsResponse>, Status> {
+        unimplemented!()
+    }
+
+    async fn get_pruning_horizon(
+        &self,
+        _request: Request<GetPruningHorizonRequest>,
+    ) -> Result<Response<GetPruningHorizonResponse>, Status> {
+        let mut num_pruning_horizons = self.num_pruning_horizons.lock().unwrap();
+        *num_pruning_horizons += 1;
+        Ok(Response::new(GetPruningHorizonResponse {
+            block_num: *num_pruning_horizons as u32,
+        }))
+    }
+
+    async fn get_block_inputs(
+        &self,
+        _request: Request<GetBlockInputsRequest>,
+    ) -> Result<Response<GetBlockInputsResponse>, Status> {
+        unimplemented!()
+    }
+
+    async fn propose_block(
+        &self,
+        request: Request<ApplyBlockRequest>,
+    ) -> Result<Response<ProposeBlockResponse>, Status> {
+        *self.num_proposals.lock().unwrap() += 1;
+        if let Some(status) = self.propose_failures.lock().unwrap().pop_front() {
+            return Err(status);
+        }
+
+        let block_header = request.into_inner().block.unwrap();
+        let block_hash = BlockHeader::try_from(block_header).unwrap().hash();
+        Ok(Response::new(ProposeBlockResponse {
+            block_hash: Some(block_hash.into()),
+        }))
+    }
+
+    async fn commit_block(
+        &self,
+        _request: Request<CommitBlockRequest>,
+    ) -> Result<Response<CommitBlockResponse>, Status> {
+        *self.num_commits.lock().unwrap() += 1;
+        if let Some(status) = self.commit_failures.lock().unwrap().pop_front() {
+            return Err(status);
+        }
+
+        Ok(Response::new(CommitBlockResponse { block_num: 1 }))
+    }
+
+    async fn get_node_info(
+        &self,
+        _request: Request<GetNodeInfoRequest>,
+    ) -> Result<Response<GetNodeInfoResponse>, Status> {
+        unimplemented!()
+    }
+
+    async fn get_block_header_by_number(
+        &self,
+        _request: Request<GetBlockHeaderByNumberRequest>,
+    ) -> Result<Response<GetBlockHeaderByNumberResponse>, Status> {
+        unimplemented!()
+    }
+
+    async fn get_chain_counters(
+        &self,
+        _request: Request<GetChainCountersRequest>,
+    ) -> Result<Response<GetChainCountersResponse>, Status> {
+        unimplemented!()
+    }
+}
+
+fn block() -> Block {
+    Block {
+        header: BlockHeader::new(
+            Digest::default(),
+            1,
+            Digest::default(),
+            Digest::default(),
+            Digest::default(),
+            Digest::default(),
+            Digest::default(),
+            Digest::default(),
+            ONE,
+            1_700_000_000u64.into(),
+        ),
+        chain_id: 1,
+        updated_accounts: Vec::new(),
+        account_details: Vec::new(),
+        created_notes: BTreeMap::new(),
+        produced_nullifiers: Vec::new(),
+        consumed_notes: Vec::new(),
+        private_notes: BTreeSet::new(),
+        tx_kernel_versions: BTreeSet::new(),
+        signature: None,
+        timestamp_attestation: None,
+        tx_ordering: TxOrdering::Arrival,
+        batch_proofs: Vec::new(),
+    }
+}
+
+// TESTS
+// ================================================================================================
+
+/// Tests that the proposal and the commit of a block are retried while the store is busy or
+/// unreachable
+#[tokio::test]
+async fn test_apply_block_retries() {
+    let store = DefaultStore::new(ScriptedStore::new(
+        [Status::aborted("Concurrent write")],
+        [Status::unavailable("Connection reset")],
+    ));
+
+    store.apply_block(block()).await.unwrap();
+    assert_eq!(store.store.calls(), (2, 2));
+}
+
+/// Tests that the pruning horizon is fetched once per block, and refreshed once a block is applied
+#[tokio::test]
+async fn test_pruning_horizon_cached() {
+    let store = DefaultStore::new(ScriptedStore::default());
+
+    assert_eq!(store.pruning_horizon().await.unwrap(), 1);
+    assert_eq!(store.pruning_horizon().await.unwrap(), 1);
+    assert_eq!(*store.store.num_pruning_horizons.lock().unwrap(), 1);
+
+    store.apply_block(block()).await.unwrap();
+    assert_eq!(store.pruning_horizon().await.unwrap(), 2);
+    assert_eq!(*store.store.num_pruning_horizons.lock().unwrap(), 2);
+}
+
+/// Tests that the proposal of a block is given up once the store stayed busy for every attempt
+#[tokio::test]
+async fn test_apply_block_store_busy() {
+    let store = DefaultStore::new(ScriptedStore::new(
+        (0..APPLY_BLOCK_ATTEMPTS).map(|_| Status::aborted("Proposal pending")),
+        [],
+    ));
+
+    assert_eq!(
+        store.apply_block(block()).await,
+        Err(ApplyBlockError::StoreBusy("Proposal pending".to_string()))
+    );
+    assert_eq!(store.store.calls(), (APPLY_BLOCK_ATTEMPTS as usize, 0));
+}
+
+/// Tests that the failures of the store which retrying can't fix are reported at once, each one
+/// as its own error
+#[tokio::test]
+async fn test_apply_block_errors() {
+    for (propose_failure, commit_failure, expected) in [
+        (
+            Some(Status::invalid_argument("Duplicated nullifiers")),
+            None,
+            ApplyBlockError::BlockRejected("Duplicated nullifiers".to_string()),
+        ),
+        (
+            Some(Status::resource_exhausted("Disk limit exceeded")),
+            None,
+            ApplyBlockError::StoreReadOnly("Disk limit exceeded".to_string()),
+        ),
+        (
+            None,
+            Some(Status::deadline_exceeded("Proposal expired")),
+            ApplyBlockError::ProposalExpired("Proposal expired".to_string()),
+        ),
+        (
+            None,
+            Some(Status::not_found("Proposal not found")),
+            ApplyBlockError::ProposalNotFound("Proposal not found".to_string()),
+        ),
+        (
+            None,
+            Some(Status::internal("Database error")),
+            ApplyBlockError::GrpcClientError("Database error".to_string()),
+        ),
+    ] {
+        let num_commits = usize::from(propose_failure.is_none());
+        let store = DefaultStore::new(ScriptedStore::new(propose_failure, commit_failure));
+
+        assert_eq!(store.apply_block(block()).await, Err(expected));
+        assert_eq!(store.store.calls(), (1, num_commits));
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use miden_node_proto::domain::BlockInputs;
 use miden_objects::{
@@ -10,7 +10,8 @@ use miden_vm::crypto::SimpleSmt;
 use super::MockStoreSuccess;
 use crate::{
     block::Block,
-    block_builder::prover::{block_witness::BlockWitness, BlockProver},
+    block_builder::prover::{block_witness::BlockWitness, BlockKernel},
+    config::TxOrdering,
     store::Store,
     TransactionBatch,
 };
@@ -87,7 +88,7 @@ pub async fn build_actual_block_header(
 
     let block_witness = BlockWitness::new(block_inputs_from_store, &batches).unwrap();
 
-    BlockProver::new().prove(block_witness).unwrap()
+    BlockKernel::new().prove(block_witness).unwrap()
 }
 
 #[derive(Debug)]
@@ -161,9 +162,18 @@ impl MockBlockBuilder {
 
         Block {
             header,
+            chain_id: 1,
             updated_accounts: self.updated_accounts.unwrap_or_default(),
+            account_details: Vec::new(),
             created_notes: self.created_notes.unwrap_or_default(),
             produced_nullifiers: self.produced_nullifiers.unwrap_or_default(),
+            consumed_notes: Vec::new(),
+            private_notes: BTreeSet::new(),
+            tx_kernel_versions: BTreeSet::new(),
+            signature: None,
+            timestamp_attestation: None,
+            tx_ordering: TxOrdering::Arrival,
+            batch_proofs: Vec::new(),
         }
     }
 }
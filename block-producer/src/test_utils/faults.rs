@@ -0,0 +1,197 @@
+//! Failure injection into the calls of the block producer to the store, to test its retry and
+//! recovery logic.
+//!
+//! The [FaultyStore] wraps a store, and applies the faults injected for a call to the next calls
+//! of that kind, in order. The calls without a pending fault are forwarded as is.
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Mutex,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use miden_node_proto::domain::BlockInputs;
+use miden_objects::{accounts::AccountId, Digest, Word, ONE};
+use tokio::time;
+
+use crate::{
+    block::Block,
+    store::{ApplyBlock, ApplyBlockError, BlockInputsError, Store, TxInputs, TxInputsError},
+    ProvenTransaction,
+};
+
+/// A call of the block producer to the store, into which faults can be injected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StoreCall {
+    GetTxInputs,
+    GetBlockInputs,
+    ApplyBlock,
+}
+
+/// A fault injected into a call to the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The call fails, as if the store was unreachable.
+    Fail,
+
+    /// The call is forwarded to the store after the delay.
+    Delay(Duration),
+
+    /// The call is forwarded to the store, with corrupted account hashes: the ones of the
+    /// response for the inputs, and the ones of the block for [StoreCall::ApplyBlock].
+    Corrupt,
+}
+
+/// A store whose calls fail, are delayed or are corrupted on demand.
+pub struct FaultyStore<S> {
+    inner: S,
+    faults: Mutex<BTreeMap<StoreCall, VecDeque<Fault>>>,
+    num_calls: Mutex<BTreeMap<StoreCall, usize>>,
+}
+
+impl<S> FaultyStore<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            faults: Default::default(),
+            num_calls: Default::default(),
+        }
+    }
+
+    /// Injects `fault` into the next call of kind `call` without a pending fault.
+    pub fn inject(
+        &self,
+        call: StoreCall,
+        fault: Fault,
+    ) {
+        self.faults
+            .lock()
+            .expect("poisoned lock")
+            .entry(call)
+            .or_default()
+            .push_back(fault);
+    }
+
+    /// Injects `fault` into the next `count` calls of kind `call` without a pending fault.
+    pub fn inject_times(
+        &self,
+        call: StoreCall,
+        fault: Fault,
+        count: usize,
+    ) {
+        for _ in 0..count {
+            self.inject(call, fault);
+        }
+    }
+
+    /// Drops the pending faults, the following calls are forwarded as is.
+    pub fn clear(&self) {
+        self.faults.lock().expect("poisoned lock").clear();
+    }
+
+    /// Returns the number of calls of kind `call` received, faulty or not.
+    pub fn num_calls(
+        &self,
+        call: StoreCall,
+    ) -> usize {
+        self.num_calls
+            .lock()
+            .expect("poisoned lock")
+            .get(&call)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Records the call, and returns its fault once the delay it may have elapsed.
+    async fn next_fault(
+        &self,
+        call: StoreCall,
+    ) -> Option<Fault> {
+        *self.num_calls.lock().expect("poisoned lock").entry(call).or_default() += 1;
+        let fault = self
+            .faults
+            .lock()
+            .expect("poisoned lock")
+            .get_mut(&call)
+            .and_then(VecDeque::pop_front);
+
+        if let Some(Fault::Delay(delay)) = fault {
+            time::sleep(delay).await;
+        }
+        fault
+    }
+}
+
+#[async_trait]
+impl<S: ApplyBlock> ApplyBlock for FaultyStore<S> {
+    async fn apply_block(
+        &self,
+        mut block: Block,
+    ) -> Result<(), ApplyBlockError> {
+        match self.next_fault(StoreCall::ApplyBlock).await {
+            Some(Fault::Fail) => {
+                return Err(ApplyBlockError::GrpcClientError("injected failure".to_string()))
+            },
+            Some(Fault::Corrupt) => {
+                for (_, account_hash) in &mut block.updated_accounts {
+                    *account_hash = corrupt(*account_hash);
+                }
+            },
+            Some(Fault::Delay(_)) | None => (),
+        }
+
+        self.inner.apply_block(block).await
+    }
+}
+
+#[async_trait]
+impl<S: Store> Store for FaultyStore<S> {
+    async fn get_tx_inputs(
+        &self,
+        proven_tx: &ProvenTransaction,
+    ) -> Result<TxInputs, TxInputsError> {
+        let fault = self.next_fault(StoreCall::GetTxInputs).await;
+        if fault == Some(Fault::Fail) {
+            return Err(TxInputsError::GrpcClientError("injected failure".to_string()));
+        }
+
+        let mut tx_inputs = self.inner.get_tx_inputs(proven_tx).await?;
+        if fault == Some(Fault::Corrupt) {
+            tx_inputs.account_hash = tx_inputs.account_hash.map(corrupt);
+        }
+
+        Ok(tx_inputs)
+    }
+
+    async fn get_block_inputs(
+        &self,
+        updated_accounts: impl Iterator<Item = &AccountId> + Send,
+        produced_nullifiers: impl Iterator<Item = &Digest> + Send,
+    ) -> Result<BlockInputs, BlockInputsError> {
+        let fault = self.next_fault(StoreCall::GetBlockInputs).await;
+        if fault == Some(Fault::Fail) {
+            return Err(BlockInputsError::GrpcClientError("injected failure".to_string()));
+        }
+
+        let mut block_inputs =
+            self.inner.get_block_inputs(updated_accounts, produced_nullifiers).await?;
+        if fault == Some(Fault::Corrupt) {
+            for account_state in &mut block_inputs.account_states {
+                account_state.account_hash = corrupt(account_state.account_hash);
+            }
+        }
+
+        Ok(block_inputs)
+    }
+}
+
+/// Returns a digest different from `digest`.
+fn corrupt(digest: Digest) -> Digest {
+    let mut word = Word::from(digest);
+    word[0] += ONE;
+    word.into()
+}
@@ -1,3 +1,7 @@
+//! Mocks of the block producer's inputs, to test the block producer without a store or a
+//! transaction prover.
+//!
+//! Available to other crates with the `testing` feature.
 use std::{collections::BTreeSet, sync::Arc};
 
 use miden_objects::{accounts::AccountId, Digest};
@@ -9,9 +13,23 @@ pub use proven_tx::{DummyProvenTxGenerator, MockProvenTxBuilder};
 mod store;
 pub use store::{MockStoreFailure, MockStoreSuccess, MockStoreSuccessBuilder};
 
+mod faults;
+pub use faults::{Fault, FaultyStore, StoreCall};
+
+/// The traits implemented by the mock stores.
+pub use crate::store::{ApplyBlock, Store, TxInputs};
+
 mod account;
 pub use account::MockPrivateAccount;
 
-pub mod block;
+mod note;
+pub use note::{note_by_index, nullifier_by_index};
+
+#[cfg(any(test, feature = "test-vectors"))]
+pub mod vectors;
+
+mod block;
+pub use block::{build_actual_block_header, build_expected_block_header, MockBlockBuilder};
 
-pub mod batch;
+mod batch;
+pub use batch::TransactionBatchConstructor;
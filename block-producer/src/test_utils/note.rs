@@ -0,0 +1,32 @@
+use miden_crypto::{rand::RpoRandomCoin, Felt, ZERO};
+use miden_lib::notes::create_p2id_note;
+use miden_mock::constants::ACCOUNT_ID_REGULAR_ACCOUNT_UPDATABLE_CODE_ON_CHAIN;
+use miden_objects::{
+    accounts::AccountId,
+    notes::{Note, Nullifier},
+    Hasher, EMPTY_WORD,
+};
+
+/// Returns a different nullifier for each `index`.
+pub fn nullifier_by_index(index: u32) -> Nullifier {
+    Nullifier::new(
+        Hasher::hash(&index.to_be_bytes()),
+        Hasher::hash(
+            &[index.to_be_bytes(), index.to_be_bytes()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>(),
+        ),
+        EMPTY_WORD.into(),
+        [ZERO, ZERO, ZERO, index.into()],
+    )
+}
+
+/// Returns a different public note for each `index`, sent to and consumable by a mock account.
+pub fn note_by_index(index: u32) -> Note {
+    let account_id =
+        AccountId::try_from(ACCOUNT_ID_REGULAR_ACCOUNT_UPDATABLE_CODE_ON_CHAIN).unwrap();
+    let rng = RpoRandomCoin::new([Felt::from(index); 4]);
+
+    create_p2id_note(account_id, account_id, Vec::new(), rng).unwrap()
+}
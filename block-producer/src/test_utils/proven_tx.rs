@@ -33,6 +33,7 @@ static NUM_NOTES_CREATED: Lazy<Arc<Mutex<u64>>> = Lazy::new(|| Arc::new(Mutex::n
 pub struct MockProvenTxBuilder {
     mock_account: MockPrivateAccount,
     notes_created: Option<Vec<NoteEnvelope>>,
+    block_ref: Digest,
 }
 
 impl MockProvenTxBuilder {
@@ -49,9 +50,19 @@ impl MockProvenTxBuilder {
         Self {
             mock_account: account_index.into(),
             notes_created: None,
+            block_ref: Digest::default(),
         }
     }
 
+    pub fn block_ref(
+        mut self,
+        block_ref: Digest,
+    ) -> Self {
+        self.block_ref = block_ref;
+
+        self
+    }
+
     pub fn num_notes_created(
         mut self,
         num_notes_created_in_tx: u64,
@@ -82,7 +93,7 @@ impl MockProvenTxBuilder {
             InputNotes::new(Vec::new()).unwrap(),
             OutputNotes::new(self.notes_created.unwrap_or_default()).unwrap(),
             None,
-            Digest::default(),
+            self.block_ref,
             ExecutionProof::new(StarkProof::new_dummy(), HashFunction::Blake3_192),
         )
     }
@@ -1,7 +1,11 @@
+use std::collections::BTreeMap;
+
 use async_trait::async_trait;
 use miden_crypto::merkle::ValuePath;
 use miden_node_proto::domain::{AccountInputRecord, BlockInputs};
-use miden_objects::{crypto::merkle::Mmr, BlockHeader, ACCOUNT_TREE_DEPTH, EMPTY_WORD, ONE, ZERO};
+use miden_objects::{
+    accounts::Account, crypto::merkle::Mmr, BlockHeader, ACCOUNT_TREE_DEPTH, EMPTY_WORD, ONE, ZERO,
+};
 use miden_vm::crypto::SimpleSmt;
 
 use super::*;
@@ -15,8 +19,10 @@ use crate::{
 #[derive(Debug, Default)]
 pub struct MockStoreSuccessBuilder {
     accounts: Option<SimpleSmt<ACCOUNT_TREE_DEPTH>>,
+    account_details: BTreeMap<AccountId, Account>,
     consumed_nullifiers: Option<BTreeSet<Digest>>,
     chain_mmr: Option<Mmr>,
+    pruning_horizon: u32,
 }
 
 impl MockStoreSuccessBuilder {
@@ -42,6 +48,17 @@ impl MockStoreSuccessBuilder {
         self
     }
 
+    /// Sets the states of the public `accounts`, whose hashes must be set by
+    /// [Self::initial_accounts].
+    pub fn initial_account_details(
+        mut self,
+        accounts: impl Iterator<Item = Account>,
+    ) -> Self {
+        self.account_details = accounts.map(|account| (account.id(), account)).collect();
+
+        self
+    }
+
     pub fn initial_nullifiers(
         mut self,
         consumed_nullifiers: BTreeSet<Digest>,
@@ -60,6 +77,15 @@ impl MockStoreSuccessBuilder {
         self
     }
 
+    pub fn pruning_horizon(
+        mut self,
+        pruning_horizon: u32,
+    ) -> Self {
+        self.pruning_horizon = pruning_horizon;
+
+        self
+    }
+
     pub fn build(self) -> MockStoreSuccess {
         let accounts_smt = self.accounts.unwrap_or(SimpleSmt::<ACCOUNT_TREE_DEPTH>::new().unwrap());
         let chain_mmr = self.chain_mmr.unwrap_or_default();
@@ -80,12 +106,19 @@ impl MockStoreSuccessBuilder {
 
         MockStoreSuccess {
             accounts: Arc::new(RwLock::new(accounts_smt)),
+            account_details: Arc::new(RwLock::new(self.account_details)),
             consumed_nullifiers: Arc::new(RwLock::new(
                 self.consumed_nullifiers.unwrap_or_default(),
             )),
             chain_mmr: Arc::new(RwLock::new(chain_mmr)),
+            block_nums: Arc::new(RwLock::new(BTreeMap::from([(
+                initial_block_header.hash(),
+                initial_block_header.block_num(),
+            )]))),
             last_block_header: Arc::new(RwLock::new(initial_block_header)),
+            pruning_horizon: self.pruning_horizon,
             num_apply_block_called: Arc::new(RwLock::new(0)),
+            num_get_tx_inputs_called: Arc::new(RwLock::new(0)),
         }
     }
 }
@@ -94,17 +127,29 @@ pub struct MockStoreSuccess {
     /// Map account id -> account hash
     pub accounts: Arc<RwLock<SimpleSmt<ACCOUNT_TREE_DEPTH>>>,
 
+    /// Map account id -> account state, for the public accounts whose state is known
+    pub account_details: Arc<RwLock<BTreeMap<AccountId, Account>>>,
+
     /// Stores the nullifiers of the notes that were consumed
     pub consumed_nullifiers: Arc<RwLock<BTreeSet<Digest>>>,
 
     // Stores the chain MMR
     pub chain_mmr: Arc<RwLock<Mmr>>,
 
+    /// Maps block hash -> block number
+    pub block_nums: Arc<RwLock<BTreeMap<Digest, u32>>>,
+
     // Stores the header of the last applied block
     pub last_block_header: Arc<RwLock<BlockHeader>>,
 
+    /// The oldest block whose notes are retained
+    pub pruning_horizon: u32,
+
     /// The number of times `apply_block()` was called
     pub num_apply_block_called: Arc<RwLock<u32>>,
+
+    /// The number of times `get_tx_inputs()` was called
+    pub num_get_tx_inputs_called: Arc<RwLock<u32>>,
 }
 
 impl MockStoreSuccess {
@@ -126,8 +171,13 @@ impl ApplyBlock for MockStoreSuccess {
         let mut locked_consumed_nullifiers = self.consumed_nullifiers.write().await;
 
         // update accounts
+        let mut locked_account_details = self.account_details.write().await;
         for &(account_id, account_hash) in block.updated_accounts.iter() {
             locked_accounts.insert(account_id.into(), account_hash.into());
+            locked_account_details.remove(&account_id);
+        }
+        for account in block.account_details.iter() {
+            locked_account_details.insert(account.id(), account.clone());
         }
         debug_assert_eq!(locked_accounts.root(), block.header.account_root());
 
@@ -143,6 +193,11 @@ impl ApplyBlock for MockStoreSuccess {
             chain_mmr.add(block.header.hash());
         }
 
+        self.block_nums
+            .write()
+            .await
+            .insert(block.header.hash(), block.header.block_num());
+
         // update last block header
         *self.last_block_header.write().await = block.header;
 
@@ -159,6 +214,8 @@ impl Store for MockStoreSuccess {
         &self,
         proven_tx: &ProvenTransaction,
     ) -> Result<TxInputs, TxInputsError> {
+        *self.num_get_tx_inputs_called.write().await += 1;
+
         let locked_accounts = self.accounts.read().await;
         let locked_consumed_nullifiers = self.consumed_nullifiers.read().await;
 
@@ -172,6 +229,9 @@ impl Store for MockStoreSuccess {
             }
         };
 
+        let account_details =
+            self.account_details.read().await.get(&proven_tx.account_id()).cloned();
+
         let nullifiers = proven_tx
             .input_notes()
             .iter()
@@ -180,9 +240,14 @@ impl Store for MockStoreSuccess {
             })
             .collect();
 
+        let block_ref_num = self.block_nums.read().await.get(&proven_tx.block_ref()).copied();
+
         Ok(TxInputs {
             account_hash,
+            account_details,
             nullifiers,
+            block_ref_num,
+            pruning_horizon: self.pruning_horizon,
         })
     }
 
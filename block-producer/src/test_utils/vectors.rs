@@ -0,0 +1,410 @@
+//! Canonical test vectors of the block headers.
+//!
+//! Given a fixed genesis and a scripted sequence of transactions, the blocks are built by the same
+//! block kernel as the node, and their headers are returned along with the transactions they
+//! include. Client SDKs validate their own implementation of the roots against these vectors.
+//!
+//! Every input is derived from the script: the accounts are [MockPrivateAccount]s, whose every
+//! transaction moves them to their next state, the created notes are numbered in the order of the
+//! script, the consumed notes are given by the index of their nullifier, see [nullifier_by_index],
+//! and the timestamps are fixed. The same script always gives the same vectors.
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use miden_crypto::hash::rpo::Rpo256;
+use miden_objects::{
+    notes::{NoteEnvelope, NoteMetadata},
+    transaction::{InputNotes, OutputNotes},
+    BlockHeader, Hasher, ONE,
+};
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use crate::{
+    block::Block,
+    block_builder::prover::{block_witness::BlockWitness, BlockKernel},
+    config::TxOrdering,
+    TransactionBatch, MAX_NUM_CREATED_NOTES_PER_BATCH,
+};
+
+// SCRIPT
+// ================================================================================================
+
+/// Sequence of blocks to build, starting from a genesis holding `num_accounts` accounts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Script {
+    /// Number of accounts of the genesis, the account `i` is `MockPrivateAccount::from(i)`.
+    pub num_accounts: u32,
+    /// Timestamp of the first block, in seconds since the UNIX epoch.
+    pub timestamp: u64,
+    /// Number of seconds between two blocks.
+    pub block_interval_s: u64,
+    pub blocks: Vec<ScriptBlock>,
+}
+
+/// The batches of a block, each one a list of transactions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptBlock {
+    #[serde(default)]
+    pub batches: Vec<Vec<ScriptTransaction>>,
+}
+
+/// A transaction of the account `account`, moving it to its next state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptTransaction {
+    pub account: u32,
+    /// Number of notes created by the transaction.
+    #[serde(default)]
+    pub created_notes: u64,
+    /// Indices of the nullifiers of the notes consumed by the transaction.
+    #[serde(default)]
+    pub consumed_notes: Vec<u32>,
+}
+
+// VECTORS
+// ================================================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestVectors {
+    pub genesis: HeaderVector,
+    pub blocks: Vec<BlockVector>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockVector {
+    pub batches: Vec<Vec<TransactionVector>>,
+    pub header: HeaderVector,
+}
+
+/// The inputs of a transaction, ids and digests hex encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionVector {
+    pub account_id: String,
+    pub initial_account_hash: String,
+    pub final_account_hash: String,
+    pub created_notes: Vec<String>,
+    pub nullifiers: Vec<String>,
+}
+
+/// The fields of a block header, digests hex encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeaderVector {
+    pub hash: String,
+    pub prev_hash: String,
+    pub block_num: u32,
+    pub chain_root: String,
+    pub account_root: String,
+    pub nullifier_root: String,
+    pub note_root: String,
+    pub batch_root: String,
+    pub proof_hash: String,
+    pub version: u64,
+    pub timestamp: u64,
+}
+
+impl From<&BlockHeader> for HeaderVector {
+    fn from(header: &BlockHeader) -> Self {
+        Self {
+            hash: header.hash().to_hex(),
+            prev_hash: header.prev_hash().to_hex(),
+            block_num: header.block_num(),
+            chain_root: header.chain_root().to_hex(),
+            account_root: header.account_root().to_hex(),
+            nullifier_root: header.nullifier_root().to_hex(),
+            note_root: header.note_root().to_hex(),
+            batch_root: header.batch_root().to_hex(),
+            proof_hash: header.proof_hash().to_hex(),
+            version: header.version().as_int(),
+            timestamp: header.timestamp().as_int(),
+        }
+    }
+}
+
+// GENERATOR
+// ================================================================================================
+
+/// Builds the blocks of `script`, and returns their headers.
+pub async fn generate(script: &Script) -> Result<TestVectors> {
+    let accounts: Vec<MockPrivateAccount<1>> =
+        (0..script.num_accounts).map(MockPrivateAccount::from).collect();
+    let mut account_states: BTreeMap<u32, Digest> = (0..script.num_accounts)
+        .map(|index| (index, accounts[index as usize].states[0]))
+        .collect();
+
+    let store = MockStoreSuccessBuilder::new()
+        .initial_accounts(accounts.iter().map(|account| (account.id, account.states[0])))
+        .build();
+    let genesis = HeaderVector::from(&*store.last_block_header.read().await);
+
+    let tx_gen = DummyProvenTxGenerator::new();
+    let prover = BlockKernel::new();
+    let mut num_notes_created: u64 = 0;
+    let mut blocks = Vec::with_capacity(script.blocks.len());
+
+    for (block_idx, script_block) in script.blocks.iter().enumerate() {
+        let mut batches = Vec::with_capacity(script_block.batches.len());
+        let mut batch_vectors = Vec::with_capacity(script_block.batches.len());
+
+        for script_batch in &script_block.batches {
+            let mut txs = Vec::with_capacity(script_batch.len());
+            let mut tx_vectors = Vec::with_capacity(script_batch.len());
+
+            for script_tx in script_batch {
+                let account = accounts
+                    .get(script_tx.account as usize)
+                    .ok_or(anyhow!("Unknown account {}", script_tx.account))?;
+                let initial_account_hash = account_states[&script_tx.account];
+                let final_account_hash = Hasher::hash(&initial_account_hash.as_bytes());
+                account_states.insert(script_tx.account, final_account_hash);
+
+                let created_notes: Vec<NoteEnvelope> = (num_notes_created
+                    ..num_notes_created + script_tx.created_notes)
+                    .map(|note_index| {
+                        let note_hash = Rpo256::hash(&note_index.to_be_bytes());
+                        NoteEnvelope::new(note_hash.into(), NoteMetadata::new(account.id, ONE))
+                    })
+                    .collect();
+                num_notes_created += script_tx.created_notes;
+                let nullifiers: Vec<_> =
+                    script_tx.consumed_notes.iter().copied().map(nullifier_by_index).collect();
+
+                tx_vectors.push(TransactionVector {
+                    account_id: format!("{:#018x}", u64::from(account.id)),
+                    initial_account_hash: initial_account_hash.to_hex(),
+                    final_account_hash: final_account_hash.to_hex(),
+                    created_notes: created_notes
+                        .iter()
+                        .map(|note| note.note_id().to_hex())
+                        .collect(),
+                    nullifiers: nullifiers
+                        .iter()
+                        .map(|nullifier| nullifier.inner().to_hex())
+                        .collect(),
+                });
+                txs.push(tx_gen.dummy_proven_tx_with_params(
+                    account.id,
+                    initial_account_hash,
+                    final_account_hash,
+                    InputNotes::new(nullifiers)?,
+                    OutputNotes::new(created_notes)?,
+                ));
+            }
+
+            batches.push(TransactionBatch::new(txs)?);
+            batch_vectors.push(tx_vectors);
+        }
+
+        let updated_accounts: Vec<(AccountId, Digest)> =
+            batches.iter().flat_map(|batch| batch.updated_accounts()).collect();
+        let produced_nullifiers: Vec<Digest> =
+            batches.iter().flat_map(|batch| batch.produced_nullifiers()).collect();
+        let created_notes = batches
+            .iter()
+            .enumerate()
+            .flat_map(|(batch_idx, batch)| {
+                batch.created_notes().enumerate().map(move |(note_idx_in_batch, note)| {
+                    let note_idx_in_block =
+                        batch_idx * MAX_NUM_CREATED_NOTES_PER_BATCH + note_idx_in_batch;
+                    (note_idx_in_block as u64, *note)
+                })
+            })
+            .collect();
+
+        let block_inputs = store
+            .get_block_inputs(
+                updated_accounts.iter().map(|(account_id, _)| account_id),
+                produced_nullifiers.iter(),
+            )
+            .await?;
+        let witness = BlockWitness::new(block_inputs, &batches)?;
+        let timestamp = script.timestamp + block_idx as u64 * script.block_interval_s;
+        let header = prover.prove_at(witness, timestamp.into())?;
+
+        blocks.push(BlockVector {
+            batches: batch_vectors,
+            header: HeaderVector::from(&header),
+        });
+        store
+            .apply_block(Block {
+                header,
+                chain_id: 1,
+                updated_accounts,
+                account_details: Vec::new(),
+                created_notes,
+                produced_nullifiers,
+                consumed_notes: Vec::new(),
+                private_notes: BTreeSet::new(),
+                tx_kernel_versions: BTreeSet::new(),
+                signature: None,
+                timestamp_attestation: None,
+                tx_ordering: TxOrdering::Arrival,
+                batch_proofs: Vec::new(),
+            })
+            .await?;
+    }
+
+    Ok(TestVectors { genesis, blocks })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        generate, BlockVector, HeaderVector, Script, ScriptBlock, ScriptTransaction, TestVectors,
+        TransactionVector,
+    };
+
+    /// Fixed encoding of the vectors of `test_vectors_encoding`, as consumed by the client SDKs, so
+    /// that a change of the format is caught.
+    const ENCODED_VECTORS: &str = r#"{
+  "genesis": {
+    "hash": "0x01",
+    "prev_hash": "0x00",
+    "block_num": 0,
+    "chain_root": "0x02",
+    "account_root": "0x03",
+    "nullifier_root": "0x04",
+    "note_root": "0x05",
+    "batch_root": "0x06",
+    "proof_hash": "0x07",
+    "version": 1,
+    "timestamp": 1700000000
+  },
+  "blocks": [
+    {
+      "batches": [
+        [
+          {
+            "account_id": "0x0000000000000001",
+            "initial_account_hash": "0x0a",
+            "final_account_hash": "0x0b",
+            "created_notes": [
+              "0x0c"
+            ],
+            "nullifiers": []
+          }
+        ]
+      ],
+      "header": {
+        "hash": "0x11",
+        "prev_hash": "0x01",
+        "block_num": 1,
+        "chain_root": "0x12",
+        "account_root": "0x13",
+        "nullifier_root": "0x14",
+        "note_root": "0x15",
+        "batch_root": "0x16",
+        "proof_hash": "0x17",
+        "version": 1,
+        "timestamp": 1700000010
+      }
+    }
+  ]
+}"#;
+
+    /// A script, with the optional fields of its transactions missing.
+    const SCRIPT: &str = r#"
+        num_accounts = 2
+        timestamp = 1700000000
+        block_interval_s = 10
+
+        [[blocks]]
+
+        [[blocks]]
+        batches = [[{ account = 0, created_notes = 2 }, { account = 1, consumed_notes = [0] }]]
+    "#;
+
+    #[tokio::test]
+    async fn test_generate_vectors() {
+        let script: Script = toml::from_str(include_str!("../../test-vectors.toml")).unwrap();
+
+        let vectors = generate(&script).await.unwrap();
+        assert_eq!(vectors.blocks.len(), 3);
+
+        // the blocks are chained to the genesis
+        let mut prev_header = &vectors.genesis;
+        for block in &vectors.blocks {
+            assert_eq!(block.header.prev_hash, prev_header.hash);
+            assert_eq!(block.header.block_num, prev_header.block_num + 1);
+            prev_header = &block.header;
+        }
+        assert_eq!(
+            vectors.blocks[2].header.timestamp,
+            script.timestamp + 2 * script.block_interval_s
+        );
+
+        // the transactions of an account follow each other
+        let first = &vectors.blocks[1].batches[0][0];
+        let second = &vectors.blocks[2].batches[0][0];
+        assert_eq!(first.account_id, second.account_id);
+        assert_eq!(first.final_account_hash, second.initial_account_hash);
+
+        // the same script always gives the same vectors
+        assert_eq!(generate(&script).await.unwrap(), vectors);
+    }
+
+    #[test]
+    fn test_vectors_encoding() {
+        let header = |base: u8, block_num: u32, prev_hash: &str, timestamp: u64| HeaderVector {
+            hash: format!("{:#04x}", base + 1),
+            prev_hash: prev_hash.to_string(),
+            block_num,
+            chain_root: format!("{:#04x}", base + 2),
+            account_root: format!("{:#04x}", base + 3),
+            nullifier_root: format!("{:#04x}", base + 4),
+            note_root: format!("{:#04x}", base + 5),
+            batch_root: format!("{:#04x}", base + 6),
+            proof_hash: format!("{:#04x}", base + 7),
+            version: 1,
+            timestamp,
+        };
+        let vectors = TestVectors {
+            genesis: header(0x00, 0, "0x00", 1700000000),
+            blocks: vec![BlockVector {
+                batches: vec![vec![TransactionVector {
+                    account_id: "0x0000000000000001".to_string(),
+                    initial_account_hash: "0x0a".to_string(),
+                    final_account_hash: "0x0b".to_string(),
+                    created_notes: vec!["0x0c".to_string()],
+                    nullifiers: Vec::new(),
+                }]],
+                header: header(0x10, 1, "0x01", 1700000010),
+            }],
+        };
+
+        assert_eq!(serde_json::to_string_pretty(&vectors).unwrap(), ENCODED_VECTORS);
+        assert_eq!(serde_json::from_str::<TestVectors>(ENCODED_VECTORS).unwrap(), vectors);
+    }
+
+    #[test]
+    fn test_script_decoding() {
+        let script: Script = toml::from_str(SCRIPT).unwrap();
+
+        assert_eq!(
+            script,
+            Script {
+                num_accounts: 2,
+                timestamp: 1700000000,
+                block_interval_s: 10,
+                blocks: vec![
+                    ScriptBlock {
+                        batches: Vec::new()
+                    },
+                    ScriptBlock {
+                        batches: vec![vec![
+                            ScriptTransaction {
+                                account: 0,
+                                created_notes: 2,
+                                consumed_notes: Vec::new(),
+                            },
+                            ScriptTransaction {
+                                account: 1,
+                                created_notes: 0,
+                                consumed_notes: vec![0],
+                            },
+                        ]],
+                    },
+                ],
+            }
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Attestation of the time the blocks were produced by a time authority.
+//!
+//! The timestamp of a block header is taken from the block producer's clock. Once a block is
+//! applied its hash is posted as JSON to the configured time authority, which answers with the time
+//! it saw the hash, signed with its key. The attestation is carried with the next block into the
+//! store, for the consumers trusting the authority to bound the time the block was produced.
+//!
+//! The attestation is requested in the background, a slow authority doesn't hold the production of
+//! the blocks back. It is optional: a block whose parent wasn't attested in time is applied without
+//! one.
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use hyper::{
+    body,
+    client::HttpConnector,
+    header::{CONTENT_TYPE, USER_AGENT},
+    Body, Client, Method, Request, Uri,
+};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use miden_crypto::utils::Deserializable;
+use miden_node_proto::domain::TimestampAttestation;
+use miden_objects::Digest;
+use serde::{Deserialize, Serialize};
+use tokio::time;
+
+use crate::{config::TimeAuthorityConfig, errors::TimeAttestationError};
+
+#[cfg(test)]
+mod tests;
+
+// MESSAGES
+// ================================================================================================
+
+/// Body posted to the time authority.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationRequest {
+    /// Identifier of the chain of the block
+    pub chain_id: u32,
+
+    /// Hex encoded hash of the block header
+    pub block_hash: String,
+}
+
+/// Answer of the time authority, see [TimestampAttestation].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationResponse {
+    /// Hex encoded public key of the time authority
+    pub authority_key: String,
+
+    /// Time at which the authority saw the block, in seconds since the UNIX epoch
+    pub timestamp: u64,
+
+    /// Hex encoded signature of [TimestampAttestation::message] by `authority_key`
+    pub signature: String,
+}
+
+// TIME AUTHORITY
+// ================================================================================================
+
+/// Client of the time authority attesting the blocks.
+pub struct TimeAuthority {
+    url: Uri,
+    authority_key: Digest,
+    /// Identifier of the chain whose blocks are attested
+    chain_id: u32,
+    timeout: Duration,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl TimeAuthority {
+    /// Returns a client of the time authority at `config.url` attesting the blocks of the chain
+    /// `chain_id`, or `None` if it isn't set.
+    pub fn from_config(
+        config: &TimeAuthorityConfig,
+        chain_id: u32,
+    ) -> Result<Option<Self>> {
+        let Some(url) = &config.url else {
+            return Ok(None);
+        };
+        let url = url.parse().with_context(|| format!("Invalid time authority URL {url}"))?;
+        let authority_key = config
+            .authority_key
+            .as_deref()
+            .ok_or_else(|| anyhow!("The key of the time authority is not configured"))?;
+        let authority_key = parse_hex(authority_key)
+            .ok_or_else(|| anyhow!("Invalid time authority key {authority_key}"))?;
+        let connector = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Ok(Some(Self {
+            url,
+            authority_key,
+            chain_id,
+            timeout: Duration::from_millis(config.timeout_ms),
+            client: Client::builder().build(connector),
+        }))
+    }
+
+    /// Returns the public key of the time authority.
+    pub fn authority_key(&self) -> Digest {
+        self.authority_key
+    }
+
+    /// Requests the attestation of the block `block_hash`, checked to be signed by the configured
+    /// authority.
+    pub async fn attest(
+        &self,
+        block_hash: Digest,
+    ) -> Result<TimestampAttestation, TimeAttestationError> {
+        let body = AttestationRequest {
+            chain_id: self.chain_id,
+            block_hash: block_hash.to_hex(),
+        };
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(self.url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(USER_AGENT, concat!("miden-node-block-producer/", env!("CARGO_PKG_VERSION")))
+            .body(Body::from(
+                serde_json::to_vec(&body).expect("the attestation requests serialize to JSON"),
+            ))
+            .expect("the request is valid");
+
+        let response = match time::timeout(self.timeout, self.client.request(request)).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(err)) => return Err(TimeAttestationError::RequestFailed(err.to_string())),
+            Err(_) => return Err(TimeAttestationError::TimedOut),
+        };
+        if !response.status().is_success() {
+            return Err(TimeAttestationError::UnexpectedStatus(response.status().as_u16()));
+        }
+        let body = body::to_bytes(response.into_body())
+            .await
+            .map_err(|err| TimeAttestationError::RequestFailed(err.to_string()))?;
+        let response: AttestationResponse = serde_json::from_slice(&body)
+            .map_err(|err| TimeAttestationError::InvalidResponse(err.to_string()))?;
+
+        let attestation = parse_response(&response)?;
+        if attestation.authority_key != self.authority_key {
+            return Err(TimeAttestationError::UnexpectedKey(attestation.authority_key));
+        }
+        if !attestation.verify(self.chain_id, block_hash) {
+            return Err(TimeAttestationError::InvalidSignature);
+        }
+
+        Ok(attestation)
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+fn parse_response(
+    response: &AttestationResponse
+) -> Result<TimestampAttestation, TimeAttestationError> {
+    let authority_key = parse_hex(&response.authority_key).ok_or_else(|| {
+        TimeAttestationError::InvalidResponse(format!(
+            "invalid authority key {}",
+            response.authority_key
+        ))
+    })?;
+    let signature = parse_hex(&response.signature)
+        .ok_or_else(|| TimeAttestationError::InvalidResponse("invalid signature".to_string()))?;
+
+    Ok(TimestampAttestation {
+        authority_key,
+        timestamp: response.timestamp,
+        signature,
+    })
+}
+
+/// Decodes `value` from its hex encoded bytes, with or without the `0x` prefix.
+fn parse_hex<T: Deserializable>(value: &str) -> Option<T> {
+    let bytes = hex::decode(value.strip_prefix("0x").unwrap_or(value)).ok()?;
+    T::read_from_bytes(&bytes).ok()
+}
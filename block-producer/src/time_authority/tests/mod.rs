@@ -0,0 +1,130 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Response, Server, StatusCode,
+};
+use miden_crypto::{dsa::rpo_falcon512::KeyPair, utils::Serializable, Word, ONE};
+
+use super::*;
+
+// HELPERS
+// ================================================================================================
+
+const CHAIN_ID: u32 = 1;
+
+/// Serves a time authority attesting the posted blocks with `keypair` at `timestamp`, or answering
+/// with `status` if set, and returns its address.
+fn time_authority(
+    keypair: KeyPair,
+    timestamp: u64,
+    status: Option<StatusCode>,
+) -> SocketAddr {
+    let keypair = Arc::new(keypair);
+
+    let make_service = make_service_fn(move |_| {
+        let keypair = keypair.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                let keypair = keypair.clone();
+                async move {
+                    if let Some(status) = status {
+                        return Ok::<_, Infallible>(
+                            Response::builder().status(status).body(Body::empty()).unwrap(),
+                        );
+                    }
+
+                    let body = body::to_bytes(request.into_body()).await.unwrap();
+                    let request: AttestationRequest = serde_json::from_slice(&body).unwrap();
+                    let block_hash = parse_hex(&request.block_hash).unwrap();
+                    let attestation = TimestampAttestation::sign(
+                        &keypair,
+                        request.chain_id,
+                        block_hash,
+                        timestamp,
+                    )
+                    .unwrap();
+                    let response = AttestationResponse {
+                        authority_key: attestation.authority_key.to_hex(),
+                        timestamp: attestation.timestamp,
+                        signature: hex::encode(attestation.signature.to_bytes()),
+                    };
+
+                    Ok::<_, Infallible>(Response::new(Body::from(
+                        serde_json::to_vec(&response).unwrap(),
+                    )))
+                }
+            }))
+        }
+    });
+
+    let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+
+    addr
+}
+
+fn client(
+    addr: SocketAddr,
+    authority_key: Digest,
+) -> TimeAuthority {
+    let config = TimeAuthorityConfig {
+        url: Some(format!("http://{addr}/attest")),
+        authority_key: Some(authority_key.to_hex()),
+        timeout_ms: 5000,
+    };
+
+    TimeAuthority::from_config(&config, CHAIN_ID).unwrap().unwrap()
+}
+
+fn public_key(keypair: &KeyPair) -> Digest {
+    Word::from(keypair.public_key()).into()
+}
+
+// TESTS
+// ================================================================================================
+
+/// Tests that the attestation of the configured time authority is returned
+#[tokio::test]
+async fn test_attestation() {
+    let keypair = KeyPair::new().unwrap();
+    let authority_key = public_key(&keypair);
+    let addr = time_authority(keypair, 1_700_000_000, None);
+    let block_hash = Digest::new([ONE, ONE, ONE, ONE]);
+
+    let attestation = client(addr, authority_key).attest(block_hash).await.unwrap();
+    assert_eq!(attestation.authority_key, authority_key);
+    assert_eq!(attestation.timestamp, 1_700_000_000);
+    assert!(attestation.verify(CHAIN_ID, block_hash));
+}
+
+/// Tests that the attestations of another authority, or answered with an error, are rejected
+#[tokio::test]
+async fn test_attestation_is_rejected() {
+    let keypair = KeyPair::new().unwrap();
+    let other_key = public_key(&KeyPair::new().unwrap());
+    let addr = time_authority(keypair, 1_700_000_000, None);
+    let block_hash = Digest::new([ONE, ONE, ONE, ONE]);
+
+    let result = client(addr, other_key).attest(block_hash).await;
+    assert!(matches!(result, Err(TimeAttestationError::UnexpectedKey(_))));
+
+    let addr = time_authority(KeyPair::new().unwrap(), 0, Some(StatusCode::SERVICE_UNAVAILABLE));
+    let result = client(addr, other_key).attest(block_hash).await;
+    assert_eq!(result, Err(TimeAttestationError::UnexpectedStatus(503)));
+}
+
+/// Tests that the time authority is only required along with its key
+#[test]
+fn test_config() {
+    assert!(TimeAuthority::from_config(&TimeAuthorityConfig::default(), CHAIN_ID)
+        .unwrap()
+        .is_none());
+
+    let config = TimeAuthorityConfig {
+        url: Some("https://time.example.com/attest".to_string()),
+        ..Default::default()
+    };
+    assert!(TimeAuthority::from_config(&config, CHAIN_ID).is_err());
+}
@@ -0,0 +1,194 @@
+//! Negative acknowledgements of the transactions dropped after they were accepted.
+//!
+//! A transaction accepted by the queue may still be dropped before it is batched, e.g. evicted
+//! from the full queue. Without a trace of it, its submitter can't tell a transaction still waiting
+//! from one which is gone, other than by timing out. The latest dropped transactions are kept to be
+//! reported along the pending ones, and every drop is notified to the webhooks.
+//!
+//! The queued transactions starting from the account state a dropped transaction led to can't be
+//! applied either, they are dropped as orphaned.
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Formatter},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use miden_objects::{accounts::AccountId, transaction::TransactionId, Digest};
+
+use super::account_transition;
+use crate::{
+    notifier::{Event, Notifier},
+    ProvenTransaction,
+};
+
+// DROP REASON
+// ================================================================================================
+
+/// The reason an accepted transaction was dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DropReason {
+    /// Evicted from the full queue to make room for another transaction, see
+    /// [super::EvictionPolicy].
+    Evicted,
+
+    /// Consumes or creates the same notes as another transaction of its batch.
+    Conflicting,
+
+    /// Its proof was found invalid when its batch was built.
+    InvalidProof,
+
+    /// Part of too many batches which failed to be built.
+    BatchFailures,
+
+    /// Not batched while too many blocks were applied, see [super::TransactionQueue::with_ttl].
+    Expired,
+
+    /// Starts from the final account state of a transaction which was dropped.
+    Orphaned,
+}
+
+impl DropReason {
+    /// Returns the name of the reason, as reported to the clients.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DropReason::Evicted => "evicted",
+            DropReason::Conflicting => "conflicting",
+            DropReason::InvalidProof => "invalid_proof",
+            DropReason::BatchFailures => "batch_failures",
+            DropReason::Expired => "expired",
+            DropReason::Orphaned => "orphaned",
+        }
+    }
+}
+
+impl Display for DropReason {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// DROPPED TRANSACTIONS
+// ================================================================================================
+
+/// A transaction which was accepted by the queue, then dropped before being batched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DroppedTransaction {
+    pub tx_id: TransactionId,
+    pub account_id: AccountId,
+    pub reason: DropReason,
+
+    /// Time the transaction was dropped, in milliseconds since the UNIX epoch
+    pub dropped_at: u64,
+}
+
+/// The latest `capacity` transactions dropped by the queue.
+///
+/// A dropped transaction which is submitted and accepted again is forgotten, it is pending again.
+pub struct DroppedTransactions {
+    capacity: usize,
+    dropped: Mutex<VecDeque<DroppedTransaction>>,
+    notifier: Option<Arc<Notifier>>,
+
+    /// The accounts and final states of the transactions dropped since the queue last dropped the
+    /// transactions orphaned by them
+    transitions: Mutex<Vec<(AccountId, Digest)>>,
+}
+
+impl DroppedTransactions {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            dropped: Mutex::new(VecDeque::with_capacity(capacity)),
+            notifier: None,
+            transitions: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Notifies the webhooks of the `notifier` of the dropped transactions.
+    pub fn with_notifier(
+        mut self,
+        notifier: Arc<Notifier>,
+    ) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Records that `tx` was dropped for `reason`.
+    pub fn record(
+        &self,
+        tx: &ProvenTransaction,
+        reason: DropReason,
+    ) {
+        if let Some(transition) = account_transition(tx) {
+            self.transitions
+                .lock()
+                .expect("dropped transactions lock poisoned")
+                .push(transition);
+        }
+
+        {
+            let mut dropped = self.dropped.lock().expect("dropped transactions lock poisoned");
+            dropped.retain(|dropped| dropped.tx_id != tx.id());
+            if dropped.len() == self.capacity {
+                dropped.pop_front();
+            }
+            if self.capacity > 0 {
+                dropped.push_back(DroppedTransaction {
+                    tx_id: tx.id(),
+                    account_id: tx.account_id(),
+                    reason,
+                    dropped_at: now_ms(),
+                });
+            }
+        }
+
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(Event::TransactionDropped {
+                tx_id: tx.id().to_hex(),
+                reason: reason.as_str(),
+            });
+        }
+    }
+
+    /// Forgets the transaction `tx_id`, which was accepted again.
+    pub fn forget(
+        &self,
+        tx_id: TransactionId,
+    ) {
+        self.dropped
+            .lock()
+            .expect("dropped transactions lock poisoned")
+            .retain(|dropped| dropped.tx_id != tx_id);
+    }
+
+    /// Returns the accounts and final states of the transactions dropped since the last call, the
+    /// queued transactions starting from these states are orphaned.
+    pub fn take_transitions(&self) -> Vec<(AccountId, Digest)> {
+        std::mem::take(&mut *self.transitions.lock().expect("dropped transactions lock poisoned"))
+    }
+
+    /// Returns the dropped transactions of `account_id`, the oldest first.
+    pub fn by_account(
+        &self,
+        account_id: AccountId,
+    ) -> Vec<DroppedTransaction> {
+        self.dropped
+            .lock()
+            .expect("dropped transactions lock poisoned")
+            .iter()
+            .filter(|dropped| dropped.account_id == account_id)
+            .copied()
+            .collect()
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
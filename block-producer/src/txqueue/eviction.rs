@@ -0,0 +1,161 @@
+//! Eviction policies of the transaction queue.
+//!
+//! Once the queue holds its maximum number of transactions, every new transaction either replaces
+//! a queued one or is rejected. The [EvictionPolicy] chooses which, so operators can decide who
+//! bears the congestion: late submitters, stale transactions, the accounts flooding the queue, or
+//! the transactions paying the lowest fees.
+use std::collections::BTreeMap;
+
+use miden_objects::accounts::AccountId;
+
+use crate::{
+    config::{EvictionConfig, EvictionPolicyKind},
+    ProvenTransaction,
+};
+
+// EVICTION POLICY
+// ================================================================================================
+
+/// What to drop to make room for a new transaction in a full queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eviction {
+    /// The new transaction is rejected, the queue is unchanged.
+    RejectCandidate,
+
+    /// The queued transaction at the given position is evicted, and the new one is queued.
+    Evict(usize),
+}
+
+/// Decides which transaction is dropped when the queue is full.
+pub trait EvictionPolicy: Send + Sync + 'static {
+    /// Chooses what to drop to admit `candidate` into the full `queue`, whose transactions are
+    /// ordered from the oldest to the newest.
+    fn evict(
+        &self,
+        queue: &[&ProvenTransaction],
+        candidate: &ProvenTransaction,
+    ) -> Eviction;
+}
+
+/// Rejects new transactions while the queue is full.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RejectNewest;
+
+impl EvictionPolicy for RejectNewest {
+    fn evict(
+        &self,
+        _queue: &[&ProvenTransaction],
+        _candidate: &ProvenTransaction,
+    ) -> Eviction {
+        Eviction::RejectCandidate
+    }
+}
+
+/// Evicts the transaction which waited the longest, favoring recently submitted transactions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OldestFirst;
+
+impl EvictionPolicy for OldestFirst {
+    fn evict(
+        &self,
+        queue: &[&ProvenTransaction],
+        _candidate: &ProvenTransaction,
+    ) -> Eviction {
+        if queue.is_empty() {
+            Eviction::RejectCandidate
+        } else {
+            Eviction::Evict(0)
+        }
+    }
+}
+
+/// Evicts the newest transaction of the account with the most queued transactions, if it has more
+/// than `max_per_account` of them. New transactions of accounts at their cap, and new transactions
+/// while no account is over its cap, are rejected.
+///
+/// A single account can't hold more than `max_per_account` of the queue's capacity at the expense
+/// of the others.
+#[derive(Debug, Clone, Copy)]
+pub struct AccountCap {
+    pub max_per_account: usize,
+}
+
+impl EvictionPolicy for AccountCap {
+    fn evict(
+        &self,
+        queue: &[&ProvenTransaction],
+        candidate: &ProvenTransaction,
+    ) -> Eviction {
+        let mut queued: BTreeMap<AccountId, (usize, usize)> = BTreeMap::new();
+        for (position, tx) in queue.iter().enumerate() {
+            let (count, newest) = queued.entry(tx.account_id()).or_default();
+            *count += 1;
+            *newest = position;
+        }
+
+        let candidate_count = queued.get(&candidate.account_id()).map_or(0, |(count, _)| *count);
+        if candidate_count >= self.max_per_account {
+            return Eviction::RejectCandidate;
+        }
+
+        match queued.values().max_by_key(|(count, _)| *count) {
+            Some(&(count, newest)) if count > self.max_per_account => Eviction::Evict(newest),
+            _ => Eviction::RejectCandidate,
+        }
+    }
+}
+
+/// Evicts the queued transaction paying the lowest fee, the oldest one among those paying the same
+/// fee, if `candidate` pays a higher fee. `candidate` is rejected otherwise.
+///
+/// The fee of a transaction is given by `fee`, [transaction_fee] by default.
+#[derive(Debug, Clone, Copy)]
+pub struct LowestFeeFirst {
+    pub fee: fn(&ProvenTransaction) -> u64,
+}
+
+impl Default for LowestFeeFirst {
+    fn default() -> Self {
+        Self {
+            fee: transaction_fee,
+        }
+    }
+}
+
+impl EvictionPolicy for LowestFeeFirst {
+    fn evict(
+        &self,
+        queue: &[&ProvenTransaction],
+        candidate: &ProvenTransaction,
+    ) -> Eviction {
+        // `min_by_key` returns the first of the minimums, i.e. the oldest one
+        let lowest = queue.iter().enumerate().min_by_key(|(_, tx)| (self.fee)(tx));
+        match lowest {
+            Some((position, tx)) if (self.fee)(tx) < (self.fee)(candidate) => {
+                Eviction::Evict(position)
+            },
+            _ => Eviction::RejectCandidate,
+        }
+    }
+}
+
+/// Returns the fee paid by `tx`.
+///
+/// TODO: return the fee paid by the transaction once transactions pay fees, `ProvenTransaction`
+/// has no field for them yet. Until then, every transaction pays no fee, and [LowestFeeFirst]
+/// rejects the new transactions like [RejectNewest].
+pub fn transaction_fee(_tx: &ProvenTransaction) -> u64 {
+    0
+}
+
+/// Returns the policy selected by `config`.
+pub fn policy_from_config(config: &EvictionConfig) -> Box<dyn EvictionPolicy> {
+    match config.policy {
+        EvictionPolicyKind::RejectNewest => Box::new(RejectNewest),
+        EvictionPolicyKind::OldestFirst => Box::new(OldestFirst),
+        EvictionPolicyKind::AccountCap => Box::new(AccountCap {
+            max_per_account: config.max_per_account,
+        }),
+        EvictionPolicyKind::LowestFeeFirst => Box::new(LowestFeeFirst::default()),
+    }
+}
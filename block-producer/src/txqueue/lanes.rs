@@ -0,0 +1,167 @@
+//! Prioritization lanes of the transaction queue.
+//!
+//! Transactions consuming notes, e.g. withdrawals and claims, are guaranteed a share of every
+//! batch selection, so a flood of transactions which only create notes can't starve them. The
+//! transactions of the partners of the block producer are guaranteed their own share ahead of
+//! them, see [super::Reservations].
+use std::{
+    collections::BTreeSet,
+    fmt::{Display, Formatter},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use miden_objects::accounts::AccountId;
+
+use crate::ProvenTransaction;
+
+// LANE
+// ================================================================================================
+
+/// The lane of a queued transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Lane {
+    /// Transactions consuming at least one note.
+    Consuming,
+
+    /// Transactions which don't consume any note.
+    Creating,
+}
+
+impl Lane {
+    pub const ALL: [Lane; 2] = [Lane::Consuming, Lane::Creating];
+
+    /// Returns the lane of `tx`.
+    pub fn of(tx: &ProvenTransaction) -> Self {
+        if tx.input_notes().is_empty() {
+            Lane::Creating
+        } else {
+            Lane::Consuming
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Lane::Consuming => 0,
+            Lane::Creating => 1,
+        }
+    }
+}
+
+impl Display for Lane {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Lane::Consuming => f.write_str("consuming"),
+            Lane::Creating => f.write_str("creating"),
+        }
+    }
+}
+
+// LANE SELECTION
+// ================================================================================================
+
+/// Selects up to `capacity` transactions out of `queue`, the lanes, accounts and partners of the
+/// queued transactions in queue order, and returns the positions of the selected transactions in
+/// queue order.
+///
+/// The oldest transactions of every partner are selected first, up to the number of transactions
+/// in `reserved_partners` for that partner, then the oldest consuming transactions, up to
+/// `reserved_consuming` of them. The remaining capacity is filled with the oldest transactions of
+/// either lane. Capacity reserved for partners or consuming transactions and not used by them is
+/// given to the other transactions.
+///
+/// A transaction is never selected ahead of an older transaction of the same account, since it
+/// may depend on the account state produced by that transaction.
+pub(super) fn select_transactions(
+    queue: &[(Lane, AccountId, Option<usize>)],
+    capacity: usize,
+    reserved_consuming: usize,
+    reserved_partners: &[usize],
+) -> Vec<usize> {
+    let mut selected = vec![false; queue.len()];
+    let mut num_selected = 0;
+
+    let mut select = |filter: &dyn Fn(Lane, Option<usize>) -> bool, limit: usize| {
+        let mut skipped_accounts = BTreeSet::new();
+        let mut num_filter_selected = 0;
+
+        for (position, &(tx_lane, account_id, tx_partner)) in queue.iter().enumerate() {
+            if num_filter_selected == limit || num_selected == capacity {
+                break;
+            }
+            if selected[position] {
+                continue;
+            }
+
+            if filter(tx_lane, tx_partner) && !skipped_accounts.contains(&account_id) {
+                selected[position] = true;
+                num_selected += 1;
+                num_filter_selected += 1;
+            } else {
+                skipped_accounts.insert(account_id);
+            }
+        }
+    };
+
+    for (partner, &reserved) in reserved_partners.iter().enumerate() {
+        select(&|_, tx_partner| tx_partner == Some(partner), reserved.min(capacity));
+    }
+    select(&|tx_lane, _| tx_lane == Lane::Consuming, reserved_consuming.min(capacity));
+    select(&|_, _| true, capacity);
+
+    selected
+        .into_iter()
+        .enumerate()
+        .filter_map(|(position, selected)| selected.then_some(position))
+        .collect()
+}
+
+// LANE METRICS
+// ================================================================================================
+
+/// Number of transactions of a lane, as reported by [LaneMetrics::report].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LaneStats {
+    /// Transactions accepted into the queue.
+    pub queued: u64,
+
+    /// Transactions selected to be batched.
+    pub selected: u64,
+}
+
+/// Counters of the transactions going through every lane.
+#[derive(Debug, Default)]
+pub struct LaneMetrics {
+    queued: [AtomicU64; 2],
+    selected: [AtomicU64; 2],
+}
+
+impl LaneMetrics {
+    pub(super) fn queued(
+        &self,
+        lane: Lane,
+    ) {
+        self.queued[lane.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn selected(
+        &self,
+        lane: Lane,
+        num_txs: u64,
+    ) {
+        self.selected[lane.index()].fetch_add(num_txs, Ordering::Relaxed);
+    }
+
+    /// Returns the counters of `lane` since the queue was started.
+    pub fn report(
+        &self,
+        lane: Lane,
+    ) -> LaneStats {
+        LaneStats {
+            queued: self.queued[lane.index()].load(Ordering::Relaxed),
+            selected: self.selected[lane.index()].load(Ordering::Relaxed),
+        }
+    }
+}
@@ -1,15 +1,59 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
 use async_trait::async_trait;
-use tokio::{sync::RwLock, time};
-use tracing::{debug, info, info_span, instrument, Instrument};
+use miden_crypto::utils::{Deserializable, Serializable};
+use miden_node_proto::{
+    digest, domain::PartnerSignature, requests::SubmitProvenTransactionRequest,
+};
+use miden_node_utils::formatting::format_blake3_digest;
+use miden_objects::{
+    accounts::{AccountDelta, AccountId},
+    notes::Note,
+    transaction::TransactionId,
+    Digest,
+};
+use tokio::{
+    sync::RwLock,
+    time::{self, Instant},
+};
+use tracing::{debug, info, info_span, instrument, warn, Instrument};
 
 use crate::{
-    batch_builder::BatchBuilder,
-    errors::{AddTransactionError, VerifyTxError},
-    ProvenTransaction, SharedRwVec, COMPONENT,
+    batch_builder::{
+        batch::{order_transitions, tx_precedes},
+        prover::{BatchProof, BatchProofVerifier},
+        BatchBuilder, TransactionBatch,
+    },
+    config::{TxKernelConfig, TxOrdering},
+    errors::{AddBatchError, AddTransactionError, BuildBatchError, VerifyTxError},
+    latency::LatencyTracker,
+    load_shedder::LoadShedder,
+    notifier::Notifier,
+    pacer::BatchPacer,
+    ProvenTransaction, SharedRwVec, COMPONENT, MAX_NUM_CREATED_NOTES_PER_BATCH,
 };
 
+mod dropped;
+mod eviction;
+mod lanes;
+mod reservations;
+mod wal;
+pub use dropped::{DropReason, DroppedTransaction, DroppedTransactions};
+pub use eviction::{
+    policy_from_config, transaction_fee, AccountCap, Eviction, EvictionPolicy, LowestFeeFirst,
+    OldestFirst, RejectNewest,
+};
+pub use lanes::{Lane, LaneMetrics, LaneStats};
+pub use reservations::Reservations;
+pub use wal::TransactionLog;
+
 #[cfg(test)]
 mod tests;
 
@@ -31,27 +75,280 @@ pub trait TransactionVerifier: Send + Sync + 'static {
     ///    in-flight transactions.
     /// 2. Track the necessary state of the transaction until it is commited to the `store`, to
     ///    perform the check above.
+    ///
+    /// `account_delta` holds the changes made by `tx` to the state of its account, which are
+    /// published for public accounts only.
     async fn verify_tx(
         &self,
         tx: &ProvenTransaction,
+        account_delta: Option<&AccountDelta>,
     ) -> Result<(), VerifyTxError>;
+
+    /// Stops tracking `tx`, a verified transaction which was dropped before being batched, e.g.
+    /// evicted from the full queue.
+    ///
+    /// Does nothing by default, for verifiers which don't track in-flight transactions.
+    async fn release_tx(
+        &self,
+        _tx: &ProvenTransaction,
+    ) {
+    }
+
+    /// Returns the number of blocks applied since the verifier was created, the age of the queued
+    /// transactions being measured in blocks, see [TransactionQueue::with_ttl].
+    ///
+    /// Returns `0` by default, for verifiers which don't follow the applied blocks, whose queued
+    /// transactions never expire.
+    fn num_applied_blocks(&self) -> u32 {
+        0
+    }
+
+    /// Returns the number of accounts and nullifiers locked by the transactions in flight, i.e.
+    /// verified but not yet included in an applied block.
+    ///
+    /// Returns no locks by default, for verifiers which don't track in-flight transactions.
+    async fn in_flight(&self) -> InFlight {
+        InFlight::default()
+    }
+}
+
+/// The state locked by the transactions in flight, see [TransactionVerifier::in_flight].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InFlight {
+    /// Number of accounts modified by the transactions, the next transactions of these accounts are
+    /// only accepted if they start from the final state of the last one
+    pub num_accounts: usize,
+
+    /// Number of nullifiers of the notes consumed by the transactions
+    pub num_nullifiers: usize,
+}
+
+// PENDING TRANSACTION
+// ================================================================================================
+
+/// A transaction which was accepted by the queue, but not yet sent to be batched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingTransaction {
+    pub tx_id: TransactionId,
+    pub initial_account_hash: Digest,
+    pub final_account_hash: Digest,
+
+    /// Position of the transaction in the queue, `0` being the next transaction to be batched.
+    pub queue_position: usize,
 }
 
 // TRANSACTION QUEUE
 // ================================================================================================
 
 pub struct TransactionQueueOptions {
-    /// The frequency at which we try to build batches from transactions in the queue
+    /// The frequency at which we check whether the queue contains batches ready to be sealed
     pub build_batch_frequency: Duration,
 
-    /// The size of a batch
+    /// The size of a batch, a batch is sealed as soon as enough transactions are queued to fill it
     pub batch_size: usize,
+
+    /// The maximum time a transaction waits in the queue, a partially filled batch is sealed once
+    /// its oldest transaction exceeds it
+    pub max_batch_latency: Duration,
+
+    /// Percentage of the transactions selected to be batched reserved for transactions consuming
+    /// notes, see [Lane]
+    pub reserved_consuming_percent: u8,
+
+    /// The number of failed batches a transaction is part of before it is dropped
+    pub max_batch_attempts: u32,
+
+    /// The number of dropped transactions kept to be reported, see [DroppedTransactions]
+    pub dropped_history: usize,
+}
+
+/// A transaction in the ready queue, along with what it was submitted with.
+#[derive(Debug, Clone)]
+struct QueuedTransaction {
+    tx: ProvenTransaction,
+
+    /// Version of the transaction kernel the transaction was proven with
+    tx_kernel_version: u32,
+
+    /// Changes the transaction made to its account, if submitted
+    account_delta: Option<AccountDelta>,
+
+    /// Public notes consumed by the transaction, if submitted
+    consumed_notes: Vec<Note>,
+
+    /// Hashes of the notes created by the transaction whose details are private
+    private_notes: Vec<Digest>,
+
+    lane: Lane,
+
+    /// Index of the partner which signed the transaction, see [Reservations]
+    partner: Option<usize>,
+
+    /// Signature of the partner, saved along with the transaction
+    partner_signature: Option<PartnerSignature>,
+
+    /// Time at which the transaction was queued, or queued again after a failed batch
+    queued_at: Instant,
+
+    /// Number of applied blocks when the transaction was first queued, see `ttl_blocks`
+    queued_at_block: u32,
+
+    /// Number of batches the transaction was part of which failed to be built
+    batch_attempts: u32,
+}
+
+impl QueuedTransaction {
+    fn new(
+        tx: ProvenTransaction,
+        tx_kernel_version: u32,
+        account_delta: Option<AccountDelta>,
+    ) -> Self {
+        Self {
+            lane: Lane::of(&tx),
+            tx,
+            tx_kernel_version,
+            account_delta,
+            consumed_notes: Vec::new(),
+            private_notes: Vec::new(),
+            partner: None,
+            partner_signature: None,
+            queued_at: Instant::now(),
+            queued_at_block: 0,
+            batch_attempts: 0,
+        }
+    }
+}
+
+/// A batch built and proven by an external batch producer, waiting in the queue to be sent to the
+/// batch builder, together with its transactions and the number of applied blocks at which it was
+/// queued.
+#[derive(Debug, Clone)]
+struct QueuedBatch {
+    batch: TransactionBatch,
+    txs: Vec<QueuedTransaction>,
+    queued_at_block: u32,
+}
+
+impl QueuedBatch {
+    /// Returns the account transitions of the transactions of the batch, see [account_transition].
+    fn transitions(&self) -> BTreeSet<(AccountId, Digest)> {
+        self.txs.iter().filter_map(|queued| account_transition(&queued.tx)).collect()
+    }
+}
+
+/// The queued transactions and batches orphaned by dropped transactions, see [take_orphaned].
+#[derive(Debug, Default)]
+struct Orphaned {
+    txs: Vec<QueuedTransaction>,
+    batches: Vec<QueuedBatch>,
+}
+
+/// A queued transaction as it was submitted, saved across a restart of the block producer, see
+/// [crate::restart], and to the [TransactionLog].
+#[derive(Debug, Clone)]
+pub struct QueuedSubmission {
+    pub tx: ProvenTransaction,
+    pub tx_kernel_version: u32,
+    pub account_delta: Option<AccountDelta>,
+    pub consumed_notes: Vec<Note>,
+    pub private_notes: Vec<Digest>,
+    /// Signature of the partner the transaction was submitted by, admitting it again against the
+    /// partner's reservation
+    pub partner_signature: Option<PartnerSignature>,
+}
+
+impl QueuedSubmission {
+    /// Returns the submission as it is sent to the block producer.
+    pub fn to_proto(&self) -> SubmitProvenTransactionRequest {
+        submission_request(
+            &self.tx,
+            self.tx_kernel_version,
+            self.account_delta.as_ref(),
+            &self.consumed_notes,
+            &self.private_notes,
+            self.partner_signature.as_ref(),
+        )
+    }
+
+    pub fn from_proto(request: SubmitProvenTransactionRequest) -> Result<Self, String> {
+        let tx = ProvenTransaction::read_from_bytes(&request.transaction)
+            .map_err(|err| format!("invalid transaction: {err}"))?;
+        let account_delta: Option<AccountDelta> = request
+            .account_delta
+            .map(TryInto::try_into)
+            .transpose()
+            .map_err(|err| format!("invalid account delta: {err}"))?;
+        let consumed_notes = parse_consumed_notes(&request.consumed_notes)?;
+        let private_notes = parse_private_notes(&request.private_notes)?;
+        let partner_signature = request
+            .partner_signature
+            .as_ref()
+            .map(PartnerSignature::try_from)
+            .transpose()
+            .map_err(|err| format!("invalid partner signature: {err}"))?;
+
+        Ok(Self {
+            tx,
+            tx_kernel_version: request.tx_kernel_version,
+            account_delta,
+            consumed_notes,
+            private_notes,
+            partner_signature,
+        })
+    }
 }
 
 pub struct TransactionQueue<BB, TV> {
-    ready_queue: SharedRwVec<ProvenTransaction>,
+    ready_queue: SharedRwVec<QueuedTransaction>,
+
+    /// Batches of the external batch producers, in the order they were admitted, see
+    /// [TransactionQueue::add_batch]
+    proven_batches: Arc<RwLock<VecDeque<QueuedBatch>>>,
+
+    /// Verifies the proofs of the batches of the external batch producers
+    batch_proof_verifier: Arc<BatchProofVerifier>,
+
     tx_verifier: Arc<TV>,
     batch_builder: Arc<BB>,
+    load_shedder: Arc<LoadShedder>,
+    latency: Arc<LatencyTracker>,
+    lane_metrics: LaneMetrics,
+
+    /// Number of batches sent to the batch builder which are not built yet
+    batches_in_flight: Arc<AtomicUsize>,
+
+    /// The accounts and final states of the transactions sent to the batch builder which are not
+    /// built yet, the transactions starting from these states wait for their batch
+    transitions_in_batches: Arc<Mutex<BTreeSet<(AccountId, Digest)>>>,
+
+    /// Maximum number of queued transactions, including the ones of the queued batches, `0` for
+    /// an unbounded queue
+    max_queued_transactions: usize,
+
+    /// Decides what to drop when a transaction arrives while the queue is full
+    eviction_policy: Box<dyn EvictionPolicy>,
+
+    /// Versions of the transaction kernel accepted
+    tx_kernel: TxKernelConfig,
+
+    /// Order of the transactions within the batches
+    tx_ordering: TxOrdering,
+
+    /// Slows the batch building down while the block prover falls behind, if set
+    pacer: Option<Arc<BatchPacer>>,
+
+    /// Share of the selected transactions reserved for the partners of the block producer
+    reservations: Reservations,
+
+    /// Latest transactions dropped after they were accepted
+    dropped: Arc<DroppedTransactions>,
+
+    /// Log the accepted transactions are persisted to until they are finished, if enabled
+    tx_log: Option<Arc<TransactionLog>>,
+
+    /// Number of blocks applied after which a queued transaction is dropped, `0` never drops it
+    ttl_blocks: u32,
+
     options: TransactionQueueOptions,
 }
 
@@ -63,17 +360,117 @@ where
     pub fn new(
         tx_verifier: Arc<TV>,
         batch_builder: Arc<BB>,
+        load_shedder: Arc<LoadShedder>,
+        latency: Arc<LatencyTracker>,
         options: TransactionQueueOptions,
     ) -> Self {
         Self {
             ready_queue: Arc::new(RwLock::new(Vec::new())),
+            proven_batches: Arc::new(RwLock::new(VecDeque::new())),
+            batch_proof_verifier: Arc::new(BatchProofVerifier::new()),
             tx_verifier,
             batch_builder,
+            load_shedder,
+            latency,
+            lane_metrics: LaneMetrics::default(),
+            batches_in_flight: Arc::new(AtomicUsize::new(0)),
+            transitions_in_batches: Arc::new(Mutex::new(BTreeSet::new())),
+            max_queued_transactions: 0,
+            eviction_policy: Box::new(RejectNewest),
+            tx_kernel: TxKernelConfig::default(),
+            tx_ordering: TxOrdering::default(),
+            pacer: None,
+            reservations: Reservations::default(),
+            dropped: Arc::new(DroppedTransactions::new(options.dropped_history)),
+            tx_log: None,
+            ttl_blocks: 0,
             options,
         }
     }
 
-    #[instrument(target = "miden-block-producer", name = "block_producer" skip_all)]
+    /// Seals batches at the pace set by `pacer`, instead of as soon as they are ready.
+    pub fn with_pacer(
+        mut self,
+        pacer: Arc<BatchPacer>,
+    ) -> Self {
+        self.pacer = Some(pacer);
+        self
+    }
+
+    /// Accepts the transactions proven with the versions of the transaction kernel of `tx_kernel`,
+    /// instead of the current version only.
+    pub fn with_tx_kernel(
+        mut self,
+        tx_kernel: TxKernelConfig,
+    ) -> Self {
+        self.tx_kernel = tx_kernel;
+        self
+    }
+
+    /// Orders the transactions of the batches as set by `tx_ordering`, instead of as they arrived.
+    pub fn with_tx_ordering(
+        mut self,
+        tx_ordering: TxOrdering,
+    ) -> Self {
+        self.tx_ordering = tx_ordering;
+        self
+    }
+
+    /// Bounds the queue to `max_queued_transactions`, `eviction_policy` deciding what to drop when
+    /// a transaction arrives while the queue is full. The queue is unbounded otherwise.
+    pub fn with_eviction(
+        mut self,
+        max_queued_transactions: usize,
+        eviction_policy: Box<dyn EvictionPolicy>,
+    ) -> Self {
+        self.max_queued_transactions = max_queued_transactions;
+        self.eviction_policy = eviction_policy;
+        self
+    }
+
+    /// Notifies the webhooks of the `notifier` of the transactions dropped after they were accepted.
+    pub fn with_notifier(
+        mut self,
+        notifier: Arc<Notifier>,
+    ) -> Self {
+        self.dropped = Arc::new(
+            DroppedTransactions::new(self.options.dropped_history).with_notifier(notifier),
+        );
+        self
+    }
+
+    /// Reserves part of the selected transactions for the transactions signed by the partners of
+    /// `reservations`, see [TransactionQueue::add_partner_transaction].
+    pub fn with_reservations(
+        mut self,
+        reservations: Reservations,
+    ) -> Self {
+        self.reservations = reservations;
+        self
+    }
+
+    /// Persists the accepted transactions to `tx_log` before they are queued, and marks the dropped
+    /// ones finished. A transaction which can't be persisted is rejected.
+    pub fn with_transaction_log(
+        mut self,
+        tx_log: Arc<TransactionLog>,
+    ) -> Self {
+        self.tx_log = Some(tx_log);
+        self
+    }
+
+    /// Drops the transactions which waited in the queue while `ttl_blocks` blocks were applied,
+    /// see [TransactionQueue::expire_transactions]. The transactions wait until they are batched
+    /// otherwise.
+    pub fn with_ttl(
+        mut self,
+        ttl_blocks: u32,
+    ) -> Self {
+        self.ttl_blocks = ttl_blocks;
+        self
+    }
+
+    #[instrument(target = "miden-block-producer", name = "transaction_queue", skip_all)]
     pub async fn run(self: Arc<Self>) {
         let mut interval = time::interval(self.options.build_batch_frequency);
 
@@ -81,74 +478,1426 @@ where
 
         loop {
             interval.tick().await;
+            self.expire_transactions().await;
+            self.drop_orphaned_transactions().await;
             self.try_build_batches().await;
         }
     }
 
+    /// Drops the queued transactions which were not batched while `ttl_blocks` blocks were
+    /// applied, as counted by [TransactionVerifier::num_applied_blocks].
+    ///
+    /// The accounts and nullifiers of the expired transactions are released, so a transaction
+    /// stuck in the queue doesn't keep the other transactions of its account from being admitted.
+    /// The queued batches expire as a whole, e.g. while the ready batches fill the blocks.
+    #[instrument(target = "miden-block-producer", skip_all)]
+    async fn expire_transactions(&self) {
+        if self.ttl_blocks == 0 {
+            return;
+        }
+
+        let num_applied_blocks = self.tx_verifier.num_applied_blocks();
+        let is_expired = |queued_at_block: u32| {
+            num_applied_blocks.saturating_sub(queued_at_block) >= self.ttl_blocks
+        };
+
+        // the transactions following the expired ones are orphaned along with them
+        let (expired_batches, expired, orphaned) = {
+            let mut locked_ready_queue = self.ready_queue.write().await;
+            let mut locked_batches = self.proven_batches.write().await;
+            let (expired_batches, remaining): (Vec<_>, VecDeque<_>) =
+                locked_batches.drain(..).partition(|queued| is_expired(queued.queued_at_block));
+            *locked_batches = remaining;
+            let (expired, remaining): (Vec<_>, Vec<_>) = locked_ready_queue
+                .drain(..)
+                .partition(|queued| is_expired(queued.queued_at_block));
+            *locked_ready_queue = remaining;
+
+            let expired_transitions = expired
+                .iter()
+                .chain(expired_batches.iter().flat_map(|queued| &queued.txs))
+                .filter_map(|queued| account_transition(&queued.tx))
+                .collect();
+            let orphaned =
+                take_orphaned(&mut locked_ready_queue, &mut locked_batches, expired_transitions);
+            (expired_batches, expired, orphaned)
+        };
+        self.drop_batches(expired_batches, DropReason::Expired).await;
+
+        for queued in &expired {
+            warn!(target: COMPONENT, tx_id = %queued.tx.id().to_hex(), ttl_blocks = self.ttl_blocks, "Transaction dropped, not batched in time");
+            self.tx_verifier.release_tx(&queued.tx).await;
+            self.latency.rejected(queued.tx.id());
+            self.dropped.record(&queued.tx, DropReason::Expired);
+        }
+        if let Some(tx_log) = &self.tx_log {
+            tx_log.finished(expired.iter().map(|queued| queued.tx.id())).await;
+        }
+        self.release_orphaned(orphaned).await;
+    }
+
+    /// Drops the queued transactions starting from the final account state of a dropped
+    /// transaction, which can't be reached anymore, along with the transactions following them.
+    /// A queued batch holding such a transaction is dropped as a whole.
+    ///
+    /// The transactions removed from the queue without being batched take their orphans along in
+    /// the same critical section, and the transactions dropped elsewhere are waited for by the
+    /// transactions following them until they are recorded as dropped. The orphans are also taken
+    /// before the transactions and batches are selected, see [TransactionQueue::try_build_batches],
+    /// so the transactions following a dropped transaction never reach a batch.
+    #[instrument(target = "miden-block-producer", skip_all)]
+    async fn drop_orphaned_transactions(&self) {
+        let dropped_transitions: BTreeSet<(AccountId, Digest)> =
+            self.dropped.take_transitions().into_iter().collect();
+        if dropped_transitions.is_empty() {
+            return;
+        }
+
+        let orphaned = {
+            let mut locked_ready_queue = self.ready_queue.write().await;
+            let mut locked_batches = self.proven_batches.write().await;
+            take_orphaned(&mut locked_ready_queue, &mut locked_batches, dropped_transitions)
+        };
+        self.release_orphaned(orphaned).await;
+    }
+
+    /// Releases the `orphaned` transactions and batches, taken out of the queue.
+    async fn release_orphaned(
+        &self,
+        orphaned: Orphaned,
+    ) {
+        self.drop_batches(orphaned.batches, DropReason::Orphaned).await;
+
+        for queued in &orphaned.txs {
+            warn!(target: COMPONENT, tx_id = %queued.tx.id().to_hex(), "Transaction dropped, the transaction it follows was dropped");
+            self.tx_verifier.release_tx(&queued.tx).await;
+            self.latency.rejected(queued.tx.id());
+            self.dropped.record(&queued.tx, DropReason::Orphaned);
+        }
+        if let Some(tx_log) = &self.tx_log {
+            tx_log.finished(orphaned.txs.iter().map(|queued| queued.tx.id())).await;
+        }
+    }
+
     /// Divides the queue in groups to be batched; those that failed are appended back on the queue
+    ///
+    /// Only full batches are sealed, unless the oldest transaction in the queue waited longer than
+    /// the maximum batch latency, in which case all queued transactions are sealed. At most
+    /// [LoadShedder::max_concurrent_batches] batches are built concurrently, the remaining
+    /// transactions stay in the queue.
+    ///
+    /// Part of the selected transactions is reserved for the partners of the block producer, then
+    /// for the [Lane::Consuming] lane, the remaining ones are the oldest transactions of either
+    /// lane. Reservations not used in a round expire, see [Reservations].
+    ///
+    /// A batch is sealed early once its transactions create as many notes as fit in a batch, see
+    /// [batch_sizes]. The transactions of a batch are ordered by id if the [TxOrdering] is
+    /// canonical, the selection of the transactions is unaffected. The transactions of an account
+    /// follow the transaction they start from, see [select_chained_transactions].
+    ///
+    /// While the [BatchPacer] slows the batch building down, the rounds it doesn't allow are
+    /// skipped.
+    ///
+    /// The batches of the external batch producers are sent first, taking at most half of the
+    /// batches of the round while transactions wait in the queue, see
+    /// [TransactionQueue::send_proven_batches].
+    ///
+    /// The transactions of a batch which failed to be built are queued again, unless they were part
+    /// of `max_batch_attempts` failed batches, or conflict with the other transactions of the batch,
+    /// in which case they are dropped. The transactions and batches following a dropped transaction
+    /// are dropped before the others are selected, see
+    /// [TransactionQueue::drop_orphaned_transactions].
     #[instrument(target = "miden-block-producer", skip_all)]
     async fn try_build_batches(&self) {
-        let txs: Vec<ProvenTransaction> = {
+        if self.pacer.as_ref().is_some_and(|pacer| !pacer.start_round()) {
+            debug!(target: COMPONENT, "Batch building slowed down, round skipped");
+            return;
+        }
+
+        let max_new_batches = self
+            .load_shedder
+            .max_concurrent_batches()
+            .saturating_sub(self.batches_in_flight.load(Ordering::Relaxed));
+        if max_new_batches == 0 {
+            debug!(target: COMPONENT, "Maximum number of concurrent batches reached");
+            return;
+        }
+
+        let max_proven_batches = if self.ready_queue.read().await.is_empty() {
+            max_new_batches
+        } else {
+            max_new_batches.div_ceil(2)
+        };
+        let max_new_batches = max_new_batches - self.send_proven_batches(max_proven_batches).await;
+        if max_new_batches == 0 {
+            debug!(target: COMPONENT, "Maximum number of concurrent batches reached");
+            return;
+        }
+
+        let (orphaned, selected) = {
             let mut locked_ready_queue = self.ready_queue.write().await;
 
-            // If there are no transactions in the queue, this call is a no-op. The [BatchBuilder]
-            // will produce empty blocks if necessary.
-            if locked_ready_queue.is_empty() {
-                debug!(target: COMPONENT, "Transaction queue empty");
-                return;
-            }
+            // the transactions following a dropped transaction are dropped before they can be
+            // selected, see [TransactionQueue::drop_orphaned_transactions]
+            let dropped_transitions = self.dropped.take_transitions().into_iter().collect();
+            let orphaned = take_orphaned(
+                &mut locked_ready_queue,
+                &mut *self.proven_batches.write().await,
+                dropped_transitions,
+            );
 
-            locked_ready_queue.drain(..).collect()
+            (
+                orphaned,
+                self.select_transactions(&mut locked_ready_queue, max_new_batches).await,
+            )
+        };
+        self.release_orphaned(orphaned).await;
+        let Some((txs, batch_sizes)) = selected else {
+            return;
         };
 
-        let tx_groups = txs.chunks(self.options.batch_size).map(|txs| txs.to_vec());
+        for lane in Lane::ALL {
+            let num_selected = txs.iter().filter(|queued| queued.lane == lane).count();
+            self.lane_metrics.selected(lane, num_selected as u64);
+        }
+        let consuming = self.lane_metrics.report(Lane::Consuming);
+        let creating = self.lane_metrics.report(Lane::Creating);
+        info!(
+            target: COMPONENT,
+            num_txs = txs.len(),
+            consuming_queued = consuming.queued,
+            consuming_selected = consuming.selected,
+            creating_queued = creating.queued,
+            creating_selected = creating.selected,
+            "Transactions selected to be batched"
+        );
+        for partner in txs.iter().filter_map(|queued| queued.partner).collect::<BTreeSet<_>>() {
+            let num_selected = txs.iter().filter(|queued| queued.partner == Some(partner)).count();
+            debug!(target: COMPONENT, partner = self.reservations.name(partner), num_selected, "Partner transactions selected");
+        }
+
+        self.latency.dequeued(txs.iter().map(|queued| queued.tx.id()));
+
+        let mut unbatched = txs.as_slice();
+        for batch_size in batch_sizes {
+            let (group, rest) = unbatched.split_at(batch_size);
+            unbatched = rest;
+
+            let mut txs: Vec<ProvenTransaction> =
+                group.iter().map(|queued| queued.tx.clone()).collect();
+            if self.tx_ordering == TxOrdering::Canonical {
+                txs.sort_by_key(|tx| tx.id());
+                txs = order_transitions(txs, tx_precedes);
+            }
+            let transitions: Vec<(AccountId, Digest)> =
+                txs.iter().filter_map(account_transition).collect();
+            let note_links: Vec<(Digest, Digest)> = group
+                .iter()
+                .flat_map(|queued| consumed_note_links(&queued.consumed_notes))
+                .collect();
+            // the transactions as queued, put back in the queue if the batch fails to be built
+            let mut queued: BTreeMap<TransactionId, QueuedTransaction> =
+                group.iter().map(|queued| (queued.tx.id(), queued.clone())).collect();
+            let max_batch_attempts = self.options.max_batch_attempts;
 
-        for txs in tx_groups {
             let ready_queue = self.ready_queue.clone();
             let batch_builder = self.batch_builder.clone();
+            let batches_in_flight = self.batches_in_flight.clone();
+            let tx_verifier = self.tx_verifier.clone();
+            let latency = self.latency.clone();
+            let dropped = self.dropped.clone();
+            let tx_log = self.tx_log.clone();
+            let transitions_in_batches = self.transitions_in_batches.clone();
+            batches_in_flight.fetch_add(1, Ordering::Relaxed);
 
             tokio::spawn(
                 async move {
-                    match batch_builder.build_batch(txs).await {
+                    let versions: BTreeSet<u32> =
+                        queued.values().map(|queued| queued.tx_kernel_version).collect();
+                    let batch_private_notes: Vec<Digest> = queued
+                        .values()
+                        .flat_map(|queued| queued.private_notes.iter().copied())
+                        .collect();
+                    let result = batch_builder
+                        .build_batch(txs, versions, note_links, batch_private_notes)
+                        .await;
+                    batches_in_flight.fetch_sub(1, Ordering::Relaxed);
+
+                    match result {
                         Ok(_) => {
                             // batch was successfully built, do nothing
                         },
                         Err(e) => {
-                            // batch building failed, add txs back at the end of the queue
-                            ready_queue.write().await.append(&mut e.into_transactions());
+                            let rejected = match &e {
+                                BuildBatchError::ConflictingTransactions { rejected, .. } => {
+                                    Some((rejected, DropReason::Conflicting))
+                                },
+                                BuildBatchError::InvalidTransactionProofs { rejected, .. } => {
+                                    Some((rejected, DropReason::InvalidProof))
+                                },
+                                _ => None,
+                            };
+                            if let Some((rejected, reason)) = rejected {
+                                for tx in rejected {
+                                    dropped.record(tx, reason);
+                                }
+                                if let Some(tx_log) = &tx_log {
+                                    tx_log.finished(rejected.iter().map(|tx| tx.id())).await;
+                                }
+                            }
+
+                            // batch building failed, add txs back at the end of the queue, unless
+                            // they failed too many times already
+                            let (requeued, exhausted): (Vec<_>, Vec<_>) = e
+                                .into_transactions()
+                                .into_iter()
+                                .map(|tx| {
+                                    let mut queued = queued
+                                        .remove(&tx.id())
+                                        .expect("failed transactions must be of the batch");
+                                    queued.queued_at = Instant::now();
+                                    queued.batch_attempts += 1;
+                                    queued
+                                })
+                                .partition(|queued| queued.batch_attempts < max_batch_attempts);
+                            ready_queue.write().await.extend(requeued);
+
+                            if let Some(tx_log) = &tx_log {
+                                tx_log.finished(exhausted.iter().map(|queued| queued.tx.id())).await;
+                            }
+                            for QueuedTransaction { tx, .. } in exhausted {
+                                warn!(target: COMPONENT, tx_id = %tx.id().to_hex(), max_batch_attempts, "Transaction dropped, too many failed batches");
+                                tx_verifier.release_tx(&tx).await;
+                                latency.rejected(tx.id());
+                                dropped.record(&tx, DropReason::BatchFailures);
+                            }
                         },
                     }
+
+                    // the failed transactions are back in the queue, or dropped, before the
+                    // transactions following them can be selected
+                    {
+                        let mut locked_transitions_in_batches =
+                            transitions_in_batches.lock().expect("transitions lock poisoned");
+                        for transition in &transitions {
+                            locked_transitions_in_batches.remove(transition);
+                        }
+                    }
+                }
+                .instrument(info_span!(target: COMPONENT, "batch_builder")),
+            );
+        }
+    }
+
+    /// Removes the transactions to be batched from the `ready_queue`, in at most `max_new_batches`
+    /// batches, see [TransactionQueue::try_build_batches].
+    ///
+    /// Returns the selected transactions and the sizes of their batches, or `None` if no batch is
+    /// ready to be sealed.
+    async fn select_transactions(
+        &self,
+        ready_queue: &mut Vec<QueuedTransaction>,
+        max_new_batches: usize,
+    ) -> Option<(Vec<QueuedTransaction>, Vec<usize>)> {
+        // If there are no transactions in the queue, this call is a no-op. The [BatchBuilder]
+        // will produce empty blocks if necessary.
+        let Some(oldest_tx) = ready_queue.first() else {
+            debug!(target: COMPONENT, "Transaction queue empty");
+            return None;
+        };
+
+        let num_txs = if oldest_tx.queued_at.elapsed() >= self.options.max_batch_latency {
+            ready_queue.len()
+        } else {
+            ready_queue.len() - ready_queue.len() % self.options.batch_size
+        };
+        let num_txs = num_txs.min(max_new_batches * self.options.batch_size);
+
+        if num_txs == 0 {
+            debug!(target: COMPONENT, "No batch ready to be sealed");
+            return None;
+        }
+
+        let reserved_consuming =
+            (num_txs * self.options.reserved_consuming_percent.min(100) as usize).div_ceil(100);
+        let reserved_partners = self.reservations.reserved(num_txs);
+        let queue_lanes: Vec<_> = ready_queue
+            .iter()
+            .map(|queued| (queued.lane, queued.tx.account_id(), queued.partner))
+            .collect();
+        let selected = lanes::select_transactions(
+            &queue_lanes,
+            num_txs,
+            reserved_consuming,
+            &reserved_partners,
+        );
+
+        // the transactions following the ones of the queued batches wait for these batches
+        let mut transitions_in_batches =
+            self.transitions_in_batches.lock().expect("transitions lock poisoned").clone();
+        transitions_in_batches
+            .extend(self.proven_batches.read().await.iter().flat_map(QueuedBatch::transitions));
+
+        // the batches are sealed before they create too many notes, the transactions which
+        // don't fit in the allowed batches stay queued
+        let (selected, batch_sizes) = select_chained_transactions(
+            ready_queue,
+            selected,
+            &transitions_in_batches,
+            self.options.batch_size,
+            max_new_batches,
+        );
+        self.transitions_in_batches.lock().expect("transitions lock poisoned").extend(
+            selected
+                .iter()
+                .filter_map(|&position| account_transition(&ready_queue[position].tx)),
+        );
+        if selected.is_empty() {
+            debug!(target: COMPONENT, "Selected transactions wait for the transactions they follow");
+            return None;
+        }
+
+        let num_txs: usize = batch_sizes.iter().sum();
+        let mut selected = selected.into_iter().peekable();
+
+        let mut txs = Vec::with_capacity(num_txs);
+        let mut remaining = Vec::with_capacity(ready_queue.len() - num_txs);
+        for (position, queued) in ready_queue.drain(..).enumerate() {
+            if selected.next_if_eq(&position).is_some() {
+                txs.push(queued);
+            } else {
+                remaining.push(queued);
+            }
+        }
+        *ready_queue = remaining;
+
+        Some((txs, batch_sizes))
+    }
+
+    /// Sends up to `max_batches` of the queued batches of the external batch producers to the
+    /// batch builder, the oldest first, and returns the number of batches sent.
+    ///
+    /// A batch waits while one of its transactions starts from the final account state of a
+    /// transaction waiting in the queue, being batched, or part of an older waiting batch. A batch
+    /// refused by the batch builder while the ready batches fill a block is queued again ahead of
+    /// the others, a batch whose transactions have an invalid proof is dropped as a whole.
+    async fn send_proven_batches(
+        &self,
+        max_batches: usize,
+    ) -> usize {
+        let (orphaned, batches) = {
+            let mut locked_ready_queue = self.ready_queue.write().await;
+            let mut locked_batches = self.proven_batches.write().await;
+            if locked_batches.is_empty() {
+                return 0;
+            }
+
+            // the batches following a dropped transaction are dropped before they can be sent
+            let dropped_transitions = self.dropped.take_transitions().into_iter().collect();
+            let orphaned =
+                take_orphaned(&mut locked_ready_queue, &mut locked_batches, dropped_transitions);
+
+            let mut locked_transitions_in_batches =
+                self.transitions_in_batches.lock().expect("transitions lock poisoned");
+            let mut pending: BTreeSet<(AccountId, Digest)> = locked_ready_queue
+                .iter()
+                .filter_map(|queued| account_transition(&queued.tx))
+                .chain(locked_transitions_in_batches.iter().copied())
+                .collect();
+
+            let mut batches = Vec::new();
+            let mut waiting = VecDeque::new();
+            for queued in locked_batches.drain(..) {
+                let transitions = queued.transitions();
+                let follows_pending = queued.txs.iter().any(|queued| {
+                    let initial_state = (queued.tx.account_id(), queued.tx.initial_account_hash());
+                    pending.contains(&initial_state) && !transitions.contains(&initial_state)
+                });
+                if batches.len() < max_batches && !follows_pending {
+                    locked_transitions_in_batches.extend(transitions);
+                    batches.push(queued);
+                } else {
+                    pending.extend(transitions);
+                    waiting.push_back(queued);
+                }
+            }
+            *locked_batches = waiting;
+
+            (orphaned, batches)
+        };
+        self.release_orphaned(orphaned).await;
+
+        for queued in &batches {
+            for lane in Lane::ALL {
+                let num_selected = queued.txs.iter().filter(|queued| queued.lane == lane).count();
+                self.lane_metrics.selected(lane, num_selected as u64);
+            }
+            self.latency.dequeued(queued.batch.transaction_ids());
+        }
+        let num_batches = batches.len();
+
+        for queued in batches {
+            let proven_batches = self.proven_batches.clone();
+            let batch_builder = self.batch_builder.clone();
+            let batches_in_flight = self.batches_in_flight.clone();
+            let tx_verifier = self.tx_verifier.clone();
+            let latency = self.latency.clone();
+            let dropped = self.dropped.clone();
+            let tx_log = self.tx_log.clone();
+            let transitions_in_batches = self.transitions_in_batches.clone();
+            batches_in_flight.fetch_add(1, Ordering::Relaxed);
+
+            tokio::spawn(
+                async move {
+                    let batch_id = format_blake3_digest(queued.batch.id());
+                    let transitions = queued.transitions();
+                    let txs = queued.txs.iter().map(|queued| queued.tx.clone()).collect();
+                    let result = batch_builder.add_batch(queued.batch.clone(), txs).await;
+
+                    match result {
+                        Ok(()) => {
+                            info!(target: COMPONENT, %batch_id, "Batch sent to the batch builder");
+                        },
+                        Err(BuildBatchError::ReadyBatchesFull(_)) => {
+                            debug!(target: COMPONENT, %batch_id, "Batch waits for the next block");
+                            proven_batches.write().await.push_front(queued);
+                        },
+                        Err(err) => {
+                            warn!(target: COMPONENT, %batch_id, %err, "Batch dropped, rejected by the batch builder");
+                            let rejected: BTreeSet<TransactionId> = match &err {
+                                BuildBatchError::InvalidTransactionProofs { rejected, .. } => {
+                                    rejected.iter().map(|tx| tx.id()).collect()
+                                },
+                                _ => BTreeSet::new(),
+                            };
+                            for queued in &queued.txs {
+                                let reason = if rejected.contains(&queued.tx.id()) {
+                                    DropReason::InvalidProof
+                                } else {
+                                    DropReason::BatchFailures
+                                };
+                                tx_verifier.release_tx(&queued.tx).await;
+                                latency.rejected(queued.tx.id());
+                                dropped.record(&queued.tx, reason);
+                            }
+                            if let Some(tx_log) = &tx_log {
+                                tx_log.finished(queued.batch.transaction_ids()).await;
+                            }
+                        },
+                    }
+
+                    // a batch queued again is accounted for by the queue until it is sent again
+                    {
+                        let mut locked_transitions_in_batches =
+                            transitions_in_batches.lock().expect("transitions lock poisoned");
+                        for transition in &transitions {
+                            locked_transitions_in_batches.remove(transition);
+                        }
+                    }
+
+                    // the batch is back in the queue, or dropped, before it is no longer in flight
+                    batches_in_flight.fetch_sub(1, Ordering::Relaxed);
                 }
                 .instrument(info_span!(target: COMPONENT, "batch_builder")),
             );
         }
+
+        num_batches
+    }
+
+    /// Drops the queued `batches` for `reason`, releasing their transactions.
+    async fn drop_batches(
+        &self,
+        batches: Vec<QueuedBatch>,
+        reason: DropReason,
+    ) {
+        for queued in &batches {
+            warn!(target: COMPONENT, batch_id = %format_blake3_digest(queued.batch.id()), %reason, "Batch dropped");
+            for queued in &queued.txs {
+                self.tx_verifier.release_tx(&queued.tx).await;
+                self.latency.rejected(queued.tx.id());
+                self.dropped.record(&queued.tx, reason);
+            }
+        }
+        if let Some(tx_log) = &self.tx_log {
+            tx_log
+                .finished(batches.iter().flat_map(|queued| queued.batch.transaction_ids()))
+                .await;
+        }
     }
 
     /// Queues `tx` to be added in a batch and subsequently into a block.
     ///
     /// This method will validate the `tx` and ensure it is valid w.r.t. the rollup state, and the
-    /// current in-flight transactions.
+    /// current in-flight transactions. If the queue holds enough transactions to fill a batch, the
+    /// batch is sealed right away.
+    ///
+    /// Returns the position of `tx` in the queue when it was added.
+    ///
+    /// Transactions are rejected without being verified while the [LoadShedder] sheds load. Once the
+    /// queue is full, the [EvictionPolicy] decides whether `tx` is rejected or replaces a queued
+    /// transaction. A verified transaction is persisted to the [TransactionLog], if enabled, before
+    /// it is queued.
+    ///
+    /// `tx_kernel_version` is the version of the transaction kernel `tx` was proven with, it must
+    /// be one of the accepted versions, see [TransactionQueue::with_tx_kernel]. `account_delta`
+    /// holds the changes made by `tx` to the state of its account, see
+    /// [TransactionVerifier::verify_tx]. `consumed_notes` are the public notes consumed by `tx`,
+    /// linked to their nullifiers in the block including `tx`, `tx` is rejected if it doesn't
+    /// produce the nullifier of one of them. `private_notes` are the hashes of the notes created by
+    /// `tx` whose details are private, `tx` is rejected if it doesn't create one of them.
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(target = "miden-block-producer", skip_all, err)]
     pub async fn add_transaction(
         &self,
         tx: ProvenTransaction,
-    ) -> Result<(), AddTransactionError> {
+        tx_kernel_version: u32,
+        account_delta: Option<AccountDelta>,
+        consumed_notes: Vec<Note>,
+        private_notes: Vec<Digest>,
+    ) -> Result<usize, AddTransactionError> {
         info!(target: COMPONENT, tx_id = %tx.id().to_hex(), account_id = %tx.account_id().to_hex());
 
-        self.tx_verifier
-            .verify_tx(&tx)
-            .await
-            .map_err(AddTransactionError::VerificationFailed)?;
+        self.admit_transaction(
+            tx,
+            tx_kernel_version,
+            account_delta,
+            consumed_notes,
+            private_notes,
+            None,
+        )
+        .await
+    }
+
+    /// Queues `tx`, signed by a partner of the block producer with `signature`, to be batched
+    /// against the capacity reserved for the partner.
+    ///
+    /// See [TransactionQueue::add_transaction], `tx` is rejected if `signature` isn't a signature
+    /// of `tx` by one of the partners of [TransactionQueue::with_reservations].
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-block-producer", skip_all, err)]
+    pub async fn add_partner_transaction(
+        &self,
+        tx: ProvenTransaction,
+        tx_kernel_version: u32,
+        account_delta: Option<AccountDelta>,
+        consumed_notes: Vec<Note>,
+        private_notes: Vec<Digest>,
+        signature: &PartnerSignature,
+    ) -> Result<usize, AddTransactionError> {
+        info!(target: COMPONENT, tx_id = %tx.id().to_hex(), account_id = %tx.account_id().to_hex(), partner_key = %signature.partner_key);
+        let partner = self.reservations.partner_of(signature, tx.id())?;
+
+        self.admit_transaction(
+            tx,
+            tx_kernel_version,
+            account_delta,
+            consumed_notes,
+            private_notes,
+            Some((partner, signature.clone())),
+        )
+        .await
+    }
+
+    /// Verifies and queues `tx`, see [TransactionQueue::add_transaction].
+    ///
+    /// The latency of `tx` is tracked from now on, and stops being tracked on every path not
+    /// queuing it, including the cancellation of the submission.
+    async fn admit_transaction(
+        &self,
+        tx: ProvenTransaction,
+        tx_kernel_version: u32,
+        account_delta: Option<AccountDelta>,
+        consumed_notes: Vec<Note>,
+        private_notes: Vec<Digest>,
+        partner: Option<(usize, PartnerSignature)>,
+    ) -> Result<usize, AddTransactionError> {
+        let admission = self.latency.submitted([tx.id()]);
+        if self.load_shedder.is_shedding() {
+            warn!(target: COMPONENT, tx_id = %tx.id().to_hex(), "Transaction rejected, shedding load");
+            return Err(AddTransactionError::Overloaded);
+        }
+
+        if !self.tx_kernel.supports(tx_kernel_version) {
+            return Err(AddTransactionError::UnsupportedTxKernelVersion {
+                version: tx_kernel_version,
+                min_version: self.tx_kernel.min_version,
+                max_version: self.tx_kernel.max_version,
+            });
+        }
+
+        ensure_notes_consumed(&tx, &consumed_notes)
+            .and_then(|_| ensure_notes_created(&tx, &private_notes))?;
+
+        // Reject early the transactions which wouldn't be admitted anyway, the queue is checked
+        // again once the transaction is verified
+        let eviction = {
+            let locked_ready_queue = self.ready_queue.read().await;
+            self.eviction(&locked_ready_queue, self.num_batched_transactions().await, &tx)
+        };
+        if let Some(Eviction::RejectCandidate) = eviction {
+            warn!(target: COMPONENT, tx_id = %tx.id().to_hex(), "Transaction rejected, queue full");
+            return Err(AddTransactionError::QueueFull);
+        }
+
+        if let Err(err) = self.tx_verifier.verify_tx(&tx, account_delta.as_ref()).await {
+            return Err(AddTransactionError::VerificationFailed(err));
+        }
+        self.latency.verified(tx.id());
+
+        if let Some(tx_log) = &self.tx_log {
+            let persisted = tx_log
+                .accepted(
+                    &tx,
+                    tx_kernel_version,
+                    account_delta.as_ref(),
+                    &consumed_notes,
+                    &private_notes,
+                    partner.as_ref().map(|(_, signature)| signature),
+                )
+                .await;
+            if let Err(err) = persisted {
+                warn!(target: COMPONENT, tx_id = %tx.id().to_hex(), %err, "Transaction rejected, failed to persist it");
+                self.tx_verifier.release_tx(&tx).await;
+                return Err(AddTransactionError::PersistenceFailed(err.to_string()));
+            }
+        }
+
+        let (partner, partner_signature) = partner.unzip();
+        let tx = QueuedTransaction {
+            consumed_notes,
+            private_notes,
+            partner,
+            partner_signature,
+            queued_at_block: self.tx_verifier.num_applied_blocks(),
+            ..QueuedTransaction::new(tx, tx_kernel_version, account_delta)
+        };
+        let lane = tx.lane;
+        let tx_id = tx.tx.id();
 
-        let queue_len = {
+        let (queue_len, evicted, orphaned) = {
             let mut queue_write_guard = self.ready_queue.write().await;
+            let num_batched = self.num_batched_transactions().await;
+            let evicted = match self.eviction(&queue_write_guard, num_batched, &tx.tx) {
+                None => None,
+                Some(Eviction::Evict(position)) if position < queue_write_guard.len() => {
+                    Some(queue_write_guard.remove(position))
+                },
+                Some(_) => {
+                    drop(queue_write_guard);
+                    warn!(target: COMPONENT, tx_id = %tx.tx.id().to_hex(), "Transaction rejected, queue full");
+                    self.tx_verifier.release_tx(&tx.tx).await;
+                    if let Some(tx_log) = &self.tx_log {
+                        tx_log.finished([tx.tx.id()]).await;
+                    }
+                    return Err(AddTransactionError::QueueFull);
+                },
+            };
             queue_write_guard.push(tx);
-            queue_write_guard.len()
+            admission.admitted();
+
+            // the transactions following the evicted one are orphaned along with it, `tx` too if
+            // it follows the evicted transaction
+            let evicted_transitions: BTreeSet<_> =
+                evicted.iter().filter_map(|evicted| account_transition(&evicted.tx)).collect();
+            let orphaned = take_orphaned(
+                &mut queue_write_guard,
+                &mut *self.proven_batches.write().await,
+                evicted_transitions,
+            );
+            (queue_write_guard.len(), evicted, orphaned)
         };
 
+        self.dropped.forget(tx_id);
+        if let Some(evicted) = evicted {
+            self.release_evicted(&evicted).await;
+        }
+        let tx_orphaned = orphaned.txs.iter().any(|queued| queued.tx.id() == tx_id);
+        self.release_orphaned(orphaned).await;
+        if tx_orphaned {
+            warn!(target: COMPONENT, tx_id = %tx_id.to_hex(), "Transaction rejected, the transaction it follows was evicted");
+            return Err(AddTransactionError::QueueFull);
+        }
+
+        self.lane_metrics.queued(lane);
         info!(target: COMPONENT, queue_len, "Transaction added to tx queue");
 
-        Ok(())
+        if queue_len >= self.options.batch_size {
+            self.try_build_batches().await;
+        }
+
+        Ok(queue_len - 1)
+    }
+
+    /// Verifies a batch built and proven by an external batch producer, and queues it to be sent
+    /// to the batch builder as is, see [BatchBuilder::add_batch].
+    ///
+    /// The batch is admitted or rejected as a whole. It holds at most the batch size of the queue,
+    /// its transactions must form a valid [TransactionBatch] and are verified as by
+    /// [TransactionQueue::add_transaction]. `proof` must be a proof of the batch kernel executed
+    /// over the batch, see [BatchProofVerifier]. They are persisted to the [TransactionLog], if
+    /// enabled, the transactions of a batch not included in a block before a crash being admitted
+    /// again one by one.
+    ///
+    /// The transactions of the batch count against the capacity of the queue, the [EvictionPolicy]
+    /// deciding for each of them whether the batch is rejected or a queued transaction is evicted.
+    /// The batch expires as a whole, see [TransactionQueue::with_ttl].
+    ///
+    /// Returns the queued batch, with its proof.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-block-producer", skip_all, err)]
+    pub async fn add_batch(
+        &self,
+        submissions: Vec<QueuedSubmission>,
+        proof: BatchProof,
+    ) -> Result<TransactionBatch, AddBatchError> {
+        info!(target: COMPONENT, num_txs = submissions.len(), "Batch submitted");
+        if submissions.is_empty() {
+            return Err(AddBatchError::EmptyBatch);
+        }
+        if submissions.len() > self.options.batch_size {
+            return Err(AddBatchError::TooManyTransactions(
+                submissions.len(),
+                self.options.batch_size,
+            ));
+        }
+
+        let admission =
+            self.latency.submitted(submissions.iter().map(|submission| submission.tx.id()));
+        let batch = self.verify_batch(&submissions, proof).await?;
+
+        let queued_at_block = self.tx_verifier.num_applied_blocks();
+        let txs: Vec<QueuedTransaction> = submissions
+            .into_iter()
+            .map(|submission| QueuedTransaction {
+                consumed_notes: submission.consumed_notes,
+                private_notes: submission.private_notes,
+                queued_at_block,
+                ..QueuedTransaction::new(
+                    submission.tx,
+                    submission.tx_kernel_version,
+                    submission.account_delta,
+                )
+            })
+            .collect();
+        let lanes: Vec<Lane> = txs.iter().map(|queued| queued.lane).collect();
+
+        let (evicted, orphaned) = {
+            let mut locked_ready_queue = self.ready_queue.write().await;
+            let mut locked_batches = self.proven_batches.write().await;
+            let num_batched: usize = locked_batches.iter().map(|queued| queued.txs.len()).sum();
+            let candidates: Vec<&ProvenTransaction> = txs.iter().map(|queued| &queued.tx).collect();
+            let Some(positions) =
+                self.batch_eviction(&locked_ready_queue, num_batched, &candidates)
+            else {
+                drop(locked_batches);
+                drop(locked_ready_queue);
+                warn!(target: COMPONENT, batch_id = %format_blake3_digest(batch.id()), "Batch rejected, queue full");
+                for queued in &txs {
+                    self.tx_verifier.release_tx(&queued.tx).await;
+                }
+                if let Some(tx_log) = &self.tx_log {
+                    tx_log.finished(batch.transaction_ids()).await;
+                }
+                return Err(AddBatchError::QueueFull);
+            };
+
+            let evicted: Vec<QueuedTransaction> = positions
+                .into_iter()
+                .map(|position| locked_ready_queue.remove(position))
+                .collect();
+            locked_batches.push_back(QueuedBatch {
+                batch: batch.clone(),
+                txs,
+                queued_at_block,
+            });
+            admission.admitted();
+
+            // the transactions following the evicted ones are orphaned along with them, the batch
+            // too if it follows an evicted transaction
+            let evicted_transitions =
+                evicted.iter().filter_map(|evicted| account_transition(&evicted.tx)).collect();
+            let orphaned =
+                take_orphaned(&mut locked_ready_queue, &mut locked_batches, evicted_transitions);
+            (evicted, orphaned)
+        };
+
+        for tx_id in batch.transaction_ids() {
+            self.dropped.forget(tx_id);
+        }
+        for evicted in &evicted {
+            self.release_evicted(evicted).await;
+        }
+        let batch_orphaned = orphaned.batches.iter().any(|queued| queued.batch.id() == batch.id());
+        self.release_orphaned(orphaned).await;
+        if batch_orphaned {
+            warn!(target: COMPONENT, batch_id = %format_blake3_digest(batch.id()), "Batch rejected, a transaction it follows was evicted");
+            return Err(AddBatchError::QueueFull);
+        }
+
+        for lane in lanes {
+            self.lane_metrics.queued(lane);
+        }
+        info!(target: COMPONENT, batch_id = %format_blake3_digest(batch.id()), "Batch added to tx queue");
+
+        self.try_build_batches().await;
+
+        Ok(batch)
+    }
+
+    /// Builds the batch of `submissions`, verifies its `proof` and its transactions, see
+    /// [TransactionQueue::add_batch]. The transactions are released if the batch is rejected.
+    async fn verify_batch(
+        &self,
+        submissions: &[QueuedSubmission],
+        proof: BatchProof,
+    ) -> Result<TransactionBatch, AddBatchError> {
+        if self.load_shedder.is_shedding() {
+            warn!(target: COMPONENT, "Batch rejected, shedding load");
+            return Err(AddBatchError::Overloaded);
+        }
+
+        // Reject early the batches which wouldn't be admitted anyway, the queue is checked again
+        // once the batch is verified
+        let queue_full = {
+            let locked_ready_queue = self.ready_queue.read().await;
+            let num_batched = self.num_batched_transactions().await;
+            let candidates: Vec<&ProvenTransaction> =
+                submissions.iter().map(|submission| &submission.tx).collect();
+            self.batch_eviction(&locked_ready_queue, num_batched, &candidates).is_none()
+        };
+        if queue_full {
+            warn!(target: COMPONENT, "Batch rejected, queue full");
+            return Err(AddBatchError::QueueFull);
+        }
+
+        for submission in submissions {
+            if !self.tx_kernel.supports(submission.tx_kernel_version) {
+                return Err(AddBatchError::TransactionRejected {
+                    tx_id: submission.tx.id(),
+                    error: AddTransactionError::UnsupportedTxKernelVersion {
+                        version: submission.tx_kernel_version,
+                        min_version: self.tx_kernel.min_version,
+                        max_version: self.tx_kernel.max_version,
+                    },
+                });
+            }
+            ensure_notes_consumed(&submission.tx, &submission.consumed_notes)
+                .and_then(|_| ensure_notes_created(&submission.tx, &submission.private_notes))
+                .map_err(|error| AddBatchError::TransactionRejected {
+                    tx_id: submission.tx.id(),
+                    error,
+                })?;
+        }
+
+        let txs = submissions.iter().map(|submission| submission.tx.clone()).collect();
+        let tx_kernel_versions =
+            submissions.iter().map(|submission| submission.tx_kernel_version).collect();
+        let note_links = submissions
+            .iter()
+            .flat_map(|submission| consumed_note_links(&submission.consumed_notes))
+            .collect();
+        let private_notes = submissions
+            .iter()
+            .flat_map(|submission| submission.private_notes.iter().copied())
+            .collect();
+        let batch = TransactionBatch::new(txs)?
+            .with_tx_kernel_versions(tx_kernel_versions)
+            .with_consumed_notes(note_links)
+            .with_private_notes(private_notes);
+
+        // the proof is checked off the runtime, before the transactions lock their accounts and
+        // nullifiers
+        let verifier = self.batch_proof_verifier.clone();
+        let (batch, proof, verified) = tokio::task::spawn_blocking(move || {
+            let verified = verifier.verify(&batch, &proof);
+            (batch, proof, verified)
+        })
+        .await
+        .map_err(|err| AddBatchError::InvalidProof(format!("verification task failed: {err}")))?;
+        if let Err(err) = verified {
+            warn!(target: COMPONENT, batch_id = %format_blake3_digest(batch.id()), %err, "Batch rejected, invalid proof");
+            return Err(AddBatchError::InvalidProof(err));
+        }
+        let batch = batch.with_proof(proof);
+
+        for (position, submission) in submissions.iter().enumerate() {
+            let verified = self
+                .tx_verifier
+                .verify_tx(&submission.tx, submission.account_delta.as_ref())
+                .await;
+            if let Err(err) = verified {
+                for verified in &submissions[..position] {
+                    self.tx_verifier.release_tx(&verified.tx).await;
+                }
+                return Err(AddBatchError::TransactionRejected {
+                    tx_id: submission.tx.id(),
+                    error: AddTransactionError::VerificationFailed(err),
+                });
+            }
+        }
+        for submission in submissions {
+            self.latency.verified(submission.tx.id());
+        }
+
+        if let Some(tx_log) = &self.tx_log {
+            for (position, submission) in submissions.iter().enumerate() {
+                let persisted = tx_log
+                    .accepted(
+                        &submission.tx,
+                        submission.tx_kernel_version,
+                        submission.account_delta.as_ref(),
+                        &submission.consumed_notes,
+                        &submission.private_notes,
+                        None,
+                    )
+                    .await;
+                if let Err(err) = persisted {
+                    warn!(target: COMPONENT, tx_id = %submission.tx.id().to_hex(), %err, "Batch rejected, failed to persist it");
+                    tx_log
+                        .finished(
+                            submissions[..position].iter().map(|submission| submission.tx.id()),
+                        )
+                        .await;
+                    for submission in submissions {
+                        self.tx_verifier.release_tx(&submission.tx).await;
+                    }
+                    return Err(AddBatchError::TransactionRejected {
+                        tx_id: submission.tx.id(),
+                        error: AddTransactionError::PersistenceFailed(err.to_string()),
+                    });
+                }
+            }
+        }
+
+        Ok(batch)
+    }
+
+    /// Returns what to drop to admit `candidate` into `queue`, or `None` if the queue isn't full.
+    ///
+    /// `num_batched` transactions of the queued batches count against the capacity of the queue,
+    /// but can't be evicted.
+    fn eviction(
+        &self,
+        queue: &[QueuedTransaction],
+        num_batched: usize,
+        candidate: &ProvenTransaction,
+    ) -> Option<Eviction> {
+        if self.max_queued_transactions == 0
+            || queue.len() + num_batched < self.max_queued_transactions
+        {
+            return None;
+        }
+
+        let queue: Vec<&ProvenTransaction> = queue.iter().map(|queued| &queued.tx).collect();
+        Some(self.eviction_policy.evict(&queue, candidate))
+    }
+
+    /// Returns the positions of the transactions of `queue` to evict to admit the transactions
+    /// `candidates` of a batch, from the last to the first, or `None` if the batch is rejected.
+    ///
+    /// The [EvictionPolicy] is applied to every transaction of the batch in turn, as if they were
+    /// admitted one by one, the batch being rejected if one of them is.
+    fn batch_eviction(
+        &self,
+        queue: &[QueuedTransaction],
+        num_batched: usize,
+        candidates: &[&ProvenTransaction],
+    ) -> Option<Vec<usize>> {
+        let mut remaining: Vec<(usize, &ProvenTransaction)> =
+            queue.iter().map(|queued| &queued.tx).enumerate().collect();
+        let mut evicted = Vec::new();
+        for (num_admitted, candidate) in candidates.iter().enumerate() {
+            if self.max_queued_transactions == 0
+                || remaining.len() + num_batched + num_admitted < self.max_queued_transactions
+            {
+                continue;
+            }
+
+            let queue: Vec<&ProvenTransaction> = remaining.iter().map(|(_, tx)| *tx).collect();
+            match self.eviction_policy.evict(&queue, candidate) {
+                Eviction::Evict(position) if position < remaining.len() => {
+                    evicted.push(remaining.remove(position).0)
+                },
+                _ => return None,
+            }
+        }
+
+        evicted.sort_unstable_by(|a, b| b.cmp(a));
+        Some(evicted)
+    }
+
+    /// Releases `evicted`, a transaction evicted from the full queue.
+    async fn release_evicted(
+        &self,
+        evicted: &QueuedTransaction,
+    ) {
+        warn!(target: COMPONENT, tx_id = %evicted.tx.id().to_hex(), "Transaction evicted from the full queue");
+        self.tx_verifier.release_tx(&evicted.tx).await;
+        self.latency.rejected(evicted.tx.id());
+        self.dropped.record(&evicted.tx, DropReason::Evicted);
+        if let Some(tx_log) = &self.tx_log {
+            tx_log.finished([evicted.tx.id()]).await;
+        }
+    }
+
+    /// Returns the number of transactions of the queued batches.
+    async fn num_batched_transactions(&self) -> usize {
+        self.proven_batches.read().await.iter().map(|queued| queued.txs.len()).sum()
+    }
+
+    /// Returns the counters of the transactions going through `lane`.
+    pub fn lane_stats(
+        &self,
+        lane: Lane,
+    ) -> LaneStats {
+        self.lane_metrics.report(lane)
+    }
+
+    /// Returns the number of transactions waiting in the queue, including the ones of the queued
+    /// batches.
+    pub async fn num_queued_transactions(&self) -> usize {
+        self.ready_queue.read().await.len() + self.num_batched_transactions().await
+    }
+
+    /// Returns the number of batches sent to the batch builder which are not built yet.
+    pub fn num_batches_in_flight(&self) -> usize {
+        self.batches_in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Returns the state locked by the transactions in flight, see [TransactionVerifier::in_flight].
+    pub async fn in_flight(&self) -> InFlight {
+        self.tx_verifier.in_flight().await
     }
+
+    /// Returns the transactions waiting in the queue as they were submitted, the ones of the
+    /// queued batches first, ordered by their position in it.
+    pub async fn queued_submissions(&self) -> Vec<QueuedSubmission> {
+        let locked_ready_queue = self.ready_queue.read().await;
+        let locked_batches = self.proven_batches.read().await;
+        queued_transactions(&locked_ready_queue, &locked_batches)
+            .map(|queued| QueuedSubmission {
+                tx: queued.tx.clone(),
+                tx_kernel_version: queued.tx_kernel_version,
+                account_delta: queued.account_delta.clone(),
+                consumed_notes: queued.consumed_notes.clone(),
+                private_notes: queued.private_notes.clone(),
+                partner_signature: queued.partner_signature.clone(),
+            })
+            .collect()
+    }
+
+    /// Removes the transactions `tx_ids` from the queue, e.g. once they were saved to a snapshot,
+    /// and returns the number of transactions removed.
+    ///
+    /// The transactions sent to the batch builder in the meantime are left to be batched. The
+    /// removed transactions are finished in the [TransactionLog], the snapshot admitting them again.
+    /// A queued batch is removed as a whole along with its transactions.
+    pub async fn remove_transactions(
+        &self,
+        tx_ids: &BTreeSet<TransactionId>,
+    ) -> usize {
+        let removed: Vec<QueuedTransaction> = {
+            let mut locked_ready_queue = self.ready_queue.write().await;
+            let mut locked_batches = self.proven_batches.write().await;
+            let (mut removed, remaining): (Vec<_>, Vec<_>) = locked_ready_queue
+                .drain(..)
+                .partition(|queued| tx_ids.contains(&queued.tx.id()));
+            *locked_ready_queue = remaining;
+            let (removed_batches, remaining): (Vec<_>, VecDeque<_>) =
+                locked_batches.drain(..).partition(|queued| {
+                    queued.txs.iter().any(|queued| tx_ids.contains(&queued.tx.id()))
+                });
+            *locked_batches = remaining;
+            removed.extend(removed_batches.into_iter().flat_map(|queued| queued.txs));
+            removed
+        };
+
+        for queued in &removed {
+            self.tx_verifier.release_tx(&queued.tx).await;
+        }
+        self.latency.snapshotted(removed.iter().map(|queued| queued.tx.id()));
+        if let Some(tx_log) = &self.tx_log {
+            tx_log.finished(removed.iter().map(|queued| queued.tx.id())).await;
+        }
+
+        removed.len()
+    }
+
+    /// Returns the number of transactions of `lane` waiting in the queue.
+    pub async fn num_queued_transactions_in_lane(
+        &self,
+        lane: Lane,
+    ) -> usize {
+        let locked_ready_queue = self.ready_queue.read().await;
+        let locked_batches = self.proven_batches.read().await;
+        queued_transactions(&locked_ready_queue, &locked_batches)
+            .filter(|queued| queued.lane == lane)
+            .count()
+    }
+
+    /// Returns the transactions of `account_id` which are waiting in the queue, ordered by their
+    /// position in it.
+    ///
+    /// Transactions which were already sent to the batch builder are no longer reported. The
+    /// transactions of the queued batches are reported ahead of the other ones.
+    pub async fn get_pending_transactions_by_account(
+        &self,
+        account_id: AccountId,
+    ) -> Vec<PendingTransaction> {
+        let locked_ready_queue = self.ready_queue.read().await;
+        let locked_batches = self.proven_batches.read().await;
+        queued_transactions(&locked_ready_queue, &locked_batches)
+            .map(|queued| &queued.tx)
+            .enumerate()
+            .filter(|(_, tx)| tx.account_id() == account_id)
+            .map(|(queue_position, tx)| PendingTransaction {
+                tx_id: tx.id(),
+                initial_account_hash: tx.initial_account_hash(),
+                final_account_hash: tx.final_account_hash(),
+                queue_position,
+            })
+            .collect()
+    }
+
+    /// Returns the latest transactions of `account_id` which were dropped after they were
+    /// accepted, the oldest first.
+    ///
+    /// A dropped transaction is no longer reported once it is accepted again.
+    pub fn get_dropped_transactions_by_account(
+        &self,
+        account_id: AccountId,
+    ) -> Vec<DroppedTransaction> {
+        self.dropped.by_account(account_id)
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Removes from the `ready_queue` and the queued `batches` the transactions starting from the final
+/// account state of one of the `dropped_transitions`, which can't be reached anymore, along with
+/// the transactions following them. A queued batch holding such a transaction is removed as a
+/// whole.
+fn take_orphaned(
+    ready_queue: &mut Vec<QueuedTransaction>,
+    batches: &mut VecDeque<QueuedBatch>,
+    mut dropped_transitions: BTreeSet<(AccountId, Digest)>,
+) -> Orphaned {
+    let is_orphaned = |queued: &QueuedTransaction, transitions: &BTreeSet<_>| {
+        transitions.contains(&(queued.tx.account_id(), queued.tx.initial_account_hash()))
+    };
+
+    let mut orphaned = Orphaned::default();
+    while !dropped_transitions.is_empty() {
+        let (newly_orphaned, remaining): (Vec<_>, Vec<_>) = ready_queue
+            .drain(..)
+            .partition(|queued| is_orphaned(queued, &dropped_transitions));
+        *ready_queue = remaining;
+        let (newly_orphaned_batches, remaining): (Vec<_>, VecDeque<_>) =
+            batches.drain(..).partition(|queued| {
+                queued.txs.iter().any(|queued| is_orphaned(queued, &dropped_transitions))
+            });
+        *batches = remaining;
+
+        dropped_transitions = newly_orphaned
+            .iter()
+            .chain(newly_orphaned_batches.iter().flat_map(|queued| &queued.txs))
+            .filter_map(|queued| account_transition(&queued.tx))
+            .collect();
+        orphaned.txs.extend(newly_orphaned);
+        orphaned.batches.extend(newly_orphaned_batches);
+    }
+
+    orphaned
+}
+
+/// Returns the transactions of the queued `batches`, then the ones of the `ready_queue`.
+fn queued_transactions<'a>(
+    ready_queue: &'a [QueuedTransaction],
+    batches: &'a VecDeque<QueuedBatch>,
+) -> impl Iterator<Item = &'a QueuedTransaction> {
+    batches.iter().flat_map(|queued| &queued.txs).chain(ready_queue)
+}
+
+fn submission_request(
+    tx: &ProvenTransaction,
+    tx_kernel_version: u32,
+    account_delta: Option<&AccountDelta>,
+    consumed_notes: &[Note],
+    private_notes: &[Digest],
+    partner_signature: Option<&PartnerSignature>,
+) -> SubmitProvenTransactionRequest {
+    SubmitProvenTransactionRequest {
+        transaction: tx.to_bytes(),
+        tx_kernel_version,
+        account_delta: account_delta.map(Into::into),
+        partner_signature: partner_signature.map(Into::into),
+        consumed_notes: consumed_notes.iter().map(|note| note.to_bytes()).collect(),
+        private_notes: private_notes.iter().map(Into::into).collect(),
+    }
+}
+
+/// Returns the public notes consumed by a submitted transaction, parsed from `consumed_notes`.
+pub(crate) fn parse_consumed_notes(consumed_notes: &[Vec<u8>]) -> Result<Vec<Note>, String> {
+    consumed_notes
+        .iter()
+        .map(|note| {
+            Note::read_from_bytes(note).map_err(|err| format!("invalid consumed note: {err}"))
+        })
+        .collect()
+}
+
+/// Returns the hashes of the private notes created by a submitted transaction, parsed from
+/// `private_notes`.
+pub(crate) fn parse_private_notes(private_notes: &[digest::Digest]) -> Result<Vec<Digest>, String> {
+    private_notes
+        .iter()
+        .map(|note_hash| {
+            Digest::try_from(note_hash).map_err(|err| format!("invalid private note: {err}"))
+        })
+        .collect()
+}
+
+/// Returns the `(note_hash, nullifier)` links of the `consumed_notes`, sent to the store along
+/// with the block consuming them.
+fn consumed_note_links(consumed_notes: &[Note]) -> impl Iterator<Item = (Digest, Digest)> + '_ {
+    consumed_notes.iter().map(|note| (note.id().inner(), note.nullifier().inner()))
+}
+
+/// Returns an error if `tx` doesn't produce the nullifier of one of the `consumed_notes`, which
+/// would link the note to a nullifier of another transaction.
+fn ensure_notes_consumed(
+    tx: &ProvenTransaction,
+    consumed_notes: &[Note],
+) -> Result<(), AddTransactionError> {
+    for note in consumed_notes {
+        if !tx.input_notes().iter().any(|nullifier| *nullifier == note.nullifier()) {
+            return Err(AddTransactionError::NoteNotConsumed(note.id().inner()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns an error if `tx` doesn't create one of the `private_notes`.
+fn ensure_notes_created(
+    tx: &ProvenTransaction,
+    private_notes: &[Digest],
+) -> Result<(), AddTransactionError> {
+    for note_hash in private_notes {
+        if !tx.output_notes().iter().any(|note| note.note_id().inner() == *note_hash) {
+            return Err(AddTransactionError::NoteNotCreated(*note_hash));
+        }
+    }
+
+    Ok(())
+}
+
+// BATCH ASSEMBLY
+// ================================================================================================
+
+/// Returns the positions of the `selected` transactions of `queue` which can be batched right
+/// away, in queue order, along with the sizes of the batches they are split in, at most
+/// `max_batches` of `batch_size` transactions, see [batch_sizes].
+///
+/// A transaction starting from the final state of another transaction of its account is applied
+/// after it. It is deferred to a later round while that transaction waits in the queue without
+/// being batched ahead of it, is being batched, as recorded in `transitions_in_batches`, or falls
+/// in another batch of the round, which may be included in a block after its own batch.
+fn select_chained_transactions(
+    queue: &[QueuedTransaction],
+    mut selected: Vec<usize>,
+    transitions_in_batches: &BTreeSet<(AccountId, Digest)>,
+    batch_size: usize,
+    max_batches: usize,
+) -> (Vec<usize>, Vec<usize>) {
+    let follows = |position: usize, transitions: &BTreeSet<(AccountId, Digest)>| {
+        let tx = &queue[position].tx;
+        transitions.contains(&(tx.account_id(), tx.initial_account_hash()))
+    };
+
+    loop {
+        // the final states of the transactions neither batched nor selected ahead of a transaction
+        let mut pending = transitions_in_batches.clone();
+        pending.extend(queue.iter().filter_map(|queued| account_transition(&queued.tx)));
+        selected.retain(|&position| {
+            if follows(position, &pending) {
+                return false;
+            }
+            if let Some(transition) = account_transition(&queue[position].tx) {
+                pending.remove(&transition);
+            }
+            true
+        });
+
+        let num_notes: Vec<usize> = selected
+            .iter()
+            .map(|&position| queue[position].tx.output_notes().iter().count())
+            .collect();
+        let mut sizes = batch_sizes(&num_notes, batch_size, MAX_NUM_CREATED_NOTES_PER_BATCH);
+        sizes.truncate(max_batches);
+        let num_txs: usize = sizes.iter().sum();
+        selected.truncate(num_txs);
+
+        let mut earlier_batches = BTreeSet::new();
+        let mut remaining = selected.as_slice();
+        let mut deferred = None;
+        for &size in &sizes {
+            let (batch, rest) = remaining.split_at(size);
+            remaining = rest;
+            deferred = batch.iter().copied().find(|&position| follows(position, &earlier_batches));
+            if deferred.is_some() {
+                break;
+            }
+            earlier_batches.extend(
+                batch.iter().filter_map(|&position| account_transition(&queue[position].tx)),
+            );
+        }
+
+        // the transactions following the deferred one are deferred along with it
+        match deferred {
+            Some(deferred) => selected.retain(|&position| position != deferred),
+            None => return (selected, sizes),
+        }
+    }
+}
+
+/// Returns the account of `tx` and the state `tx` leaves it in, the transactions of the account
+/// starting from that state follow `tx`, or `None` if `tx` leaves its account unchanged.
+fn account_transition(tx: &ProvenTransaction) -> Option<(AccountId, Digest)> {
+    (tx.initial_account_hash() != tx.final_account_hash())
+        .then_some((tx.account_id(), tx.final_account_hash()))
+}
+
+/// Splits transactions creating `num_notes` notes each, in order, into batches of at most
+/// `batch_size` transactions creating at most `max_notes` notes, and returns the number of
+/// transactions of every batch.
+///
+/// A batch is sealed as soon as the next transaction would exceed one of the limits, instead of
+/// building an oversized batch which fails with [BuildBatchError::TooManyNotesCreated] and
+/// re-queues all its transactions. A transaction creating more than `max_notes` notes on its own
+/// is batched alone.
+pub(super) fn batch_sizes(
+    num_notes: &[usize],
+    batch_size: usize,
+    max_notes: usize,
+) -> Vec<usize> {
+    let mut sizes = Vec::new();
+    let mut batch_txs = 0;
+    let mut batch_notes = 0;
+    for &tx_notes in num_notes {
+        if batch_txs > 0 && (batch_txs == batch_size || batch_notes + tx_notes > max_notes) {
+            sizes.push(batch_txs);
+            batch_txs = 0;
+            batch_notes = 0;
+        }
+        batch_txs += 1;
+        batch_notes += tx_notes;
+    }
+    if batch_txs > 0 {
+        sizes.push(batch_txs);
+    }
+
+    sizes
 }
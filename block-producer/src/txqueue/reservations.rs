@@ -0,0 +1,114 @@
+//! Block space reserved for the partners of the block producer.
+//!
+//! Partners, e.g. exchanges and bridges, sign their transactions with a key listed in the
+//! configuration. A share of every batch selection is reserved for the transactions of every
+//! partner, so their transactions are batched without waiting behind a congested queue. The share a
+//! partner doesn't use expires at the end of the selection, and is given to the other transactions.
+use std::collections::BTreeSet;
+
+use miden_crypto::utils::Deserializable;
+use miden_node_proto::domain::PartnerSignature;
+use miden_objects::{transaction::TransactionId, Digest};
+
+use crate::{
+    config::ReservationsConfig,
+    errors::{AddTransactionError, ReservationError},
+};
+
+/// A partner of the block producer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Partner {
+    name: String,
+    key: Digest,
+    reserved_percent: u8,
+}
+
+/// The partners of the block producer, and the share of every batch selection reserved for them.
+///
+/// Partners are identified by their position in the configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reservations {
+    partners: Vec<Partner>,
+}
+
+impl Reservations {
+    /// Parses the partners of the configuration, whose reservations must add up to 100% at most.
+    pub fn from_config(config: &ReservationsConfig) -> Result<Self, ReservationError> {
+        let mut keys = BTreeSet::new();
+        let mut partners = Vec::with_capacity(config.partners.len());
+        for partner in &config.partners {
+            let key = parse_key(&partner.key).ok_or_else(|| ReservationError::InvalidKey {
+                name: partner.name.clone(),
+                key: partner.key.clone(),
+            })?;
+            if !keys.insert(key) {
+                return Err(ReservationError::DuplicateKey {
+                    key: partner.key.clone(),
+                });
+            }
+
+            partners.push(Partner {
+                name: partner.name.clone(),
+                key,
+                reserved_percent: partner.reserved_percent,
+            });
+        }
+
+        let total_percent: u32 =
+            partners.iter().map(|partner| partner.reserved_percent as u32).sum();
+        if total_percent > 100 {
+            return Err(ReservationError::OverReserved(total_percent));
+        }
+
+        Ok(Self { partners })
+    }
+
+    /// Returns `true` if the block producer has no partner.
+    pub fn is_empty(&self) -> bool {
+        self.partners.is_empty()
+    }
+
+    /// Returns the partner which signed the transaction `tx_id` with `signature`.
+    pub fn partner_of(
+        &self,
+        signature: &PartnerSignature,
+        tx_id: TransactionId,
+    ) -> Result<usize, AddTransactionError> {
+        let partner = self
+            .partners
+            .iter()
+            .position(|partner| partner.key == signature.partner_key)
+            .ok_or(AddTransactionError::UnknownPartner(signature.partner_key))?;
+
+        if !signature.verify(tx_id.inner()) {
+            return Err(AddTransactionError::InvalidPartnerSignature(signature.partner_key));
+        }
+
+        Ok(partner)
+    }
+
+    /// Returns the name of `partner`.
+    pub fn name(
+        &self,
+        partner: usize,
+    ) -> &str {
+        &self.partners[partner].name
+    }
+
+    /// Returns the number of transactions reserved for every partner, out of the `num_txs`
+    /// transactions selected to be batched.
+    pub(super) fn reserved(
+        &self,
+        num_txs: usize,
+    ) -> Vec<usize> {
+        self.partners
+            .iter()
+            .map(|partner| (num_txs * partner.reserved_percent.min(100) as usize).div_ceil(100))
+            .collect()
+    }
+}
+
+fn parse_key(key: &str) -> Option<Digest> {
+    let bytes = hex::decode(key.strip_prefix("0x").unwrap_or(key)).ok()?;
+    Digest::read_from_bytes(&bytes).ok()
+}
@@ -1,10 +1,35 @@
+use std::{
+    collections::BTreeSet,
+    fs::{self, OpenOptions},
+    path::Path,
+    sync::atomic::AtomicU32,
+};
+
+use figment::Jail;
+use miden_crypto::dsa::rpo_falcon512::KeyPair;
+use miden_node_proto::{domain::PartnerSignature, TX_KERNEL_VERSION};
+use miden_node_utils::operator_auth::format_key;
+use miden_objects::transaction::{InputNotes, OutputNotes};
 use tokio::{
     sync::mpsc::{self, error::TryRecvError},
     time,
 };
 
 use super::*;
-use crate::{errors::BuildBatchError, test_utils::DummyProvenTxGenerator, TransactionBatch};
+use crate::{
+    batch_builder::prover::{BatchProver, LocalBatchProver},
+    config::{
+        LoadSheddingConfig, PartnerConfig, ProverPriority, ReservationsConfig, TxKernelConfig,
+        TxOrdering,
+    },
+    errors::BuildBatchError,
+    load_shedder::{LoadSample, LoadShedderOptions},
+    test_utils::{
+        note_by_index, nullifier_by_index, DummyProvenTxGenerator, MockPrivateAccount,
+        MockProvenTxBuilder,
+    },
+    TransactionBatch,
+};
 
 // STRUCTS
 // ================================================================================================
@@ -17,6 +42,7 @@ impl TransactionVerifier for TransactionVerifierSuccess {
     async fn verify_tx(
         &self,
         _tx: &ProvenTransaction,
+        _account_delta: Option<&AccountDelta>,
     ) -> Result<(), VerifyTxError> {
         Ok(())
     }
@@ -30,11 +56,41 @@ impl TransactionVerifier for TransactionVerifierFailure {
     async fn verify_tx(
         &self,
         tx: &ProvenTransaction,
+        _account_delta: Option<&AccountDelta>,
     ) -> Result<(), VerifyTxError> {
         Err(VerifyTxError::AccountAlreadyModifiedByOtherTx(tx.account_id()))
     }
 }
 
+/// All transactions verify successfully, the number of applied blocks being set by the test
+#[derive(Default)]
+struct TransactionVerifierBlocks {
+    num_applied_blocks: AtomicU32,
+    num_released: AtomicUsize,
+}
+
+#[async_trait]
+impl TransactionVerifier for TransactionVerifierBlocks {
+    async fn verify_tx(
+        &self,
+        _tx: &ProvenTransaction,
+        _account_delta: Option<&AccountDelta>,
+    ) -> Result<(), VerifyTxError> {
+        Ok(())
+    }
+
+    async fn release_tx(
+        &self,
+        _tx: &ProvenTransaction,
+    ) {
+        self.num_released.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn num_applied_blocks(&self) -> u32 {
+        self.num_applied_blocks.load(Ordering::Relaxed)
+    }
+}
+
 /// Records all batches built in `ready_batches`
 struct BatchBuilderSuccess {
     ready_batches: mpsc::UnboundedSender<TransactionBatch>,
@@ -51,8 +107,27 @@ impl BatchBuilder for BatchBuilderSuccess {
     async fn build_batch(
         &self,
         txs: Vec<ProvenTransaction>,
+        tx_kernel_versions: BTreeSet<u32>,
+        consumed_notes: Vec<(Digest, Digest)>,
+        private_notes: Vec<Digest>,
+    ) -> Result<(), BuildBatchError> {
+        let batch = TransactionBatch::new(txs)
+            .expect("Tx batch building should have succeeded")
+            .with_tx_kernel_versions(tx_kernel_versions)
+            .with_consumed_notes(consumed_notes)
+            .with_private_notes(private_notes);
+        self.ready_batches
+            .send(batch)
+            .expect("Sending to channel should have succeeded");
+
+        Ok(())
+    }
+
+    async fn add_batch(
+        &self,
+        batch: TransactionBatch,
+        _txs: Vec<ProvenTransaction>,
     ) -> Result<(), BuildBatchError> {
-        let batch = TransactionBatch::new(txs).expect("Tx batch building should have succeeded");
         self.ready_batches
             .send(batch)
             .expect("Sending to channel should have succeeded");
@@ -70,11 +145,85 @@ impl BatchBuilder for BatchBuilderFailure {
     async fn build_batch(
         &self,
         txs: Vec<ProvenTransaction>,
+        _tx_kernel_versions: BTreeSet<u32>,
+        _consumed_notes: Vec<(Digest, Digest)>,
+        _private_notes: Vec<Digest>,
+    ) -> Result<(), BuildBatchError> {
+        Err(BuildBatchError::TooManyNotesCreated(0, txs))
+    }
+
+    async fn add_batch(
+        &self,
+        _batch: TransactionBatch,
+        txs: Vec<ProvenTransaction>,
     ) -> Result<(), BuildBatchError> {
         Err(BuildBatchError::TooManyNotesCreated(0, txs))
     }
 }
 
+/// Never has room for a new batch, as if the ready batches always filled a block
+struct BatchBuilderReadyBatchesFull;
+
+#[async_trait]
+impl BatchBuilder for BatchBuilderReadyBatchesFull {
+    async fn build_batch(
+        &self,
+        txs: Vec<ProvenTransaction>,
+        _tx_kernel_versions: BTreeSet<u32>,
+        _consumed_notes: Vec<(Digest, Digest)>,
+        _private_notes: Vec<Digest>,
+    ) -> Result<(), BuildBatchError> {
+        Err(BuildBatchError::ReadyBatchesFull(txs))
+    }
+
+    async fn add_batch(
+        &self,
+        _batch: TransactionBatch,
+        txs: Vec<ProvenTransaction>,
+    ) -> Result<(), BuildBatchError> {
+        Err(BuildBatchError::ReadyBatchesFull(txs))
+    }
+}
+
+/// Rejects the transactions of a single account, counting the released transactions
+struct TransactionVerifierRejecting {
+    account_id: AccountId,
+    num_released: AtomicUsize,
+}
+
+#[async_trait]
+impl TransactionVerifier for TransactionVerifierRejecting {
+    async fn verify_tx(
+        &self,
+        tx: &ProvenTransaction,
+        _account_delta: Option<&AccountDelta>,
+    ) -> Result<(), VerifyTxError> {
+        if tx.account_id() == self.account_id {
+            return Err(VerifyTxError::AccountAlreadyModifiedByOtherTx(tx.account_id()));
+        }
+        Ok(())
+    }
+
+    async fn release_tx(
+        &self,
+        _tx: &ProvenTransaction,
+    ) {
+        self.num_released.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Load shedder with the given limits, which builds at most 8 batches concurrently, 1 when shedding
+fn load_shedder(limits: LoadSheddingConfig) -> Arc<LoadShedder> {
+    Arc::new(LoadShedder::new(
+        limits,
+        LoadShedderOptions {
+            check_frequency: Duration::from_millis(5),
+            max_concurrent_batches: 8,
+            shedding_concurrent_batches: 1,
+        },
+    ))
+}
+
 // TESTS
 // ================================================================================================
 
@@ -89,9 +238,15 @@ async fn test_build_batch_success() {
     let tx_queue = Arc::new(TransactionQueue::new(
         Arc::new(TransactionVerifierSuccess),
         Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
         TransactionQueueOptions {
             build_batch_frequency,
             batch_size,
+            max_batch_latency: build_batch_frequency,
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
         },
     ));
 
@@ -112,7 +267,7 @@ async fn test_build_batch_success() {
     // created with that single transaction
     let tx = tx_generator.dummy_proven_tx();
     tx_queue
-        .add_transaction(tx.clone())
+        .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
         .await
         .expect("Transaction queue is running");
 
@@ -123,7 +278,9 @@ async fn test_build_batch_success() {
         receiver.try_recv(),
         "A single transaction produces a single batch"
     );
-    let expected = TransactionBatch::new(vec![tx]).expect("Valid transactions");
+    let expected = TransactionBatch::new(vec![tx])
+        .expect("Valid transactions")
+        .with_tx_kernel_versions(BTreeSet::from([TX_KERNEL_VERSION]));
     assert_eq!(expected, batch, "The batch should have the one transaction added to the queue");
 
     // a batch will include up to `batch_size` transactions
@@ -131,7 +288,7 @@ async fn test_build_batch_success() {
     for _ in 0..batch_size {
         let tx = tx_generator.dummy_proven_tx();
         tx_queue
-            .add_transaction(tx.clone())
+            .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
             .await
             .expect("Transaciton queue is running");
         txs.push(tx)
@@ -143,7 +300,9 @@ async fn test_build_batch_success() {
         receiver.try_recv(),
         "{batch_size} transactions create a single batch"
     );
-    let expected = TransactionBatch::new(txs).expect("Valid transactions");
+    let expected = TransactionBatch::new(txs)
+        .expect("Valid transactions")
+        .with_tx_kernel_versions(BTreeSet::from([TX_KERNEL_VERSION]));
     assert_eq!(expected, batch, "The batch should the transactions to fill a batch");
 
     // the transaction queue eagerly produces batches
@@ -151,7 +310,7 @@ async fn test_build_batch_success() {
     for _ in 0..(2 * batch_size + 1) {
         let tx = tx_generator.dummy_proven_tx();
         tx_queue
-            .add_transaction(tx.clone())
+            .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
             .await
             .expect("Transaciton queue is running");
         txs.push(tx)
@@ -159,7 +318,9 @@ async fn test_build_batch_success() {
     for expected_batch in txs.chunks(batch_size).map(|txs| txs.to_vec()) {
         tokio::time::advance(build_batch_frequency).await;
         let batch = receiver.try_recv().expect("Queue not empty");
-        let expected = TransactionBatch::new(expected_batch).expect("Valid transactions");
+        let expected = TransactionBatch::new(expected_batch)
+            .expect("Valid transactions")
+            .with_tx_kernel_versions(BTreeSet::from([TX_KERNEL_VERSION]));
         assert_eq!(expected, batch, "The batch should the transactions to fill a batch");
     }
 
@@ -172,6 +333,77 @@ async fn test_build_batch_success() {
     );
 }
 
+/// Tests that a full batch is sealed as soon as enough transactions are queued, and that a partial
+/// batch is only sealed once its oldest transaction exceeds the maximum batch latency
+#[tokio::test(start_paused = true)]
+async fn test_build_batch_triggers() {
+    let build_batch_frequency = Duration::from_millis(5);
+    let max_batch_latency = Duration::from_millis(20);
+    let batch_size = 3;
+    let (sender, mut receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+
+    let tx_queue = Arc::new(TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency,
+            batch_size,
+            max_batch_latency,
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    ));
+
+    tokio::spawn(tx_queue.clone().run());
+
+    let tx_generator = DummyProvenTxGenerator::new();
+
+    // a full batch is sealed without waiting for the next tick
+    let mut txs = Vec::new();
+    for _ in 0..batch_size {
+        let tx = tx_generator.dummy_proven_tx();
+        tx_queue
+            .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+            .await
+            .expect("Transaction queue is running");
+        txs.push(tx);
+    }
+    tokio::task::yield_now().await;
+    let batch = receiver.try_recv().expect("Full batch should be sealed immediately");
+    assert_eq!(
+        TransactionBatch::new(txs)
+            .expect("Valid transactions")
+            .with_tx_kernel_versions(BTreeSet::from([TX_KERNEL_VERSION])),
+        batch
+    );
+
+    // a partial batch waits for the maximum latency
+    let tx = tx_generator.dummy_proven_tx();
+    tx_queue
+        .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await
+        .expect("Transaction queue is running");
+
+    tokio::time::advance(build_batch_frequency).await;
+    assert_eq!(
+        Err(TryRecvError::Empty),
+        receiver.try_recv(),
+        "Partial batch sealed before the maximum latency"
+    );
+
+    tokio::time::advance(max_batch_latency).await;
+    let batch = receiver.try_recv().expect("Partial batch should be sealed after the latency");
+    assert_eq!(
+        TransactionBatch::new(vec![tx])
+            .expect("Valid transactions")
+            .with_tx_kernel_versions(BTreeSet::from([TX_KERNEL_VERSION])),
+        batch
+    );
+}
+
 /// Tests that when transactions fail to verify, they are not added to the queue
 #[tokio::test(start_paused = true)]
 async fn test_tx_verify_failure() {
@@ -184,9 +416,15 @@ async fn test_tx_verify_failure() {
     let tx_queue = Arc::new(TransactionQueue::new(
         Arc::new(TransactionVerifierFailure),
         batch_builder.clone(),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
         TransactionQueueOptions {
             build_batch_frequency,
             batch_size,
+            max_batch_latency: build_batch_frequency,
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
         },
     ));
 
@@ -196,7 +434,15 @@ async fn test_tx_verify_failure() {
     // Add a bunch of transactions that will all fail tx verification
     let proven_tx_generator = DummyProvenTxGenerator::new();
     for _ in 0..(3 * batch_size) {
-        let r = tx_queue.add_transaction(proven_tx_generator.dummy_proven_tx()).await;
+        let r = tx_queue
+            .add_transaction(
+                proven_tx_generator.dummy_proven_tx(),
+                TX_KERNEL_VERSION,
+                None,
+                Vec::new(),
+                Vec::new(),
+            )
+            .await;
 
         assert!(matches!(r, Err(AddTransactionError::VerificationFailed(_))));
         assert_eq!(
@@ -218,9 +464,15 @@ async fn test_build_batch_failure() {
     let tx_queue = TransactionQueue::new(
         Arc::new(TransactionVerifierSuccess),
         batch_builder.clone(),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
         TransactionQueueOptions {
             build_batch_frequency,
             batch_size,
+            max_batch_latency: build_batch_frequency,
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
         },
     );
 
@@ -230,7 +482,16 @@ async fn test_build_batch_failure() {
 
     // Add enough transactions so that we have 1 batch
     for _i in 0..batch_size {
-        tx_queue.add_transaction(proven_tx_generator.dummy_proven_tx()).await.unwrap();
+        tx_queue
+            .add_transaction(
+                proven_tx_generator.dummy_proven_tx(),
+                TX_KERNEL_VERSION,
+                None,
+                Vec::new(),
+                Vec::new(),
+            )
+            .await
+            .unwrap();
     }
 
     // Start the queue
@@ -241,3 +502,1567 @@ async fn test_build_batch_failure() {
 
     assert_eq!(internal_ready_queue.read().await.len(), 3);
 }
+
+/// Tests that the transactions of batches which keep failing are dropped once they were part of
+/// `max_batch_attempts` failed batches
+#[tokio::test]
+async fn test_transactions_dropped_after_batch_failures() {
+    let build_batch_frequency = Duration::from_millis(30);
+    let batch_size = 3;
+
+    let tx_queue = TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderFailure),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency,
+            batch_size,
+            max_batch_latency: build_batch_frequency,
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 2,
+            dropped_history: 10,
+        },
+    );
+
+    let internal_ready_queue = tx_queue.ready_queue.clone();
+    let txs: Vec<_> = (0..batch_size).map(|_| MockProvenTxBuilder::new().build()).collect();
+
+    // the batch fails a first time as soon as it is full
+    for tx in &txs {
+        tx_queue
+            .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+            .await
+            .unwrap();
+    }
+    time::sleep(Duration::from_millis(5)).await;
+    assert_eq!(internal_ready_queue.read().await.len(), batch_size);
+    assert!(tx_queue.get_dropped_transactions_by_account(txs[0].account_id()).is_empty());
+
+    // and a second time once the queue runs
+    let tx_queue = Arc::new(tx_queue);
+    tokio::spawn(tx_queue.clone().run());
+    time::sleep(Duration::from_millis(45)).await;
+
+    assert!(internal_ready_queue.read().await.is_empty());
+    for tx in &txs {
+        let dropped = tx_queue.get_dropped_transactions_by_account(tx.account_id());
+        assert_eq!(dropped.len(), 1);
+        assert_eq!((dropped[0].tx_id, dropped[0].reason), (tx.id(), DropReason::BatchFailures));
+    }
+}
+
+/// Tests that pending transactions are reported per account, together with their queue position
+#[tokio::test]
+async fn test_get_pending_transactions_by_account() {
+    let (sender, _receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+
+    let tx_queue = TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 3,
+            max_batch_latency: Duration::from_millis(5),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    );
+
+    let tx_account_1 = MockProvenTxBuilder::new().build();
+    let tx_account_2 = MockProvenTxBuilder::new().build();
+
+    assert_eq!(
+        tx_queue
+            .add_transaction(tx_account_1.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        tx_queue
+            .add_transaction(tx_account_2.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+            .await
+            .unwrap(),
+        1
+    );
+
+    let pending = tx_queue.get_pending_transactions_by_account(tx_account_2.account_id()).await;
+    let expected = vec![PendingTransaction {
+        tx_id: tx_account_2.id(),
+        initial_account_hash: tx_account_2.initial_account_hash(),
+        final_account_hash: tx_account_2.final_account_hash(),
+        queue_position: 1,
+    }];
+    assert_eq!(pending, expected);
+
+    // the queue is not running, so the transactions were not batched yet
+    let pending = tx_queue.get_pending_transactions_by_account(tx_account_1.account_id()).await;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].queue_position, 0);
+}
+
+/// Tests that transactions are rejected while the load shedder sheds load, and accepted again once
+/// the load is back under the limits
+#[tokio::test]
+async fn test_add_transaction_rejected_while_shedding() {
+    let (sender, _receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+
+    let load_shedder = load_shedder(LoadSheddingConfig {
+        max_rss_mb: 0,
+        max_queued_transactions: 10,
+        max_ready_batches: 0,
+    });
+
+    let tx_queue = TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder.clone(),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 3,
+            max_batch_latency: Duration::from_millis(5),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    );
+
+    let proven_tx_generator = DummyProvenTxGenerator::new();
+
+    assert!(load_shedder.update(&LoadSample {
+        rss_bytes: None,
+        queued_transactions: 11,
+        ready_batches: 0,
+    }));
+    assert_eq!(load_shedder.max_concurrent_batches(), 1);
+
+    let result = tx_queue
+        .add_transaction(
+            proven_tx_generator.dummy_proven_tx(),
+            TX_KERNEL_VERSION,
+            None,
+            Vec::new(),
+            Vec::new(),
+        )
+        .await;
+    assert!(matches!(result, Err(AddTransactionError::Overloaded)));
+    assert_eq!(tx_queue.num_queued_transactions().await, 0);
+
+    // still over 90% of the limit
+    assert!(load_shedder.update(&LoadSample {
+        rss_bytes: None,
+        queued_transactions: 10,
+        ready_batches: 0,
+    }));
+
+    assert!(!load_shedder.update(&LoadSample {
+        rss_bytes: None,
+        queued_transactions: 9,
+        ready_batches: 0,
+    }));
+    assert_eq!(load_shedder.max_concurrent_batches(), 8);
+
+    tx_queue
+        .add_transaction(
+            proven_tx_generator.dummy_proven_tx(),
+            TX_KERNEL_VERSION,
+            None,
+            Vec::new(),
+            Vec::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(tx_queue.num_queued_transactions().await, 1);
+}
+
+/// Tests that consuming transactions get the reserved share of the selection, that unused reserved
+/// capacity goes to the other lane, and that the transactions of an account keep their order
+#[test]
+fn test_select_transactions() {
+    let account = |index| MockPrivateAccount::<3>::from(index).id;
+    let (a, b, c) = (account(1000), account(1001), account(1002));
+
+    let queue = [
+        (Lane::Creating, a, None),
+        (Lane::Creating, a, None),
+        (Lane::Creating, a, None),
+        (Lane::Consuming, b, None),
+        (Lane::Creating, b, None),
+        (Lane::Consuming, c, None),
+    ];
+
+    // arrival order without a reservation
+    assert_eq!(lanes::select_transactions(&queue, 3, 0, &[]), vec![0, 1, 2]);
+
+    // the consuming transactions are selected ahead of older creating transactions
+    assert_eq!(lanes::select_transactions(&queue, 3, 2, &[]), vec![0, 3, 5]);
+
+    // unused reserved capacity is given to the creating lane
+    assert_eq!(lanes::select_transactions(&queue, 5, 4, &[]), vec![0, 1, 2, 3, 5]);
+
+    // a consuming transaction is not selected ahead of an older transaction of its account
+    let queue = [(Lane::Creating, a, None), (Lane::Creating, b, None), (Lane::Consuming, a, None)];
+    assert_eq!(lanes::select_transactions(&queue, 1, 1, &[]), vec![0]);
+    assert_eq!(lanes::select_transactions(&queue, 2, 1, &[]), vec![0, 1]);
+    assert_eq!(lanes::select_transactions(&queue, 3, 1, &[]), vec![0, 1, 2]);
+}
+
+/// Tests that the batches are sealed at the transaction or note limit, whichever comes first
+#[test]
+fn test_batch_sizes() {
+    // only the number of transactions limits the batches
+    assert_eq!(batch_sizes(&[1, 1, 1, 1, 1], 2, 10), vec![2, 2, 1]);
+
+    // a batch is sealed exactly at the note limit, before the transaction which would exceed it
+    assert_eq!(batch_sizes(&[4, 6, 1, 9, 2], 4, 10), vec![2, 2, 1]);
+    assert_eq!(batch_sizes(&[3, 3, 3, 3], 4, 10), vec![3, 1]);
+
+    // a transaction over the note limit is batched alone
+    assert_eq!(batch_sizes(&[1, 12, 1], 4, 10), vec![1, 1, 1]);
+
+    assert_eq!(batch_sizes(&[], 4, 10), Vec::<usize>::new());
+}
+
+/// Tests that the transactions of every partner get their reserved share of the selection ahead of
+/// the consuming lane, and that unused reservations are given to the other transactions
+#[test]
+fn test_select_partner_transactions() {
+    let account = |index| MockPrivateAccount::<3>::from(index).id;
+    let (a, b, c, d) = (account(1000), account(1001), account(1002), account(1003));
+
+    let queue = [
+        (Lane::Creating, a, None),
+        (Lane::Consuming, b, None),
+        (Lane::Creating, c, Some(0)),
+        (Lane::Creating, d, Some(1)),
+        (Lane::Creating, c, Some(0)),
+    ];
+
+    // every partner gets its reservation, ahead of the consuming lane
+    assert_eq!(lanes::select_transactions(&queue, 2, 1, &[1, 1]), vec![2, 3]);
+    assert_eq!(lanes::select_transactions(&queue, 3, 1, &[2, 0]), vec![1, 2, 4]);
+
+    // a partner without queued transactions leaves its reservation to the others
+    assert_eq!(lanes::select_transactions(&queue, 3, 0, &[0, 0, 2]), vec![0, 1, 2]);
+
+    // a partner transaction is not selected ahead of an older transaction of its account
+    let queue = [(Lane::Creating, a, None), (Lane::Creating, a, Some(0))];
+    assert_eq!(lanes::select_transactions(&queue, 1, 0, &[1]), vec![0]);
+}
+
+/// Tests that the transactions signed by a partner are selected against its reservation, and that
+/// the transactions signed by anyone else are rejected
+#[tokio::test]
+async fn test_partner_reservations() {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+
+    let partner_key = KeyPair::new().unwrap();
+    let reservations = Reservations::from_config(&ReservationsConfig {
+        partners: vec![PartnerConfig {
+            name: "exchange".to_string(),
+            key: format_key(partner_key.public_key()),
+            reserved_percent: 50,
+        }],
+    })
+    .unwrap();
+
+    let tx_queue = TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 4,
+            max_batch_latency: Duration::from_secs(60),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    )
+    .with_reservations(reservations);
+
+    let txs: Vec<_> = (0..6).map(|_| MockProvenTxBuilder::new().build()).collect();
+    tx_queue
+        .ready_queue
+        .write()
+        .await
+        .extend(txs.iter().map(|tx| QueuedTransaction::new(tx.clone(), TX_KERNEL_VERSION, None)));
+
+    // transactions signed by an unknown key, or not signed by the partner's key, are rejected
+    let partner_tx = MockProvenTxBuilder::new().build();
+    let other_key = KeyPair::new().unwrap();
+    let signature = PartnerSignature::sign(&other_key, partner_tx.id().inner()).unwrap();
+    let result = tx_queue
+        .add_partner_transaction(
+            partner_tx.clone(),
+            TX_KERNEL_VERSION,
+            None,
+            Vec::new(),
+            Vec::new(),
+            &signature,
+        )
+        .await;
+    assert!(matches!(result, Err(AddTransactionError::UnknownPartner(_))));
+
+    let signature = PartnerSignature::sign(&partner_key, txs[0].id().inner()).unwrap();
+    let result = tx_queue
+        .add_partner_transaction(
+            partner_tx.clone(),
+            TX_KERNEL_VERSION,
+            None,
+            Vec::new(),
+            Vec::new(),
+            &signature,
+        )
+        .await;
+    assert!(matches!(result, Err(AddTransactionError::InvalidPartnerSignature(_))));
+    assert_eq!(tx_queue.num_queued_transactions().await, 6);
+
+    // the partner's transaction is batched ahead of older transactions, its unused reservation
+    // goes to the oldest ones
+    let signature = PartnerSignature::sign(&partner_key, partner_tx.id().inner()).unwrap();
+    tx_queue
+        .add_partner_transaction(
+            partner_tx.clone(),
+            TX_KERNEL_VERSION,
+            None,
+            Vec::new(),
+            Vec::new(),
+            &signature,
+        )
+        .await
+        .unwrap();
+
+    let batch = receiver.recv().await.expect("A batch should have been built");
+    let expected =
+        TransactionBatch::new(vec![txs[0].clone(), txs[1].clone(), txs[2].clone(), partner_tx])
+            .expect("Valid transactions")
+            .with_tx_kernel_versions(BTreeSet::from([TX_KERNEL_VERSION]));
+    assert_eq!(batch, expected);
+    assert_eq!(tx_queue.num_queued_transactions().await, 3);
+}
+
+/// Tests that the queue reserves part of a batch for consuming transactions, even if older creating
+/// transactions fill the queue
+#[tokio::test]
+async fn test_consuming_lane_reserved() {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+
+    let load_shedder = load_shedder(LoadSheddingConfig {
+        max_rss_mb: 0,
+        max_queued_transactions: 1,
+        max_ready_batches: 0,
+    });
+    let tx_queue = TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder.clone(),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 2,
+            max_batch_latency: Duration::from_millis(5),
+            reserved_consuming_percent: 50,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    );
+
+    let creating_txs: Vec<_> = (0..4).map(|_| MockProvenTxBuilder::new().build()).collect();
+    let account = MockPrivateAccount::<3>::from(2000);
+    let consuming_tx = DummyProvenTxGenerator::new().dummy_proven_tx_with_params(
+        account.id,
+        account.states[0],
+        account.states[1],
+        InputNotes::new(vec![nullifier_by_index(2000)]).unwrap(),
+        OutputNotes::new(Vec::new()).unwrap(),
+    );
+    assert_eq!(Lane::of(&consuming_tx), Lane::Consuming);
+
+    tx_queue.ready_queue.write().await.extend(
+        creating_txs
+            .iter()
+            .chain([&consuming_tx])
+            .map(|tx| QueuedTransaction::new(tx.clone(), TX_KERNEL_VERSION, None)),
+    );
+    assert_eq!(tx_queue.num_queued_transactions_in_lane(Lane::Consuming).await, 1);
+    assert_eq!(tx_queue.num_queued_transactions_in_lane(Lane::Creating).await, 4);
+
+    // a single batch is built while shedding load
+    assert!(load_shedder.update(&LoadSample {
+        rss_bytes: None,
+        queued_transactions: 5,
+        ready_batches: 0,
+    }));
+    tx_queue.try_build_batches().await;
+
+    let batch = receiver.recv().await.expect("A batch should have been built");
+    let expected = TransactionBatch::new(vec![creating_txs[0].clone(), consuming_tx])
+        .expect("Valid transactions")
+        .with_tx_kernel_versions(BTreeSet::from([TX_KERNEL_VERSION]));
+    assert_eq!(batch, expected);
+    assert_eq!(tx_queue.num_queued_transactions().await, 3);
+    assert_eq!(tx_queue.num_queued_transactions_in_lane(Lane::Consuming).await, 0);
+
+    assert_eq!(tx_queue.lane_stats(Lane::Consuming).selected, 1);
+    assert_eq!(tx_queue.lane_stats(Lane::Creating).selected, 1);
+}
+
+/// Tests that a full queue rejects or evicts transactions according to its eviction policy
+#[tokio::test]
+async fn test_queue_eviction() {
+    let new_queue = |eviction_policy: Box<dyn EvictionPolicy>| {
+        let (sender, _receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+        TransactionQueue::new(
+            Arc::new(TransactionVerifierSuccess),
+            Arc::new(BatchBuilderSuccess::new(sender)),
+            load_shedder(LoadSheddingConfig::default()),
+            Arc::new(LatencyTracker::new(10)),
+            TransactionQueueOptions {
+                build_batch_frequency: Duration::from_millis(5),
+                batch_size: 10,
+                max_batch_latency: Duration::from_secs(10),
+                reserved_consuming_percent: 0,
+                max_batch_attempts: 10,
+                dropped_history: 10,
+            },
+        )
+        .with_eviction(2, eviction_policy)
+    };
+    let txs: Vec<_> = (0..3).map(|_| MockProvenTxBuilder::new().build()).collect();
+
+    // new transactions are rejected by default
+    let tx_queue = new_queue(Box::new(RejectNewest));
+    tx_queue
+        .add_transaction(txs[0].clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+    tx_queue
+        .add_transaction(txs[1].clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+    let result = tx_queue
+        .add_transaction(txs[2].clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await;
+    assert!(matches!(result, Err(AddTransactionError::QueueFull)));
+    assert_eq!(tx_queue.num_queued_transactions().await, 2);
+
+    // the oldest transaction makes room for the new one
+    let tx_queue = new_queue(Box::new(OldestFirst));
+    for tx in &txs {
+        tx_queue
+            .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+            .await
+            .unwrap();
+    }
+    let queued: Vec<_> =
+        tx_queue.ready_queue.read().await.iter().map(|queued| queued.tx.id()).collect();
+    assert_eq!(queued, vec![txs[1].id(), txs[2].id()]);
+
+    // the evicted transaction is reported as dropped, until it is accepted again
+    let dropped = tx_queue.get_dropped_transactions_by_account(txs[0].account_id());
+    assert_eq!(dropped.len(), 1);
+    assert_eq!((dropped[0].tx_id, dropped[0].reason), (txs[0].id(), DropReason::Evicted));
+
+    tx_queue
+        .add_transaction(txs[0].clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+    assert!(tx_queue.get_dropped_transactions_by_account(txs[0].account_id()).is_empty());
+    assert_eq!(tx_queue.get_dropped_transactions_by_account(txs[1].account_id()).len(), 1);
+}
+
+/// Tests that the transactions not batched while `ttl_blocks` blocks were applied are dropped, and
+/// released from the verifier
+#[tokio::test]
+async fn test_queue_ttl() {
+    let tx_verifier = Arc::new(TransactionVerifierBlocks::default());
+    let (sender, _receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+    let tx_queue = TransactionQueue::new(
+        tx_verifier.clone(),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 10,
+            max_batch_latency: Duration::from_secs(10),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    )
+    .with_ttl(2);
+    let txs: Vec<_> = (0..2).map(|_| MockProvenTxBuilder::new().build()).collect();
+
+    tx_queue
+        .add_transaction(txs[0].clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+    tx_verifier.num_applied_blocks.store(1, Ordering::Relaxed);
+    tx_queue
+        .add_transaction(txs[1].clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+
+    tx_queue.expire_transactions().await;
+    assert_eq!(tx_queue.num_queued_transactions().await, 2);
+
+    // the first transaction waited two blocks, the second one a single block
+    tx_verifier.num_applied_blocks.store(2, Ordering::Relaxed);
+    tx_queue.expire_transactions().await;
+    let queued: Vec<_> =
+        tx_queue.ready_queue.read().await.iter().map(|queued| queued.tx.id()).collect();
+    assert_eq!(queued, vec![txs[1].id()]);
+    assert_eq!(tx_verifier.num_released.load(Ordering::Relaxed), 1);
+
+    let dropped = tx_queue.get_dropped_transactions_by_account(txs[0].account_id());
+    assert_eq!(dropped.len(), 1);
+    assert_eq!((dropped[0].tx_id, dropped[0].reason), (txs[0].id(), DropReason::Expired));
+}
+
+/// Tests that a transaction is only selected once the transaction of its account it follows is
+/// batched ahead of it, in the same batch or an earlier round
+#[test]
+fn test_chained_transactions_selection() {
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account = MockPrivateAccount::<4>::from(5000);
+    let chained_tx = |initial: usize| {
+        let tx = tx_gen.dummy_proven_tx_with_params(
+            account.id,
+            account.states[initial],
+            account.states[initial + 1],
+            InputNotes::new(Vec::new()).unwrap(),
+            OutputNotes::new(Vec::new()).unwrap(),
+        );
+        QueuedTransaction::new(tx, TX_KERNEL_VERSION, None)
+    };
+    let other_tx =
+        QueuedTransaction::new(MockProvenTxBuilder::new().build(), TX_KERNEL_VERSION, None);
+    let no_transitions = BTreeSet::new();
+
+    // chained transactions of the same batch
+    let queue = vec![chained_tx(0), other_tx.clone(), chained_tx(1)];
+    let selection = select_chained_transactions(&queue, vec![0, 1, 2], &no_transitions, 10, 2);
+    assert_eq!(selection, (vec![0, 1, 2], vec![3]));
+
+    // the second transaction would be part of another batch built concurrently
+    let selection = select_chained_transactions(&queue, vec![0, 1, 2], &no_transitions, 1, 3);
+    assert_eq!(selection, (vec![0, 1], vec![1, 1]));
+
+    // the first transaction is not selected, or is being batched
+    let selection = select_chained_transactions(&queue, vec![1, 2], &no_transitions, 10, 2);
+    assert_eq!(selection, (vec![1], vec![1]));
+    let queue = vec![other_tx, chained_tx(1), chained_tx(2)];
+    let in_batches = BTreeSet::from([(account.id, account.states[1])]);
+    let selection = select_chained_transactions(&queue, vec![0, 1, 2], &in_batches, 10, 2);
+    assert_eq!(selection, (vec![0], vec![1]));
+
+    // the first transaction was queued again behind the second one after its batch failed
+    let queue = vec![chained_tx(1), chained_tx(0)];
+    let selection = select_chained_transactions(&queue, vec![0, 1], &no_transitions, 10, 2);
+    assert_eq!(selection, (vec![1], vec![1]));
+}
+
+/// Tests that the queued transactions following a dropped transaction of their account are
+/// dropped as orphaned
+#[tokio::test]
+async fn test_orphaned_transactions_dropped() {
+    let tx_verifier = Arc::new(TransactionVerifierBlocks::default());
+    let (sender, _receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+    let tx_queue = TransactionQueue::new(
+        tx_verifier.clone(),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 10,
+            max_batch_latency: Duration::from_secs(10),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    )
+    .with_ttl(2);
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account = MockPrivateAccount::<4>::from(6000);
+    let chained_txs: Vec<_> = (0..3)
+        .map(|initial| {
+            tx_gen.dummy_proven_tx_with_params(
+                account.id,
+                account.states[initial],
+                account.states[initial + 1],
+                InputNotes::new(Vec::new()).unwrap(),
+                OutputNotes::new(Vec::new()).unwrap(),
+            )
+        })
+        .collect();
+    let other_tx = MockProvenTxBuilder::new().build();
+
+    tx_queue
+        .add_transaction(chained_txs[0].clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+    tx_verifier.num_applied_blocks.store(1, Ordering::Relaxed);
+    for tx in [&chained_txs[1], &chained_txs[2], &other_tx] {
+        tx_queue
+            .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+            .await
+            .unwrap();
+    }
+
+    // only the first transaction expires, the ones following it can't be applied anymore and are
+    // dropped along with it
+    tx_verifier.num_applied_blocks.store(2, Ordering::Relaxed);
+    tx_queue.expire_transactions().await;
+
+    let queued: Vec<_> =
+        tx_queue.ready_queue.read().await.iter().map(|queued| queued.tx.id()).collect();
+    assert_eq!(queued, vec![other_tx.id()]);
+    assert_eq!(tx_verifier.num_released.load(Ordering::Relaxed), 3);
+
+    let dropped: Vec<_> = tx_queue
+        .get_dropped_transactions_by_account(account.id)
+        .into_iter()
+        .map(|dropped| (dropped.tx_id, dropped.reason))
+        .collect();
+    assert_eq!(
+        dropped,
+        vec![
+            (chained_txs[0].id(), DropReason::Expired),
+            (chained_txs[1].id(), DropReason::Orphaned),
+            (chained_txs[2].id(), DropReason::Orphaned),
+        ]
+    );
+}
+
+/// Tests that the transactions following a transaction dropped while being batched are dropped
+/// before a batch is selected, rather than once the queue drops the orphans on its own
+#[tokio::test]
+async fn test_orphaned_transactions_not_batched() {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+    let tx_queue = TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 2,
+            max_batch_latency: Duration::ZERO,
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    );
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account = MockPrivateAccount::<3>::from(6500);
+    let chained_txs: Vec<_> = (0..2)
+        .map(|initial| {
+            tx_gen.dummy_proven_tx_with_params(
+                account.id,
+                account.states[initial],
+                account.states[initial + 1],
+                InputNotes::new(Vec::new()).unwrap(),
+                OutputNotes::new(Vec::new()).unwrap(),
+            )
+        })
+        .collect();
+    let other_tx = MockProvenTxBuilder::new().build();
+
+    // the first transaction was dropped out of the queue, e.g. by a failed batch, the second one
+    // still follows it
+    tx_queue.dropped.record(&chained_txs[0], DropReason::BatchFailures);
+    tx_queue.ready_queue.write().await.extend(
+        [&chained_txs[1], &other_tx]
+            .into_iter()
+            .map(|tx| QueuedTransaction::new(tx.clone(), TX_KERNEL_VERSION, None)),
+    );
+    tx_queue.try_build_batches().await;
+
+    let batch = receiver.recv().await.unwrap();
+    let batched: Vec<_> = batch.transaction_ids().collect();
+    assert_eq!(batched, vec![other_tx.id()]);
+    assert!(tx_queue.ready_queue.read().await.is_empty());
+
+    let dropped = tx_queue.get_dropped_transactions_by_account(account.id);
+    assert_eq!(
+        (dropped[1].tx_id, dropped[1].reason),
+        (chained_txs[1].id(), DropReason::Orphaned)
+    );
+}
+
+/// Tests that evicting a transaction drops the queued transaction following it along with it, so
+/// the chained transaction never reaches a batch
+#[tokio::test]
+async fn test_evicted_parent_orphans_child() {
+    let tx_verifier = Arc::new(TransactionVerifierBlocks::default());
+    let (sender, mut receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+    let tx_queue = TransactionQueue::new(
+        tx_verifier.clone(),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 3,
+            max_batch_latency: Duration::ZERO,
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    )
+    .with_eviction(2, Box::new(OldestFirst));
+    let tx_gen = DummyProvenTxGenerator::new();
+    let account = MockPrivateAccount::<3>::from(6600);
+    let chained_txs: Vec<_> = (0..2)
+        .map(|initial| {
+            tx_gen.dummy_proven_tx_with_params(
+                account.id,
+                account.states[initial],
+                account.states[initial + 1],
+                InputNotes::new(Vec::new()).unwrap(),
+                OutputNotes::new(Vec::new()).unwrap(),
+            )
+        })
+        .collect();
+    let other_tx = MockProvenTxBuilder::new().build();
+
+    // the child is accepted while its parent is queued
+    for tx in &chained_txs {
+        tx_queue
+            .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+            .await
+            .unwrap();
+    }
+
+    // the parent is the oldest transaction, evicted to admit the new one, along with its child
+    tx_queue
+        .add_transaction(other_tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+    let queued: Vec<_> =
+        tx_queue.ready_queue.read().await.iter().map(|queued| queued.tx.id()).collect();
+    assert_eq!(queued, vec![other_tx.id()]);
+    assert_eq!(tx_verifier.num_released.load(Ordering::Relaxed), 2);
+
+    let dropped: Vec<_> = tx_queue
+        .get_dropped_transactions_by_account(account.id)
+        .into_iter()
+        .map(|dropped| (dropped.tx_id, dropped.reason))
+        .collect();
+    assert_eq!(
+        dropped,
+        vec![
+            (chained_txs[0].id(), DropReason::Evicted),
+            (chained_txs[1].id(), DropReason::Orphaned),
+        ]
+    );
+
+    // only the new transaction is batched
+    tx_queue.try_build_batches().await;
+    let batch = receiver.recv().await.unwrap();
+    assert_eq!(batch.transaction_ids().collect::<Vec<_>>(), vec![other_tx.id()]);
+    tx_queue.try_build_batches().await;
+    assert_eq!(Err(TryRecvError::Empty), receiver.try_recv());
+}
+
+/// Tests that the lowest fee first policy evicts the oldest of the transactions paying the lowest
+/// fee, if the new transaction pays more
+#[test]
+fn test_lowest_fee_first_policy() {
+    // the fee of a transaction is the number of notes it creates
+    let txs: Vec<_> = (0..3)
+        .map(|num_notes| MockProvenTxBuilder::new().num_notes_created(num_notes).build())
+        .collect();
+    let queue: Vec<_> = txs[..2].iter().collect();
+    let policy = LowestFeeFirst {
+        fee: |tx| tx.output_notes().iter().count() as u64,
+    };
+
+    // the first transaction pays the lowest fee
+    assert_eq!(policy.evict(&queue, &txs[2]), Eviction::Evict(0));
+
+    // a new transaction paying no more than the lowest fee is rejected
+    assert_eq!(policy.evict(&queue, &txs[0]), Eviction::RejectCandidate);
+
+    // the oldest transaction is evicted among the ones paying the same fee
+    let queue = [&txs[2], &txs[1], &txs[1]];
+    let candidate = &txs[2];
+    assert_eq!(policy.evict(&queue, candidate), Eviction::Evict(1));
+
+    // transactions don't pay fees yet, every new transaction is rejected
+    let policy = LowestFeeFirst::default();
+    assert_eq!(transaction_fee(&txs[2]), 0);
+    assert_eq!(policy.evict(&queue, candidate), Eviction::RejectCandidate);
+}
+
+/// Tests that the account cap policy evicts the newest transaction of the account over its cap
+#[test]
+fn test_account_cap_policy() {
+    let account = MockPrivateAccount::<3>::from(4000);
+    let account_tx = |index: usize| {
+        DummyProvenTxGenerator::new().dummy_proven_tx_with_params(
+            account.id,
+            account.states[index],
+            account.states[index + 1],
+            InputNotes::new(Vec::new()).unwrap(),
+            OutputNotes::new(Vec::new()).unwrap(),
+        )
+    };
+    let other_tx = MockProvenTxBuilder::new().build();
+    let policy = AccountCap { max_per_account: 1 };
+
+    // the account holds two transactions, above its cap
+    let queue = [account_tx(0), other_tx.clone(), account_tx(1)];
+    let queue: Vec<_> = queue.iter().collect();
+    let candidate = MockProvenTxBuilder::new().build();
+    assert_eq!(policy.evict(&queue, &candidate), Eviction::Evict(2));
+
+    // transactions of an account at its cap are rejected
+    assert_eq!(policy.evict(&queue, &account_tx(1)), Eviction::RejectCandidate);
+
+    // no account is over its cap
+    let queue = [queue[0], &other_tx];
+    assert_eq!(policy.evict(&queue, &candidate), Eviction::RejectCandidate);
+}
+
+/// Tests that only the supported transaction kernels are accepted, and that batches record the
+/// kernels of their transactions
+#[tokio::test]
+async fn test_tx_kernel_versions() {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+    let tx_queue = TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 2,
+            max_batch_latency: Duration::from_secs(10),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    )
+    .with_tx_kernel(TxKernelConfig {
+        min_version: 1,
+        max_version: 2,
+    });
+
+    let result = tx_queue
+        .add_transaction(MockProvenTxBuilder::new().build(), 3, None, Vec::new(), Vec::new())
+        .await;
+    assert!(matches!(
+        result,
+        Err(AddTransactionError::UnsupportedTxKernelVersion {
+            version: 3,
+            min_version: 1,
+            max_version: 2
+        })
+    ));
+
+    let txs: Vec<_> = (0..2).map(|_| MockProvenTxBuilder::new().build()).collect();
+    tx_queue
+        .add_transaction(txs[0].clone(), 1, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+    tx_queue
+        .add_transaction(txs[1].clone(), 2, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+
+    let batch = receiver.recv().await.expect("A batch should have been built");
+    assert_eq!(batch.tx_kernel_versions().collect::<Vec<_>>(), vec![1, 2]);
+}
+
+/// Tests that the public notes submitted along with their transactions are linked to their
+/// nullifiers in the batches, and that a note not consumed by its transaction is rejected
+#[tokio::test]
+async fn test_consumed_notes_are_linked() {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+    let tx_queue = TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 2,
+            max_batch_latency: Duration::from_secs(10),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    );
+
+    let notes: Vec<_> = (0..2).map(note_by_index).collect();
+    let txs: Vec<_> = notes
+        .iter()
+        .enumerate()
+        .map(|(index, note)| {
+            let account = MockPrivateAccount::<3>::from(3000 + index as u32);
+            DummyProvenTxGenerator::new().dummy_proven_tx_with_params(
+                account.id,
+                account.states[0],
+                account.states[1],
+                InputNotes::new(vec![note.nullifier()]).unwrap(),
+                OutputNotes::new(Vec::new()).unwrap(),
+            )
+        })
+        .collect();
+
+    // the first note isn't consumed by the second transaction
+    let result = tx_queue
+        .add_transaction(
+            txs[1].clone(),
+            TX_KERNEL_VERSION,
+            None,
+            vec![notes[0].clone()],
+            Vec::new(),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(AddTransactionError::NoteNotConsumed(note_hash)) if note_hash == notes[0].id().inner()
+    ));
+    assert_eq!(tx_queue.num_queued_transactions().await, 0);
+
+    // the second transaction consumes a note it doesn't submit, which isn't linked
+    tx_queue
+        .add_transaction(
+            txs[0].clone(),
+            TX_KERNEL_VERSION,
+            None,
+            vec![notes[0].clone()],
+            Vec::new(),
+        )
+        .await
+        .unwrap();
+    tx_queue
+        .add_transaction(txs[1].clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+
+    let batch = receiver.recv().await.expect("A batch should have been built");
+    assert_eq!(
+        batch.consumed_notes().collect::<Vec<_>>(),
+        vec![(notes[0].id().inner(), notes[0].nullifier().inner())]
+    );
+    assert_eq!(
+        batch.produced_nullifiers().collect::<Vec<_>>(),
+        notes.iter().map(|note| note.nullifier().inner()).collect::<Vec<_>>()
+    );
+}
+
+/// Tests that the private notes submitted along with a transaction must be created by it, and are
+/// marked as private in its batch
+#[tokio::test]
+async fn test_private_notes_are_marked() {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+    let tx_queue = TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 2,
+            max_batch_latency: Duration::from_secs(10),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    );
+
+    let txs: Vec<_> = (0..2)
+        .map(|_| MockProvenTxBuilder::new().num_notes_created(2).build())
+        .collect();
+    let note_hashes: Vec<Vec<Digest>> = txs
+        .iter()
+        .map(|tx| tx.output_notes().iter().map(|note| note.note_id().inner()).collect())
+        .collect();
+
+    // the first transaction doesn't create the notes of the second one
+    let result = tx_queue
+        .add_transaction(
+            txs[0].clone(),
+            TX_KERNEL_VERSION,
+            None,
+            Vec::new(),
+            note_hashes[1].clone(),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(AddTransactionError::NoteNotCreated(note_hash)) if note_hash == note_hashes[1][0]
+    ));
+    assert_eq!(tx_queue.num_queued_transactions().await, 0);
+
+    tx_queue
+        .add_transaction(
+            txs[0].clone(),
+            TX_KERNEL_VERSION,
+            None,
+            Vec::new(),
+            vec![note_hashes[0][1]],
+        )
+        .await
+        .unwrap();
+    tx_queue
+        .add_transaction(txs[1].clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+
+    let batch = receiver.recv().await.expect("A batch should have been built");
+    assert_eq!(batch.private_notes().collect::<Vec<_>>(), vec![note_hashes[0][1]]);
+    assert_eq!(batch.created_notes().count(), 4);
+}
+
+/// Tests that the transactions of a batch are ordered by id with the canonical ordering, and as
+/// they arrived otherwise
+#[tokio::test]
+async fn test_tx_ordering() {
+    for tx_ordering in [TxOrdering::Arrival, TxOrdering::Canonical] {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+        let tx_queue = TransactionQueue::new(
+            Arc::new(TransactionVerifierSuccess),
+            Arc::new(BatchBuilderSuccess::new(sender)),
+            load_shedder(LoadSheddingConfig::default()),
+            Arc::new(LatencyTracker::new(10)),
+            TransactionQueueOptions {
+                build_batch_frequency: Duration::from_millis(5),
+                batch_size: 4,
+                max_batch_latency: Duration::from_secs(10),
+                reserved_consuming_percent: 0,
+                max_batch_attempts: 10,
+                dropped_history: 10,
+            },
+        )
+        .with_tx_ordering(tx_ordering);
+
+        let mut txs: Vec<_> = (0..4).map(|_| MockProvenTxBuilder::new().build()).collect();
+        // arrival in descending order of id, the reverse of the canonical order
+        txs.sort_by_key(|tx| std::cmp::Reverse(tx.id()));
+        for tx in &txs {
+            tx_queue
+                .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+                .await
+                .unwrap();
+        }
+
+        let batch = receiver.recv().await.expect("A batch should have been built");
+        let mut expected: Vec<_> = txs.iter().map(|tx| tx.id()).collect();
+        if tx_ordering == TxOrdering::Canonical {
+            expected.sort();
+        }
+        assert_eq!(batch.transaction_ids().collect::<Vec<_>>(), expected, "{tx_ordering}");
+    }
+}
+
+/// Tests that the queued transactions are reported as they were submitted, and that the removed
+/// ones are no longer queued
+#[tokio::test]
+async fn test_remove_queued_transactions() {
+    let (sender, _receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+    let tx_queue = TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 4,
+            max_batch_latency: Duration::from_secs(10),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    );
+
+    let txs: Vec<_> = (0..3).map(|_| MockProvenTxBuilder::new().build()).collect();
+    for tx in &txs {
+        tx_queue
+            .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+            .await
+            .unwrap();
+    }
+
+    let submissions = tx_queue.queued_submissions().await;
+    let queued: Vec<_> = submissions
+        .iter()
+        .map(|submission| (submission.tx.id(), submission.tx_kernel_version))
+        .collect();
+    let expected: Vec<_> = txs.iter().map(|tx| (tx.id(), TX_KERNEL_VERSION)).collect();
+    assert_eq!(queued, expected);
+
+    // the removal of a transaction which is no longer queued is ignored
+    let removed = BTreeSet::from([
+        txs[0].id(),
+        txs[2].id(),
+        DummyProvenTxGenerator::new().dummy_proven_tx().id(),
+    ]);
+    assert_eq!(tx_queue.remove_transactions(&removed).await, 2);
+
+    let pending = tx_queue.get_pending_transactions_by_account(txs[1].account_id()).await;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(tx_queue.num_queued_transactions().await, 1);
+}
+
+/// Tests that the transactions of the log which were not finished are read back in the order they
+/// were accepted, the log being compacted once opened
+#[test]
+fn test_transaction_log_is_replayed() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let path = Path::new("wal/mempool.wal");
+            let txs: Vec<_> = (0..3).map(|_| MockProvenTxBuilder::new().build()).collect();
+
+            let (tx_log, submissions) = TransactionLog::open(path).unwrap();
+            assert!(submissions.is_empty());
+            for (index, tx) in txs.iter().enumerate() {
+                tx_log.accepted(tx, index as u32 + 1, None, &[], &[], None).await.unwrap();
+            }
+            // a transaction admitted again on startup is only logged once
+            tx_log.accepted(&txs[2], 3, None, &[], &[], None).await.unwrap();
+            tx_log
+                .finished([txs[1].id(), DummyProvenTxGenerator::new().dummy_proven_tx().id()])
+                .await;
+            assert_eq!(tx_log.num_pending(), 2);
+            drop(tx_log);
+            let len = fs::metadata(path).unwrap().len();
+
+            let (tx_log, submissions) = TransactionLog::open(path).unwrap();
+            let replayed: Vec<_> = submissions
+                .iter()
+                .map(|submission| (submission.tx.id(), submission.tx_kernel_version))
+                .collect();
+            assert_eq!(replayed, vec![(txs[0].id(), 1), (txs[2].id(), 3)]);
+            assert_eq!(tx_log.num_pending(), 2);
+            assert!(fs::metadata(path).unwrap().len() < len);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that a log whose last record was partially written is read up to that record
+#[test]
+fn test_truncated_transaction_log() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let path = Path::new("mempool.wal");
+            let txs: Vec<_> = (0..2).map(|_| MockProvenTxBuilder::new().build()).collect();
+
+            let (tx_log, _) = TransactionLog::open(path).unwrap();
+            for tx in &txs {
+                tx_log.accepted(tx, TX_KERNEL_VERSION, None, &[], &[], None).await.unwrap();
+            }
+            drop(tx_log);
+
+            // e.g. a block producer killed while appending the transaction
+            let len = fs::metadata(path).unwrap().len();
+            OpenOptions::new().write(true).open(path).unwrap().set_len(len - 10).unwrap();
+
+            let (tx_log, submissions) = TransactionLog::open(path).unwrap();
+            let replayed: Vec<_> =
+                submissions.iter().map(|submission| submission.tx.id()).collect();
+            assert_eq!(replayed, vec![txs[0].id()]);
+            assert_eq!(tx_log.num_pending(), 1);
+
+            // the log is appended to after its last complete record
+            tx_log.accepted(&txs[1], TX_KERNEL_VERSION, None, &[], &[], None).await.unwrap();
+            drop(tx_log);
+            let (_, submissions) = TransactionLog::open(path).unwrap();
+            assert_eq!(submissions.len(), 2);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that the queue logs the accepted transactions only, and finishes the ones it removes
+#[test]
+fn test_queue_transaction_log() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (tx_log, _) = TransactionLog::open(Path::new("mempool.wal")).unwrap();
+            let tx_log = Arc::new(tx_log);
+            let (sender, _receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+            let options = TransactionQueueOptions {
+                build_batch_frequency: Duration::from_millis(5),
+                batch_size: 4,
+                max_batch_latency: Duration::from_secs(10),
+                reserved_consuming_percent: 0,
+                max_batch_attempts: 10,
+                dropped_history: 10,
+            };
+            let tx_queue = TransactionQueue::new(
+                Arc::new(TransactionVerifierSuccess),
+                Arc::new(BatchBuilderSuccess::new(sender)),
+                load_shedder(LoadSheddingConfig::default()),
+                Arc::new(LatencyTracker::new(10)),
+                options,
+            )
+            .with_transaction_log(tx_log.clone());
+
+            let txs: Vec<_> = (0..3).map(|_| MockProvenTxBuilder::new().build()).collect();
+            for tx in &txs {
+                tx_queue
+                    .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+                    .await
+                    .unwrap();
+            }
+            let rejected = MockProvenTxBuilder::new().build();
+            let result = tx_queue.add_transaction(rejected, 0, None, Vec::new(), Vec::new()).await;
+            assert!(matches!(result, Err(AddTransactionError::UnsupportedTxKernelVersion { .. })));
+            assert_eq!(tx_log.num_pending(), 3);
+
+            tx_queue.remove_transactions(&BTreeSet::from([txs[1].id()])).await;
+            assert_eq!(tx_log.num_pending(), 2);
+        });
+
+        Ok(())
+    });
+}
+
+/// Returns the submission of `tx`, proven with the current transaction kernel
+fn submission(tx: &ProvenTransaction) -> QueuedSubmission {
+    QueuedSubmission {
+        tx: tx.clone(),
+        tx_kernel_version: TX_KERNEL_VERSION,
+        account_delta: None,
+        consumed_notes: Vec::new(),
+        private_notes: Vec::new(),
+        partner_signature: None,
+    }
+}
+
+/// Returns the proof of the batch of `txs`, as proven by an external batch producer
+async fn batch_proof(txs: &[ProvenTransaction]) -> BatchProof {
+    let batch = TransactionBatch::new(txs.to_vec()).unwrap();
+    LocalBatchProver::new(1, ProverPriority::Normal)
+        .prove_batch(&batch)
+        .await
+        .unwrap()
+}
+
+/// Waits until the batches sent to the batch builder were added, or sent back to the queue
+async fn wait_for_batches_in_flight<BB: BatchBuilder, TV: TransactionVerifier>(
+    tx_queue: &TransactionQueue<BB, TV>
+) {
+    time::timeout(Duration::from_secs(5), async {
+        while tx_queue.num_batches_in_flight() > 0 {
+            time::sleep(Duration::from_millis(1)).await;
+        }
+    })
+    .await
+    .expect("the batches in flight must be handled");
+}
+
+/// Tests that a submitted batch is verified along with its proof, then sent to the batch builder
+/// as is
+#[tokio::test]
+async fn test_add_batch() {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+    let tx_queue = TransactionQueue::new(
+        Arc::new(TransactionVerifierSuccess),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 3,
+            max_batch_latency: Duration::from_secs(10),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    );
+    let txs: Vec<_> = (0..3)
+        .map(|_| MockProvenTxBuilder::new().num_notes_created(2).build())
+        .collect();
+    let proof = batch_proof(&txs).await;
+
+    let batch = tx_queue
+        .add_batch(txs.iter().map(submission).collect(), proof.clone())
+        .await
+        .unwrap();
+    let expected = TransactionBatch::new(txs.clone())
+        .unwrap()
+        .with_tx_kernel_versions(BTreeSet::from([TX_KERNEL_VERSION]))
+        .with_proof(proof.clone());
+    assert_eq!(batch, expected);
+    assert_eq!(receiver.recv().await, Some(expected));
+    assert_eq!(tx_queue.num_queued_transactions().await, 0);
+
+    let result = tx_queue.add_batch(Vec::new(), proof.clone()).await;
+    assert!(matches!(result, Err(AddBatchError::EmptyBatch)));
+
+    let oversized: Vec<_> =
+        (0..4).map(|_| submission(&MockProvenTxBuilder::new().build())).collect();
+    let result = tx_queue.add_batch(oversized, proof.clone()).await;
+    assert!(matches!(result, Err(AddBatchError::TooManyTransactions(4, 3))));
+
+    // both transactions create the same notes
+    let tx = MockProvenTxBuilder::new().num_notes_created(1).build();
+    let result = tx_queue.add_batch(vec![submission(&tx), submission(&tx)], proof.clone()).await;
+    assert!(matches!(
+        result,
+        Err(AddBatchError::InvalidBatch(BuildBatchError::ConflictingTransactions { .. }))
+    ));
+
+    // the proof of another batch, or a malformed proof, are rejected
+    let other_txs: Vec<_> = (0..2).map(|_| MockProvenTxBuilder::new().build()).collect();
+    let result = tx_queue.add_batch(other_txs.iter().map(submission).collect(), proof).await;
+    assert!(matches!(result, Err(AddBatchError::InvalidProof(_))));
+
+    let other_proof = batch_proof(&other_txs).await;
+    let malformed = BatchProof::new(other_proof.commitment(), vec![0; 32]);
+    let result = tx_queue.add_batch(other_txs.iter().map(submission).collect(), malformed).await;
+    assert!(matches!(result, Err(AddBatchError::InvalidProof(_))));
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+}
+
+/// Tests that a batch is rejected as a whole when one of its transactions fails to verify, the
+/// transactions verified before it being released
+#[tokio::test]
+async fn test_add_batch_rejected() {
+    let txs: Vec<_> = (0..3).map(|_| MockProvenTxBuilder::new().build()).collect();
+    let tx_verifier = Arc::new(TransactionVerifierRejecting {
+        account_id: txs[2].account_id(),
+        num_released: AtomicUsize::new(0),
+    });
+    let (sender, mut receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+    let tx_queue = TransactionQueue::new(
+        tx_verifier.clone(),
+        Arc::new(BatchBuilderSuccess::new(sender)),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 3,
+            max_batch_latency: Duration::from_secs(10),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    );
+    let proof = batch_proof(&txs).await;
+
+    let result = tx_queue.add_batch(txs.iter().map(submission).collect(), proof.clone()).await;
+    let Err(AddBatchError::TransactionRejected { tx_id, error }) = result else {
+        panic!("Batch should have been rejected, got {result:?}");
+    };
+    assert_eq!(tx_id, txs[2].id());
+    assert!(matches!(error, AddTransactionError::VerificationFailed(_)));
+    assert_eq!(tx_verifier.num_released.load(Ordering::Relaxed), 2);
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+
+    // transactions proven with an unsupported kernel are rejected before being verified
+    let mut unsupported = submission(&txs[0]);
+    unsupported.tx_kernel_version = TX_KERNEL_VERSION + 1;
+    let result = tx_queue.add_batch(vec![unsupported], proof).await;
+    assert!(matches!(
+        result,
+        Err(AddBatchError::TransactionRejected {
+            error: AddTransactionError::UnsupportedTxKernelVersion { .. },
+            ..
+        })
+    ));
+    assert_eq!(tx_verifier.num_released.load(Ordering::Relaxed), 2);
+}
+
+/// Tests that a queued batch the batch builder rejects, e.g. for an invalid transaction proof, is
+/// dropped as a whole, its transactions being released
+#[tokio::test]
+async fn test_add_batch_rejected_by_batch_builder() {
+    let txs: Vec<_> = (0..3).map(|_| MockProvenTxBuilder::new().build()).collect();
+    let tx_verifier = Arc::new(TransactionVerifierRejecting {
+        account_id: MockProvenTxBuilder::new().build().account_id(),
+        num_released: AtomicUsize::new(0),
+    });
+    let tx_queue = TransactionQueue::new(
+        tx_verifier.clone(),
+        Arc::new(BatchBuilderFailure),
+        load_shedder(LoadSheddingConfig::default()),
+        Arc::new(LatencyTracker::new(10)),
+        TransactionQueueOptions {
+            build_batch_frequency: Duration::from_millis(5),
+            batch_size: 3,
+            max_batch_latency: Duration::from_secs(10),
+            reserved_consuming_percent: 0,
+            max_batch_attempts: 10,
+            dropped_history: 10,
+        },
+    );
+
+    let proof = batch_proof(&txs).await;
+    tx_queue.add_batch(txs.iter().map(submission).collect(), proof).await.unwrap();
+    wait_for_batches_in_flight(&tx_queue).await;
+
+    assert_eq!(tx_verifier.num_released.load(Ordering::Relaxed), 3);
+    assert_eq!(tx_queue.num_queued_transactions().await, 0);
+    let dropped = tx_queue.get_dropped_transactions_by_account(txs[0].account_id());
+    assert_eq!((dropped[0].tx_id, dropped[0].reason), (txs[0].id(), DropReason::BatchFailures));
+}
+
+/// Tests that the transactions of the queued batches count against the capacity of the queue, and
+/// that the queued batches expire as a whole
+#[tokio::test]
+async fn test_add_batch_queue_limits() {
+    let new_queue = |tx_verifier: Arc<TransactionVerifierBlocks>,
+                     eviction_policy: Box<dyn EvictionPolicy>| {
+        TransactionQueue::new(
+            tx_verifier,
+            Arc::new(BatchBuilderReadyBatchesFull),
+            load_shedder(LoadSheddingConfig::default()),
+            Arc::new(LatencyTracker::new(10)),
+            TransactionQueueOptions {
+                build_batch_frequency: Duration::from_millis(5),
+                batch_size: 10,
+                max_batch_latency: Duration::from_secs(10),
+                reserved_consuming_percent: 0,
+                max_batch_attempts: 10,
+                dropped_history: 10,
+            },
+        )
+        .with_eviction(3, eviction_policy)
+        .with_ttl(2)
+    };
+    let txs: Vec<_> = (0..6).map(|_| MockProvenTxBuilder::new().build()).collect();
+    let batch = [txs[0].clone(), txs[1].clone()];
+    let proof = batch_proof(&batch).await;
+
+    // the batch waits in the queue while the ready batches fill a block
+    let tx_verifier = Arc::new(TransactionVerifierBlocks::default());
+    let tx_queue = new_queue(tx_verifier.clone(), Box::new(RejectNewest));
+    tx_queue
+        .add_batch(batch.iter().map(submission).collect(), proof.clone())
+        .await
+        .unwrap();
+    wait_for_batches_in_flight(&tx_queue).await;
+    tx_verifier.num_applied_blocks.store(1, Ordering::Relaxed);
+    tx_queue
+        .add_transaction(txs[2].clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await
+        .unwrap();
+    wait_for_batches_in_flight(&tx_queue).await;
+    assert_eq!(tx_queue.num_queued_transactions().await, 3);
+    assert_eq!(tx_queue.lane_stats(Lane::Creating).queued, 3);
+
+    let result = tx_queue
+        .add_transaction(txs[3].clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+        .await;
+    assert!(matches!(result, Err(AddTransactionError::QueueFull)));
+    let other_batch = [txs[4].clone()];
+    let result = tx_queue
+        .add_batch(other_batch.iter().map(submission).collect(), batch_proof(&other_batch).await)
+        .await;
+    assert!(matches!(result, Err(AddBatchError::QueueFull)));
+
+    // the batch waited two blocks, the transaction a single block
+    tx_verifier.num_applied_blocks.store(2, Ordering::Relaxed);
+    tx_queue.expire_transactions().await;
+    assert_eq!(tx_queue.num_queued_transactions().await, 1);
+    assert_eq!(tx_verifier.num_released.load(Ordering::Relaxed), 2);
+    let dropped = tx_queue.get_dropped_transactions_by_account(txs[1].account_id());
+    assert_eq!((dropped[0].tx_id, dropped[0].reason), (txs[1].id(), DropReason::Expired));
+
+    // the queued transactions are evicted to make room for the batch, the transactions of the
+    // queued batches can't be
+    let tx_verifier = Arc::new(TransactionVerifierBlocks::default());
+    let tx_queue = new_queue(tx_verifier.clone(), Box::new(OldestFirst));
+    for tx in &txs[2..4] {
+        tx_queue
+            .add_transaction(tx.clone(), TX_KERNEL_VERSION, None, Vec::new(), Vec::new())
+            .await
+            .unwrap();
+    }
+    tx_queue.add_batch(batch.iter().map(submission).collect(), proof).await.unwrap();
+    wait_for_batches_in_flight(&tx_queue).await;
+    let queued: Vec<_> =
+        tx_queue.ready_queue.read().await.iter().map(|queued| queued.tx.id()).collect();
+    assert_eq!(queued, vec![txs[3].id()]);
+    let dropped = tx_queue.get_dropped_transactions_by_account(txs[2].account_id());
+    assert_eq!((dropped[0].tx_id, dropped[0].reason), (txs[2].id(), DropReason::Evicted));
+
+    let other_batch = [txs[4].clone(), txs[5].clone()];
+    let result = tx_queue
+        .add_batch(other_batch.iter().map(submission).collect(), batch_proof(&other_batch).await)
+        .await;
+    assert!(matches!(result, Err(AddBatchError::QueueFull)));
+    assert_eq!(tx_queue.num_queued_transactions().await, 3);
+}
+
+/// Tests that the queued batches take at most half of the batches of a round while transactions
+/// wait in the queue, and all of them otherwise
+#[tokio::test]
+async fn test_add_batch_fairness() {
+    let new_queue = || {
+        let (sender, receiver) = mpsc::unbounded_channel::<TransactionBatch>();
+        let tx_queue = TransactionQueue::new(
+            Arc::new(TransactionVerifierSuccess),
+            Arc::new(BatchBuilderSuccess::new(sender)),
+            load_shedder(LoadSheddingConfig::default()),
+            Arc::new(LatencyTracker::new(10)),
+            TransactionQueueOptions {
+                build_batch_frequency: Duration::from_millis(5),
+                batch_size: 1,
+                max_batch_latency: Duration::ZERO,
+                reserved_consuming_percent: 0,
+                max_batch_attempts: 10,
+                dropped_history: 10,
+            },
+        );
+        (tx_queue, receiver)
+    };
+    let queue_batches = |tx_queue: &TransactionQueue<_, _>, num_batches: usize| {
+        let batches = (0..num_batches).map(|_| {
+            let tx = MockProvenTxBuilder::new().build();
+            QueuedBatch {
+                batch: TransactionBatch::new(vec![tx.clone()])
+                    .unwrap()
+                    .with_proof(BatchProof::new(Digest::default(), vec![1])),
+                txs: vec![QueuedTransaction::new(tx, TX_KERNEL_VERSION, None)],
+                queued_at_block: 0,
+            }
+        });
+        tx_queue.proven_batches.try_write().unwrap().extend(batches);
+    };
+
+    // 8 batches are built concurrently, see `load_shedder()`
+    let (tx_queue, mut receiver) = new_queue();
+    queue_batches(&tx_queue, 6);
+    tx_queue.ready_queue.write().await.extend((0..10).map(|_| {
+        QueuedTransaction::new(MockProvenTxBuilder::new().build(), TX_KERNEL_VERSION, None)
+    }));
+    tx_queue.try_build_batches().await;
+
+    let mut num_proven = 0;
+    for _ in 0..8 {
+        let batch = receiver.recv().await.unwrap();
+        num_proven += batch.proof().is_some() as usize;
+    }
+    assert_eq!(num_proven, 4);
+    assert_eq!(tx_queue.num_queued_transactions().await, 8);
+
+    let (tx_queue, mut receiver) = new_queue();
+    queue_batches(&tx_queue, 10);
+    tx_queue.try_build_batches().await;
+    for _ in 0..8 {
+        assert!(receiver.recv().await.unwrap().proof().is_some());
+    }
+    assert_eq!(tx_queue.num_queued_transactions().await, 2);
+}
@@ -0,0 +1,419 @@
+//! Write-ahead log of the transactions accepted by the queue.
+//!
+//! The queue lives in memory, a block producer which crashed or was killed loses the transactions
+//! it accepted but didn't include in a block yet. Every accepted transaction is appended to the
+//! log, and synced to disk, before the queue admits it. Once the transaction is included in an
+//! applied block, or dropped, a record marking it finished is appended. On startup the transactions
+//! of the log which were not finished are admitted again, in the order they were accepted.
+//!
+//! The log is compacted once it holds as many finished transactions as pending ones, by rewriting
+//! the pending transactions to a new file which replaces it.
+//!
+//! The log is written, synced and compacted on the blocking threads of the runtime, its lock is
+//! never taken by an async task.
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use miden_crypto::utils::{Deserializable, Serializable};
+use miden_node_proto::{domain::PartnerSignature, requests::SubmitProvenTransactionRequest};
+use miden_objects::{accounts::AccountDelta, notes::Note, transaction::TransactionId, Digest};
+use prost::Message;
+use tracing::{info, warn};
+
+use super::{submission_request, QueuedSubmission};
+use crate::{ProvenTransaction, COMPONENT};
+
+// CONSTANTS
+// ================================================================================================
+
+/// Number of finished transactions below which the log is never compacted, so a short queue doesn't
+/// rewrite the log after every block.
+const MIN_COMPACTION_RECORDS: usize = 256;
+
+/// Tag of the record of an accepted transaction.
+const ACCEPTED_TAG: u8 = 1;
+
+/// Tag of the record of a transaction which was included in a block or dropped.
+const FINISHED_TAG: u8 = 2;
+
+/// Size of the serialized id of a transaction.
+const TX_ID_SIZE: usize = 32;
+
+// LOG RECORD
+// ================================================================================================
+
+/// A record of the log, a tag followed by the id of the transaction, and for an accepted
+/// transaction its length-delimited [SubmitProvenTransactionRequest].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Record {
+    Accepted {
+        tx_id: TransactionId,
+        submission: Vec<u8>,
+    },
+    Finished(TransactionId),
+}
+
+impl Record {
+    fn tx_id(&self) -> TransactionId {
+        match self {
+            Record::Accepted { tx_id, .. } | Record::Finished(tx_id) => *tx_id,
+        }
+    }
+
+    fn write_into(
+        &self,
+        target: &mut Vec<u8>,
+    ) {
+        match self {
+            Record::Accepted { tx_id, submission } => {
+                target.push(ACCEPTED_TAG);
+                target.extend_from_slice(&tx_id.inner().to_bytes());
+                prost::encoding::encode_varint(submission.len() as u64, target);
+                target.extend_from_slice(submission);
+            },
+            Record::Finished(tx_id) => {
+                target.push(FINISHED_TAG);
+                target.extend_from_slice(&tx_id.inner().to_bytes());
+            },
+        }
+    }
+
+    /// Reads the record at the start of `source`, advanced past it, or `None` if it is not a
+    /// complete record.
+    fn read_from(source: &mut &[u8]) -> Option<Self> {
+        let (&tag, rest) = source.split_first()?;
+        if rest.len() < TX_ID_SIZE {
+            return None;
+        }
+        let (tx_id, mut rest) = rest.split_at(TX_ID_SIZE);
+        let tx_id = TransactionId::from(Digest::read_from_bytes(tx_id).ok()?);
+
+        let record = match tag {
+            ACCEPTED_TAG => {
+                let len = prost::encoding::decode_varint(&mut rest).ok()? as usize;
+                if rest.len() < len {
+                    return None;
+                }
+                let (submission, remaining) = rest.split_at(len);
+                rest = remaining;
+                Record::Accepted {
+                    tx_id,
+                    submission: submission.to_vec(),
+                }
+            },
+            FINISHED_TAG => Record::Finished(tx_id),
+            _ => return None,
+        };
+        *source = rest;
+
+        Some(record)
+    }
+}
+
+// TRANSACTION LOG
+// ================================================================================================
+
+/// Append-only log of the accepted transactions, see the [module](self) documentation.
+pub struct TransactionLog {
+    path: PathBuf,
+    state: Arc<Mutex<LogState>>,
+}
+
+struct LogState {
+    file: File,
+
+    /// Transactions accepted which were neither included in a block nor dropped
+    pending: BTreeSet<TransactionId>,
+
+    /// Number of finished records appended since the log was last compacted
+    num_finished: usize,
+
+    /// Whether a failed append couldn't be truncated away, every later append failing until the
+    /// log is opened again
+    failed: bool,
+}
+
+impl LogState {
+    /// Appends `bytes` to the log, synced to disk if `sync` is set.
+    ///
+    /// The log is truncated back to its length before the append if the append fails, so a
+    /// partially written record doesn't hide the records appended after it. The log fails
+    /// permanently if it can't be truncated.
+    fn append(
+        &mut self,
+        bytes: &[u8],
+        sync: bool,
+    ) -> io::Result<()> {
+        if self.failed {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "transaction log failed, a partially written record couldn't be truncated",
+            ));
+        }
+
+        let len = self.file.metadata()?.len();
+        let mut appended = self.file.write_all(bytes);
+        if appended.is_ok() && sync {
+            appended = self.file.sync_data();
+        }
+        if appended.is_err() {
+            if let Err(err) = self.file.set_len(len) {
+                warn!(target: COMPONENT, %err, "Failed to truncate the transaction log");
+                self.failed = true;
+            }
+        }
+
+        appended
+    }
+}
+
+impl TransactionLog {
+    /// Opens the log at `path`, created along with its directory if missing, and returns it along
+    /// with the transactions it holds which were not finished, in the order they were accepted.
+    ///
+    /// The log ends at its first record which can't be read, e.g. the one written by a block
+    /// producer killed while appending it. The log is compacted once read, which discards that
+    /// record along with the finished transactions.
+    pub fn open(path: &Path) -> io::Result<(Self, Vec<QueuedSubmission>)> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let records = read_records(path)?;
+        let mut pending = BTreeSet::new();
+        for record in &records {
+            match record {
+                Record::Accepted { tx_id, .. } => pending.insert(*tx_id),
+                Record::Finished(tx_id) => pending.remove(tx_id),
+            };
+        }
+
+        let mut records = pending_records(records, &pending);
+        let mut submissions = Vec::with_capacity(records.len());
+        records.retain(|record| {
+            let Record::Accepted { tx_id, submission } = record else {
+                return false;
+            };
+            let submission = SubmitProvenTransactionRequest::decode(submission.as_slice())
+                .map_err(|err| err.to_string())
+                .and_then(QueuedSubmission::from_proto);
+            match submission {
+                Ok(submission) => submissions.push(submission),
+                Err(err) => {
+                    warn!(target: COMPONENT, ?path, tx_id = %tx_id.to_hex(), %err, "Invalid transaction in the log skipped");
+                    pending.remove(tx_id);
+                },
+            }
+            pending.contains(tx_id)
+        });
+        let file = compact(path, &records)?;
+
+        info!(target: COMPONENT, ?path, num_txs = pending.len(), "Transaction log opened");
+
+        let log = Self {
+            path: path.to_path_buf(),
+            state: Arc::new(Mutex::new(LogState {
+                file,
+                pending,
+                num_finished: 0,
+                failed: false,
+            })),
+        };
+
+        Ok((log, submissions))
+    }
+
+    /// Appends the accepted `tx`, as it was submitted, to the log, which is synced to disk once it
+    /// is written.
+    ///
+    /// A transaction already pending in the log, e.g. admitted again on startup, is not appended.
+    /// The record is truncated away if it can't be fully written and synced, see [LogState::append].
+    pub async fn accepted(
+        &self,
+        tx: &ProvenTransaction,
+        tx_kernel_version: u32,
+        account_delta: Option<&AccountDelta>,
+        consumed_notes: &[Note],
+        private_notes: &[Digest],
+        partner_signature: Option<&PartnerSignature>,
+    ) -> io::Result<()> {
+        let tx_id = tx.id();
+        let mut bytes = Vec::new();
+        Record::Accepted {
+            tx_id,
+            submission: submission_request(
+                tx,
+                tx_kernel_version,
+                account_delta,
+                consumed_notes,
+                private_notes,
+                partner_signature,
+            )
+            .encode_to_vec(),
+        }
+        .write_into(&mut bytes);
+
+        let state = self.state.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut state = state.lock().expect("transaction log lock poisoned");
+            if state.pending.contains(&tx_id) {
+                return Ok(());
+            }
+
+            state.append(&bytes, true)?;
+            state.pending.insert(tx_id);
+
+            Ok(())
+        })
+        .await
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
+    }
+
+    /// Marks the transactions `tx_ids` as finished, they are no longer admitted again on startup,
+    /// then compacts the log if it holds as many finished transactions as pending ones.
+    ///
+    /// The records are not synced to disk, a finished transaction which is admitted again after a
+    /// crash is rejected by the verification. Failing to write them is logged.
+    pub async fn finished(
+        &self,
+        tx_ids: impl IntoIterator<Item = TransactionId>,
+    ) {
+        let tx_ids: Vec<_> = tx_ids.into_iter().collect();
+        let path = self.path.clone();
+        let state = self.state.clone();
+        let finished = tokio::task::spawn_blocking(move || finish(&path, &state, tx_ids)).await;
+        if let Err(err) = finished {
+            warn!(target: COMPONENT, path = ?self.path, %err, "Transaction log task failed");
+        }
+    }
+
+    /// Returns the number of transactions accepted which were neither included in a block nor
+    /// dropped.
+    pub fn num_pending(&self) -> usize {
+        self.state.lock().expect("transaction log lock poisoned").pending.len()
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Appends the finished records of `tx_ids` to the log at `path`, then compacts it if it holds as
+/// many finished transactions as pending ones, see [TransactionLog::finished].
+fn finish(
+    path: &Path,
+    state: &Mutex<LogState>,
+    tx_ids: Vec<TransactionId>,
+) {
+    let mut state = state.lock().expect("transaction log lock poisoned");
+
+    let mut bytes = Vec::new();
+    let mut num_finished = 0;
+    for tx_id in tx_ids {
+        if state.pending.remove(&tx_id) {
+            Record::Finished(tx_id).write_into(&mut bytes);
+            num_finished += 1;
+        }
+    }
+    if num_finished == 0 {
+        return;
+    }
+    if let Err(err) = state.append(&bytes, false) {
+        warn!(target: COMPONENT, ?path, %err, "Failed to write the transaction log");
+        return;
+    }
+    state.num_finished += num_finished;
+
+    if state.num_finished >= MIN_COMPACTION_RECORDS.max(state.pending.len()) {
+        let compacted = read_records(path)
+            .and_then(|records| compact(path, &pending_records(records, &state.pending)));
+        match compacted {
+            Ok(file) => {
+                info!(target: COMPONENT, ?path, num_txs = state.pending.len(), "Transaction log compacted");
+                state.file = file;
+                state.num_finished = 0;
+            },
+            Err(err) => {
+                warn!(target: COMPONENT, ?path, %err, "Failed to compact the transaction log")
+            },
+        }
+    }
+}
+
+/// Reads the records of the log at `path`, up to the first record which can't be read.
+fn read_records(path: &Path) -> io::Result<Vec<Record>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut records = Vec::new();
+    let mut remaining = bytes.as_slice();
+    while !remaining.is_empty() {
+        match Record::read_from(&mut remaining) {
+            Some(record) => records.push(record),
+            None => {
+                warn!(target: COMPONENT, ?path, num_bytes = remaining.len(), "Truncated transaction log, remaining records skipped");
+                break;
+            },
+        }
+    }
+
+    Ok(records)
+}
+
+/// Returns the first record accepting each of the `pending` transactions, in order.
+fn pending_records(
+    records: Vec<Record>,
+    pending: &BTreeSet<TransactionId>,
+) -> Vec<Record> {
+    let mut kept = BTreeSet::new();
+    records
+        .into_iter()
+        .filter(|record| {
+            matches!(record, Record::Accepted { .. })
+                && pending.contains(&record.tx_id())
+                && kept.insert(record.tx_id())
+        })
+        .collect()
+}
+
+/// Replaces the log at `path` with `records`, and returns the new log opened for appending.
+///
+/// The records are written to `<path>.tmp`, synced to disk, then renamed over the log, so a crash
+/// leaves either log in place. The directory of the log is synced once the log is renamed, so the
+/// new log survives a crash once this returns.
+fn compact(
+    path: &Path,
+    records: &[Record],
+) -> io::Result<File> {
+    let mut bytes = Vec::new();
+    for record in records {
+        record.write_into(&mut bytes);
+    }
+
+    let mut tmp_path = OsString::from(path);
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(&bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        File::open(parent)?.sync_all()?;
+    }
+
+    OpenOptions::new().append(true).open(path)
+}
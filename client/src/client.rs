@@ -0,0 +1,709 @@
+use std::{future::Future, time::Duration};
+
+use miden_crypto::{
+    dsa::rpo_falcon512::KeyPair,
+    merkle::{MerklePath, TieredSmtProof},
+    utils::Serializable,
+};
+use miden_node_proto::{
+    account,
+    chain_id::ChainIdInterceptor,
+    digest,
+    domain::{PartnerSignature, SubmissionReceipt},
+    errors::ParseError,
+    requests::{
+        CheckNullifiersRequest, GetBlockHeaderByNumberRequest, GetMempoolStatusRequest,
+        GetNoteAuthenticationPathRequest, GetNoteTagStatsRequest,
+        GetPendingTransactionsByAccountRequest, GetProposedNotesRequest, GetUnconsumedNotesRequest,
+        SubmitProvenBatchRequest, SubmitProvenTransactionRequest, SubscribeNullifiersRequest,
+        SyncAccountsRequest, SyncStateRequest,
+    },
+    responses::SubscribeNullifiersResponse,
+    rpc::api_client,
+    SUBMIT_PROVEN_BATCH_OPERATION, TX_KERNEL_VERSION,
+};
+use miden_node_utils::operator_auth::sign_request;
+use miden_objects::{
+    accounts::{AccountDelta, AccountId},
+    notes::Note,
+    transaction::ProvenTransaction,
+    BlockHeader, Digest,
+};
+use tonic::{
+    codec::Streaming,
+    service::interceptor::InterceptedService,
+    transport::{Channel, ClientTlsConfig, Endpoint},
+    Code, Status,
+};
+use tracing::warn;
+
+use crate::{
+    errors::ClientError, AccountTransactions, AccountsSync, AuthenticatedBlockHeader, ChainTip,
+    ConsumedNullifiers, MempoolStatus, NoteActivity, ProposedNotes, StateSyncInfo, UnconsumedNotes,
+    COMPONENT,
+};
+
+// RPC CLIENT OPTIONS
+// ================================================================================================
+
+#[derive(Clone, Debug)]
+pub struct RpcClientOptions {
+    /// Number of times a request is retried while the node is unavailable
+    pub max_retries: u32,
+
+    /// Time to wait between two attempts of the same request
+    pub retry_interval: Duration,
+
+    /// Deadline of every request, `None` waits indefinitely
+    pub timeout: Option<Duration>,
+
+    /// TLS configuration, `None` connects over plaintext
+    pub tls: Option<ClientTlsConfig>,
+}
+
+impl Default for RpcClientOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            retry_interval: Duration::from_secs(1),
+            timeout: Some(Duration::from_secs(10)),
+            tls: None,
+        }
+    }
+}
+
+// RPC CLIENT
+// ================================================================================================
+
+type ApiClient = api_client::ApiClient<InterceptedService<Channel, ChainIdInterceptor>>;
+
+/// Client to the RPC component of a Miden node.
+///
+/// All requests carry the chain id given on connection, so a client configured for one network is
+/// rejected by the nodes of another.
+#[derive(Clone, Debug)]
+pub struct RpcClient {
+    client: ApiClient,
+    chain_id: u32,
+    options: RpcClientOptions,
+}
+
+impl RpcClient {
+    /// Connects to the RPC listening at `url`, serving the chain `chain_id`.
+    pub async fn connect(
+        url: impl Into<String>,
+        chain_id: u32,
+        options: RpcClientOptions,
+    ) -> Result<Self, ClientError> {
+        let mut endpoint = Endpoint::new(url.into())?;
+        if let Some(timeout) = options.timeout {
+            endpoint = endpoint.timeout(timeout);
+        }
+        if let Some(tls) = options.tls.clone() {
+            endpoint = endpoint.tls_config(tls)?;
+        }
+
+        let channel = endpoint.connect().await?;
+        let client =
+            api_client::ApiClient::with_interceptor(channel, ChainIdInterceptor::new(chain_id));
+
+        Ok(Self {
+            client,
+            chain_id,
+            options,
+        })
+    }
+
+    /// Returns the proofs of the given nullifiers in the nullifier tree, in the same order.
+    pub async fn check_nullifiers(
+        &self,
+        nullifiers: &[Digest],
+    ) -> Result<Vec<TieredSmtProof>, ClientError> {
+        let request = CheckNullifiersRequest {
+            nullifiers: nullifiers.iter().map(digest::Digest::from).collect(),
+            nullifiers_without_proofs: Vec::new(),
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.check_nullifiers(request).await }
+            })
+            .await?;
+
+        check_entries(nullifiers.len(), response.proofs.len())?;
+        Ok(response
+            .proofs
+            .into_iter()
+            .map(TieredSmtProof::try_from)
+            .collect::<Result<_, ParseError>>()?)
+    }
+
+    /// Returns the number of the block in which each of the given nullifiers was consumed, or `0`
+    /// if it wasn't consumed yet, in the same order.
+    pub async fn check_nullifiers_without_proofs(
+        &self,
+        nullifiers: &[Digest],
+    ) -> Result<Vec<u32>, ClientError> {
+        let request = CheckNullifiersRequest {
+            nullifiers: Vec::new(),
+            nullifiers_without_proofs: nullifiers.iter().map(digest::Digest::from).collect(),
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.check_nullifiers(request).await }
+            })
+            .await?;
+
+        check_entries(nullifiers.len(), response.block_nums.len())?;
+        Ok(response.block_nums)
+    }
+
+    /// Returns the header of the block `block_num`, or of the chain tip if `None`.
+    ///
+    /// Returns `None` if the block doesn't exist yet.
+    pub async fn get_block_header_by_number(
+        &self,
+        block_num: Option<u32>,
+    ) -> Result<Option<BlockHeader>, ClientError> {
+        let request = GetBlockHeaderByNumberRequest {
+            block_num,
+            include_mmr_proof: None,
+            if_none_match: None,
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get_block_header_by_number(request).await }
+            })
+            .await?;
+
+        Ok(response.block_header.map(BlockHeader::try_from).transpose()?)
+    }
+
+    /// Returns the number of the latest block of the chain, and of the latest block the node
+    /// considers final.
+    pub async fn get_chain_tip(&self) -> Result<ChainTip, ClientError> {
+        let request = GetBlockHeaderByNumberRequest {
+            block_num: None,
+            include_mmr_proof: None,
+            if_none_match: None,
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get_block_header_by_number(request).await }
+            })
+            .await?;
+
+        Ok(ChainTip::from(&response))
+    }
+
+    /// Returns the header of the chain tip, if its hash is no longer `known_tip`.
+    ///
+    /// Returns `None` if the chain tip didn't change, which is cheaper to serve than the header.
+    pub async fn get_chain_tip_if_changed(
+        &self,
+        known_tip: Digest,
+    ) -> Result<Option<BlockHeader>, ClientError> {
+        let request = GetBlockHeaderByNumberRequest {
+            block_num: None,
+            include_mmr_proof: None,
+            if_none_match: Some(known_tip.into()),
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get_block_header_by_number(request).await }
+            })
+            .await?;
+
+        if response.not_modified {
+            return Ok(None);
+        }
+        Ok(response.block_header.map(BlockHeader::try_from).transpose()?)
+    }
+
+    /// Returns the header of the block `block_num`, or of the chain tip if `None`, together with
+    /// its authentication path in the chain MMR of the chain tip.
+    ///
+    /// Returns `None` if the block doesn't exist yet.
+    pub async fn get_block_header_with_mmr_proof(
+        &self,
+        block_num: Option<u32>,
+    ) -> Result<Option<AuthenticatedBlockHeader>, ClientError> {
+        let request = GetBlockHeaderByNumberRequest {
+            block_num,
+            include_mmr_proof: Some(true),
+            if_none_match: None,
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get_block_header_by_number(request).await }
+            })
+            .await?;
+
+        if response.block_header.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(response.try_into()?))
+    }
+
+    /// Returns the inclusion path of the note `note_index` in the note tree of block `block_num`.
+    pub async fn get_note_authentication_path(
+        &self,
+        block_num: u32,
+        note_index: u32,
+    ) -> Result<MerklePath, ClientError> {
+        let request = GetNoteAuthenticationPathRequest {
+            block_num,
+            note_index,
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get_note_authentication_path(request).await }
+            })
+            .await?;
+
+        Ok(response.merkle_path.ok_or(ParseError::ProtobufMissingData)?.try_into()?)
+    }
+
+    /// Returns the number of notes created over the latest `num_blocks` blocks, with any tag and
+    /// with each of `note_tags`, given as the 16 high bits of the tags, and the number of
+    /// transactions waiting to be batched.
+    ///
+    /// The node only keeps the activity of a bounded number of blocks, which covers every block if
+    /// `num_blocks` is zero. Intended to suggest fees according to the congestion of the network.
+    pub async fn get_note_tag_stats(
+        &self,
+        note_tags: &[u32],
+        num_blocks: u32,
+    ) -> Result<NoteActivity, ClientError> {
+        let request = GetNoteTagStatsRequest {
+            note_tags: note_tags.to_vec(),
+            num_blocks,
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get_note_tag_stats(request).await }
+            })
+            .await?;
+
+        Ok(response.try_into()?)
+    }
+
+    /// Returns the notes created from block `from_block` with one of `note_tags`, given as the 16
+    /// high bits of the tags, which weren't consumed.
+    ///
+    /// The notes of many blocks are returned over several calls, each one from the
+    /// `next_block_num` of the previous one, until it is past the chain tip. Only the consumption
+    /// of public notes is known to the node, the private notes are always returned.
+    pub async fn get_unconsumed_notes(
+        &self,
+        note_tags: &[u32],
+        from_block: u32,
+    ) -> Result<UnconsumedNotes, ClientError> {
+        let request = GetUnconsumedNotesRequest {
+            note_tags: note_tags.to_vec(),
+            from_block,
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get_unconsumed_notes(request).await }
+            })
+            .await?;
+
+        Ok(response.try_into()?)
+    }
+
+    /// Returns the notes of `note_hashes` created by the block waiting to be committed by the node,
+    /// all of its notes if `note_hashes` is empty, along with their inclusion paths in the block.
+    ///
+    /// The paths are provisional, they only hold once the block is committed, a block aborted or
+    /// replaced by another one invalidates them. Returns `None` if no block is waiting to be
+    /// committed.
+    pub async fn get_proposed_notes(
+        &self,
+        note_hashes: &[Digest],
+    ) -> Result<Option<ProposedNotes>, ClientError> {
+        let request = GetProposedNotesRequest {
+            note_hashes: note_hashes.iter().map(digest::Digest::from).collect(),
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get_proposed_notes(request).await }
+            })
+            .await?;
+
+        if response.block_header.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(response.try_into()?))
+    }
+
+    /// Returns the changes to the chain after block `block_num` which are relevant to the given
+    /// accounts, note tags and nullifier prefixes, and which of the notes of `note_hashes` were
+    /// consumed.
+    ///
+    /// See the `SyncState` endpoint of the RPC for the exact semantics of the filters.
+    pub async fn sync_state(
+        &self,
+        block_num: u32,
+        account_ids: &[AccountId],
+        note_tags: &[u32],
+        nullifier_prefixes: &[u32],
+        note_hashes: &[Digest],
+    ) -> Result<StateSyncInfo, ClientError> {
+        let request = SyncStateRequest {
+            block_num,
+            account_ids: account_ids.iter().map(|&id| account::AccountId::from(id)).collect(),
+            note_tags: note_tags.to_vec(),
+            nullifiers: nullifier_prefixes.to_vec(),
+            note_hashes: note_hashes.iter().map(digest::Digest::from).collect(),
+            resume_token: Vec::new(),
+        };
+        self.send_sync_state(request).await
+    }
+
+    /// Resumes a sync after the block of the response which returned `resume_token`.
+    ///
+    /// The filters must be the ones of the request which returned the token, the node rejects the
+    /// token otherwise, or if the chain was rolled back past its block.
+    pub async fn resume_sync_state(
+        &self,
+        resume_token: &[u8],
+        account_ids: &[AccountId],
+        note_tags: &[u32],
+        nullifier_prefixes: &[u32],
+        note_hashes: &[Digest],
+    ) -> Result<StateSyncInfo, ClientError> {
+        let request = SyncStateRequest {
+            block_num: 0,
+            account_ids: account_ids.iter().map(|&id| account::AccountId::from(id)).collect(),
+            note_tags: note_tags.to_vec(),
+            nullifiers: nullifier_prefixes.to_vec(),
+            note_hashes: note_hashes.iter().map(digest::Digest::from).collect(),
+            resume_token: resume_token.to_vec(),
+        };
+        self.send_sync_state(request).await
+    }
+
+    async fn send_sync_state(
+        &self,
+        request: SyncStateRequest,
+    ) -> Result<StateSyncInfo, ClientError> {
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.sync_state(request).await }
+            })
+            .await?;
+
+        Ok(response.try_into()?)
+    }
+
+    /// Returns the latest hashes of the accounts of `account_ids` changed after block `block_num`,
+    /// with their paths in the account tree of the chain tip.
+    ///
+    /// Unlike [RpcClient::sync_state], the accounts are synced up to the chain tip at once, and the
+    /// paths let the client verify them trusting only the header of the chain tip.
+    pub async fn sync_accounts(
+        &self,
+        block_num: u32,
+        account_ids: &[AccountId],
+    ) -> Result<AccountsSync, ClientError> {
+        let request = SyncAccountsRequest {
+            block_num,
+            account_ids: account_ids.iter().map(|&id| account::AccountId::from(id)).collect(),
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.sync_accounts(request).await }
+            })
+            .await?;
+
+        Ok(response.try_into()?)
+    }
+
+    /// Subscribes to the consumption of `nullifiers`, starting at the block `from_block`.
+    ///
+    /// The blocks consuming some of the nullifiers are returned by [NullifierStream::next] as soon
+    /// as they are applied, and the stream ends once every nullifier was consumed. An interrupted
+    /// subscription is resumed from the last returned block, which may then be returned again.
+    pub async fn subscribe_nullifiers(
+        &self,
+        nullifiers: &[Digest],
+        from_block: u32,
+    ) -> Result<NullifierStream, ClientError> {
+        let request = SubscribeNullifiersRequest {
+            nullifiers: nullifiers.iter().map(digest::Digest::from).collect(),
+            from_block,
+        };
+
+        let stream = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.subscribe_nullifiers(request).await }
+            })
+            .await?;
+
+        Ok(NullifierStream { stream })
+    }
+
+    /// Submits a proven transaction to the block producer.
+    ///
+    /// Returns the receipt signed by the node when it accepted the transaction, if the node signs
+    /// receipts. The signature of the receipt is verified for the chain of the client, but it's up
+    /// to the caller to check the receipt's `node_key` is the key of the node.
+    ///
+    /// The transaction is assumed to be proven with the transaction kernel [TX_KERNEL_VERSION].
+    /// `account_delta` holds the changes made by the transaction to its account, it must be given
+    /// for public accounts and omitted for private accounts. `consumed_notes` are the public notes
+    /// consumed by the transaction, which the store links to their nullifiers so the clients
+    /// tracking them learn they were consumed, see [RpcClient::sync_state]. `private_notes` are the
+    /// hashes of the notes created by the transaction whose details the store never serves, their
+    /// visibility being reported by [RpcClient::sync_state].
+    pub async fn submit_proven_transaction(
+        &self,
+        transaction: &ProvenTransaction,
+        account_delta: Option<&AccountDelta>,
+        consumed_notes: &[Note],
+        private_notes: &[Digest],
+    ) -> Result<Option<SubmissionReceipt>, ClientError> {
+        self.submit(transaction, account_delta, consumed_notes, private_notes, None)
+            .await
+    }
+
+    /// Submits a proven transaction to the block producer, against the block space reserved for
+    /// the partner of the block producer owning `partner_key`.
+    ///
+    /// See [RpcClient::submit_proven_transaction], the transaction is rejected if `partner_key`
+    /// isn't the key of one of the block producer's partners.
+    pub async fn submit_partner_transaction(
+        &self,
+        transaction: &ProvenTransaction,
+        account_delta: Option<&AccountDelta>,
+        consumed_notes: &[Note],
+        private_notes: &[Digest],
+        partner_key: &KeyPair,
+    ) -> Result<Option<SubmissionReceipt>, ClientError> {
+        let signature = PartnerSignature::sign(partner_key, transaction.id().inner())
+            .map_err(ClientError::SigningFailed)?;
+        self.submit(transaction, account_delta, consumed_notes, private_notes, Some(signature))
+            .await
+    }
+
+    async fn submit(
+        &self,
+        transaction: &ProvenTransaction,
+        account_delta: Option<&AccountDelta>,
+        consumed_notes: &[Note],
+        private_notes: &[Digest],
+        partner_signature: Option<PartnerSignature>,
+    ) -> Result<Option<SubmissionReceipt>, ClientError> {
+        let request = SubmitProvenTransactionRequest {
+            transaction: transaction.to_bytes(),
+            tx_kernel_version: TX_KERNEL_VERSION,
+            account_delta: account_delta.map(Into::into),
+            partner_signature: partner_signature.as_ref().map(Into::into),
+            consumed_notes: consumed_notes.iter().map(|note| note.to_bytes()).collect(),
+            private_notes: private_notes.iter().map(Into::into).collect(),
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.submit_proven_transaction(request).await }
+            })
+            .await?;
+
+        let Some(receipt) = response.receipt else {
+            return Ok(None);
+        };
+        let receipt = SubmissionReceipt::try_from(receipt)?;
+        if receipt.tx_id != transaction.id().inner() || !receipt.verify(self.chain_id) {
+            return Err(ClientError::InvalidReceipt(receipt.tx_id));
+        }
+
+        Ok(Some(receipt))
+    }
+
+    /// Submits a batch of proven transactions, built and proven by an external batch producer, to
+    /// the block producer, which includes it as is in a block.
+    ///
+    /// `transactions` are the transactions of the batch in order, along with the changes they made
+    /// to their accounts, the public notes they consumed and the hashes of the private notes they
+    /// created, see [RpcClient::submit_proven_transaction]. `proof` is the serialized proof of the batch kernel
+    /// executed over the batch, which output `commitment`. The request is signed by `operator`, one
+    /// of the operators of the block producer. The batch is accepted or rejected as a whole.
+    ///
+    /// Returns the root of the tree of the notes created by the batch.
+    pub async fn submit_proven_batch(
+        &self,
+        transactions: &[(ProvenTransaction, Option<AccountDelta>, Vec<Note>, Vec<Digest>)],
+        commitment: Digest,
+        proof: Vec<u8>,
+        operator: &KeyPair,
+    ) -> Result<Digest, ClientError> {
+        let request = SubmitProvenBatchRequest {
+            transactions: transactions
+                .iter()
+                .map(|(transaction, account_delta, consumed_notes, private_notes)| {
+                    SubmitProvenTransactionRequest {
+                        transaction: transaction.to_bytes(),
+                        tx_kernel_version: TX_KERNEL_VERSION,
+                        account_delta: account_delta.as_ref().map(Into::into),
+                        partner_signature: None,
+                        consumed_notes: consumed_notes.iter().map(|note| note.to_bytes()).collect(),
+                        private_notes: private_notes.iter().map(Into::into).collect(),
+                    }
+                })
+                .collect(),
+            commitment: Some(commitment.into()),
+            proof,
+        };
+        // the request is signed anew for every attempt, the node rejecting the replayed signatures
+        let response = self
+            .call(|mut client| {
+                let mut request = tonic::Request::new(request.clone());
+                let signed = sign_request(
+                    &mut request,
+                    operator,
+                    self.chain_id,
+                    SUBMIT_PROVEN_BATCH_OPERATION,
+                )
+                .map_err(|err| {
+                    Status::internal(format!("Failed to sign the request as an operator: {err}"))
+                });
+                async move {
+                    signed?;
+                    client.submit_proven_batch(request).await
+                }
+            })
+            .await?;
+
+        Ok(response.created_notes_root.ok_or(ParseError::ProtobufMissingData)?.try_into()?)
+    }
+
+    /// Returns the transactions of `account_id` waiting to be included in a batch, in queue order,
+    /// and the latest ones which were dropped instead and must be submitted again.
+    pub async fn get_pending_transactions_by_account(
+        &self,
+        account_id: AccountId,
+    ) -> Result<AccountTransactions, ClientError> {
+        let request = GetPendingTransactionsByAccountRequest {
+            account_id: Some(account_id.into()),
+        };
+
+        let response = self
+            .call(|mut client| {
+                let request = request.clone();
+                async move { client.get_pending_transactions_by_account(request).await }
+            })
+            .await?;
+
+        Ok(response.try_into()?)
+    }
+
+    /// Returns the number of transactions and batches waiting to be included in a block, and of
+    /// the accounts and nullifiers locked by them.
+    pub async fn get_mempool_status(&self) -> Result<MempoolStatus, ClientError> {
+        let response = self
+            .call(|mut client| async move {
+                client.get_mempool_status(GetMempoolStatusRequest {}).await
+            })
+            .await?;
+
+        Ok(response.into())
+    }
+
+    // HELPERS
+    // --------------------------------------------------------------------------------------------
+
+    /// Sends a request with `send`, retrying while the node is unavailable.
+    async fn call<T, F, Fut>(
+        &self,
+        send: F,
+    ) -> Result<T, ClientError>
+    where
+        F: Fn(ApiClient) -> Fut,
+        Fut: Future<Output = Result<tonic::Response<T>, Status>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match send(self.client.clone()).await {
+                Ok(response) => return Ok(response.into_inner()),
+                Err(status) if is_retryable(&status) && attempt < self.options.max_retries => {
+                    attempt += 1;
+                    warn!(
+                        target: COMPONENT,
+                        attempt,
+                        max_retries = self.options.max_retries,
+                        %status,
+                        "Node unavailable, retrying request",
+                    );
+                    tokio::time::sleep(self.options.retry_interval).await;
+                },
+                Err(status) => return Err(ClientError::RequestFailed(status)),
+            }
+        }
+    }
+}
+
+// NULLIFIER STREAM
+// ================================================================================================
+
+/// Notifications of a subscription to the consumption of nullifiers, see
+/// [RpcClient::subscribe_nullifiers].
+#[derive(Debug)]
+pub struct NullifierStream {
+    stream: Streaming<SubscribeNullifiersResponse>,
+}
+
+impl NullifierStream {
+    /// Waits for the next block consuming some of the watched nullifiers.
+    ///
+    /// Returns `None` once every nullifier was consumed.
+    pub async fn next(&mut self) -> Result<Option<ConsumedNullifiers>, ClientError> {
+        let response = self.stream.message().await.map_err(ClientError::RequestFailed)?;
+
+        Ok(response.map(ConsumedNullifiers::try_from).transpose()?)
+    }
+}
+
+/// Returns true if the request failed because the node couldn't be reached, and sending it again
+/// may succeed.
+///
+/// Requests rejected by the node are never retried, including transactions, which are not
+/// idempotent.
+fn is_retryable(status: &Status) -> bool {
+    matches!(status.code(), Code::Unavailable)
+}
+
+/// Returns an error if the response doesn't contain one entry per item of the request.
+fn check_entries(
+    expected: usize,
+    got: usize,
+) -> Result<(), ClientError> {
+    if expected != got {
+        return Err(ClientError::UnexpectedNumberOfEntries { expected, got });
+    }
+    Ok(())
+}
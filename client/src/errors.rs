@@ -0,0 +1,21 @@
+use miden_crypto::dsa::rpo_falcon512::FalconError;
+use miden_node_proto::errors::ParseError;
+use miden_objects::Digest;
+use thiserror::Error;
+use tonic::Status;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("Failed to connect to the node: {0}")]
+    ConnectionFailed(#[from] tonic::transport::Error),
+    #[error("Request failed: {0}")]
+    RequestFailed(Status),
+    #[error("Malformed response: {0}")]
+    MalformedResponse(#[from] ParseError),
+    #[error("Expected {expected} entries in the response, got {got}")]
+    UnexpectedNumberOfEntries { expected: usize, got: usize },
+    #[error("Receipt of transaction {0} has an invalid signature")]
+    InvalidReceipt(Digest),
+    #[error("Failed to sign the transaction: {0:?}")]
+    SigningFailed(FalconError),
+}
@@ -0,0 +1,25 @@
+//! Typed client for the Miden node's RPC component.
+//!
+//! [RpcClient] wraps the gRPC API served by the RPC, converting requests and responses from and to
+//! [miden_objects] types, retrying requests while the node is unavailable, and optionally
+//! connecting over TLS.
+mod client;
+mod errors;
+mod types;
+
+pub use client::{NullifierStream, RpcClient, RpcClientOptions};
+pub use errors::ClientError;
+pub use miden_node_proto::domain::{
+    AccountHashUpdate, CommittedNote, ConsumedNoteUpdate, NullifierUpdate, SubmissionReceipt,
+};
+pub use types::{
+    AccountTransactions, AccountsSync, AuthenticatedBlockHeader, ChainTip, ConsumedNullifiers,
+    DroppedTransaction, MempoolStatus, NoteActivity, PendingTransaction, ProposedNotes,
+    StateSyncInfo, SyncedAccount, TagActivity, UnconsumedNote, UnconsumedNotes,
+};
+
+// CONSTANTS
+// =================================================================================================
+
+/// The name of the client component, used as the target of its logs
+pub const COMPONENT: &str = "miden-node-client";
@@ -0,0 +1,533 @@
+use miden_crypto::merkle::{MerklePath, MmrDelta, MmrProof};
+use miden_node_proto::{
+    account,
+    domain::{AccountHashUpdate, CommittedNote, ConsumedNoteUpdate, NullifierUpdate},
+    errors::ParseError,
+    note, responses,
+    responses::{
+        GetBlockHeaderByNumberResponse, GetMempoolStatusResponse, GetNoteTagStatsResponse,
+        GetPendingTransactionsByAccountResponse, GetProposedNotesResponse,
+        GetUnconsumedNotesResponse, SubscribeNullifiersResponse, SyncAccountsResponse,
+        SyncStateResponse,
+    },
+};
+use miden_objects::{accounts::AccountId, BlockHeader, Digest};
+
+// BLOCK HEADERS
+// ================================================================================================
+
+/// Block header, with its authentication path in the chain MMR of a reference block
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthenticatedBlockHeader {
+    pub block_header: BlockHeader,
+
+    /// Number of the reference block, whose chain root commits to the `chain_length` blocks before
+    /// it
+    pub chain_length: u32,
+
+    /// Path of the header in the chain MMR, `None` if the header is the reference block itself
+    pub mmr_path: Option<MerklePath>,
+}
+
+impl AuthenticatedBlockHeader {
+    /// Returns the proof of the header in the chain MMR committed to by the reference block's chain
+    /// root, if any.
+    pub fn mmr_proof(&self) -> Option<MmrProof> {
+        self.mmr_path.clone().map(|merkle_path| MmrProof {
+            forest: self.chain_length as usize,
+            position: self.block_header.block_num() as usize,
+            merkle_path,
+        })
+    }
+}
+
+impl TryFrom<GetBlockHeaderByNumberResponse> for AuthenticatedBlockHeader {
+    type Error = ParseError;
+
+    fn try_from(response: GetBlockHeaderByNumberResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block_header: response
+                .block_header
+                .ok_or(ParseError::ProtobufMissingData)?
+                .try_into()?,
+            chain_length: response.chain_length.ok_or(ParseError::ProtobufMissingData)?,
+            mmr_path: response.mmr_path.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+/// The latest block of the chain, and the latest block the node considers final
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainTip {
+    pub chain_tip: u32,
+
+    /// Number of the latest final block, the node's safe depth below the chain tip. Clients
+    /// requiring confirmations, e.g. bridges, should only act on the blocks up to this one
+    pub safe_chain_tip: u32,
+}
+
+impl From<&GetBlockHeaderByNumberResponse> for ChainTip {
+    fn from(response: &GetBlockHeaderByNumberResponse) -> Self {
+        Self {
+            chain_tip: response.chain_tip,
+            safe_chain_tip: response.safe_chain_tip,
+        }
+    }
+}
+
+// NOTE ACTIVITY
+// ================================================================================================
+
+/// Recent activity of the chain, returned by
+/// [RpcClient::get_note_tag_stats](crate::RpcClient::get_note_tag_stats) for clients to estimate
+/// the congestion of the network
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NoteActivity {
+    /// Number of the latest block in the chain, the last block covered by the activity
+    pub chain_tip: u32,
+
+    /// First block covered by the activity
+    pub from_block: u32,
+
+    /// Number of notes created over the covered blocks, with any tag
+    pub total_notes: u32,
+
+    /// Activity of the requested tags, in the order of the request
+    pub tags: Vec<TagActivity>,
+
+    /// Queued transactions consuming at least one note
+    pub queued_consuming: u32,
+
+    /// Queued transactions which don't consume any note
+    pub queued_creating: u32,
+}
+
+impl NoteActivity {
+    /// Returns the average number of notes created per block over the covered blocks.
+    pub fn notes_per_block(&self) -> f64 {
+        f64::from(self.total_notes) / f64::from(self.chain_tip - self.from_block + 1)
+    }
+}
+
+impl TryFrom<GetNoteTagStatsResponse> for NoteActivity {
+    type Error = ParseError;
+
+    fn try_from(response: GetNoteTagStatsResponse) -> Result<Self, Self::Error> {
+        let queue_depth = response.queue_depth.ok_or(ParseError::ProtobufMissingData)?;
+
+        Ok(Self {
+            chain_tip: response.chain_tip,
+            from_block: response.from_block,
+            total_notes: response.total_notes,
+            tags: response
+                .tags
+                .into_iter()
+                .map(|stats| TagActivity {
+                    tag: stats.tag,
+                    num_notes: stats.num_notes,
+                    num_blocks: stats.num_blocks,
+                })
+                .collect(),
+            queued_consuming: queue_depth.consuming,
+            queued_creating: queue_depth.creating,
+        })
+    }
+}
+
+/// Notes created with a tag over the blocks covered by a [NoteActivity]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TagActivity {
+    /// The 16 high bits of the tag
+    pub tag: u32,
+
+    pub num_notes: u32,
+
+    /// Number of blocks with at least one note created with the tag
+    pub num_blocks: u32,
+}
+
+// STATE SYNC
+// ================================================================================================
+
+/// Changes to the chain relevant to a client, returned by
+/// [RpcClient::sync_state](crate::RpcClient::sync_state)
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateSyncInfo {
+    /// Number of the latest block in the chain
+    pub chain_tip: u32,
+
+    /// Number of the latest block the node considers final, see [ChainTip::safe_chain_tip]
+    pub safe_chain_tip: u32,
+
+    /// Header of the block with the first note matching the request, or of the chain tip
+    pub block_header: BlockHeader,
+
+    /// Data needed to update the client's partial MMR up to the above block
+    pub mmr_delta: MmrDelta,
+
+    /// Latest hashes of the requested accounts which changed in the synced block range
+    pub account_hash_updates: Vec<AccountHashUpdate>,
+
+    /// Notes matching the request created in the above block
+    pub notes: Vec<CommittedNote>,
+
+    /// Nullifiers matching the request consumed in the synced block range
+    pub nullifiers: Vec<NullifierUpdate>,
+
+    /// Requested notes consumed in the synced block range
+    pub consumed_notes: Vec<ConsumedNoteUpdate>,
+
+    /// Opaque token resuming the sync after the above block, see
+    /// [RpcClient::resume_sync_state](crate::RpcClient::resume_sync_state)
+    pub resume_token: Vec<u8>,
+}
+
+impl TryFrom<SyncStateResponse> for StateSyncInfo {
+    type Error = ParseError;
+
+    fn try_from(response: SyncStateResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_tip: response.chain_tip,
+            safe_chain_tip: response.safe_chain_tip,
+            block_header: response
+                .block_header
+                .ok_or(ParseError::ProtobufMissingData)?
+                .try_into()?,
+            mmr_delta: response.mmr_delta.ok_or(ParseError::ProtobufMissingData)?.try_into()?,
+            account_hash_updates: response
+                .accounts
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            notes: response.notes.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?,
+            nullifiers: response
+                .nullifiers
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            consumed_notes: response
+                .consumed_notes
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            resume_token: response.resume_token,
+        })
+    }
+}
+
+/// Latest states of the accounts tracked by a client, returned by
+/// [RpcClient::sync_accounts](crate::RpcClient::sync_accounts)
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountsSync {
+    /// Header of the chain tip, whose account root authenticates the accounts
+    pub block_header: BlockHeader,
+
+    /// Requested accounts changed after the synced block, ordered by their latest change
+    pub accounts: Vec<SyncedAccount>,
+}
+
+impl TryFrom<SyncAccountsResponse> for AccountsSync {
+    type Error = ParseError;
+
+    fn try_from(response: SyncAccountsResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block_header: response
+                .block_header
+                .ok_or(ParseError::ProtobufMissingData)?
+                .try_into()?,
+            accounts: response
+                .accounts
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Hash of an account at the chain tip, the block in which the account was last changed, and the
+/// path of the hash in the chain tip's account tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyncedAccount {
+    pub account_id: AccountId,
+    pub account_hash: Digest,
+    pub block_num: u32,
+    pub merkle_path: MerklePath,
+}
+
+impl TryFrom<responses::AccountSyncRecord> for SyncedAccount {
+    type Error = ParseError;
+
+    fn try_from(record: responses::AccountSyncRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            account_id: record.account_id.ok_or(ParseError::ProtobufMissingData)?.try_into()?,
+            account_hash: record.account_hash.ok_or(ParseError::ProtobufMissingData)?.try_into()?,
+            block_num: record.block_num,
+            merkle_path: record.proof.ok_or(ParseError::ProtobufMissingData)?.try_into()?,
+        })
+    }
+}
+
+/// Block consuming some of the nullifiers watched by a subscription, returned by
+/// [NullifierStream::next](crate::NullifierStream::next)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsumedNullifiers {
+    pub block_num: u32,
+    pub nullifiers: Vec<Digest>,
+}
+
+impl TryFrom<SubscribeNullifiersResponse> for ConsumedNullifiers {
+    type Error = ParseError;
+
+    fn try_from(response: SubscribeNullifiersResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block_num: response.block_num,
+            nullifiers: response
+                .nullifiers
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+// UNCONSUMED NOTES
+// ================================================================================================
+
+/// Notes matching some tags which weren't consumed, returned by
+/// [RpcClient::get_unconsumed_notes](crate::RpcClient::get_unconsumed_notes)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnconsumedNotes {
+    /// Number of the latest block in the chain, the notes weren't consumed by any block up to it
+    pub chain_tip: u32,
+
+    /// Notes ordered by block number and note index
+    pub notes: Vec<UnconsumedNote>,
+
+    /// First block whose notes weren't listed, the block after the chain tip once all the blocks
+    /// are listed
+    pub next_block_num: u32,
+}
+
+impl TryFrom<GetUnconsumedNotesResponse> for UnconsumedNotes {
+    type Error = ParseError;
+
+    fn try_from(response: GetUnconsumedNotesResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_tip: response.chain_tip,
+            notes: response.notes.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?,
+            next_block_num: response.next_block_num,
+        })
+    }
+}
+
+/// Note created in a block, without its inclusion path
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnconsumedNote {
+    pub block_num: u32,
+    pub note_index: u32,
+    pub note_hash: Digest,
+    pub sender: AccountId,
+    pub tag: u64,
+}
+
+impl TryFrom<note::Note> for UnconsumedNote {
+    type Error = ParseError;
+
+    fn try_from(note: note::Note) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block_num: note.block_num,
+            note_index: note.note_index,
+            note_hash: note.note_hash.ok_or(ParseError::ProtobufMissingData)?.try_into()?,
+            sender: account::AccountId::from(note.sender).try_into()?,
+            tag: note.tag,
+        })
+    }
+}
+
+// PROPOSED NOTES
+// ================================================================================================
+
+/// Notes created by the block waiting to be committed by the node, returned by
+/// [RpcClient::get_proposed_notes](crate::RpcClient::get_proposed_notes)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProposedNotes {
+    /// Header of the proposed block
+    pub block_header: BlockHeader,
+
+    /// Notes created by the proposed block, with their merkle paths against the note root of the
+    /// above header, ordered by note index. The paths only hold once the block is committed.
+    pub notes: Vec<CommittedNote>,
+}
+
+impl TryFrom<GetProposedNotesResponse> for ProposedNotes {
+    type Error = ParseError;
+
+    fn try_from(response: GetProposedNotesResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block_header: response
+                .block_header
+                .ok_or(ParseError::ProtobufMissingData)?
+                .try_into()?,
+            notes: response
+                .notes
+                .into_iter()
+                .map(|note| note::NoteSyncRecord::from(note).try_into())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+// PENDING TRANSACTIONS
+// ================================================================================================
+
+/// Transactions of an account accepted by the block producer, returned by
+/// [RpcClient::get_pending_transactions_by_account](crate::RpcClient::get_pending_transactions_by_account)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountTransactions {
+    /// Transactions waiting to be included in a batch, in queue order
+    pub pending: Vec<PendingTransaction>,
+
+    /// Latest transactions which were dropped before being batched, the oldest first
+    pub dropped: Vec<DroppedTransaction>,
+}
+
+impl TryFrom<GetPendingTransactionsByAccountResponse> for AccountTransactions {
+    type Error = ParseError;
+
+    fn try_from(response: GetPendingTransactionsByAccountResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            pending: response
+                .transactions
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            dropped: response
+                .dropped_transactions
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Transaction accepted by the block producer, but not yet part of a batch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingTransaction {
+    pub tx_id: Digest,
+    pub initial_account_hash: Digest,
+    pub final_account_hash: Digest,
+
+    /// Position of the transaction in the queue, zero being the next transaction to be batched
+    pub queue_position: u32,
+}
+
+impl TryFrom<responses::PendingTransaction> for PendingTransaction {
+    type Error = ParseError;
+
+    fn try_from(tx: responses::PendingTransaction) -> Result<Self, Self::Error> {
+        Ok(Self {
+            tx_id: tx.tx_id.ok_or(ParseError::ProtobufMissingData)?.try_into()?,
+            initial_account_hash: tx
+                .initial_account_hash
+                .ok_or(ParseError::ProtobufMissingData)?
+                .try_into()?,
+            final_account_hash: tx
+                .final_account_hash
+                .ok_or(ParseError::ProtobufMissingData)?
+                .try_into()?,
+            queue_position: tx.queue_position,
+        })
+    }
+}
+
+/// Transaction accepted by the block producer, then dropped before being batched, which must be
+/// submitted again to be included in a block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DroppedTransaction {
+    pub tx_id: Digest,
+
+    /// Reason the transaction was dropped, e.g. `evicted`
+    pub reason: String,
+
+    /// Time the transaction was dropped, in milliseconds since the UNIX epoch
+    pub dropped_at: u64,
+}
+
+impl TryFrom<responses::DroppedTransaction> for DroppedTransaction {
+    type Error = ParseError;
+
+    fn try_from(tx: responses::DroppedTransaction) -> Result<Self, Self::Error> {
+        Ok(Self {
+            tx_id: tx.tx_id.ok_or(ParseError::ProtobufMissingData)?.try_into()?,
+            reason: tx.reason,
+            dropped_at: tx.dropped_at,
+        })
+    }
+}
+
+// MEMPOOL STATUS
+// ================================================================================================
+
+/// Transactions accepted by the block producer and not yet included in a block, returned by
+/// [RpcClient::get_mempool_status](crate::RpcClient::get_mempool_status) for clients to estimate
+/// the latency of their inclusion
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MempoolStatus {
+    /// Transactions waiting in the queue to be batched
+    pub queued_transactions: u32,
+
+    /// Batches being built
+    pub batches_in_flight: u32,
+
+    /// Batches built, waiting to be included in a block
+    pub ready_batches: u32,
+
+    /// Accounts modified by the transactions in flight, no other transaction of these accounts is
+    /// accepted until they are included in a block
+    pub locked_accounts: u32,
+
+    /// Nullifiers of the notes consumed by the transactions in flight
+    pub reserved_nullifiers: u32,
+}
+
+impl From<GetMempoolStatusResponse> for MempoolStatus {
+    fn from(response: GetMempoolStatusResponse) -> Self {
+        Self {
+            queued_transactions: response.queued_transactions,
+            batches_in_flight: response.batches_in_flight,
+            ready_batches: response.ready_batches,
+            locked_accounts: response.locked_accounts,
+            reserved_nullifiers: response.reserved_nullifiers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_node_proto::{digest, merkle, note::NoteSyncRecord, responses};
+
+    use super::{CommittedNote, ParseError, PendingTransaction};
+
+    #[test]
+    fn test_missing_fields_are_rejected() {
+        let note = NoteSyncRecord {
+            note_index: 3,
+            note_hash: None,
+            sender: 0,
+            tag: 5,
+            merkle_path: Some(merkle::MerklePath {
+                siblings: vec![digest::Digest::from([5u64, 6, 7, 8])],
+            }),
+            private: false,
+        };
+        assert_eq!(CommittedNote::try_from(note), Err(ParseError::ProtobufMissingData));
+
+        let tx = responses::PendingTransaction {
+            tx_id: Some(digest::Digest::from([1u64, 0, 0, 0])),
+            initial_account_hash: Some(digest::Digest::from([2u64, 0, 0, 0])),
+            final_account_hash: None,
+            queue_position: 0,
+        };
+        assert_eq!(PendingTransaction::try_from(tx), Err(ParseError::ProtobufMissingData));
+    }
+}
@@ -0,0 +1,18 @@
+//! Decodes arbitrary bytes as a protobuf account id, and converts it into an account id.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use miden_node_proto::account;
+use miden_objects::accounts::AccountId;
+use prost::Message;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(account_id) = account::AccountId::decode(data) else {
+        return;
+    };
+
+    // an account id accepted by the conversion round trips
+    if let Ok(converted) = AccountId::try_from(account_id.clone()) {
+        assert_eq!(account::AccountId::from(converted), account_id);
+    }
+});
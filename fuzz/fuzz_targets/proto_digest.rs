@@ -0,0 +1,18 @@
+//! Decodes arbitrary bytes as a protobuf digest, and converts it into a digest of field elements.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use miden_node_proto::digest::Digest;
+use miden_objects::Digest as RpoDigest;
+use prost::Message;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(digest) = Digest::decode(data) else {
+        return;
+    };
+
+    // a digest accepted by the conversion round trips
+    if let Ok(rpo_digest) = RpoDigest::try_from(&digest) {
+        assert_eq!(Digest::from(rpo_digest), digest);
+    }
+});
@@ -0,0 +1,19 @@
+//! Decodes arbitrary bytes as a protobuf merkle path, and converts it into a merkle path.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use miden_crypto::merkle::MerklePath;
+use miden_node_proto::merkle;
+use prost::Message;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(merkle_path) = merkle::MerklePath::decode(data) else {
+        return;
+    };
+
+    // a path accepted by the conversion round trips, and its depth is the number of siblings
+    if let Ok(converted) = MerklePath::try_from(merkle_path.clone()) {
+        assert_eq!(converted.depth() as usize, merkle_path.siblings.len());
+        assert_eq!(merkle::MerklePath::from(converted), merkle_path);
+    }
+});
@@ -0,0 +1,13 @@
+//! Decodes arbitrary bytes as a nullifier blob of the store's database.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use miden_crypto::utils::Serializable;
+use miden_node_store::db::decode_rpo_digest;
+
+fuzz_target!(|data: &[u8]| {
+    // a blob accepted by the decoding is exactly the serialized nullifier
+    if let Ok(nullifier) = decode_rpo_digest(data) {
+        assert_eq!(nullifier.to_bytes(), data);
+    }
+});
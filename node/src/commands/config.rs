@@ -0,0 +1,78 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use miden_node_utils::config_template::ConfigTemplate;
+
+use super::start::StartCommandConfig;
+
+/// Comment at the top of the config files written by `config init`.
+const HEADER: &str = "Configuration of the Miden node, generated by `miden-node config init`.
+
+Every option is set to its default value, and the optional ones are commented out along with an
+example value if they have one. Start the node with `miden-node start --config <path-to-config-file>`.";
+
+// CONFIG INIT
+// ================================================================================================
+
+/// Writes the default config of the node to `output_path`, every option commented with its
+/// documentation.
+///
+/// An existing file is only overwritten if `force` is set.
+pub fn init_config(
+    output_path: &Path,
+    force: bool,
+) -> Result<()> {
+    if !force && output_path.try_exists()? {
+        return Err(anyhow!(
+            "Failed to write the config file {} because it already exists. Use the --force flag to overwrite.",
+            output_path.display()
+        ));
+    }
+
+    let config = config_template()
+        .render(&StartCommandConfig::default())
+        .context("Failed to serialize the default config")?;
+    fs::write(output_path, config)
+        .with_context(|| format!("Failed to write {}", output_path.display()))?;
+
+    println!("Config file written to {}", output_path.display());
+
+    Ok(())
+}
+
+fn config_template() -> ConfigTemplate {
+    ConfigTemplate::new(HEADER).with_config::<StartCommandConfig>("")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use figment::Jail;
+    use miden_node_utils::config::load_config;
+
+    use super::{config_template, StartCommandConfig};
+    use crate::NODE_CONFIG_FILE_PATH;
+
+    #[test]
+    fn test_config_template_documents_every_option() {
+        let template = config_template();
+        let config = StartCommandConfig::default();
+
+        assert_eq!(template.undocumented_options(&config).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_config_init() {
+        Jail::expect_with(|jail| {
+            let config = config_template().render(&StartCommandConfig::default()).unwrap();
+            jail.create_file(NODE_CONFIG_FILE_PATH, &config)?;
+
+            let loaded: StartCommandConfig =
+                load_config(PathBuf::from(NODE_CONFIG_FILE_PATH).as_path()).extract()?;
+            assert_eq!(loaded, StartCommandConfig::default());
+
+            Ok(())
+        });
+    }
+}
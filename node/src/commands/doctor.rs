@@ -0,0 +1,560 @@
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter},
+    fs, io,
+    net::TcpListener,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use miden_node_block_producer::cli::BatchingArgs;
+use miden_node_proto::{
+    block_header,
+    block_producer::api_client as block_producer_client,
+    chain_id::ChainIdInterceptor,
+    domain::NodeInfo,
+    requests::{GetNodeInfoRequest, GetQueueDepthRequest},
+    store::api_client as store_client,
+};
+use miden_node_store::{
+    config::StoreConfig,
+    db::{self, SCHEMA_VERSION},
+    genesis::GenesisState,
+};
+use miden_node_utils::{
+    config::{load_config, parse_genesis_hash, ListenAddress},
+    grpc,
+    operator_auth::OperatorKeys,
+};
+use miden_objects::Digest;
+use tokio::time;
+
+use super::start::StartCommandConfig;
+
+/// Time given to a component to answer a request before it is reported as unreachable.
+const CONNECTIVITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// DOCTOR
+// ================================================================================================
+
+/// Checks that the node can be started with the config at `config_filepath`, printing a report of
+/// the checks, and fails if any of them failed. `data_directory` overrides the one of the store
+/// config, and `batching` the batching settings of the block producer config.
+///
+/// The checks only read the node's files, so they can run next to a running node.
+pub async fn doctor(
+    config_filepath: &Path,
+    data_directory: Option<PathBuf>,
+    batching: &BatchingArgs,
+) -> Result<()> {
+    let mut report = Report::default();
+
+    match load_config(config_filepath).extract::<StartCommandConfig>() {
+        Ok(mut config) => {
+            report.pass("config", format!("loaded `{}`", config_filepath.display()));
+            if let Some(data_directory) = data_directory {
+                config.store.data_directory = data_directory;
+            }
+            batching.apply(&mut config.block_producer.batching);
+
+            check_config(&mut report, &config);
+            let genesis_hash = check_data_directory(&mut report, &config.store);
+            check_genesis_hashes(&mut report, &config, genesis_hash);
+            check_ports(&mut report, &config);
+            // None of the components terminates TLS, a proxy in front of the rpc does
+            report.skip("tls", "the components serve plaintext gRPC, no certificate to check");
+            check_connectivity(&mut report, &config, genesis_hash).await;
+        },
+        Err(err) => report.fail(
+            "config",
+            format!("failed to load config file `{}`: {err}", config_filepath.display()),
+        ),
+    }
+
+    println!("{report}");
+
+    match report.num_failed() {
+        0 => Ok(()),
+        num_failed => Err(anyhow!("{num_failed} check(s) failed")),
+    }
+}
+
+// CHECKS
+// ================================================================================================
+
+/// Checks that the components are configured for the same chain, and that the keys and secrets
+/// they load at startup are valid.
+fn check_config(
+    report: &mut Report,
+    config: &StartCommandConfig,
+) {
+    let chain_ids = [config.store.chain_id, config.block_producer.chain_id, config.rpc.chain_id];
+    if chain_ids.iter().all(|chain_id| *chain_id == config.store.chain_id) {
+        report.pass("chain id", format!("all components use chain {}", config.store.chain_id));
+    } else {
+        report.fail(
+            "chain id",
+            format!(
+                "store: {}, block producer: {}, rpc: {}",
+                chain_ids[0], chain_ids[1], chain_ids[2]
+            ),
+        );
+    }
+
+    let operator_keys = [
+        ("operator keys", &config.store.operator_keys),
+        ("block producer operator keys", &config.block_producer.operator_keys),
+    ];
+    for (name, keys) in operator_keys {
+        match OperatorKeys::from_hex(keys) {
+            Ok(parsed) if parsed.is_empty() => {
+                report.pass(name, "none, the operator endpoints are disabled")
+            },
+            Ok(_) => report.pass(name, format!("{} key(s)", keys.len())),
+            Err(err) => report.fail(name, err.to_string()),
+        }
+    }
+
+    match config.block_producer.batching.validate() {
+        Ok(()) => report.pass("batching", config.block_producer.batching.to_string()),
+        Err(err) => report.fail("batching", err.to_string()),
+    }
+
+    let key_files = [
+        ("receipts key", &config.block_producer.receipts.key_file),
+        ("identity key", &config.block_producer.identity.key_file),
+        ("webhooks secret", &config.block_producer.webhooks.secret_file),
+    ];
+    for (name, path) in key_files {
+        match path {
+            None => report.skip(name, "not configured"),
+            Some(path) => match fs::read(path) {
+                Ok(_) => report.pass(name, format!("`{}` is readable", path.display())),
+                Err(err) => {
+                    report.fail(name, format!("failed to read `{}`: {err}", path.display()))
+                },
+            },
+        }
+    }
+}
+
+/// Checks that the genesis hashes configured for the block producer and the rpc, if any, are the
+/// hash of the genesis file.
+fn check_genesis_hashes(
+    report: &mut Report,
+    config: &StartCommandConfig,
+    genesis_hash: Option<Digest>,
+) {
+    let configured = [
+        ("block producer genesis hash", config.block_producer.genesis_hash.as_deref()),
+        ("rpc genesis hash", config.rpc.genesis_hash.as_deref()),
+    ];
+    for (name, configured) in configured {
+        match (parse_genesis_hash(configured), genesis_hash) {
+            (Ok(None), _) => report.skip(name, "not configured, any genesis block is accepted"),
+            (Ok(Some(configured)), Some(hash)) if configured != hash => report.fail(
+                name,
+                format!("{configured} doesn't match the hash of the genesis file {hash}"),
+            ),
+            (Ok(Some(configured)), _) => report.pass(name, configured.to_string()),
+            (Err(err), _) => report.fail(name, err.to_string()),
+        }
+    }
+}
+
+/// Checks the store's database and genesis file, returning the hash of the genesis block if the
+/// genesis file could be read.
+fn check_data_directory(
+    report: &mut Report,
+    config: &StoreConfig,
+) -> Option<Digest> {
+    let data_directory = config.data_directory();
+    let database_filepath = data_directory.database_filepath();
+    let genesis_filepath = data_directory.genesis_filepath();
+
+    let genesis = match GenesisState::read_from_file(&genesis_filepath) {
+        Ok(GenesisState {
+            chain_id: Some(genesis_chain_id),
+            ..
+        }) if genesis_chain_id != config.chain_id => {
+            report.fail(
+                "genesis",
+                format!(
+                    "`{}` is for chain {}, the store is configured for chain {}",
+                    genesis_filepath.display(),
+                    genesis_chain_id,
+                    config.chain_id
+                ),
+            );
+            None
+        },
+        Ok(genesis) => match genesis.into_block_parts() {
+            Ok((header, _)) => {
+                let hash = header.hash();
+                report.pass("genesis", format!("`{}`, hash {hash}", genesis_filepath.display()));
+                Some((hash, block_header::BlockHeader::from(header)))
+            },
+            Err(err) => {
+                report.fail("genesis", format!("malformed genesis state: {err}"));
+                None
+            },
+        },
+        Err(err) => {
+            report.fail("genesis", err.to_string());
+            None
+        },
+    };
+
+    if !database_filepath.exists() {
+        report.warn(
+            "database",
+            format!("`{}` is missing, it is created at startup", database_filepath.display()),
+        );
+        return genesis.map(|(hash, _)| hash);
+    }
+
+    match db::inspect(&database_filepath) {
+        Ok(info) => {
+            match info.schema_version.cmp(&SCHEMA_VERSION) {
+                Ordering::Equal => report.pass(
+                    "database",
+                    format!(
+                        "`{}`, schema version {}",
+                        database_filepath.display(),
+                        info.schema_version
+                    ),
+                ),
+                Ordering::Less => report.warn(
+                    "database",
+                    format!(
+                        "schema version {}, {} migration(s) are applied at startup",
+                        info.schema_version,
+                        SCHEMA_VERSION - info.schema_version
+                    ),
+                ),
+                Ordering::Greater => report.fail(
+                    "database",
+                    format!(
+                        "schema version {} is newer than the supported version {SCHEMA_VERSION}",
+                        info.schema_version
+                    ),
+                ),
+            }
+
+            match (&genesis, info.genesis_header) {
+                (Some((_, expected)), Some(actual)) => {
+                    if *expected == actual {
+                        report.pass(
+                            "genesis block",
+                            "the database's genesis block matches the genesis file",
+                        );
+                    } else {
+                        report.fail(
+                            "genesis block",
+                            "the database's genesis block doesn't match the genesis file",
+                        );
+                    }
+                },
+                (_, None) => report.skip("genesis block", "not in the database yet"),
+                (None, Some(_)) => report.skip("genesis block", "no genesis file to compare with"),
+            }
+        },
+        Err(err) => report
+            .fail("database", format!("failed to read `{}`: {err}", database_filepath.display())),
+    }
+
+    genesis.map(|(hash, _)| hash)
+}
+
+/// Checks that the addresses the components listen on are free.
+fn check_ports(
+    report: &mut Report,
+    config: &StartCommandConfig,
+) {
+    let components = [
+        ("store", &config.store.endpoint, &config.store.listen),
+        ("block producer", &config.block_producer.endpoint, &config.block_producer.listen),
+        ("rpc", &config.rpc.endpoint, &config.rpc.listen),
+    ];
+    for (component, endpoint, listen) in components {
+        check_address(report, component, &ListenAddress::Tcp(endpoint.clone()));
+        for address in listen {
+            check_address(report, component, address);
+        }
+    }
+
+    if let Some(endpoint) = &config.block_producer.dashboard.endpoint {
+        check_address(report, "dashboard", &ListenAddress::Tcp(endpoint.clone()));
+    }
+}
+
+fn check_address(
+    report: &mut Report,
+    component: &str,
+    address: &ListenAddress,
+) {
+    let name = format!("{component} address");
+    match bind(address) {
+        Ok(()) => report.pass(name, format!("{address} is free")),
+        Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
+            report.warn(name, format!("{address} is in use, is the node already running?"))
+        },
+        Err(err) => report.fail(name, format!("failed to bind {address}: {err}")),
+    }
+}
+
+/// Binds `address` and releases it right away.
+fn bind(address: &ListenAddress) -> io::Result<()> {
+    match address {
+        ListenAddress::Tcp(endpoint) => TcpListener::bind(endpoint).map(drop),
+        #[cfg(unix)]
+        ListenAddress::Unix { unix_socket } => {
+            // a stale socket is replaced at startup, a live one is served by a running component
+            if std::os::unix::net::UnixStream::connect(unix_socket).is_ok() {
+                return Err(io::ErrorKind::AddrInUse.into());
+            }
+            match unix_socket.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => Err(
+                    io::Error::new(io::ErrorKind::NotFound, "the socket's directory is missing"),
+                ),
+                _ => Ok(()),
+            }
+        },
+        #[cfg(not(unix))]
+        ListenAddress::Unix { .. } => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unix domain sockets are not supported",
+        )),
+    }
+}
+
+/// Checks that the store serves the chain of the block producer and the rpc, and that the rpc
+/// reaches the block producer.
+///
+/// The components are usually not running when the node is checked, so unreachable components
+/// are warnings, while components serving another chain are failures.
+async fn check_connectivity(
+    report: &mut Report,
+    config: &StartCommandConfig,
+    genesis_hash: Option<Digest>,
+) {
+    let block_producer_store =
+        (!config.block_producer.embedded_store).then_some(&config.block_producer.store_url);
+    let clients = [
+        ("block producer → store", block_producer_store, config.block_producer.chain_id),
+        ("rpc → store", Some(&config.rpc.store_url), config.rpc.chain_id),
+    ];
+    for (name, store_url, chain_id) in clients {
+        let Some(store_url) = store_url else {
+            report.skip(name, "the block producer embeds the store");
+            continue;
+        };
+
+        match time::timeout(CONNECTIVITY_TIMEOUT, get_node_info(store_url, chain_id)).await {
+            Ok(Ok(node_info)) => match node_info.verify(chain_id, genesis_hash) {
+                Ok(()) => report.pass(
+                    name,
+                    format!(
+                        "{store_url} serves chain {chain_id}, store version {}",
+                        node_info.version
+                    ),
+                ),
+                Err(err) => report.fail(name, format!("{store_url}: {err}")),
+            },
+            Ok(Err(err)) => report.warn(name, format!("{store_url} is unreachable: {err}")),
+            Err(_) => report.warn(name, format!("{store_url} didn't answer in time")),
+        }
+    }
+
+    let name = "rpc → block producer";
+    let url = &config.rpc.block_producer_url;
+    match time::timeout(CONNECTIVITY_TIMEOUT, get_queue_depth(url, config.rpc.chain_id)).await {
+        Ok(Ok(())) => report.pass(name, format!("{url} is reachable")),
+        Ok(Err(err)) => report.warn(name, format!("{url} is unreachable: {err}")),
+        Err(_) => report.warn(name, format!("{url} didn't answer in time")),
+    }
+}
+
+async fn get_node_info(
+    store_url: &str,
+    chain_id: u32,
+) -> Result<NodeInfo> {
+    let channel = grpc::connect(store_url).await?;
+    let mut store =
+        store_client::ApiClient::with_interceptor(channel, ChainIdInterceptor::new(chain_id));
+    let node_info = store.get_node_info(GetNodeInfoRequest {}).await?.into_inner();
+
+    Ok(node_info.try_into()?)
+}
+
+async fn get_queue_depth(
+    block_producer_url: &str,
+    chain_id: u32,
+) -> Result<()> {
+    let channel = grpc::connect(block_producer_url).await?;
+    let mut block_producer = block_producer_client::ApiClient::with_interceptor(
+        channel,
+        ChainIdInterceptor::new(chain_id),
+    );
+    block_producer.get_queue_depth(GetQueueDepthRequest {}).await?;
+
+    Ok(())
+}
+
+// REPORT
+// ================================================================================================
+
+/// Outcome of a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Pass,
+    /// The node can start, but the operator should have a look.
+    Warn,
+    Fail,
+    /// The check doesn't apply to the config.
+    Skip,
+}
+
+impl Display for Status {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Status::Pass => f.write_str("PASS"),
+            Status::Warn => f.write_str("WARN"),
+            Status::Fail => f.write_str("FAIL"),
+            Status::Skip => f.write_str("SKIP"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+}
+
+/// The checks run by the `doctor` command, in order.
+#[derive(Debug, Default)]
+struct Report {
+    checks: Vec<Check>,
+}
+
+impl Report {
+    fn push(
+        &mut self,
+        name: impl Into<String>,
+        status: Status,
+        detail: impl Into<String>,
+    ) {
+        self.checks.push(Check {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    fn pass(
+        &mut self,
+        name: impl Into<String>,
+        detail: impl Into<String>,
+    ) {
+        self.push(name, Status::Pass, detail)
+    }
+
+    fn warn(
+        &mut self,
+        name: impl Into<String>,
+        detail: impl Into<String>,
+    ) {
+        self.push(name, Status::Warn, detail)
+    }
+
+    fn fail(
+        &mut self,
+        name: impl Into<String>,
+        detail: impl Into<String>,
+    ) {
+        self.push(name, Status::Fail, detail)
+    }
+
+    fn skip(
+        &mut self,
+        name: impl Into<String>,
+        detail: impl Into<String>,
+    ) {
+        self.push(name, Status::Skip, detail)
+    }
+
+    fn num_failed(&self) -> usize {
+        self.checks.iter().filter(|check| check.status == Status::Fail).count()
+    }
+}
+
+impl Display for Report {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        let width = self.checks.iter().map(|check| check.name.chars().count()).max().unwrap_or(0);
+        for check in &self.checks {
+            writeln!(f, "[{}] {:width$}  {}", check.status, check.name, check.detail)?;
+        }
+
+        let count = |status| self.checks.iter().filter(|check| check.status == status).count();
+        write!(
+            f,
+            "{} passed, {} warning(s), {} failed, {} skipped",
+            count(Status::Pass),
+            count(Status::Warn),
+            count(Status::Fail),
+            count(Status::Skip)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use miden_node_utils::config::{Endpoint, ListenAddress};
+
+    use super::{check_address, Report, Status};
+
+    #[test]
+    fn test_report() {
+        let mut report = Report::default();
+        report.pass("config", "loaded");
+        report.fail("chain id", "mismatch");
+        report.skip("tls", "not configured");
+
+        assert_eq!(report.num_failed(), 1);
+        assert_eq!(
+            report.to_string(),
+            "[PASS] config    loaded\n\
+             [FAIL] chain id  mismatch\n\
+             [SKIP] tls       not configured\n\
+             1 passed, 0 warning(s), 1 failed, 0 skipped"
+        );
+    }
+
+    #[test]
+    fn test_check_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let in_use = Endpoint {
+            host: "127.0.0.1".to_string(),
+            port: listener.local_addr().unwrap().port(),
+        };
+
+        let mut report = Report::default();
+        check_address(&mut report, "store", &ListenAddress::Tcp(in_use.clone()));
+        drop(listener);
+        check_address(&mut report, "store", &ListenAddress::Tcp(in_use));
+
+        let statuses: Vec<_> = report.checks.iter().map(|check| check.status).collect();
+        assert_eq!(statuses, [Status::Warn, Status::Pass]);
+    }
+}
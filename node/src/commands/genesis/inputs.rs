@@ -9,7 +9,9 @@ use serde::Deserialize;
 #[derive(Debug, Clone, Deserialize)]
 pub struct GenesisInput {
     pub version: u64,
-    pub timestamp: u64,
+    /// Seconds since the UNIX epoch, the time of the genesis generation if absent
+    pub timestamp: Option<u64>,
+    pub chain_id: u32,
     pub accounts: Vec<AccountInput>,
 }
 
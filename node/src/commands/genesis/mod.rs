@@ -1,6 +1,7 @@
 use std::{
     fs,
     path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{anyhow, Result};
@@ -35,6 +36,8 @@ const DEFAULT_ACCOUNTS_DIR: &str = "accounts/";
 /// * `output_path` - A `PathBuf` reference to the path where the genesis file will be created.
 /// * `force` - A boolean flag to determine if an existing genesis file should be overwritten.
 /// * `inputs_path` - A `PathBuf` reference to the genesis inputs file's path.
+/// * `timestamp` - Timestamp of the genesis block in seconds since the UNIX epoch, overrides the
+///   one of the inputs file. The current time is used if neither is set.
 ///
 /// # Returns
 ///
@@ -45,6 +48,7 @@ pub fn make_genesis(
     inputs_path: &PathBuf,
     output_path: &PathBuf,
     force: &bool,
+    timestamp: Option<u64>,
 ) -> Result<()> {
     let inputs_path = Path::new(inputs_path);
     let output_path = Path::new(output_path);
@@ -92,7 +96,14 @@ pub fn make_genesis(
         DEFAULT_ACCOUNTS_DIR
     );
 
-    let genesis_state = GenesisState::new(accounts, genesis_input.version, genesis_input.timestamp);
+    let timestamp = match timestamp.or(genesis_input.timestamp) {
+        Some(timestamp) => timestamp,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+    println!("Genesis block timestamp: {timestamp}");
+
+    let genesis_state =
+        GenesisState::new(accounts, genesis_input.version, timestamp, genesis_input.chain_id);
     fs::write(output_path, genesis_state.to_bytes()).unwrap_or_else(|_| {
         panic!("Failed to write genesis state to output file {}", output_path.display())
     });
@@ -219,6 +230,7 @@ mod tests {
                 r#"
                 version = 1
                 timestamp = 1672531200
+                chain_id = 1
 
                 [[accounts]]
                 type = "BasicWallet"
@@ -240,7 +252,7 @@ mod tests {
             let genesis_dat_file_path = PathBuf::from(DEFAULT_GENESIS_FILE_PATH);
 
             //  run make_genesis to generate genesis.dat and accounts folder and files
-            make_genesis(&genesis_inputs_file_path, &genesis_dat_file_path, &true).unwrap();
+            make_genesis(&genesis_inputs_file_path, &genesis_dat_file_path, &true, None).unwrap();
 
             let a0_file_path = PathBuf::from("accounts/account0.mac");
             let a1_file_path = PathBuf::from("accounts/account1.mac");
@@ -259,7 +271,7 @@ mod tests {
 
             // build supposed genesis_state
             let supposed_genesis_state =
-                GenesisState::new(vec![a0.account, a1.account], 1, 1672531200);
+                GenesisState::new(vec![a0.account, a1.account], 1, 1672531200, 1);
 
             // assert that both genesis_state(s) are eq
             assert_eq!(genesis_state, supposed_genesis_state);
@@ -267,4 +279,41 @@ mod tests {
             Ok(())
         });
     }
+
+    #[test]
+    fn test_make_genesis_timestamp() {
+        let genesis_inputs_file_path = PathBuf::from("genesis.toml");
+        let genesis_dat_file_path = PathBuf::from(DEFAULT_GENESIS_FILE_PATH);
+        let read_timestamp = || {
+            let genesis_file_contents = fs::read(&genesis_dat_file_path).unwrap();
+            GenesisState::read_from_bytes(&genesis_file_contents).unwrap().timestamp
+        };
+
+        Jail::expect_with(|jail| {
+            jail.create_file(
+                genesis_inputs_file_path.as_path(),
+                r#"
+                version = 1
+                chain_id = 1
+                accounts = []
+            "#,
+            )?;
+
+            // the explicit timestamp is used
+            make_genesis(
+                &genesis_inputs_file_path,
+                &genesis_dat_file_path,
+                &true,
+                Some(1700000000),
+            )
+            .unwrap();
+            assert_eq!(read_timestamp(), 1700000000);
+
+            // without any timestamp, the genesis is dated at its generation
+            make_genesis(&genesis_inputs_file_path, &genesis_dat_file_path, &true, None).unwrap();
+            assert!(read_timestamp() > 1700000000);
+
+            Ok(())
+        });
+    }
 }
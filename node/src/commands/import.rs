@@ -0,0 +1,117 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use miden_node_proto::{
+    chain_id::ChainIdInterceptor,
+    requests::{ApplyBlockRequest, ApplyBlocksRequest, GetBlockHeaderByNumberRequest},
+    store::api_client as store_client,
+};
+use miden_node_store::{APPLY_BLOCKS_OPERATION, MAX_APPLY_BLOCKS};
+use miden_node_utils::{config::load_config, grpc};
+use prost::Message;
+
+use super::{
+    restart::{load_key, Operator},
+    start::StartCommandConfig,
+};
+
+// IMPORT BLOCKS
+// ================================================================================================
+
+/// Applies the blocks of the archive at `archive_filepath` to the store of the node configured at
+/// `config_filepath`, signing the requests with the operator key whose hex encoded seed is in
+/// `operator_key_file`.
+///
+/// The archive holds length delimited `ApplyBlockRequest` protobuf messages, in order. The blocks
+/// already applied by the store are skipped, so an interrupted import is resumed by running it
+/// again. The remaining blocks are applied by runs of up to [MAX_APPLY_BLOCKS], each run being
+/// applied entirely or not at all.
+pub async fn import_blocks(
+    config_filepath: &Path,
+    operator_key_file: &Path,
+    archive_filepath: &Path,
+) -> Result<()> {
+    let config: StartCommandConfig = load_config(config_filepath).extract().map_err(|err| {
+        anyhow!("failed to load config file `{}`: {err}", config_filepath.display())
+    })?;
+    let chain_id = config.store.chain_id;
+    let operator = Operator {
+        keypair: load_key(operator_key_file)?,
+        chain_id,
+    };
+
+    let blocks = read_archive(archive_filepath)?;
+    if let Some((block_num, block)) = blocks.iter().find(|(_, block)| block.chain_id != chain_id) {
+        bail!(
+            "Block {block_num} of the archive is of chain {}, the store is of chain {chain_id}",
+            block.chain_id
+        );
+    }
+
+    let channel = grpc::connect(&config.rpc.store_url).await?;
+    let mut client =
+        store_client::ApiClient::with_interceptor(channel, ChainIdInterceptor::new(chain_id));
+
+    let chain_tip = client
+        .get_block_header_by_number(GetBlockHeaderByNumberRequest::default())
+        .await?
+        .into_inner()
+        .block_header
+        .context("The store didn't return its chain tip")?
+        .block_num;
+
+    let mut blocks = blocks
+        .into_iter()
+        .skip_while(|(block_num, _)| *block_num <= chain_tip)
+        .map(|(_, block)| block)
+        .peekable();
+    if blocks.peek().is_none() {
+        println!("The store is already at block {chain_tip}, nothing to import");
+        return Ok(());
+    }
+
+    loop {
+        let run: Vec<ApplyBlockRequest> = blocks.by_ref().take(MAX_APPLY_BLOCKS).collect();
+        if run.is_empty() {
+            break;
+        }
+
+        let request = operator.sign(ApplyBlocksRequest { blocks: run }, APPLY_BLOCKS_OPERATION)?;
+        let chain_tip = client.apply_blocks(request).await?.into_inner().chain_tip;
+        println!("Blocks applied up to block {chain_tip}");
+    }
+
+    Ok(())
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Reads the blocks of the archive at `filepath`, along with their block numbers, checking they
+/// are contiguous.
+fn read_archive(filepath: &Path) -> Result<Vec<(u32, ApplyBlockRequest)>> {
+    let bytes =
+        fs::read(filepath).with_context(|| format!("Failed to read {}", filepath.display()))?;
+
+    let mut buf = bytes.as_slice();
+    let mut blocks: Vec<(u32, ApplyBlockRequest)> = Vec::new();
+    while !buf.is_empty() {
+        let block = ApplyBlockRequest::decode_length_delimited(&mut buf).with_context(|| {
+            format!("Failed to decode block {} of {}", blocks.len(), filepath.display())
+        })?;
+        let block_num = block
+            .block
+            .as_ref()
+            .with_context(|| format!("Block {} of the archive has no header", blocks.len()))?
+            .block_num;
+
+        if let Some((prev_block_num, _)) = blocks.last() {
+            if block_num != prev_block_num + 1 {
+                bail!("Block {block_num} of the archive doesn't follow block {prev_block_num}");
+            }
+        }
+        blocks.push((block_num, block));
+    }
+
+    Ok(blocks)
+}
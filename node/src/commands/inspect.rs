@@ -0,0 +1,161 @@
+use std::{fmt::Write, fs, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use miden_crypto::utils::Deserializable;
+use miden_node_proto::{block_header, note::NoteArchive};
+use miden_node_store::genesis::GenesisState;
+use miden_node_utils::formatting::{
+    format_account_id, format_block_header, format_opt, format_proven_transaction,
+};
+use miden_objects::{transaction::ProvenTransaction, BlockHeader};
+use prost::Message;
+
+// INSPECT
+// ================================================================================================
+
+/// Kinds of files decoded by [inspect].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum FileKind {
+    /// A serialized proven transaction, as submitted to the block producer
+    Transaction,
+    /// A genesis file, as generated by `make-genesis`
+    Genesis,
+    /// An encoded `BlockHeader` protobuf message
+    BlockHeader,
+    /// An encoded `NoteArchive` protobuf message, as exported by the store
+    NoteArchive,
+}
+
+/// Decodes the file at `filepath`, holding a `kind` data structure, and prints it.
+pub fn inspect(
+    kind: FileKind,
+    filepath: &Path,
+) -> Result<()> {
+    let bytes =
+        fs::read(filepath).with_context(|| format!("Failed to read {}", filepath.display()))?;
+
+    let output = match kind {
+        FileKind::Transaction => inspect_transaction(&bytes),
+        FileKind::Genesis => inspect_genesis(&bytes),
+        FileKind::BlockHeader => inspect_block_header(&bytes),
+        FileKind::NoteArchive => inspect_note_archive(&bytes),
+    }
+    .with_context(|| format!("Failed to decode {} as {kind:?}", filepath.display()))?;
+    print!("{output}");
+
+    Ok(())
+}
+
+fn inspect_transaction(bytes: &[u8]) -> Result<String> {
+    let tx = ProvenTransaction::read_from_bytes(bytes)
+        .map_err(|err| anyhow!("Invalid transaction: {err}"))?;
+
+    Ok(format!("transaction: {}\n", format_proven_transaction(&tx)))
+}
+
+fn inspect_genesis(bytes: &[u8]) -> Result<String> {
+    let genesis =
+        GenesisState::read_from_bytes(bytes).map_err(|err| anyhow!("Invalid genesis: {err}"))?;
+
+    let mut output = String::new();
+    match genesis.chain_id {
+        Some(chain_id) => writeln!(output, "chain_id: {chain_id}")?,
+        None => writeln!(output, "chain_id: none, the genesis file predates the chain ids")?,
+    }
+    writeln!(output, "version: {}", genesis.version)?;
+    writeln!(output, "timestamp: {}", genesis.timestamp)?;
+    writeln!(output, "accounts: {}", genesis.accounts.len())?;
+    for account in &genesis.accounts {
+        writeln!(
+            output,
+            "  {{ account_id: {}, hash: {}, nonce: {} }}",
+            account.id().to_hex(),
+            account.hash(),
+            account.nonce()
+        )?;
+    }
+
+    let (block_header, _) = genesis.into_block_parts()?;
+    writeln!(output, "block_header: {}", format_block_header(&block_header))?;
+
+    Ok(output)
+}
+
+fn inspect_block_header(bytes: &[u8]) -> Result<String> {
+    let block_header: BlockHeader = block_header::BlockHeader::decode(bytes)?.try_into()?;
+
+    Ok(format!("block_header: {}\n", format_block_header(&block_header)))
+}
+
+fn inspect_note_archive(bytes: &[u8]) -> Result<String> {
+    let archive = NoteArchive::decode(bytes)?;
+
+    let mut output = String::new();
+    writeln!(output, "chain_id: {}", archive.chain_id)?;
+    writeln!(output, "blocks: {}..={}", archive.from_block, archive.to_block)?;
+    writeln!(output, "note_tags: {:?}", archive.note_tags)?;
+    writeln!(output, "block_headers: {}", archive.block_headers.len())?;
+    for block_header in archive.block_headers {
+        let block_header: BlockHeader = block_header.try_into()?;
+        writeln!(output, "  {}", format_block_header(&block_header))?;
+    }
+    writeln!(output, "notes: {}", archive.notes.len())?;
+    for note in archive.notes {
+        writeln!(
+            output,
+            "  {{ block_num: {}, note_index: {}, note_hash: {}, sender: {}, tag: {}, merkle_path: {} }}",
+            note.block_num,
+            note.note_index,
+            format_opt(note.note_hash.as_ref()),
+            format_account_id(note.sender),
+            note.tag,
+            note.merkle_path.map_or(0, |path| path.siblings.len()),
+        )?;
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_node_proto::{
+        block_header,
+        note::{Note, NoteArchive},
+    };
+    use miden_node_store::genesis::GenesisState;
+    use miden_objects::utils::Serializable;
+    use prost::Message;
+
+    use super::{inspect_block_header, inspect_genesis, inspect_note_archive};
+
+    #[test]
+    fn test_inspect() {
+        let genesis = GenesisState::new(Vec::new(), 1, 1700000000, 7);
+        let output = inspect_genesis(&genesis.to_bytes()).unwrap();
+        assert!(output.starts_with("chain_id: 7\nversion: 1\ntimestamp: 1700000000\naccounts: 0\n"));
+
+        let (header, _) = genesis.into_block_parts().unwrap();
+        let hash = header.hash().to_string();
+        let proto_header = block_header::BlockHeader::from(header);
+        let output = inspect_block_header(&proto_header.encode_to_vec()).unwrap();
+        assert!(output.contains(&format!("hash: {hash}")));
+        assert!(inspect_block_header(&[0xff]).is_err());
+
+        let archive = NoteArchive {
+            chain_id: 7,
+            from_block: 0,
+            to_block: 0,
+            note_tags: vec![1],
+            block_headers: vec![proto_header],
+            notes: vec![Note {
+                sender: 0x100,
+                tag: 1 << 48,
+                ..Default::default()
+            }],
+        };
+        let output = inspect_note_archive(&archive.encode_to_vec()).unwrap();
+        assert!(output.contains(&format!("hash: {hash}")));
+        assert!(output.contains("note_hash: None, sender: 0x100"));
+    }
+}
@@ -1,5 +1,20 @@
+mod config;
+pub use config::init_config;
+
+mod doctor;
+pub use doctor::doctor;
+
 mod genesis;
 pub use genesis::make_genesis;
 
+mod import;
+pub use import::import_blocks;
+
+mod inspect;
+pub use inspect::{inspect, FileKind};
+
+mod restart;
+pub use restart::restart_producer;
+
 mod start;
 pub use start::start_node;
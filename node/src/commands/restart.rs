@@ -0,0 +1,173 @@
+use std::{fs, path::Path, time::Duration};
+
+use anyhow::{anyhow, bail, Context, Result};
+use miden_crypto::dsa::rpo_falcon512::KeyPair;
+use miden_node_block_producer::restart::{
+    PAUSE_INTAKE_OPERATION, SHUTDOWN_OPERATION, SNAPSHOT_QUEUE_OPERATION,
+};
+use miden_node_proto::{
+    block_producer::api_client as block_producer_client,
+    chain_id::ChainIdInterceptor,
+    requests::{
+        GetRestartStatusRequest, PauseIntakeRequest, ShutdownRequest, SnapshotQueueRequest,
+    },
+    responses::GetRestartStatusResponse,
+};
+use miden_node_utils::{config::load_config, grpc, operator_auth::sign_request};
+use prost::Message;
+use tokio::time::{self, Instant};
+use tonic::{codegen::InterceptedService, transport::Channel, Code, Request};
+
+use super::start::StartCommandConfig;
+
+/// Interval at which the block producer is polled while it drains its queue.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+type BlockProducerClient =
+    block_producer_client::ApiClient<InterceptedService<Channel, ChainIdInterceptor>>;
+
+// RESTART PRODUCER
+// ================================================================================================
+
+/// Stops the block producer of the node configured at `config_filepath` without losing its
+/// transactions, for the node to start it again. The admin operations are signed with the
+/// operator key whose hex encoded seed is in `operator_key_file`.
+///
+/// The intake of transactions is paused, then the queue is drained into blocks, or saved to the
+/// block producer's snapshot if `snapshot` is set. Once the remaining batches were included in a
+/// block, the block producer is shut down. If the queue isn't drained within `drain_timeout`, the
+/// intake is resumed and the restart aborted.
+pub async fn restart_producer(
+    config_filepath: &Path,
+    operator_key_file: &Path,
+    snapshot: bool,
+    drain_timeout: Duration,
+) -> Result<()> {
+    let config: StartCommandConfig = load_config(config_filepath).extract().map_err(|err| {
+        anyhow!("failed to load config file `{}`: {err}", config_filepath.display())
+    })?;
+    let chain_id = config.block_producer.chain_id;
+    let operator = Operator {
+        keypair: load_key(operator_key_file)?,
+        chain_id,
+    };
+
+    let channel = grpc::connect(&config.rpc.block_producer_url).await?;
+    let mut client = block_producer_client::ApiClient::with_interceptor(
+        channel,
+        ChainIdInterceptor::new(chain_id),
+    );
+
+    let status = restart_status(&mut client).await?;
+    if status.block_production_stopped {
+        bail!("The block producer is already exiting");
+    }
+
+    let request = operator.sign(PauseIntakeRequest { paused: true }, PAUSE_INTAKE_OPERATION)?;
+    client.pause_intake(request).await?;
+    println!("Transaction intake paused");
+
+    let deadline = Instant::now() + drain_timeout;
+    loop {
+        if let Err(err) = drain(&mut client, &operator, snapshot, deadline).await {
+            // best effort, the block producer keeps serving its clients
+            let request =
+                operator.sign(PauseIntakeRequest { paused: false }, PAUSE_INTAKE_OPERATION)?;
+            match client.pause_intake(request).await {
+                Ok(_) => println!("Transaction intake resumed"),
+                Err(status) => println!("Failed to resume the transaction intake: {status}"),
+            }
+            return Err(err);
+        }
+
+        // the block being built may fail, its batches waiting for the next block
+        let request = operator.sign(ShutdownRequest {}, SHUTDOWN_OPERATION)?;
+        match client.shutdown(request).await {
+            Ok(_) => break,
+            Err(status) if status.code() == Code::FailedPrecondition => {
+                println!("Shutdown postponed: {}", status.message());
+            },
+            Err(status) => return Err(status.into()),
+        }
+    }
+
+    println!("Block producer stopped, it exits once its in-flight requests completed");
+
+    Ok(())
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Signs the admin operations for the chain `chain_id`.
+pub(super) struct Operator {
+    pub(super) keypair: KeyPair,
+    pub(super) chain_id: u32,
+}
+
+impl Operator {
+    pub(super) fn sign<T: Message>(
+        &self,
+        request: T,
+        operation: &str,
+    ) -> Result<Request<T>> {
+        let mut request = Request::new(request);
+        sign_request(&mut request, &self.keypair, self.chain_id, operation)?;
+        Ok(request)
+    }
+}
+
+/// Waits for the block producer to include all its transactions in blocks, the queued ones being
+/// saved to the snapshot first if `snapshot` is set.
+async fn drain(
+    client: &mut BlockProducerClient,
+    operator: &Operator,
+    snapshot: bool,
+    deadline: Instant,
+) -> Result<()> {
+    let mut reported = None;
+    loop {
+        let mut status = restart_status(client).await?;
+
+        // the transactions of a failed batch go back to the queue, to be snapshotted too
+        if snapshot && status.queued_transactions > 0 {
+            let request = operator.sign(SnapshotQueueRequest {}, SNAPSHOT_QUEUE_OPERATION)?;
+            let num_txs = client.snapshot_queue(request).await?.into_inner().num_transactions;
+            println!("{num_txs} queued transaction(s) saved to the snapshot");
+            status = restart_status(client).await?;
+        }
+
+        let pending = (status.queued_transactions, status.batches_in_flight, status.ready_batches);
+        if pending == (0, 0, 0) {
+            return Ok(());
+        }
+        if reported != Some(pending) {
+            println!(
+                "Waiting for {} queued transaction(s), {} batch(es) in flight and {} ready batch(es)",
+                pending.0, pending.1, pending.2
+            );
+            reported = Some(pending);
+        }
+
+        if Instant::now() >= deadline {
+            bail!("The block producer wasn't drained in time");
+        }
+        time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn restart_status(client: &mut BlockProducerClient) -> Result<GetRestartStatusResponse> {
+    Ok(client.get_restart_status(GetRestartStatusRequest {}).await?.into_inner())
+}
+
+/// Loads the operator key from `key_file`, which holds the hex encoded seed of the key.
+pub(super) fn load_key(key_file: &Path) -> Result<KeyPair> {
+    let seed = fs::read_to_string(key_file)
+        .with_context(|| format!("Failed to read key file {}", key_file.display()))?;
+    let seed = seed.trim();
+    let seed = hex::decode(seed.strip_prefix("0x").unwrap_or(seed))
+        .with_context(|| format!("Key file {} is not valid hex", key_file.display()))?;
+
+    KeyPair::from_seed(&seed)
+        .map_err(|err| anyhow!("Invalid key seed in {}: {err:?}", key_file.display()))
+}
@@ -1,62 +1,162 @@
-use std::{path::Path, time::Duration};
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-use anyhow::{anyhow, Result};
-use miden_node_block_producer::{config::BlockProducerConfig, server as block_producer_server};
+use anyhow::{anyhow, bail, Result};
+use miden_node_block_producer::{
+    cli::BatchingArgs, config::BlockProducerConfig, server as block_producer_server,
+};
 use miden_node_rpc::{config::RpcConfig, server as rpc_server};
-use miden_node_store::{config::StoreConfig, db::Db, server as store_server};
-use miden_node_utils::config::load_config;
+use miden_node_store::{
+    config::StoreConfig,
+    db::Db,
+    server::{self as store_server, StoreApi},
+};
+use miden_node_utils::{config::load_config, documented_config};
 use serde::{Deserialize, Serialize};
 use tokio::task::JoinSet;
+use tracing::{info, warn};
 
 // Top-level config
 // ================================================================================================
 
-/// Node top-level configuration.
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
-pub struct StartCommandConfig {
-    pub block_producer: BlockProducerConfig,
-    pub rpc: RpcConfig,
-    pub store: StoreConfig,
+documented_config! {
+    /// Node top-level configuration.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    pub struct StartCommandConfig {
+        /// Block producer, batching the transactions and building the blocks out of them.
+        pub block_producer: BlockProducerConfig => table,
+        /// RPC, the public gRPC endpoint of the node serving the clients.
+        pub rpc: RpcConfig => table,
+        /// Store, holding the state of the chain.
+        pub store: StoreConfig => table,
+    }
 }
 
 // START
 // ===================================================================================================
 
-pub async fn start_node(config_filepath: &Path) -> Result<()> {
-    let config: StartCommandConfig = load_config(config_filepath).extract().map_err(|err| {
+/// Starts all the components of the node, `data_directory` overrides the one of the store config,
+/// and `batching` the batching settings of the block producer config.
+pub async fn start_node(
+    config_filepath: &Path,
+    data_directory: Option<PathBuf>,
+    batching: &BatchingArgs,
+) -> Result<()> {
+    let mut config: StartCommandConfig = load_config(config_filepath).extract().map_err(|err| {
         anyhow!("failed to load config file `{}`: {err}", config_filepath.display())
     })?;
+    if let Some(data_directory) = data_directory {
+        config.store.data_directory = data_directory;
+    }
+    batching.apply(&mut config.block_producer.batching);
 
     let mut join_set = JoinSet::new();
     let db = Db::setup(config.store.clone()).await?;
-    join_set.spawn(store_server::serve(config.store, db));
 
-    // wait for store before starting block producer
-    tokio::time::sleep(Duration::from_secs(1)).await;
-    join_set.spawn(block_producer_server::serve(config.block_producer));
+    // with partial availability, the store is served while its state is loaded, see
+    // `store_server::serve`, and the block producer starts once it is
+    let store = Arc::new(StoreApi::new(&config.store, db)?);
+    let store_config = config.store.clone();
+    let partial_availability = config.store.startup.partial_availability;
+    if !partial_availability {
+        store.finish_loading(&store_config).await?;
+    }
+    join_set.spawn(component(STORE, store_server::serve_api(config.store, store.clone())));
+
+    join_set.spawn(component(BLOCK_PRODUCER, {
+        let block_producer = serve_block_producer(config.block_producer.clone(), store.clone());
+        let store = store.clone();
+        async move {
+            if partial_availability {
+                store.finish_loading(&store_config).await?;
+            }
+
+            // wait for store before starting block producer
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            block_producer.await
+        }
+    }));
 
     // wait for block producer before starting rpc
     tokio::time::sleep(Duration::from_secs(1)).await;
-    join_set.spawn(rpc_server::serve(config.rpc));
+    join_set.spawn(component(RPC, rpc_server::serve(config.rpc)));
 
-    // block on all tasks
+    // the block producer shut down to be restarted, see `restart-producer`, is started again while
+    // the store and the rpc keep serving, the node only exits once a component failed
+    let mut block_producer_config = config.block_producer;
     while let Some(res) = join_set.join_next().await {
-        // For now, if one of the components fails, crash the node
-        res.unwrap().unwrap();
+        let (name, result) = res?;
+        match result {
+            Ok(()) if name == BLOCK_PRODUCER => {
+                // its configuration is read again, the previous one being kept if the file is no
+                // longer valid
+                match load_config(config_filepath).extract::<StartCommandConfig>() {
+                    Ok(config) => block_producer_config = config.block_producer,
+                    Err(err) => {
+                        warn!(%err, "Invalid config file, the block producer config is kept")
+                    },
+                }
+                info!("Block producer shut down, starting it again");
+                join_set.spawn(component(
+                    BLOCK_PRODUCER,
+                    serve_block_producer(block_producer_config.clone(), store.clone()),
+                ));
+            },
+            Ok(()) => bail!("The {name} stopped"),
+            Err(err) => return Err(err.context(format!("The {name} failed"))),
+        }
     }
 
     Ok(())
 }
 
+// COMPONENTS
+// ================================================================================================
+
+const STORE: &str = "store";
+const BLOCK_PRODUCER: &str = "block producer";
+const RPC: &str = "rpc";
+
+/// Serves the block producer configured by `config`, which shares `store` if it is embedded.
+async fn serve_block_producer(
+    config: BlockProducerConfig,
+    store: Arc<StoreApi>,
+) -> Result<()> {
+    if config.embedded_store {
+        // the block producer shares the store served to the rpc, so the state is loaded once
+        block_producer_server::serve_embedded(config, store).await
+    } else {
+        block_producer_server::serve(config).await
+    }
+}
+
+/// Runs the component `name`, returning its name along with its result.
+async fn component(
+    name: &'static str,
+    run: impl Future<Output = Result<()>>,
+) -> (&'static str, Result<()>) {
+    (name, run.await)
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
 
     use figment::Jail;
-    use miden_node_block_producer::config::BlockProducerConfig;
-    use miden_node_rpc::config::RpcConfig;
+    use miden_node_block_producer::config::{
+        AdaptiveBatchingConfig, BatchProvingConfig, BatchingConfig, BlockProducerConfig,
+        DashboardConfig, EvictionConfig, IdentityConfig, JournalConfig, LanesConfig,
+        LoadSheddingConfig, MempoolConfig, ProverPriority, QuarantineConfig, ReceiptsConfig,
+        RemoteProverConfig, ReservationsConfig, RestartConfig, TimeAuthorityConfig, TxKernelConfig,
+        TxOrdering, WebhooksConfig,
+    };
+    use miden_node_rpc::config::{AuditConfig, FieldMasks, RpcConfig, UsageConfig};
     use miden_node_store::config::StoreConfig;
-    use miden_node_utils::config::{load_config, Endpoint};
+    use miden_node_utils::config::{load_config, Endpoint, GrpcServerConfig};
 
     use super::StartCommandConfig;
     use crate::NODE_CONFIG_FILE_PATH;
@@ -69,19 +169,45 @@ mod tests {
                 r#"
                     [block_producer]
                     store_url = "http://store:8000"
+                    embedded_store = true
+                    chain_id = 1
+                    prover_threads = 2
+                    prover_priority = "low"
 
                     [block_producer.endpoint]
                     host = "127.0.0.1"
                     port = 8080
 
+                    [block_producer.load_shedding]
+                    max_rss_mb = 4096
+                    max_queued_transactions = 1000
+                    max_ready_batches = 0
+
+                    [block_producer.quarantine]
+                    directory = "quarantine"
+                    max_size_mb = 256
+
+                    [block_producer.lanes]
+                    reserved_consuming_percent = 25
+
+                    [block_producer.receipts]
+
                     [rpc]
                     store_url = "http://store:8000"
                     block_producer_url = "http://block_producer:8001"
+                    chain_id = 1
+                    max_requests_per_second = 100
                     endpoint = { host = "127.0.0.1",  port = 8080 }
+                    grpc = { max_connections = 10000 }
 
                     [store]
-                    database_filepath = "local.sqlite3"
-                    genesis_filepath = "genesis.dat"
+                    data_directory = "data"
+                    chain_id = 1
+                    query_timeout_ms = 5000
+                    max_block_timestamp_skew_s = 60
+                    proposal_timeout_ms = 30000
+                    operator_keys = []
+                    tag_policy = { network = [], local = [], account = [] }
 
                     [store.endpoint]
                     host = "127.0.0.1"
@@ -100,23 +226,88 @@ mod tests {
                             host: "127.0.0.1".to_string(),
                             port: 8080,
                         },
+                        listen: Vec::new(),
                         store_url: "http://store:8000".to_string(),
+                        embedded_store: true,
+                        store_operator_key_file: None,
+                        chain_id: 1,
+                        genesis_hash: None,
+                        prover_threads: 2,
+                        prover_priority: ProverPriority::Low,
+                        load_shedding: LoadSheddingConfig {
+                            max_rss_mb: 4096,
+                            max_queued_transactions: 1000,
+                            max_ready_batches: 0,
+                        },
+                        quarantine: QuarantineConfig {
+                            directory: "quarantine".into(),
+                            max_size_mb: 256,
+                        },
+                        lanes: LanesConfig {
+                            reserved_consuming_percent: 25,
+                        },
+                        reservations: ReservationsConfig::default(),
+                        receipts: ReceiptsConfig { key_file: None },
+                        identity: IdentityConfig::default(),
+                        eviction: EvictionConfig::default(),
+                        tx_kernel: TxKernelConfig::default(),
+                        batching: BatchingConfig::default(),
+                        adaptive_batching: AdaptiveBatchingConfig::default(),
+                        tx_ordering: TxOrdering::default(),
+                        grpc: GrpcServerConfig::default(),
+                        dashboard: DashboardConfig::default(),
+                        webhooks: WebhooksConfig::default(),
+                        journal: JournalConfig::default(),
+                        operator_keys: Vec::new(),
+                        restart: RestartConfig::default(),
+                        mempool: MempoolConfig::default(),
+                        time_authority: TimeAuthorityConfig::default(),
+                        batch_proving: BatchProvingConfig::default(),
+                        remote_prover: RemoteProverConfig::default(),
                     },
                     rpc: RpcConfig {
                         endpoint: Endpoint {
                             host: "127.0.0.1".to_string(),
                             port: 8080,
                         },
+                        listen: Vec::new(),
                         store_url: "http://store:8000".to_string(),
                         block_producer_url: "http://block_producer:8001".to_string(),
+                        chain_id: 1,
+                        genesis_hash: None,
+                        max_requests_per_second: 100,
+                        networks: Vec::new(),
+                        audit: AuditConfig::default(),
+                        usage: UsageConfig::default(),
+                        field_masks: FieldMasks::default(),
+                        operator_keys: Vec::new(),
+                        grpc: GrpcServerConfig {
+                            max_connections: 10000,
+                            ..Default::default()
+                        },
                     },
                     store: StoreConfig {
                         endpoint: Endpoint {
                             host: "127.0.0.1".to_string(),
                             port: 8080,
                         },
-                        database_filepath: "local.sqlite3".into(),
-                        genesis_filepath: "genesis.dat".into()
+                        listen: Vec::new(),
+                        data_directory: "data".into(),
+                        chain_id: 1,
+                        query_timeout_ms: 5000,
+                        max_block_timestamp_skew_s: 60,
+                        proposal_timeout_ms: 30000,
+                        operator_keys: vec![],
+                        tag_policy: Default::default(),
+                        safe_depth: 0,
+                        tenants: Vec::new(),
+                        account_history: Default::default(),
+                        state_commitment_interval: 0,
+                        disk_limits: Default::default(),
+                        note_visibility: Default::default(),
+                        verify_batch_proofs: false,
+                        grpc: GrpcServerConfig::default(),
+                        startup: Default::default(),
                     },
                 }
             );
@@ -1,6 +1,7 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use clap::{Parser, Subcommand};
+use miden_node_block_producer::cli::BatchingArgs;
 
 mod commands;
 
@@ -12,6 +13,8 @@ const NODE_CONFIG_FILE_PATH: &str = "miden-node.toml";
 const DEFAULT_GENESIS_FILE_PATH: &str = "genesis.dat";
 const DEFAULT_GENESIS_INPUTS_PATH: &str = "genesis.toml";
 
+const DEFAULT_DRAIN_TIMEOUT_S: u64 = 300;
+
 // COMMANDS
 // ================================================================================================
 
@@ -28,6 +31,79 @@ pub enum Command {
     Start {
         #[arg(short, long, value_name = "FILE", default_value = NODE_CONFIG_FILE_PATH)]
         config: PathBuf,
+
+        /// Overrides the store's data directory set in the config file
+        #[arg(long, value_name = "DIR")]
+        data_directory: Option<PathBuf>,
+
+        #[command(flatten)]
+        batching: BatchingArgs,
+    },
+
+    /// Manages the config file of the node
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Checks that the node can start with the config, and prints a pass/fail report
+    ///
+    /// The config, the store's database and genesis file, the availability of the addresses to
+    /// listen on, and the connectivity between the components are checked. The command only reads
+    /// the node's files, and fails if any check failed.
+    Doctor {
+        #[arg(short, long, value_name = "FILE", default_value = NODE_CONFIG_FILE_PATH)]
+        config: PathBuf,
+
+        /// Overrides the store's data directory set in the config file
+        #[arg(long, value_name = "DIR")]
+        data_directory: Option<PathBuf>,
+
+        #[command(flatten)]
+        batching: BatchingArgs,
+    },
+
+    /// Stops the block producer without losing its transactions, for its supervisor to restart it
+    ///
+    /// The intake of transactions is paused, then the queue is drained into blocks, or saved to a
+    /// snapshot admitted again once the block producer started. The block producer exits once its
+    /// last block was applied by the store. The admin operations are signed with an operator key
+    /// listed in the block producer's `operator_keys`.
+    RestartProducer {
+        #[arg(short, long, value_name = "FILE", default_value = NODE_CONFIG_FILE_PATH)]
+        config: PathBuf,
+
+        /// File holding the hex encoded seed of the operator key
+        #[arg(long, value_name = "FILE")]
+        operator_key_file: PathBuf,
+
+        /// Saves the queued transactions to the snapshot instead of draining them into blocks
+        #[arg(long)]
+        snapshot: bool,
+
+        /// Time given to the block producer to drain its queue before the restart is aborted, and
+        /// the intake resumed
+        #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_DRAIN_TIMEOUT_S)]
+        drain_timeout_s: u64,
+    },
+
+    /// Applies the blocks of an archive to the store, e.g. to bootstrap it from another node's chain
+    ///
+    /// The archive holds length delimited `ApplyBlockRequest` protobuf messages, in order. The
+    /// blocks already applied by the store are skipped, and the others are applied by runs of up to
+    /// 1000 blocks, each run entirely or not at all. The requests are signed with an operator key
+    /// listed in the store's `operator_keys`.
+    ImportBlocks {
+        #[arg(short, long, value_name = "FILE", default_value = NODE_CONFIG_FILE_PATH)]
+        config: PathBuf,
+
+        /// File holding the hex encoded seed of the operator key
+        #[arg(long, value_name = "FILE")]
+        operator_key_file: PathBuf,
+
+        /// Archive of the blocks to apply
+        #[arg(value_name = "FILE")]
+        archive: PathBuf,
     },
 
     /// Generates a genesis file and associated account files based on a specified genesis input
@@ -47,6 +123,43 @@ pub enum Command {
         /// Generate the output file even if a file already exists
         #[arg(short, long)]
         force: bool,
+
+        /// Timestamp of the genesis block in seconds since the UNIX epoch, overrides the timestamp
+        /// of the inputs file
+        #[arg(long, value_name = "SECONDS")]
+        timestamp: Option<u64>,
+    },
+
+    /// Decodes a file holding one of the node's data structures, and prints it
+    ///
+    /// Proven transactions, genesis files, block headers and note archives are printed with their
+    /// ids, roots and notes, e.g. to debug a rejected transaction or check an exported archive.
+    Inspect {
+        /// Kind of data structure held by the file
+        #[arg(value_enum)]
+        kind: commands::FileKind,
+
+        /// File to decode
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Writes a config file setting every option of the node to its default value
+    ///
+    /// Every option is commented with its documentation, and the optional ones which are unset by
+    /// default are commented out along with an example value. The file is generated from the
+    /// config of the components, so it lists every option of the node's version.
+    Init {
+        /// Write the config file to this location
+        #[arg(short, long, value_name = "FILE", default_value = NODE_CONFIG_FILE_PATH)]
+        output_path: PathBuf,
+
+        /// Overwrite the config file if it already exists
+        #[arg(short, long)]
+        force: bool,
     },
 }
 
@@ -57,11 +170,44 @@ async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match &cli.command {
-        Command::Start { config } => commands::start_node(config).await,
+        Command::Start {
+            config,
+            data_directory,
+            batching,
+        } => commands::start_node(config, data_directory.clone(), batching).await,
+        Command::Config {
+            command: ConfigCommand::Init { output_path, force },
+        } => commands::init_config(output_path, *force),
+        Command::Doctor {
+            config,
+            data_directory,
+            batching,
+        } => commands::doctor(config, data_directory.clone(), batching).await,
+        Command::RestartProducer {
+            config,
+            operator_key_file,
+            snapshot,
+            drain_timeout_s,
+        } => {
+            commands::restart_producer(
+                config,
+                operator_key_file,
+                *snapshot,
+                Duration::from_secs(*drain_timeout_s),
+            )
+            .await
+        },
+        Command::ImportBlocks {
+            config,
+            operator_key_file,
+            archive,
+        } => commands::import_blocks(config, operator_key_file, archive).await,
         Command::MakeGenesis {
             output_path,
             force,
             inputs_path,
-        } => commands::make_genesis(inputs_path, output_path, force),
+            timestamp,
+        } => commands::make_genesis(inputs_path, output_path, force, *timestamp),
+        Command::Inspect { kind, file } => commands::inspect(*kind, file),
     }
 }
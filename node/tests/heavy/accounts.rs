@@ -0,0 +1,260 @@
+//! Accounts of the tests, and the execution and proving of their transactions with `miden-tx`.
+use anyhow::{anyhow, Result};
+use miden_crypto::{
+    dsa::rpo_falcon512::KeyPair, rand::RpoRandomCoin, utils::Serializable, StarkField,
+};
+use miden_lib::{
+    accounts::{faucets::create_basic_fungible_faucet, wallets::create_basic_wallet},
+    notes::create_p2id_note,
+    AuthScheme,
+};
+use miden_objects::{
+    accounts::{Account, AccountDelta, AccountId, AccountType},
+    assembly::{ModuleAst, ProgramAst},
+    assets::{Asset, FungibleAsset, TokenSymbol},
+    notes::{Note, NoteId},
+    transaction::{ChainMmr, InputNote, InputNotes, ProvenTransaction, TransactionInputs},
+    BlockHeader, Felt, Word,
+};
+use miden_tx::{DataStore, DataStoreError, ProvingOptions, TransactionExecutor, TransactionProver};
+
+// TEST ACCOUNT
+// ================================================================================================
+
+/// An account of the genesis block, along with the key authenticating its transactions.
+pub struct TestAccount {
+    pub account: Account,
+    seed: Word,
+    keypair: KeyPair,
+}
+
+impl TestAccount {
+    /// Creates a basic wallet, `n` seeding its id and key.
+    pub fn wallet(n: u8) -> Result<Self> {
+        let keypair = keypair(n)?;
+        let (account, seed) = create_basic_wallet(
+            [n; 32],
+            AuthScheme::RpoFalcon512 {
+                pub_key: keypair.public_key(),
+            },
+            AccountType::RegularAccountImmutableCode,
+        )
+        .map_err(|err| anyhow!("Failed to create the wallet: {err}"))?;
+
+        Ok(Self {
+            account,
+            seed,
+            keypair,
+        })
+    }
+
+    /// Creates a basic fungible faucet, `n` seeding its id and key.
+    pub fn faucet(n: u8) -> Result<Self> {
+        let keypair = keypair(n)?;
+        let (account, seed) = create_basic_fungible_faucet(
+            [n; 32],
+            TokenSymbol::try_from("TST").map_err(|err| anyhow!("Invalid symbol: {err}"))?,
+            8,
+            Felt::from(1_000_000_u32),
+            AuthScheme::RpoFalcon512 {
+                pub_key: keypair.public_key(),
+            },
+        )
+        .map_err(|err| anyhow!("Failed to create the faucet: {err}"))?;
+
+        Ok(Self {
+            account,
+            seed,
+            keypair,
+        })
+    }
+
+    pub fn id(&self) -> AccountId {
+        self.account.id()
+    }
+
+    /// Returns a P2ID note of `amount` tokens of this faucet to `target`, as minted by
+    /// [TestAccount::mint_script].
+    pub fn p2id_note(
+        &self,
+        target: AccountId,
+        amount: u64,
+    ) -> Result<Note> {
+        let asset: Asset = FungibleAsset::new(self.id(), amount)
+            .map_err(|err| anyhow!("Invalid asset: {err}"))?
+            .into();
+        let rng = RpoRandomCoin::new([Felt::new(amount); 4]);
+
+        create_p2id_note(self.id(), target, vec![asset], rng)
+            .map_err(|err| anyhow!("Failed to create the note: {err}"))
+    }
+
+    /// Returns the script of a transaction of this faucet minting `note`, a note of `amount`
+    /// tokens.
+    pub fn mint_script(
+        &self,
+        note: &Note,
+        amount: u64,
+    ) -> String {
+        let recipient = note
+            .recipient()
+            .as_elements()
+            .iter()
+            .map(|felt| felt.as_int().to_string())
+            .collect::<Vec<_>>()
+            .join(".");
+
+        format!(
+            "
+            use.miden::contracts::faucets::basic_fungible->faucet
+            use.miden::contracts::auth::basic->auth_tx
+
+            begin
+                push.{recipient}
+                push.{tag}
+                push.{amount}
+                call.faucet::distribute
+
+                call.auth_tx::auth_tx_rpo_falcon512
+                dropw dropw
+            end
+            ",
+            tag = note.metadata().tag(),
+        )
+    }
+
+    /// Returns the script of a transaction of this wallet consuming its input notes.
+    pub fn consume_script(&self) -> String {
+        "
+        use.miden::contracts::auth::basic->auth_tx
+
+        begin
+            call.auth_tx::auth_tx_rpo_falcon512
+        end
+        "
+        .to_string()
+    }
+
+    /// Executes the transaction `script` of this account against the block `block_header`,
+    /// consuming `input_notes`, and proves it.
+    ///
+    /// `chain_mmr` must hold the chain MMR committed to by the block, and the paths of the blocks
+    /// of `input_notes`.
+    pub fn prove_transaction(
+        &self,
+        block_header: BlockHeader,
+        chain_mmr: ChainMmr,
+        input_notes: Vec<InputNote>,
+        script: &str,
+    ) -> Result<TestTransaction> {
+        let note_ids: Vec<NoteId> = input_notes.iter().map(|note| note.note().id()).collect();
+        let data_store = TestDataStore {
+            account: self.account.clone(),
+            seed: self.account.is_new().then_some(self.seed),
+            block_header,
+            chain_mmr,
+            input_notes,
+        };
+
+        let mut executor = TransactionExecutor::new(data_store);
+        executor
+            .load_account(self.id())
+            .map_err(|err| anyhow!("Failed to load the account: {err}"))?;
+
+        // the falcon key is given to the authentication procedure through the advice map
+        let secret_key: Vec<Felt> =
+            self.keypair.to_bytes().iter().map(|&byte| Felt::from(byte)).collect();
+        let script = ProgramAst::parse(script).map_err(|err| anyhow!("Invalid script: {err}"))?;
+        let script = executor
+            .compile_tx_script(script, vec![(self.keypair.public_key().into(), secret_key)], vec![])
+            .map_err(|err| anyhow!("Failed to compile the script: {err}"))?;
+
+        let executed = executor
+            .execute_transaction(self.id(), block_header.block_num(), &note_ids, Some(script))
+            .map_err(|err| anyhow!("Failed to execute the transaction: {err}"))?;
+        let final_account = executed.final_account().clone();
+        let account_delta = executed.account_delta().clone();
+
+        let proven = TransactionProver::new(ProvingOptions::default())
+            .prove_transaction(executed)
+            .map_err(|err| anyhow!("Failed to prove the transaction: {err}"))?;
+
+        Ok(TestTransaction {
+            proven,
+            final_account,
+            account_delta,
+        })
+    }
+}
+
+fn keypair(n: u8) -> Result<KeyPair> {
+    KeyPair::from_seed(&[n; 40]).map_err(|err| anyhow!("Failed to create the key: {err}"))
+}
+
+// TEST TRANSACTION
+// ================================================================================================
+
+/// A transaction proven by [TestAccount::prove_transaction].
+pub struct TestTransaction {
+    pub proven: ProvenTransaction,
+    pub final_account: Account,
+    account_delta: AccountDelta,
+}
+
+impl TestTransaction {
+    /// Returns the changes made to the account, submitted along with the transaction of a public
+    /// account.
+    pub fn account_delta(&self) -> Option<&AccountDelta> {
+        self.final_account.id().is_on_chain().then_some(&self.account_delta)
+    }
+}
+
+// TEST DATA STORE
+// ================================================================================================
+
+/// Inputs of a single transaction, as read from the node by the test.
+struct TestDataStore {
+    account: Account,
+    seed: Option<Word>,
+    block_header: BlockHeader,
+    chain_mmr: ChainMmr,
+    input_notes: Vec<InputNote>,
+}
+
+impl DataStore for TestDataStore {
+    fn get_transaction_inputs(
+        &self,
+        account_id: AccountId,
+        block_num: u32,
+        _notes: &[NoteId],
+    ) -> Result<TransactionInputs, DataStoreError> {
+        if account_id != self.account.id() {
+            return Err(DataStoreError::AccountNotFound(account_id));
+        }
+        if block_num != self.block_header.block_num() {
+            return Err(DataStoreError::BlockNotFound(block_num));
+        }
+
+        let input_notes = InputNotes::new(self.input_notes.clone())
+            .map_err(|err| DataStoreError::InternalError(err.to_string()))?;
+        TransactionInputs::new(
+            self.account.clone(),
+            self.seed,
+            self.block_header,
+            self.chain_mmr.clone(),
+            input_notes,
+        )
+        .map_err(DataStoreError::InvalidTransactionInput)
+    }
+
+    fn get_account_code(
+        &self,
+        account_id: AccountId,
+    ) -> Result<ModuleAst, DataStoreError> {
+        if account_id != self.account.id() {
+            return Err(DataStoreError::AccountNotFound(account_id));
+        }
+
+        Ok(self.account.code().module().clone())
+    }
+}
@@ -0,0 +1,217 @@
+//! End-to-end tests of the node with genuinely proven transactions.
+//!
+//! The unit tests of the components mock the proofs and the components they talk to, so they miss
+//! the breaks between the components and the transaction kernel. These tests start the
+//! `miden-node` binary, execute and prove transactions with `miden-tx`, and follow them through
+//! the submission, batching, block proving and store, until the notes they create are consumed by
+//! other proven transactions.
+//!
+//! Proving the transactions and the blocks takes minutes, so the tests are only built with the
+//! `heavy-tests` feature, and are ignored unless run explicitly:
+//!
+//! ```sh
+//! cargo test --release -p miden-node --features heavy-tests -- --ignored
+//! ```
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use miden_crypto::{
+    merkle::{MmrPeaks, PartialMmr},
+    StarkField,
+};
+use miden_node_client::{CommittedNote, RpcClient, StateSyncInfo};
+use miden_objects::{
+    notes::{Note, NoteInclusionProof},
+    transaction::{ChainMmr, InputNote},
+};
+
+use crate::{accounts::TestAccount, node::TestNode};
+
+mod accounts;
+mod node;
+
+/// Tokens minted by the faucet.
+const MINTED_AMOUNT: u64 = 100;
+
+/// Time given to the node to include a submitted transaction in a block.
+const BLOCK_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Interval between two polls of the node.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Tests that a note minted by a faucet is synced by its target wallet, and consumed by it.
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "proves transactions for minutes, run with `--features heavy-tests -- --ignored`"]
+async fn test_minted_note_is_consumed() -> Result<()> {
+    let faucet = TestAccount::faucet(1)?;
+    let wallet = TestAccount::wallet(2)?;
+    let node = TestNode::start(vec![faucet.account.clone(), wallet.account.clone()])?;
+    let client = node.connect().await?;
+
+    let genesis = client
+        .get_block_header_by_number(Some(0))
+        .await?
+        .context("Missing genesis block")?;
+
+    // the faucet mints the note against the genesis block, whose chain MMR is empty
+    let note = faucet.p2id_note(wallet.id(), MINTED_AMOUNT)?;
+    let empty_mmr = PartialMmr::from_peaks(
+        MmrPeaks::new(0, Vec::new()).map_err(|err| anyhow!("Invalid peaks: {err}"))?,
+    );
+    let mint = faucet.prove_transaction(
+        genesis,
+        chain_mmr(empty_mmr)?,
+        Vec::new(),
+        &faucet.mint_script(&note, MINTED_AMOUNT),
+    )?;
+    client
+        .submit_proven_transaction(&mint.proven, mint.account_delta(), &[], &[])
+        .await?;
+
+    // the wallet syncs the note, along with the chain MMR up to its block
+    let (sync, committed) = wait_for_note(&client, &note).await?;
+    assert!(!committed.private);
+    let block_header = sync.block_header;
+    let mut partial_mmr = PartialMmr::from_peaks(
+        MmrPeaks::new(1, vec![genesis.hash()]).map_err(|err| anyhow!("Invalid peaks: {err}"))?,
+    );
+    partial_mmr
+        .apply(sync.mmr_delta)
+        .map_err(|err| anyhow!("Failed to apply the MMR delta: {err}"))?;
+
+    // and consumes it against the block which created it
+    let proof = NoteInclusionProof::new(
+        block_header.block_num(),
+        block_header.sub_hash(),
+        block_header.note_root(),
+        committed.note_index.into(),
+        committed.merkle_path,
+    )
+    .map_err(|err| anyhow!("Invalid note inclusion proof: {err}"))?;
+    let consume = wallet.prove_transaction(
+        block_header,
+        chain_mmr(partial_mmr)?,
+        vec![InputNote::new(note.clone(), proof)],
+        &wallet.consume_script(),
+    )?;
+    client
+        .submit_proven_transaction(
+            &consume.proven,
+            consume.account_delta(),
+            std::slice::from_ref(&note),
+            &[],
+        )
+        .await?;
+
+    // the note's nullifier is recorded, and so is the new state of the wallet
+    let block_num = wait_for_nullifier(&client, &note).await?;
+    assert!(block_num > block_header.block_num());
+
+    let sync = client
+        .sync_state(block_num - 1, &[wallet.id()], &[], &[], &[note.id().inner()])
+        .await?;
+    let update = sync
+        .account_hash_updates
+        .iter()
+        .find(|update| update.account_id == wallet.id())
+        .context("Missing update of the wallet")?;
+    assert_eq!(update.account_hash, consume.final_account.hash());
+    assert_eq!(update.block_num, block_num);
+
+    // the note was submitted along with the transaction, the store linked it to its nullifier
+    let consumed = sync
+        .consumed_notes
+        .iter()
+        .find(|consumed| consumed.note_hash == note.id().inner())
+        .context("Missing consumed note")?;
+    assert_eq!(consumed.nullifier, note.nullifier().inner());
+    assert_eq!(consumed.block_num, block_num);
+
+    Ok(())
+}
+
+/// Tests that a note submitted as private is synced as private by the clients tracking its tag.
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "proves transactions for minutes, run with `--features heavy-tests -- --ignored`"]
+async fn test_private_note_is_synced_as_private() -> Result<()> {
+    let faucet = TestAccount::faucet(1)?;
+    let wallet = TestAccount::wallet(2)?;
+    let node = TestNode::start(vec![faucet.account.clone(), wallet.account.clone()])?;
+    let client = node.connect().await?;
+
+    let genesis = client
+        .get_block_header_by_number(Some(0))
+        .await?
+        .context("Missing genesis block")?;
+
+    let note = faucet.p2id_note(wallet.id(), MINTED_AMOUNT)?;
+    let empty_mmr = PartialMmr::from_peaks(
+        MmrPeaks::new(0, Vec::new()).map_err(|err| anyhow!("Invalid peaks: {err}"))?,
+    );
+    let mint = faucet.prove_transaction(
+        genesis,
+        chain_mmr(empty_mmr)?,
+        Vec::new(),
+        &faucet.mint_script(&note, MINTED_AMOUNT),
+    )?;
+    client
+        .submit_proven_transaction(&mint.proven, mint.account_delta(), &[], &[note.id().inner()])
+        .await?;
+
+    // the visibility submitted along with the transaction went through the batch and the block
+    let (_, committed) = wait_for_note(&client, &note).await?;
+    assert!(committed.private);
+
+    Ok(())
+}
+
+// HELPERS
+// ================================================================================================
+
+fn chain_mmr(partial_mmr: PartialMmr) -> Result<ChainMmr> {
+    ChainMmr::new(partial_mmr, Vec::new()).map_err(|err| anyhow!("Invalid chain MMR: {err}"))
+}
+
+/// Waits for `note` to be created, and returns the sync from the genesis block up to its block.
+async fn wait_for_note(
+    client: &RpcClient,
+    note: &Note,
+) -> Result<(StateSyncInfo, CommittedNote)> {
+    let tag = note.metadata().tag().as_int();
+    let note_hash = note.hash();
+
+    let started_at = Instant::now();
+    while started_at.elapsed() < BLOCK_TIMEOUT {
+        let sync = client.sync_state(0, &[], &[(tag >> 48) as u32], &[], &[]).await?;
+        if let Some(committed) =
+            sync.notes.iter().find(|committed| committed.note_hash == note_hash)
+        {
+            let committed = committed.clone();
+            return Ok((sync, committed));
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    bail!("The note {note_hash} wasn't created within {BLOCK_TIMEOUT:?}")
+}
+
+/// Waits for `note` to be consumed, and returns the block consuming it.
+async fn wait_for_nullifier(
+    client: &RpcClient,
+    note: &Note,
+) -> Result<u32> {
+    let nullifier = note.nullifier().inner();
+
+    let started_at = Instant::now();
+    while started_at.elapsed() < BLOCK_TIMEOUT {
+        let block_nums = client.check_nullifiers_without_proofs(&[nullifier]).await?;
+        if block_nums[0] != 0 {
+            return Ok(block_nums[0]);
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    bail!("The note {} wasn't consumed within {BLOCK_TIMEOUT:?}", note.hash())
+}
@@ -0,0 +1,135 @@
+//! A node running the `miden-node` binary, with its own data directory and ports.
+use std::{
+    fs,
+    net::TcpListener,
+    path::PathBuf,
+    process::{Child, Command},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use miden_node_client::{RpcClient, RpcClientOptions};
+use miden_node_store::{data_directory::DataDirectory, genesis::GenesisState};
+use miden_objects::{accounts::Account, utils::Serializable};
+
+/// Chain id of the node's network.
+pub const CHAIN_ID: u32 = 1;
+
+/// Time given to the node to start serving its RPC.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A node started from a genesis block holding the given accounts, stopped on drop.
+pub struct TestNode {
+    process: Child,
+    directory: PathBuf,
+    rpc_port: u16,
+}
+
+impl TestNode {
+    /// Starts a node whose genesis block holds `accounts`.
+    pub fn start(accounts: Vec<Account>) -> Result<Self> {
+        let directory =
+            std::env::temp_dir().join(format!("miden-node-heavy-{}", std::process::id()));
+        let data_directory = DataDirectory::new(directory.join("data"));
+        fs::create_dir_all(data_directory.root())?;
+
+        // the genesis file is read from the store's data directory
+        let genesis = GenesisState::new(accounts, 1, unix_timestamp(), CHAIN_ID);
+        fs::write(data_directory.genesis_filepath(), genesis.to_bytes())?;
+
+        let [store_port, block_producer_port, rpc_port] =
+            [free_port()?, free_port()?, free_port()?];
+        let config_filepath = directory.join("miden-node.toml");
+        fs::write(
+            &config_filepath,
+            format!(
+                r#"
+                    [block_producer]
+                    endpoint = {{ host = "127.0.0.1", port = {block_producer_port} }}
+                    store_url = "http://127.0.0.1:{store_port}"
+                    chain_id = {CHAIN_ID}
+                    prover_threads = 1
+                    prover_priority = "normal"
+                    load_shedding = {{ max_rss_mb = 0, max_queued_transactions = 0, max_ready_batches = 0 }}
+                    quarantine = {{ directory = "quarantine", max_size_mb = 0 }}
+                    lanes = {{ reserved_consuming_percent = 0 }}
+                    receipts = {{}}
+
+                    [rpc]
+                    endpoint = {{ host = "127.0.0.1", port = {rpc_port} }}
+                    store_url = "http://127.0.0.1:{store_port}"
+                    block_producer_url = "http://127.0.0.1:{block_producer_port}"
+                    chain_id = {CHAIN_ID}
+                    max_requests_per_second = 0
+
+                    [store]
+                    endpoint = {{ host = "127.0.0.1", port = {store_port} }}
+                    data_directory = "data"
+                    chain_id = {CHAIN_ID}
+                    query_timeout_ms = 5000
+                    max_block_timestamp_skew_s = 60
+                    proposal_timeout_ms = 30000
+                    operator_keys = []
+                    tag_policy = {{ network = [], local = [], account = [] }}
+                "#
+            ),
+        )?;
+
+        let process = Command::new(env!("CARGO_BIN_EXE_miden-node"))
+            .arg("start")
+            .arg("--config")
+            .arg(&config_filepath)
+            .current_dir(&directory)
+            .spawn()
+            .context("Failed to start the node")?;
+
+        Ok(Self {
+            process,
+            directory,
+            rpc_port,
+        })
+    }
+
+    /// Connects to the RPC of the node, waiting for the node to start.
+    pub async fn connect(&self) -> Result<RpcClient> {
+        let url = format!("http://127.0.0.1:{}", self.rpc_port);
+        let started_at = Instant::now();
+        loop {
+            match RpcClient::connect(url.clone(), CHAIN_ID, client_options()).await {
+                Ok(client) => return Ok(client),
+                Err(err) if started_at.elapsed() > STARTUP_TIMEOUT => {
+                    return Err(anyhow!("The node didn't start: {err}"))
+                },
+                Err(_) => tokio::time::sleep(Duration::from_millis(500)).await,
+            }
+        }
+    }
+}
+
+impl Drop for TestNode {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
+
+/// Waits longer for the responses than the default options, the node proves blocks meanwhile.
+fn client_options() -> RpcClientOptions {
+    RpcClientOptions {
+        timeout: Some(Duration::from_secs(60)),
+        ..Default::default()
+    }
+}
+
+/// Returns a port no other process listens on.
+fn free_port() -> Result<u16> {
+    Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("time before the UNIX epoch")
+        .as_secs()
+}
@@ -15,6 +15,7 @@ fn main() -> miette::Result<()> {
     // Compile the proto file for all servers APIs
     let protos = &[
         proto_dir.join("block_producer.proto"),
+        proto_dir.join("block_prover.proto"),
         proto_dir.join("store.proto"),
         proto_dir.join("rpc.proto"),
     ];
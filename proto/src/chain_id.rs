@@ -0,0 +1,105 @@
+//! Propagation of the chain id between the node's components.
+//!
+//! Every request sent between components carries the chain id of the sender as gRPC metadata, the
+//! receiving component rejects requests for a different chain. This prevents components of
+//! different networks (e.g. devnet and testnet) from being wired together by mistake.
+use tonic::{metadata::MetadataValue, service::Interceptor, Request, Status};
+
+/// The gRPC metadata key under which the chain id is transmitted.
+pub const CHAIN_ID_METADATA_KEY: &str = "miden-chain-id";
+
+/// Reads the chain id from the metadata of `request`, if present.
+///
+/// Returns an error if the metadata value is not a valid chain id.
+pub fn read_chain_id<T>(request: &Request<T>) -> Result<Option<u32>, Status> {
+    request
+        .metadata()
+        .get(CHAIN_ID_METADATA_KEY)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .ok_or(Status::invalid_argument("Malformed chain id metadata"))
+        })
+        .transpose()
+}
+
+/// Client side interceptor which attaches the chain id to all outgoing requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainIdInterceptor {
+    chain_id: u32,
+}
+
+impl ChainIdInterceptor {
+    pub fn new(chain_id: u32) -> Self {
+        Self { chain_id }
+    }
+}
+
+impl Interceptor for ChainIdInterceptor {
+    fn call(
+        &mut self,
+        mut request: Request<()>,
+    ) -> Result<Request<()>, Status> {
+        request
+            .metadata_mut()
+            .insert(CHAIN_ID_METADATA_KEY, MetadataValue::from(self.chain_id));
+        Ok(request)
+    }
+}
+
+/// Server side interceptor which rejects requests sent for a different chain, or without chain id
+/// metadata.
+///
+/// Ad-hoc tooling, e.g. `grpcurl`, must set the [CHAIN_ID_METADATA_KEY] header as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainIdValidator {
+    chain_id: u32,
+}
+
+impl ChainIdValidator {
+    pub fn new(chain_id: u32) -> Self {
+        Self { chain_id }
+    }
+}
+
+impl Interceptor for ChainIdValidator {
+    fn call(
+        &mut self,
+        request: Request<()>,
+    ) -> Result<Request<()>, Status> {
+        match read_chain_id(&request)? {
+            Some(chain_id) if chain_id == self.chain_id => Ok(request),
+            Some(chain_id) => Err(Status::failed_precondition(format!(
+                "Chain id mismatch, expected {}, got {chain_id}",
+                self.chain_id
+            ))),
+            None => Err(Status::failed_precondition(format!(
+                "Missing chain id, expected {} in the {CHAIN_ID_METADATA_KEY} metadata",
+                self.chain_id
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tonic::{service::Interceptor, Request};
+
+    use super::{read_chain_id, ChainIdInterceptor, ChainIdValidator};
+
+    #[test]
+    fn test_chain_id_round_trip() {
+        let request = ChainIdInterceptor::new(7).call(Request::new(())).unwrap();
+        assert_eq!(read_chain_id(&request).unwrap(), Some(7));
+
+        assert!(ChainIdValidator::new(7).call(request).is_ok());
+
+        let request = ChainIdInterceptor::new(8).call(Request::new(())).unwrap();
+        assert!(ChainIdValidator::new(7).call(request).is_err());
+
+        // requests without a chain id are rejected
+        assert!(ChainIdValidator::new(7).call(Request::new(())).is_err());
+    }
+}
@@ -1,17 +1,32 @@
+//! Conversions between the protobuf messages and the [miden_objects] types.
+//!
+//! Every message is converted from its domain type with [From], and back with [TryFrom], failing
+//! with a [ParseError](errors::ParseError) on missing fields or values the domain type can't hold
+//! rather than silently altering them.
+
 use miden_crypto::{
+    dsa::rpo_falcon512::Signature,
     merkle::{MerklePath, MmrDelta, MmrPeaks, TieredSmtProof},
+    utils::{Deserializable, Serializable},
     Felt, FieldElement, StarkField, Word,
 };
+use miden_node_utils::tag_policy::{TagPolicy, TagRange};
 use miden_objects::{
-    accounts::AccountId,
-    notes::{NoteEnvelope, NoteId, Nullifier},
-    BlockHeader, Digest as RpoDigest,
+    accounts::{AccountDelta, AccountId, AccountStorageDelta, AccountVaultDelta},
+    assets::Asset,
+    notes::{NoteEnvelope, NoteId, NoteMetadata, Nullifier},
+    transaction::TransactionId,
+    BlockHeader, Digest as RpoDigest, ACCOUNT_TREE_DEPTH,
 };
 
 use crate::{
     account, block_header,
     digest::{self, Digest},
-    domain::{AccountInputRecord, BlockInputs, NullifierInputRecord},
+    domain::{
+        AccountHashUpdate, AccountInputRecord, BlockInputs, BlockSignature, CommittedNote,
+        ConsumedNoteUpdate, MerkleMultiproof, NodeInfo, NullifierInputRecord, NullifierUpdate,
+        PartnerSignature, SubmissionReceipt, TimestampAttestation,
+    },
     errors, merkle, mmr, note, requests, responses, tsmt,
 };
 
@@ -174,7 +189,7 @@ impl TryFrom<block_header::BlockHeader> for BlockHeader {
             value.batch_root.ok_or(errors::ParseError::ProtobufMissingData)?.try_into()?,
             value.proof_hash.ok_or(errors::ParseError::ProtobufMissingData)?.try_into()?,
             value.version.into(),
-            value.timestamp.into(),
+            felt_from_u64(value.timestamp)?,
         ))
     }
 }
@@ -244,10 +259,41 @@ impl TryFrom<merkle::MerklePath> for MerklePath {
     type Error = errors::ParseError;
 
     fn try_from(merkle_path: merkle::MerklePath) -> Result<Self, Self::Error> {
+        // the depth of a merkle path is a `u8`
+        if merkle_path.siblings.len() > u8::MAX as usize {
+            return Err(errors::ParseError::MerklePathTooDeep(merkle_path.siblings.len()));
+        }
+
         merkle_path.siblings.into_iter().map(|v| v.try_into()).collect()
     }
 }
 
+impl From<MerkleMultiproof> for merkle::MerkleMultiproof {
+    fn from(value: MerkleMultiproof) -> Self {
+        Self {
+            nodes: convert(value.nodes),
+            depth: value.depth.into(),
+        }
+    }
+}
+
+impl TryFrom<merkle::MerkleMultiproof> for MerkleMultiproof {
+    type Error = errors::ParseError;
+
+    fn try_from(multiproof: merkle::MerkleMultiproof) -> Result<Self, Self::Error> {
+        // the multiproofs are of the account tree, the paths are rebuilt by shifting the 64 bit
+        // leaf indices by up to the depth
+        if multiproof.depth != ACCOUNT_TREE_DEPTH as u32 {
+            return Err(errors::ParseError::InvalidMultiproofDepth(multiproof.depth));
+        }
+
+        Ok(Self {
+            depth: ACCOUNT_TREE_DEPTH,
+            nodes: try_convert(multiproof.nodes)?,
+        })
+    }
+}
+
 impl From<note::Note> for note::NoteSyncRecord {
     fn from(value: note::Note) -> Self {
         Self {
@@ -256,6 +302,38 @@ impl From<note::Note> for note::NoteSyncRecord {
             sender: value.sender,
             tag: value.tag,
             merkle_path: value.merkle_path,
+            private: value.private,
+        }
+    }
+}
+
+impl TryFrom<note::NoteSyncRecord> for CommittedNote {
+    type Error = errors::ParseError;
+
+    fn try_from(note: note::NoteSyncRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            note_index: note.note_index,
+            note_hash: note.note_hash.ok_or(errors::ParseError::ProtobufMissingData)?.try_into()?,
+            sender: account::AccountId::from(note.sender).try_into()?,
+            tag: note.tag,
+            merkle_path: note
+                .merkle_path
+                .ok_or(errors::ParseError::ProtobufMissingData)?
+                .try_into()?,
+            private: note.private,
+        })
+    }
+}
+
+impl From<CommittedNote> for note::NoteSyncRecord {
+    fn from(note: CommittedNote) -> Self {
+        Self {
+            note_index: note.note_index,
+            note_hash: Some(note.note_hash.into()),
+            sender: note.sender.into(),
+            tag: note.tag,
+            merkle_path: Some(note.merkle_path.into()),
+            private: note.private,
         }
     }
 }
@@ -284,7 +362,99 @@ impl TryFrom<account::AccountId> for AccountId {
     type Error = errors::ParseError;
 
     fn try_from(account_id: account::AccountId) -> Result<Self, Self::Error> {
-        account_id.id.try_into().map_err(|_| errors::ParseError::NotAValidFelt)
+        account_id
+            .id
+            .try_into()
+            .map_err(|err| errors::ParseError::InvalidAccountId(err.to_string()))
+    }
+}
+
+impl From<&AccountDelta> for account::AccountDelta {
+    fn from(delta: &AccountDelta) -> Self {
+        let storage = delta.storage();
+        let vault = delta.vault();
+        let asset_digest = |asset: &Asset| digest::Digest::from(Word::from(*asset));
+
+        Self {
+            storage: Some(account::AccountStorageDelta {
+                cleared_slots: storage.cleared_items.iter().map(|&index| index.into()).collect(),
+                updated_slots: storage
+                    .updated_items
+                    .iter()
+                    .map(|(index, value)| account::StorageSlotUpdate {
+                        index: (*index).into(),
+                        value: Some(value.into()),
+                    })
+                    .collect(),
+            }),
+            vault: Some(account::AccountVaultDelta {
+                added_assets: vault.added_assets.iter().map(asset_digest).collect(),
+                removed_assets: vault.removed_assets.iter().map(asset_digest).collect(),
+            }),
+            nonce: delta.nonce().map(|nonce| nonce.as_int()),
+        }
+    }
+}
+
+impl TryFrom<account::AccountDelta> for AccountDelta {
+    type Error = errors::ParseError;
+
+    fn try_from(delta: account::AccountDelta) -> Result<Self, Self::Error> {
+        let slot_index = |index: u32| {
+            u8::try_from(index).map_err(|_| {
+                errors::ParseError::InvalidAccountDelta(format!(
+                    "storage slot index {index} is out of range"
+                ))
+            })
+        };
+        let asset = |digest: digest::Digest| {
+            let word: Word = digest.try_into()?;
+            Asset::try_from(word).map_err(|err| {
+                errors::ParseError::InvalidAccountDelta(format!("invalid asset: {err}"))
+            })
+        };
+
+        let storage = delta.storage.unwrap_or_default();
+        let storage = AccountStorageDelta {
+            cleared_items: storage
+                .cleared_slots
+                .into_iter()
+                .map(slot_index)
+                .collect::<Result<_, _>>()?,
+            updated_items: storage
+                .updated_slots
+                .into_iter()
+                .map(|update| {
+                    let value: Word =
+                        update.value.ok_or(errors::ParseError::ProtobufMissingData)?.try_into()?;
+                    Ok((slot_index(update.index)?, value))
+                })
+                .collect::<Result<_, Self::Error>>()?,
+        };
+
+        let vault = delta.vault.unwrap_or_default();
+        let vault = AccountVaultDelta {
+            added_assets: vault.added_assets.into_iter().map(asset).collect::<Result<_, _>>()?,
+            removed_assets: vault
+                .removed_assets
+                .into_iter()
+                .map(asset)
+                .collect::<Result<_, _>>()?,
+        };
+
+        let nonce = delta
+            .nonce
+            .map(|nonce| {
+                if nonce < <Felt as StarkField>::MODULUS {
+                    Ok(Felt::new(nonce))
+                } else {
+                    Err(errors::ParseError::NotAValidFelt)
+                }
+            })
+            .transpose()?;
+
+        AccountDelta::new(storage, vault, nonce)
+            .map_err(|err| errors::ParseError::InvalidAccountDelta(err.to_string()))
     }
 }
 
@@ -357,24 +527,319 @@ impl TryFrom<responses::GetBlockInputsResponse> for BlockInputs {
             .map_err(Self::Error::MmrPeaksError)?
         };
 
+        let account_states = match get_block_inputs.account_proofs {
+            // the records' paths are rebuilt from the multiproof
+            Some(account_proofs) => {
+                let multiproof: MerkleMultiproof = account_proofs.try_into()?;
+                let accounts = get_block_inputs
+                    .account_states
+                    .into_iter()
+                    .map(|record| {
+                        let account_id: AccountId = record
+                            .account_id
+                            .ok_or(errors::ParseError::ProtobufMissingData)?
+                            .try_into()?;
+                        let account_hash: RpoDigest = record
+                            .account_hash
+                            .ok_or(errors::ParseError::ProtobufMissingData)?
+                            .try_into()?;
+                        Ok((account_id, account_hash))
+                    })
+                    .collect::<Result<Vec<_>, Self::Error>>()?;
+
+                let leaves: Vec<(u64, RpoDigest)> = accounts
+                    .iter()
+                    .map(|&(account_id, account_hash)| (account_id.into(), account_hash))
+                    .collect();
+                let proofs = multiproof.into_paths(&leaves)?;
+
+                accounts
+                    .into_iter()
+                    .zip(proofs)
+                    .map(|((account_id, account_hash), proof)| AccountInputRecord {
+                        account_id,
+                        account_hash,
+                        proof,
+                    })
+                    .collect()
+            },
+            None => try_convert(get_block_inputs.account_states)?,
+        };
+
         Ok(Self {
             block_header,
             chain_peaks,
-            account_states: try_convert(get_block_inputs.account_states)?,
+            account_states,
             nullifiers: try_convert(get_block_inputs.nullifiers)?,
         })
     }
 }
 
+impl TryFrom<responses::GetNodeInfoResponse> for NodeInfo {
+    type Error = errors::ParseError;
+
+    fn try_from(node_info: responses::GetNodeInfoResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            protocol_version: node_info.protocol_version,
+            genesis_hash: node_info
+                .genesis_hash
+                .ok_or(errors::ParseError::ProtobufMissingData)?
+                .try_into()?,
+            version: node_info.version,
+            chain_id: node_info.chain_id,
+            tag_policy: node_info.tag_policy.map(Into::into).unwrap_or_default(),
+        })
+    }
+}
+
+impl From<&SubmissionReceipt> for responses::SubmissionReceipt {
+    fn from(receipt: &SubmissionReceipt) -> Self {
+        Self {
+            tx_id: Some(receipt.tx_id.into()),
+            received_at: receipt.received_at,
+            queue_position: receipt.queue_position,
+            node_key: Some(receipt.node_key.into()),
+            signature: receipt.signature.to_bytes(),
+        }
+    }
+}
+
+impl TryFrom<responses::SubmissionReceipt> for SubmissionReceipt {
+    type Error = errors::ParseError;
+
+    fn try_from(receipt: responses::SubmissionReceipt) -> Result<Self, Self::Error> {
+        Ok(Self {
+            tx_id: receipt.tx_id.ok_or(errors::ParseError::ProtobufMissingData)?.try_into()?,
+            received_at: receipt.received_at,
+            queue_position: receipt.queue_position,
+            node_key: receipt
+                .node_key
+                .ok_or(errors::ParseError::ProtobufMissingData)?
+                .try_into()?,
+            signature: Signature::read_from_bytes(&receipt.signature)
+                .map_err(|_| errors::ParseError::InvalidSignature)?,
+        })
+    }
+}
+
+impl From<&BlockSignature> for block_header::BlockSignature {
+    fn from(signature: &BlockSignature) -> Self {
+        Self {
+            producer_key: Some(signature.producer_key.into()),
+            signature: signature.signature.to_bytes(),
+        }
+    }
+}
+
+impl TryFrom<&block_header::BlockSignature> for BlockSignature {
+    type Error = errors::ParseError;
+
+    fn try_from(signature: &block_header::BlockSignature) -> Result<Self, Self::Error> {
+        Ok(Self {
+            producer_key: signature
+                .producer_key
+                .as_ref()
+                .ok_or(errors::ParseError::ProtobufMissingData)?
+                .try_into()?,
+            signature: Signature::read_from_bytes(&signature.signature)
+                .map_err(|_| errors::ParseError::InvalidSignature)?,
+        })
+    }
+}
+
+impl From<&TimestampAttestation> for block_header::TimestampAttestation {
+    fn from(attestation: &TimestampAttestation) -> Self {
+        Self {
+            authority_key: Some(attestation.authority_key.into()),
+            timestamp: attestation.timestamp,
+            signature: attestation.signature.to_bytes(),
+        }
+    }
+}
+
+impl TryFrom<&block_header::TimestampAttestation> for TimestampAttestation {
+    type Error = errors::ParseError;
+
+    fn try_from(attestation: &block_header::TimestampAttestation) -> Result<Self, Self::Error> {
+        Ok(Self {
+            authority_key: attestation
+                .authority_key
+                .as_ref()
+                .ok_or(errors::ParseError::ProtobufMissingData)?
+                .try_into()?,
+            timestamp: attestation.timestamp,
+            signature: Signature::read_from_bytes(&attestation.signature)
+                .map_err(|_| errors::ParseError::InvalidSignature)?,
+        })
+    }
+}
+
+impl From<&PartnerSignature> for requests::PartnerSignature {
+    fn from(signature: &PartnerSignature) -> Self {
+        Self {
+            partner_key: Some(signature.partner_key.into()),
+            signature: signature.signature.to_bytes(),
+        }
+    }
+}
+
+impl TryFrom<&requests::PartnerSignature> for PartnerSignature {
+    type Error = errors::ParseError;
+
+    fn try_from(signature: &requests::PartnerSignature) -> Result<Self, Self::Error> {
+        Ok(Self {
+            partner_key: signature
+                .partner_key
+                .as_ref()
+                .ok_or(errors::ParseError::ProtobufMissingData)?
+                .try_into()?,
+            signature: Signature::read_from_bytes(&signature.signature)
+                .map_err(|_| errors::ParseError::InvalidSignature)?,
+        })
+    }
+}
+
+impl From<&TagPolicy> for note::NoteTagPolicy {
+    fn from(policy: &TagPolicy) -> Self {
+        let convert = |ranges: &[TagRange]| {
+            ranges
+                .iter()
+                .map(|range| note::NoteTagRange {
+                    start: range.start,
+                    end: range.end,
+                })
+                .collect()
+        };
+
+        Self {
+            network: convert(&policy.network),
+            local: convert(&policy.local),
+            account: convert(&policy.account),
+        }
+    }
+}
+
+impl From<note::NoteTagPolicy> for TagPolicy {
+    fn from(policy: note::NoteTagPolicy) -> Self {
+        let convert = |ranges: Vec<note::NoteTagRange>| {
+            ranges
+                .into_iter()
+                .map(|range| TagRange {
+                    start: range.start,
+                    end: range.end,
+                })
+                .collect()
+        };
+
+        Self {
+            network: convert(policy.network),
+            local: convert(policy.local),
+            account: convert(policy.account),
+        }
+    }
+}
+
 impl From<(AccountId, RpoDigest)> for requests::AccountUpdate {
     fn from((account_id, account_hash): (AccountId, RpoDigest)) -> Self {
         Self {
             account_id: Some(account_id.into()),
             account_hash: Some(account_hash.into()),
+            details: Vec::new(),
+        }
+    }
+}
+
+impl TryFrom<requests::AccountUpdate> for (AccountId, RpoDigest) {
+    type Error = errors::ParseError;
+
+    fn try_from(update: requests::AccountUpdate) -> Result<Self, Self::Error> {
+        Ok((
+            update.account_id.ok_or(errors::ParseError::ProtobufMissingData)?.try_into()?,
+            update.account_hash.ok_or(errors::ParseError::ProtobufMissingData)?.try_into()?,
+        ))
+    }
+}
+
+impl TryFrom<responses::AccountHashUpdate> for AccountHashUpdate {
+    type Error = errors::ParseError;
+
+    fn try_from(update: responses::AccountHashUpdate) -> Result<Self, Self::Error> {
+        Ok(Self {
+            account_id: update
+                .account_id
+                .ok_or(errors::ParseError::ProtobufMissingData)?
+                .try_into()?,
+            account_hash: update
+                .account_hash
+                .ok_or(errors::ParseError::ProtobufMissingData)?
+                .try_into()?,
+            block_num: update.block_num,
+        })
+    }
+}
+
+impl From<AccountHashUpdate> for responses::AccountHashUpdate {
+    fn from(update: AccountHashUpdate) -> Self {
+        Self {
+            account_id: Some(update.account_id.into()),
+            account_hash: Some(update.account_hash.into()),
+            block_num: update.block_num,
+        }
+    }
+}
+
+impl TryFrom<responses::NullifierUpdate> for NullifierUpdate {
+    type Error = errors::ParseError;
+
+    fn try_from(update: responses::NullifierUpdate) -> Result<Self, Self::Error> {
+        Ok(Self {
+            nullifier: update
+                .nullifier
+                .ok_or(errors::ParseError::ProtobufMissingData)?
+                .try_into()?,
+            block_num: update.block_num,
+        })
+    }
+}
+
+impl From<NullifierUpdate> for responses::NullifierUpdate {
+    fn from(update: NullifierUpdate) -> Self {
+        Self {
+            nullifier: Some(update.nullifier.into()),
+            block_num: update.block_num,
         }
     }
 }
 
+impl TryFrom<responses::ConsumedNoteUpdate> for ConsumedNoteUpdate {
+    type Error = errors::ParseError;
+
+    fn try_from(update: responses::ConsumedNoteUpdate) -> Result<Self, Self::Error> {
+        Ok(Self {
+            note_hash: update
+                .note_hash
+                .ok_or(errors::ParseError::ProtobufMissingData)?
+                .try_into()?,
+            nullifier: update
+                .nullifier
+                .ok_or(errors::ParseError::ProtobufMissingData)?
+                .try_into()?,
+            block_num: update.block_num,
+        })
+    }
+}
+
+impl From<ConsumedNoteUpdate> for responses::ConsumedNoteUpdate {
+    fn from(update: ConsumedNoteUpdate) -> Self {
+        Self {
+            note_hash: Some(update.note_hash.into()),
+            nullifier: Some(update.nullifier.into()),
+            block_num: update.block_num,
+        }
+    }
+}
+
+/// The note is public, its visibility isn't part of the [NoteEnvelope].
 impl From<(u64, NoteEnvelope)> for note::NoteCreated {
     fn from((note_idx, note): (u64, NoteEnvelope)) -> Self {
         Self {
@@ -382,10 +847,24 @@ impl From<(u64, NoteEnvelope)> for note::NoteCreated {
             sender: note.metadata().sender().into(),
             tag: note.metadata().tag().into(),
             note_index: note_idx as u32,
+            private: false,
         }
     }
 }
 
+impl TryFrom<note::NoteCreated> for (u64, NoteEnvelope) {
+    type Error = errors::ParseError;
+
+    fn try_from(note: note::NoteCreated) -> Result<Self, Self::Error> {
+        let note_id: RpoDigest =
+            note.note_hash.ok_or(errors::ParseError::ProtobufMissingData)?.try_into()?;
+        let sender = account::AccountId::from(note.sender).try_into()?;
+        let metadata = NoteMetadata::new(sender, felt_from_u64(note.tag)?);
+
+        Ok((note.note_index.into(), NoteEnvelope::new(note_id.into(), metadata)))
+    }
+}
+
 impl From<&Nullifier> for Digest {
     fn from(value: &Nullifier) -> Self {
         (*value).inner().into()
@@ -410,6 +889,42 @@ impl From<NoteId> for Digest {
     }
 }
 
+impl From<&TransactionId> for Digest {
+    fn from(value: &TransactionId) -> Self {
+        (*value).inner().into()
+    }
+}
+
+impl From<TransactionId> for Digest {
+    fn from(value: TransactionId) -> Self {
+        value.inner().into()
+    }
+}
+
+impl TryFrom<Digest> for Nullifier {
+    type Error = errors::ParseError;
+
+    fn try_from(value: Digest) -> Result<Self, Self::Error> {
+        Ok(RpoDigest::try_from(value)?.into())
+    }
+}
+
+impl TryFrom<Digest> for NoteId {
+    type Error = errors::ParseError;
+
+    fn try_from(value: Digest) -> Result<Self, Self::Error> {
+        Ok(RpoDigest::try_from(value)?.into())
+    }
+}
+
+impl TryFrom<Digest> for TransactionId {
+    type Error = errors::ParseError;
+
+    fn try_from(value: Digest) -> Result<Self, Self::Error> {
+        Ok(RpoDigest::try_from(value)?.into())
+    }
+}
+
 // UTILITIES
 // ================================================================================================
 
@@ -429,6 +944,15 @@ where
     from.into_iter().map(|e| e.try_into()).collect()
 }
 
+/// Converts `value` into a field element, rejecting the values which would be reduced.
+pub fn felt_from_u64(value: u64) -> Result<Felt, errors::ParseError> {
+    if value < <Felt as StarkField>::MODULUS {
+        Ok(Felt::new(value))
+    } else {
+        Err(errors::ParseError::NotAValidFelt)
+    }
+}
+
 /// Given the leaf value of the nullifier TSMT, returns the nullifier's block number.
 ///
 /// There are no nullifiers in the genesis block. The value zero is instead used to signal absence
@@ -436,3 +960,220 @@ where
 pub fn nullifier_value_to_blocknum(value: Word) -> u32 {
     value[3].as_int().try_into().expect("invalid block number found in store")
 }
+
+#[cfg(test)]
+mod test {
+    use miden_crypto::{
+        merkle::{MerklePath, MmrDelta},
+        StarkField,
+    };
+    use miden_objects::{
+        accounts::AccountId,
+        notes::{NoteEnvelope, NoteMetadata},
+        BlockHeader, Digest as RpoDigest, Felt, ACCOUNT_TREE_DEPTH,
+    };
+    use proptest::prelude::*;
+
+    use crate::{
+        account, block_header,
+        digest::Digest,
+        domain::{AccountHashUpdate, CommittedNote, MerkleMultiproof},
+        errors::ParseError,
+        merkle, mmr, note, requests, responses,
+    };
+
+    // STRATEGIES
+    // --------------------------------------------------------------------------------------------
+
+    fn felt() -> impl Strategy<Value = Felt> {
+        (0..Felt::MODULUS).prop_map(Felt::new)
+    }
+
+    fn rpo_digest() -> impl Strategy<Value = RpoDigest> {
+        prop::array::uniform4(felt()).prop_map(RpoDigest::new)
+    }
+
+    fn account_id() -> impl Strategy<Value = AccountId> {
+        any::<u64>().prop_filter_map("invalid account id", |id| AccountId::try_from(id).ok())
+    }
+
+    fn merkle_path() -> impl Strategy<Value = MerklePath> {
+        prop::collection::vec(rpo_digest(), 0..=u8::MAX as usize).prop_map(MerklePath::new)
+    }
+
+    fn block_header() -> impl Strategy<Value = BlockHeader> {
+        (
+            prop::array::uniform6(rpo_digest()),
+            rpo_digest(),
+            any::<u32>(),
+            any::<u32>(),
+            felt(),
+        )
+            .prop_map(|(roots, proof_hash, block_num, version, timestamp)| {
+                BlockHeader::new(
+                    roots[0],
+                    block_num,
+                    roots[1],
+                    roots[2],
+                    roots[3],
+                    roots[4],
+                    roots[5],
+                    proof_hash,
+                    version.into(),
+                    timestamp,
+                )
+            })
+    }
+
+    // ROUND TRIPS
+    // --------------------------------------------------------------------------------------------
+
+    proptest! {
+        #[test]
+        fn test_digest_round_trip(digest in rpo_digest()) {
+            prop_assert_eq!(RpoDigest::try_from(Digest::from(digest)), Ok(digest));
+        }
+
+        #[test]
+        fn test_account_id_round_trip(account_id in account_id()) {
+            prop_assert_eq!(AccountId::try_from(account::AccountId::from(account_id)), Ok(account_id));
+        }
+
+        #[test]
+        fn test_block_header_round_trip(header in block_header()) {
+            prop_assert_eq!(BlockHeader::try_from(block_header::BlockHeader::from(header)), Ok(header));
+        }
+
+        #[test]
+        fn test_merkle_path_round_trip(path in merkle_path()) {
+            prop_assert_eq!(MerklePath::try_from(merkle::MerklePath::from(path.clone())), Ok(path));
+        }
+
+        #[test]
+        fn test_mmr_delta_round_trip(
+            forest in any::<u32>(),
+            data in prop::collection::vec(rpo_digest(), 0..32),
+        ) {
+            let delta = MmrDelta { forest: forest as usize, data };
+            let converted = MmrDelta::try_from(mmr::MmrDelta::from(delta.clone())).unwrap();
+            prop_assert_eq!(converted.forest, delta.forest);
+            prop_assert_eq!(converted.data, delta.data);
+        }
+
+        #[test]
+        fn test_account_update_round_trip(account_id in account_id(), hash in rpo_digest()) {
+            let update = requests::AccountUpdate::from((account_id, hash));
+            prop_assert_eq!(<(AccountId, RpoDigest)>::try_from(update), Ok((account_id, hash)));
+        }
+
+        #[test]
+        fn test_account_hash_update_round_trip(
+            account_id in account_id(),
+            account_hash in rpo_digest(),
+            block_num in any::<u32>(),
+        ) {
+            let update = AccountHashUpdate { account_id, account_hash, block_num };
+            let converted = responses::AccountHashUpdate::from(update.clone());
+            prop_assert_eq!(AccountHashUpdate::try_from(converted), Ok(update));
+        }
+
+        #[test]
+        fn test_note_created_round_trip(
+            note_index in any::<u32>(),
+            note_id in rpo_digest(),
+            sender in account_id(),
+            tag in felt(),
+        ) {
+            let note = || NoteEnvelope::new(note_id.into(), NoteMetadata::new(sender, tag));
+            let converted = note::NoteCreated::from((note_index as u64, note()));
+            prop_assert_eq!(<(u64, NoteEnvelope)>::try_from(converted), Ok((note_index as u64, note())));
+        }
+
+        #[test]
+        fn test_committed_note_round_trip(
+            note_index in any::<u32>(),
+            note_hash in rpo_digest(),
+            sender in account_id(),
+            tag in any::<u64>(),
+            merkle_path in merkle_path(),
+            private in any::<bool>(),
+        ) {
+            let note = CommittedNote { note_index, note_hash, sender, tag, merkle_path, private };
+            let converted = note::NoteSyncRecord::from(note.clone());
+            prop_assert_eq!(CommittedNote::try_from(converted), Ok(note));
+        }
+    }
+
+    // INVALID MESSAGES
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn test_digest_out_of_field_is_rejected() {
+        let digest = Digest {
+            d0: 1,
+            d1: 2,
+            d2: Felt::MODULUS,
+            d3: 4,
+        };
+        assert_eq!(RpoDigest::try_from(digest), Err(ParseError::NotAValidFelt));
+    }
+
+    #[test]
+    fn test_invalid_account_id_is_rejected() {
+        // the account ids are felts, whose high bits encode the type of the account
+        let account_id = account::AccountId { id: u64::MAX };
+        assert!(matches!(AccountId::try_from(account_id), Err(ParseError::InvalidAccountId(_))));
+    }
+
+    #[test]
+    fn test_timestamp_out_of_field_is_rejected() {
+        let mut header = block_header::BlockHeader::from(BlockHeader::new(
+            Default::default(),
+            1,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Felt::new(1),
+            Felt::new(2),
+        ));
+        header.timestamp = Felt::MODULUS;
+        assert_eq!(BlockHeader::try_from(header), Err(ParseError::NotAValidFelt));
+    }
+
+    #[test]
+    fn test_merkle_path_depth_is_bounded() {
+        let siblings = |depth: usize| merkle::MerklePath {
+            siblings: vec![RpoDigest::default().into(); depth],
+        };
+
+        let path = MerklePath::try_from(siblings(u8::MAX as usize)).unwrap();
+        assert_eq!(path.depth(), u8::MAX);
+        assert_eq!(
+            MerklePath::try_from(siblings(u8::MAX as usize + 1)),
+            Err(ParseError::MerklePathTooDeep(u8::MAX as usize + 1))
+        );
+    }
+
+    #[test]
+    fn test_merkle_multiproof_depth_is_account_tree_depth() {
+        let multiproof = |depth: u32| merkle::MerkleMultiproof {
+            nodes: vec![RpoDigest::default().into(); 2],
+            depth,
+        };
+
+        let depth = ACCOUNT_TREE_DEPTH as u32;
+        assert_eq!(
+            MerkleMultiproof::try_from(multiproof(depth)).unwrap().depth,
+            ACCOUNT_TREE_DEPTH
+        );
+        for depth in [0, depth - 1, depth + 1, u8::MAX as u32] {
+            assert_eq!(
+                MerkleMultiproof::try_from(multiproof(depth)),
+                Err(ParseError::InvalidMultiproofDepth(depth))
+            );
+        }
+    }
+}
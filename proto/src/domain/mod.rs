@@ -1,6 +1,19 @@
-use miden_crypto::merkle::{MerklePath, MmrPeaks};
+use std::collections::{BTreeMap, BTreeSet};
+
+use miden_crypto::{
+    dsa::rpo_falcon512::{FalconError, KeyPair, PublicKey, Signature},
+    hash::rpo::Rpo256,
+    merkle::{MerkleError, MerklePath, MmrPeaks, NodeIndex, SimpleSmt},
+    Felt, Word,
+};
+use miden_node_utils::tag_policy::TagPolicy;
 use miden_objects::{accounts::AccountId, BlockHeader, Digest};
 
+use crate::{
+    errors::{NodeInfoError, ParseError},
+    PROTOCOL_VERSION,
+};
+
 #[derive(Clone, Debug)]
 pub struct AccountInputRecord {
     pub account_id: AccountId,
@@ -29,3 +42,611 @@ pub struct BlockInputs {
     /// The requested nullifiers and their authentication paths
     pub nullifiers: Vec<NullifierInputRecord>,
 }
+
+/// Authentication paths of several leaves of the same [SimpleSmt]
+///
+/// The nodes shared by the paths, or computable from the opened leaves, are included only once. The
+/// multiproofs received are of the account tree, other depths are rejected when parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleMultiproof {
+    /// Depth of the opened leaves
+    pub depth: u8,
+
+    /// Sibling nodes which can't be computed from the opened leaves, level by level starting from
+    /// the leaves, and by increasing index within a level
+    pub nodes: Vec<Digest>,
+}
+
+impl MerkleMultiproof {
+    /// Builds the multiproof of the leaves at `leaf_indices` in the `tree`.
+    pub fn from_tree<const DEPTH: u8>(
+        tree: &SimpleSmt<DEPTH>,
+        leaf_indices: impl IntoIterator<Item = u64>,
+    ) -> Result<Self, MerkleError> {
+        let mut known: BTreeSet<u64> = leaf_indices.into_iter().collect();
+        let mut nodes = Vec::new();
+
+        for depth in (1..=DEPTH).rev() {
+            for index in known.iter() {
+                let sibling = index ^ 1;
+                if !known.contains(&sibling) {
+                    nodes.push(tree.get_node(NodeIndex::new(depth, sibling)?)?);
+                }
+            }
+            known = known.into_iter().map(|index| index >> 1).collect();
+        }
+
+        Ok(Self {
+            depth: DEPTH,
+            nodes,
+        })
+    }
+
+    /// Rebuilds the authentication path of each one of the `leaves`, given as pairs of leaf index
+    /// and leaf value. The paths are returned in the same order as the `leaves`.
+    pub fn into_paths(
+        self,
+        leaves: &[(u64, Digest)],
+    ) -> Result<Vec<MerklePath>, ParseError> {
+        let mut level = BTreeMap::new();
+        for &(index, value) in leaves {
+            NodeIndex::new(self.depth, index).map_err(|_| ParseError::InvalidProof)?;
+            level.insert(index, value);
+        }
+
+        let mut nodes = self.nodes.into_iter();
+        let mut paths = vec![Vec::with_capacity(self.depth as usize); leaves.len()];
+
+        for height in 0..self.depth {
+            // fill in the siblings which can't be computed, in the same order they were added
+            let missing: Vec<u64> = level
+                .keys()
+                .map(|index| index ^ 1)
+                .filter(|sibling| !level.contains_key(sibling))
+                .collect();
+            for sibling in missing {
+                level.insert(sibling, nodes.next().ok_or(ParseError::InvalidProof)?);
+            }
+
+            for ((leaf_index, _), path) in leaves.iter().zip(paths.iter_mut()) {
+                path.push(level[&((leaf_index >> height) ^ 1)]);
+            }
+
+            let mut parents = BTreeMap::new();
+            for index in level.keys() {
+                let parent = index >> 1;
+                parents.entry(parent).or_insert_with(|| {
+                    Rpo256::merge(&[level[&(parent << 1)], level[&((parent << 1) | 1)]])
+                });
+            }
+            level = parents;
+        }
+
+        if nodes.next().is_some() {
+            return Err(ParseError::InvalidProof);
+        }
+
+        Ok(paths.into_iter().map(MerklePath::new).collect())
+    }
+}
+
+/// Chain and protocol information reported by the store
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeInfo {
+    /// Version of the protocol spoken by the store
+    pub protocol_version: u32,
+
+    /// Hash of the genesis block of the chain served by the store
+    pub genesis_hash: Digest,
+
+    /// Version of the store's software, informational only
+    pub version: String,
+
+    /// Identifier of the chain served by the store
+    pub chain_id: u32,
+
+    /// Policy of the note tags accepted by the store, which the other components must enforce
+    pub tag_policy: TagPolicy,
+}
+
+impl NodeInfo {
+    /// Verifies that a component speaking [PROTOCOL_VERSION] and configured for `chain_id` can
+    /// operate against this store.
+    ///
+    /// When `genesis_hash` is provided, the store must also serve the chain starting at that
+    /// block.
+    pub fn verify(
+        &self,
+        chain_id: u32,
+        genesis_hash: Option<Digest>,
+    ) -> Result<(), NodeInfoError> {
+        if self.chain_id != chain_id {
+            return Err(NodeInfoError::ChainIdMismatch {
+                expected: chain_id,
+                actual: self.chain_id,
+            });
+        }
+
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(NodeInfoError::ProtocolVersionMismatch {
+                expected: PROTOCOL_VERSION,
+                actual: self.protocol_version,
+            });
+        }
+
+        match genesis_hash {
+            Some(expected) if expected != self.genesis_hash => {
+                Err(NodeInfoError::GenesisHashMismatch {
+                    expected,
+                    actual: self.genesis_hash,
+                })
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Domain of the messages signed by a block producer to acknowledge the transactions it accepted.
+pub const SUBMISSION_RECEIPT_DOMAIN: &str = "miden-node/submission-receipt";
+
+/// Domain of the messages signed by a block producer to sign the blocks it produced.
+pub const BLOCK_SIGNATURE_DOMAIN: &str = "miden-node/block-signature";
+
+/// Domain of the messages signed by a time authority to attest the time it saw a block.
+pub const TIMESTAMP_ATTESTATION_DOMAIN: &str = "miden-node/timestamp-attestation";
+
+/// Returns the message signed to authenticate `payload` as a message of the kind `domain` on the
+/// chain `chain_id`.
+///
+/// Every kind of signed message has its own domain, and a message signed for a chain can't be
+/// replayed on another one.
+fn signed_message(
+    domain: &str,
+    chain_id: u32,
+    payload: &[Felt],
+) -> Word {
+    let mut elements = Rpo256::hash(domain.as_bytes()).as_elements().to_vec();
+    elements.push(Felt::from(chain_id));
+    elements.extend_from_slice(payload);
+    Rpo256::hash_elements(&elements).into()
+}
+
+/// Acknowledgement of a transaction accepted by the block producer, signed by the node
+///
+/// A receipt proves the node accepted the transaction, even if the transaction is later lost, e.g.
+/// because the node failed before including it in a block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubmissionReceipt {
+    /// ID of the accepted transaction
+    pub tx_id: Digest,
+
+    /// Time at which the transaction was accepted, in milliseconds since the UNIX epoch
+    pub received_at: u64,
+
+    /// Position of the transaction in the queue when it was accepted
+    pub queue_position: u32,
+
+    /// Public key of the node which signed the receipt
+    pub node_key: Digest,
+
+    /// Signature of the receipt by `node_key`
+    pub signature: Signature,
+}
+
+impl SubmissionReceipt {
+    /// Returns the message signed by the node to acknowledge the transaction `tx_id` on the chain
+    /// `chain_id`, see [SUBMISSION_RECEIPT_DOMAIN].
+    pub fn message(
+        chain_id: u32,
+        tx_id: Digest,
+        received_at: u64,
+        queue_position: u32,
+    ) -> Word {
+        let mut payload = tx_id.as_elements().to_vec();
+        payload.push(Felt::new(received_at));
+        payload.push(Felt::from(queue_position));
+        signed_message(SUBMISSION_RECEIPT_DOMAIN, chain_id, &payload)
+    }
+
+    /// Signs a receipt of the transaction `tx_id` on the chain `chain_id` with the node's
+    /// `keypair`.
+    pub fn sign(
+        keypair: &KeyPair,
+        chain_id: u32,
+        tx_id: Digest,
+        received_at: u64,
+        queue_position: u32,
+    ) -> Result<Self, FalconError> {
+        let signature =
+            keypair.sign(Self::message(chain_id, tx_id, received_at, queue_position))?;
+
+        Ok(Self {
+            tx_id,
+            received_at,
+            queue_position,
+            node_key: Word::from(keypair.public_key()).into(),
+            signature,
+        })
+    }
+
+    /// Returns `true` if the receipt is signed by `node_key` for the chain `chain_id`.
+    ///
+    /// Clients must also check `node_key` is the key of the node they submitted the transaction to.
+    pub fn verify(
+        &self,
+        chain_id: u32,
+    ) -> bool {
+        let message = Self::message(chain_id, self.tx_id, self.received_at, self.queue_position);
+        PublicKey::new(self.node_key.into()).verify(message, &self.signature)
+    }
+}
+
+/// Signature of a block header by the identity key of the block producer which produced it
+///
+/// Consumers knowing the keys of the block producers of a network attribute every block to its
+/// producer, and detect the blocks produced by anyone else.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockSignature {
+    /// Public key of the block producer which signed the block
+    pub producer_key: Digest,
+
+    /// Signature of the block hash by `producer_key`
+    pub signature: Signature,
+}
+
+impl BlockSignature {
+    /// Returns the message signed by the block producer to sign the block `block_hash` of the
+    /// chain `chain_id`, see [BLOCK_SIGNATURE_DOMAIN].
+    pub fn message(
+        chain_id: u32,
+        block_hash: Digest,
+    ) -> Word {
+        signed_message(BLOCK_SIGNATURE_DOMAIN, chain_id, block_hash.as_elements())
+    }
+
+    /// Signs the block `block_hash` of the chain `chain_id` with the block producer's `keypair`.
+    pub fn sign(
+        keypair: &KeyPair,
+        chain_id: u32,
+        block_hash: Digest,
+    ) -> Result<Self, FalconError> {
+        let signature = keypair.sign(Self::message(chain_id, block_hash))?;
+
+        Ok(Self {
+            producer_key: Word::from(keypair.public_key()).into(),
+            signature,
+        })
+    }
+
+    /// Returns `true` if the signature is of the block `block_hash` of the chain `chain_id` by
+    /// `producer_key`.
+    ///
+    /// Consumers must also check `producer_key` is the key of a known block producer.
+    pub fn verify(
+        &self,
+        chain_id: u32,
+        block_hash: Digest,
+    ) -> bool {
+        let message = Self::message(chain_id, block_hash);
+        PublicKey::new(self.producer_key.into()).verify(message, &self.signature)
+    }
+}
+
+/// Attestation by a time authority of the time it saw a block
+///
+/// The block header's timestamp is taken from the clock of the block producer, which could set it
+/// to any time. An attestation bounds the time the block was produced by the clock of an authority
+/// its consumers trust, since the block hash it signed didn't exist before.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimestampAttestation {
+    /// Public key of the time authority which signed the attestation
+    pub authority_key: Digest,
+
+    /// Time at which the authority saw the block, in seconds since the UNIX epoch
+    pub timestamp: u64,
+
+    /// Signature of the attestation by `authority_key`
+    pub signature: Signature,
+}
+
+impl TimestampAttestation {
+    /// Returns the message signed by the time authority to attest it saw the block `block_hash` of
+    /// the chain `chain_id` at `timestamp`, see [TIMESTAMP_ATTESTATION_DOMAIN].
+    pub fn message(
+        chain_id: u32,
+        block_hash: Digest,
+        timestamp: u64,
+    ) -> Word {
+        let mut payload = block_hash.as_elements().to_vec();
+        payload.push(Felt::new(timestamp));
+        signed_message(TIMESTAMP_ATTESTATION_DOMAIN, chain_id, &payload)
+    }
+
+    /// Attests with the time authority's `keypair` that it saw the block `block_hash` of the chain
+    /// `chain_id` at `timestamp`.
+    pub fn sign(
+        keypair: &KeyPair,
+        chain_id: u32,
+        block_hash: Digest,
+        timestamp: u64,
+    ) -> Result<Self, FalconError> {
+        let signature = keypair.sign(Self::message(chain_id, block_hash, timestamp))?;
+
+        Ok(Self {
+            authority_key: Word::from(keypair.public_key()).into(),
+            timestamp,
+            signature,
+        })
+    }
+
+    /// Returns `true` if the attestation is of the block `block_hash` of the chain `chain_id` by
+    /// `authority_key`.
+    ///
+    /// Consumers must also check `authority_key` is the key of a time authority they trust.
+    pub fn verify(
+        &self,
+        chain_id: u32,
+        block_hash: Digest,
+    ) -> bool {
+        let message = Self::message(chain_id, block_hash, self.timestamp);
+        PublicKey::new(self.authority_key.into()).verify(message, &self.signature)
+    }
+}
+
+/// Signature of a transaction by a partner of the block producer
+///
+/// Partners are given a share of the block space for the transactions they sign, see the
+/// `reservations` of the block producer's configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartnerSignature {
+    /// Public key of the partner which signed the transaction
+    pub partner_key: Digest,
+
+    /// Signature of the transaction ID by `partner_key`
+    pub signature: Signature,
+}
+
+impl PartnerSignature {
+    /// Signs the transaction `tx_id` with the partner's `keypair`.
+    pub fn sign(
+        keypair: &KeyPair,
+        tx_id: Digest,
+    ) -> Result<Self, FalconError> {
+        let signature = keypair.sign(tx_id.into())?;
+
+        Ok(Self {
+            partner_key: Word::from(keypair.public_key()).into(),
+            signature,
+        })
+    }
+
+    /// Returns `true` if the signature is of the transaction `tx_id` by `partner_key`.
+    ///
+    /// The block producer must also check `partner_key` is the key of one of its partners.
+    pub fn verify(
+        &self,
+        tx_id: Digest,
+    ) -> bool {
+        PublicKey::new(self.partner_key.into()).verify(tx_id.into(), &self.signature)
+    }
+}
+
+/// New hash of an account, and the block in which the account was last changed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountHashUpdate {
+    pub account_id: AccountId,
+    pub account_hash: Digest,
+    pub block_num: u32,
+}
+
+/// Note included in a block, with its inclusion path in the block's note tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommittedNote {
+    pub note_index: u32,
+    pub note_hash: Digest,
+    pub sender: AccountId,
+    pub tag: u64,
+    pub merkle_path: MerklePath,
+    /// Whether the note is private, its details being kept off chain by the parties to the note
+    pub private: bool,
+}
+
+/// Nullifier, and the block in which it was consumed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NullifierUpdate {
+    pub nullifier: Digest,
+    pub block_num: u32,
+}
+
+/// Note identified by its hash, and the block in which it was consumed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsumedNoteUpdate {
+    pub note_hash: Digest,
+    pub nullifier: Digest,
+    pub block_num: u32,
+}
+
+#[cfg(test)]
+mod test {
+    use miden_crypto::{
+        dsa::rpo_falcon512::KeyPair,
+        merkle::{LeafIndex, SimpleSmt},
+        Felt, Word, ONE, ZERO,
+    };
+    use miden_objects::Digest;
+
+    use super::{
+        BlockSignature, MerkleMultiproof, NodeInfo, PartnerSignature, SubmissionReceipt,
+        TimestampAttestation,
+    };
+    use crate::{
+        errors::{NodeInfoError, ParseError},
+        PROTOCOL_VERSION,
+    };
+
+    #[test]
+    fn test_merkle_multiproof() {
+        const DEPTH: u8 = 8;
+        let leaf = |n: u64| -> Word { [Felt::new(n), ZERO, ZERO, ZERO] };
+        let tree = SimpleSmt::<DEPTH>::with_leaves([
+            (0, leaf(1)),
+            (1, leaf(2)),
+            (5, leaf(3)),
+            (200, leaf(4)),
+        ])
+        .unwrap();
+
+        // opened leaves may be empty, and are given in any order
+        let indices = [200u64, 0, 5, 100];
+        let multiproof = MerkleMultiproof::from_tree(&tree, indices).unwrap();
+
+        let individual_nodes = indices.len() * DEPTH as usize;
+        assert!(multiproof.nodes.len() < individual_nodes);
+
+        let leaves: Vec<(u64, Digest)> = indices
+            .iter()
+            .map(|&index| (index, tree.get_leaf(&LeafIndex::new(index).unwrap()).into()))
+            .collect();
+        let paths = multiproof.clone().into_paths(&leaves).unwrap();
+        for (&index, path) in indices.iter().zip(paths) {
+            assert_eq!(path, tree.open(&LeafIndex::<DEPTH>::new(index).unwrap()).path);
+        }
+
+        // missing or extra nodes are rejected
+        let mut truncated = multiproof.clone();
+        truncated.nodes.pop();
+        assert_eq!(truncated.into_paths(&leaves), Err(ParseError::InvalidProof));
+
+        let mut extended = multiproof;
+        extended.nodes.push(Digest::default());
+        assert_eq!(extended.into_paths(&leaves), Err(ParseError::InvalidProof));
+    }
+
+    #[test]
+    fn test_node_info_verify() {
+        let genesis_hash = Digest::new([ONE, ONE, ONE, ONE]);
+        let other_hash = Digest::new([Felt::new(2), ONE, ONE, ONE]);
+        let node_info = NodeInfo {
+            protocol_version: PROTOCOL_VERSION,
+            genesis_hash,
+            version: "0.1.0".to_string(),
+            chain_id: 1,
+            tag_policy: Default::default(),
+        };
+
+        assert_eq!(node_info.verify(1, None), Ok(()));
+        assert_eq!(node_info.verify(1, Some(genesis_hash)), Ok(()));
+        assert_eq!(
+            node_info.verify(2, Some(genesis_hash)),
+            Err(NodeInfoError::ChainIdMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(
+            node_info.verify(1, Some(other_hash)),
+            Err(NodeInfoError::GenesisHashMismatch {
+                expected: other_hash,
+                actual: genesis_hash
+            })
+        );
+
+        let node_info = NodeInfo {
+            protocol_version: PROTOCOL_VERSION + 1,
+            ..node_info
+        };
+        assert_eq!(
+            node_info.verify(1, Some(genesis_hash)),
+            Err(NodeInfoError::ProtocolVersionMismatch {
+                expected: PROTOCOL_VERSION,
+                actual: PROTOCOL_VERSION + 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_submission_receipt() {
+        let keypair = KeyPair::new().unwrap();
+        let tx_id = Digest::new([ONE, ONE, ONE, ONE]);
+
+        let receipt = SubmissionReceipt::sign(&keypair, 1, tx_id, 1_700_000_000_000, 3).unwrap();
+        assert!(receipt.verify(1));
+
+        // the signature doesn't cover a different queue position, nor a different transaction, nor
+        // another chain
+        let moved = SubmissionReceipt {
+            queue_position: 0,
+            ..receipt.clone()
+        };
+        assert!(!moved.verify(1));
+        let other_tx = SubmissionReceipt {
+            tx_id: Digest::default(),
+            ..receipt.clone()
+        };
+        assert!(!other_tx.verify(1));
+        assert!(!receipt.verify(2));
+    }
+
+    #[test]
+    fn test_block_signature() {
+        let keypair = KeyPair::new().unwrap();
+        let block_hash = Digest::new([ONE, ONE, ONE, ONE]);
+
+        let signature = BlockSignature::sign(&keypair, 1, block_hash).unwrap();
+        assert!(signature.verify(1, block_hash));
+
+        // the signature doesn't cover another block, nor another chain, nor is it valid for another
+        // key
+        assert!(!signature.verify(1, Digest::default()));
+        assert!(!signature.verify(2, block_hash));
+        let other_key = BlockSignature {
+            producer_key: Word::from(KeyPair::new().unwrap().public_key()).into(),
+            ..signature.clone()
+        };
+        assert!(!other_key.verify(1, block_hash));
+
+        // nor is it valid as another kind of signed message of the same block
+        let attestation = TimestampAttestation {
+            authority_key: signature.producer_key,
+            timestamp: 0,
+            signature: signature.signature,
+        };
+        assert!(!attestation.verify(1, block_hash));
+    }
+
+    #[test]
+    fn test_timestamp_attestation() {
+        let keypair = KeyPair::new().unwrap();
+        let block_hash = Digest::new([ONE, ONE, ONE, ONE]);
+
+        let attestation =
+            TimestampAttestation::sign(&keypair, 1, block_hash, 1_700_000_000).unwrap();
+        assert!(attestation.verify(1, block_hash));
+
+        // the signature doesn't cover another time, nor another block, nor another chain
+        assert!(!attestation.verify(1, Digest::default()));
+        assert!(!attestation.verify(2, block_hash));
+        let other_time = TimestampAttestation {
+            timestamp: 1_700_000_001,
+            ..attestation
+        };
+        assert!(!other_time.verify(1, block_hash));
+    }
+
+    #[test]
+    fn test_partner_signature() {
+        let keypair = KeyPair::new().unwrap();
+        let tx_id = Digest::new([ONE, ZERO, ONE, ZERO]);
+
+        let signature = PartnerSignature::sign(&keypair, tx_id).unwrap();
+        assert!(signature.verify(tx_id));
+
+        // the signature doesn't cover another transaction, nor is it valid for another key
+        assert!(!signature.verify(Digest::default()));
+        let other_key = PartnerSignature {
+            partner_key: Word::from(KeyPair::new().unwrap().public_key()).into(),
+            ..signature
+        };
+        assert!(!other_key.verify(tx_id));
+    }
+}
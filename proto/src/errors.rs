@@ -1,4 +1,4 @@
-use miden_crypto::merkle::MmrError;
+use miden_crypto::{hash::rpo::RpoDigest, merkle::MmrError};
 use thiserror::Error;
 
 #[derive(Error, Debug, Clone, PartialEq)]
@@ -17,8 +17,35 @@ pub enum ParseError {
     TooManyMmrPeaks,
     #[error("Value is not in the range 0..MODULUS")]
     NotAValidFelt,
+    #[error("Invalid account id: {0}")]
+    InvalidAccountId(String),
+    #[error("Merkle path of depth {0} is deeper than any merkle tree")]
+    MerklePathTooDeep(usize),
     #[error("Received TSMT proof is invalid")]
     InvalidProof,
+    #[error("Merkle multiproof of depth {0} is not a multiproof of the account tree")]
+    InvalidMultiproofDepth(u32),
     #[error("Protobuf message missing data")]
     ProtobufMissingData,
+    #[error("Malformed signature")]
+    InvalidSignature,
+    #[error("Invalid account delta: {0}")]
+    InvalidAccountDelta(String),
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum NodeInfoError {
+    #[error("Failed to retrieve node info: {0}")]
+    GrpcClientError(String),
+    #[error("Malformed node info: {0}")]
+    MalformedNodeInfo(#[from] ParseError),
+    #[error("Chain id mismatch, expected {expected}, store reports {actual}")]
+    ChainIdMismatch { expected: u32, actual: u32 },
+    #[error("Protocol version mismatch, expected {expected}, store reports {actual}")]
+    ProtocolVersionMismatch { expected: u32, actual: u32 },
+    #[error("Genesis block hash mismatch, expected {expected}, store reports {actual}")]
+    GenesisHashMismatch {
+        expected: RpoDigest,
+        actual: RpoDigest,
+    },
 }
@@ -20,3 +20,49 @@ pub struct AccountInfo {
     #[prost(uint32, tag = "3")]
     pub block_num: u32,
 }
+/// Changes made by a transaction to the state of a public account.
+///
+/// The transactions of public accounts publish their changes, so the state of the account can be
+/// tracked by anyone. The transactions of private accounts only commit to the hash of the final
+/// state, and don't include a delta.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AccountDelta {
+    #[prost(message, optional, tag = "1")]
+    pub storage: ::core::option::Option<AccountStorageDelta>,
+    #[prost(message, optional, tag = "2")]
+    pub vault: ::core::option::Option<AccountVaultDelta>,
+    /// New nonce of the account, absent if the state of the account didn't change.
+    #[prost(fixed64, optional, tag = "3")]
+    pub nonce: ::core::option::Option<u64>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AccountStorageDelta {
+    /// Indices of the storage slots set to the empty word.
+    #[prost(uint32, repeated, tag = "1")]
+    pub cleared_slots: ::prost::alloc::vec::Vec<u32>,
+    #[prost(message, repeated, tag = "2")]
+    pub updated_slots: ::prost::alloc::vec::Vec<StorageSlotUpdate>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StorageSlotUpdate {
+    #[prost(uint32, tag = "1")]
+    pub index: u32,
+    #[prost(message, optional, tag = "2")]
+    pub value: ::core::option::Option<super::digest::Digest>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AccountVaultDelta {
+    /// Assets encoded as words.
+    #[prost(message, repeated, tag = "1")]
+    pub added_assets: ::prost::alloc::vec::Vec<super::digest::Digest>,
+    #[prost(message, repeated, tag = "2")]
+    pub removed_assets: ::prost::alloc::vec::Vec<super::digest::Digest>,
+}
@@ -33,3 +33,32 @@ pub struct BlockHeader {
     #[prost(uint64, tag = "10")]
     pub timestamp: u64,
 }
+/// Signature of a block header by the identity key of the block producer which produced it.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockSignature {
+    /// Public key of the block producer.
+    #[prost(message, optional, tag = "1")]
+    pub producer_key: ::core::option::Option<super::digest::Digest>,
+    /// RPO Falcon512 signature by `producer_key` of the hash of the block signature domain tag, the
+    /// chain id and the block hash.
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
+/// Attestation by a time authority that it saw a block at a given time.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TimestampAttestation {
+    /// Public key of the time authority.
+    #[prost(message, optional, tag = "1")]
+    pub authority_key: ::core::option::Option<super::digest::Digest>,
+    /// Time at which the authority saw the block, in seconds since the UNIX epoch.
+    #[prost(uint64, tag = "2")]
+    pub timestamp: u64,
+    /// RPO Falcon512 signature by `authority_key` of the hash of the timestamp attestation domain
+    /// tag, the chain id, the block hash and `timestamp`.
+    #[prost(bytes = "vec", tag = "3")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
@@ -112,6 +112,306 @@ pub mod api_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        pub async fn submit_proven_batch(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::SubmitProvenBatchRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SubmitProvenBatchResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/block_producer.Api/SubmitProvenBatch",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("block_producer.Api", "SubmitProvenBatch"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_pending_transactions_by_account(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetPendingTransactionsByAccountRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<
+                super::super::responses::GetPendingTransactionsByAccountResponse,
+            >,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/block_producer.Api/GetPendingTransactionsByAccount",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "block_producer.Api",
+                        "GetPendingTransactionsByAccount",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_mempool_status(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetMempoolStatusRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetMempoolStatusResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/block_producer.Api/GetMempoolStatus",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("block_producer.Api", "GetMempoolStatus"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_last_block_failure(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetLastBlockFailureRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetLastBlockFailureResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/block_producer.Api/GetLastBlockFailure",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("block_producer.Api", "GetLastBlockFailure"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_transaction_latency(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetTransactionLatencyRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetTransactionLatencyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/block_producer.Api/GetTransactionLatency",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("block_producer.Api", "GetTransactionLatency"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_queue_depth(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetQueueDepthRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetQueueDepthResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/block_producer.Api/GetQueueDepth",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("block_producer.Api", "GetQueueDepth"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_startup_audit(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetStartupAuditRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetStartupAuditResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/block_producer.Api/GetStartupAudit",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("block_producer.Api", "GetStartupAudit"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_restart_status(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetRestartStatusRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetRestartStatusResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/block_producer.Api/GetRestartStatus",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("block_producer.Api", "GetRestartStatus"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn pause_intake(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::PauseIntakeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::PauseIntakeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/block_producer.Api/PauseIntake",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("block_producer.Api", "PauseIntake"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn snapshot_queue(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::SnapshotQueueRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SnapshotQueueResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/block_producer.Api/SnapshotQueue",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("block_producer.Api", "SnapshotQueue"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn shutdown(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::ShutdownRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ShutdownResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/block_producer.Api/Shutdown",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("block_producer.Api", "Shutdown"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -130,6 +430,87 @@ pub mod api_server {
             tonic::Response<super::super::responses::SubmitProvenTransactionResponse>,
             tonic::Status,
         >;
+        async fn submit_proven_batch(
+            &self,
+            request: tonic::Request<super::super::requests::SubmitProvenBatchRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SubmitProvenBatchResponse>,
+            tonic::Status,
+        >;
+        async fn get_pending_transactions_by_account(
+            &self,
+            request: tonic::Request<
+                super::super::requests::GetPendingTransactionsByAccountRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<
+                super::super::responses::GetPendingTransactionsByAccountResponse,
+            >,
+            tonic::Status,
+        >;
+        async fn get_mempool_status(
+            &self,
+            request: tonic::Request<super::super::requests::GetMempoolStatusRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetMempoolStatusResponse>,
+            tonic::Status,
+        >;
+        async fn get_last_block_failure(
+            &self,
+            request: tonic::Request<super::super::requests::GetLastBlockFailureRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetLastBlockFailureResponse>,
+            tonic::Status,
+        >;
+        async fn get_transaction_latency(
+            &self,
+            request: tonic::Request<super::super::requests::GetTransactionLatencyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetTransactionLatencyResponse>,
+            tonic::Status,
+        >;
+        async fn get_queue_depth(
+            &self,
+            request: tonic::Request<super::super::requests::GetQueueDepthRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetQueueDepthResponse>,
+            tonic::Status,
+        >;
+        async fn get_startup_audit(
+            &self,
+            request: tonic::Request<super::super::requests::GetStartupAuditRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetStartupAuditResponse>,
+            tonic::Status,
+        >;
+        async fn get_restart_status(
+            &self,
+            request: tonic::Request<super::super::requests::GetRestartStatusRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetRestartStatusResponse>,
+            tonic::Status,
+        >;
+        async fn pause_intake(
+            &self,
+            request: tonic::Request<super::super::requests::PauseIntakeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::PauseIntakeResponse>,
+            tonic::Status,
+        >;
+        async fn snapshot_queue(
+            &self,
+            request: tonic::Request<super::super::requests::SnapshotQueueRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SnapshotQueueResponse>,
+            tonic::Status,
+        >;
+        async fn shutdown(
+            &self,
+            request: tonic::Request<super::super::requests::ShutdownRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ShutdownResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct ApiServer<T: Api> {
@@ -212,13 +593,262 @@ pub mod api_server {
             match req.uri().path() {
                 "/block_producer.Api/SubmitProvenTransaction" => {
                     #[allow(non_camel_case_types)]
-                    struct SubmitProvenTransactionSvc<T: Api>(pub Arc<T>);
+                    struct SubmitProvenTransactionSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::SubmitProvenTransactionRequest,
+                    > for SubmitProvenTransactionSvc<T> {
+                        type Response = super::super::responses::SubmitProvenTransactionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::SubmitProvenTransactionRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::submit_proven_transaction(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubmitProvenTransactionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/block_producer.Api/SubmitProvenBatch" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubmitProvenBatchSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::SubmitProvenBatchRequest,
+                    > for SubmitProvenBatchSvc<T> {
+                        type Response = super::super::responses::SubmitProvenBatchResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::SubmitProvenBatchRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::submit_proven_batch(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubmitProvenBatchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/block_producer.Api/GetPendingTransactionsByAccount" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetPendingTransactionsByAccountSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetPendingTransactionsByAccountRequest,
+                    > for GetPendingTransactionsByAccountSvc<T> {
+                        type Response = super::super::responses::GetPendingTransactionsByAccountResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetPendingTransactionsByAccountRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_pending_transactions_by_account(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetPendingTransactionsByAccountSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/block_producer.Api/GetMempoolStatus" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetMempoolStatusSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetMempoolStatusRequest,
+                    > for GetMempoolStatusSvc<T> {
+                        type Response = super::super::responses::GetMempoolStatusResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetMempoolStatusRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_mempool_status(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetMempoolStatusSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/block_producer.Api/GetLastBlockFailure" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetLastBlockFailureSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetLastBlockFailureRequest,
+                    > for GetLastBlockFailureSvc<T> {
+                        type Response = super::super::responses::GetLastBlockFailureResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetLastBlockFailureRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_last_block_failure(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetLastBlockFailureSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/block_producer.Api/GetTransactionLatency" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTransactionLatencySvc<T: Api>(pub Arc<T>);
                     impl<
                         T: Api,
                     > tonic::server::UnaryService<
-                        super::super::requests::SubmitProvenTransactionRequest,
-                    > for SubmitProvenTransactionSvc<T> {
-                        type Response = super::super::responses::SubmitProvenTransactionResponse;
+                        super::super::requests::GetTransactionLatencyRequest,
+                    > for GetTransactionLatencySvc<T> {
+                        type Response = super::super::responses::GetTransactionLatencyResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -226,12 +856,12 @@ pub mod api_server {
                         fn call(
                             &mut self,
                             request: tonic::Request<
-                                super::super::requests::SubmitProvenTransactionRequest,
+                                super::super::requests::GetTransactionLatencyRequest,
                             >,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Api>::submit_proven_transaction(&inner, request).await
+                                <T as Api>::get_transaction_latency(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -243,7 +873,301 @@ pub mod api_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = SubmitProvenTransactionSvc(inner);
+                        let method = GetTransactionLatencySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/block_producer.Api/GetQueueDepth" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetQueueDepthSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetQueueDepthRequest,
+                    > for GetQueueDepthSvc<T> {
+                        type Response = super::super::responses::GetQueueDepthResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetQueueDepthRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_queue_depth(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetQueueDepthSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/block_producer.Api/GetStartupAudit" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetStartupAuditSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetStartupAuditRequest,
+                    > for GetStartupAuditSvc<T> {
+                        type Response = super::super::responses::GetStartupAuditResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetStartupAuditRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_startup_audit(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetStartupAuditSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/block_producer.Api/GetRestartStatus" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetRestartStatusSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetRestartStatusRequest,
+                    > for GetRestartStatusSvc<T> {
+                        type Response = super::super::responses::GetRestartStatusResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetRestartStatusRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_restart_status(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetRestartStatusSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/block_producer.Api/PauseIntake" => {
+                    #[allow(non_camel_case_types)]
+                    struct PauseIntakeSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::PauseIntakeRequest,
+                    > for PauseIntakeSvc<T> {
+                        type Response = super::super::responses::PauseIntakeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::PauseIntakeRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::pause_intake(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PauseIntakeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/block_producer.Api/SnapshotQueue" => {
+                    #[allow(non_camel_case_types)]
+                    struct SnapshotQueueSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::SnapshotQueueRequest,
+                    > for SnapshotQueueSvc<T> {
+                        type Response = super::super::responses::SnapshotQueueResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::SnapshotQueueRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::snapshot_queue(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SnapshotQueueSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/block_producer.Api/Shutdown" => {
+                    #[allow(non_camel_case_types)]
+                    struct ShutdownSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::ShutdownRequest,
+                    > for ShutdownSvc<T> {
+                        type Response = super::super::responses::ShutdownResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::ShutdownRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::shutdown(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ShutdownSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -5,3 +5,19 @@ pub struct MerklePath {
     #[prost(message, repeated, tag = "1")]
     pub siblings: ::prost::alloc::vec::Vec<super::digest::Digest>,
 }
+/// Authentication paths of several leaves of the same tree.
+///
+/// Nodes shared by the paths, or computable from the opened leaves, are included only once. The
+/// leaves are not part of the message, they are needed to rebuild the individual paths.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MerkleMultiproof {
+    /// Sibling nodes which can't be computed from the opened leaves. Ordered level by level starting
+    /// from the leaves, and by increasing index within a level.
+    #[prost(message, repeated, tag = "1")]
+    pub nodes: ::prost::alloc::vec::Vec<super::digest::Digest>,
+    /// Depth of the opened leaves, the multiproofs are of the account tree whose depth is 64.
+    #[prost(uint32, tag = "2")]
+    pub depth: u32,
+}
@@ -14,6 +14,9 @@ pub struct Note {
     pub tag: u64,
     #[prost(message, optional, tag = "7")]
     pub merkle_path: ::core::option::Option<super::merkle::MerklePath>,
+    /// Whether the note is private, its details being kept off chain by the parties to the note.
+    #[prost(bool, tag = "8")]
+    pub private: bool,
 }
 /// TODO: change `sender` to AccountId
 #[derive(Eq, PartialOrd, Ord, Hash)]
@@ -30,6 +33,9 @@ pub struct NoteSyncRecord {
     pub tag: u64,
     #[prost(message, optional, tag = "6")]
     pub merkle_path: ::core::option::Option<super::merkle::MerklePath>,
+    /// Whether the note is private, its details being kept off chain by the parties to the note.
+    #[prost(bool, tag = "7")]
+    pub private: bool,
 }
 /// TODO: change `sender` to AccountId
 #[derive(Eq, PartialOrd, Ord, Hash)]
@@ -44,4 +50,88 @@ pub struct NoteCreated {
     pub sender: u64,
     #[prost(uint64, tag = "4")]
     pub tag: u64,
+    /// Whether the note is private, its details being kept off chain by the parties to the note.
+    #[prost(bool, tag = "5")]
+    pub private: bool,
+}
+/// Range of note tags, both bounds included.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NoteTagRange {
+    #[prost(uint64, tag = "1")]
+    pub start: u64,
+    #[prost(uint64, tag = "2")]
+    pub end: u64,
+}
+/// Ranges of the note tags accepted by the node, by kind of tag. Every tag is accepted if there is
+/// no range.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NoteTagPolicy {
+    /// Tags of the notes consumed by the network.
+    #[prost(message, repeated, tag = "1")]
+    pub network: ::prost::alloc::vec::Vec<NoteTagRange>,
+    /// Tags matched locally by the clients.
+    #[prost(message, repeated, tag = "2")]
+    pub local: ::prost::alloc::vec::Vec<NoteTagRange>,
+    /// Tags of the notes targeting an account.
+    #[prost(message, repeated, tag = "3")]
+    pub account: ::prost::alloc::vec::Vec<NoteTagRange>,
+}
+/// Details of a note published by its owner, retained by the node along with the note's inclusion
+/// path, so the note can be consumed even once the node no longer serves the path otherwise.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NoteRecoveryRecord {
+    #[prost(uint32, tag = "1")]
+    pub block_num: u32,
+    #[prost(uint32, tag = "2")]
+    pub note_index: u32,
+    #[prost(message, optional, tag = "3")]
+    pub note_hash: ::core::option::Option<super::digest::Digest>,
+    /// The note, serialized as a `miden_objects::notes::Note`.
+    #[prost(bytes = "vec", tag = "4")]
+    pub details: ::prost::alloc::vec::Vec<u8>,
+    /// Note's inclusion path, to be verified against the `note_root` of the block's header.
+    #[prost(message, optional, tag = "5")]
+    pub merkle_path: ::core::option::Option<super::merkle::MerklePath>,
+}
+/// Links a public note to the nullifier produced when it is consumed.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NoteNullifier {
+    #[prost(message, optional, tag = "1")]
+    pub note_hash: ::core::option::Option<super::digest::Digest>,
+    #[prost(message, optional, tag = "2")]
+    pub nullifier: ::core::option::Option<super::digest::Digest>,
+}
+/// Notes exported by the store with their inclusion proofs, so they can be verified without access
+/// to the node.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NoteArchive {
+    /// Chain the notes were created on.
+    #[prost(uint32, tag = "1")]
+    pub chain_id: u32,
+    /// First block of the exported range.
+    #[prost(uint32, tag = "2")]
+    pub from_block: u32,
+    /// Last block of the exported range, included.
+    #[prost(uint32, tag = "3")]
+    pub to_block: u32,
+    /// The 16 high bits of the tags of the exported notes.
+    #[prost(uint32, repeated, tag = "4")]
+    pub note_tags: ::prost::alloc::vec::Vec<u32>,
+    /// Headers of the blocks which created the notes, ordered by block number. The merkle path of a
+    /// note is verified against the `note_root` of its block.
+    #[prost(message, repeated, tag = "5")]
+    pub block_headers: ::prost::alloc::vec::Vec<super::block_header::BlockHeader>,
+    /// Notes with their merkle paths, ordered by block number and note index.
+    #[prost(message, repeated, tag = "6")]
+    pub notes: ::prost::alloc::vec::Vec<Note>,
 }
@@ -6,6 +6,10 @@ pub struct AccountUpdate {
     pub account_id: ::core::option::Option<super::account::AccountId>,
     #[prost(message, optional, tag = "2")]
     pub account_hash: ::core::option::Option<super::digest::Digest>,
+    /// Serialized state of the account, whose hash is `account_hash`, if the account is public and
+    /// its state is known to the block producer. Empty otherwise.
+    #[prost(bytes = "vec", tag = "3")]
+    pub details: ::prost::alloc::vec::Vec<u8>,
 }
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -19,13 +23,87 @@ pub struct ApplyBlockRequest {
     pub nullifiers: ::prost::alloc::vec::Vec<super::digest::Digest>,
     #[prost(message, repeated, tag = "4")]
     pub notes: ::prost::alloc::vec::Vec<super::note::NoteCreated>,
+    /// Optional links between the public notes consumed in the block and their nullifiers, every
+    /// nullifier must be one of `nullifiers`.
+    #[prost(message, repeated, tag = "5")]
+    pub consumed_notes: ::prost::alloc::vec::Vec<super::note::NoteNullifier>,
+    /// Versions of the transaction kernels the transactions of the block were proven with.
+    #[prost(uint32, repeated, tag = "6")]
+    pub tx_kernel_versions: ::prost::alloc::vec::Vec<u32>,
+    /// Signature of the block by the block producer, missing if it has no identity key.
+    #[prost(message, optional, tag = "7")]
+    pub signature: ::core::option::Option<super::block_header::BlockSignature>,
+    /// Whether the transactions of the block were ordered canonically, i.e. the transactions of
+    /// every batch by id and the batches by id, rather than as they arrived.
+    #[prost(bool, tag = "8")]
+    pub canonical_tx_ordering: bool,
+    /// Attestation of the time the previous block was produced by the block producer's time
+    /// authority, missing if it has none or the authority didn't answer in time.
+    #[prost(message, optional, tag = "9")]
+    pub timestamp_attestation: ::core::option::Option<
+        super::block_header::TimestampAttestation,
+    >,
+    /// Proofs of the proven batches of the block, in the order they are applied. The block commits
+    /// to them in its batch root and proof hash.
+    #[prost(message, repeated, tag = "10")]
+    pub batch_proofs: ::prost::alloc::vec::Vec<BatchProof>,
+    /// Identifier of the chain of the block, the store rejects the blocks of another chain.
+    #[prost(uint32, tag = "11")]
+    pub chain_id: u32,
+}
+/// Proof of the execution of the batch kernel over a batch of a block.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchProof {
+    /// Commitment to the transactions and created notes of the batch, output by the batch kernel.
+    #[prost(message, optional, tag = "1")]
+    pub commitment: ::core::option::Option<super::digest::Digest>,
+    /// Number of transactions of the batch.
+    #[prost(uint32, tag = "2")]
+    pub num_txs: u32,
+    /// Root of the notes created by the batch.
+    #[prost(message, optional, tag = "3")]
+    pub created_notes_root: ::core::option::Option<super::digest::Digest>,
+    /// Serialized proof of the batch kernel execution.
+    #[prost(bytes = "vec", tag = "4")]
+    pub proof: ::prost::alloc::vec::Vec<u8>,
+    /// Index of the batch in the block, the notes it created are the notes of the block in the
+    /// subtree of the batch.
+    #[prost(uint32, tag = "5")]
+    pub batch_index: u32,
+}
+/// Applies a contiguous run of blocks at once, e.g. to ingest an archive of the chain.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ApplyBlocksRequest {
+    /// Blocks to apply, in order, the first one following the chain tip.
+    #[prost(message, repeated, tag = "1")]
+    pub blocks: ::prost::alloc::vec::Vec<ApplyBlockRequest>,
+}
+/// Commits a block staged by `ProposeBlock`. The block to propose is sent as an `ApplyBlockRequest`.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CommitBlockRequest {
+    /// Hash of the proposed block, as returned by `ProposeBlock`.
+    #[prost(message, optional, tag = "1")]
+    pub block_hash: ::core::option::Option<super::digest::Digest>,
 }
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CheckNullifiersRequest {
+    /// Nullifiers for which a full nullifier proof is requested.
     #[prost(message, repeated, tag = "1")]
     pub nullifiers: ::prost::alloc::vec::Vec<super::digest::Digest>,
+    /// Nullifiers for which only the block number at which they were consumed is requested.
+    ///
+    /// Skips the generation of the proofs, for callers which only need to know whether the
+    /// nullifiers have been consumed.
+    #[prost(message, repeated, tag = "2")]
+    pub nullifiers_without_proofs: ::prost::alloc::vec::Vec<super::digest::Digest>,
 }
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -36,6 +114,15 @@ pub struct GetBlockHeaderByNumberRequest {
     /// If not provided, means latest know block.
     #[prost(uint32, optional, tag = "1")]
     pub block_num: ::core::option::Option<u32>,
+    /// Whether to include an MMR proof of the block header against the chain root of the tip.
+    #[prost(bool, optional, tag = "2")]
+    pub include_mmr_proof: ::core::option::Option<bool>,
+    /// Hash of the chain tip known by the client. When requesting the latest block, the header is
+    /// only returned if the tip changed, otherwise `not_modified` is set.
+    ///
+    /// Only honored by the RPC.
+    #[prost(message, optional, tag = "3")]
+    pub if_none_match: ::core::option::Option<super::digest::Digest>,
 }
 /// State synchronization request.
 ///
@@ -71,6 +158,54 @@ pub struct SyncStateRequest {
     /// Similarly to the note_tags, this determins only the 16high bits of the target nullifier.
     #[prost(uint32, repeated, tag = "4")]
     pub nullifiers: ::prost::alloc::vec::Vec<u32>,
+    /// Hashes of the notes tracked by the client, to learn when they are consumed.
+    ///
+    /// Only public notes linked to their nullifier when the consuming block was applied are reported.
+    #[prost(message, repeated, tag = "5")]
+    pub note_hashes: ::prost::alloc::vec::Vec<super::digest::Digest>,
+    /// Token returned by a previous response, resuming the sync after the block of that response.
+    /// Overrides `block_num` when set. The other fields must be the ones of the request which
+    /// returned the token.
+    #[prost(bytes = "vec", tag = "6")]
+    pub resume_token: ::prost::alloc::vec::Vec<u8>,
+}
+/// The objects tracked by a client, synchronized by a single `SyncAll` request.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SyncAllRequest {
+    /// Last block known by the client, see `SyncStateRequest.block_num`.
+    #[prost(uint32, tag = "1")]
+    pub block_num: u32,
+    /// Accounts whose latest hash the client tracks, see `SyncStateRequest.account_ids`.
+    #[prost(message, repeated, tag = "2")]
+    pub account_ids: ::prost::alloc::vec::Vec<super::account::AccountId>,
+    /// High 16 bits of the tags of the notes the client is interested in, see
+    /// `SyncStateRequest.note_tags`.
+    #[prost(uint32, repeated, tag = "3")]
+    pub note_tags: ::prost::alloc::vec::Vec<u32>,
+    /// Nullifiers of the notes tracked by the client, to learn in which block they were consumed.
+    ///
+    /// Unlike `SyncStateRequest.nullifiers` these are the complete nullifiers, and only the
+    /// requested ones are returned. Nullifiers consumed up to `SyncAllResponse.block_header` are
+    /// returned, including the ones consumed before `block_num`.
+    #[prost(message, repeated, tag = "4")]
+    pub nullifiers: ::prost::alloc::vec::Vec<super::digest::Digest>,
+    /// Hashes of the notes tracked by the client, see `SyncStateRequest.note_hashes`.
+    #[prost(message, repeated, tag = "5")]
+    pub note_hashes: ::prost::alloc::vec::Vec<super::digest::Digest>,
+}
+/// The accounts tracked by a light client, whose latest states are proven against the chain tip.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SyncAccountsRequest {
+    /// Last block known by the client, only the accounts updated after it are returned.
+    #[prost(uint32, tag = "1")]
+    pub block_num: u32,
+    /// Accounts tracked by the client.
+    #[prost(message, repeated, tag = "2")]
+    pub account_ids: ::prost::alloc::vec::Vec<super::account::AccountId>,
 }
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -91,6 +226,9 @@ pub struct GetTransactionInputsRequest {
     pub account_id: ::core::option::Option<super::account::AccountId>,
     #[prost(message, repeated, tag = "2")]
     pub nullifiers: ::prost::alloc::vec::Vec<super::digest::Digest>,
+    /// Hash of the block the transaction was executed against.
+    #[prost(message, optional, tag = "3")]
+    pub block_ref: ::core::option::Option<super::digest::Digest>,
 }
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -99,10 +237,183 @@ pub struct SubmitProvenTransactionRequest {
     /// Transaction encoded using miden's native format
     #[prost(bytes = "vec", tag = "1")]
     pub transaction: ::prost::alloc::vec::Vec<u8>,
+    /// Version of the transaction kernel the transaction was proven with. `0` stands for the
+    /// version used before submissions specified it, i.e. version `1`.
+    #[prost(uint32, tag = "2")]
+    pub tx_kernel_version: u32,
+    /// Changes made by the transaction to the state of the account, required for public accounts and
+    /// rejected for private accounts.
+    #[prost(message, optional, tag = "3")]
+    pub account_delta: ::core::option::Option<super::account::AccountDelta>,
+    /// Signature of the transaction by a partner of the block producer, submitting it against the
+    /// block space reserved for the partner. Unsigned transactions don't use any reservation.
+    #[prost(message, optional, tag = "4")]
+    pub partner_signature: ::core::option::Option<PartnerSignature>,
+    /// Public notes consumed by the transaction, encoded using miden's native format. Each note is
+    /// linked by the store to the nullifier the transaction produces for it, so the clients tracking
+    /// the note learn it was consumed. The notes not consumed by the transaction are rejected.
+    #[prost(bytes = "vec", repeated, tag = "5")]
+    pub consumed_notes: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// Hashes of the notes created by the transaction which are private: the node only holds their
+    /// hash and metadata, and never their details. The other notes created by the transaction are
+    /// public. The hashes of notes not created by the transaction are rejected.
+    #[prost(message, repeated, tag = "6")]
+    pub private_notes: ::prost::alloc::vec::Vec<super::digest::Digest>,
+}
+/// A batch of transactions built and proven by an external batch producer, included as is in a
+/// block. The request must be signed by an operator of the block producer.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubmitProvenBatchRequest {
+    /// Transactions of the batch, in the order they are batched. Until recursive proofs are
+    /// available the proof of the batch doesn't cover the proofs of the transactions, every
+    /// transaction comes with its own. The partner signatures are not accepted, batches don't use
+    /// the partner reservations.
+    #[prost(message, repeated, tag = "1")]
+    pub transactions: ::prost::alloc::vec::Vec<SubmitProvenTransactionRequest>,
+    /// Commitment to the transactions and created notes of the batch, output by the batch kernel.
+    #[prost(message, optional, tag = "2")]
+    pub commitment: ::core::option::Option<super::digest::Digest>,
+    /// Serialized proof of the execution of the batch kernel over the batch.
+    #[prost(bytes = "vec", tag = "3")]
+    pub proof: ::prost::alloc::vec::Vec<u8>,
+}
+/// An account updated by the block to prove.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockWitnessAccount {
+    #[prost(message, optional, tag = "1")]
+    pub account_id: ::core::option::Option<super::account::AccountId>,
+    /// Hash of the account before the block.
+    #[prost(message, optional, tag = "2")]
+    pub initial_state_hash: ::core::option::Option<super::digest::Digest>,
+    /// Hash of the account after the block.
+    #[prost(message, optional, tag = "3")]
+    pub final_state_hash: ::core::option::Option<super::digest::Digest>,
+    /// Authentication path of `initial_state_hash` against the account root of the previous block.
+    #[prost(message, optional, tag = "4")]
+    pub proof: ::core::option::Option<super::merkle::MerklePath>,
+}
+/// Root of the notes created by a batch of the block to prove.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockWitnessNotesRoot {
+    /// Index of the batch within the block.
+    #[prost(uint32, tag = "1")]
+    pub batch_index: u32,
+    #[prost(message, optional, tag = "2")]
+    pub created_notes_root: ::core::option::Option<super::digest::Digest>,
+}
+/// Inputs of the block kernel computing the header of a block.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProveBlockRequest {
+    /// Header of the previous block, the block is built upon.
+    #[prost(message, optional, tag = "1")]
+    pub prev_header: ::core::option::Option<super::block_header::BlockHeader>,
+    /// Peaks of the chain MMR as of the previous block, whose number of leaves is the number of the
+    /// previous block.
+    #[prost(message, repeated, tag = "2")]
+    pub chain_peaks: ::prost::alloc::vec::Vec<super::digest::Digest>,
+    /// Accounts updated by the block.
+    #[prost(message, repeated, tag = "3")]
+    pub updated_accounts: ::prost::alloc::vec::Vec<BlockWitnessAccount>,
+    /// Roots of the notes created by the batches of the block, for the batches which created notes.
+    #[prost(message, repeated, tag = "4")]
+    pub batch_created_notes_roots: ::prost::alloc::vec::Vec<BlockWitnessNotesRoot>,
+    /// Commitment to the proofs of the batches of the block.
+    #[prost(message, optional, tag = "5")]
+    pub batch_root: ::core::option::Option<super::digest::Digest>,
+    /// Timestamp of the block, in seconds since the UNIX epoch.
+    #[prost(uint64, tag = "6")]
+    pub timestamp: u64,
+    /// Hash of the proofs of the batches of the block.
+    #[prost(message, optional, tag = "7")]
+    pub proof_hash: ::core::option::Option<super::digest::Digest>,
+}
+/// Signature of a transaction by a partner of the block producer.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PartnerSignature {
+    /// Public key of the partner.
+    #[prost(message, optional, tag = "1")]
+    pub partner_key: ::core::option::Option<super::digest::Digest>,
+    /// RPO Falcon512 signature of the transaction ID by `partner_key`.
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPendingTransactionsByAccountRequest {
+    /// ID of the account whose pending transactions are requested.
+    #[prost(message, optional, tag = "1")]
+    pub account_id: ::core::option::Option<super::account::AccountId>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetLastBlockFailureRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetTransactionLatencyRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubmitNoteRecoveryRecordRequest {
+    /// The note, serialized as a `miden_objects::notes::Note`. It must have been created by a block
+    /// of the chain.
+    #[prost(bytes = "vec", tag = "1")]
+    pub note: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNoteRecoveryRecordsRequest {
+    /// Hashes of the notes whose records are requested.
+    #[prost(message, repeated, tag = "1")]
+    pub note_hashes: ::prost::alloc::vec::Vec<super::digest::Digest>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNoteAuthenticationPathRequest {
+    /// Number of the block in which the note was created.
+    #[prost(uint32, tag = "1")]
+    pub block_num: u32,
+    /// Index of the note in the block's note tree.
+    #[prost(uint32, tag = "2")]
+    pub note_index: u32,
 }
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetGenesisBlockRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNodeInfoRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPruningHorizonRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetBackfillStatusRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetStartupProgressRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListNullifiersRequest {}
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -112,3 +423,125 @@ pub struct ListAccountsRequest {}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListNotesRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportNotesRequest {
+    /// Specifies the tags of the notes to export, as the 16 high bits of the tags.
+    #[prost(uint32, repeated, tag = "1")]
+    pub note_tags: ::prost::alloc::vec::Vec<u32>,
+    /// First block whose notes are exported.
+    #[prost(uint32, tag = "2")]
+    pub from_block: u32,
+    /// Last block whose notes are exported, included.
+    #[prost(uint32, tag = "3")]
+    pub to_block: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubscribeNullifiersRequest {
+    /// Nullifiers to watch, in full.
+    #[prost(message, repeated, tag = "1")]
+    pub nullifiers: ::prost::alloc::vec::Vec<super::digest::Digest>,
+    /// First block whose nullifiers are reported. A subscription is resumed by sending the number of
+    /// the last block notified, which may then be notified again.
+    #[prost(uint32, tag = "2")]
+    pub from_block: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubscribeChainTipRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNoteTagStatsRequest {
+    /// Tags to report the activity of, as the 16 high bits of the tags.
+    #[prost(uint32, repeated, tag = "1")]
+    pub note_tags: ::prost::alloc::vec::Vec<u32>,
+    /// Number of latest blocks the activity is reported over, bounded by the window kept by the
+    /// store. The whole window is used if zero.
+    #[prost(uint32, tag = "2")]
+    pub num_blocks: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetUnconsumedNotesRequest {
+    /// Tags of the notes to list, as the 16 high bits of the tags.
+    #[prost(uint32, repeated, tag = "1")]
+    pub note_tags: ::prost::alloc::vec::Vec<u32>,
+    /// First block whose notes are listed. The notes of many blocks are listed over several
+    /// requests, each one sending the `next_block_num` of the previous response.
+    #[prost(uint32, tag = "2")]
+    pub from_block: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetProposedNotesRequest {
+    /// Hashes of the notes created by the proposed block to return, all of its notes if empty.
+    #[prost(message, repeated, tag = "1")]
+    pub note_hashes: ::prost::alloc::vec::Vec<super::digest::Digest>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetStateCommitmentRequest {
+    /// Block the state is committed at, the latest block if not provided.
+    #[prost(uint32, optional, tag = "1")]
+    pub block_num: ::core::option::Option<u32>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetQueueDepthRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetMempoolStatusRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetChainCountersRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetStartupAuditRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRestartStatusRequest {}
+/// Signed by an operator for the `PauseIntake` operation.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PauseIntakeRequest {
+    /// Whether new transactions are refused, `false` resumes their admission.
+    #[prost(bool, tag = "1")]
+    pub paused: bool,
+}
+/// Signed by an operator for the `SnapshotQueue` operation.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotQueueRequest {}
+/// Signed by an operator for the `Shutdown` operation.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ShutdownRequest {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetDatabaseSizeRequest {}
+/// Signed by an operator of the RPC for the `GetUsageReport` operation.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetUsageReportRequest {
+    /// Day of the report as `YYYY-MM-DD`, the current UTC day if empty.
+    #[prost(string, tag = "1")]
+    pub day: ::prost::alloc::string::String,
+}
@@ -5,10 +5,38 @@ pub struct ApplyBlockResponse {}
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ApplyBlocksResponse {
+    /// Number of the latest block in the chain, i.e. the last applied block.
+    #[prost(uint32, tag = "1")]
+    pub chain_tip: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProposeBlockResponse {
+    /// Hash of the staged block, to be committed with `CommitBlock`.
+    #[prost(message, optional, tag = "1")]
+    pub block_hash: ::core::option::Option<super::digest::Digest>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CommitBlockResponse {
+    /// Number of the committed block.
+    #[prost(uint32, tag = "1")]
+    pub block_num: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CheckNullifiersResponse {
     /// Each requested nullifier has its corresponding nullifier proof at the same position.
     #[prost(message, repeated, tag = "1")]
     pub proofs: ::prost::alloc::vec::Vec<super::tsmt::NullifierProof>,
+    /// Each nullifier requested without proof has the block at which it has been consumed at the
+    /// same position, zero if not consumed.
+    #[prost(uint32, repeated, tag = "2")]
+    pub block_nums: ::prost::alloc::vec::Vec<u32>,
 }
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -16,6 +44,49 @@ pub struct CheckNullifiersResponse {
 pub struct GetBlockHeaderByNumberResponse {
     #[prost(message, optional, tag = "1")]
     pub block_header: ::core::option::Option<super::block_header::BlockHeader>,
+    /// Number of the reference block, its header's chain root commits to the first `chain_length`
+    /// blocks. Only set if an MMR proof was requested.
+    #[prost(uint32, optional, tag = "2")]
+    pub chain_length: ::core::option::Option<u32>,
+    /// Authentication path of the block header in the chain MMR of the reference block. Missing if
+    /// not requested, or if the requested block is the reference block itself.
+    #[prost(message, optional, tag = "3")]
+    pub mmr_path: ::core::option::Option<super::merkle::MerklePath>,
+    /// Set if the chain tip is still the one given by `if_none_match`, in which case no other field
+    /// is set.
+    #[prost(bool, tag = "4")]
+    pub not_modified: bool,
+    /// Versions of the transaction kernels the transactions of the block were proven with. Empty
+    /// for the blocks applied before the versions were recorded.
+    #[prost(uint32, repeated, tag = "5")]
+    pub tx_kernel_versions: ::prost::alloc::vec::Vec<u32>,
+    /// Number of the latest block in the chain.
+    #[prost(uint32, tag = "6")]
+    pub chain_tip: u32,
+    /// Number of the latest block considered final by the node, `safe_depth` blocks below the
+    /// chain tip, see `GetNodeInfoResponse.safe_depth`.
+    #[prost(uint32, tag = "7")]
+    pub safe_chain_tip: u32,
+    /// Hash of the latest block in the chain, the block `chain_tip`. The MMR proof, if requested,
+    /// is against its chain root.
+    #[prost(message, optional, tag = "8")]
+    pub chain_tip_hash: ::core::option::Option<super::digest::Digest>,
+    /// Signature of the block by the block producer which produced it, missing if the block was
+    /// not signed.
+    #[prost(message, optional, tag = "9")]
+    pub signature: ::core::option::Option<super::block_header::BlockSignature>,
+    /// Whether the transactions of the block were ordered canonically by the block producer, see
+    /// `ApplyBlockRequest.canonical_tx_ordering`. False for the blocks applied before the ordering
+    /// was recorded.
+    #[prost(bool, tag = "10")]
+    pub canonical_tx_ordering: bool,
+    /// Attestation of the time the block was produced by a time authority, received with the next
+    /// block, so missing for the chain tip and if the next block was applied without one. Its
+    /// consumers must check the authority is one they trust.
+    #[prost(message, optional, tag = "11")]
+    pub timestamp_attestation: ::core::option::Option<
+        super::block_header::TimestampAttestation,
+    >,
 }
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -40,6 +111,17 @@ pub struct NullifierUpdate {
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConsumedNoteUpdate {
+    #[prost(message, optional, tag = "1")]
+    pub note_hash: ::core::option::Option<super::digest::Digest>,
+    #[prost(message, optional, tag = "2")]
+    pub nullifier: ::core::option::Option<super::digest::Digest>,
+    #[prost(uint32, tag = "3")]
+    pub block_num: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SyncStateResponse {
     /// number of the latest block in the chain
     #[prost(uint32, tag = "1")]
@@ -59,6 +141,88 @@ pub struct SyncStateResponse {
     /// a list of nullifiers created between `block_num + 1` and `block_header.block_num`
     #[prost(message, repeated, tag = "7")]
     pub nullifiers: ::prost::alloc::vec::Vec<NullifierUpdate>,
+    /// a list of the requested notes consumed between `block_num + 1` and `block_header.block_num`
+    #[prost(message, repeated, tag = "8")]
+    pub consumed_notes: ::prost::alloc::vec::Vec<ConsumedNoteUpdate>,
+    /// opaque token resuming the sync after `block_header.block_num`, see
+    /// `SyncStateRequest.resume_token`
+    #[prost(bytes = "vec", tag = "9")]
+    pub resume_token: ::prost::alloc::vec::Vec<u8>,
+    /// number of the latest block considered final by the node, see
+    /// `GetBlockHeaderByNumberResponse.safe_chain_tip`
+    #[prost(uint32, tag = "10")]
+    pub safe_chain_tip: u32,
+}
+/// The updates of a single block, as returned by `SyncAll`.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockSyncUpdate {
+    #[prost(uint32, tag = "1")]
+    pub block_num: u32,
+    /// Latest hashes of the requested accounts, updated in this block.
+    #[prost(message, repeated, tag = "2")]
+    pub accounts: ::prost::alloc::vec::Vec<AccountHashUpdate>,
+    /// Notes matching the requested tags created in this block, with their Merkle paths from the
+    /// block's `note_root`.
+    #[prost(message, repeated, tag = "3")]
+    pub notes: ::prost::alloc::vec::Vec<super::note::NoteSyncRecord>,
+    /// Requested nullifiers consumed in this block.
+    #[prost(message, repeated, tag = "4")]
+    pub nullifiers: ::prost::alloc::vec::Vec<NullifierUpdate>,
+    /// Requested notes consumed in this block.
+    #[prost(message, repeated, tag = "5")]
+    pub consumed_notes: ::prost::alloc::vec::Vec<ConsumedNoteUpdate>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SyncAllResponse {
+    /// number of the latest block in the chain
+    #[prost(uint32, tag = "1")]
+    pub chain_tip: u32,
+    /// block header of the last block covered by the response, see `SyncStateResponse.block_header`
+    #[prost(message, optional, tag = "2")]
+    pub block_header: ::core::option::Option<super::block_header::BlockHeader>,
+    /// data needed to update the partial MMR from `block_num + 1` to `block_header.block_num`
+    #[prost(message, optional, tag = "3")]
+    pub mmr_delta: ::core::option::Option<super::mmr::MmrDelta>,
+    /// the updates up to `block_header.block_num`, one entry per block with at least one update,
+    /// ordered by block number
+    #[prost(message, repeated, tag = "4")]
+    pub blocks: ::prost::alloc::vec::Vec<BlockSyncUpdate>,
+    /// number of the latest block considered final by the node, see
+    /// `GetBlockHeaderByNumberResponse.safe_chain_tip`
+    #[prost(uint32, tag = "5")]
+    pub safe_chain_tip: u32,
+}
+/// Latest state of an account, as returned by `SyncAccounts`.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AccountSyncRecord {
+    #[prost(message, optional, tag = "1")]
+    pub account_id: ::core::option::Option<super::account::AccountId>,
+    /// Hash of the account as of the chain tip.
+    #[prost(message, optional, tag = "2")]
+    pub account_hash: ::core::option::Option<super::digest::Digest>,
+    /// Number of the block which last updated the account.
+    #[prost(uint32, tag = "3")]
+    pub block_num: u32,
+    /// Path of the account hash in the account tree committed to by the chain tip's `account_root`.
+    #[prost(message, optional, tag = "4")]
+    pub proof: ::core::option::Option<super::merkle::MerklePath>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SyncAccountsResponse {
+    /// Header of the chain tip, which the proofs of the accounts are anchored to.
+    #[prost(message, optional, tag = "1")]
+    pub block_header: ::core::option::Option<super::block_header::BlockHeader>,
+    /// The requested accounts updated after `block_num`, ordered by the block of their latest update.
+    #[prost(message, repeated, tag = "2")]
+    pub accounts: ::prost::alloc::vec::Vec<AccountSyncRecord>,
 }
 /// An account returned as a response to the GetBlockInputs
 #[derive(Eq, PartialOrd, Ord, Hash)]
@@ -69,6 +233,7 @@ pub struct AccountBlockInputRecord {
     pub account_id: ::core::option::Option<super::account::AccountId>,
     #[prost(message, optional, tag = "2")]
     pub account_hash: ::core::option::Option<super::digest::Digest>,
+    /// Empty when the authentication paths are sent as a `GetBlockInputsResponse.account_proofs`.
     #[prost(message, optional, tag = "3")]
     pub proof: ::core::option::Option<super::merkle::MerklePath>,
 }
@@ -98,6 +263,10 @@ pub struct GetBlockInputsResponse {
     /// The requested nullifiers and their authentication paths
     #[prost(message, repeated, tag = "4")]
     pub nullifiers: ::prost::alloc::vec::Vec<NullifierBlockInputRecord>,
+    /// Authentication paths of all the `account_states`, against the account root of the above
+    /// block header
+    #[prost(message, optional, tag = "5")]
+    pub account_proofs: ::core::option::Option<super::merkle::MerkleMultiproof>,
 }
 /// An account returned as a response to the GetTransactionInputs
 #[derive(Eq, PartialOrd, Ord, Hash)]
@@ -109,6 +278,10 @@ pub struct AccountTransactionInputRecord {
     /// The latest account hash, zero hash if the account doesn't exist.
     #[prost(message, optional, tag = "2")]
     pub account_hash: ::core::option::Option<super::digest::Digest>,
+    /// Serialized latest state of the account, empty if the account is private or its state is
+    /// unknown to the store.
+    #[prost(bytes = "vec", tag = "3")]
+    pub details: ::prost::alloc::vec::Vec<u8>,
 }
 /// A nullifier returned as a response to the GetTransactionInputs
 #[derive(Eq, PartialOrd, Ord, Hash)]
@@ -129,11 +302,221 @@ pub struct GetTransactionInputsResponse {
     pub account_state: ::core::option::Option<AccountTransactionInputRecord>,
     #[prost(message, repeated, tag = "2")]
     pub nullifiers: ::prost::alloc::vec::Vec<NullifierTransactionInputRecord>,
+    /// Number of the block the transaction was executed against, missing if the block is unknown.
+    #[prost(uint32, optional, tag = "3")]
+    pub block_ref_num: ::core::option::Option<u32>,
+}
+/// Acknowledgement of a transaction accepted by the block producer, signed by the node.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubmissionReceipt {
+    /// ID of the accepted transaction.
+    #[prost(message, optional, tag = "1")]
+    pub tx_id: ::core::option::Option<super::digest::Digest>,
+    /// Time at which the transaction was accepted, in milliseconds since the UNIX epoch.
+    #[prost(uint64, tag = "2")]
+    pub received_at: u64,
+    /// Position of the transaction in the queue when it was accepted.
+    #[prost(uint32, tag = "3")]
+    pub queue_position: u32,
+    /// Public key of the node which signed the receipt.
+    #[prost(message, optional, tag = "4")]
+    pub node_key: ::core::option::Option<super::digest::Digest>,
+    /// RPO Falcon512 signature by `node_key` of the hash of the submission receipt domain tag, the
+    /// chain id and the three fields above.
+    #[prost(bytes = "vec", tag = "5")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubmitProvenTransactionResponse {
+    /// Receipt of the transaction, missing if the node doesn't sign receipts.
+    #[prost(message, optional, tag = "1")]
+    pub receipt: ::core::option::Option<SubmissionReceipt>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubmitProvenBatchResponse {
+    /// ID of the batch, the BLAKE3 hash of the IDs of its transactions.
+    #[prost(bytes = "vec", tag = "1")]
+    pub batch_id: ::prost::alloc::vec::Vec<u8>,
+    /// Root of the tree of the notes created by the batch.
+    #[prost(message, optional, tag = "2")]
+    pub created_notes_root: ::core::option::Option<super::digest::Digest>,
+}
+/// A transaction which was accepted by the block producer but is not yet part of a batch.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PendingTransaction {
+    #[prost(message, optional, tag = "1")]
+    pub tx_id: ::core::option::Option<super::digest::Digest>,
+    #[prost(message, optional, tag = "2")]
+    pub initial_account_hash: ::core::option::Option<super::digest::Digest>,
+    #[prost(message, optional, tag = "3")]
+    pub final_account_hash: ::core::option::Option<super::digest::Digest>,
+    /// Position of the transaction in the queue, zero being the next transaction to be batched.
+    #[prost(uint32, tag = "4")]
+    pub queue_position: u32,
+}
+/// A transaction which was accepted by the block producer, then dropped before being batched. The
+/// transaction will not be included in a block unless it is submitted again.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DroppedTransaction {
+    #[prost(message, optional, tag = "1")]
+    pub tx_id: ::core::option::Option<super::digest::Digest>,
+    /// Reason the transaction was dropped: `evicted`, `conflicting`, `invalid_proof`,
+    /// `batch_failures`, `expired` or `orphaned`.
+    #[prost(string, tag = "2")]
+    pub reason: ::prost::alloc::string::String,
+    /// Time the transaction was dropped, in milliseconds since the UNIX epoch.
+    #[prost(uint64, tag = "3")]
+    pub dropped_at: u64,
 }
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct SubmitProvenTransactionResponse {}
+pub struct GetPendingTransactionsByAccountResponse {
+    /// Pending transactions of the requested account, ordered by their queue position.
+    #[prost(message, repeated, tag = "1")]
+    pub transactions: ::prost::alloc::vec::Vec<PendingTransaction>,
+    /// Latest transactions of the requested account which were dropped, the oldest first.
+    #[prost(message, repeated, tag = "2")]
+    pub dropped_transactions: ::prost::alloc::vec::Vec<DroppedTransaction>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockFailure {
+    /// Number of the block which failed to be built, absent if the failure happened before the
+    /// chain tip was retrieved from the store.
+    #[prost(uint32, optional, tag = "1")]
+    pub block_num: ::core::option::Option<u32>,
+    /// Error which caused the failure.
+    #[prost(string, tag = "2")]
+    pub error: ::prost::alloc::string::String,
+    /// Number of batches the block contained.
+    #[prost(uint32, tag = "3")]
+    pub num_batches: u32,
+    /// Time of the failure, in milliseconds since the UNIX epoch.
+    #[prost(uint64, tag = "4")]
+    pub timestamp: u64,
+    /// Directory of the diagnostic dump on the block producer's host, absent if it wasn't kept.
+    #[prost(string, optional, tag = "5")]
+    pub dump_path: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetLastBlockFailureResponse {
+    /// Latest failure to build a block since the block producer started, if any.
+    #[prost(message, optional, tag = "1")]
+    pub failure: ::core::option::Option<BlockFailure>,
+}
+/// Percentiles of the durations of a stage, in microseconds.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LatencyPercentiles {
+    #[prost(uint64, tag = "1")]
+    pub p50: u64,
+    #[prost(uint64, tag = "2")]
+    pub p95: u64,
+    #[prost(uint64, tag = "3")]
+    pub p99: u64,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetTransactionLatencyResponse {
+    /// Number of latest committed transactions the percentiles are computed over.
+    #[prost(uint32, tag = "1")]
+    pub num_samples: u32,
+    /// From the submission of the transaction until its block is applied by the store.
+    #[prost(message, optional, tag = "2")]
+    pub end_to_end: ::core::option::Option<LatencyPercentiles>,
+    /// Verification of the transaction against the state of the chain.
+    #[prost(message, optional, tag = "3")]
+    pub verify: ::core::option::Option<LatencyPercentiles>,
+    /// Wait in the transaction queue, until the transaction is sent to the batch builder.
+    #[prost(message, optional, tag = "4")]
+    pub queue_wait: ::core::option::Option<LatencyPercentiles>,
+    /// Building of the batch.
+    #[prost(message, optional, tag = "5")]
+    pub batch: ::core::option::Option<LatencyPercentiles>,
+    /// Wait for a block, and proving of the block.
+    #[prost(message, optional, tag = "6")]
+    pub prove: ::core::option::Option<LatencyPercentiles>,
+    /// Application of the block by the store.
+    #[prost(message, optional, tag = "7")]
+    pub apply: ::core::option::Option<LatencyPercentiles>,
+    /// Number of transactions tracked until they are committed or dropped.
+    #[prost(uint32, tag = "8")]
+    pub num_in_flight: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNoteAuthenticationPathResponse {
+    /// Note's inclusion path, to be verified against the `note_root` of the block's header.
+    #[prost(message, optional, tag = "1")]
+    pub merkle_path: ::core::option::Option<super::merkle::MerklePath>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubmitNoteRecoveryRecordResponse {
+    /// The record retained by the node.
+    #[prost(message, optional, tag = "1")]
+    pub record: ::core::option::Option<super::note::NoteRecoveryRecord>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNoteRecoveryRecordsResponse {
+    /// Records of the requested notes, notes without a record are omitted.
+    #[prost(message, repeated, tag = "1")]
+    pub records: ::prost::alloc::vec::Vec<super::note::NoteRecoveryRecord>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetGenesisBlockResponse {
+    /// Header of the genesis block.
+    #[prost(message, optional, tag = "1")]
+    pub block_header: ::core::option::Option<super::block_header::BlockHeader>,
+    /// Accounts of the genesis block, each one an `Account` serialized as in the genesis file.
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub accounts: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNodeInfoResponse {
+    /// Version of the protocol used between the node's components.
+    #[prost(uint32, tag = "1")]
+    pub protocol_version: u32,
+    /// Hash of the genesis block of the chain served by the store.
+    #[prost(message, optional, tag = "2")]
+    pub genesis_hash: ::core::option::Option<super::digest::Digest>,
+    /// Version of the store's software.
+    #[prost(string, tag = "3")]
+    pub version: ::prost::alloc::string::String,
+    /// Identifier of the chain served by the store.
+    #[prost(uint32, tag = "4")]
+    pub chain_id: u32,
+    /// Policy of the note tags accepted by the store.
+    #[prost(message, optional, tag = "5")]
+    pub tag_policy: ::core::option::Option<super::note::NoteTagPolicy>,
+    /// Number of blocks below the chain tip after which the node considers blocks final.
+    #[prost(uint32, tag = "6")]
+    pub safe_depth: u32,
+}
 #[derive(Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -158,3 +541,385 @@ pub struct ListNotesResponse {
     #[prost(message, repeated, tag = "1")]
     pub notes: ::prost::alloc::vec::Vec<super::note::Note>,
 }
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportNotesResponse {
+    /// The matching notes, with the headers of their blocks.
+    #[prost(message, optional, tag = "1")]
+    pub archive: ::core::option::Option<super::note::NoteArchive>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPruningHorizonResponse {
+    /// The oldest block whose notes are retained by the store, transactions consuming notes of older
+    /// blocks can no longer be verified.
+    #[prost(uint32, tag = "1")]
+    pub block_num: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackfillStatus {
+    /// Name of the backfill.
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// Number of the first block which wasn't processed yet.
+    #[prost(uint32, tag = "2")]
+    pub next_block_num: u32,
+    /// Whether the backfill processed every block it had to.
+    #[prost(bool, tag = "3")]
+    pub completed: bool,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetBackfillStatusResponse {
+    /// Progress of every backfill, completed or not.
+    #[prost(message, repeated, tag = "1")]
+    pub backfills: ::prost::alloc::vec::Vec<BackfillStatus>,
+    /// Number of the latest block of the chain.
+    #[prost(uint32, tag = "2")]
+    pub chain_tip: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProveBlockResponse {
+    /// Header of the proven block.
+    #[prost(message, optional, tag = "1")]
+    pub block_header: ::core::option::Option<super::block_header::BlockHeader>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetStartupProgressResponse {
+    /// Stage of the startup, `loaded` once every endpoint is served.
+    #[prost(string, tag = "1")]
+    pub stage: ::prost::alloc::string::String,
+    /// Number of rows inserted into the in-memory trees so far.
+    #[prost(uint64, tag = "2")]
+    pub rows_loaded: u64,
+    /// Number of rows to read from the database and insert into the in-memory trees, 0 until they
+    /// are counted.
+    #[prost(uint64, tag = "3")]
+    pub total_rows: u64,
+    /// Percentage of the work done, every row being read then inserted into the in-memory trees.
+    #[prost(uint32, tag = "4")]
+    pub percent_complete: u32,
+    /// Estimated number of seconds until the state is loaded, unset until a row was read.
+    #[prost(uint64, optional, tag = "5")]
+    pub eta_s: ::core::option::Option<u64>,
+    /// Number of seconds spent reading the rows and building the in-memory trees so far.
+    #[prost(uint64, tag = "6")]
+    pub elapsed_s: u64,
+    /// Number of rows read from the database so far.
+    #[prost(uint64, tag = "7")]
+    pub rows_read: u64,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubscribeNullifiersResponse {
+    /// Number of the block which consumed the nullifiers.
+    #[prost(uint32, tag = "1")]
+    pub block_num: u32,
+    /// The watched nullifiers consumed by the block.
+    #[prost(message, repeated, tag = "2")]
+    pub nullifiers: ::prost::alloc::vec::Vec<super::digest::Digest>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubscribeChainTipResponse {
+    /// Number of the latest block in the chain.
+    #[prost(uint32, tag = "1")]
+    pub block_num: u32,
+    /// Hash of the latest block in the chain.
+    #[prost(message, optional, tag = "2")]
+    pub block_hash: ::core::option::Option<super::digest::Digest>,
+}
+/// Notes created with a tag over the blocks covered by a `GetNoteTagStatsResponse`.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NoteTagStats {
+    /// Tag, as the 16 high bits of the tags.
+    #[prost(uint32, tag = "1")]
+    pub tag: u32,
+    /// Number of notes created with the tag.
+    #[prost(uint32, tag = "2")]
+    pub num_notes: u32,
+    /// Number of blocks with at least one note created with the tag.
+    #[prost(uint32, tag = "3")]
+    pub num_blocks: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetQueueDepthResponse {
+    /// Queued transactions consuming at least one note.
+    #[prost(uint32, tag = "1")]
+    pub consuming: u32,
+    /// Queued transactions which don't consume any note.
+    #[prost(uint32, tag = "2")]
+    pub creating: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetMempoolStatusResponse {
+    /// Transactions waiting in the queue to be batched.
+    #[prost(uint32, tag = "1")]
+    pub queued_transactions: u32,
+    /// Batches sent to the batch builder which are not built yet.
+    #[prost(uint32, tag = "2")]
+    pub batches_in_flight: u32,
+    /// Batches built, waiting to be included in a block.
+    #[prost(uint32, tag = "3")]
+    pub ready_batches: u32,
+    /// Accounts modified by the transactions in flight, i.e. accepted but not yet included in a
+    /// block. The next transactions of these accounts must start from the final state of the
+    /// latest one.
+    #[prost(uint32, tag = "4")]
+    pub locked_accounts: u32,
+    /// Nullifiers of the notes consumed by the transactions in flight.
+    #[prost(uint32, tag = "5")]
+    pub reserved_nullifiers: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetChainCountersResponse {
+    /// Number of the latest block in the chain, the block the counters are taken at.
+    #[prost(uint32, tag = "1")]
+    pub block_num: u32,
+    /// Hash of the latest block in the chain.
+    #[prost(message, optional, tag = "2")]
+    pub block_hash: ::core::option::Option<super::digest::Digest>,
+    /// Root of the account tree held by the store, which must be the account root of the latest
+    /// block.
+    #[prost(message, optional, tag = "3")]
+    pub account_root: ::core::option::Option<super::digest::Digest>,
+    /// Number of accounts of the chain.
+    #[prost(uint64, tag = "4")]
+    pub num_accounts: u64,
+    /// Number of notes created by all blocks.
+    #[prost(uint64, tag = "5")]
+    pub num_notes: u64,
+    /// Number of nullifiers produced by all blocks.
+    #[prost(uint64, tag = "6")]
+    pub num_nullifiers: u64,
+    /// Number of the blocks applied since the store started which were flagged as suspicious.
+    #[prost(uint64, tag = "7")]
+    pub num_suspicious_blocks: u64,
+    /// Number of the anomalies found in the blocks applied since the store started.
+    #[prost(uint64, tag = "8")]
+    pub num_block_anomalies: u64,
+    /// Block of the latest state commitment published by the store, see `GetStateCommitment`.
+    #[prost(uint32, tag = "9")]
+    pub commitment_block_num: u32,
+    /// Digest of the latest state commitment published by the store, missing if none was published.
+    #[prost(message, optional, tag = "10")]
+    pub commitment: ::core::option::Option<super::digest::Digest>,
+    /// Number of the state commitments taken at the chain tip since the store started whose roots
+    /// and counts, read from the store's in-memory state, didn't match its database.
+    #[prost(uint64, tag = "11")]
+    pub num_diverged_commitments: u64,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetStartupAuditResponse {
+    /// Latest block of the chain when the block producer started.
+    #[prost(message, optional, tag = "1")]
+    pub chain_tip: ::core::option::Option<super::block_header::BlockHeader>,
+    /// Counters of the store, taken at the same block. Missing if the block producer has no operator
+    /// key of a remote store.
+    #[prost(message, optional, tag = "2")]
+    pub counters: ::core::option::Option<GetChainCountersResponse>,
+    /// Time at which the audit passed, in seconds since the UNIX epoch.
+    #[prost(uint64, tag = "3")]
+    pub audited_at: u64,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRestartStatusResponse {
+    /// Whether new transactions are refused.
+    #[prost(bool, tag = "1")]
+    pub intake_paused: bool,
+    /// Transactions waiting in the queue.
+    #[prost(uint32, tag = "2")]
+    pub queued_transactions: u32,
+    /// Batches being built out of transactions taken from the queue.
+    #[prost(uint32, tag = "3")]
+    pub batches_in_flight: u32,
+    /// Batches built, waiting to be included in a block.
+    #[prost(uint32, tag = "4")]
+    pub ready_batches: u32,
+    /// Whether block production stopped, the block producer exiting.
+    #[prost(bool, tag = "5")]
+    pub block_production_stopped: bool,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PauseIntakeResponse {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotQueueResponse {
+    /// Transactions taken out of the queue into the snapshot.
+    #[prost(uint32, tag = "1")]
+    pub num_transactions: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ShutdownResponse {}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNoteTagStatsResponse {
+    /// Number of the latest block in the chain, the last block covered by the stats.
+    #[prost(uint32, tag = "1")]
+    pub chain_tip: u32,
+    /// First block covered by the stats.
+    #[prost(uint32, tag = "2")]
+    pub from_block: u32,
+    /// Number of notes created over the covered blocks, with any tag.
+    #[prost(uint32, tag = "3")]
+    pub total_notes: u32,
+    /// Activity of the requested tags, in the order of the request. Tags without any note are
+    /// reported with zero notes.
+    #[prost(message, repeated, tag = "4")]
+    pub tags: ::prost::alloc::vec::Vec<NoteTagStats>,
+    /// Transactions waiting in the block producer's queue, by lane. Only set by the RPC.
+    #[prost(message, optional, tag = "5")]
+    pub queue_depth: ::core::option::Option<GetQueueDepthResponse>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetUnconsumedNotesResponse {
+    /// Number of the latest block in the chain, the listed notes weren't consumed by any block up to
+    /// this one.
+    #[prost(uint32, tag = "1")]
+    pub chain_tip: u32,
+    /// Notes matching the tags whose consumption wasn't recorded, without their merkle path, ordered
+    /// by block number and note index.
+    #[prost(message, repeated, tag = "2")]
+    pub notes: ::prost::alloc::vec::Vec<super::note::Note>,
+    /// First block whose notes weren't listed, the block after the chain tip once all the blocks are
+    /// listed.
+    #[prost(uint32, tag = "3")]
+    pub next_block_num: u32,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetProposedNotesResponse {
+    /// Header of the block waiting to be committed, missing if no block is proposed.
+    #[prost(message, optional, tag = "1")]
+    pub block_header: ::core::option::Option<super::block_header::BlockHeader>,
+    /// Requested notes created by the proposed block, with their merkle path against the note root
+    /// of the header, ordered by note index. The paths are provisional, they only hold once the
+    /// block is committed.
+    #[prost(message, repeated, tag = "2")]
+    pub notes: ::prost::alloc::vec::Vec<super::note::Note>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetStateCommitmentResponse {
+    /// Header of the block the state is committed at, whose roots commit to the chain, accounts,
+    /// nullifiers and notes.
+    #[prost(message, optional, tag = "1")]
+    pub block_header: ::core::option::Option<super::block_header::BlockHeader>,
+    /// Number of notes created up to the block.
+    #[prost(uint64, tag = "2")]
+    pub num_notes: u64,
+    /// Number of nullifiers produced up to the block.
+    #[prost(uint64, tag = "3")]
+    pub num_nullifiers: u64,
+    /// Number of notes created up to the block which were consumed up to the block, as linked to their
+    /// nullifier.
+    #[prost(uint64, tag = "4")]
+    pub num_consumed_notes: u64,
+    /// Digest of the block hash, the chain and account roots and the counts, equal on the stores
+    /// holding the same state.
+    #[prost(message, optional, tag = "5")]
+    pub commitment: ::core::option::Option<super::digest::Digest>,
+    /// Root of the chain MMR before the block. At the chain tip, the roots and the counts are read
+    /// from the store's in-memory state, otherwise from its database.
+    #[prost(message, optional, tag = "6")]
+    pub chain_root: ::core::option::Option<super::digest::Digest>,
+    /// Root of the account tree after the block.
+    #[prost(message, optional, tag = "7")]
+    pub account_root: ::core::option::Option<super::digest::Digest>,
+    /// Root of the nullifier tree after the block, only provided at the chain tip. It isn't
+    /// digested, since the block headers don't commit to it yet.
+    #[prost(message, optional, tag = "8")]
+    pub nullifier_root: ::core::option::Option<super::digest::Digest>,
+}
+/// Rows and space used by a table of the store's database.
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TableSize {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub num_rows: u64,
+    /// Bytes used by the table and its indexes, absent if the database doesn't report them.
+    #[prost(uint64, optional, tag = "3")]
+    pub num_bytes: ::core::option::Option<u64>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetDatabaseSizeResponse {
+    /// Size of the database file, in bytes.
+    #[prost(uint64, tag = "1")]
+    pub database_size: u64,
+    /// Size of the write-ahead log of the database, in bytes.
+    #[prost(uint64, tag = "2")]
+    pub wal_size: u64,
+    /// Every table of the database, ordered by name.
+    #[prost(message, repeated, tag = "3")]
+    pub tables: ::prost::alloc::vec::Vec<TableSize>,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClientUsage {
+    /// Hex encoded SHA-256 of the API key the client is accounted to, empty for the other clients.
+    #[prost(string, tag = "1")]
+    pub api_key_sha256: ::prost::alloc::string::String,
+    /// Address of the client, empty for the clients accounted to an API key or connected through a
+    /// Unix domain socket.
+    #[prost(string, tag = "2")]
+    pub ip: ::prost::alloc::string::String,
+    /// Whether the usage is the one of the clients beyond the maximum number accounted for the day.
+    #[prost(bool, tag = "3")]
+    pub overflow: bool,
+    /// Requests received from the client.
+    #[prost(uint64, tag = "4")]
+    pub requests: u64,
+    /// Bytes of the responses served to the client.
+    #[prost(uint64, tag = "5")]
+    pub bytes_served: u64,
+}
+#[derive(Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetUsageReportResponse {
+    /// Day of the report as `YYYY-MM-DD`.
+    #[prost(string, tag = "1")]
+    pub day: ::prost::alloc::string::String,
+    /// Usage of every client during the day, including the usage not written to the report yet.
+    #[prost(message, repeated, tag = "2")]
+    pub clients: ::prost::alloc::vec::Vec<ClientUsage>,
+}
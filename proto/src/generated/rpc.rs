@@ -134,6 +134,135 @@ pub mod api_client {
                 .insert(GrpcMethod::new("rpc.Api", "GetBlockHeaderByNumber"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn get_note_authentication_path(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetNoteAuthenticationPathRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetNoteAuthenticationPathResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rpc.Api/GetNoteAuthenticationPath",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("rpc.Api", "GetNoteAuthenticationPath"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_note_recovery_records(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetNoteRecoveryRecordsRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetNoteRecoveryRecordsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rpc.Api/GetNoteRecoveryRecords",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("rpc.Api", "GetNoteRecoveryRecords"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_note_tag_stats(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetNoteTagStatsRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetNoteTagStatsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/rpc.Api/GetNoteTagStats");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("rpc.Api", "GetNoteTagStats"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_proposed_notes(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetProposedNotesRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetProposedNotesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/rpc.Api/GetProposedNotes");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("rpc.Api", "GetProposedNotes"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_unconsumed_notes(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetUnconsumedNotesRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetUnconsumedNotesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rpc.Api/GetUnconsumedNotes",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("rpc.Api", "GetUnconsumedNotes"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn sync_state(
             &mut self,
             request: impl tonic::IntoRequest<super::super::requests::SyncStateRequest>,
@@ -156,6 +285,81 @@ pub mod api_client {
             req.extensions_mut().insert(GrpcMethod::new("rpc.Api", "SyncState"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn sync_all(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::SyncAllRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SyncAllResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/rpc.Api/SyncAll");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("rpc.Api", "SyncAll"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn sync_accounts(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::SyncAccountsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SyncAccountsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/rpc.Api/SyncAccounts");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("rpc.Api", "SyncAccounts"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn subscribe_nullifiers(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::SubscribeNullifiersRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<
+                tonic::codec::Streaming<
+                    super::super::responses::SubscribeNullifiersResponse,
+                >,
+            >,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rpc.Api/SubscribeNullifiers",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("rpc.Api", "SubscribeNullifiers"));
+            self.inner.server_streaming(req, path, codec).await
+        }
         pub async fn submit_proven_transaction(
             &mut self,
             request: impl tonic::IntoRequest<
@@ -183,50 +387,290 @@ pub mod api_client {
                 .insert(GrpcMethod::new("rpc.Api", "SubmitProvenTransaction"));
             self.inner.unary(req, path, codec).await
         }
-    }
-}
-/// Generated server implementations.
-pub mod api_server {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with ApiServer.
-    #[async_trait]
-    pub trait Api: Send + Sync + 'static {
-        async fn check_nullifiers(
-            &self,
-            request: tonic::Request<super::super::requests::CheckNullifiersRequest>,
+        pub async fn submit_proven_batch(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::SubmitProvenBatchRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::CheckNullifiersResponse>,
+            tonic::Response<super::super::responses::SubmitProvenBatchResponse>,
             tonic::Status,
-        >;
-        async fn get_block_header_by_number(
-            &self,
-            request: tonic::Request<
-                super::super::requests::GetBlockHeaderByNumberRequest,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rpc.Api/SubmitProvenBatch",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("rpc.Api", "SubmitProvenBatch"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn submit_note_recovery_record(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::SubmitNoteRecoveryRecordRequest,
             >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::GetBlockHeaderByNumberResponse>,
+            tonic::Response<super::super::responses::SubmitNoteRecoveryRecordResponse>,
             tonic::Status,
-        >;
-        async fn sync_state(
-            &self,
-            request: tonic::Request<super::super::requests::SyncStateRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rpc.Api/SubmitNoteRecoveryRecord",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("rpc.Api", "SubmitNoteRecoveryRecord"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_pending_transactions_by_account(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetPendingTransactionsByAccountRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::SyncStateResponse>,
+            tonic::Response<
+                super::super::responses::GetPendingTransactionsByAccountResponse,
+            >,
             tonic::Status,
-        >;
-        async fn submit_proven_transaction(
-            &self,
-            request: tonic::Request<
-                super::super::requests::SubmitProvenTransactionRequest,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/rpc.Api/GetPendingTransactionsByAccount",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("rpc.Api", "GetPendingTransactionsByAccount"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_mempool_status(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetMempoolStatusRequest,
             >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::SubmitProvenTransactionResponse>,
+            tonic::Response<super::super::responses::GetMempoolStatusResponse>,
             tonic::Status,
-        >;
-    }
-    #[derive(Debug)]
-    pub struct ApiServer<T: Api> {
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/rpc.Api/GetMempoolStatus");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("rpc.Api", "GetMempoolStatus"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_usage_report(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetUsageReportRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetUsageReportResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/rpc.Api/GetUsageReport");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("rpc.Api", "GetUsageReport"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod api_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with ApiServer.
+    #[async_trait]
+    pub trait Api: Send + Sync + 'static {
+        async fn check_nullifiers(
+            &self,
+            request: tonic::Request<super::super::requests::CheckNullifiersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::CheckNullifiersResponse>,
+            tonic::Status,
+        >;
+        async fn get_block_header_by_number(
+            &self,
+            request: tonic::Request<
+                super::super::requests::GetBlockHeaderByNumberRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetBlockHeaderByNumberResponse>,
+            tonic::Status,
+        >;
+        async fn get_note_authentication_path(
+            &self,
+            request: tonic::Request<
+                super::super::requests::GetNoteAuthenticationPathRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetNoteAuthenticationPathResponse>,
+            tonic::Status,
+        >;
+        async fn get_note_recovery_records(
+            &self,
+            request: tonic::Request<
+                super::super::requests::GetNoteRecoveryRecordsRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetNoteRecoveryRecordsResponse>,
+            tonic::Status,
+        >;
+        async fn get_note_tag_stats(
+            &self,
+            request: tonic::Request<super::super::requests::GetNoteTagStatsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetNoteTagStatsResponse>,
+            tonic::Status,
+        >;
+        async fn get_proposed_notes(
+            &self,
+            request: tonic::Request<super::super::requests::GetProposedNotesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetProposedNotesResponse>,
+            tonic::Status,
+        >;
+        async fn get_unconsumed_notes(
+            &self,
+            request: tonic::Request<super::super::requests::GetUnconsumedNotesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetUnconsumedNotesResponse>,
+            tonic::Status,
+        >;
+        async fn sync_state(
+            &self,
+            request: tonic::Request<super::super::requests::SyncStateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SyncStateResponse>,
+            tonic::Status,
+        >;
+        async fn sync_all(
+            &self,
+            request: tonic::Request<super::super::requests::SyncAllRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SyncAllResponse>,
+            tonic::Status,
+        >;
+        async fn sync_accounts(
+            &self,
+            request: tonic::Request<super::super::requests::SyncAccountsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SyncAccountsResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the SubscribeNullifiers method.
+        type SubscribeNullifiersStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<
+                    super::super::responses::SubscribeNullifiersResponse,
+                    tonic::Status,
+                >,
+            >
+            + Send
+            + 'static;
+        async fn subscribe_nullifiers(
+            &self,
+            request: tonic::Request<super::super::requests::SubscribeNullifiersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::SubscribeNullifiersStream>,
+            tonic::Status,
+        >;
+        async fn submit_proven_transaction(
+            &self,
+            request: tonic::Request<
+                super::super::requests::SubmitProvenTransactionRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SubmitProvenTransactionResponse>,
+            tonic::Status,
+        >;
+        async fn submit_proven_batch(
+            &self,
+            request: tonic::Request<super::super::requests::SubmitProvenBatchRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SubmitProvenBatchResponse>,
+            tonic::Status,
+        >;
+        async fn submit_note_recovery_record(
+            &self,
+            request: tonic::Request<
+                super::super::requests::SubmitNoteRecoveryRecordRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SubmitNoteRecoveryRecordResponse>,
+            tonic::Status,
+        >;
+        async fn get_pending_transactions_by_account(
+            &self,
+            request: tonic::Request<
+                super::super::requests::GetPendingTransactionsByAccountRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<
+                super::super::responses::GetPendingTransactionsByAccountResponse,
+            >,
+            tonic::Status,
+        >;
+        async fn get_mempool_status(
+            &self,
+            request: tonic::Request<super::super::requests::GetMempoolStatusRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetMempoolStatusResponse>,
+            tonic::Status,
+        >;
+        async fn get_usage_report(
+            &self,
+            request: tonic::Request<super::super::requests::GetUsageReportRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetUsageReportResponse>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct ApiServer<T: Api> {
         inner: _Inner<T>,
         accept_compression_encodings: EnabledCompressionEncodings,
         send_compression_encodings: EnabledCompressionEncodings,
@@ -306,13 +750,406 @@ pub mod api_server {
             match req.uri().path() {
                 "/rpc.Api/CheckNullifiers" => {
                     #[allow(non_camel_case_types)]
-                    struct CheckNullifiersSvc<T: Api>(pub Arc<T>);
+                    struct CheckNullifiersSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::CheckNullifiersRequest,
+                    > for CheckNullifiersSvc<T> {
+                        type Response = super::super::responses::CheckNullifiersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::CheckNullifiersRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::check_nullifiers(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CheckNullifiersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/GetBlockHeaderByNumber" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBlockHeaderByNumberSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetBlockHeaderByNumberRequest,
+                    > for GetBlockHeaderByNumberSvc<T> {
+                        type Response = super::super::responses::GetBlockHeaderByNumberResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetBlockHeaderByNumberRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_block_header_by_number(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBlockHeaderByNumberSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/GetNoteAuthenticationPath" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetNoteAuthenticationPathSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetNoteAuthenticationPathRequest,
+                    > for GetNoteAuthenticationPathSvc<T> {
+                        type Response = super::super::responses::GetNoteAuthenticationPathResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetNoteAuthenticationPathRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_note_authentication_path(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetNoteAuthenticationPathSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/GetNoteRecoveryRecords" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetNoteRecoveryRecordsSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetNoteRecoveryRecordsRequest,
+                    > for GetNoteRecoveryRecordsSvc<T> {
+                        type Response = super::super::responses::GetNoteRecoveryRecordsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetNoteRecoveryRecordsRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_note_recovery_records(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetNoteRecoveryRecordsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/GetNoteTagStats" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetNoteTagStatsSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetNoteTagStatsRequest,
+                    > for GetNoteTagStatsSvc<T> {
+                        type Response = super::super::responses::GetNoteTagStatsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetNoteTagStatsRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_note_tag_stats(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetNoteTagStatsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/GetProposedNotes" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetProposedNotesSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetProposedNotesRequest,
+                    > for GetProposedNotesSvc<T> {
+                        type Response = super::super::responses::GetProposedNotesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetProposedNotesRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_proposed_notes(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetProposedNotesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/GetUnconsumedNotes" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetUnconsumedNotesSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetUnconsumedNotesRequest,
+                    > for GetUnconsumedNotesSvc<T> {
+                        type Response = super::super::responses::GetUnconsumedNotesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetUnconsumedNotesRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_unconsumed_notes(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetUnconsumedNotesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/SyncState" => {
+                    #[allow(non_camel_case_types)]
+                    struct SyncStateSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::SyncStateRequest,
+                    > for SyncStateSvc<T> {
+                        type Response = super::super::responses::SyncStateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::SyncStateRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::sync_state(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SyncStateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/SyncAll" => {
+                    #[allow(non_camel_case_types)]
+                    struct SyncAllSvc<T: Api>(pub Arc<T>);
                     impl<
                         T: Api,
-                    > tonic::server::UnaryService<
-                        super::super::requests::CheckNullifiersRequest,
-                    > for CheckNullifiersSvc<T> {
-                        type Response = super::super::responses::CheckNullifiersResponse;
+                    > tonic::server::UnaryService<super::super::requests::SyncAllRequest>
+                    for SyncAllSvc<T> {
+                        type Response = super::super::responses::SyncAllResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -320,12 +1157,12 @@ pub mod api_server {
                         fn call(
                             &mut self,
                             request: tonic::Request<
-                                super::super::requests::CheckNullifiersRequest,
+                                super::super::requests::SyncAllRequest,
                             >,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Api>::check_nullifiers(&inner, request).await
+                                <T as Api>::sync_all(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -337,7 +1174,7 @@ pub mod api_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = CheckNullifiersSvc(inner);
+                        let method = SyncAllSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -353,15 +1190,15 @@ pub mod api_server {
                     };
                     Box::pin(fut)
                 }
-                "/rpc.Api/GetBlockHeaderByNumber" => {
+                "/rpc.Api/SyncAccounts" => {
                     #[allow(non_camel_case_types)]
-                    struct GetBlockHeaderByNumberSvc<T: Api>(pub Arc<T>);
+                    struct SyncAccountsSvc<T: Api>(pub Arc<T>);
                     impl<
                         T: Api,
                     > tonic::server::UnaryService<
-                        super::super::requests::GetBlockHeaderByNumberRequest,
-                    > for GetBlockHeaderByNumberSvc<T> {
-                        type Response = super::super::responses::GetBlockHeaderByNumberResponse;
+                        super::super::requests::SyncAccountsRequest,
+                    > for SyncAccountsSvc<T> {
+                        type Response = super::super::responses::SyncAccountsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -369,13 +1206,12 @@ pub mod api_server {
                         fn call(
                             &mut self,
                             request: tonic::Request<
-                                super::super::requests::GetBlockHeaderByNumberRequest,
+                                super::super::requests::SyncAccountsRequest,
                             >,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Api>::get_block_header_by_number(&inner, request)
-                                    .await
+                                <T as Api>::sync_accounts(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -387,7 +1223,7 @@ pub mod api_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetBlockHeaderByNumberSvc(inner);
+                        let method = SyncAccountsSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -403,28 +1239,29 @@ pub mod api_server {
                     };
                     Box::pin(fut)
                 }
-                "/rpc.Api/SyncState" => {
+                "/rpc.Api/SubscribeNullifiers" => {
                     #[allow(non_camel_case_types)]
-                    struct SyncStateSvc<T: Api>(pub Arc<T>);
+                    struct SubscribeNullifiersSvc<T: Api>(pub Arc<T>);
                     impl<
                         T: Api,
-                    > tonic::server::UnaryService<
-                        super::super::requests::SyncStateRequest,
-                    > for SyncStateSvc<T> {
-                        type Response = super::super::responses::SyncStateResponse;
+                    > tonic::server::ServerStreamingService<
+                        super::super::requests::SubscribeNullifiersRequest,
+                    > for SubscribeNullifiersSvc<T> {
+                        type Response = super::super::responses::SubscribeNullifiersResponse;
+                        type ResponseStream = T::SubscribeNullifiersStream;
                         type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
+                            tonic::Response<Self::ResponseStream>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
                             request: tonic::Request<
-                                super::super::requests::SyncStateRequest,
+                                super::super::requests::SubscribeNullifiersRequest,
                             >,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Api>::sync_state(&inner, request).await
+                                <T as Api>::subscribe_nullifiers(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -436,7 +1273,7 @@ pub mod api_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = SyncStateSvc(inner);
+                        let method = SubscribeNullifiersSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -447,7 +1284,7 @@ pub mod api_server {
                                 max_decoding_message_size,
                                 max_encoding_message_size,
                             );
-                        let res = grpc.unary(method, req).await;
+                        let res = grpc.server_streaming(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
@@ -501,6 +1338,256 @@ pub mod api_server {
                     };
                     Box::pin(fut)
                 }
+                "/rpc.Api/SubmitProvenBatch" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubmitProvenBatchSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::SubmitProvenBatchRequest,
+                    > for SubmitProvenBatchSvc<T> {
+                        type Response = super::super::responses::SubmitProvenBatchResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::SubmitProvenBatchRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::submit_proven_batch(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubmitProvenBatchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/SubmitNoteRecoveryRecord" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubmitNoteRecoveryRecordSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::SubmitNoteRecoveryRecordRequest,
+                    > for SubmitNoteRecoveryRecordSvc<T> {
+                        type Response = super::super::responses::SubmitNoteRecoveryRecordResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::SubmitNoteRecoveryRecordRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::submit_note_recovery_record(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubmitNoteRecoveryRecordSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/GetPendingTransactionsByAccount" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetPendingTransactionsByAccountSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetPendingTransactionsByAccountRequest,
+                    > for GetPendingTransactionsByAccountSvc<T> {
+                        type Response = super::super::responses::GetPendingTransactionsByAccountResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetPendingTransactionsByAccountRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_pending_transactions_by_account(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetPendingTransactionsByAccountSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/GetMempoolStatus" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetMempoolStatusSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetMempoolStatusRequest,
+                    > for GetMempoolStatusSvc<T> {
+                        type Response = super::super::responses::GetMempoolStatusResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetMempoolStatusRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_mempool_status(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetMempoolStatusSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/rpc.Api/GetUsageReport" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetUsageReportSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetUsageReportRequest,
+                    > for GetUsageReportSvc<T> {
+                        type Response = super::super::responses::GetUsageReportResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetUsageReportRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_usage_report(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetUsageReportSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -105,6 +105,72 @@ pub mod api_client {
             req.extensions_mut().insert(GrpcMethod::new("store.Api", "ApplyBlock"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn apply_blocks(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::ApplyBlocksRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ApplyBlocksResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Api/ApplyBlocks");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "ApplyBlocks"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn propose_block(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::ApplyBlockRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ProposeBlockResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Api/ProposeBlock");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "ProposeBlock"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn commit_block(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::CommitBlockRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::CommitBlockResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Api/CommitBlock");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "CommitBlock"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn check_nullifiers(
             &mut self,
             request: impl tonic::IntoRequest<
@@ -131,6 +197,55 @@ pub mod api_client {
             req.extensions_mut().insert(GrpcMethod::new("store.Api", "CheckNullifiers"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn export_notes(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::ExportNotesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ExportNotesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Api/ExportNotes");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "ExportNotes"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_backfill_status(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetBackfillStatusRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetBackfillStatusResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetBackfillStatus",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "GetBackfillStatus"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn get_block_header_by_number(
             &mut self,
             request: impl tonic::IntoRequest<
@@ -182,13 +297,13 @@ pub mod api_client {
             req.extensions_mut().insert(GrpcMethod::new("store.Api", "GetBlockInputs"));
             self.inner.unary(req, path, codec).await
         }
-        pub async fn get_transaction_inputs(
+        pub async fn get_chain_counters(
             &mut self,
             request: impl tonic::IntoRequest<
-                super::super::requests::GetTransactionInputsRequest,
+                super::super::requests::GetChainCountersRequest,
             >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::GetTransactionInputsResponse>,
+            tonic::Response<super::super::responses::GetChainCountersResponse>,
             tonic::Status,
         > {
             self.inner
@@ -202,18 +317,20 @@ pub mod api_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/store.Api/GetTransactionInputs",
+                "/store.Api/GetChainCounters",
             );
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("store.Api", "GetTransactionInputs"));
+                .insert(GrpcMethod::new("store.Api", "GetChainCounters"));
             self.inner.unary(req, path, codec).await
         }
-        pub async fn sync_state(
+        pub async fn get_database_size(
             &mut self,
-            request: impl tonic::IntoRequest<super::super::requests::SyncStateRequest>,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetDatabaseSizeRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::SyncStateResponse>,
+            tonic::Response<super::super::responses::GetDatabaseSizeResponse>,
             tonic::Status,
         > {
             self.inner
@@ -226,18 +343,20 @@ pub mod api_client {
                     )
                 })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/store.Api/SyncState");
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetDatabaseSize",
+            );
             let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("store.Api", "SyncState"));
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "GetDatabaseSize"));
             self.inner.unary(req, path, codec).await
         }
-        pub async fn list_nullifiers(
+        pub async fn get_genesis_block(
             &mut self,
             request: impl tonic::IntoRequest<
-                super::super::requests::ListNullifiersRequest,
+                super::super::requests::GetGenesisBlockRequest,
             >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::ListNullifiersResponse>,
+            tonic::Response<super::super::responses::GetGenesisBlockResponse>,
             tonic::Status,
         > {
             self.inner
@@ -250,16 +369,20 @@ pub mod api_client {
                     )
                 })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/store.Api/ListNullifiers");
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetGenesisBlock",
+            );
             let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("store.Api", "ListNullifiers"));
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "GetGenesisBlock"));
             self.inner.unary(req, path, codec).await
         }
-        pub async fn list_accounts(
+        pub async fn get_note_authentication_path(
             &mut self,
-            request: impl tonic::IntoRequest<super::super::requests::ListAccountsRequest>,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetNoteAuthenticationPathRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::ListAccountsResponse>,
+            tonic::Response<super::super::responses::GetNoteAuthenticationPathResponse>,
             tonic::Status,
         > {
             self.inner
@@ -272,16 +395,21 @@ pub mod api_client {
                     )
                 })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/store.Api/ListAccounts");
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetNoteAuthenticationPath",
+            );
             let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("store.Api", "ListAccounts"));
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "GetNoteAuthenticationPath"));
             self.inner.unary(req, path, codec).await
         }
-        pub async fn list_notes(
+        pub async fn get_note_recovery_records(
             &mut self,
-            request: impl tonic::IntoRequest<super::super::requests::ListNotesRequest>,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetNoteRecoveryRecordsRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::ListNotesResponse>,
+            tonic::Response<super::super::responses::GetNoteRecoveryRecordsResponse>,
             tonic::Status,
         > {
             self.inner
@@ -294,174 +422,1545 @@ pub mod api_client {
                     )
                 })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/store.Api/ListNotes");
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetNoteRecoveryRecords",
+            );
             let mut req = request.into_request();
-            req.extensions_mut().insert(GrpcMethod::new("store.Api", "ListNotes"));
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "GetNoteRecoveryRecords"));
             self.inner.unary(req, path, codec).await
         }
-    }
-}
-/// Generated server implementations.
-pub mod api_server {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with ApiServer.
-    #[async_trait]
-    pub trait Api: Send + Sync + 'static {
-        async fn apply_block(
-            &self,
-            request: tonic::Request<super::super::requests::ApplyBlockRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::super::responses::ApplyBlockResponse>,
-            tonic::Status,
-        >;
-        async fn check_nullifiers(
-            &self,
-            request: tonic::Request<super::super::requests::CheckNullifiersRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::super::responses::CheckNullifiersResponse>,
-            tonic::Status,
-        >;
-        async fn get_block_header_by_number(
-            &self,
-            request: tonic::Request<
-                super::super::requests::GetBlockHeaderByNumberRequest,
+        pub async fn get_note_tag_stats(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetNoteTagStatsRequest,
             >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::GetBlockHeaderByNumberResponse>,
+            tonic::Response<super::super::responses::GetNoteTagStatsResponse>,
             tonic::Status,
-        >;
-        async fn get_block_inputs(
-            &self,
-            request: tonic::Request<super::super::requests::GetBlockInputsRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetNoteTagStats",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "GetNoteTagStats"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_node_info(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::GetNodeInfoRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::GetBlockInputsResponse>,
+            tonic::Response<super::super::responses::GetNodeInfoResponse>,
             tonic::Status,
-        >;
-        async fn get_transaction_inputs(
-            &self,
-            request: tonic::Request<super::super::requests::GetTransactionInputsRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Api/GetNodeInfo");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "GetNodeInfo"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_proposed_notes(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetProposedNotesRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::GetTransactionInputsResponse>,
+            tonic::Response<super::super::responses::GetProposedNotesResponse>,
             tonic::Status,
-        >;
-        async fn sync_state(
-            &self,
-            request: tonic::Request<super::super::requests::SyncStateRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetProposedNotes",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "GetProposedNotes"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_pruning_horizon(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetPruningHorizonRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::SyncStateResponse>,
+            tonic::Response<super::super::responses::GetPruningHorizonResponse>,
             tonic::Status,
-        >;
-        async fn list_nullifiers(
-            &self,
-            request: tonic::Request<super::super::requests::ListNullifiersRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetPruningHorizon",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "GetPruningHorizon"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_startup_progress(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetStartupProgressRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::ListNullifiersResponse>,
+            tonic::Response<super::super::responses::GetStartupProgressResponse>,
             tonic::Status,
-        >;
-        async fn list_accounts(
-            &self,
-            request: tonic::Request<super::super::requests::ListAccountsRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetStartupProgress",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "GetStartupProgress"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_state_commitment(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetStateCommitmentRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::ListAccountsResponse>,
+            tonic::Response<super::super::responses::GetStateCommitmentResponse>,
             tonic::Status,
-        >;
-        async fn list_notes(
-            &self,
-            request: tonic::Request<super::super::requests::ListNotesRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetStateCommitment",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "GetStateCommitment"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_transaction_inputs(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetTransactionInputsRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<super::super::responses::ListNotesResponse>,
+            tonic::Response<super::super::responses::GetTransactionInputsResponse>,
             tonic::Status,
-        >;
-    }
-    #[derive(Debug)]
-    pub struct ApiServer<T: Api> {
-        inner: _Inner<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
-        max_decoding_message_size: Option<usize>,
-        max_encoding_message_size: Option<usize>,
-    }
-    struct _Inner<T>(Arc<T>);
-    impl<T: Api> ApiServer<T> {
-        pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
-        }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            let inner = _Inner(inner);
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
-                max_decoding_message_size: None,
-                max_encoding_message_size: None,
-            }
-        }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> InterceptedService<Self, F>
-        where
-            F: tonic::service::Interceptor,
-        {
-            InterceptedService::new(Self::new(inner), interceptor)
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetTransactionInputs",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "GetTransactionInputs"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Enable decompressing requests with the given encoding.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.accept_compression_encodings.enable(encoding);
-            self
+        pub async fn get_unconsumed_notes(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::GetUnconsumedNotesRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetUnconsumedNotesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/GetUnconsumedNotes",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "GetUnconsumedNotes"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Compress responses with the given encoding, if the client supports it.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
-            self
+        pub async fn submit_note_recovery_record(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::SubmitNoteRecoveryRecordRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SubmitNoteRecoveryRecordResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/SubmitNoteRecoveryRecord",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "SubmitNoteRecoveryRecord"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Limits the maximum size of a decoded message.
-        ///
-        /// Default: `4MB`
-        #[must_use]
-        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
-            self.max_decoding_message_size = Some(limit);
-            self
+        pub async fn sync_state(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::SyncStateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SyncStateResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Api/SyncState");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "SyncState"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Limits the maximum size of an encoded message.
-        ///
-        /// Default: `usize::MAX`
-        #[must_use]
-        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
-            self.max_encoding_message_size = Some(limit);
-            self
+        pub async fn sync_accounts(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::SyncAccountsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SyncAccountsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Api/SyncAccounts");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "SyncAccounts"));
+            self.inner.unary(req, path, codec).await
         }
-    }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for ApiServer<T>
-    where
-        T: Api,
-        B: Body + Send + 'static,
-        B::Error: Into<StdError> + Send + 'static,
-    {
-        type Response = http::Response<tonic::body::BoxBody>;
-        type Error = std::convert::Infallible;
-        type Future = BoxFuture<Self::Response, Self::Error>;
-        fn poll_ready(
+        pub async fn subscribe_nullifiers(
             &mut self,
-            _cx: &mut Context<'_>,
-        ) -> Poll<std::result::Result<(), Self::Error>> {
-            Poll::Ready(Ok(()))
+            request: impl tonic::IntoRequest<
+                super::super::requests::SubscribeNullifiersRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<
+                tonic::codec::Streaming<
+                    super::super::responses::SubscribeNullifiersResponse,
+                >,
+            >,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/SubscribeNullifiers",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "SubscribeNullifiers"));
+            self.inner.server_streaming(req, path, codec).await
         }
-        fn call(&mut self, req: http::Request<B>) -> Self::Future {
-            let inner = self.inner.clone();
-            match req.uri().path() {
-                "/store.Api/ApplyBlock" => {
+        pub async fn subscribe_chain_tip(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::SubscribeChainTipRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<
+                tonic::codec::Streaming<
+                    super::super::responses::SubscribeChainTipResponse,
+                >,
+            >,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/store.Api/SubscribeChainTip",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("store.Api", "SubscribeChainTip"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn list_nullifiers(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::requests::ListNullifiersRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ListNullifiersResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Api/ListNullifiers");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "ListNullifiers"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_accounts(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::ListAccountsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ListAccountsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Api/ListAccounts");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "ListAccounts"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_notes(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::requests::ListNotesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ListNotesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/store.Api/ListNotes");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("store.Api", "ListNotes"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod api_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with ApiServer.
+    #[async_trait]
+    pub trait Api: Send + Sync + 'static {
+        async fn apply_block(
+            &self,
+            request: tonic::Request<super::super::requests::ApplyBlockRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ApplyBlockResponse>,
+            tonic::Status,
+        >;
+        async fn apply_blocks(
+            &self,
+            request: tonic::Request<super::super::requests::ApplyBlocksRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ApplyBlocksResponse>,
+            tonic::Status,
+        >;
+        async fn propose_block(
+            &self,
+            request: tonic::Request<super::super::requests::ApplyBlockRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ProposeBlockResponse>,
+            tonic::Status,
+        >;
+        async fn commit_block(
+            &self,
+            request: tonic::Request<super::super::requests::CommitBlockRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::CommitBlockResponse>,
+            tonic::Status,
+        >;
+        async fn check_nullifiers(
+            &self,
+            request: tonic::Request<super::super::requests::CheckNullifiersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::CheckNullifiersResponse>,
+            tonic::Status,
+        >;
+        async fn export_notes(
+            &self,
+            request: tonic::Request<super::super::requests::ExportNotesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ExportNotesResponse>,
+            tonic::Status,
+        >;
+        async fn get_backfill_status(
+            &self,
+            request: tonic::Request<super::super::requests::GetBackfillStatusRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetBackfillStatusResponse>,
+            tonic::Status,
+        >;
+        async fn get_block_header_by_number(
+            &self,
+            request: tonic::Request<
+                super::super::requests::GetBlockHeaderByNumberRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetBlockHeaderByNumberResponse>,
+            tonic::Status,
+        >;
+        async fn get_block_inputs(
+            &self,
+            request: tonic::Request<super::super::requests::GetBlockInputsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetBlockInputsResponse>,
+            tonic::Status,
+        >;
+        async fn get_chain_counters(
+            &self,
+            request: tonic::Request<super::super::requests::GetChainCountersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetChainCountersResponse>,
+            tonic::Status,
+        >;
+        async fn get_database_size(
+            &self,
+            request: tonic::Request<super::super::requests::GetDatabaseSizeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetDatabaseSizeResponse>,
+            tonic::Status,
+        >;
+        async fn get_genesis_block(
+            &self,
+            request: tonic::Request<super::super::requests::GetGenesisBlockRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetGenesisBlockResponse>,
+            tonic::Status,
+        >;
+        async fn get_note_authentication_path(
+            &self,
+            request: tonic::Request<
+                super::super::requests::GetNoteAuthenticationPathRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetNoteAuthenticationPathResponse>,
+            tonic::Status,
+        >;
+        async fn get_note_recovery_records(
+            &self,
+            request: tonic::Request<
+                super::super::requests::GetNoteRecoveryRecordsRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetNoteRecoveryRecordsResponse>,
+            tonic::Status,
+        >;
+        async fn get_note_tag_stats(
+            &self,
+            request: tonic::Request<super::super::requests::GetNoteTagStatsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetNoteTagStatsResponse>,
+            tonic::Status,
+        >;
+        async fn get_node_info(
+            &self,
+            request: tonic::Request<super::super::requests::GetNodeInfoRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetNodeInfoResponse>,
+            tonic::Status,
+        >;
+        async fn get_proposed_notes(
+            &self,
+            request: tonic::Request<super::super::requests::GetProposedNotesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetProposedNotesResponse>,
+            tonic::Status,
+        >;
+        async fn get_pruning_horizon(
+            &self,
+            request: tonic::Request<super::super::requests::GetPruningHorizonRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetPruningHorizonResponse>,
+            tonic::Status,
+        >;
+        async fn get_startup_progress(
+            &self,
+            request: tonic::Request<super::super::requests::GetStartupProgressRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetStartupProgressResponse>,
+            tonic::Status,
+        >;
+        async fn get_state_commitment(
+            &self,
+            request: tonic::Request<super::super::requests::GetStateCommitmentRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetStateCommitmentResponse>,
+            tonic::Status,
+        >;
+        async fn get_transaction_inputs(
+            &self,
+            request: tonic::Request<super::super::requests::GetTransactionInputsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetTransactionInputsResponse>,
+            tonic::Status,
+        >;
+        async fn get_unconsumed_notes(
+            &self,
+            request: tonic::Request<super::super::requests::GetUnconsumedNotesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::GetUnconsumedNotesResponse>,
+            tonic::Status,
+        >;
+        async fn submit_note_recovery_record(
+            &self,
+            request: tonic::Request<
+                super::super::requests::SubmitNoteRecoveryRecordRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SubmitNoteRecoveryRecordResponse>,
+            tonic::Status,
+        >;
+        async fn sync_state(
+            &self,
+            request: tonic::Request<super::super::requests::SyncStateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SyncStateResponse>,
+            tonic::Status,
+        >;
+        async fn sync_accounts(
+            &self,
+            request: tonic::Request<super::super::requests::SyncAccountsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::SyncAccountsResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the SubscribeNullifiers method.
+        type SubscribeNullifiersStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<
+                    super::super::responses::SubscribeNullifiersResponse,
+                    tonic::Status,
+                >,
+            >
+            + Send
+            + 'static;
+        async fn subscribe_nullifiers(
+            &self,
+            request: tonic::Request<super::super::requests::SubscribeNullifiersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::SubscribeNullifiersStream>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the SubscribeChainTip method.
+        type SubscribeChainTipStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<
+                    super::super::responses::SubscribeChainTipResponse,
+                    tonic::Status,
+                >,
+            >
+            + Send
+            + 'static;
+        async fn subscribe_chain_tip(
+            &self,
+            request: tonic::Request<super::super::requests::SubscribeChainTipRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::SubscribeChainTipStream>,
+            tonic::Status,
+        >;
+        async fn list_nullifiers(
+            &self,
+            request: tonic::Request<super::super::requests::ListNullifiersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ListNullifiersResponse>,
+            tonic::Status,
+        >;
+        async fn list_accounts(
+            &self,
+            request: tonic::Request<super::super::requests::ListAccountsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ListAccountsResponse>,
+            tonic::Status,
+        >;
+        async fn list_notes(
+            &self,
+            request: tonic::Request<super::super::requests::ListNotesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::responses::ListNotesResponse>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct ApiServer<T: Api> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: Api> ApiServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for ApiServer<T>
+    where
+        T: Api,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/store.Api/ApplyBlock" => {
+                    #[allow(non_camel_case_types)]
+                    struct ApplyBlockSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::ApplyBlockRequest,
+                    > for ApplyBlockSvc<T> {
+                        type Response = super::super::responses::ApplyBlockResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::ApplyBlockRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::apply_block(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ApplyBlockSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/ApplyBlocks" => {
+                    #[allow(non_camel_case_types)]
+                    struct ApplyBlocksSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::ApplyBlocksRequest,
+                    > for ApplyBlocksSvc<T> {
+                        type Response = super::super::responses::ApplyBlocksResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::ApplyBlocksRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::apply_blocks(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ApplyBlocksSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/ProposeBlock" => {
+                    #[allow(non_camel_case_types)]
+                    struct ProposeBlockSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::ApplyBlockRequest,
+                    > for ProposeBlockSvc<T> {
+                        type Response = super::super::responses::ProposeBlockResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::ApplyBlockRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::propose_block(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ProposeBlockSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/CommitBlock" => {
+                    #[allow(non_camel_case_types)]
+                    struct CommitBlockSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::CommitBlockRequest,
+                    > for CommitBlockSvc<T> {
+                        type Response = super::super::responses::CommitBlockResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::CommitBlockRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::commit_block(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CommitBlockSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/CheckNullifiers" => {
+                    #[allow(non_camel_case_types)]
+                    struct CheckNullifiersSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::CheckNullifiersRequest,
+                    > for CheckNullifiersSvc<T> {
+                        type Response = super::super::responses::CheckNullifiersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::CheckNullifiersRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::check_nullifiers(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CheckNullifiersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/ExportNotes" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExportNotesSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::ExportNotesRequest,
+                    > for ExportNotesSvc<T> {
+                        type Response = super::super::responses::ExportNotesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::ExportNotesRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::export_notes(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ExportNotesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/GetBackfillStatus" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBackfillStatusSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetBackfillStatusRequest,
+                    > for GetBackfillStatusSvc<T> {
+                        type Response = super::super::responses::GetBackfillStatusResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetBackfillStatusRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_backfill_status(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBackfillStatusSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/GetBlockHeaderByNumber" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBlockHeaderByNumberSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetBlockHeaderByNumberRequest,
+                    > for GetBlockHeaderByNumberSvc<T> {
+                        type Response = super::super::responses::GetBlockHeaderByNumberResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetBlockHeaderByNumberRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_block_header_by_number(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBlockHeaderByNumberSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/GetBlockInputs" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBlockInputsSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetBlockInputsRequest,
+                    > for GetBlockInputsSvc<T> {
+                        type Response = super::super::responses::GetBlockInputsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetBlockInputsRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_block_inputs(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBlockInputsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/GetChainCounters" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetChainCountersSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetChainCountersRequest,
+                    > for GetChainCountersSvc<T> {
+                        type Response = super::super::responses::GetChainCountersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetChainCountersRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_chain_counters(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetChainCountersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/GetDatabaseSize" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetDatabaseSizeSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetDatabaseSizeRequest,
+                    > for GetDatabaseSizeSvc<T> {
+                        type Response = super::super::responses::GetDatabaseSizeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetDatabaseSizeRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_database_size(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetDatabaseSizeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/GetGenesisBlock" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetGenesisBlockSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetGenesisBlockRequest,
+                    > for GetGenesisBlockSvc<T> {
+                        type Response = super::super::responses::GetGenesisBlockResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetGenesisBlockRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_genesis_block(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetGenesisBlockSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/GetNoteAuthenticationPath" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetNoteAuthenticationPathSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetNoteAuthenticationPathRequest,
+                    > for GetNoteAuthenticationPathSvc<T> {
+                        type Response = super::super::responses::GetNoteAuthenticationPathResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetNoteAuthenticationPathRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_note_authentication_path(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetNoteAuthenticationPathSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/GetNoteRecoveryRecords" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetNoteRecoveryRecordsSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetNoteRecoveryRecordsRequest,
+                    > for GetNoteRecoveryRecordsSvc<T> {
+                        type Response = super::super::responses::GetNoteRecoveryRecordsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetNoteRecoveryRecordsRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_note_recovery_records(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetNoteRecoveryRecordsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/GetNoteTagStats" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetNoteTagStatsSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetNoteTagStatsRequest,
+                    > for GetNoteTagStatsSvc<T> {
+                        type Response = super::super::responses::GetNoteTagStatsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetNoteTagStatsRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_note_tag_stats(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetNoteTagStatsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/GetNodeInfo" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetNodeInfoSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetNodeInfoRequest,
+                    > for GetNodeInfoSvc<T> {
+                        type Response = super::super::responses::GetNodeInfoResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetNodeInfoRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_node_info(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetNodeInfoSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/GetProposedNotes" => {
                     #[allow(non_camel_case_types)]
-                    struct ApplyBlockSvc<T: Api>(pub Arc<T>);
+                    struct GetProposedNotesSvc<T: Api>(pub Arc<T>);
                     impl<
                         T: Api,
                     > tonic::server::UnaryService<
-                        super::super::requests::ApplyBlockRequest,
-                    > for ApplyBlockSvc<T> {
-                        type Response = super::super::responses::ApplyBlockResponse;
+                        super::super::requests::GetProposedNotesRequest,
+                    > for GetProposedNotesSvc<T> {
+                        type Response = super::super::responses::GetProposedNotesResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -469,12 +1968,12 @@ pub mod api_server {
                         fn call(
                             &mut self,
                             request: tonic::Request<
-                                super::super::requests::ApplyBlockRequest,
+                                super::super::requests::GetProposedNotesRequest,
                             >,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Api>::apply_block(&inner, request).await
+                                <T as Api>::get_proposed_notes(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -486,7 +1985,7 @@ pub mod api_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = ApplyBlockSvc(inner);
+                        let method = GetProposedNotesSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -502,15 +2001,15 @@ pub mod api_server {
                     };
                     Box::pin(fut)
                 }
-                "/store.Api/CheckNullifiers" => {
+                "/store.Api/GetPruningHorizon" => {
                     #[allow(non_camel_case_types)]
-                    struct CheckNullifiersSvc<T: Api>(pub Arc<T>);
+                    struct GetPruningHorizonSvc<T: Api>(pub Arc<T>);
                     impl<
                         T: Api,
                     > tonic::server::UnaryService<
-                        super::super::requests::CheckNullifiersRequest,
-                    > for CheckNullifiersSvc<T> {
-                        type Response = super::super::responses::CheckNullifiersResponse;
+                        super::super::requests::GetPruningHorizonRequest,
+                    > for GetPruningHorizonSvc<T> {
+                        type Response = super::super::responses::GetPruningHorizonResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -518,12 +2017,12 @@ pub mod api_server {
                         fn call(
                             &mut self,
                             request: tonic::Request<
-                                super::super::requests::CheckNullifiersRequest,
+                                super::super::requests::GetPruningHorizonRequest,
                             >,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Api>::check_nullifiers(&inner, request).await
+                                <T as Api>::get_pruning_horizon(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -535,7 +2034,7 @@ pub mod api_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = CheckNullifiersSvc(inner);
+                        let method = GetPruningHorizonSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -551,15 +2050,15 @@ pub mod api_server {
                     };
                     Box::pin(fut)
                 }
-                "/store.Api/GetBlockHeaderByNumber" => {
+                "/store.Api/GetStartupProgress" => {
                     #[allow(non_camel_case_types)]
-                    struct GetBlockHeaderByNumberSvc<T: Api>(pub Arc<T>);
+                    struct GetStartupProgressSvc<T: Api>(pub Arc<T>);
                     impl<
                         T: Api,
                     > tonic::server::UnaryService<
-                        super::super::requests::GetBlockHeaderByNumberRequest,
-                    > for GetBlockHeaderByNumberSvc<T> {
-                        type Response = super::super::responses::GetBlockHeaderByNumberResponse;
+                        super::super::requests::GetStartupProgressRequest,
+                    > for GetStartupProgressSvc<T> {
+                        type Response = super::super::responses::GetStartupProgressResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -567,13 +2066,12 @@ pub mod api_server {
                         fn call(
                             &mut self,
                             request: tonic::Request<
-                                super::super::requests::GetBlockHeaderByNumberRequest,
+                                super::super::requests::GetStartupProgressRequest,
                             >,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Api>::get_block_header_by_number(&inner, request)
-                                    .await
+                                <T as Api>::get_startup_progress(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -585,7 +2083,7 @@ pub mod api_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetBlockHeaderByNumberSvc(inner);
+                        let method = GetStartupProgressSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -601,15 +2099,15 @@ pub mod api_server {
                     };
                     Box::pin(fut)
                 }
-                "/store.Api/GetBlockInputs" => {
+                "/store.Api/GetStateCommitment" => {
                     #[allow(non_camel_case_types)]
-                    struct GetBlockInputsSvc<T: Api>(pub Arc<T>);
+                    struct GetStateCommitmentSvc<T: Api>(pub Arc<T>);
                     impl<
                         T: Api,
                     > tonic::server::UnaryService<
-                        super::super::requests::GetBlockInputsRequest,
-                    > for GetBlockInputsSvc<T> {
-                        type Response = super::super::responses::GetBlockInputsResponse;
+                        super::super::requests::GetStateCommitmentRequest,
+                    > for GetStateCommitmentSvc<T> {
+                        type Response = super::super::responses::GetStateCommitmentResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
@@ -617,12 +2115,12 @@ pub mod api_server {
                         fn call(
                             &mut self,
                             request: tonic::Request<
-                                super::super::requests::GetBlockInputsRequest,
+                                super::super::requests::GetStateCommitmentRequest,
                             >,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Api>::get_block_inputs(&inner, request).await
+                                <T as Api>::get_state_commitment(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -634,7 +2132,7 @@ pub mod api_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetBlockInputsSvc(inner);
+                        let method = GetStateCommitmentSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -699,6 +2197,105 @@ pub mod api_server {
                     };
                     Box::pin(fut)
                 }
+                "/store.Api/GetUnconsumedNotes" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetUnconsumedNotesSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::GetUnconsumedNotesRequest,
+                    > for GetUnconsumedNotesSvc<T> {
+                        type Response = super::super::responses::GetUnconsumedNotesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::GetUnconsumedNotesRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::get_unconsumed_notes(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetUnconsumedNotesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/SubmitNoteRecoveryRecord" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubmitNoteRecoveryRecordSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::SubmitNoteRecoveryRecordRequest,
+                    > for SubmitNoteRecoveryRecordSvc<T> {
+                        type Response = super::super::responses::SubmitNoteRecoveryRecordResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::SubmitNoteRecoveryRecordRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::submit_note_recovery_record(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubmitNoteRecoveryRecordSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/store.Api/SyncState" => {
                     #[allow(non_camel_case_types)]
                     struct SyncStateSvc<T: Api>(pub Arc<T>);
@@ -748,6 +2345,155 @@ pub mod api_server {
                     };
                     Box::pin(fut)
                 }
+                "/store.Api/SyncAccounts" => {
+                    #[allow(non_camel_case_types)]
+                    struct SyncAccountsSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::UnaryService<
+                        super::super::requests::SyncAccountsRequest,
+                    > for SyncAccountsSvc<T> {
+                        type Response = super::super::responses::SyncAccountsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::SyncAccountsRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::sync_accounts(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SyncAccountsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/SubscribeNullifiers" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeNullifiersSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::ServerStreamingService<
+                        super::super::requests::SubscribeNullifiersRequest,
+                    > for SubscribeNullifiersSvc<T> {
+                        type Response = super::super::responses::SubscribeNullifiersResponse;
+                        type ResponseStream = T::SubscribeNullifiersStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::SubscribeNullifiersRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::subscribe_nullifiers(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubscribeNullifiersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/store.Api/SubscribeChainTip" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeChainTipSvc<T: Api>(pub Arc<T>);
+                    impl<
+                        T: Api,
+                    > tonic::server::ServerStreamingService<
+                        super::super::requests::SubscribeChainTipRequest,
+                    > for SubscribeChainTipSvc<T> {
+                        type Response = super::super::responses::SubscribeChainTipResponse;
+                        type ResponseStream = T::SubscribeChainTipStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::requests::SubscribeChainTipRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Api>::subscribe_chain_tip(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubscribeChainTipSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/store.Api/ListNullifiers" => {
                     #[allow(non_camel_case_types)]
                     struct ListNullifiersSvc<T: Api>(pub Arc<T>);
@@ -1,3 +1,4 @@
+pub mod chain_id;
 pub mod conversion;
 pub mod domain;
 pub mod errors;
@@ -10,6 +11,28 @@ mod generated;
 // RE-EXPORTS
 // ------------------------------------------------------------------------------------------------
 pub use generated::{
-    account, block_header, block_producer, digest, merkle, mmr, note, requests, responses, rpc,
-    store, tsmt,
+    account, block_header, block_producer, block_prover, digest, merkle, mmr, note, requests,
+    responses, rpc, store, tsmt,
 };
+
+// CONSTANTS
+// ------------------------------------------------------------------------------------------------
+
+/// Version of the protocol spoken between the node's components.
+///
+/// Components refuse to operate against a store which reports a different version.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Version of the transaction kernel the node is built against.
+///
+/// The clients of this release submit their transactions with this version, and the block producer
+/// accepts it by default.
+pub const TX_KERNEL_VERSION: u32 = 1;
+
+/// Version of the transaction kernel assumed for the transactions submitted without one, i.e. by
+/// the clients predating the kernel versions.
+pub const FIRST_TX_KERNEL_VERSION: u32 = 1;
+
+/// Operation signed by an operator of the block producer to submit a proven batch, see
+/// [miden_node_utils::operator_auth].
+pub const SUBMIT_PROVEN_BATCH_OPERATION: &str = "SubmitProvenBatch";
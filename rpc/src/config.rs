@@ -1,6 +1,14 @@
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    num::ParseIntError,
+    path::PathBuf,
+};
 
-use miden_node_utils::config::Endpoint;
+use miden_node_utils::{
+    config::{Endpoint, GrpcServerConfig, ListenAddress},
+    documented_config,
+    formatting::format_array,
+};
 use serde::{Deserialize, Serialize};
 
 pub const CONFIG_FILENAME: &str = "miden-rpc.toml";
@@ -8,29 +16,275 @@ pub const CONFIG_FILENAME: &str = "miden-rpc.toml";
 // Main config
 // ================================================================================================
 
+documented_config! {
+    /// RPC specific configuration
+    ///
+    /// The store, block producer, and chain id define the default network, serving the requests
+    /// which don't name a network.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    pub struct RpcConfig {
+        /// Endpoint of the RPC's gRPC server.
+        pub endpoint: Endpoint => table,
+        /// Additional addresses to listen on, e.g. IPv6 or Unix domain sockets, as in
+        /// `[{ host = "::1", port = 57291 }]`.
+        #[serde(default)]
+        pub listen: Vec<ListenAddress>,
+        /// Store gRPC endpoint in the format `http://<host>[:<port>]`, or `unix://<path>` for a
+        /// Unix domain socket.
+        pub store_url: String,
+        /// Block producer gRPC endpoint in the format `http://<host>[:<port>]`, or `unix://<path>`
+        /// for a Unix domain socket.
+        pub block_producer_url: String,
+        /// Identifier of the chain served, must match the chain id of the genesis file.
+        pub chain_id: u32,
+        /// Hex encoded hash of the genesis block, the RPC refuses to serve a store of another
+        /// genesis block. Any genesis block is accepted if missing.
+        #[serde(default)]
+        pub genesis_hash: Option<String> => "\"0x...\"",
+        /// Maximum number of requests per second served for the default network, `0` disables the
+        /// limit.
+        pub max_requests_per_second: u32,
+        /// Other networks served to the requests which name them, each one with a `name`, and the
+        /// same `store_url`, `block_producer_url`, `chain_id`, `genesis_hash` and
+        /// `max_requests_per_second` fields as above.
+        #[serde(default)]
+        pub networks: Vec<NetworkConfig>,
+        /// Audit log of the transaction submissions, recorded as JSON lines.
+        #[serde(default)]
+        pub audit: AuditConfig => table,
+        /// Requests and response bytes of every client, identified by its `x-api-key` metadata if
+        /// configured or by its address, written to a report per UTC day.
+        #[serde(default)]
+        pub usage: UsageConfig => table,
+        /// Response fields cleared for privacy, for all the networks.
+        #[serde(default)]
+        pub field_masks: FieldMasks => table,
+        /// Hex encoded public keys of the operators allowed to sign the admin operations, e.g.
+        /// `GetUsageReport`. The admin operations are disabled if empty.
+        #[serde(default)]
+        pub operator_keys: Vec<String>,
+        /// Tuning of the gRPC server, every option left to `0` keeps the default of tonic.
+        #[serde(default)]
+        pub grpc: GrpcServerConfig => table,
+    }
+}
+
+impl RpcConfig {
+    pub fn as_url(&self) -> String {
+        self.endpoint.to_string()
+    }
+}
+
+impl Display for RpcConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ endpoint: \"{}\", listen: {}, store_url: \"{}\", block_producer_url: \"{}\", chain_id: {}, genesis_hash: {:?}, max_requests_per_second: {}, networks: {}, audit: {}, usage: {}, field_masks: {}, operator_keys: {:?}, grpc: {} }}",
+            self.endpoint, format_array(&self.listen), self.store_url, self.block_producer_url, self.chain_id, self.genesis_hash, self.max_requests_per_second, format_array(&self.networks), self.audit, self.usage, self.field_masks, self.operator_keys, self.grpc
+        ))
+    }
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            // port defined as: sum(ord(c)**p for (p, c) in enumerate('miden-rpc', 1)) % 2**16
+            endpoint: Endpoint {
+                host: "localhost".to_string(),
+                port: 57291,
+            },
+            listen: Vec::new(),
+            store_url: "http://localhost:28943".to_string(),
+            block_producer_url: "http://localhost:48046".to_string(),
+            chain_id: 1,
+            genesis_hash: None,
+            max_requests_per_second: 0,
+            networks: Vec::new(),
+            audit: AuditConfig::default(),
+            usage: UsageConfig::default(),
+            field_masks: FieldMasks::default(),
+            operator_keys: Vec::new(),
+            grpc: GrpcServerConfig::default(),
+        }
+    }
+}
+
+/// A network served by the RPC in addition to the default one
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
-pub struct RpcConfig {
-    pub endpoint: Endpoint,
+pub struct NetworkConfig {
+    /// Name of the network, given by the requests in the `miden-network` metadata or as the
+    /// first segment of their path, e.g. `/testnet/rpc.Api/SyncState`.
+    pub name: String,
     /// Store gRPC endpoint in the format `http://<host>[:<port>]`.
     pub store_url: String,
     /// Block producer gRPC endpoint in the format `http://<host>[:<port>]`.
     pub block_producer_url: String,
+    /// Identifier of the chain of the network.
+    pub chain_id: u32,
+    /// Hex encoded hash of the genesis block of the network, any genesis block is accepted if
+    /// missing.
+    #[serde(default)]
+    pub genesis_hash: Option<String>,
+    /// Maximum number of requests per second served for this network, `0` disables the limit.
+    pub max_requests_per_second: u32,
 }
 
-impl RpcConfig {
-    pub fn as_url(&self) -> String {
-        self.endpoint.to_string()
+impl Display for NetworkConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ name: \"{}\", store_url: \"{}\", block_producer_url: \"{}\", chain_id: {}, genesis_hash: {:?}, max_requests_per_second: {} }}",
+            self.name, self.store_url, self.block_producer_url, self.chain_id, self.genesis_hash, self.max_requests_per_second
+        ))
     }
 }
 
-impl Display for RpcConfig {
+documented_config! {
+    /// Audit log of the mutating requests served by the RPC
+    ///
+    /// Every record is written as a JSON line to the log files, and optionally emitted as a
+    /// tracing event with the `miden-rpc-audit` target, so it can be forwarded to an external
+    /// sink.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct AuditConfig {
+        /// Directory of the log files, no file is written if missing.
+        pub directory: Option<PathBuf> => "\"./audit\"",
+        /// Size of a log file in MiB beyond which a new one is started, `0` disables the rotation.
+        pub max_file_size_mb: u64,
+        /// Number of rotated log files kept, the oldest ones are removed. `0` keeps all of them.
+        pub max_files: u32,
+        /// Whether every record is also emitted as a tracing event with the `miden-rpc-audit`
+        /// target.
+        pub emit_events: bool,
+    }
+}
+
+impl AuditConfig {
+    /// Returns `true` if the records are written anywhere.
+    pub fn is_enabled(&self) -> bool {
+        self.directory.is_some() || self.emit_events
+    }
+}
+
+impl Display for AuditConfig {
     fn fmt(
         &self,
         f: &mut Formatter<'_>,
     ) -> std::fmt::Result {
         f.write_fmt(format_args!(
-            "{{ endpoint: \"{}\", store_url: \"{}\", block_producer_url: \"{}\" }}",
-            self.endpoint, self.store_url, self.block_producer_url
+            "{{ directory: {:?}, max_file_size_mb: {}, max_files: {}, emit_events: {} }}",
+            self.directory, self.max_file_size_mb, self.max_files, self.emit_events
+        ))
+    }
+}
+
+documented_config! {
+    /// Accounting of the usage of the RPC by its clients, for the operators billing it
+    ///
+    /// The clients are identified by the API key given in the `x-api-key` metadata, if it is one
+    /// of the configured keys, or by their address. Their number of requests and bytes served are
+    /// written to a report per UTC day.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct UsageConfig {
+        /// Directory of the daily reports, usage isn't accounted if missing.
+        pub directory: Option<PathBuf> => "\"./usage\"",
+        /// Interval in seconds at which the reports are written.
+        pub flush_interval_s: u64,
+        /// Number of daily reports kept, the oldest ones are removed. `0` keeps all of them.
+        pub max_days: u32,
+        /// SHA-256 of the API keys the clients are accounted to, hex encoded. A client giving
+        /// another key is accounted to its address, so that the clients can't make up keys.
+        pub api_keys_sha256: Vec<String>,
+        /// Whether the clients without an API key are identified by the first address of their
+        /// `x-forwarded-for` metadata, rather than by the address of the peer. Only to be set
+        /// behind a proxy overwriting the header.
+        pub trust_forwarded_for: bool,
+    }
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            flush_interval_s: 60,
+            max_days: 0,
+            api_keys_sha256: Vec::new(),
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+impl Display for UsageConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ directory: {:?}, flush_interval_s: {}, max_days: {}, api_keys_sha256: {}, trust_forwarded_for: {} }}",
+            self.directory, self.flush_interval_s, self.max_days, self.api_keys_sha256.len(), self.trust_forwarded_for
+        ))
+    }
+}
+
+documented_config! {
+    /// Response fields hidden from the clients, for the public endpoints of privacy-conscious
+    /// deployments
+    ///
+    /// The masked fields are cleared by the RPC before responding, the store and the block
+    /// producer are unaffected. A cleared field holds the default value of its type.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct FieldMasks {
+        /// Whether the sender of the notes returned by the syncs and of the listed unconsumed
+        /// notes is cleared, and the details of the recovered notes, unless the sender is owned by
+        /// the client.
+        pub note_senders: bool,
+        /// Whether the account hashes of the pending transactions of an account are cleared,
+        /// leaving their ids and queue positions.
+        pub pending_account_hashes: bool,
+        /// Accounts owned by the clients, whose notes keep their sender when sent to them, as
+        /// tables of `api_key_sha256`, the hex encoded SHA-256 of the API key of a client, and
+        /// `account_ids`, the hex encoded ids of its accounts.
+        pub account_owners: Vec<AccountOwner>,
+    }
+}
+
+/// The accounts owned by the client authenticated by an API key, whose notes are not masked when
+/// sent to this client
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+pub struct AccountOwner {
+    /// SHA-256 of the API key given by the client in the `x-api-key` metadata, hex encoded.
+    pub api_key_sha256: String,
+    /// Ids of the accounts owned by the client, hex encoded, e.g. `0x8000000000000001`.
+    pub account_ids: Vec<String>,
+}
+
+impl AccountOwner {
+    /// Returns the ids of the accounts owned by the client, failing if one of them isn't valid.
+    pub fn parse_account_ids(&self) -> Result<Vec<u64>, ParseIntError> {
+        self.account_ids
+            .iter()
+            .map(|id| u64::from_str_radix(id.trim_start_matches("0x"), 16))
+            .collect()
+    }
+}
+
+impl Display for FieldMasks {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ note_senders: {}, pending_account_hashes: {}, account_owners: {} }}",
+            self.note_senders,
+            self.pending_account_hashes,
+            self.account_owners.len()
         ))
     }
 }
@@ -49,9 +303,12 @@ mod tests {
     use std::path::PathBuf;
 
     use figment::Jail;
-    use miden_node_utils::config::{load_config, Endpoint};
+    use miden_node_utils::config::{load_config, Endpoint, GrpcServerConfig};
 
-    use super::{RpcConfig, RpcTopLevelConfig, CONFIG_FILENAME};
+    use super::{
+        AccountOwner, AuditConfig, FieldMasks, NetworkConfig, RpcConfig, RpcTopLevelConfig,
+        UsageConfig, CONFIG_FILENAME,
+    };
 
     #[test]
     fn test_rpc_config() {
@@ -62,10 +319,42 @@ mod tests {
                     [rpc]
                     store_url = "http://store:8000"
                     block_producer_url = "http://block_producer:8001"
+                    chain_id = 1
+                    max_requests_per_second = 100
+                    operator_keys = ["0x01"]
 
                     [rpc.endpoint]
                     host = "127.0.0.1"
                     port = 8080
+
+                    [[rpc.networks]]
+                    name = "testnet"
+                    store_url = "http://testnet-store:8000"
+                    block_producer_url = "http://testnet-block_producer:8001"
+                    chain_id = 2
+                    genesis_hash = "0xab01"
+                    max_requests_per_second = 0
+
+                    [rpc.audit]
+                    directory = "audit"
+                    max_file_size_mb = 64
+
+                    [rpc.usage]
+                    directory = "usage"
+                    max_days = 90
+                    api_keys_sha256 = ["cd02"]
+
+                    [rpc.field_masks]
+                    note_senders = true
+
+                    [[rpc.field_masks.account_owners]]
+                    api_key_sha256 = "ab01"
+                    account_ids = ["0x1", "0x8000000000000002"]
+
+                    [rpc.grpc]
+                    max_connections = 10000
+                    tcp_keepalive_s = 60
+                    http2_keepalive_interval_s = 30
                 "#,
             )?;
 
@@ -80,8 +369,51 @@ mod tests {
                             host: "127.0.0.1".to_string(),
                             port: 8080,
                         },
+                        listen: Vec::new(),
                         store_url: "http://store:8000".to_string(),
                         block_producer_url: "http://block_producer:8001".to_string(),
+                        chain_id: 1,
+                        genesis_hash: None,
+                        max_requests_per_second: 100,
+                        networks: vec![NetworkConfig {
+                            name: "testnet".to_string(),
+                            store_url: "http://testnet-store:8000".to_string(),
+                            block_producer_url: "http://testnet-block_producer:8001".to_string(),
+                            chain_id: 2,
+                            genesis_hash: Some("0xab01".to_string()),
+                            max_requests_per_second: 0,
+                        }],
+                        audit: AuditConfig {
+                            directory: Some("audit".into()),
+                            max_file_size_mb: 64,
+                            max_files: 0,
+                            emit_events: false,
+                        },
+                        usage: UsageConfig {
+                            directory: Some("usage".into()),
+                            flush_interval_s: 60,
+                            max_days: 90,
+                            api_keys_sha256: vec!["cd02".to_string()],
+                            trust_forwarded_for: false,
+                        },
+                        field_masks: FieldMasks {
+                            note_senders: true,
+                            pending_account_hashes: false,
+                            account_owners: vec![AccountOwner {
+                                api_key_sha256: "ab01".to_string(),
+                                account_ids: vec![
+                                    "0x1".to_string(),
+                                    "0x8000000000000002".to_string(),
+                                ],
+                            }],
+                        },
+                        operator_keys: vec!["0x01".to_string()],
+                        grpc: GrpcServerConfig {
+                            max_connections: 10000,
+                            tcp_keepalive_s: 60,
+                            http2_keepalive_interval_s: 30,
+                            ..Default::default()
+                        },
                     }
                 }
             );
@@ -1,6 +1,28 @@
+use std::time::Duration;
+
 pub mod config;
 pub mod server;
 
 // CONSTANTS
 // =================================================================================================
 pub const COMPONENT: &str = "miden-rpc";
+
+/// Operation signed by an operator of the RPC to read the usage of its clients with
+/// `GetUsageReport`, see [miden_node_utils::operator_auth].
+pub const GET_USAGE_REPORT_OPERATION: &str = "GetUsageReport";
+
+/// The frequency at which the store's genesis block hash and protocol version are verified
+const SERVER_NODE_INFO_CHECK_FREQUENCY: Duration = Duration::from_secs(60);
+
+/// How long the chain tip fetched from the store is served to the clients polling for it
+const SERVER_TIP_CACHE_TTL: Duration = Duration::from_secs(1);
+
+/// Maximum number of unspent nullifiers cached per network, until the next block is applied
+const SERVER_NULLIFIER_CACHE_CAPACITY: usize = 100_000;
+
+/// Time before a failed subscription to the store's chain tip is re-established
+const SERVER_TIP_SUBSCRIPTION_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Maximum number of clients whose usage is accounted per day, the requests of the other clients
+/// are accounted together
+const SERVER_MAX_USAGE_CLIENTS_PER_DAY: usize = 100_000;
@@ -1,56 +1,158 @@
-use anyhow::Result;
+use std::{collections::BTreeMap, sync::Arc};
+
+use anyhow::{bail, Context, Result};
 use miden_crypto::hash::rpo::RpoDigest;
 use miden_node_proto::{
     block_producer::api_client as block_producer_client,
+    chain_id::ChainIdInterceptor,
     requests::{
-        CheckNullifiersRequest, GetBlockHeaderByNumberRequest, SubmitProvenTransactionRequest,
-        SyncStateRequest,
+        CheckNullifiersRequest, GetBlockHeaderByNumberRequest, GetMempoolStatusRequest,
+        GetNoteAuthenticationPathRequest, GetNoteRecoveryRecordsRequest, GetNoteTagStatsRequest,
+        GetPendingTransactionsByAccountRequest, GetProposedNotesRequest, GetQueueDepthRequest,
+        GetUnconsumedNotesRequest, GetUsageReportRequest, SubmitNoteRecoveryRecordRequest,
+        SubmitProvenBatchRequest, SubmitProvenTransactionRequest, SubscribeNullifiersRequest,
+        SyncAccountsRequest, SyncAllRequest, SyncStateRequest,
     },
     responses::{
-        CheckNullifiersResponse, GetBlockHeaderByNumberResponse, SubmitProvenTransactionResponse,
-        SyncStateResponse,
+        CheckNullifiersResponse, GetBlockHeaderByNumberResponse, GetMempoolStatusResponse,
+        GetNoteAuthenticationPathResponse, GetNoteRecoveryRecordsResponse, GetNoteTagStatsResponse,
+        GetPendingTransactionsByAccountResponse, GetProposedNotesResponse,
+        GetUnconsumedNotesResponse, GetUsageReportResponse, SubmitNoteRecoveryRecordResponse,
+        SubmitProvenBatchResponse, SubmitProvenTransactionResponse, SubscribeNullifiersResponse,
+        SyncAccountsResponse, SyncAllResponse, SyncStateResponse,
     },
     rpc::api_server,
     store::api_client as store_client,
 };
+use miden_node_utils::{config::parse_genesis_hash, operator_auth::require_operator};
 use tonic::{
-    transport::{Channel, Error},
-    Request, Response, Status,
+    codec::Streaming, service::interceptor::InterceptedService, transport::Channel, Request,
+    Response, Status,
 };
 use tracing::{debug, info, instrument};
 
-use crate::{config::RpcConfig, COMPONENT};
+use super::{
+    audit::{AuditLog, AuditRecord},
+    masks::{
+        mask_note_recovery_records, mask_note_senders, mask_pending_transactions,
+        mask_unconsumed_note_senders, owned_accounts,
+    },
+    network::{Network, NETWORK_METADATA_KEY},
+    sync::merge_sync_updates,
+    usage::{current_day, parse_day, UsageMeter},
+};
+use crate::{
+    config::{FieldMasks, RpcConfig},
+    COMPONENT, GET_USAGE_REPORT_OPERATION,
+};
 
 // RPC API
 // ================================================================================================
 
+/// Client to the store, which attaches the network's chain id to every request.
+pub(super) type StoreClient =
+    store_client::ApiClient<InterceptedService<Channel, ChainIdInterceptor>>;
+
+/// Client to the block producer, which attaches the network's chain id to every request.
+pub(super) type BlockProducerClient =
+    block_producer_client::ApiClient<InterceptedService<Channel, ChainIdInterceptor>>;
+
 pub struct RpcApi {
-    store: store_client::ApiClient<Channel>,
-    block_producer: block_producer_client::ApiClient<Channel>,
+    /// Network serving the requests which don't name one
+    default_network: Network,
+    /// Additional networks, by name
+    networks: BTreeMap<String, Network>,
+    /// Audit log of the mutating requests, `None` if auditing is disabled
+    audit: Option<AuditLog>,
+    /// Response fields cleared before responding
+    field_masks: FieldMasks,
+    /// Usage of the clients, `None` if usage isn't accounted
+    usage: Option<Arc<UsageMeter>>,
 }
 
 impl RpcApi {
-    pub(super) async fn from_config(config: &RpcConfig) -> Result<Self, Error> {
-        let store = store_client::ApiClient::connect(config.store_url.clone()).await?;
-        info!(target: COMPONENT, store_endpoint = config.store_url, "Store client initialized");
-
-        let block_producer =
-            block_producer_client::ApiClient::connect(config.block_producer_url.clone()).await?;
-        info!(
-            target: COMPONENT,
-            block_producer_endpoint = config.block_producer_url,
-            "Block producer client initialized",
-        );
+    pub(super) async fn from_config(config: &RpcConfig) -> Result<Self> {
+        let default_network = Network::connect(
+            None,
+            &config.store_url,
+            &config.block_producer_url,
+            config.chain_id,
+            parse_genesis_hash(config.genesis_hash.as_deref())?,
+            config.max_requests_per_second,
+        )
+        .await?;
+
+        for owner in &config.field_masks.account_owners {
+            if let Err(err) = owner.parse_account_ids() {
+                bail!("Invalid account id of the owner {}: {err}", owner.api_key_sha256);
+            }
+        }
+
+        let mut networks = BTreeMap::new();
+        for network in &config.networks {
+            if networks.contains_key(&network.name) {
+                bail!("Network {} is configured more than once", network.name);
+            }
+
+            let client = Network::connect(
+                Some(network.name.clone()),
+                &network.store_url,
+                &network.block_producer_url,
+                network.chain_id,
+                parse_genesis_hash(network.genesis_hash.as_deref())
+                    .with_context(|| format!("Invalid config of network {}", network.name))?,
+                network.max_requests_per_second,
+            )
+            .await?;
+            networks.insert(network.name.clone(), client);
+        }
 
         Ok(Self {
-            store,
-            block_producer,
+            default_network,
+            networks,
+            audit: AuditLog::new(&config.audit),
+            field_masks: config.field_masks.clone(),
+            usage: UsageMeter::new(&config.usage).map(Arc::new),
         })
     }
+
+    /// Returns the meter accounting the usage of the clients, `None` if usage isn't accounted.
+    pub(super) fn usage(&self) -> Option<Arc<UsageMeter>> {
+        self.usage.clone()
+    }
+
+    /// Returns all the networks served by this API, starting with the default one.
+    pub(super) fn networks(&self) -> impl Iterator<Item = &Network> {
+        std::iter::once(&self.default_network).chain(self.networks.values())
+    }
+
+    /// Returns the network the `request` is sent to, if it can serve the request.
+    fn network<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<&Network, Status> {
+        let network = match request.metadata().get(NETWORK_METADATA_KEY) {
+            None => &self.default_network,
+            Some(name) => {
+                let name = name
+                    .to_str()
+                    .map_err(|_| Status::invalid_argument("Malformed network metadata"))?;
+                self.networks
+                    .get(name)
+                    .ok_or_else(|| Status::not_found(format!("Unknown network {name}")))?
+            },
+        };
+
+        network.admit(request)?;
+
+        Ok(network)
+    }
 }
 
 #[tonic::async_trait]
 impl api_server::Api for RpcApi {
+    type SubscribeNullifiersStream = Streaming<SubscribeNullifiersResponse>;
+
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(
         target = "miden-rpc",
@@ -66,13 +168,37 @@ impl api_server::Api for RpcApi {
         debug!(target: COMPONENT, request = ?request.get_ref());
 
         // validate all the nullifiers from the user request
-        for nullifier in request.get_ref().nullifiers.iter() {
+        let CheckNullifiersRequest {
+            nullifiers,
+            nullifiers_without_proofs,
+        } = request.get_ref();
+        for nullifier in nullifiers.iter().chain(nullifiers_without_proofs) {
             let _: RpoDigest = nullifier
                 .try_into()
                 .or(Err(Status::invalid_argument("Digest field is not in the modulos range")))?;
         }
 
-        self.store.clone().check_nullifiers(request).await
+        let network = self.network(&request)?;
+
+        // Only the unspent nullifiers are cached, the proofs are generated by the store
+        if !request.get_ref().nullifiers.is_empty() {
+            return network
+                .store
+                .clone()
+                .check_nullifiers(request)
+                .await
+                .map(|response| network.with_chain_id(response));
+        }
+
+        let block_nums = network
+            .check_nullifiers_without_proofs(&request.get_ref().nullifiers_without_proofs)
+            .await?;
+        let response = CheckNullifiersResponse {
+            proofs: Vec::new(),
+            block_nums,
+        };
+
+        Ok(network.with_chain_id(Response::new(response)))
     }
 
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
@@ -89,7 +215,50 @@ impl api_server::Api for RpcApi {
     ) -> Result<Response<GetBlockHeaderByNumberResponse>, Status> {
         info!(target: COMPONENT, request = ?request.get_ref());
 
-        self.store.clone().get_block_header_by_number(request).await
+        let network = self.network(&request)?;
+
+        // Only the chain tip is cached, requests for other blocks or for MMR proofs go to the store
+        let cacheable = request.get_ref().block_num.is_none()
+            && !request.get_ref().include_mmr_proof.unwrap_or_default();
+        if !cacheable {
+            let response = network.store.clone().get_block_header_by_number(request).await?;
+            let chain_tip_hash = response
+                .get_ref()
+                .chain_tip_hash
+                .as_ref()
+                .and_then(|hash| RpoDigest::try_from(hash).ok());
+            network.tip.observe(response.get_ref().chain_tip, chain_tip_hash).await;
+
+            return Ok(network.with_chain_id(response));
+        }
+
+        let if_none_match = request
+            .get_ref()
+            .if_none_match
+            .as_ref()
+            .map(RpoDigest::try_from)
+            .transpose()
+            .map_err(|err| Status::invalid_argument(format!("Invalid tip hash: {err}")))?;
+
+        let tip = network
+            .tip
+            .get(|| async {
+                let request = Request::new(request.get_ref().clone());
+                let response = network.store.clone().get_block_header_by_number(request).await?;
+                Ok(response.into_inner())
+            })
+            .await?;
+
+        let response = if if_none_match == Some(tip.hash) {
+            GetBlockHeaderByNumberResponse {
+                not_modified: true,
+                ..Default::default()
+            }
+        } else {
+            tip.into_response()
+        };
+
+        Ok(network.with_chain_id(Response::new(response)))
     }
 
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
@@ -106,7 +275,238 @@ impl api_server::Api for RpcApi {
     ) -> Result<Response<SyncStateResponse>, Status> {
         debug!(target: COMPONENT, request = ?request.get_ref());
 
-        self.store.clone().sync_state(request).await
+        let network = self.network(&request)?;
+        let owned_accounts = owned_accounts(&self.field_masks, &request);
+        let mut response = network.store.clone().sync_state(request).await?;
+        mask_note_senders(&self.field_masks, &owned_accounts, &mut response.get_mut().notes);
+        network.tip.observe(response.get_ref().chain_tip, None).await;
+
+        Ok(network.with_chain_id(response))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-rpc", name = "rpc:sync_all", skip_all, ret(level = "debug"), err)]
+    async fn sync_all(
+        &self,
+        request: Request<SyncAllRequest>,
+    ) -> Result<Response<SyncAllResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.get_ref());
+
+        let network = self.network(&request)?;
+        let owned_accounts = owned_accounts(&self.field_masks, &request);
+        let SyncAllRequest {
+            block_num,
+            account_ids,
+            note_tags,
+            nullifiers,
+            note_hashes,
+        } = request.into_inner();
+
+        for nullifier in &nullifiers {
+            let _: RpoDigest = nullifier
+                .try_into()
+                .or(Err(Status::invalid_argument("Digest field is not in the modulos range")))?;
+        }
+
+        // the nullifiers are checked exactly, so they are not part of the sync request's filter
+        let sync_request = SyncStateRequest {
+            block_num,
+            account_ids,
+            note_tags,
+            nullifiers: Vec::new(),
+            note_hashes,
+            resume_token: Vec::new(),
+        };
+        let check_nullifiers = network.check_nullifiers_without_proofs(&nullifiers);
+
+        let (sync, block_nums) =
+            tokio::try_join!(network.store.clone().sync_state(sync_request), check_nullifiers)?;
+
+        let mut sync = sync.into_inner();
+        mask_note_senders(&self.field_masks, &owned_accounts, &mut sync.notes);
+
+        let response = merge_sync_updates(sync, nullifiers, block_nums);
+        network.tip.observe(response.chain_tip, None).await;
+
+        Ok(network.with_chain_id(Response::new(response)))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-rpc",
+        name = "rpc:sync_accounts",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn sync_accounts(
+        &self,
+        request: Request<SyncAccountsRequest>,
+    ) -> Result<Response<SyncAccountsResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.get_ref());
+
+        let network = self.network(&request)?;
+        let response = network.store.clone().sync_accounts(request).await?;
+        if let Some(block_header) = &response.get_ref().block_header {
+            network.tip.observe(block_header.block_num, None).await;
+        }
+
+        Ok(network.with_chain_id(response))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-rpc", name = "rpc:subscribe_nullifiers", skip_all, err)]
+    async fn subscribe_nullifiers(
+        &self,
+        request: Request<SubscribeNullifiersRequest>,
+    ) -> Result<Response<Self::SubscribeNullifiersStream>, Status> {
+        debug!(target: COMPONENT, request = ?request.get_ref());
+
+        for nullifier in request.get_ref().nullifiers.iter() {
+            let _: RpoDigest = nullifier
+                .try_into()
+                .or(Err(Status::invalid_argument("Digest field is not in the modulos range")))?;
+        }
+
+        // the store's stream is forwarded as is, the subscription ends along with it
+        let network = self.network(&request)?;
+        network
+            .store
+            .clone()
+            .subscribe_nullifiers(request)
+            .await
+            .map(|response| network.with_chain_id(response))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-rpc",
+        name = "rpc:get_note_authentication_path",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_note_authentication_path(
+        &self,
+        request: Request<GetNoteAuthenticationPathRequest>,
+    ) -> Result<Response<GetNoteAuthenticationPathResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.get_ref());
+
+        let network = self.network(&request)?;
+        network
+            .store
+            .clone()
+            .get_note_authentication_path(request)
+            .await
+            .map(|response| network.with_chain_id(response))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-rpc",
+        name = "rpc:get_note_recovery_records",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_note_recovery_records(
+        &self,
+        request: Request<GetNoteRecoveryRecordsRequest>,
+    ) -> Result<Response<GetNoteRecoveryRecordsResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.get_ref());
+
+        let network = self.network(&request)?;
+        let owned_accounts = owned_accounts(&self.field_masks, &request);
+        let mut response = network.store.clone().get_note_recovery_records(request).await?;
+        mask_note_recovery_records(
+            &self.field_masks,
+            &owned_accounts,
+            &mut response.get_mut().records,
+        );
+
+        Ok(network.with_chain_id(response))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-rpc",
+        name = "rpc:get_note_tag_stats",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_note_tag_stats(
+        &self,
+        request: Request<GetNoteTagStatsRequest>,
+    ) -> Result<Response<GetNoteTagStatsResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.get_ref());
+
+        let network = self.network(&request)?;
+
+        // the note activity is kept by the store, the queued transactions by the block producer
+        let (stats, queue_depth) = tokio::try_join!(
+            network.store.clone().get_note_tag_stats(request.into_inner()),
+            network.block_producer.clone().get_queue_depth(GetQueueDepthRequest {}),
+        )?;
+
+        let mut response = stats.into_inner();
+        response.queue_depth = Some(queue_depth.into_inner());
+        network.tip.observe(response.chain_tip, None).await;
+
+        Ok(network.with_chain_id(Response::new(response)))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-rpc",
+        name = "rpc:get_unconsumed_notes",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_unconsumed_notes(
+        &self,
+        request: Request<GetUnconsumedNotesRequest>,
+    ) -> Result<Response<GetUnconsumedNotesResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.get_ref());
+
+        let network = self.network(&request)?;
+        let owned_accounts = owned_accounts(&self.field_masks, &request);
+        let mut response = network.store.clone().get_unconsumed_notes(request).await?;
+        mask_unconsumed_note_senders(
+            &self.field_masks,
+            &owned_accounts,
+            &mut response.get_mut().notes,
+        );
+        network.tip.observe(response.get_ref().chain_tip, None).await;
+
+        Ok(network.with_chain_id(response))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-rpc",
+        name = "rpc:get_proposed_notes",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_proposed_notes(
+        &self,
+        request: Request<GetProposedNotesRequest>,
+    ) -> Result<Response<GetProposedNotesResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.get_ref());
+
+        let network = self.network(&request)?;
+        let owned_accounts = owned_accounts(&self.field_masks, &request);
+        let mut response = network.store.clone().get_proposed_notes(request).await?;
+        mask_unconsumed_note_senders(
+            &self.field_masks,
+            &owned_accounts,
+            &mut response.get_mut().notes,
+        );
+
+        Ok(network.with_chain_id(response))
     }
 
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
@@ -117,6 +517,169 @@ impl api_server::Api for RpcApi {
     ) -> Result<Response<SubmitProvenTransactionResponse>, Status> {
         debug!(target: COMPONENT, request = ?request.get_ref());
 
-        self.block_producer.clone().submit_proven_transaction(request).await
+        let record = self
+            .audit
+            .as_ref()
+            .map(|_| AuditRecord::submission(&request, &request.get_ref().transaction));
+
+        let result = match self.network(&request) {
+            Ok(network) => network
+                .block_producer
+                .clone()
+                .submit_proven_transaction(request)
+                .await
+                .map(|response| network.with_chain_id(response)),
+            Err(err) => Err(err),
+        };
+
+        if let (Some(audit), Some(record)) = (&self.audit, record) {
+            audit.write(&record.with_outcome(&result));
+        }
+
+        result
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-rpc", name = "rpc:submit_proven_batch", skip_all, err)]
+    async fn submit_proven_batch(
+        &self,
+        request: Request<SubmitProvenBatchRequest>,
+    ) -> Result<Response<SubmitProvenBatchResponse>, Status> {
+        debug!(target: COMPONENT, num_txs = request.get_ref().transactions.len());
+
+        // every transaction of the batch is recorded, with the outcome of the whole batch
+        let records: Vec<AuditRecord> = match &self.audit {
+            Some(_) => request
+                .get_ref()
+                .transactions
+                .iter()
+                .map(|tx| AuditRecord::batch_submission(&request, &tx.transaction))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let result = match self.network(&request) {
+            Ok(network) => network
+                .block_producer
+                .clone()
+                .submit_proven_batch(request)
+                .await
+                .map(|response| network.with_chain_id(response)),
+            Err(err) => Err(err),
+        };
+
+        if let Some(audit) = &self.audit {
+            for record in records {
+                audit.write(&record.with_outcome(&result));
+            }
+        }
+
+        result
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-rpc",
+        name = "rpc:submit_note_recovery_record",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn submit_note_recovery_record(
+        &self,
+        request: Request<SubmitNoteRecoveryRecordRequest>,
+    ) -> Result<Response<SubmitNoteRecoveryRecordResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.get_ref());
+
+        let network = self.network(&request)?;
+        network
+            .store
+            .clone()
+            .submit_note_recovery_record(request)
+            .await
+            .map(|response| network.with_chain_id(response))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-rpc",
+        name = "rpc:get_pending_transactions_by_account",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_pending_transactions_by_account(
+        &self,
+        request: Request<GetPendingTransactionsByAccountRequest>,
+    ) -> Result<Response<GetPendingTransactionsByAccountResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.get_ref());
+
+        let network = self.network(&request)?;
+        let mut response = network
+            .block_producer
+            .clone()
+            .get_pending_transactions_by_account(request)
+            .await?;
+        mask_pending_transactions(&self.field_masks, &mut response.get_mut().transactions);
+
+        Ok(network.with_chain_id(response))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-rpc",
+        name = "rpc:get_mempool_status",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_mempool_status(
+        &self,
+        request: Request<GetMempoolStatusRequest>,
+    ) -> Result<Response<GetMempoolStatusResponse>, Status> {
+        debug!(target: COMPONENT, request = ?request.get_ref());
+
+        let network = self.network(&request)?;
+        network
+            .block_producer
+            .clone()
+            .get_mempool_status(request)
+            .await
+            .map(|response| network.with_chain_id(response))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-rpc",
+        name = "rpc:get_usage_report",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_usage_report(
+        &self,
+        request: Request<GetUsageReportRequest>,
+    ) -> Result<Response<GetUsageReportResponse>, Status> {
+        require_operator(&request, GET_USAGE_REPORT_OPERATION)?;
+        let request = request.into_inner();
+        debug!(target: COMPONENT, ?request);
+
+        let usage = self
+            .usage
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("Usage isn't accounted"))?;
+        let day = if request.day.is_empty() {
+            current_day()
+        } else {
+            parse_day(&request.day)
+                .ok_or_else(|| Status::invalid_argument(format!("Invalid day {}", request.day)))?
+        };
+
+        let report = usage
+            .report(day)
+            .await
+            .map_err(|err| Status::internal(format!("Failed to read the usage report: {err}")))?;
+
+        Ok(Response::new(report.into()))
     }
 }
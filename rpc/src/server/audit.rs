@@ -0,0 +1,372 @@
+//! Audit log of the mutating requests served by the RPC.
+//!
+//! Operators of public endpoints may need to account for every transaction submitted through them.
+//! Every submission is recorded with the identity of the client, as far as the RPC can tell it, the
+//! id of the transaction and the outcome of the request.
+//!
+//! The records are appended as JSON lines to `<directory>/audit.log`. Once the file exceeds the
+//! configured size it is renamed to `audit-<timestamp>.log`, and the oldest rotated files beyond the
+//! configured number are removed. The files are written off the async runtime. Failing to write a
+//! record is logged, and doesn't fail the request.
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    net::SocketAddr,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use miden_crypto::utils::Deserializable;
+use miden_objects::transaction::ProvenTransaction;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tonic::{Request, Status};
+use tracing::{info, warn};
+
+use super::network::NETWORK_METADATA_KEY;
+use crate::{config::AuditConfig, COMPONENT};
+
+/// The target of the tracing events emitted for the audit records.
+pub const AUDIT_TARGET: &str = "miden-rpc-audit";
+
+/// Name of the log file the records are appended to.
+const ACTIVE_FILE_NAME: &str = "audit.log";
+
+/// Number of records waiting to be written beyond which further records are dropped.
+const AUDIT_LOG_CAPACITY: usize = 10_000;
+
+// AUDIT RECORD
+// ================================================================================================
+
+/// The client which sent a request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(super) struct ClientIdentity {
+    /// Address of the peer, i.e. of the proxy if the RPC runs behind one
+    pub addr: Option<SocketAddr>,
+    /// Addresses given by the proxies in the `x-forwarded-for` header
+    pub forwarded_for: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl ClientIdentity {
+    fn of<T>(request: &Request<T>) -> Self {
+        let read = |key: &str| {
+            request
+                .metadata()
+                .get(key)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+
+        Self {
+            addr: request.remote_addr(),
+            forwarded_for: read("x-forwarded-for"),
+            user_agent: read("user-agent"),
+        }
+    }
+}
+
+/// A mutating request, and its outcome.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(super) struct AuditRecord {
+    /// Time the request was received at, in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+    pub method: &'static str,
+    /// Network named by the request, `None` for the default network
+    pub network: Option<String>,
+    pub client: ClientIdentity,
+    /// ID of the submitted transaction, `None` if it couldn't be parsed
+    pub tx_id: Option<String>,
+    /// `OK`, or the gRPC status code of the error
+    pub outcome: String,
+    /// Message of the error, if the request failed
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    /// Starts the record of the submission of the serialized `transaction`.
+    pub(super) fn submission<T>(
+        request: &Request<T>,
+        transaction: &[u8],
+    ) -> Self {
+        let network = request
+            .metadata()
+            .get(NETWORK_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let tx_id = ProvenTransaction::read_from_bytes(transaction).ok().map(|tx| tx.id().to_hex());
+
+        Self {
+            timestamp: now_millis(),
+            method: "SubmitProvenTransaction",
+            network,
+            client: ClientIdentity::of(request),
+            tx_id,
+            outcome: String::new(),
+            error: None,
+        }
+    }
+
+    /// Starts the record of the serialized `transaction`, submitted as part of a batch.
+    pub(super) fn batch_submission<T>(
+        request: &Request<T>,
+        transaction: &[u8],
+    ) -> Self {
+        Self {
+            method: "SubmitProvenBatch",
+            ..Self::submission(request, transaction)
+        }
+    }
+
+    /// Completes the record with the `result` of the request.
+    pub(super) fn with_outcome<R>(
+        mut self,
+        result: &Result<R, Status>,
+    ) -> Self {
+        match result {
+            Ok(_) => self.outcome = "OK".to_string(),
+            Err(status) => {
+                self.outcome = format!("{:?}", status.code());
+                self.error = Some(status.message().to_string());
+            },
+        }
+        self
+    }
+}
+
+// AUDIT LOG
+// ================================================================================================
+
+/// Writes the [AuditRecord]s to the rotating log files, and to the tracing events.
+///
+/// The files are written by a [AuditWriter] running off the async runtime, which the records are
+/// sent to, so the requests never wait on the disk.
+pub(super) struct AuditLog {
+    emit_events: bool,
+    /// Sends the records to the writer, `None` if the records are not written to files
+    writer: Option<mpsc::Sender<String>>,
+}
+
+impl AuditLog {
+    /// Returns the audit log described by `config`, or `None` if auditing is disabled.
+    ///
+    /// Must be called within the tokio runtime, which runs the writer of the log files.
+    pub(super) fn new(config: &AuditConfig) -> Option<Self> {
+        if !config.is_enabled() {
+            return None;
+        }
+
+        let writer = AuditWriter::new(config).map(|mut writer| {
+            let (sender, mut receiver) = mpsc::channel::<String>(AUDIT_LOG_CAPACITY);
+            tokio::task::spawn_blocking(move || {
+                while let Some(line) = receiver.blocking_recv() {
+                    writer.write(&line);
+                }
+            });
+            sender
+        });
+
+        Some(Self {
+            emit_events: config.emit_events,
+            writer,
+        })
+    }
+
+    /// Records `record`, failures are logged.
+    pub(super) fn write(
+        &self,
+        record: &AuditRecord,
+    ) {
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(err) => {
+                warn!(target: COMPONENT, %err, "Failed to serialize audit record");
+                return;
+            },
+        };
+
+        if self.emit_events {
+            info!(target: AUDIT_TARGET, record = %line);
+        }
+
+        if let Some(writer) = &self.writer {
+            if let Err(err) = writer.try_send(line) {
+                warn!(target: COMPONENT, %err, "Failed to queue audit record");
+            }
+        }
+    }
+}
+
+// AUDIT WRITER
+// ================================================================================================
+
+/// Appends the records to the active log file, and rotates it.
+struct AuditWriter {
+    directory: PathBuf,
+    /// Size of the active file in bytes beyond which it is rotated, `0` disables the rotation
+    max_file_size: u64,
+    max_files: usize,
+    /// The active file and its size, opened on the first record
+    active: Option<(File, u64)>,
+}
+
+impl AuditWriter {
+    /// Returns the writer of the log files described by `config`, `None` if it has no directory.
+    fn new(config: &AuditConfig) -> Option<Self> {
+        Some(Self {
+            directory: config.directory.clone()?,
+            max_file_size: config.max_file_size_mb * 1024 * 1024,
+            max_files: config.max_files as usize,
+            active: None,
+        })
+    }
+
+    /// Appends `line` to the active file, failures are logged.
+    fn write(
+        &mut self,
+        line: &str,
+    ) {
+        if let Err(err) = self.append(line) {
+            warn!(target: COMPONENT, directory = ?self.directory, %err, "Failed to write audit record");
+        }
+    }
+
+    fn append(
+        &mut self,
+        line: &str,
+    ) -> io::Result<()> {
+        if let Some((_, size)) = self.active.as_ref() {
+            if self.max_file_size != 0 && *size >= self.max_file_size {
+                self.active = None;
+                self.rotate()?;
+            }
+        }
+
+        if self.active.is_none() {
+            fs::create_dir_all(&self.directory)?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.directory.join(ACTIVE_FILE_NAME))?;
+            let size = file.metadata()?.len();
+            self.active = Some((file, size));
+        }
+
+        let (file, size) = self.active.as_mut().expect("active file was just opened");
+        file.write_all(format!("{line}\n").as_bytes())?;
+        *size += line.len() as u64 + 1;
+
+        Ok(())
+    }
+
+    /// Renames the active file after the current time, and removes the oldest rotated files.
+    fn rotate(&self) -> io::Result<()> {
+        let directory = &self.directory;
+
+        let mut timestamp = now_millis();
+        let mut rotated = directory.join(format!("audit-{timestamp:013}.log"));
+        while rotated.exists() {
+            timestamp += 1;
+            rotated = directory.join(format!("audit-{timestamp:013}.log"));
+        }
+        fs::rename(directory.join(ACTIVE_FILE_NAME), &rotated)?;
+        info!(target: COMPONENT, ?rotated, "Audit log rotated");
+
+        if self.max_files == 0 {
+            return Ok(());
+        }
+
+        // the zero padded timestamps sort the rotated files by age
+        let mut files = Vec::new();
+        for entry in fs::read_dir(directory)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if name.starts_with("audit-") && name.ends_with(".log") {
+                files.push(name);
+            }
+        }
+        files.sort();
+
+        let num_removed = files.len().saturating_sub(self.max_files);
+        for name in &files[..num_removed] {
+            fs::remove_file(directory.join(name))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the current time, in milliseconds since the UNIX epoch.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use figment::Jail;
+    use tonic::{Request, Status};
+
+    use super::{AuditLog, AuditRecord, AuditWriter, ACTIVE_FILE_NAME};
+    use crate::config::AuditConfig;
+
+    fn record(request: &Request<()>) -> AuditRecord {
+        AuditRecord::submission(request, &[0, 1, 2])
+    }
+
+    #[test]
+    fn test_audit_record_outcome() {
+        let mut request = Request::new(());
+        request.metadata_mut().insert("user-agent", "miden-client".parse().unwrap());
+
+        let accepted = record(&request).with_outcome(&Ok::<_, Status>(()));
+        assert_eq!(accepted.outcome, "OK");
+        assert_eq!(accepted.error, None);
+        assert_eq!(accepted.tx_id, None, "malformed transactions have no id");
+        assert_eq!(accepted.client.user_agent.as_deref(), Some("miden-client"));
+
+        let rejected =
+            record(&request).with_outcome(&Err::<(), _>(Status::invalid_argument("bad proof")));
+        assert_eq!(rejected.outcome, "InvalidArgument");
+        assert_eq!(rejected.error.as_deref(), Some("bad proof"));
+    }
+
+    #[test]
+    fn test_audit_log_rotation() {
+        Jail::expect_with(|_jail| {
+            assert!(AuditLog::new(&AuditConfig::default()).is_none());
+
+            let mut writer = AuditWriter::new(&AuditConfig {
+                directory: Some("audit".into()),
+                max_file_size_mb: 1,
+                max_files: 2,
+                emit_events: false,
+            })
+            .unwrap();
+            // rotate after every record
+            writer.max_file_size = 1;
+
+            let record = record(&Request::new(()));
+            let line = serde_json::to_string(&record).unwrap();
+            for _ in 0..4 {
+                writer.write(&line);
+            }
+
+            let mut files: Vec<String> = fs::read_dir("audit")
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            files.sort();
+            assert_eq!(files.len(), 3, "the active file and two rotated files are kept");
+            assert_eq!(files[2], ACTIVE_FILE_NAME);
+
+            let line = fs::read_to_string(format!("audit/{ACTIVE_FILE_NAME}")).unwrap();
+            assert_eq!(line, format!("{}\n", serde_json::to_string(&record).unwrap()));
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,288 @@
+//! Clearing of the response fields masked by the operator, see [FieldMasks].
+//!
+//! The notes sent by an account owned by the client keep their sender, the client already knows
+//! it. A client owns the accounts configured for the API key it gives in the `x-api-key` metadata,
+//! see [AccountOwner](crate::config::AccountOwner). The accounts a client requests are not owned by
+//! it, as any client may request any account.
+use std::collections::BTreeSet;
+
+use miden_crypto::utils::Deserializable;
+use miden_node_proto::{
+    note::{Note, NoteRecoveryRecord, NoteSyncRecord},
+    responses::PendingTransaction,
+};
+use miden_objects::notes::Note as ObjectNote;
+use sha2::{Digest, Sha256};
+use tonic::Request;
+
+use super::usage::API_KEY_METADATA_KEY;
+use crate::config::FieldMasks;
+
+/// Returns the ids of the accounts owned by the client which sent `request`, authenticated by its
+/// API key, if the note senders are masked.
+pub(super) fn owned_accounts<T>(
+    masks: &FieldMasks,
+    request: &Request<T>,
+) -> BTreeSet<u64> {
+    if !masks.note_senders || masks.account_owners.is_empty() {
+        return BTreeSet::new();
+    }
+    let Some(api_key) = request.metadata().get(API_KEY_METADATA_KEY) else {
+        return BTreeSet::new();
+    };
+
+    let api_key_sha256 = hex::encode(Sha256::digest(api_key.as_bytes()));
+    masks
+        .account_owners
+        .iter()
+        .filter(|owner| owner.api_key_sha256.eq_ignore_ascii_case(&api_key_sha256))
+        .flat_map(|owner| owner.parse_account_ids().unwrap_or_default())
+        .collect()
+}
+
+/// Clears the sender of the synced `notes`, if the note senders are masked.
+///
+/// The notes sent by one of the `owned_accounts` of the client keep their sender.
+pub(super) fn mask_note_senders(
+    masks: &FieldMasks,
+    owned_accounts: &BTreeSet<u64>,
+    notes: &mut [NoteSyncRecord],
+) {
+    if !masks.note_senders {
+        return;
+    }
+
+    for note in notes.iter_mut() {
+        if !owned_accounts.contains(&note.sender) {
+            note.sender = 0;
+        }
+    }
+}
+
+/// Clears the details of the recovered notes, which hold their sender, if the note senders are
+/// masked.
+///
+/// The notes sent by one of the `owned_accounts` of the client keep their details, the others are
+/// left with their hash and inclusion path.
+pub(super) fn mask_note_recovery_records(
+    masks: &FieldMasks,
+    owned_accounts: &BTreeSet<u64>,
+    records: &mut [NoteRecoveryRecord],
+) {
+    if !masks.note_senders {
+        return;
+    }
+
+    for record in records.iter_mut() {
+        let owned = ObjectNote::read_from_bytes(&record.details)
+            .is_ok_and(|note| owned_accounts.contains(&note.metadata().sender().into()));
+        if !owned {
+            record.details.clear();
+        }
+    }
+}
+
+/// Clears the sender of the unconsumed or proposed `notes`, if the note senders are masked.
+///
+/// The notes sent by one of the `owned_accounts` of the client keep their sender.
+pub(super) fn mask_unconsumed_note_senders(
+    masks: &FieldMasks,
+    owned_accounts: &BTreeSet<u64>,
+    notes: &mut [Note],
+) {
+    if !masks.note_senders {
+        return;
+    }
+
+    for note in notes.iter_mut() {
+        if !owned_accounts.contains(&note.sender) {
+            note.sender = 0;
+        }
+    }
+}
+
+/// Clears the account hashes of the pending `transactions`, if they are masked.
+pub(super) fn mask_pending_transactions(
+    masks: &FieldMasks,
+    transactions: &mut [PendingTransaction],
+) {
+    if !masks.pending_account_hashes {
+        return;
+    }
+
+    for transaction in transactions.iter_mut() {
+        transaction.initial_account_hash = None;
+        transaction.final_account_hash = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use miden_crypto::{rand::RpoRandomCoin, utils::Serializable};
+    use miden_lib::notes::create_p2id_note;
+    use miden_node_proto::{
+        digest::Digest,
+        note::{Note, NoteRecoveryRecord, NoteSyncRecord},
+        responses::PendingTransaction,
+    };
+    use miden_objects::{accounts::AccountId, Felt};
+    use tonic::Request;
+
+    use super::{
+        mask_note_recovery_records, mask_note_senders, mask_pending_transactions,
+        mask_unconsumed_note_senders, owned_accounts, API_KEY_METADATA_KEY,
+    };
+    use crate::config::{AccountOwner, FieldMasks};
+
+    const SENDER: u64 = 0x8000_0000_0000_0001;
+
+    /// Returns the masks of the note senders, the accounts 1 and [SENDER] being owned by the
+    /// client with the API key `customer-1`.
+    fn sender_masks() -> FieldMasks {
+        FieldMasks {
+            note_senders: true,
+            account_owners: vec![AccountOwner {
+                // SHA-256 of `customer-1`
+                api_key_sha256: "e83f10dcd2c68747c3f3ba14a54258d5c1843a8d75b0f5cb52c6f3df052a72d1"
+                    .to_string(),
+                account_ids: vec!["0x1".to_string(), format!("{SENDER:#x}")],
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn request(api_key: Option<&str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(api_key) = api_key {
+            request.metadata_mut().insert(API_KEY_METADATA_KEY, api_key.parse().unwrap());
+        }
+        request
+    }
+
+    fn note(sender: u64) -> NoteSyncRecord {
+        NoteSyncRecord {
+            sender,
+            tag: 1 << 48,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_owned_accounts() {
+        let masks = sender_masks();
+        let owned = owned_accounts(&masks, &request(Some("customer-1")));
+        assert_eq!(owned, BTreeSet::from([1, SENDER]));
+
+        // the clients with another API key, or without any, own no account
+        assert!(owned_accounts(&masks, &request(Some("customer-2"))).is_empty());
+        assert!(owned_accounts(&masks, &request(None)).is_empty());
+
+        // the owners are ignored unless the senders are masked
+        let masks = FieldMasks {
+            note_senders: false,
+            ..sender_masks()
+        };
+        assert!(owned_accounts(&masks, &request(Some("customer-1"))).is_empty());
+    }
+
+    #[test]
+    fn test_mask_note_senders() {
+        let masks = sender_masks();
+
+        // only the notes sent by an owned account keep their sender, requesting an account
+        // doesn't reveal the senders of its notes
+        let mut notes = vec![note(1), note(2)];
+        mask_note_senders(&masks, &BTreeSet::from([1]), &mut notes);
+        assert_eq!(notes, vec![note(1), note(0)]);
+
+        let mut notes = vec![note(1), note(2)];
+        mask_note_senders(&masks, &BTreeSet::new(), &mut notes);
+        assert_eq!(notes, vec![note(0), note(0)]);
+
+        // nothing is cleared unless masked
+        let mut notes = vec![note(1), note(2)];
+        mask_note_senders(&FieldMasks::default(), &BTreeSet::new(), &mut notes);
+        assert_eq!(notes, vec![note(1), note(2)]);
+
+        // and so do the unconsumed notes
+        let unconsumed = |sender: u64| Note {
+            sender,
+            ..Default::default()
+        };
+        let mut notes = vec![unconsumed(1), unconsumed(2)];
+        mask_unconsumed_note_senders(&masks, &BTreeSet::from([1]), &mut notes);
+        assert_eq!(notes, vec![unconsumed(1), unconsumed(0)]);
+    }
+
+    #[test]
+    fn test_mask_note_recovery_records() {
+        let sender = AccountId::new_unchecked(Felt::new(SENDER));
+        let target = AccountId::new_unchecked(Felt::new(0x8000_0000_0000_0002));
+        let note =
+            create_p2id_note(sender, target, Vec::new(), RpoRandomCoin::new([Felt::new(1); 4]))
+                .unwrap();
+        let record = NoteRecoveryRecord {
+            block_num: 1,
+            details: note.to_bytes(),
+            ..Default::default()
+        };
+        let masks = sender_masks();
+
+        // the owner of the sender recovers the note
+        let mut records = vec![record.clone()];
+        mask_note_recovery_records(&masks, &BTreeSet::from([SENDER]), &mut records);
+        assert_eq!(records, vec![record.clone()]);
+
+        // the other clients only get its hash and path
+        let mut records = vec![record.clone()];
+        mask_note_recovery_records(&masks, &BTreeSet::from([1]), &mut records);
+        assert_eq!(
+            records,
+            vec![NoteRecoveryRecord {
+                details: Vec::new(),
+                ..record.clone()
+            }]
+        );
+
+        // nothing is cleared unless masked
+        let mut records = vec![record.clone()];
+        mask_note_recovery_records(&FieldMasks::default(), &BTreeSet::new(), &mut records);
+        assert_eq!(records, vec![record]);
+    }
+
+    #[test]
+    fn test_mask_pending_transactions() {
+        let transaction = PendingTransaction {
+            tx_id: Some(Digest {
+                d0: 1,
+                ..Default::default()
+            }),
+            initial_account_hash: Some(Digest {
+                d0: 2,
+                ..Default::default()
+            }),
+            final_account_hash: Some(Digest {
+                d0: 3,
+                ..Default::default()
+            }),
+            queue_position: 4,
+        };
+        let masks = FieldMasks {
+            pending_account_hashes: true,
+            ..Default::default()
+        };
+
+        let mut transactions = vec![transaction.clone()];
+        mask_pending_transactions(&masks, &mut transactions);
+        assert_eq!(
+            transactions,
+            vec![PendingTransaction {
+                initial_account_hash: None,
+                final_account_hash: None,
+                ..transaction
+            }]
+        );
+    }
+}
@@ -1,13 +1,31 @@
-use std::net::ToSocketAddrs;
+use anyhow::{Context, Result};
+use miden_crypto::hash::rpo::RpoDigest;
+use miden_node_proto::{
+    domain::NodeInfo, errors::NodeInfoError, requests::GetNodeInfoRequest, rpc::api_server,
+};
+use miden_node_utils::operator_auth::{OperatorAuthenticator, OperatorKeys};
+use tokio::{task::JoinSet, time};
+use tracing::{info, instrument, warn};
 
-use anyhow::{anyhow, Result};
-use miden_node_proto::rpc::api_server;
-use tonic::transport::Server;
-use tracing::{info, instrument};
-
-use crate::{config::RpcConfig, COMPONENT};
+use self::{
+    api::StoreClient,
+    network::NetworkPathLayer,
+    usage::{flush_usage, UsageLayer},
+};
+use crate::{config::RpcConfig, COMPONENT, SERVER_NODE_INFO_CHECK_FREQUENCY};
 
 mod api;
+mod audit;
+mod masks;
+mod network;
+mod nullifier_cache;
+mod sync;
+mod tip_cache;
+mod usage;
+
+pub use audit::AUDIT_TARGET;
+pub use network::NETWORK_METADATA_KEY;
+pub use usage::API_KEY_METADATA_KEY;
 
 // RPC INITIALIZER
 // ================================================================================================
@@ -15,17 +33,110 @@ mod api;
 pub async fn serve(config: RpcConfig) -> Result<()> {
     info!(target: COMPONENT, %config, "Initializing server");
 
+    let operator_keys = OperatorKeys::from_hex(&config.operator_keys)?;
+    if operator_keys.is_empty() {
+        info!(target: COMPONENT, "No operator key configured, admin operations are disabled");
+    }
+
     let api = api::RpcApi::from_config(&config).await?;
-    let rpc = api_server::ApiServer::new(api);
+
+    // Refuse to serve clients on behalf of a store running an incompatible protocol.
+    let mut watchers = JoinSet::new();
+    for network in api.networks() {
+        let name = network.display_name().to_string();
+        let store = network.store.clone();
+        let chain_id = network.chain_id;
+
+        let node_info = verify_node_info(store.clone(), chain_id, network.genesis_hash)
+            .await
+            .with_context(|| format!("Store handshake failed for network {name}"))?;
+        info!(
+            target: COMPONENT,
+            network = name,
+            genesis_hash = %node_info.genesis_hash,
+            store_version = node_info.version,
+            "Store handshake succeeded"
+        );
+
+        watchers.spawn(async move {
+            let err = watch_node_info(store, chain_id, node_info.genesis_hash).await;
+            (name, err)
+        });
+    }
+
+    let usage = api.usage();
+    let rpc = api_server::ApiServer::with_interceptor(
+        api,
+        OperatorAuthenticator::new(config.chain_id, operator_keys),
+    );
+
+    if let Some(usage) = &usage {
+        tokio::spawn(flush_usage(usage.clone()));
+    }
 
     info!(target: COMPONENT, "Server initialized");
 
-    let addr = config
-        .endpoint
-        .to_socket_addrs()?
-        .next()
-        .ok_or(anyhow!("Couldn't resolve server address"))?;
-    Server::builder().add_service(rpc).serve(addr).await?;
+    let incoming = config.grpc.incoming(&config.endpoint, &config.listen)?;
+    let server = config
+        .grpc
+        .server_builder()
+        .layer(NetworkPathLayer)
+        .layer(UsageLayer::new(usage))
+        .add_service(rpc);
+
+    tokio::select! {
+        result = server.serve_with_incoming(incoming) => result?,
+        Some(result) = watchers.join_next() => {
+            let (name, err) = result?;
+            return Err(anyhow::Error::from(err).context(format!("Store of network {name} failed verification")))
+        },
+    }
 
     Ok(())
 }
+
+// NODE INFO
+// ================================================================================================
+
+/// Retrieves the store's [NodeInfo], and verifies the RPC can operate against it.
+///
+/// See [NodeInfo::verify] for the meaning of `chain_id` and `genesis_hash`.
+async fn verify_node_info(
+    mut store: StoreClient,
+    chain_id: u32,
+    genesis_hash: Option<RpoDigest>,
+) -> Result<NodeInfo, NodeInfoError> {
+    let node_info: NodeInfo = store
+        .get_node_info(tonic::Request::new(GetNodeInfoRequest {}))
+        .await
+        .map_err(|status| NodeInfoError::GrpcClientError(status.message().to_string()))?
+        .into_inner()
+        .try_into()?;
+
+    node_info.verify(chain_id, genesis_hash)?;
+
+    Ok(node_info)
+}
+
+/// Periodically verifies the store still serves the chain `chain_id` starting at `genesis_hash`.
+///
+/// Returns only once the verification failed, failures to reach the store are retried.
+async fn watch_node_info(
+    store: StoreClient,
+    chain_id: u32,
+    genesis_hash: RpoDigest,
+) -> NodeInfoError {
+    let mut interval = time::interval(SERVER_NODE_INFO_CHECK_FREQUENCY);
+
+    loop {
+        interval.tick().await;
+
+        match verify_node_info(store.clone(), chain_id, Some(genesis_hash)).await {
+            Ok(_) => (),
+            Err(NodeInfoError::GrpcClientError(err)) => {
+                warn!(target: COMPONENT, %err, "Failed to retrieve node info from the store");
+            },
+            Err(err) => return err,
+        }
+    }
+}
@@ -0,0 +1,361 @@
+//! Routing of the requests to the networks served by the RPC.
+//!
+//! A request names its network either in the `miden-network` metadata, or as the first segment of
+//! its path, e.g. `/testnet/rpc.Api/SyncState`. Requests naming no network are served by the
+//! default network.
+use std::{
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use miden_crypto::hash::rpo::RpoDigest;
+use miden_node_proto::{
+    block_producer::api_client as block_producer_client,
+    chain_id::{read_chain_id, ChainIdInterceptor, CHAIN_ID_METADATA_KEY},
+    digest::Digest,
+    requests::CheckNullifiersRequest,
+    store::api_client as store_client,
+};
+use miden_node_utils::grpc;
+use tonic::{
+    codegen::http::{self, uri::PathAndQuery, HeaderValue, Uri},
+    metadata::MetadataValue,
+    transport::Error,
+    Request, Response, Status,
+};
+use tower::{Layer, Service};
+use tracing::info;
+
+use super::{
+    api::{BlockProducerClient, StoreClient},
+    nullifier_cache::NullifierCache,
+    tip_cache::{follow_chain_tip, TipCache},
+};
+use crate::{
+    COMPONENT, SERVER_NULLIFIER_CACHE_CAPACITY, SERVER_TIP_CACHE_TTL,
+    SERVER_TIP_SUBSCRIPTION_RETRY_INTERVAL,
+};
+
+/// The gRPC metadata key under which the network of a request is given.
+pub const NETWORK_METADATA_KEY: &str = "miden-network";
+
+// NETWORK
+// ================================================================================================
+
+/// A (store, block producer) pair served by the RPC.
+pub(super) struct Network {
+    /// Name of the network, `None` for the default network.
+    pub(super) name: Option<String>,
+    pub(super) chain_id: u32,
+    /// Hash of the genesis block the store must have, any if `None`.
+    pub(super) genesis_hash: Option<RpoDigest>,
+    pub(super) store: StoreClient,
+    pub(super) block_producer: BlockProducerClient,
+    /// The chain tip, as recently fetched from the store.
+    pub(super) tip: Arc<TipCache>,
+    /// The nullifiers unspent at the chain tip, as recently checked by the store.
+    pub(super) nullifiers: Arc<NullifierCache>,
+    rate_limiter: RateLimiter,
+}
+
+impl Network {
+    pub(super) async fn connect(
+        name: Option<String>,
+        store_url: &str,
+        block_producer_url: &str,
+        chain_id: u32,
+        genesis_hash: Option<RpoDigest>,
+        max_requests_per_second: u32,
+    ) -> Result<Self, Error> {
+        let interceptor = ChainIdInterceptor::new(chain_id);
+        let network = name.as_deref().unwrap_or("default");
+
+        let store_channel = grpc::connect(store_url).await?;
+        let store = store_client::ApiClient::with_interceptor(store_channel, interceptor);
+        info!(target: COMPONENT, network, store_endpoint = store_url, "Store client initialized");
+
+        let block_producer_channel = grpc::connect(block_producer_url).await?;
+        let block_producer =
+            block_producer_client::ApiClient::with_interceptor(block_producer_channel, interceptor);
+        info!(
+            target: COMPONENT,
+            network,
+            block_producer_endpoint = block_producer_url,
+            "Block producer client initialized",
+        );
+
+        let tip = Arc::new(TipCache::new(SERVER_TIP_CACHE_TTL));
+        let nullifiers = Arc::new(NullifierCache::new(SERVER_NULLIFIER_CACHE_CAPACITY));
+        tokio::spawn(follow_chain_tip(
+            store.clone(),
+            tip.clone(),
+            nullifiers.clone(),
+            network.to_string(),
+            SERVER_TIP_SUBSCRIPTION_RETRY_INTERVAL,
+        ));
+
+        Ok(Self {
+            name,
+            chain_id,
+            genesis_hash,
+            store,
+            block_producer,
+            tip,
+            nullifiers,
+            rate_limiter: RateLimiter::new(max_requests_per_second),
+        })
+    }
+
+    /// Returns the name of the network, as displayed in the logs.
+    pub(super) fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("default")
+    }
+
+    /// Verifies the `request` can be served by this network.
+    ///
+    /// Requests for a different chain, and requests over the network's rate limit, are rejected.
+    pub(super) fn admit<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<(), Status> {
+        match read_chain_id(request)? {
+            Some(chain_id) if chain_id != self.chain_id => {
+                return Err(Status::failed_precondition(format!(
+                    "Chain id mismatch, expected {}, got {chain_id}",
+                    self.chain_id
+                )))
+            },
+            _ => (),
+        }
+
+        if !self.rate_limiter.try_acquire() {
+            return Err(Status::resource_exhausted(format!(
+                "Rate limit of network {} exceeded",
+                self.display_name()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Stamps the chain id of this network on the `response`.
+    pub(super) fn with_chain_id<T>(
+        &self,
+        mut response: Response<T>,
+    ) -> Response<T> {
+        response
+            .metadata_mut()
+            .insert(CHAIN_ID_METADATA_KEY, MetadataValue::from(self.chain_id));
+        response
+    }
+
+    /// Returns the block at which each of the `nullifiers` was consumed, zero if not consumed.
+    ///
+    /// The nullifiers found unspent at the current chain tip are served from the cache, only the
+    /// other ones are checked by the store.
+    pub(super) async fn check_nullifiers_without_proofs(
+        &self,
+        nullifiers: &[Digest],
+    ) -> Result<Vec<u32>, Status> {
+        let keys = nullifiers
+            .iter()
+            .map(RpoDigest::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Status::invalid_argument("Digest field is not in the modulos range"))?;
+
+        let (chain_tip, cached) = self.nullifiers.lookup(&keys);
+        let uncached: Vec<usize> = (0..keys.len()).filter(|&index| !cached[index]).collect();
+        let mut block_nums = vec![0; keys.len()];
+        if uncached.is_empty() {
+            return Ok(block_nums);
+        }
+
+        let request = CheckNullifiersRequest {
+            nullifiers: Vec::new(),
+            nullifiers_without_proofs: uncached
+                .iter()
+                .map(|&index| nullifiers[index].clone())
+                .collect(),
+        };
+        let checked = self.store.clone().check_nullifiers(request).await?.into_inner().block_nums;
+        if checked.len() != uncached.len() {
+            return Err(Status::internal(format!(
+                "Store returned {} consumption blocks for {} nullifiers",
+                checked.len(),
+                uncached.len()
+            )));
+        }
+
+        for (&index, &block_num) in uncached.iter().zip(&checked) {
+            block_nums[index] = block_num;
+        }
+        if let Some(chain_tip) = chain_tip {
+            let unspent = uncached
+                .iter()
+                .zip(&checked)
+                .filter(|(_, &block_num)| block_num == 0)
+                .map(|(&index, _)| keys[index]);
+            self.nullifiers.insert_unspent(chain_tip, unspent);
+        }
+
+        Ok(block_nums)
+    }
+}
+
+// RATE LIMITER
+// ================================================================================================
+
+/// Limits the number of requests served per second, over fixed one second windows.
+#[derive(Debug)]
+struct RateLimiter {
+    /// Maximum number of requests per window, `0` disables the limit.
+    max_requests_per_second: u32,
+    /// Start of the current window, and the number of requests served during it.
+    window: Mutex<(Instant, u32)>,
+}
+
+impl RateLimiter {
+    fn new(max_requests_per_second: u32) -> Self {
+        Self {
+            max_requests_per_second,
+            window: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    /// Returns `true` if a request can be served now, counting it against the limit.
+    fn try_acquire(&self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(
+        &self,
+        now: Instant,
+    ) -> bool {
+        if self.max_requests_per_second == 0 {
+            return true;
+        }
+
+        let mut window = self.window.lock().expect("rate limiter lock poisoned");
+        let (start, served) = &mut *window;
+        if now.saturating_duration_since(*start) >= Duration::from_secs(1) {
+            *start = now;
+            *served = 0;
+        }
+
+        if *served < self.max_requests_per_second {
+            *served += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// NETWORK PATH
+// ================================================================================================
+
+/// Splits the network from a `/<network>/<service>/<method>` path.
+///
+/// Returns `None` for paths without a network, i.e. plain gRPC `/<service>/<method>` paths.
+fn split_network_path(path: &str) -> Option<(&str, &str)> {
+    let (network, rest) = path.strip_prefix('/')?.split_once('/')?;
+    if network.is_empty() || !rest.contains('/') {
+        return None;
+    }
+
+    Some((network, &path[network.len() + 1..]))
+}
+
+/// Layer moving the network named in the request path to the request metadata.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NetworkPathLayer;
+
+impl<S> Layer<S> for NetworkPathLayer {
+    type Service = NetworkPath<S>;
+
+    fn layer(
+        &self,
+        inner: S,
+    ) -> Self::Service {
+        NetworkPath { inner }
+    }
+}
+
+/// Rewrites `/<network>/<service>/<method>` requests to `/<service>/<method>`, setting the
+/// `miden-network` metadata to `<network>`.
+#[derive(Debug, Clone)]
+pub struct NetworkPath<S> {
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for NetworkPath<S>
+where
+    S: Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(
+        &mut self,
+        mut request: http::Request<B>,
+    ) -> Self::Future {
+        let rewrite = split_network_path(request.uri().path()).and_then(|(network, path)| {
+            let network = HeaderValue::from_str(network).ok()?;
+            let path = path.parse::<PathAndQuery>().ok()?;
+            let mut uri = request.uri().clone().into_parts();
+            uri.path_and_query = Some(path);
+            Some((network, Uri::from_parts(uri).ok()?))
+        });
+
+        if let Some((network, uri)) = rewrite {
+            *request.uri_mut() = uri;
+            request.headers_mut().insert(NETWORK_METADATA_KEY, network);
+        }
+
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{split_network_path, RateLimiter};
+
+    #[test]
+    fn test_split_network_path() {
+        assert_eq!(
+            split_network_path("/testnet/rpc.Api/SyncState"),
+            Some(("testnet", "/rpc.Api/SyncState"))
+        );
+        assert_eq!(split_network_path("/rpc.Api/SyncState"), None);
+        assert_eq!(split_network_path("//rpc.Api/SyncState"), None);
+        assert_eq!(split_network_path(""), None);
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(2);
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire_at(start));
+        assert!(limiter.try_acquire_at(start + Duration::from_millis(500)));
+        assert!(!limiter.try_acquire_at(start + Duration::from_millis(900)));
+
+        // a new window starts after a second
+        assert!(limiter.try_acquire_at(start + Duration::from_secs(1)));
+
+        // a limit of zero disables rate limiting
+        let unlimited = RateLimiter::new(0);
+        assert!((0..100).all(|_| unlimited.try_acquire_at(start)));
+    }
+}
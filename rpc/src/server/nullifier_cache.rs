@@ -0,0 +1,177 @@
+//! Cache of the nullifiers known to be unspent.
+//!
+//! Wallets poll the store for the nullifiers of their notes, which are mostly unspent. A nullifier
+//! unspent at the chain tip stays unspent until the next block, so the RPC keeps the unspent
+//! nullifiers along with the chain tip they were checked at, and answers the repeated checks
+//! without reaching the store until a new block is applied.
+//!
+//! The chain tip is followed through the store's chain tip subscription, and the cached nullifiers
+//! are dropped as soon as it reports a new block. While the subscription is down, new blocks could
+//! go unnoticed, so no nullifier is cached nor served from the cache.
+use std::{collections::BTreeSet, sync::Mutex};
+
+use miden_crypto::hash::rpo::RpoDigest;
+
+// CHAIN TIP
+// ================================================================================================
+
+/// The chain tip reported by the store's chain tip subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ChainTip {
+    pub(super) block_num: u32,
+    /// Hash of the block, `None` if the store reported a malformed one.
+    pub(super) block_hash: Option<RpoDigest>,
+}
+
+// NULLIFIER CACHE
+// ================================================================================================
+
+#[derive(Debug, Default)]
+struct CachedNullifiers {
+    /// The chain tip the nullifiers are unspent at, `None` while the subscription is down.
+    chain_tip: Option<ChainTip>,
+    unspent: BTreeSet<RpoDigest>,
+}
+
+/// Keeps up to `capacity` nullifiers found unspent at the current chain tip.
+#[derive(Debug)]
+pub(super) struct NullifierCache {
+    capacity: usize,
+    nullifiers: Mutex<CachedNullifiers>,
+}
+
+impl NullifierCache {
+    pub(super) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            nullifiers: Mutex::new(CachedNullifiers::default()),
+        }
+    }
+
+    /// Returns the current chain tip, and whether each of the `nullifiers` is known to be unspent
+    /// at it.
+    ///
+    /// The chain tip is `None`, and no nullifier is known to be unspent, while the subscription to
+    /// the chain tip is down.
+    pub(super) fn lookup(
+        &self,
+        nullifiers: &[RpoDigest],
+    ) -> (Option<ChainTip>, Vec<bool>) {
+        let cached = self.nullifiers.lock().expect("nullifier cache lock poisoned");
+        let unspent =
+            nullifiers.iter().map(|nullifier| cached.unspent.contains(nullifier)).collect();
+
+        (cached.chain_tip, unspent)
+    }
+
+    /// Caches the `nullifiers` found unspent by a check started at the chain tip `chain_tip`, as
+    /// returned by [NullifierCache::lookup].
+    ///
+    /// The nullifiers are dropped if a new block was applied since, as they might have been
+    /// consumed by it, or if the cache is full.
+    pub(super) fn insert_unspent(
+        &self,
+        chain_tip: ChainTip,
+        nullifiers: impl IntoIterator<Item = RpoDigest>,
+    ) {
+        let mut cached = self.nullifiers.lock().expect("nullifier cache lock poisoned");
+        if cached.chain_tip != Some(chain_tip) {
+            return;
+        }
+
+        for nullifier in nullifiers {
+            if cached.unspent.len() >= self.capacity {
+                break;
+            }
+            cached.unspent.insert(nullifier);
+        }
+    }
+
+    /// Sets the chain tip reported by the subscription, `None` once the subscription is down.
+    ///
+    /// The cached nullifiers are dropped if the chain tip changed.
+    pub(super) fn observe(
+        &self,
+        chain_tip: Option<ChainTip>,
+    ) {
+        let mut cached = self.nullifiers.lock().expect("nullifier cache lock poisoned");
+        if cached.chain_tip != chain_tip {
+            cached.chain_tip = chain_tip;
+            cached.unspent.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_crypto::hash::rpo::RpoDigest;
+    use miden_objects::{Felt, FieldElement};
+
+    use super::{ChainTip, NullifierCache};
+
+    fn nullifier(n: u64) -> RpoDigest {
+        RpoDigest::new([Felt::new(n), Felt::ZERO, Felt::ZERO, Felt::ZERO])
+    }
+
+    fn tip(block_num: u32) -> ChainTip {
+        ChainTip {
+            block_num,
+            block_hash: Some(nullifier(1000 + block_num as u64)),
+        }
+    }
+
+    #[test]
+    fn test_nullifier_cache_follows_chain_tip() {
+        let cache = NullifierCache::new(10);
+
+        // nothing is cached until the chain tip is known
+        cache.insert_unspent(tip(1), [nullifier(1)]);
+        assert_eq!(cache.lookup(&[nullifier(1)]), (None, vec![false]));
+
+        cache.observe(Some(tip(1)));
+        cache.insert_unspent(tip(1), [nullifier(1), nullifier(2)]);
+        assert_eq!(
+            cache.lookup(&[nullifier(1), nullifier(3), nullifier(2)]),
+            (Some(tip(1)), vec![true, false, true])
+        );
+
+        // the same tip keeps the cached nullifiers, a new block drops them
+        cache.observe(Some(tip(1)));
+        assert_eq!(cache.lookup(&[nullifier(1)]).1, vec![true]);
+        cache.observe(Some(tip(2)));
+        assert_eq!(cache.lookup(&[nullifier(1)]), (Some(tip(2)), vec![false]));
+
+        // so does a different block of the same number
+        cache.insert_unspent(tip(2), [nullifier(1)]);
+        cache.observe(Some(ChainTip {
+            block_num: 2,
+            block_hash: None,
+        }));
+        assert_eq!(cache.lookup(&[nullifier(1)]).1, vec![false]);
+
+        // and the end of the subscription
+        cache.observe(Some(tip(3)));
+        cache.insert_unspent(tip(3), [nullifier(1)]);
+        cache.observe(None);
+        assert_eq!(cache.lookup(&[nullifier(1)]), (None, vec![false]));
+    }
+
+    #[test]
+    fn test_nullifier_cache_drops_stale_checks() {
+        let cache = NullifierCache::new(2);
+        cache.observe(Some(tip(1)));
+
+        // a check started before the latest block might have missed its nullifiers
+        let (chain_tip, _) = cache.lookup(&[nullifier(1)]);
+        cache.observe(Some(tip(2)));
+        cache.insert_unspent(chain_tip.unwrap(), [nullifier(1)]);
+        assert_eq!(cache.lookup(&[nullifier(1)]).1, vec![false]);
+
+        // the cache is bounded
+        cache.insert_unspent(tip(2), [nullifier(1), nullifier(2), nullifier(3)]);
+        assert_eq!(
+            cache.lookup(&[nullifier(1), nullifier(2), nullifier(3)]).1,
+            vec![true, true, false]
+        );
+    }
+}
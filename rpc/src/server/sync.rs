@@ -0,0 +1,131 @@
+//! Merging of the store's responses into the per block updates returned by `SyncAll`.
+use std::collections::BTreeMap;
+
+use miden_node_proto::{
+    digest::Digest,
+    responses::{BlockSyncUpdate, NullifierUpdate, SyncAllResponse, SyncStateResponse},
+};
+
+/// Merges the `sync` response, and the blocks at which the `nullifiers` were consumed, into the
+/// updates of every block.
+///
+/// `block_nums` holds the consumption block of each nullifier, zero if not consumed, as returned
+/// by `CheckNullifiers`. Nullifiers consumed after the last block of `sync` are left to the next
+/// sync, so that every returned block can be authenticated with the returned MMR delta.
+pub(super) fn merge_sync_updates(
+    sync: SyncStateResponse,
+    nullifiers: Vec<Digest>,
+    block_nums: Vec<u32>,
+) -> SyncAllResponse {
+    let last_block_num =
+        sync.block_header.as_ref().map_or(sync.chain_tip, |header| header.block_num);
+
+    let mut blocks = BTreeMap::new();
+
+    // the store returns the notes of a single block, the last one covered by the response
+    if !sync.notes.is_empty() {
+        block_update(&mut blocks, last_block_num).notes = sync.notes;
+    }
+    for account in sync.accounts {
+        block_update(&mut blocks, account.block_num).accounts.push(account);
+    }
+    for note in sync.consumed_notes {
+        block_update(&mut blocks, note.block_num).consumed_notes.push(note);
+    }
+    for (nullifier, block_num) in nullifiers.into_iter().zip(block_nums) {
+        if block_num != 0 && block_num <= last_block_num {
+            block_update(&mut blocks, block_num).nullifiers.push(NullifierUpdate {
+                nullifier: Some(nullifier),
+                block_num,
+            });
+        }
+    }
+
+    SyncAllResponse {
+        chain_tip: sync.chain_tip,
+        safe_chain_tip: sync.safe_chain_tip,
+        block_header: sync.block_header,
+        mmr_delta: sync.mmr_delta,
+        blocks: blocks.into_values().collect(),
+    }
+}
+
+/// Returns the updates of the block `block_num`, inserting empty ones if missing.
+fn block_update(
+    blocks: &mut BTreeMap<u32, BlockSyncUpdate>,
+    block_num: u32,
+) -> &mut BlockSyncUpdate {
+    blocks.entry(block_num).or_insert_with(|| BlockSyncUpdate {
+        block_num,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_node_proto::{
+        block_header::BlockHeader,
+        digest::Digest,
+        note::NoteSyncRecord,
+        responses::{AccountHashUpdate, ConsumedNoteUpdate, SyncStateResponse},
+    };
+
+    use super::merge_sync_updates;
+
+    fn digest(value: u64) -> Digest {
+        Digest {
+            d0: value,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merge_sync_updates() {
+        let sync = SyncStateResponse {
+            chain_tip: 10,
+            safe_chain_tip: 8,
+            block_header: Some(BlockHeader {
+                block_num: 7,
+                ..Default::default()
+            }),
+            accounts: vec![AccountHashUpdate {
+                account_hash: Some(digest(1)),
+                block_num: 5,
+                ..Default::default()
+            }],
+            notes: vec![NoteSyncRecord {
+                note_hash: Some(digest(2)),
+                ..Default::default()
+            }],
+            consumed_notes: vec![ConsumedNoteUpdate {
+                note_hash: Some(digest(3)),
+                nullifier: Some(digest(4)),
+                block_num: 5,
+            }],
+            ..Default::default()
+        };
+
+        // consumed before the synced range, within it, after it, and not consumed
+        let nullifiers = vec![digest(5), digest(6), digest(7), digest(8)];
+        let response = merge_sync_updates(sync, nullifiers, vec![2, 7, 9, 0]);
+
+        assert_eq!(response.chain_tip, 10);
+        assert_eq!(response.safe_chain_tip, 8);
+        let block_nums: Vec<u32> = response.blocks.iter().map(|block| block.block_num).collect();
+        assert_eq!(block_nums, vec![2, 5, 7]);
+
+        let [block_2, block_5, block_7] = &response.blocks[..] else {
+            unreachable!()
+        };
+        assert_eq!(block_2.nullifiers[0].nullifier, Some(digest(5)));
+        assert!(block_2.notes.is_empty() && block_2.accounts.is_empty());
+
+        assert_eq!(block_5.accounts[0].account_hash, Some(digest(1)));
+        assert_eq!(block_5.consumed_notes[0].note_hash, Some(digest(3)));
+        assert!(block_5.nullifiers.is_empty());
+
+        assert_eq!(block_7.notes[0].note_hash, Some(digest(2)));
+        assert_eq!(block_7.nullifiers.len(), 1);
+        assert_eq!(block_7.nullifiers[0].nullifier, Some(digest(6)));
+    }
+}
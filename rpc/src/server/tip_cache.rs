@@ -0,0 +1,323 @@
+//! Cache of the chain tip of a network.
+//!
+//! Clients poll the latest block header to learn about new blocks, most of these polls return the
+//! header they already have. The RPC keeps the tip for a short time, serving the polls without
+//! reaching the store, and answers conditional polls whose tip didn't change with `not_modified`.
+//!
+//! The cached tip must not lag behind the other responses of the RPC, or a client would be given a
+//! proof against a block newer than the tip it was just told about. The RPC subscribes to the chain
+//! tip of the store, and drops the cached tip as soon as a new block is applied. The tips reported
+//! by the responses of the store are observed as well, covering the time the subscription is down.
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use miden_crypto::hash::rpo::RpoDigest;
+use miden_node_proto::{
+    block_header, errors::ParseError, requests::SubscribeChainTipRequest,
+    responses::GetBlockHeaderByNumberResponse,
+};
+use miden_objects::BlockHeader;
+use tokio::{sync::Mutex, time};
+use tonic::Status;
+use tracing::{info, warn};
+
+use super::{
+    api::StoreClient,
+    nullifier_cache::{ChainTip, NullifierCache},
+};
+use crate::COMPONENT;
+
+// CACHED TIP
+// ================================================================================================
+
+/// The latest block header, as returned by the store.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct CachedTip {
+    pub(super) header: block_header::BlockHeader,
+    pub(super) tx_kernel_versions: Vec<u32>,
+    /// Number of the latest block considered final by the store
+    pub(super) safe_chain_tip: u32,
+    pub(super) hash: RpoDigest,
+    pub(super) signature: Option<block_header::BlockSignature>,
+    pub(super) canonical_tx_ordering: bool,
+    pub(super) timestamp_attestation: Option<block_header::TimestampAttestation>,
+    fetched_at: Instant,
+}
+
+impl CachedTip {
+    fn new(
+        response: GetBlockHeaderByNumberResponse,
+        fetched_at: Instant,
+    ) -> Result<Self, ParseError> {
+        let header = response.block_header.ok_or(ParseError::ProtobufMissingData)?;
+        let hash = BlockHeader::try_from(&header)?.hash();
+        Ok(Self {
+            header,
+            tx_kernel_versions: response.tx_kernel_versions,
+            safe_chain_tip: response.safe_chain_tip,
+            hash,
+            signature: response.signature,
+            canonical_tx_ordering: response.canonical_tx_ordering,
+            timestamp_attestation: response.timestamp_attestation,
+            fetched_at,
+        })
+    }
+
+    /// Returns the response to a request for the latest block header.
+    pub(super) fn into_response(self) -> GetBlockHeaderByNumberResponse {
+        GetBlockHeaderByNumberResponse {
+            chain_tip: self.header.block_num,
+            safe_chain_tip: self.safe_chain_tip,
+            block_header: Some(self.header),
+            chain_length: None,
+            mmr_path: None,
+            not_modified: false,
+            tx_kernel_versions: self.tx_kernel_versions,
+            chain_tip_hash: Some(self.hash.into()),
+            signature: self.signature,
+            canonical_tx_ordering: self.canonical_tx_ordering,
+            timestamp_attestation: self.timestamp_attestation,
+        }
+    }
+}
+
+// TIP CACHE
+// ================================================================================================
+
+/// Keeps the chain tip for up to `ttl` after it was fetched from the store.
+#[derive(Debug)]
+pub(super) struct TipCache {
+    ttl: Duration,
+    tip: Mutex<Option<CachedTip>>,
+}
+
+impl TipCache {
+    pub(super) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            tip: Mutex::new(None),
+        }
+    }
+
+    /// Returns the chain tip, calling `fetch` to retrieve it from the store if the cached one
+    /// expired.
+    ///
+    /// Concurrent callers wait for a single fetch, instead of all reaching the store at once.
+    pub(super) async fn get<F, Fut>(
+        &self,
+        fetch: F,
+    ) -> Result<CachedTip, Status>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<GetBlockHeaderByNumberResponse, Status>>,
+    {
+        self.get_at(Instant::now(), fetch).await
+    }
+
+    async fn get_at<F, Fut>(
+        &self,
+        now: Instant,
+        fetch: F,
+    ) -> Result<CachedTip, Status>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<GetBlockHeaderByNumberResponse, Status>>,
+    {
+        let mut tip = self.tip.lock().await;
+        if let Some(cached) = tip.as_ref() {
+            if now.saturating_duration_since(cached.fetched_at) < self.ttl {
+                return Ok(cached.clone());
+            }
+        }
+
+        let response = fetch().await?;
+        if response.block_header.is_none() {
+            return Err(Status::internal("Store returned no chain tip"));
+        }
+        let fetched = CachedTip::new(response, now).map_err(|err| {
+            Status::internal(format!("Store returned a malformed chain tip: {err}"))
+        })?;
+
+        *tip = Some(fetched.clone());
+        Ok(fetched)
+    }
+
+    /// Drops the cached tip if it is older than the block `block_num`, or if it isn't the block
+    /// `block_hash` of the same number, e.g. after the store was restored from a checkpoint.
+    pub(super) async fn observe(
+        &self,
+        block_num: u32,
+        block_hash: Option<RpoDigest>,
+    ) {
+        let mut tip = self.tip.lock().await;
+        let Some(cached) = tip.as_ref() else {
+            return;
+        };
+
+        let outdated = cached.header.block_num < block_num
+            || block_hash.is_some_and(|block_hash| block_hash != cached.hash);
+        if outdated {
+            *tip = None;
+        }
+    }
+}
+
+// CHAIN TIP SUBSCRIPTION
+// ================================================================================================
+
+/// Drops the tip cached by `cache`, and the nullifiers cached by `nullifiers`, whenever the store
+/// of the network `network` reports a new chain tip.
+///
+/// The subscription is re-established `retry_interval` after it ends, in the meantime the cached
+/// tip still expires after its time to live, and no nullifier is cached.
+pub(super) async fn follow_chain_tip(
+    mut store: StoreClient,
+    cache: Arc<TipCache>,
+    nullifiers: Arc<NullifierCache>,
+    network: String,
+    retry_interval: Duration,
+) {
+    loop {
+        match store.subscribe_chain_tip(SubscribeChainTipRequest {}).await {
+            Ok(response) => {
+                info!(target: COMPONENT, network, "Subscribed to the chain tip");
+                let mut stream = response.into_inner();
+                loop {
+                    match stream.message().await {
+                        Ok(Some(tip)) => {
+                            let block_hash = tip.block_hash.as_ref().map(RpoDigest::try_from);
+                            let block_hash = match block_hash.transpose() {
+                                Ok(block_hash) => block_hash,
+                                Err(err) => {
+                                    warn!(target: COMPONENT, network, %err, "Malformed chain tip");
+                                    None
+                                },
+                            };
+                            cache.observe(tip.block_num, block_hash).await;
+                            nullifiers.observe(Some(ChainTip {
+                                block_num: tip.block_num,
+                                block_hash,
+                            }));
+                        },
+                        Ok(None) => {
+                            warn!(target: COMPONENT, network, "Chain tip subscription ended");
+                            break;
+                        },
+                        Err(err) => {
+                            warn!(target: COMPONENT, network, %err, "Chain tip subscription failed");
+                            break;
+                        },
+                    }
+                }
+            },
+            Err(err) => {
+                warn!(target: COMPONENT, network, %err, "Failed to subscribe to the chain tip");
+            },
+        }
+
+        nullifiers.observe(None);
+        time::sleep(retry_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use miden_node_proto::{block_header, responses::GetBlockHeaderByNumberResponse};
+    use miden_objects::{BlockHeader, Digest, Felt};
+
+    use super::TipCache;
+
+    fn response(block_num: u32) -> GetBlockHeaderByNumberResponse {
+        let header = BlockHeader::new(
+            Digest::default(),
+            block_num,
+            Digest::default(),
+            Digest::default(),
+            Digest::default(),
+            Digest::default(),
+            Digest::default(),
+            Digest::default(),
+            Felt::new(0),
+            Felt::new(0),
+        );
+
+        GetBlockHeaderByNumberResponse {
+            block_header: Some(block_header::BlockHeader::from(header)),
+            chain_length: None,
+            mmr_path: None,
+            not_modified: false,
+            tx_kernel_versions: vec![1],
+            chain_tip: block_num,
+            safe_chain_tip: block_num.saturating_sub(1),
+            chain_tip_hash: None,
+            signature: None,
+            canonical_tx_ordering: true,
+            timestamp_attestation: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tip_cache_expires() {
+        let cache = TipCache::new(Duration::from_secs(1));
+        let start = Instant::now();
+
+        let first = cache.get_at(start, || async { Ok(response(1)) }).await.unwrap();
+        assert_eq!(first.header.block_num, 1);
+        assert_eq!(first.tx_kernel_versions, vec![1]);
+        assert!(first.canonical_tx_ordering);
+        assert_eq!(first.safe_chain_tip, 0);
+        assert_eq!(first.clone().into_response().chain_tip, 1);
+
+        // the cached tip is served until it expires
+        let cached = cache
+            .get_at(start + Duration::from_millis(900), || async { Ok(response(2)) })
+            .await
+            .unwrap();
+        assert_eq!(cached, first);
+
+        let refreshed = cache
+            .get_at(start + Duration::from_secs(1), || async { Ok(response(2)) })
+            .await
+            .unwrap();
+        assert_eq!(refreshed.header.block_num, 2);
+        assert_ne!(refreshed.hash, first.hash);
+    }
+
+    #[tokio::test]
+    async fn test_tip_cache_observe() {
+        let cache = TipCache::new(Duration::from_secs(60));
+        let start = Instant::now();
+
+        let first = cache.get_at(start, || async { Ok(response(2)) }).await.unwrap();
+        assert_eq!(first.clone().into_response().chain_tip_hash, Some(first.hash.into()));
+
+        // older tips, and the same tip, keep the cached one
+        cache.observe(1, None).await;
+        cache.observe(2, Some(first.hash)).await;
+        let cached = cache.get_at(start, || async { Ok(response(3)) }).await.unwrap();
+        assert_eq!(cached, first);
+
+        // a newer tip drops the cached one, before it expires
+        cache.observe(3, None).await;
+        let refreshed = cache.get_at(start, || async { Ok(response(3)) }).await.unwrap();
+        assert_eq!(refreshed.header.block_num, 3);
+
+        // so does a different block of the same number
+        cache.observe(3, Some(first.hash)).await;
+        let refreshed = cache.get_at(start, || async { Ok(response(4)) }).await.unwrap();
+        assert_eq!(refreshed.header.block_num, 4);
+    }
+
+    #[tokio::test]
+    async fn test_tip_cache_rejects_missing_tip() {
+        let cache = TipCache::new(Duration::from_secs(1));
+        let empty = GetBlockHeaderByNumberResponse::default();
+
+        assert!(cache.get_at(Instant::now(), || async { Ok(empty) }).await.is_err());
+    }
+}
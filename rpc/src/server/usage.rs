@@ -0,0 +1,756 @@
+//! Accounting of the usage of the RPC by its clients.
+//!
+//! Operators of public endpoints may bill their clients, or need to know who their traffic comes
+//! from. Every request is accounted to its client, identified by the API key given in the
+//! `x-api-key` metadata if it is one of the configured keys, or by its address otherwise. The
+//! number of requests and the bytes of the responses served to every client are aggregated per UTC
+//! day, the API keys being identified by their SHA-256 so the reports hold no key.
+//!
+//! The usage aggregated since the last flush is added to the report of its day,
+//! `<directory>/usage-<YYYY-MM-DD>.json`, every flush interval. The reports are read and written on
+//! the blocking threads of the runtime, the requests only touch the usage in memory. The requests
+//! are accounted to the day they were received, the bytes to the day their response was completed.
+//! The oldest reports beyond the configured number are removed.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{self, Write},
+    mem,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use miden_node_proto::responses::{self, GetUsageReportResponse};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::time;
+use tonic::{
+    codegen::{http, Body, BoxFuture, Bytes},
+    transport::server::TcpConnectInfo,
+};
+use tower::{Layer, Service};
+use tracing::{info, warn};
+
+use crate::{config::UsageConfig, COMPONENT, SERVER_MAX_USAGE_CLIENTS_PER_DAY};
+
+/// The gRPC metadata key under which the clients give their API key.
+pub const API_KEY_METADATA_KEY: &str = "x-api-key";
+
+/// Maximum length of an API key, longer keys are ignored.
+const MAX_API_KEY_LEN: usize = 256;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// USAGE REPORT
+// ================================================================================================
+
+/// The client a request is accounted to.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Client {
+    /// A client giving one of the configured API keys, by the hex encoded SHA-256 of the key
+    ApiKeySha256(String),
+    Ip(IpAddr),
+    /// A client without an API key, connected through a Unix domain socket
+    Unknown,
+    /// The clients beyond the maximum number accounted for a day
+    Overflow,
+}
+
+/// Usage of the RPC by a client during a day.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct Usage {
+    pub requests: u64,
+    /// Bytes of the response bodies
+    pub bytes_served: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct ClientUsage {
+    pub client: Client,
+    #[serde(flatten)]
+    pub usage: Usage,
+}
+
+/// Usage of the RPC by all its clients during a day, as written to the daily report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct UsageReport {
+    /// The day, as `YYYY-MM-DD`
+    pub day: String,
+    pub clients: Vec<ClientUsage>,
+}
+
+impl UsageReport {
+    fn new(
+        day: u64,
+        clients: &BTreeMap<Client, Usage>,
+    ) -> Self {
+        Self {
+            day: format_day(day),
+            clients: clients
+                .iter()
+                .map(|(client, usage)| ClientUsage {
+                    client: client.clone(),
+                    usage: *usage,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<UsageReport> for GetUsageReportResponse {
+    fn from(report: UsageReport) -> Self {
+        let clients = report
+            .clients
+            .into_iter()
+            .map(|entry| {
+                let mut client = responses::ClientUsage {
+                    requests: entry.usage.requests,
+                    bytes_served: entry.usage.bytes_served,
+                    ..Default::default()
+                };
+                match entry.client {
+                    Client::ApiKeySha256(key_sha256) => client.api_key_sha256 = key_sha256,
+                    Client::Ip(ip) => client.ip = ip.to_string(),
+                    Client::Unknown => (),
+                    Client::Overflow => client.overflow = true,
+                }
+                client
+            })
+            .collect();
+
+        Self {
+            day: report.day,
+            clients,
+        }
+    }
+}
+
+// USAGE METER
+// ================================================================================================
+
+/// Aggregates the usage of the clients, and writes the daily reports.
+pub(super) struct UsageMeter {
+    directory: PathBuf,
+    flush_interval: Duration,
+    /// Number of reports kept, `0` keeps all of them
+    max_days: usize,
+    /// Hex encoded SHA-256 of the API keys the clients are accounted to, in lowercase
+    api_keys_sha256: BTreeSet<String>,
+    trust_forwarded_for: bool,
+    /// Usage of the clients not added to the reports yet, by day since the UNIX epoch
+    days: Mutex<BTreeMap<u64, BTreeMap<Client, Usage>>>,
+    /// Held while the reports are written or read, so that the usage being written isn't missed
+    /// by a report read meanwhile
+    reports: tokio::sync::Mutex<()>,
+}
+
+impl UsageMeter {
+    /// Returns the meter described by `config`, or `None` if usage isn't accounted.
+    pub(super) fn new(config: &UsageConfig) -> Option<Self> {
+        let directory = config.directory.clone()?;
+
+        Some(Self {
+            directory,
+            flush_interval: Duration::from_secs(config.flush_interval_s.max(1)),
+            max_days: config.max_days as usize,
+            api_keys_sha256: config
+                .api_keys_sha256
+                .iter()
+                .map(|key_sha256| key_sha256.to_ascii_lowercase())
+                .collect(),
+            trust_forwarded_for: config.trust_forwarded_for,
+            days: Mutex::new(BTreeMap::new()),
+            reports: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Returns the client which sent `request`.
+    fn client_of<B>(
+        &self,
+        request: &http::Request<B>,
+    ) -> Client {
+        // the keys which are not configured are ignored, anyone could make them up
+        let headers = request.headers();
+        let api_key_sha256 = headers
+            .get(API_KEY_METADATA_KEY)
+            .filter(|key| !key.is_empty() && key.len() <= MAX_API_KEY_LEN)
+            .map(|key| hex::encode(Sha256::digest(key.as_bytes())))
+            .filter(|key_sha256| self.api_keys_sha256.contains(key_sha256));
+        if let Some(api_key_sha256) = api_key_sha256 {
+            return Client::ApiKeySha256(api_key_sha256);
+        }
+
+        // the proxy closest to the client is the first to add its address to the header
+        if self.trust_forwarded_for {
+            let forwarded_for = headers
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(',').next())
+                .and_then(|addr| addr.trim().parse().ok());
+            if let Some(ip) = forwarded_for {
+                return Client::Ip(ip);
+            }
+        }
+
+        request
+            .extensions()
+            .get::<TcpConnectInfo>()
+            .and_then(TcpConnectInfo::remote_addr)
+            .map_or(Client::Unknown, |addr| Client::Ip(addr.ip()))
+    }
+
+    /// Adds `usage` to the usage of `client` today.
+    fn record(
+        &self,
+        client: &Client,
+        usage: Usage,
+    ) {
+        let mut days = self.days.lock().expect("usage meter lock poisoned");
+        add_usage(days.entry(current_day()).or_default(), client, usage);
+    }
+
+    /// Adds the usage aggregated since the last flush to the reports of its days, then removes the
+    /// oldest reports.
+    ///
+    /// The usage whose report couldn't be written is kept, to be added by the next flush.
+    async fn flush(&self) -> io::Result<()> {
+        let _reports = self.reports.lock().await;
+        let mut days = mem::take(&mut *self.days.lock().expect("usage meter lock poisoned"));
+        if days.is_empty() {
+            return Ok(());
+        }
+
+        let directory = self.directory.clone();
+        let max_days = self.max_days;
+        let (days, written) = tokio::task::spawn_blocking(move || {
+            let written = write_reports(&directory, &mut days)
+                .and_then(|()| remove_old_reports(&directory, max_days));
+            (days, written)
+        })
+        .await
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+        let mut unflushed = self.days.lock().expect("usage meter lock poisoned");
+        for (day, clients) in days {
+            let total = unflushed.entry(day).or_default();
+            for (client, usage) in clients {
+                add_usage(total, &client, usage);
+            }
+        }
+
+        written
+    }
+
+    /// Returns the usage of `day`, as saved to its report along with the usage not written yet.
+    pub(super) async fn report(
+        &self,
+        day: u64,
+    ) -> io::Result<UsageReport> {
+        let _reports = self.reports.lock().await;
+        let path = report_path(&self.directory, day);
+        let mut clients = tokio::task::spawn_blocking(move || load_report(&path))
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+        let days = self.days.lock().expect("usage meter lock poisoned");
+        for (client, usage) in days.get(&day).into_iter().flatten() {
+            add_usage(&mut clients, client, *usage);
+        }
+
+        Ok(UsageReport::new(day, &clients))
+    }
+}
+
+/// Writes the usage reports of `meter` every flush interval, failures are logged.
+pub(super) async fn flush_usage(meter: Arc<UsageMeter>) {
+    let mut interval = time::interval(meter.flush_interval);
+    interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+
+    info!(target: COMPONENT, directory = ?meter.directory, "Usage accounting enabled");
+
+    loop {
+        interval.tick().await;
+        if let Err(err) = meter.flush().await {
+            warn!(target: COMPONENT, directory = ?meter.directory, %err, "Failed to write usage reports");
+        }
+    }
+}
+
+// USAGE LAYER
+// ================================================================================================
+
+/// Layer accounting the requests, and the bytes of their responses, to their clients.
+#[derive(Clone)]
+pub struct UsageLayer {
+    meter: Option<Arc<UsageMeter>>,
+}
+
+impl UsageLayer {
+    /// Returns the layer accounting the usage to `meter`, `None` disables the accounting.
+    pub(super) fn new(meter: Option<Arc<UsageMeter>>) -> Self {
+        Self { meter }
+    }
+}
+
+impl<S> Layer<S> for UsageLayer {
+    type Service = UsageService<S>;
+
+    fn layer(
+        &self,
+        inner: S,
+    ) -> Self::Service {
+        UsageService {
+            inner,
+            meter: self.meter.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct UsageService<S> {
+    inner: S,
+    meter: Option<Arc<UsageMeter>>,
+}
+
+impl<S, B, ResBody> Service<http::Request<B>> for UsageService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ResBody: Body<Data = Bytes> + Unpin + Send + 'static,
+{
+    type Response = http::Response<MeteredBody<ResBody>>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(
+        &mut self,
+        request: http::Request<B>,
+    ) -> Self::Future {
+        let meter = self.meter.clone().map(|meter| {
+            let client = meter.client_of(&request);
+            meter.record(
+                &client,
+                Usage {
+                    requests: 1,
+                    bytes_served: 0,
+                },
+            );
+            (meter, client)
+        });
+
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            Ok(response.map(|inner| MeteredBody {
+                inner,
+                meter,
+                bytes_served: 0,
+            }))
+        })
+    }
+}
+
+/// Response body counting the bytes served, accounted to the client once the body is dropped.
+pub struct MeteredBody<B> {
+    inner: B,
+    meter: Option<(Arc<UsageMeter>, Client)>,
+    bytes_served: u64,
+}
+
+impl<B: Body<Data = Bytes> + Unpin> Body for MeteredBody<B> {
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_data(cx);
+        if let Poll::Ready(Some(Ok(data))) = &poll {
+            this.bytes_served += data.len() as u64;
+        }
+        poll
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_trailers(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+}
+
+impl<B> Drop for MeteredBody<B> {
+    fn drop(&mut self) {
+        if let Some((meter, client)) = self.meter.take() {
+            if self.bytes_served > 0 {
+                meter.record(
+                    &client,
+                    Usage {
+                        requests: 0,
+                        bytes_served: self.bytes_served,
+                    },
+                );
+            }
+        }
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Adds `usage` to the usage of `client` among `clients`, accounting it to [Client::Overflow] if
+/// `clients` already holds the maximum number of clients.
+fn add_usage(
+    clients: &mut BTreeMap<Client, Usage>,
+    client: &Client,
+    usage: Usage,
+) {
+    let client = if clients.contains_key(client) || clients.len() < SERVER_MAX_USAGE_CLIENTS_PER_DAY
+    {
+        client.clone()
+    } else {
+        Client::Overflow
+    };
+    let total = clients.entry(client).or_default();
+    total.requests += usage.requests;
+    total.bytes_served += usage.bytes_served;
+}
+
+/// Adds the usage of `days` to the reports in `directory`, removing every day from `days` once its
+/// report is written.
+fn write_reports(
+    directory: &Path,
+    days: &mut BTreeMap<u64, BTreeMap<Client, Usage>>,
+) -> io::Result<()> {
+    fs::create_dir_all(directory)?;
+
+    while let Some(entry) = days.first_entry() {
+        let day = *entry.key();
+        let path = report_path(directory, day);
+        let mut clients = load_report(&path);
+        for (client, usage) in entry.get() {
+            add_usage(&mut clients, client, *usage);
+        }
+        write_report(&path, &UsageReport::new(day, &clients))?;
+        entry.remove();
+    }
+
+    Ok(())
+}
+
+/// Returns the usage saved to the report at `path`, none if there is no report.
+fn load_report(path: &Path) -> BTreeMap<Client, Usage> {
+    let report = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return BTreeMap::new(),
+        Err(err) => {
+            warn!(target: COMPONENT, ?path, %err, "Failed to read usage report");
+            return BTreeMap::new();
+        },
+    };
+
+    match serde_json::from_slice::<UsageReport>(&report) {
+        Ok(report) => report.clients.into_iter().map(|entry| (entry.client, entry.usage)).collect(),
+        Err(err) => {
+            warn!(target: COMPONENT, ?path, %err, "Invalid usage report replaced");
+            BTreeMap::new()
+        },
+    }
+}
+
+/// Removes the oldest reports of `directory` beyond `max_days`, `0` keeping all of them.
+fn remove_old_reports(
+    directory: &Path,
+    max_days: usize,
+) -> io::Result<()> {
+    if max_days == 0 {
+        return Ok(());
+    }
+
+    // the dates sort the reports by age
+    let mut reports = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if name.starts_with("usage-") && name.ends_with(".json") {
+            reports.push(name);
+        }
+    }
+    reports.sort();
+
+    let num_removed = reports.len().saturating_sub(max_days);
+    for name in &reports[..num_removed] {
+        fs::remove_file(directory.join(name))?;
+    }
+
+    Ok(())
+}
+
+fn report_path(
+    directory: &Path,
+    day: u64,
+) -> PathBuf {
+    directory.join(format!("usage-{}.json", format_day(day)))
+}
+
+/// Returns the current UTC day, in days since the UNIX epoch.
+pub(super) fn current_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or_default()
+}
+
+/// Formats `day`, in days since the UNIX epoch, as `YYYY-MM-DD`.
+///
+/// See the `civil_from_days` algorithm of <http://howardhinnant.github.io/date_algorithms.html>.
+fn format_day(day: u64) -> String {
+    let days = day as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day_of_month:02}")
+}
+
+/// Parses `day`, formatted as `YYYY-MM-DD`, into days since the UNIX epoch, `None` if it isn't a
+/// valid day since the UNIX epoch.
+///
+/// See the `days_from_civil` algorithm of <http://howardhinnant.github.io/date_algorithms.html>.
+pub(super) fn parse_day(day: &str) -> Option<u64> {
+    let mut parts = day.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day_of_month: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day_of_month) {
+        return None;
+    }
+
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = (month + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day_of_month - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = u64::try_from(era * 146_097 + day_of_era - 719_468).ok()?;
+
+    // the days which don't exist, e.g. `2026-02-30`, are formatted as another day
+    (format_day(days) == day).then_some(days)
+}
+
+/// Replaces the report at `path` with `report`, written to `<path>.tmp` first so a crash leaves
+/// either report in place.
+fn write_report(
+    path: &Path,
+    report: &UsageReport,
+) -> io::Result<()> {
+    let bytes = serde_json::to_vec_pretty(report)?;
+    let tmp_path = path.with_extension("json.tmp");
+
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(&bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, net::SocketAddr};
+
+    use figment::Jail;
+    use sha2::{Digest, Sha256};
+    use tonic::{codegen::http, transport::server::TcpConnectInfo};
+
+    use super::{
+        current_day, format_day, parse_day, Client, Usage, UsageMeter, UsageReport,
+        API_KEY_METADATA_KEY,
+    };
+    use crate::config::UsageConfig;
+
+    fn meter(config: UsageConfig) -> UsageMeter {
+        UsageMeter::new(&UsageConfig {
+            directory: Some("usage".into()),
+            api_keys_sha256: vec![key_sha256("customer-1").to_ascii_uppercase()],
+            ..config
+        })
+        .unwrap()
+    }
+
+    fn key_sha256(api_key: &str) -> String {
+        hex::encode(Sha256::digest(api_key.as_bytes()))
+    }
+
+    fn request(
+        remote_addr: Option<&str>,
+        headers: &[(&'static str, &str)],
+    ) -> http::Request<()> {
+        let mut request = http::Request::new(());
+        for (key, value) in headers {
+            request.headers_mut().insert(*key, value.parse().unwrap());
+        }
+        request.extensions_mut().insert(TcpConnectInfo {
+            local_addr: None,
+            remote_addr: remote_addr.map(|addr| addr.parse::<SocketAddr>().unwrap()),
+        });
+        request
+    }
+
+    #[test]
+    fn test_format_day() {
+        assert_eq!(format_day(0), "1970-01-01");
+        assert_eq!(format_day(11_016), "2000-02-29");
+        assert_eq!(format_day(20_742), "2026-10-16");
+
+        for day in [0, 11_016, 20_742, current_day()] {
+            assert_eq!(parse_day(&format_day(day)), Some(day));
+        }
+        for day in ["1969-12-31", "2026-02-29", "2026-13-01", "2026-1-01", "2026-01", "today"] {
+            assert_eq!(parse_day(day), None);
+        }
+    }
+
+    #[test]
+    fn test_usage_client() {
+        let meter = meter(UsageConfig::default());
+        let peer = Some("10.0.0.1:4000");
+
+        let client = meter.client_of(&request(peer, &[(API_KEY_METADATA_KEY, "customer-1")]));
+        assert_eq!(client, Client::ApiKeySha256(key_sha256("customer-1")));
+
+        // the keys which are not configured are accounted to the address of the client
+        let client = meter.client_of(&request(peer, &[(API_KEY_METADATA_KEY, "customer-2")]));
+        assert_eq!(client, Client::Ip("10.0.0.1".parse().unwrap()));
+
+        // the forwarded addresses are set by the clients unless they're behind a trusted proxy
+        let forwarded = request(peer, &[("x-forwarded-for", "192.168.1.7, 10.0.0.2")]);
+        assert_eq!(meter.client_of(&forwarded), Client::Ip("10.0.0.1".parse().unwrap()));
+        assert_eq!(meter.client_of(&request(None, &[])), Client::Unknown);
+
+        let meter = self::meter(UsageConfig {
+            trust_forwarded_for: true,
+            ..Default::default()
+        });
+        assert_eq!(meter.client_of(&forwarded), Client::Ip("192.168.1.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_usage_reports() {
+        Jail::expect_with(|_jail| {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async {
+                assert!(UsageMeter::new(&UsageConfig::default()).is_none());
+
+                let meter = meter(UsageConfig {
+                    max_days: 2,
+                    ..Default::default()
+                });
+                let client = Client::ApiKeySha256(key_sha256("customer-1"));
+                meter.record(
+                    &client,
+                    Usage {
+                        requests: 1,
+                        bytes_served: 0,
+                    },
+                );
+                meter.record(
+                    &client,
+                    Usage {
+                        requests: 0,
+                        bytes_served: 100,
+                    },
+                );
+                meter.record(
+                    &Client::Unknown,
+                    Usage {
+                        requests: 1,
+                        bytes_served: 10,
+                    },
+                );
+
+                // older reports beyond the two latest days are removed
+                fs::create_dir_all("usage").unwrap();
+                for day in ["1970-01-01", "1970-01-02"] {
+                    fs::write(format!("usage/usage-{day}.json"), "{}").unwrap();
+                }
+                meter.flush().await.unwrap();
+
+                let today = format_day(current_day());
+                let mut reports: Vec<String> = fs::read_dir("usage")
+                    .unwrap()
+                    .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                    .collect();
+                reports.sort();
+                assert_eq!(
+                    reports,
+                    ["usage-1970-01-02.json".to_string(), format!("usage-{today}.json")]
+                );
+
+                let report = fs::read(format!("usage/usage-{today}.json")).unwrap();
+                let report: UsageReport = serde_json::from_slice(&report).unwrap();
+                assert_eq!(report.day, today);
+                assert_eq!(report.clients.len(), 2);
+                assert_eq!(report.clients[0].client, client);
+                assert_eq!(
+                    report.clients[0].usage,
+                    Usage {
+                        requests: 1,
+                        bytes_served: 100
+                    }
+                );
+
+                // a restarted RPC adds to the usage of the day, the usage not written yet being
+                // reported along with the written one
+                let restarted = self::meter(UsageConfig::default());
+                restarted.record(
+                    &client,
+                    Usage {
+                        requests: 1,
+                        bytes_served: 50,
+                    },
+                );
+                let expected = Usage {
+                    requests: 2,
+                    bytes_served: 150,
+                };
+                let report = restarted.report(current_day()).await.unwrap();
+                assert_eq!(report.clients[0].usage, expected);
+
+                restarted.flush().await.unwrap();
+                let report = fs::read(format!("usage/usage-{today}.json")).unwrap();
+                let report: UsageReport = serde_json::from_slice(&report).unwrap();
+                assert_eq!(report.clients[0].usage, expected);
+                assert_eq!(restarted.report(current_day()).await.unwrap(), report);
+            });
+
+            Ok(())
+        });
+    }
+}
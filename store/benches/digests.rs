@@ -0,0 +1,39 @@
+//! Encoding and decoding of the digests stored in the database, natively and protobuf encoded.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use miden_crypto::{hash::rpo::RpoDigest, Felt};
+use miden_node_proto::digest::Digest;
+use miden_node_store::db::{decode_digest, encode_digest};
+use prost::Message;
+
+fn digest() -> RpoDigest {
+    RpoDigest::new([
+        Felt::new(0x1234_5678_9abc),
+        Felt::new(0xdead_beef),
+        Felt::new(u32::MAX as u64),
+        Felt::new(0x0fed_cba9_8765_4321),
+    ])
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let digest = digest();
+
+    let mut group = c.benchmark_group("encode_digest");
+    group.bench_function("native", |b| b.iter(|| encode_digest(black_box(&digest))));
+    group.bench_function("protobuf", |b| {
+        b.iter(|| Digest::from(black_box(&digest)).encode_to_vec())
+    });
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let native = encode_digest(&digest());
+    let protobuf = Digest::from(digest()).encode_to_vec();
+
+    let mut group = c.benchmark_group("decode_digest");
+    group.bench_function("native", |b| b.iter(|| decode_digest(black_box(&native)).unwrap()));
+    group.bench_function("protobuf", |b| b.iter(|| decode_digest(black_box(&protobuf)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode, bench_decode);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+//! Insertion of the nullifiers of a block into the nullifier tree.
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use miden_crypto::{hash::rpo::RpoDigest, merkle::TieredSmt, Felt, FieldElement};
+use miden_node_store::state::NullifierMutations;
+
+/// Returns `count` pseudo-random nullifiers, spread over the leaves of the tree.
+fn nullifiers(
+    seed: u64,
+    count: u64,
+) -> Vec<RpoDigest> {
+    (0..count)
+        .map(|index| {
+            let value = (seed + index).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 1;
+            RpoDigest::new([Felt::new(index), Felt::ZERO, Felt::ZERO, Felt::new(value)])
+        })
+        .collect()
+}
+
+/// Inserts the `nullifiers` produced by the block `block_num` into `tree`.
+fn insert_nullifiers(
+    tree: &mut TieredSmt,
+    nullifiers: &[RpoDigest],
+    block_num: u32,
+) {
+    NullifierMutations::compute(tree, nullifiers, block_num)
+        .expect("the nullifiers are unique")
+        .apply(tree);
+}
+
+fn bench_insert_nullifiers(c: &mut Criterion) {
+    let mut tree = TieredSmt::default();
+    insert_nullifiers(&mut tree, &nullifiers(0, 10_000), 1);
+
+    let mut group = c.benchmark_group("insert_nullifiers");
+    for count in [10, 100, 500] {
+        let block = nullifiers(1_000_000, count);
+
+        group.bench_with_input(BenchmarkId::new("sorted", count), &block, |b, block| {
+            b.iter_batched(
+                || tree.clone(),
+                |mut tree| insert_nullifiers(&mut tree, black_box(block), 2),
+                BatchSize::LargeInput,
+            )
+        });
+
+        group.bench_with_input(BenchmarkId::new("one_by_one", count), &block, |b, block| {
+            let value = [Felt::new(2), Felt::ZERO, Felt::ZERO, Felt::ZERO];
+            b.iter_batched(
+                || tree.clone(),
+                |mut tree| {
+                    for nullifier in black_box(block) {
+                        tree.insert(*nullifier, value);
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_insert_nullifiers);
+criterion_main!(benches);
@@ -0,0 +1,97 @@
+//! Online backfills of the tables introduced by migrations.
+//!
+//! A migration introducing a table derived from existing data, e.g. an index, doesn't populate it
+//! itself, which could block the startup of the store for hours on a large database. It registers
+//! a backfill instead, which populates the table in small batches in the background while the store
+//! keeps serving requests. Until a backfill completes, the readers of its table fall back to the
+//! data it is derived from.
+//!
+//! The progress of every backfill is stored along with the data it writes, so an interrupted
+//! backfill resumes where it stopped, and is reported by the `GetBackfillStatus` endpoint.
+use std::{sync::Arc, time::Duration};
+
+use tokio::time;
+use tracing::{info, warn};
+
+use crate::{
+    state::State, types::BlockNumber, BACKFILL_BATCH_SIZE, BACKFILL_RETRY_INTERVAL, COMPONENT,
+};
+
+/// Builds the note trees of the blocks applied before the note trees were stored.
+pub const NOTE_TREES_BACKFILL: &str = "note_trees";
+
+/// Rewrites the digests stored protobuf encoded before the store adopted miden's native
+/// serialization.
+pub const NATIVE_DIGESTS_BACKFILL: &str = "native_digests";
+
+// BACKFILL PROGRESS
+// ================================================================================================
+
+/// Progress of a backfill, as stored in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillProgress {
+    /// Name of the backfill, as registered by its migration.
+    pub name: String,
+
+    /// Number of the first block which wasn't processed yet.
+    pub next_block_num: BlockNumber,
+
+    /// Whether every block applied before the migration was processed.
+    pub completed: bool,
+}
+
+// BACKFILL TASK
+// ================================================================================================
+
+/// Runs the backfills which are not completed yet, one after the other.
+///
+/// Waits `batch_pause` between two batches, failed batches are retried after
+/// [BACKFILL_RETRY_INTERVAL]. Returns once every known backfill completed.
+pub async fn run(
+    state: Arc<State>,
+    batch_pause: Duration,
+) {
+    let backfills = loop {
+        match state.backfills().await {
+            Ok(backfills) => break backfills,
+            Err(err) => {
+                warn!(target: COMPONENT, %err, "Failed to load the backfills");
+                time::sleep(BACKFILL_RETRY_INTERVAL).await;
+            },
+        }
+    };
+
+    for mut progress in backfills.into_iter().filter(|progress| !progress.completed) {
+        info!(target: COMPONENT, name = progress.name, from = progress.next_block_num, "Backfill started");
+
+        while !progress.completed {
+            let result = match progress.name.as_str() {
+                NOTE_TREES_BACKFILL => {
+                    state.backfill_note_trees(&progress, BACKFILL_BATCH_SIZE).await
+                },
+                NATIVE_DIGESTS_BACKFILL => {
+                    state.backfill_native_digests(&progress, BACKFILL_BATCH_SIZE).await
+                },
+                name => {
+                    warn!(target: COMPONENT, name, "Unknown backfill, skipping it");
+                    break;
+                },
+            };
+
+            match result {
+                Ok(next) => {
+                    progress = next;
+                    time::sleep(batch_pause).await;
+                },
+                Err(err) => {
+                    warn!(target: COMPONENT, name = progress.name, %err, "Backfill batch failed");
+                    time::sleep(BACKFILL_RETRY_INTERVAL).await;
+                },
+            }
+        }
+
+        if progress.completed {
+            info!(target: COMPONENT, name = progress.name, "Backfill completed");
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! The batch kernel, proving the batches of transactions of the blocks.
+//!
+//! The block producer proves a batch by executing the batch kernel over the ids of its
+//! transactions and the root of its created notes, which outputs the commitment of the batch. A
+//! block commits to the proofs of its batches in its header: the batch root is the hash of the
+//! commitments and proof hashes of the batches, and the proof hash is the hash of the proof hashes,
+//! see [batch_root] and [proof_hash].
+//!
+//! The proofs of the batches are sent to the store along with the block, which verifies them and
+//! rejects the block if they don't match its header, see [BatchKernelVerifier]. The store then
+//! checks the proofs cover the contents of the block: every batch of the block must be proven, and
+//! the notes created by a batch must be the notes of the block in the subtree of the batch, see
+//! [verify_batch_contents]. The proofs are only verified if enabled in the configuration of the
+//! store, which requires the block producer to prove its batches.
+//!
+//! Note: A batch proof only attests the commitment of the batch to its transaction ids and created
+//! notes. It is not a recursive proof, the proofs of the transactions are not verified by the
+//! batch kernel. Until recursive proofs are available in the Miden VM, the transaction proofs are
+//! only verified by the block producer when the transactions are added to its queue.
+use miden_crypto::{
+    merkle::{NodeIndex, SimpleSmt},
+    ZERO,
+};
+use miden_objects::{assembly::Assembler, notes::NOTE_LEAF_DEPTH, Digest, Felt, Hasher, Word};
+use miden_vm::{verify, ExecutionProof, Program, ProgramInfo, StackInputs, StackOutputs};
+
+use crate::{
+    errors::{ApplyBlockError, BatchProofError},
+    note_tree::BATCH_TREE_DEPTH,
+};
+
+/// Stack inputs: [num_txs, NOTES_ROOT]
+/// Advice stack: [TX_ID_0, ..., TX_ID_{n-1}]
+///
+/// Output: [BATCH_COMMITMENT]
+pub const BATCH_KERNEL_MASM: &str = "
+begin
+    dup neq.0
+    # => [0 or 1, num_txs, NOTES_ROOT]
+
+    while.true
+        # => [counter, COMMITMENT_i]
+
+        # Move counter down for next iteration
+        movdn.4
+        # => [COMMITMENT_i, counter]
+
+        padw adv_loadw hmerge
+        # => [COMMITMENT_{i+1}, counter]
+
+        # loop counter
+        movup.4 sub.1 dup neq.0
+        # => [0 or 1, counter-1, COMMITMENT_{i+1}]
+    end
+
+    drop
+    # => [BATCH_COMMITMENT]
+end
+";
+
+/// Number of elements of the output stack of the batch kernel.
+const KERNEL_STACK_DEPTH: usize = 16;
+
+/// Returns the batch kernel, compiled from [BATCH_KERNEL_MASM].
+pub fn batch_kernel() -> Program {
+    Assembler::default()
+        .compile(BATCH_KERNEL_MASM)
+        .expect("failed to compile the batch kernel")
+}
+
+/// Returns the stack inputs of the batch kernel proving a batch of `num_txs` transactions, whose
+/// created notes tree is at `created_notes_root`.
+pub fn kernel_stack_inputs(
+    num_txs: usize,
+    created_notes_root: Digest,
+) -> StackInputs {
+    // Note: `StackInputs::new()` reverses the input vector, so we need to construct the stack
+    // from the bottom to the top
+    let mut stack_inputs = Word::from(created_notes_root).to_vec();
+    stack_inputs.push(Felt::from(num_txs as u64));
+
+    StackInputs::new(stack_inputs)
+}
+
+/// Returns the stack outputs of the batch kernel which output `commitment`.
+fn kernel_stack_outputs(commitment: Digest) -> StackOutputs {
+    // Note: the words of the output stack are read in reverse, see `StackOutputs::get_stack_word()`
+    let mut stack: Vec<Felt> = Word::from(commitment).into_iter().rev().collect();
+    stack.resize(KERNEL_STACK_DEPTH, ZERO);
+
+    StackOutputs::new(stack, Vec::new()).expect("the batch commitment fits the output stack")
+}
+
+// BLOCK COMMITMENTS
+// ================================================================================================
+
+/// Returns the hash of the serialized proof of a batch.
+pub fn batch_proof_hash(proof: &[u8]) -> Digest {
+    Hasher::hash(proof)
+}
+
+/// Returns the batch root of a block, committing to the `(commitment, proof_hash)` of its proven
+/// batches, in the order they are applied.
+///
+/// The root is the default digest if none of the batches is proven.
+pub fn batch_root(batches: impl IntoIterator<Item = (Digest, Digest)>) -> Digest {
+    let elements: Vec<Felt> = batches
+        .into_iter()
+        .flat_map(|(commitment, proof_hash)| [Word::from(commitment), Word::from(proof_hash)])
+        .flatten()
+        .collect();
+
+    if elements.is_empty() {
+        Digest::default()
+    } else {
+        Hasher::hash_elements(&elements)
+    }
+}
+
+/// Returns the proof hash of a block, the hash of the proof hashes of its proven batches, in the
+/// order they are applied.
+///
+/// The hash is the default digest if none of the batches is proven.
+pub fn proof_hash(proof_hashes: impl IntoIterator<Item = Digest>) -> Digest {
+    let elements: Vec<Felt> = proof_hashes.into_iter().flat_map(Word::from).collect();
+
+    if elements.is_empty() {
+        Digest::default()
+    } else {
+        Hasher::hash_elements(&elements)
+    }
+}
+
+// BATCH PROOF
+// ================================================================================================
+
+/// Proof of a batch of a block, sent to the store along with the block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchProof {
+    /// Commitment to the transactions and created notes of the batch, output by the batch kernel
+    pub commitment: Digest,
+
+    /// Number of transactions of the batch
+    pub num_txs: usize,
+
+    /// Root of the notes created by the batch
+    pub created_notes_root: Digest,
+
+    /// Serialized proof of the batch kernel execution
+    pub proof: Vec<u8>,
+
+    /// Index of the batch in the block, see [verify_batch_contents]
+    pub batch_index: u32,
+}
+
+impl BatchProof {
+    pub fn proof_hash(&self) -> Digest {
+        batch_proof_hash(&self.proof)
+    }
+}
+
+// BATCH KERNEL VERIFIER
+// ================================================================================================
+
+/// Verifies the proofs of the batches of the new blocks, see the [module](self) documentation.
+#[derive(Debug, Clone)]
+pub struct BatchKernelVerifier {
+    kernel: ProgramInfo,
+}
+
+impl BatchKernelVerifier {
+    pub fn new() -> Self {
+        Self {
+            kernel: batch_kernel().into(),
+        }
+    }
+
+    /// Verifies the proof of a batch, attesting the execution of the batch kernel which output its
+    /// commitment.
+    pub fn verify(
+        &self,
+        batch: &BatchProof,
+    ) -> Result<(), BatchProofError> {
+        let proof =
+            ExecutionProof::from_bytes(&batch.proof).map_err(BatchProofError::MalformedProof)?;
+
+        verify(
+            self.kernel.clone(),
+            kernel_stack_inputs(batch.num_txs, batch.created_notes_root),
+            kernel_stack_outputs(batch.commitment),
+            proof,
+        )
+        .map_err(|err| BatchProofError::VerificationFailed(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Verifies the proofs of the `batches` of a block, in the order they are applied, and that
+    /// the block commits to them in its `batch_root` and `proof_hash`.
+    pub fn verify_block(
+        &self,
+        batch_root: Digest,
+        proof_hash: Digest,
+        batches: &[BatchProof],
+    ) -> Result<(), ApplyBlockError> {
+        let proven_batches = batches.iter().map(|batch| (batch.commitment, batch.proof_hash()));
+        if self::batch_root(proven_batches) != batch_root {
+            return Err(ApplyBlockError::NewBlockInvalidBatchRoot);
+        }
+        if self::proof_hash(batches.iter().map(BatchProof::proof_hash)) != proof_hash {
+            return Err(ApplyBlockError::NewBlockInvalidProofHash);
+        }
+
+        for batch in batches {
+            self.verify(batch).map_err(|error| ApplyBlockError::InvalidBatchProof {
+                batch_index: batch.batch_index,
+                error,
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for BatchKernelVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// BATCH CONTENTS
+// ================================================================================================
+
+/// Checks the proofs of the `batches` of a block cover the contents of the block, whose notes are
+/// in `note_tree`, and which updates `num_accounts` accounts.
+///
+/// Every batch of the block must be proven: the proofs are of the batches `0..n`, in the order
+/// they are applied, and the block has no note beyond the subtree of its last batch, at depth
+/// [BATCH_TREE_DEPTH]. Each batch holds at least one transaction, the notes it created are the
+/// notes of the block in its subtree, and every account update comes from a proven transaction.
+pub fn verify_batch_contents(
+    note_tree: &SimpleSmt<NOTE_LEAF_DEPTH>,
+    num_accounts: usize,
+    batches: &[BatchProof],
+) -> Result<(), ApplyBlockError> {
+    for (position, batch) in batches.iter().enumerate() {
+        let batch_index = batch.batch_index;
+        if batch_index as usize != position {
+            return Err(ApplyBlockError::BatchProofOutOfOrder { batch_index });
+        }
+
+        if batch.num_txs == 0 {
+            return Err(ApplyBlockError::EmptyProvenBatch { batch_index });
+        }
+
+        let created_notes_root = NodeIndex::new(BATCH_TREE_DEPTH, batch_index.into())
+            .and_then(|index| note_tree.get_node(index))
+            .map_err(|_| ApplyBlockError::BatchProofOutOfOrder { batch_index })?;
+        if created_notes_root != batch.created_notes_root {
+            return Err(ApplyBlockError::BatchProofNotesMismatch { batch_index });
+        }
+    }
+
+    let num_batches = batches.len() as u64;
+    let batch_leaf_depth = NOTE_LEAF_DEPTH - BATCH_TREE_DEPTH;
+    if let Some((leaf_index, _)) = note_tree
+        .leaves()
+        .find(|(leaf_index, _)| leaf_index >> batch_leaf_depth >= num_batches)
+    {
+        let batch_index = (leaf_index >> batch_leaf_depth) as u32;
+        return Err(ApplyBlockError::UnprovenBatch { batch_index });
+    }
+
+    let num_txs = batches.iter().map(|batch| batch.num_txs).sum();
+    if num_accounts > num_txs {
+        return Err(ApplyBlockError::UnprovenTransactions {
+            num_accounts,
+            num_txs,
+        });
+    }
+
+    Ok(())
+}
@@ -13,6 +13,10 @@ pub struct Cli {
     #[arg(short, long, value_name = "FILE", default_value = config::CONFIG_FILENAME)]
     pub config: PathBuf,
 
+    /// Overrides the data directory set in the config file.
+    #[arg(long, value_name = "DIR")]
+    pub data_directory: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Command,
 }
@@ -25,6 +29,14 @@ pub enum Command {
     #[command(subcommand)]
     /// Queries the Store via gRPC.
     Query(Query),
+
+    /// Exports the notes matching some tags over a range of blocks, with their inclusion proofs,
+    /// to a file.
+    ExportNotes(ExportNotesArgs),
+
+    /// Runs the backfills which are not completed yet without pausing between batches, the Store
+    /// must be stopped.
+    Backfill,
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Subcommand)]
@@ -38,12 +50,18 @@ pub enum Query {
     /// Query state update of a client.
     SyncState(SyncStateArgs),
 
+    /// Query the inclusion path of a note.
+    GetNoteAuthenticationPath(GetNoteAuthenticationPathArgs),
+
     /// Query inputs to create a block.
     GetBlockInputs(GetBlockInputsArgs),
 
     /// Query inputs to create a transaction.
     GetTransactionInputs(GetTransactionInputsArgs),
 
+    /// Query the protocol version and genesis block hash.
+    GetNodeInfo,
+
     /// Query all known nullifiers.
     ListNullifiers,
 
@@ -58,13 +76,21 @@ pub enum Query {
 pub struct GetBlockHeaderByNumberArgs {
     /// Optional block height, if unspecified return latest.
     pub block_num: Option<u32>,
+
+    /// Include the block header's authentication path in the chain MMR of the latest block.
+    #[arg(long)]
+    pub include_mmr_proof: bool,
 }
 
 #[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct CheckNullifiersArgs {
-    /// List of nullifiers to query.
+    /// List of nullifiers to query, with their proofs.
     #[arg(value_parser=parse_nullifier)]
     pub nullifiers: Vec<Digest>,
+
+    /// List of nullifiers to query, only the block at which they were consumed is returned.
+    #[arg(long="without-proof", value_parser=parse_nullifier)]
+    pub nullifiers_without_proofs: Vec<Digest>,
 }
 
 #[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -81,10 +107,23 @@ pub struct SyncStateArgs {
     #[arg(long = "nullifier")]
     pub nullifiers: Vec<u32>,
 
+    /// List of tracked notes, to learn whether they were consumed.
+    #[arg(long="note-hash", value_parser=parse_nullifier)]
+    pub note_hashes: Vec<Digest>,
+
     /// Start block height.
     pub block_num: u32,
 }
 
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct GetNoteAuthenticationPathArgs {
+    /// Block height at which the note was created.
+    pub block_num: u32,
+
+    /// Index of the note in the block.
+    pub note_index: u32,
+}
+
 #[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct GetBlockInputsArgs {
     /// List of account ids to query.
@@ -107,6 +146,28 @@ pub struct GetTransactionInputsArgs {
     pub nullifiers: Vec<Digest>,
 }
 
+#[derive(Args, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ExportNotesArgs {
+    /// List of prefixes of the tags of the exported notes.
+    #[arg(long = "note", required = true)]
+    pub note_tags: Vec<u32>,
+
+    /// First block whose notes are exported.
+    pub from_block: u32,
+
+    /// Last block whose notes are exported, included.
+    pub to_block: u32,
+
+    /// File the archive is written to, as an encoded `NoteArchive` protobuf message. Defaults to
+    /// `notes-<from_block>-<to_block>.bin` in the exports directory of the data directory.
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// File holding the hex encoded seed of the operator key the request is signed with.
+    #[arg(long, value_name = "FILE")]
+    pub operator_key_file: PathBuf,
+}
+
 /// Parses an `u64` used to repesent an account id, returns an error if the u64 doesn't fit in the
 /// field's modulus.
 fn parse_account_id(value: &str) -> anyhow::Result<u64> {
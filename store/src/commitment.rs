@@ -0,0 +1,226 @@
+//! Commitments to the state of the store, for operators to cross-check their replicas.
+//!
+//! Replicas of a store which applied the same blocks hold the same state. A commitment digests the
+//! hash of a block, the roots of the chain and accounts at the block, and the number of rows derived
+//! from the blocks up to it. Two replicas compare their state at a block by exchanging a single
+//! digest, whatever their chain tip, so a replica whose database diverged is noticed without
+//! copying it.
+//!
+//! At the chain tip, the roots and the totals of the chain are read from the in-memory structures
+//! of the store rather than from the database, so a replica whose in-memory state diverged from its
+//! database is noticed as well. The commitments which don't match their block are counted, see
+//! [CommitmentMetrics]. The root of the nullifier tree is only known at the chain tip, since the
+//! block headers don't commit to it yet: it is reported along with the commitment, but not digested.
+//!
+//! The commitments are served by `GetStateCommitment`, and published in the logs at every multiple
+//! of the configured interval, for the operators to alert on a divergence. The latest published
+//! commitment is served by `GetChainCounters`.
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use miden_crypto::hash::rpo::{Rpo256, RpoDigest};
+use tracing::{info, warn};
+
+use crate::{state::State, types::BlockNumber, COMPONENT};
+
+// STATE COMMITMENT
+// ================================================================================================
+
+/// The state of the store at the block `block_num`.
+///
+/// Only the tables derived from the blocks are counted, the tables whose content depends on the
+/// configuration of the store, e.g. the compacted account history, are left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateCommitment {
+    pub block_num: BlockNumber,
+
+    /// Hash of the block `block_num`.
+    pub block_hash: RpoDigest,
+
+    /// Root of the chain MMR, committing to the blocks before `block_num`.
+    pub chain_root: RpoDigest,
+
+    /// Root of the account tree after the block.
+    pub account_root: RpoDigest,
+
+    /// Root of the in-memory nullifier tree, only known at the chain tip. Not digested.
+    pub nullifier_root: Option<RpoDigest>,
+
+    /// Number of notes created up to the block.
+    pub num_notes: u64,
+
+    /// Number of nullifiers produced up to the block.
+    pub num_nullifiers: u64,
+
+    /// Number of notes created up to the block which were consumed up to the block, as linked to
+    /// their nullifier.
+    pub num_consumed_notes: u64,
+}
+
+impl StateCommitment {
+    /// Returns the digest of the state, equal on two replicas holding the same state.
+    pub fn digest(&self) -> RpoDigest {
+        let mut bytes = Vec::with_capacity(4 + 3 * 32 + 3 * 8);
+        bytes.extend_from_slice(&self.block_num.to_le_bytes());
+        for digest in [self.block_hash, self.chain_root, self.account_root] {
+            bytes.extend_from_slice(&digest.as_bytes());
+        }
+        for count in [self.num_notes, self.num_nullifiers, self.num_consumed_notes] {
+            bytes.extend_from_slice(&count.to_le_bytes());
+        }
+
+        Rpo256::hash(&bytes)
+    }
+}
+
+// COMMITMENT METRICS
+// ================================================================================================
+
+/// The latest commitment published, and the number of commitments taken at the chain tip which
+/// diverged from the database since the store was started.
+#[derive(Debug, Default)]
+pub struct CommitmentMetrics {
+    published: Mutex<Option<(BlockNumber, RpoDigest)>>,
+    diverged: AtomicU64,
+}
+
+impl CommitmentMetrics {
+    pub fn record_published(
+        &self,
+        commitment: &StateCommitment,
+    ) {
+        *self.published.lock().expect("commitment metrics lock poisoned") =
+            Some((commitment.block_num, commitment.digest()));
+    }
+
+    pub fn record_divergence(&self) {
+        self.diverged.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the block number and digest of the latest commitment published, if any.
+    pub fn published(&self) -> Option<(BlockNumber, RpoDigest)> {
+        *self.published.lock().expect("commitment metrics lock poisoned")
+    }
+
+    /// Returns the number of commitments whose in-memory state didn't match the database.
+    pub fn diverged(&self) -> u64 {
+        self.diverged.load(Ordering::Relaxed)
+    }
+}
+
+// PUBLICATION
+// ================================================================================================
+
+/// Logs the commitment of the state at every block multiple of `interval`, as the chain reaches it.
+///
+/// The blocks applied at once are published as of their latest multiple of `interval` only.
+pub async fn publish(
+    state: Arc<State>,
+    interval: u32,
+) {
+    let mut chain_tip = state.subscribe_chain_tip();
+    let mut published = None;
+    loop {
+        let tip = *chain_tip.borrow_and_update();
+        let block_num = tip.block_num - tip.block_num % interval;
+        if published != Some(block_num) {
+            match state.state_commitment(Some(block_num)).await {
+                Ok(Some(commitment)) => {
+                    info!(
+                        target: COMPONENT,
+                        block_num,
+                        block_hash = %commitment.block_hash,
+                        chain_root = %commitment.chain_root,
+                        account_root = %commitment.account_root,
+                        num_notes = commitment.num_notes,
+                        num_nullifiers = commitment.num_nullifiers,
+                        num_consumed_notes = commitment.num_consumed_notes,
+                        digest = %commitment.digest(),
+                        "State commitment"
+                    );
+                    state.commitment_metrics().record_published(&commitment);
+                    published = Some(block_num);
+                },
+                Ok(None) => warn!(target: COMPONENT, block_num, "Committed block not found"),
+                Err(err) => {
+                    warn!(target: COMPONENT, block_num, %err, "Failed to commit to the state")
+                },
+            }
+        }
+
+        if chain_tip.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_crypto::hash::rpo::RpoDigest;
+    use miden_objects::Felt;
+
+    use super::StateCommitment;
+
+    #[test]
+    fn test_state_commitment_digest() {
+        let commitment = StateCommitment {
+            block_num: 100,
+            block_hash: RpoDigest::new([Felt::new(1), Felt::new(2), Felt::new(3), Felt::new(4)]),
+            chain_root: RpoDigest::new([Felt::new(5), Felt::new(6), Felt::new(7), Felt::new(8)]),
+            account_root: RpoDigest::new([
+                Felt::new(9),
+                Felt::new(10),
+                Felt::new(11),
+                Felt::new(12),
+            ]),
+            nullifier_root: None,
+            num_notes: 10,
+            num_nullifiers: 5,
+            num_consumed_notes: 3,
+        };
+        assert_eq!(commitment.digest(), commitment.digest());
+
+        // every part of the state is committed to
+        for other in [
+            StateCommitment {
+                block_num: 101,
+                ..commitment
+            },
+            StateCommitment {
+                block_hash: RpoDigest::default(),
+                ..commitment
+            },
+            StateCommitment {
+                chain_root: RpoDigest::default(),
+                ..commitment
+            },
+            StateCommitment {
+                account_root: RpoDigest::default(),
+                ..commitment
+            },
+            StateCommitment {
+                num_notes: 11,
+                ..commitment
+            },
+            StateCommitment {
+                num_nullifiers: 6,
+                ..commitment
+            },
+            StateCommitment {
+                num_consumed_notes: 4,
+                ..commitment
+            },
+        ] {
+            assert_ne!(commitment.digest(), other.digest());
+        }
+
+        // the nullifier root is only known at the chain tip
+        let at_chain_tip = StateCommitment {
+            nullifier_root: Some(RpoDigest::default()),
+            ..commitment
+        };
+        assert_eq!(commitment.digest(), at_chain_tip.digest());
+    }
+}
@@ -1,30 +1,115 @@
 use std::{
     fmt::{Display, Formatter},
     path::PathBuf,
+    time::Duration,
 };
 
-use miden_node_utils::config::Endpoint;
+use miden_node_utils::{
+    config::{Endpoint, GrpcServerConfig, ListenAddress},
+    documented_config,
+    formatting::format_array,
+    tag_policy::{TagPolicy, TagPolicyError, TagRange},
+};
 use serde::{Deserialize, Serialize};
 
+use crate::data_directory::DataDirectory;
+
 pub const CONFIG_FILENAME: &str = "miden-store.toml";
 
 // Main config
 // ================================================================================================
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
-pub struct StoreConfig {
-    /// Defines the listening socket.
-    pub endpoint: Endpoint,
-    /// SQLite database file
-    pub database_filepath: PathBuf,
-    /// Genesis file
-    pub genesis_filepath: PathBuf,
+documented_config! {
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    pub struct StoreConfig {
+        /// Endpoint of the store's gRPC server.
+        pub endpoint: Endpoint => table,
+        /// Additional addresses to listen on, e.g. IPv6 or Unix domain sockets, as in
+        /// `[{ host = "::1", port = 28943 }, { unix_socket = "/run/miden/store.sock" }]`.
+        #[serde(default)]
+        pub listen: Vec<ListenAddress>,
+        /// Holds the database, the genesis file and the other data of the store, can't be shared
+        /// by multiple store instances.
+        pub data_directory: PathBuf,
+        /// Identifier of the chain, must match the chain id of the genesis file.
+        pub chain_id: u32,
+        /// Maximum time in milliseconds a database query serving a request is allowed to run.
+        pub query_timeout_ms: u64,
+        /// Blocks timestamped before the genesis block, or more than this many seconds in the
+        /// future, are rejected.
+        pub max_block_timestamp_skew_s: u64,
+        /// Time in milliseconds after which a block proposed by the block producer and not
+        /// committed is aborted.
+        pub proposal_timeout_ms: u64,
+        /// Hex encoded public keys of the operators allowed to sign destructive admin operations,
+        /// none can be performed if empty.
+        #[serde(default)]
+        pub operator_keys: Vec<String>,
+        /// Ranges of the note tags accepted in new blocks, by kind, e.g.
+        /// `local = [{ start = 0, end = 65535 }]`. The block producer adopts the store's policy,
+        /// every tag is accepted if there is no range.
+        #[serde(default)]
+        pub tag_policy: TagPolicy => table,
+        /// Number of blocks below the chain tip after which blocks are reported as final to the
+        /// clients, `0` reports the chain tip itself.
+        #[serde(default)]
+        pub safe_depth: u32,
+        /// Chains hosted by the store besides its own, each one with its own data directory, e.g.
+        /// `[{ chain_id = 2, data_directory = "miden-store-2" }]`. The requests are routed by
+        /// their chain id.
+        #[serde(default)]
+        pub tenants: Vec<TenantConfig>,
+        /// Retention of the history of the accounts' hashes, served to the clients syncing their
+        /// accounts.
+        #[serde(default)]
+        pub account_history: AccountHistoryConfig => table,
+        /// Number of blocks between two state commitments published in the logs, to cross-check
+        /// the replicas of the store. None is published if `0`.
+        #[serde(default)]
+        pub state_commitment_interval: u32,
+        /// Disk usage past which the store stops applying blocks and only serves reads.
+        #[serde(default)]
+        pub disk_limits: DiskLimitsConfig => table,
+        /// Visibility of the notes, by tag.
+        #[serde(default)]
+        pub note_visibility: NoteVisibilityConfig => table,
+        /// Whether the proofs of the batches of the new blocks are verified, every batch of a
+        /// block must then be proven. Requires the block producer to prove its batches, see its
+        /// `batch_proving` table.
+        #[serde(default)]
+        pub verify_batch_proofs: bool,
+        /// Tuning of the gRPC server, every option left to `0` keeps the default of tonic.
+        #[serde(default)]
+        pub grpc: GrpcServerConfig => table,
+        /// Startup of the store, while the in-memory trees of its state are rebuilt from the
+        /// database.
+        #[serde(default)]
+        pub startup: StartupConfig => table,
+    }
 }
 
 impl StoreConfig {
     pub fn as_url(&self) -> String {
         self.endpoint.to_string()
     }
+
+    pub fn data_directory(&self) -> DataDirectory {
+        DataDirectory::new(self.data_directory.clone())
+    }
+
+    /// Returns the config of the chain of `tenant`, which shares all the other settings of this
+    /// config.
+    pub fn tenant_config(
+        &self,
+        tenant: &TenantConfig,
+    ) -> StoreConfig {
+        StoreConfig {
+            data_directory: tenant.data_directory.clone(),
+            chain_id: tenant.chain_id,
+            tenants: Vec::new(),
+            ..self.clone()
+        }
+    }
 }
 
 impl Display for StoreConfig {
@@ -33,8 +118,221 @@ impl Display for StoreConfig {
         f: &mut Formatter<'_>,
     ) -> std::fmt::Result {
         f.write_fmt(format_args!(
-            "{{ endpoint: \"{}\",  database_filepath: {:?}, genesis_filepath: {:?} }}",
-            self.endpoint, self.database_filepath, self.genesis_filepath
+            "{{ endpoint: \"{}\", listen: {}, data_directory: {:?}, chain_id: {}, query_timeout_ms: {}, max_block_timestamp_skew_s: {}, proposal_timeout_ms: {}, operator_keys: {:?}, tag_policy: {:?}, safe_depth: {}, tenants: {}, account_history: {}, state_commitment_interval: {}, disk_limits: {}, note_visibility: {}, verify_batch_proofs: {}, grpc: {}, startup: {} }}",
+            self.endpoint, format_array(&self.listen), self.data_directory, self.chain_id, self.query_timeout_ms, self.max_block_timestamp_skew_s, self.proposal_timeout_ms, self.operator_keys, self.tag_policy, self.safe_depth, format_array(&self.tenants), self.account_history, self.state_commitment_interval, self.disk_limits, self.note_visibility, self.verify_batch_proofs, self.grpc, self.startup
+        ))
+    }
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        Self {
+            // port defined as: sum(ord(c)**p for (p, c) in enumerate('miden-store', 1)) % 2**16
+            endpoint: Endpoint {
+                host: "localhost".to_string(),
+                port: 28943,
+            },
+            listen: Vec::new(),
+            data_directory: "miden-store".into(),
+            chain_id: 1,
+            query_timeout_ms: 5000,
+            max_block_timestamp_skew_s: 60,
+            proposal_timeout_ms: 30_000,
+            operator_keys: Vec::new(),
+            tag_policy: TagPolicy::default(),
+            safe_depth: 0,
+            tenants: Vec::new(),
+            account_history: AccountHistoryConfig::default(),
+            state_commitment_interval: 0,
+            disk_limits: DiskLimitsConfig::default(),
+            note_visibility: NoteVisibilityConfig::default(),
+            verify_batch_proofs: false,
+            grpc: GrpcServerConfig::default(),
+            startup: StartupConfig::default(),
+        }
+    }
+}
+
+// Tenants config
+// ================================================================================================
+
+/// A chain hosted by the store besides its own, e.g. one of the devnets of a hosting provider.
+///
+/// The requests are routed to the tenant by their chain id metadata.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// Identifier of the tenant's chain, must match the chain id in its genesis file
+    pub chain_id: u32,
+    /// Root of the tenant's database, genesis file and other data, see [DataDirectory]
+    pub data_directory: PathBuf,
+}
+
+impl Display for TenantConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ chain_id: {}, data_directory: {:?} }}",
+            self.chain_id, self.data_directory
+        ))
+    }
+}
+
+// Account history config
+// ================================================================================================
+
+documented_config! {
+    /// Retention of the history of the accounts' hashes, served by `SyncState` to the clients.
+    ///
+    /// The history of an extremely active account grows with every block, and slows down the
+    /// queries of its updates. Once compacted, the history of an account keeps its latest
+    /// `keep_latest` updates, and the last update of every window of `checkpoint_interval` blocks
+    /// before them. The clients syncing a range of compacted history are served its latest
+    /// checkpoint.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct AccountHistoryConfig {
+        /// Number of the latest updates of every account kept, `0` keeps the full history.
+        pub keep_latest: u32,
+        /// The last update of every window of this many blocks is kept before the latest updates,
+        /// `0` keeps no checkpoint.
+        pub checkpoint_interval: u32,
+    }
+}
+
+impl Display for AccountHistoryConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ keep_latest: {}, checkpoint_interval: {} }}",
+            self.keep_latest, self.checkpoint_interval
+        ))
+    }
+}
+
+// Disk limits config
+// ================================================================================================
+
+documented_config! {
+    /// Disk usage past which the store refuses new blocks, and keeps serving reads.
+    ///
+    /// A database running out of disk in the middle of a write leaves the store unable to apply
+    /// the following blocks, and possibly its files corrupted. Checked before every block is
+    /// applied, the limits leave the operators the time to free some space while the chain stands
+    /// still, the block producer pausing until the store accepts blocks again.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct DiskLimitsConfig {
+        /// Maximum size in megabytes of the database and its write-ahead log, `0` sets no limit.
+        pub max_dataset_size_mb: u64,
+        /// Minimum free space in megabytes left on the disk of the data directory, `0` sets no
+        /// limit.
+        pub min_free_space_mb: u64,
+    }
+}
+
+impl Display for DiskLimitsConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ max_dataset_size_mb: {}, min_free_space_mb: {} }}",
+            self.max_dataset_size_mb, self.min_free_space_mb
+        ))
+    }
+}
+
+// Note visibility config
+// ================================================================================================
+
+documented_config! {
+    /// Visibility of the notes, by tag.
+    ///
+    /// The chain only commits to the hash and metadata of a private note, its details are kept off
+    /// chain by the parties to the note. The store must not hold them either: the recovery records
+    /// of the private notes are refused, and the ones stored before their tag was made private are
+    /// deleted when the store starts. The notes of no range are public, which is the default.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct NoteVisibilityConfig {
+        /// Ranges of the tags of the private notes, whose details the store refuses and never
+        /// holds. Every note is public if empty.
+        pub private_tags: Vec<TagRange>,
+    }
+}
+
+impl NoteVisibilityConfig {
+    /// Returns `true` if the notes tagged with `tag` are private.
+    pub fn is_private(
+        &self,
+        tag: u64,
+    ) -> bool {
+        self.private_tags.iter().any(|range| range.contains(tag))
+    }
+
+    /// Ensures the ranges of the private tags are not empty.
+    pub fn validate(&self) -> Result<(), TagPolicyError> {
+        match self.private_tags.iter().find(|range| range.start > range.end) {
+            Some(range) => Err(TagPolicyError::EmptyRange(*range)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for NoteVisibilityConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!("{{ private_tags: {} }}", format_array(&self.private_tags)))
+    }
+}
+
+// Startup config
+// ================================================================================================
+
+documented_config! {
+    /// Startup of the store, while the in-memory trees of its state are rebuilt from the database,
+    /// see [crate::startup].
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct StartupConfig {
+        /// Whether the endpoints which only read the database, e.g. the block headers, are served
+        /// while the trees are built, the others answering `UNAVAILABLE` until the state is
+        /// loaded.
+        pub partial_availability: bool,
+        /// Seconds between two logs of the progress of the trees being built, `0` logs none.
+        pub progress_interval_s: u64,
+    }
+}
+
+impl StartupConfig {
+    pub fn progress_interval(&self) -> Duration {
+        Duration::from_secs(self.progress_interval_s)
+    }
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            partial_availability: false,
+            progress_interval_s: 10,
+        }
+    }
+}
+
+impl Display for StartupConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ partial_availability: {}, progress_interval_s: {} }}",
+            self.partial_availability, self.progress_interval_s
         ))
     }
 }
@@ -53,9 +351,15 @@ mod tests {
     use std::path::PathBuf;
 
     use figment::Jail;
-    use miden_node_utils::config::load_config;
+    use miden_node_utils::{
+        config::{load_config, GrpcServerConfig},
+        tag_policy::{TagPolicy, TagRange},
+    };
 
-    use super::{Endpoint, StoreConfig, StoreTopLevelConfig};
+    use super::{
+        AccountHistoryConfig, DiskLimitsConfig, Endpoint, ListenAddress, NoteVisibilityConfig,
+        StartupConfig, StoreConfig, StoreTopLevelConfig, TenantConfig,
+    };
     use crate::config::CONFIG_FILENAME;
 
     #[test]
@@ -65,8 +369,36 @@ mod tests {
                 CONFIG_FILENAME,
                 r#"
                     [store]
-                    database_filepath = "local.sqlite3"
-                    genesis_filepath = "genesis.dat"
+                    data_directory = "data"
+                    chain_id = 1
+                    query_timeout_ms = 5000
+                    max_block_timestamp_skew_s = 60
+                    proposal_timeout_ms = 30000
+                    operator_keys = ["0x01"]
+                    tag_policy = { local = [{ start = 0, end = 65535 }] }
+                    safe_depth = 6
+                    listen = [{ host = "::1", port = 8080 }, { unix_socket = "store.sock" }]
+                    tenants = [{ chain_id = 2, data_directory = "devnet-2" }]
+                    state_commitment_interval = 100
+                    verify_batch_proofs = true
+
+                    [store.account_history]
+                    keep_latest = 1000
+                    checkpoint_interval = 10000
+
+                    [store.disk_limits]
+                    max_dataset_size_mb = 100000
+                    min_free_space_mb = 1024
+
+                    [store.note_visibility]
+                    private_tags = [{ start = 65536, end = 131071 }]
+
+                    [store.grpc]
+                    max_concurrent_streams = 100
+                    concurrency_limit_per_connection = 32
+
+                    [store.startup]
+                    partial_availability = true
 
                     [store.endpoint]
                     host = "127.0.0.1"
@@ -85,8 +417,58 @@ mod tests {
                             host: "127.0.0.1".to_string(),
                             port: 8080,
                         },
-                        database_filepath: "local.sqlite3".into(),
-                        genesis_filepath: "genesis.dat".into()
+                        listen: vec![
+                            ListenAddress::Tcp(Endpoint {
+                                host: "::1".to_string(),
+                                port: 8080,
+                            }),
+                            ListenAddress::Unix {
+                                unix_socket: "store.sock".into(),
+                            },
+                        ],
+                        data_directory: "data".into(),
+                        chain_id: 1,
+                        query_timeout_ms: 5000,
+                        max_block_timestamp_skew_s: 60,
+                        proposal_timeout_ms: 30000,
+                        operator_keys: vec!["0x01".to_string()],
+                        tag_policy: TagPolicy {
+                            local: vec![TagRange {
+                                start: 0,
+                                end: 65535
+                            }],
+                            ..Default::default()
+                        },
+                        safe_depth: 6,
+                        tenants: vec![TenantConfig {
+                            chain_id: 2,
+                            data_directory: "devnet-2".into(),
+                        }],
+                        account_history: AccountHistoryConfig {
+                            keep_latest: 1000,
+                            checkpoint_interval: 10000,
+                        },
+                        state_commitment_interval: 100,
+                        disk_limits: DiskLimitsConfig {
+                            max_dataset_size_mb: 100000,
+                            min_free_space_mb: 1024,
+                        },
+                        note_visibility: NoteVisibilityConfig {
+                            private_tags: vec![TagRange {
+                                start: 65536,
+                                end: 131071
+                            }],
+                        },
+                        verify_batch_proofs: true,
+                        grpc: GrpcServerConfig {
+                            max_concurrent_streams: 100,
+                            concurrency_limit_per_connection: 32,
+                            ..Default::default()
+                        },
+                        startup: StartupConfig {
+                            partial_availability: true,
+                            progress_interval_s: 10,
+                        },
                     }
                 }
             );
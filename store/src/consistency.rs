@@ -0,0 +1,162 @@
+//! Consistency checks of the blocks applied to the store.
+//!
+//! A block passing validation can still be suspicious, e.g. producing more nullifiers than there
+//! are notes to consume. Such blocks are not rejected, since private notes and notes created
+//! outside of the chain make these checks approximate, but they are logged and counted so a bug of
+//! the block producer is noticed early.
+use std::{
+    fmt::{Display, Formatter},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::SUSPICIOUS_NULLIFIER_ALLOWANCE;
+
+// CHAIN STATS
+// ================================================================================================
+
+/// Totals of the chain, as of the latest block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChainStats {
+    /// Number of notes created by all blocks.
+    pub num_notes: u64,
+
+    /// Number of nullifiers produced by all blocks.
+    pub num_nullifiers: u64,
+}
+
+impl ChainStats {
+    /// Adds the notes and nullifiers of a new block to the totals.
+    pub fn add_block(
+        &mut self,
+        num_notes: usize,
+        num_nullifiers: usize,
+    ) {
+        self.num_notes += num_notes as u64;
+        self.num_nullifiers += num_nullifiers as u64;
+    }
+}
+
+// BLOCK ANOMALIES
+// ================================================================================================
+
+/// A suspicious property of a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockAnomaly {
+    /// The block produces more nullifiers than there are notes it may consume, beyond the
+    /// allowance for notes which are not known to the store.
+    MoreNullifiersThanNotes {
+        num_nullifiers: u64,
+        num_consumable_notes: u64,
+    },
+}
+
+impl Display for BlockAnomaly {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            BlockAnomaly::MoreNullifiersThanNotes {
+                num_nullifiers,
+                num_consumable_notes,
+            } => f.write_fmt(format_args!(
+                "{num_nullifiers} nullifiers produced for {num_consumable_notes} notes which may be \
+                 unconsumed"
+            )),
+        }
+    }
+}
+
+/// Checks a block creating `num_notes` notes and producing `num_nullifiers` nullifiers, against
+/// the chain totals `stats` before the block.
+///
+/// The notes a block may consume are the ones created by the previous blocks which weren't
+/// consumed yet, and its own. The block is only compared to them, so a single suspicious block
+/// doesn't flag the blocks following it.
+pub fn check_block(
+    stats: &ChainStats,
+    num_notes: usize,
+    num_nullifiers: usize,
+) -> Vec<BlockAnomaly> {
+    let mut anomalies = Vec::new();
+
+    let num_nullifiers = num_nullifiers as u64;
+    let num_consumable_notes =
+        (stats.num_notes + num_notes as u64).saturating_sub(stats.num_nullifiers);
+    if num_nullifiers > num_consumable_notes + SUSPICIOUS_NULLIFIER_ALLOWANCE {
+        anomalies.push(BlockAnomaly::MoreNullifiersThanNotes {
+            num_nullifiers,
+            num_consumable_notes,
+        });
+    }
+
+    anomalies
+}
+
+// CONSISTENCY METRICS
+// ================================================================================================
+
+/// Counters of the suspicious blocks applied since the store was started.
+#[derive(Debug, Default)]
+pub struct ConsistencyMetrics {
+    suspicious_blocks: AtomicU64,
+    anomalies: AtomicU64,
+}
+
+impl ConsistencyMetrics {
+    pub fn record(
+        &self,
+        anomalies: &[BlockAnomaly],
+    ) {
+        if !anomalies.is_empty() {
+            self.suspicious_blocks.fetch_add(1, Ordering::Relaxed);
+            self.anomalies.fetch_add(anomalies.len() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the number of blocks with at least one anomaly.
+    pub fn suspicious_blocks(&self) -> u64 {
+        self.suspicious_blocks.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of anomalies found in all blocks.
+    pub fn anomalies(&self) -> u64 {
+        self.anomalies.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_block, BlockAnomaly, ChainStats, ConsistencyMetrics};
+    use crate::SUSPICIOUS_NULLIFIER_ALLOWANCE;
+
+    #[test]
+    fn test_check_block() {
+        let allowance = SUSPICIOUS_NULLIFIER_ALLOWANCE as usize;
+        let mut stats = ChainStats::default();
+
+        // the block consumes the notes it creates, and up to the allowance of unknown notes
+        assert!(check_block(&stats, 10, 10 + allowance).is_empty());
+        stats.add_block(10, 10 + allowance);
+
+        // none of the notes of the chain is left to consume
+        assert_eq!(
+            check_block(&stats, 0, allowance + 1),
+            vec![BlockAnomaly::MoreNullifiersThanNotes {
+                num_nullifiers: allowance as u64 + 1,
+                num_consumable_notes: 0,
+            }]
+        );
+        stats.add_block(0, allowance + 1);
+
+        // the blocks following a suspicious one are checked on their own
+        assert!(check_block(&stats, 0, 0).is_empty());
+        assert!(check_block(&stats, 5, 5).is_empty());
+
+        let metrics = ConsistencyMetrics::default();
+        metrics.record(&[]);
+        metrics.record(&check_block(&stats, 0, allowance + 1));
+        assert_eq!(metrics.suspicious_blocks(), 1);
+        assert_eq!(metrics.anomalies(), 1);
+    }
+}
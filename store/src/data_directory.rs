@@ -0,0 +1,282 @@
+//! Layout of the store's data directory.
+//!
+//! All the files of a store instance live under a single root directory:
+//!
+//! ```text
+//! <data_directory>/
+//! ├── store.lock            held by the store instance using the directory
+//! ├── miden-store.sqlite3   SQLite database
+//! ├── genesis.dat           genesis file, as generated by `miden-node make-genesis`
+//! └── exports/              data exported from the store, e.g. by `export-notes`
+//! ```
+//!
+//! Running multiple stores on the same host, e.g. one per network, only requires giving each one
+//! its own data directory. The lock file prevents two instances from using the same directory.
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::errors::DataDirectoryError;
+
+// CONSTANTS
+// ================================================================================================
+
+pub const DATABASE_FILENAME: &str = "miden-store.sqlite3";
+pub const GENESIS_FILENAME: &str = "genesis.dat";
+pub const LOCK_FILENAME: &str = "store.lock";
+pub const EXPORTS_DIRNAME: &str = "exports";
+
+// DATA DIRECTORY
+// ================================================================================================
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DataDirectory {
+    root: PathBuf,
+}
+
+impl DataDirectory {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn database_filepath(&self) -> PathBuf {
+        self.root.join(DATABASE_FILENAME)
+    }
+
+    pub fn genesis_filepath(&self) -> PathBuf {
+        self.root.join(GENESIS_FILENAME)
+    }
+
+    pub fn exports_dir(&self) -> PathBuf {
+        self.root.join(EXPORTS_DIRNAME)
+    }
+
+    pub fn lock_filepath(&self) -> PathBuf {
+        self.root.join(LOCK_FILENAME)
+    }
+
+    /// Write-ahead log of the database, holding the transactions not checkpointed into the
+    /// database file yet.
+    pub fn wal_filepath(&self) -> PathBuf {
+        self.root.join(format!("{DATABASE_FILENAME}-wal"))
+    }
+
+    /// Returns the size on disk of the database and of its write-ahead log.
+    ///
+    /// Missing files are counted as empty.
+    pub fn disk_usage(&self) -> io::Result<DiskUsage> {
+        Ok(DiskUsage {
+            database_size: path_size(&self.database_filepath())?,
+            wal_size: path_size(&self.wal_filepath())?,
+        })
+    }
+
+    /// Returns the space left to the store on the disk of the data directory, in bytes.
+    pub fn free_space(&self) -> io::Result<u64> {
+        free_space(&self.root)
+    }
+
+    /// Creates the missing directories of the layout, and locks the data directory.
+    ///
+    /// The directory stays locked until the returned [DataDirectoryLock] is dropped. Fails if
+    /// another store instance holds the lock.
+    pub fn open(&self) -> Result<DataDirectoryLock, DataDirectoryError> {
+        for path in [self.root.clone(), self.exports_dir()] {
+            fs::create_dir_all(&path)
+                .map_err(|error| DataDirectoryError::CreateFailed { path, error })?;
+        }
+
+        DataDirectoryLock::acquire(self.lock_filepath())
+    }
+}
+
+// DISK USAGE
+// ================================================================================================
+
+/// Size on disk of the files of a data directory, in bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub database_size: u64,
+    pub wal_size: u64,
+}
+
+impl DiskUsage {
+    /// Returns the size of the database and of its write-ahead log together.
+    pub fn dataset_size(&self) -> u64 {
+        self.database_size + self.wal_size
+    }
+}
+
+/// Returns the size of the file at `path`, zero if it doesn't exist.
+fn path_size(path: &Path) -> io::Result<u64> {
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(metadata.len()),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(error) => Err(error),
+    }
+}
+
+/// Returns the space available to unprivileged processes on the file system of `path`.
+#[cfg(unix)]
+fn free_space(path: &Path) -> io::Result<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+    // Safety: `statvfs` only writes to the zeroed struct, and reads the nul terminated path
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    #[allow(clippy::unnecessary_cast)] // the field types differ between platforms
+    Ok(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+/// Returns the space available on the file system of `path`, only supported on Unix.
+#[cfg(not(unix))]
+fn free_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Measuring the free disk space is only supported on Unix",
+    ))
+}
+
+// DATA DIRECTORY LOCK
+// ================================================================================================
+
+/// Exclusive lock on a data directory.
+///
+/// The lock is released when dropped, or by the operating system if the process exits, so a crashed
+/// instance never leaves a stale lock behind.
+#[derive(Debug)]
+pub struct DataDirectoryLock {
+    _file: File,
+}
+
+impl DataDirectoryLock {
+    fn acquire(path: PathBuf) -> Result<Self, DataDirectoryError> {
+        let lock_failed = |error| DataDirectoryError::LockFailed {
+            path: path.clone(),
+            error,
+        };
+
+        // the file is not truncated before the lock is acquired, it holds the id of the owner
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(lock_failed)?;
+
+        if !try_lock_exclusive(&file).map_err(lock_failed)? {
+            let owner_pid = fs::read_to_string(&path).unwrap_or_default().trim().to_string();
+            return Err(DataDirectoryError::AlreadyInUse { path, owner_pid });
+        }
+
+        file.set_len(0).map_err(lock_failed)?;
+        write!(file, "{}", std::process::id()).map_err(lock_failed)?;
+
+        Ok(Self { _file: file })
+    }
+}
+
+/// Tries to take an exclusive advisory lock on `file`, returns `false` if it is already locked.
+#[cfg(unix)]
+fn try_lock_exclusive(file: &File) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    // Safety: `flock` only operates on the file descriptor, which is open for the whole call
+    let result = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if result == 0 {
+        return Ok(true);
+    }
+
+    let error = io::Error::last_os_error();
+    match error.kind() {
+        io::ErrorKind::WouldBlock => Ok(false),
+        _ => Err(error),
+    }
+}
+
+/// Tries to take an exclusive advisory lock on `file`, only supported on Unix.
+#[cfg(not(unix))]
+fn try_lock_exclusive(_file: &File) -> io::Result<bool> {
+    tracing::warn!(
+        target: crate::COMPONENT,
+        "Data directory locking is only supported on Unix, concurrent use won't be detected"
+    );
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use figment::Jail;
+
+    use super::{DataDirectory, DiskUsage};
+    use crate::errors::DataDirectoryError;
+
+    #[test]
+    fn test_data_directory_lock() {
+        Jail::expect_with(|_jail| {
+            let data_directory = DataDirectory::new("network-1");
+
+            let lock = data_directory.open().expect("first instance must acquire the lock");
+            assert!(data_directory.exports_dir().is_dir());
+
+            let result = data_directory.open();
+            assert!(
+                matches!(
+                    result,
+                    Err(DataDirectoryError::AlreadyInUse { ref owner_pid, .. })
+                        if *owner_pid == std::process::id().to_string()
+                ),
+                "second instance must not acquire the lock, got {result:?}"
+            );
+
+            // a different directory can be used concurrently
+            let _other = DataDirectory::new("network-2").open().expect("other directory is free");
+
+            drop(lock);
+            let _lock = data_directory.open().expect("lock must be released on drop");
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_data_directory_disk_usage() {
+        Jail::expect_with(|_jail| {
+            let data_directory = DataDirectory::new("network-1");
+            let _lock = data_directory.open().expect("data directory must open");
+
+            assert_eq!(data_directory.disk_usage().unwrap(), DiskUsage::default());
+
+            fs::write(data_directory.database_filepath(), [0; 10]).unwrap();
+            fs::write(data_directory.wal_filepath(), [0; 5]).unwrap();
+            // the exports are not part of the dataset
+            fs::write(data_directory.exports_dir().join("notes.bin"), [0; 3]).unwrap();
+
+            assert_eq!(
+                data_directory.disk_usage().unwrap(),
+                DiskUsage {
+                    database_size: 10,
+                    wal_size: 5,
+                }
+            );
+            assert_eq!(data_directory.disk_usage().unwrap().dataset_size(), 15);
+            assert!(data_directory.free_space().unwrap() > 0);
+
+            Ok(())
+        });
+    }
+}
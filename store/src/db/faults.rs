@@ -0,0 +1,124 @@
+//! Faults injected into the writer of the DB, to test the [crate::state::State] against failures
+//! and against the interleavings of the writer with the readers.
+use std::mem;
+
+use deadpool_sqlite::Object;
+use rusqlite::ffi;
+use tokio::sync::oneshot;
+
+use super::{Db, Result};
+use crate::errors::DatabaseError;
+
+/// A step of [Db::apply_blocks], at which a fault can be injected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteStep {
+    /// The blocks are written to the transaction, which is not committed yet.
+    BlocksWritten,
+    /// The state acquired the in-memory write lock, the transaction is about to be committed.
+    LockAcquired,
+}
+
+/// Faults of the next [Db::apply_blocks] call.
+#[derive(Debug, Default)]
+pub struct WriteFaults {
+    /// The writer pauses at the step, until resumed through the [PauseHandle].
+    pub pause: Option<(WriteStep, WritePause)>,
+    /// The writer panics at the step.
+    pub panic_at: Option<WriteStep>,
+    /// The writer fails at the step, as if the disk was full.
+    pub fail_at: Option<WriteStep>,
+}
+
+impl WriteFaults {
+    /// Applies the faults injected at `step`, called from the writer's blocking task.
+    pub(super) fn reach(
+        &mut self,
+        step: WriteStep,
+    ) -> Result<()> {
+        if self.pause.as_ref().is_some_and(|(at, _)| *at == step) {
+            let (_, pause) = self.pause.take().expect("the pause is at the step");
+            let _ = pause.reached.send(());
+            // a dropped handle resumes the writer
+            let _ = pause.resume.blocking_recv();
+        }
+        if self.panic_at == Some(step) {
+            panic!("injected panic at {step:?}");
+        }
+        if self.fail_at == Some(step) {
+            return Err(DatabaseError::SqliteError(rusqlite::Error::SqliteFailure(
+                ffi::Error::new(ffi::SQLITE_FULL),
+                Some(format!("injected failure at {step:?}")),
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// The writer's side of a pause, see [WriteFaults::pause].
+#[derive(Debug)]
+pub struct WritePause {
+    reached: oneshot::Sender<()>,
+    resume: oneshot::Receiver<()>,
+}
+
+/// The test's side of a pause, see [WriteFaults::pause].
+#[derive(Debug)]
+pub struct PauseHandle {
+    reached: oneshot::Receiver<()>,
+    resume: oneshot::Sender<()>,
+}
+
+impl WritePause {
+    pub fn new() -> (Self, PauseHandle) {
+        let (reached_tx, reached_rx) = oneshot::channel();
+        let (resume_tx, resume_rx) = oneshot::channel();
+
+        (
+            Self {
+                reached: reached_tx,
+                resume: resume_rx,
+            },
+            PauseHandle {
+                reached: reached_rx,
+                resume: resume_tx,
+            },
+        )
+    }
+}
+
+impl PauseHandle {
+    /// Waits until the writer is paused.
+    pub async fn reached(&mut self) {
+        (&mut self.reached).await.expect("the writer must reach the paused step");
+    }
+
+    /// Resumes the paused writer.
+    pub fn resume(self) {
+        let _ = self.resume.send(());
+    }
+}
+
+impl Db {
+    /// Injects `faults` into the next write to the DB.
+    pub fn inject_faults(
+        &self,
+        faults: WriteFaults,
+    ) {
+        *self.faults.lock().expect("poisoned lock") = faults;
+    }
+
+    /// Takes the faults injected into the next write.
+    pub(super) fn take_faults(&self) -> WriteFaults {
+        mem::take(&mut *self.faults.lock().expect("poisoned lock"))
+    }
+
+    /// Takes every connection of the pool, the queries wait until they are dropped.
+    pub async fn exhaust_pool(&self) -> Vec<Object> {
+        let mut connections = Vec::new();
+        for _ in 0..self.pool.status().max_size {
+            connections.push(self.pool.get().await.expect("the pool must provide a connection"));
+        }
+        connections
+    }
+}
@@ -1,9 +1,51 @@
 use once_cell::sync::Lazy;
 use rusqlite_migration::{Migrations, M};
 
-pub static MIGRATIONS: Lazy<Migrations> = Lazy::new(|| {
-    Migrations::new(vec![M::up(
+use crate::NOTE_TAG_STATS_WINDOW;
+
+/// Version of the schema once all the migrations are applied, i.e. their number.
+pub const SCHEMA_VERSION: u32 = 15;
+
+/// Migration introducing the counts of the notes by tag, filled right away for the latest
+/// [NOTE_TAG_STATS_WINDOW] blocks.
+static NOTE_TAG_STATS_MIGRATION: Lazy<String> = Lazy::new(|| {
+    format!(
         "
+        CREATE TABLE
+            note_tag_stats
+        (
+            block_num INTEGER NOT NULL,
+            tag_prefix INTEGER NOT NULL,
+            num_notes INTEGER NOT NULL,
+
+            PRIMARY KEY (block_num, tag_prefix),
+            CONSTRAINT note_tag_stats_block_num_is_u32 CHECK (block_num >= 0 AND block_num < 4294967296),
+            CONSTRAINT note_tag_stats_tag_prefix_is_u16 CHECK (tag_prefix >= 0 AND tag_prefix < 65536),
+            FOREIGN KEY (block_num) REFERENCES block_headers (block_num)
+        ) STRICT, WITHOUT ROWID;
+
+        -- only the latest blocks are kept, the window is small enough to be filled from the notes
+        -- right away
+        INSERT INTO note_tag_stats (block_num, tag_prefix, num_notes)
+        SELECT
+            block_num,
+            (tag >> 48) & 65535,
+            COUNT(*)
+        FROM
+            notes
+        WHERE
+            block_num > (SELECT MAX(block_num) FROM block_headers) - {NOTE_TAG_STATS_WINDOW}
+        GROUP BY
+            block_num,
+            (tag >> 48) & 65535;
+        "
+    )
+});
+
+pub static MIGRATIONS: Lazy<Migrations> = Lazy::new(|| {
+    Migrations::new(vec![
+        M::up(
+            "
         CREATE TABLE
             block_headers
         (
@@ -56,10 +98,205 @@ pub static MIGRATIONS: Lazy<Migrations> = Lazy::new(|| {
             FOREIGN KEY (block_number) REFERENCES block_header (block_num)
         ) STRICT, WITHOUT ROWID;
         ",
-    )])
+        ),
+        M::up(
+            "
+        CREATE TABLE
+            note_nullifiers
+        (
+            note_hash BLOB NOT NULL,
+            nullifier BLOB NOT NULL,
+
+            PRIMARY KEY (note_hash),
+            CONSTRAINT note_nullifiers_nullifier_is_digest CHECK (length(nullifier) = 32),
+            FOREIGN KEY (nullifier) REFERENCES nullifiers (nullifier)
+        ) STRICT, WITHOUT ROWID;
+        ",
+        ),
+        M::up(
+            "
+        -- the note trees are stored per batch, the notes of a block being placed at the offset of
+        -- their batch
+        CREATE TABLE
+            note_trees
+        (
+            block_num INTEGER NOT NULL,
+            batches BLOB NOT NULL,
+            nodes BLOB NOT NULL,
+
+            PRIMARY KEY (block_num),
+            CONSTRAINT note_trees_block_num_is_u32 CHECK (block_num >= 0 AND block_num < 4294967296),
+            CONSTRAINT note_trees_batches_are_u32_pairs CHECK (length(batches) % 8 = 0),
+            CONSTRAINT note_trees_nodes_are_digests CHECK (length(nodes) % 32 = 0),
+            FOREIGN KEY (block_num) REFERENCES block_headers (block_num)
+        ) STRICT, WITHOUT ROWID;
+
+        -- the merkle paths are derived from the note trees, the trees of the blocks applied before
+        -- this migration are rebuilt from the notes when needed
+        ALTER TABLE notes DROP COLUMN merkle_path;
+        ",
+        ),
+        M::up(
+            "
+        CREATE TABLE
+            backfills
+        (
+            name TEXT NOT NULL,
+            next_block_num INTEGER NOT NULL,
+            completed INTEGER NOT NULL,
+
+            PRIMARY KEY (name),
+            CONSTRAINT backfills_next_block_num_is_u32 CHECK (next_block_num >= 0 AND next_block_num < 4294967296),
+            CONSTRAINT backfills_completed_is_bool CHECK (completed IN (0, 1))
+        ) STRICT, WITHOUT ROWID;
+
+        -- the note trees of the blocks applied before they were stored are built in the
+        -- background, instead of when their notes are requested
+        INSERT INTO backfills (name, next_block_num, completed) VALUES ('note_trees', 0, 0);
+        ",
+        ),
+        M::up(
+            "
+        CREATE TABLE
+            block_tx_kernels
+        (
+            block_num INTEGER NOT NULL,
+            version INTEGER NOT NULL,
+
+            PRIMARY KEY (block_num, version),
+            CONSTRAINT block_tx_kernels_block_num_is_u32 CHECK (block_num >= 0 AND block_num < 4294967296),
+            CONSTRAINT block_tx_kernels_version_is_u32 CHECK (version >= 0 AND version < 4294967296),
+            FOREIGN KEY (block_num) REFERENCES block_headers (block_num)
+        ) STRICT, WITHOUT ROWID;
+        ",
+        ),
+        M::up(&NOTE_TAG_STATS_MIGRATION),
+        M::up(
+            "
+        CREATE TABLE
+            note_recovery_records
+        (
+            note_hash BLOB NOT NULL,
+            block_num INTEGER NOT NULL,
+            note_index INTEGER NOT NULL,
+            details BLOB NOT NULL,
+            merkle_path BLOB NOT NULL,
+
+            PRIMARY KEY (note_hash),
+            CONSTRAINT note_recovery_records_block_num_is_u32 CHECK (block_num >= 0 AND block_num < 4294967296),
+            CONSTRAINT note_recovery_records_note_index_is_u32 CHECK (note_index >= 0 AND note_index < 4294967296),
+            CONSTRAINT note_recovery_records_merkle_path_is_digests CHECK (length(merkle_path) % 32 = 0),
+            FOREIGN KEY (block_num, note_index) REFERENCES notes (block_num, note_index)
+        ) STRICT, WITHOUT ROWID;
+
+        -- the notes of a record are found by hash
+        CREATE INDEX notes_note_hash ON notes (note_hash);
+        ",
+        ),
+        M::up(
+            "
+        CREATE TABLE
+            block_signatures
+        (
+            block_num INTEGER NOT NULL,
+            signature BLOB NOT NULL,
+
+            PRIMARY KEY (block_num),
+            CONSTRAINT block_signatures_block_num_is_u32 CHECK (block_num >= 0 AND block_num < 4294967296),
+            FOREIGN KEY (block_num) REFERENCES block_headers (block_num)
+        ) STRICT, WITHOUT ROWID;
+        ",
+        ),
+        M::up(
+            "
+        -- the note and account hashes were stored protobuf encoded, the ones of the blocks applied
+        -- before this migration are rewritten in the native serialization in the background, and
+        -- read in either encoding meanwhile
+        INSERT INTO backfills (name, next_block_num, completed) VALUES ('native_digests', 0, 0);
+        ",
+        ),
+        M::up(
+            "
+        -- the accounts table holds the latest hash of every account, its history is kept apart so
+        -- it can be compacted, starting with the latest updates known so far
+        CREATE TABLE
+            account_updates
+        (
+            account_id INTEGER NOT NULL,
+            block_num INTEGER NOT NULL,
+            account_hash BLOB NOT NULL,
+
+            PRIMARY KEY (account_id, block_num),
+            CONSTRAINT account_updates_block_num_is_u32 CHECK (block_num >= 0 AND block_num < 4294967296),
+            FOREIGN KEY (block_num) REFERENCES block_headers (block_num)
+        ) STRICT, WITHOUT ROWID;
+
+        INSERT INTO account_updates (account_id, block_num, account_hash)
+            SELECT account_id, block_num, account_hash FROM accounts;
+        ",
+        ),
+        M::up(
+            "
+        -- the blocks whose transactions were ordered canonically by the block producer, the
+        -- others were ordered as the transactions arrived
+        CREATE TABLE
+            canonically_ordered_blocks
+        (
+            block_num INTEGER NOT NULL,
+
+            PRIMARY KEY (block_num),
+            CONSTRAINT canonically_ordered_blocks_block_num_is_u32 CHECK (block_num >= 0 AND block_num < 4294967296),
+            FOREIGN KEY (block_num) REFERENCES block_headers (block_num)
+        ) STRICT, WITHOUT ROWID;
+        ",
+        ),
+        M::up(
+            "
+        CREATE TABLE
+            block_timestamp_attestations
+        (
+            block_num INTEGER NOT NULL,
+            attestation BLOB NOT NULL,
+
+            PRIMARY KEY (block_num),
+            CONSTRAINT block_timestamp_attestations_block_num_is_u32 CHECK (block_num >= 0 AND block_num < 4294967296),
+            FOREIGN KEY (block_num) REFERENCES block_headers (block_num)
+        ) STRICT, WITHOUT ROWID;
+        ",
+        ),
+        M::up(
+            "
+        -- the range queries seek the range of every prefix in the index instead of scanning the
+        -- whole table
+        CREATE INDEX nullifiers_prefix_block_number ON nullifiers (nullifier_prefix, block_number);
+        ",
+        ),
+        M::up(
+            "
+        -- the serialized states of the public accounts, NULL for the private accounts and the
+        -- public accounts whose state is unknown, e.g. the ones updated before this migration
+        ALTER TABLE accounts ADD COLUMN details BLOB;
+        ",
+        ),
+        M::up(
+            "
+        -- whether the notes are private, as flagged by the block which created them, the notes
+        -- created before this migration being public
+        ALTER TABLE notes ADD COLUMN private INTEGER NOT NULL DEFAULT 0;
+        ",
+        ),
+    ])
 });
 
 #[test]
 fn migrations_test() {
     assert!(MIGRATIONS.validate().is_ok());
 }
+
+#[test]
+fn schema_version_test() {
+    let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+    MIGRATIONS.to_latest(&mut conn).unwrap();
+
+    assert_eq!(super::sql::select_schema_version(&mut conn).unwrap(), SCHEMA_VERSION);
+}
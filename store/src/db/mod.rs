@@ -1,36 +1,79 @@
-use std::fs::{self, create_dir_all};
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use deadpool_sqlite::{Config as SqliteConfig, Hook, HookError, Pool, Runtime};
-use miden_crypto::{hash::rpo::RpoDigest, utils::Deserializable};
+use miden_crypto::{hash::rpo::RpoDigest, merkle::MerklePath};
 use miden_node_proto::{
     account::AccountInfo,
     block_header,
     digest::Digest,
-    note::Note,
-    responses::{AccountHashUpdate, NullifierUpdate},
+    note::{Note, NoteCreated, NoteRecoveryRecord},
+    responses::{AccountHashUpdate, ConsumedNoteUpdate, NoteTagStats, NullifierUpdate, TableSize},
 };
-use rusqlite::vtab::array;
+use miden_node_utils::tag_policy::TagRange;
+use miden_objects::{utils::serde::Serializable, BlockHeader};
+use rusqlite::{vtab::array, Connection, OpenFlags};
 use tokio::sync::oneshot;
-use tracing::{info, info_span, instrument};
+use tracing::{info, info_span, instrument, warn};
 
 use crate::{
-    config::StoreConfig,
-    errors::{DatabaseError, DatabaseSetupError, GenesisError, StateSyncError},
+    backfill::BackfillProgress,
+    commitment::StateCommitment,
+    config::{AccountHistoryConfig, DiskLimitsConfig, StoreConfig},
+    data_directory::{DataDirectory, DataDirectoryLock},
+    errors::{DatabaseError, DatabaseSetupError, DiskLimitError, GenesisError, StateSyncError},
     genesis::{GenesisState, GENESIS_BLOCK_NUM},
+    note_tree::BlockNoteTree,
     types::{AccountId, BlockNumber},
     COMPONENT,
 };
 
 mod migrations;
+mod profiling;
 mod sql;
 
+pub use migrations::SCHEMA_VERSION;
+/// Decoding of the blobs of the database, exposed to be fuzzed.
+pub use sql::{decode_digest, decode_rpo_digest, encode_digest};
+
+#[cfg(any(test, feature = "testing"))]
+pub mod faults;
 #[cfg(test)]
 mod tests;
 
 pub type Result<T, E = DatabaseError> = std::result::Result<T, E>;
 
+/// Number of SQLite virtual machine instructions between two checks of a query's interruption.
+const QUERY_INTERRUPT_CHECK_PERIOD: i32 = 1000;
+
+/// Number of prepared statements kept by every connection, large enough to hold all the statements
+/// of the store so they are only compiled once per connection.
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
 pub struct Db {
     pool: Pool,
+    /// Identifier of the chain stored in the database, defined by its genesis file.
+    chain_id: u32,
+    /// Maximum time a query issued on behalf of a request is allowed to run.
+    query_timeout: Duration,
+    /// Retention of the history of the accounts, compacted as new blocks update them.
+    account_history: AccountHistoryConfig,
+    /// Disk usage past which no block is written, see [Db::check_disk_limits].
+    disk_limits: DiskLimitsConfig,
+    /// Whether the last check of the disk limits found them exceeded.
+    disk_limits_exceeded: AtomicBool,
+    data_directory: DataDirectory,
+    /// Prevents other store instances from using the data directory while the database is open.
+    _data_directory_lock: DataDirectoryLock,
+    /// Faults injected into the next write, see [faults::WriteFaults].
+    #[cfg(any(test, feature = "testing"))]
+    faults: std::sync::Mutex<faults::WriteFaults>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -40,21 +83,38 @@ pub struct StateSyncUpdate {
     pub chain_tip: BlockNumber,
     pub account_updates: Vec<AccountHashUpdate>,
     pub nullifiers: Vec<NullifierUpdate>,
+    pub consumed_notes: Vec<ConsumedNoteUpdate>,
+}
+
+/// The data of a new block written to the DB, see [Db::apply_blocks].
+pub struct BlockUpdate {
+    pub block_header: block_header::BlockHeader,
+    pub notes: Vec<Note>,
+    pub note_tree: BlockNoteTree,
+    pub nullifiers: Vec<RpoDigest>,
+    pub accounts: Vec<(AccountId, Digest)>,
+    /// Serialized states of the public accounts of `accounts` known to the block producer
+    pub account_details: Vec<(AccountId, Vec<u8>)>,
+    pub consumed_notes: Vec<(RpoDigest, RpoDigest)>,
+    pub tx_kernel_versions: Vec<u32>,
+    pub signature: Option<block_header::BlockSignature>,
+    pub canonical_tx_ordering: bool,
+    pub timestamp_attestation: Option<block_header::TimestampAttestation>,
 }
 
 impl Db {
-    /// Open a connection to the DB, apply any pending migrations, and ensure that the genesis block
-    /// is as expected and present in the database.
+    /// Lock the data directory, open a connection to the DB, apply any pending migrations, and
+    /// ensure that the genesis block is as expected and present in the database.
     // TODO: This span is logged in a root span, we should connect it to the parent one.
     #[instrument(target = "miden-store", skip_all)]
     pub async fn setup(config: StoreConfig) -> Result<Self, DatabaseSetupError> {
         info!(target: COMPONENT, %config, "Connecting to the database");
 
-        if let Some(p) = config.database_filepath.parent() {
-            create_dir_all(p).map_err(DatabaseError::IoError)?;
-        }
+        let data_directory = config.data_directory();
+        let data_directory_lock = data_directory.open()?;
+        let database_filepath = data_directory.database_filepath();
 
-        let pool = SqliteConfig::new(config.database_filepath.clone())
+        let pool = SqliteConfig::new(database_filepath.clone())
             .builder(Runtime::Tokio1)
             .expect("Infallible")
             .post_create(Hook::async_fn(move |conn, _| {
@@ -66,6 +126,10 @@ impl Db {
                             // queries we want to run
                             array::load_module(conn)?;
 
+                            // Statements are prepared through the connection's cache, keyed by
+                            // their SQL text
+                            conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+
                             // Enable the WAL mode. This allows concurrent reads while the
                             // transaction is being written, this is required for proper
                             // synchronization of the servers in-memory and on-disk representations
@@ -87,7 +151,7 @@ impl Db {
 
         info!(
             target: COMPONENT,
-            sqlite = format!("{}", config.database_filepath.display()),
+            sqlite = format!("{}", database_filepath.display()),
             "Connected to the database"
         );
 
@@ -99,13 +163,86 @@ impl Db {
                 DatabaseError::InteractError(format!("Migration task failed: {err}"))
             })??;
 
-        let db = Db { pool };
-        db.ensure_genesis_block(&config.genesis_filepath.as_path().to_string_lossy())
+        let db = Db {
+            pool,
+            chain_id: config.chain_id,
+            query_timeout: Duration::from_millis(config.query_timeout_ms),
+            account_history: config.account_history.clone(),
+            disk_limits: config.disk_limits.clone(),
+            disk_limits_exceeded: AtomicBool::new(false),
+            data_directory: data_directory.clone(),
+            _data_directory_lock: data_directory_lock,
+            #[cfg(any(test, feature = "testing"))]
+            faults: Default::default(),
+        };
+        db.ensure_genesis_block(&data_directory.genesis_filepath(), config.chain_id)
             .await?;
 
         Ok(db)
     }
 
+    /// Returns the identifier of the chain stored in the database.
+    pub fn chain_id(&self) -> u32 {
+        self.chain_id
+    }
+
+    /// Checks that the disk usage of the data directory is under the configured limits, before a
+    /// block is written.
+    ///
+    /// Logs when the limits are first exceeded, and when the disk usage is back under them.
+    pub async fn check_disk_limits(&self) -> Result<(), DiskLimitError> {
+        let DiskLimitsConfig {
+            max_dataset_size_mb,
+            min_free_space_mb,
+        } = self.disk_limits;
+        if max_dataset_size_mb == 0 && min_free_space_mb == 0 {
+            return Ok(());
+        }
+
+        let data_directory = self.data_directory.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            const MB: u64 = 1024 * 1024;
+
+            if max_dataset_size_mb > 0 {
+                let size_mb = data_directory.disk_usage()?.dataset_size() / MB;
+                if size_mb > max_dataset_size_mb {
+                    return Err(DiskLimitError::DatasetTooLarge {
+                        size_mb,
+                        max_size_mb: max_dataset_size_mb,
+                    });
+                }
+            }
+            if min_free_space_mb > 0 {
+                let free_space_mb = data_directory.free_space()? / MB;
+                if free_space_mb < min_free_space_mb {
+                    return Err(DiskLimitError::NotEnoughFreeSpace {
+                        free_space_mb,
+                        min_free_space_mb,
+                    });
+                }
+            }
+
+            Ok(())
+        })
+        .await
+        .map_err(|err| {
+            DiskLimitError::MeasurementFailed(std::io::Error::new(std::io::ErrorKind::Other, err))
+        })?;
+
+        let was_exceeded = self.disk_limits_exceeded.swap(result.is_err(), Ordering::Relaxed);
+        match &result {
+            Err(err) if !was_exceeded => {
+                warn!(target: COMPONENT, %err, "Disk limits exceeded, the store is read-only")
+            },
+            Ok(()) if was_exceeded => {
+                info!(target: COMPONENT, "Disk usage back under the limits, the store accepts blocks")
+            },
+            _ => (),
+        }
+
+        result
+    }
+
     /// Loads all the nullifiers from the DB.
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
@@ -115,71 +252,452 @@ impl Db {
         })?
     }
 
+    /// Loads up to `limit` nullifiers following the nullifier `after`, by their encoding, from the
+    /// DB.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, err)]
+    pub async fn select_nullifiers_after(
+        &self,
+        after: Option<RpoDigest>,
+        limit: usize,
+    ) -> Result<Vec<(RpoDigest, BlockNumber)>> {
+        self.interruptible_query("Select nullifiers", move |conn| {
+            sql::select_nullifiers_after(conn, after, limit)
+        })
+        .await
+    }
+
+    /// Counts the nullifiers, the accounts and the block headers the in-memory state is built
+    /// from.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn count_startup_rows(&self) -> Result<u64> {
+        self.interruptible_query("Count startup rows", sql::count_startup_rows).await
+    }
+
     /// Loads all the notes from the DB.
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
     pub async fn select_notes(&self) -> Result<Vec<Note>> {
-        self.pool.get().await?.interact(sql::select_notes).await.map_err(|err| {
-            DatabaseError::InteractError(format!("Select notes task failed: {err}"))
-        })?
+        self.interruptible_query("Select notes", sql::select_notes).await
     }
 
-    /// Loads all the accounts from the DB.
+    /// Counts the notes in the DB.
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
-    pub async fn select_accounts(&self) -> Result<Vec<AccountInfo>> {
-        self.pool.get().await?.interact(sql::select_accounts).await.map_err(|err| {
-            DatabaseError::InteractError(format!("Select accounts task failed: {err}"))
-        })?
+    pub async fn count_notes(&self) -> Result<u64> {
+        self.interruptible_query("Count notes", sql::count_notes).await
     }
 
-    /// Search for a [block_header::BlockHeader] from the DB by its `block_num`.
+    /// Loads the notes created from the block `from_block` to the block `to_block` which the 16
+    /// high bits of the tag match `tags`.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_notes_by_tag_and_block_range(
+        &self,
+        tags: Vec<u32>,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+    ) -> Result<Vec<Note>> {
+        self.interruptible_query("Select notes by tag and block range", move |conn| {
+            sql::select_notes_by_tag_and_block_range(conn, &tags, from_block, to_block)
+        })
+        .await
+    }
+
+    /// Loads the notes created from the block `from_block` to the block `to_block` which the 16
+    /// high bits of the tag match `tags`, and whose consumption wasn't recorded, along with the
+    /// first block not listed. As many whole blocks as `limit` notes allow are listed.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_unconsumed_notes_by_tag(
+        &self,
+        tags: Vec<u32>,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+        limit: usize,
+    ) -> Result<(Vec<Note>, BlockNumber)> {
+        self.interruptible_query("Select unconsumed notes by tag", move |conn| {
+            sql::select_unconsumed_notes_by_tag(conn, &tags, from_block, to_block, limit)
+        })
+        .await
+    }
+
+    /// Loads the number of notes created from the block `from_block` with any tag, and with the tags
+    /// which the 16 high bits match `tags`.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_note_tag_stats(
+        &self,
+        tags: Vec<u32>,
+        from_block: BlockNumber,
+    ) -> Result<(u32, Vec<NoteTagStats>)> {
+        self.interruptible_query("Select note tag stats", move |conn| {
+            sql::select_note_tag_stats(conn, &tags, from_block)
+        })
+        .await
+    }
+
+    /// Loads the leaves of the note tree of the block `block_num` from the DB.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_notes_by_block_num(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<Vec<NoteCreated>> {
+        self.interruptible_query("Select notes by block", move |conn| {
+            sql::select_notes_by_block_num(conn, block_num)
+        })
+        .await
+    }
+
+    /// Loads the earliest note whose hash is `note_hash` from the DB.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_note_by_hash(
+        &self,
+        note_hash: RpoDigest,
+    ) -> Result<Option<Note>> {
+        self.interruptible_query("Select note by hash", move |conn| {
+            sql::select_note_by_hash(conn, note_hash)
+        })
+        .await
+    }
+
+    /// Inserts the recovery record of the `note`, unless the note already has one.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, err)]
+    pub async fn insert_note_recovery_record(
+        &self,
+        note: Note,
+        details: Vec<u8>,
+        merkle_path: MerklePath,
+    ) -> Result<()> {
+        self.pool
+            .get()
+            .await?
+            .interact(move |conn| -> Result<()> {
+                let transaction = conn.transaction()?;
+                sql::insert_note_recovery_record(&transaction, &note, &details, &merkle_path)?;
+                transaction.commit()?;
+
+                Ok(())
+            })
+            .await
+            .map_err(|err| {
+                DatabaseError::InteractError(format!(
+                    "Insert note recovery record task failed: {err}"
+                ))
+            })?
+    }
+
+    /// Loads the recovery records of the notes `note_hashes` from the DB.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_note_recovery_records(
+        &self,
+        note_hashes: Vec<RpoDigest>,
+    ) -> Result<Vec<NoteRecoveryRecord>> {
+        self.interruptible_query("Select note recovery records", move |conn| {
+            sql::select_note_recovery_records(conn, &note_hashes)
+        })
+        .await
+    }
+
+    /// Deletes the recovery records of the notes whose tag is within any of `tags`.
     ///
-    /// When `block_number` is [None], the latest block header is returned.
+    /// Returns the number of deleted records.
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
-    pub async fn select_block_header_by_block_num(
+    pub async fn delete_note_recovery_records_by_tag(
         &self,
-        block_number: Option<BlockNumber>,
-    ) -> Result<Option<block_header::BlockHeader>> {
+        tags: Vec<TagRange>,
+    ) -> Result<usize> {
         self.pool
             .get()
             .await?
-            .interact(move |conn| sql::select_block_header_by_block_num(conn, block_number))
+            .interact(move |conn| -> Result<usize> {
+                let transaction = conn.transaction()?;
+                let count = sql::delete_note_recovery_records_by_tag(&transaction, &tags)?;
+                transaction.commit()?;
+
+                Ok(count)
+            })
             .await
             .map_err(|err| {
-                DatabaseError::InteractError(format!("Select block header task failed: {err}"))
+                DatabaseError::InteractError(format!(
+                    "Delete note recovery records task failed: {err}"
+                ))
             })?
     }
 
-    /// Loads all the block headers from the DB.
+    /// Loads the note tree of the block `block_num` from the DB.
+    ///
+    /// Returns `None` if the block has no stored note tree.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_note_tree(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<Option<BlockNoteTree>> {
+        self.interruptible_query("Select note tree", move |conn| {
+            sql::select_note_tree(conn, block_num)
+        })
+        .await
+    }
+
+    /// Loads the numbers of up to `limit` blocks from `block_num` which have notes, but no stored
+    /// note tree.
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
-    pub async fn select_block_headers(&self) -> Result<Vec<block_header::BlockHeader>> {
+    pub async fn select_blocks_without_note_tree(
+        &self,
+        block_num: BlockNumber,
+        limit: usize,
+    ) -> Result<Vec<BlockNumber>> {
+        self.interruptible_query("Select blocks without note tree", move |conn| {
+            sql::select_blocks_without_note_tree(conn, block_num, limit)
+        })
+        .await
+    }
+
+    /// Loads the progress of all the backfills from the DB.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_backfills(&self) -> Result<Vec<BackfillProgress>> {
+        self.interruptible_query("Select backfills", sql::select_backfills).await
+    }
+
+    /// Inserts the note trees built by a batch of the note trees backfill, and records its
+    /// `progress`, in a single transaction.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, err)]
+    pub async fn insert_backfilled_note_trees(
+        &self,
+        note_trees: Vec<(BlockNumber, BlockNoteTree)>,
+        progress: BackfillProgress,
+    ) -> Result<()> {
         self.pool
             .get()
             .await?
-            .interact(sql::select_block_headers)
+            .interact(move |conn| -> Result<()> {
+                let transaction = conn.transaction()?;
+                for (block_num, note_tree) in &note_trees {
+                    sql::insert_note_tree(&transaction, *block_num, note_tree)?;
+                }
+                sql::update_backfill(&transaction, &progress)?;
+                transaction.commit()?;
+
+                Ok(())
+            })
             .await
             .map_err(|err| {
-                DatabaseError::InteractError(format!("Select block headers task failed: {err}"))
+                DatabaseError::InteractError(format!(
+                    "Insert backfilled note trees task failed: {err}"
+                ))
             })?
     }
 
-    /// Loads all the account hashes from the DB.
+    /// Rewrites the protobuf encoded digests of the blocks from `from_block` to `to_block`,
+    /// excluded, for a batch of the native digests backfill, and records its `progress`, in a
+    /// single transaction.
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
-    pub async fn select_account_hashes(&self) -> Result<Vec<(AccountId, Digest)>> {
+    pub async fn rewrite_protobuf_digests(
+        &self,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+        progress: BackfillProgress,
+    ) -> Result<usize> {
         self.pool
             .get()
             .await?
-            .interact(sql::select_account_hashes)
+            .interact(move |conn| -> Result<usize> {
+                let transaction = conn.transaction()?;
+                let count = sql::rewrite_protobuf_digests(&transaction, from_block, to_block)?;
+                sql::update_backfill(&transaction, &progress)?;
+                transaction.commit()?;
+
+                Ok(count)
+            })
             .await
             .map_err(|err| {
-                DatabaseError::InteractError(format!("Select account hashes task failed: {err}"))
+                DatabaseError::InteractError(format!("Rewrite protobuf digests task failed: {err}"))
             })?
     }
 
+    /// Loads the number of rows and the bytes used by every table of the DB.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_table_sizes(&self) -> Result<Vec<TableSize>> {
+        self.interruptible_query("Select table sizes", sql::select_table_sizes).await
+    }
+
+    /// Loads all the accounts from the DB.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_accounts(&self) -> Result<Vec<AccountInfo>> {
+        self.interruptible_query("Select accounts", sql::select_accounts).await
+    }
+
+    /// Search for a [block_header::BlockHeader] from the DB by its `block_num`.
+    ///
+    /// When `block_number` is [None], the latest block header is returned.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_block_header_by_block_num(
+        &self,
+        block_number: Option<BlockNumber>,
+    ) -> Result<Option<block_header::BlockHeader>> {
+        self.interruptible_query("Select block header", move |conn| {
+            sql::select_block_header_by_block_num(conn, block_number)
+        })
+        .await
+    }
+
+    /// Loads the latest hash of the account `account_id`, along with its serialized state if it is
+    /// public and known to the store. Returns `None` if the account doesn't exist.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, err)]
+    pub async fn select_account_details(
+        &self,
+        account_id: AccountId,
+    ) -> Result<Option<(Digest, Option<Vec<u8>>)>> {
+        self.interruptible_query("Select account details", move |conn| {
+            sql::select_account_details(conn, account_id)
+        })
+        .await
+    }
+
+    /// Loads the latest updates of the accounts of `account_ids` within the blocks
+    /// `(block_start, block_end]`, ordered by block number.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_account_updates(
+        &self,
+        block_start: BlockNumber,
+        block_end: BlockNumber,
+        account_ids: Vec<AccountId>,
+    ) -> Result<Vec<AccountHashUpdate>> {
+        self.interruptible_query("Select account updates", move |conn| {
+            sql::select_accounts_by_block_range(conn, block_start, block_end, &account_ids)
+        })
+        .await
+    }
+
+    /// Loads the commitment to the state at the block `block_num`, `None` if the block isn't known.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_state_commitment(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<Option<StateCommitment>> {
+        self.interruptible_query("Select state commitment", move |conn| {
+            let Some(block_header) = sql::select_block_header_by_block_num(conn, Some(block_num))?
+            else {
+                return Ok(None);
+            };
+            let block_header = BlockHeader::try_from(block_header)?;
+            // the rows of a block are inserted along with its header, and never change afterwards
+            let (num_notes, num_nullifiers, num_consumed_notes) =
+                sql::count_state_rows(conn, block_num)?;
+
+            Ok(Some(StateCommitment {
+                block_num,
+                block_hash: block_header.hash(),
+                chain_root: block_header.chain_root(),
+                account_root: block_header.account_root(),
+                nullifier_root: None,
+                num_notes,
+                num_nullifiers,
+                num_consumed_notes,
+            }))
+        })
+        .await
+    }
+
+    /// Loads the versions of the transaction kernels used in the block `block_num`.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_block_tx_kernel_versions(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<Vec<u32>> {
+        self.interruptible_query("Select block transaction kernel versions", move |conn| {
+            sql::select_block_tx_kernel_versions(conn, block_num)
+        })
+        .await
+    }
+
+    /// Loads the signature of the block `block_num` by its block producer.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_block_signature(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<Option<block_header::BlockSignature>> {
+        self.interruptible_query("Select block signature", move |conn| {
+            sql::select_block_signature(conn, block_num)
+        })
+        .await
+    }
+
+    /// Loads the attestation of the time the block `block_num` was produced.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_block_timestamp_attestation(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<Option<block_header::TimestampAttestation>> {
+        self.interruptible_query("Select block timestamp attestation", move |conn| {
+            sql::select_block_timestamp_attestation(conn, block_num)
+        })
+        .await
+    }
+
+    /// Loads whether the transactions of the block `block_num` were ordered canonically.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn select_block_canonical_tx_ordering(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<bool> {
+        self.interruptible_query("Select block canonical tx ordering", move |conn| {
+            sql::select_block_canonical_tx_ordering(conn, block_num)
+        })
+        .await
+    }
+
+    /// Loads up to `limit` block headers following the block `after`, ordered by block number,
+    /// from the DB.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, err)]
+    pub async fn select_block_headers_after(
+        &self,
+        after: Option<BlockNumber>,
+        limit: usize,
+    ) -> Result<Vec<block_header::BlockHeader>> {
+        self.interruptible_query("Select block headers", move |conn| {
+            sql::select_block_headers_after(conn, after, limit)
+        })
+        .await
+    }
+
+    /// Loads up to `limit` account hashes following the account `after`, ordered by account id,
+    /// from the DB.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, err)]
+    pub async fn select_account_hashes_after(
+        &self,
+        after: Option<AccountId>,
+        limit: usize,
+    ) -> Result<Vec<(AccountId, Digest)>> {
+        self.interruptible_query("Select account hashes", move |conn| {
+            sql::select_account_hashes_after(conn, after, limit)
+        })
+        .await
+    }
+
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
     pub async fn get_state_sync(
@@ -188,31 +706,27 @@ impl Db {
         account_ids: &[AccountId],
         note_tag_prefixes: &[u32],
         nullifier_prefixes: &[u32],
+        note_hashes: &[RpoDigest],
     ) -> Result<StateSyncUpdate, StateSyncError> {
         let account_ids = account_ids.to_vec();
         let note_tag_prefixes = note_tag_prefixes.to_vec();
         let nullifier_prefixes = nullifier_prefixes.to_vec();
+        let note_hashes = note_hashes.to_vec();
 
-        self.pool
-            .get()
-            .await
-            .map_err(DatabaseError::MissingDbConnection)?
-            .interact(move |conn| {
-                sql::get_state_sync(
-                    conn,
-                    block_num,
-                    &account_ids,
-                    &note_tag_prefixes,
-                    &nullifier_prefixes,
-                )
-            })
-            .await
-            .map_err(|err| {
-                DatabaseError::InteractError(format!("Get state sync task failed: {err}"))
-            })?
+        self.interruptible_query("Get state sync", move |conn| {
+            sql::get_state_sync(
+                conn,
+                block_num,
+                &account_ids,
+                &note_tag_prefixes,
+                &nullifier_prefixes,
+                &note_hashes,
+            )
+        })
+        .await
     }
 
-    /// Inserts the data of a new block into the DB.
+    /// Inserts the data of new blocks into the DB, in a single transaction.
     ///
     /// `allow_acquire` and `acquire_done` are used to synchronize writes to the DB with writes to
     /// the in-memory trees. Further details available on [super::state::State::apply_block].
@@ -220,15 +734,16 @@ impl Db {
     // Workaround of `instrument` issue
     // TODO: This span is logged in a root span, we should connect it to the parent one.
     #[instrument(target = "miden-store", skip_all, err)]
-    pub async fn apply_block(
+    pub async fn apply_blocks(
         &self,
         allow_acquire: oneshot::Sender<()>,
         acquire_done: oneshot::Receiver<()>,
-        block_header: block_header::BlockHeader,
-        notes: Vec<Note>,
-        nullifiers: Vec<RpoDigest>,
-        accounts: Vec<(AccountId, Digest)>,
+        blocks: Vec<BlockUpdate>,
     ) -> Result<()> {
+        #[cfg(any(test, feature = "testing"))]
+        let mut faults = self.take_faults();
+        let account_history = self.account_history.clone();
+
         self.pool
             .get()
             .await?
@@ -237,13 +752,60 @@ impl Db {
                 let _span = info_span!(target: COMPONENT, "write_block_to_db").entered();
 
                 let transaction = conn.transaction()?;
-                sql::apply_block(&transaction, &block_header, &notes, &nullifiers, &accounts)?;
+                for block in blocks.iter() {
+                    sql::apply_block(
+                        &transaction,
+                        &block.block_header,
+                        &block.notes,
+                        Some(&block.note_tree),
+                        &block.nullifiers,
+                        &block.accounts,
+                        &block.account_details,
+                        &block.consumed_notes,
+                        &block.tx_kernel_versions,
+                    )?;
+                    let account_ids: Vec<_> =
+                        block.accounts.iter().map(|(account_id, _)| *account_id).collect();
+                    sql::compact_account_updates(
+                        &transaction,
+                        &account_ids,
+                        account_history.keep_latest,
+                        account_history.checkpoint_interval,
+                    )?;
+                    if let Some(signature) = &block.signature {
+                        sql::insert_block_signature(
+                            &transaction,
+                            block.block_header.block_num,
+                            signature,
+                        )?;
+                    }
+                    if block.canonical_tx_ordering {
+                        sql::insert_canonically_ordered_block(
+                            &transaction,
+                            block.block_header.block_num,
+                        )?;
+                    }
+                    // the attestation carried by a block is the one of the previous block
+                    if let Some(attestation) = &block.timestamp_attestation {
+                        sql::insert_block_timestamp_attestation(
+                            &transaction,
+                            block.block_header.block_num - 1,
+                            attestation,
+                        )?;
+                    }
+                }
+
+                #[cfg(any(test, feature = "testing"))]
+                faults.reach(faults::WriteStep::BlocksWritten)?;
 
                 let _ = allow_acquire.send(());
                 acquire_done
                     .blocking_recv()
                     .map_err(DatabaseError::ApplyBlockFailedClosedChannel)?;
 
+                #[cfg(any(test, feature = "testing"))]
+                faults.reach(faults::WriteStep::LockAcquired)?;
+
                 transaction.commit()?;
 
                 Ok(())
@@ -259,29 +821,72 @@ impl Db {
     // HELPERS
     // ---------------------------------------------------------------------------------------------
 
+    /// Runs a read `query` on a pooled connection, interrupting it if the calling future is
+    /// dropped, e.g. because the gRPC client disconnected, or once the configured query timeout
+    /// elapses.
+    ///
+    /// Abandoned reads are stopped early so they don't hold connections needed by the writer.
+    async fn interruptible_query<F, R, E>(
+        &self,
+        name: &'static str,
+        query: F,
+    ) -> Result<R, E>
+    where
+        F: FnOnce(&mut Connection) -> Result<R, E> + Send + 'static,
+        R: Send + 'static,
+        E: From<DatabaseError> + Send + 'static,
+    {
+        let interrupt = QueryInterrupt::new(self.query_timeout);
+        let _cancel_on_drop = interrupt.cancel_on_drop();
+
+        self.pool
+            .get()
+            .await
+            .map_err(DatabaseError::MissingDbConnection)?
+            .interact(move |conn| run_interruptible(conn, name, &interrupt, query))
+            .await
+            .map_err(|err| DatabaseError::InteractError(format!("{name} task failed: {err}")))?
+    }
+
     /// If the database is empty, generates and stores the genesis block. Otherwise, it ensures that the
     /// genesis block in the database is consistent with the genesis block data in the genesis JSON
     /// file.
+    ///
+    /// The genesis file must define the same `chain_id` the store is configured with.
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(target = "miden-store", skip_all, err)]
     async fn ensure_genesis_block(
         &self,
-        genesis_filepath: &str,
+        genesis_filepath: &Path,
+        chain_id: u32,
     ) -> Result<(), GenesisError> {
-        let (expected_genesis_header, account_smt) = {
-            let file_contents = fs::read(genesis_filepath).map_err(|error| {
-                GenesisError::FailedToReadGenesisFile {
-                    genesis_filepath: genesis_filepath.to_string(),
-                    error,
-                }
-            })?;
+        let (expected_genesis_header, account_smt, account_details) = {
+            let genesis_state = GenesisState::read_from_file(genesis_filepath)?;
 
-            let genesis_state = GenesisState::read_from_bytes(&file_contents)
-                .map_err(GenesisError::GenesisFileDeserializationError)?;
+            match genesis_state.chain_id {
+                Some(genesis_chain_id) if genesis_chain_id != chain_id => {
+                    return Err(GenesisError::ChainIdMismatch {
+                        expected: chain_id,
+                        genesis_chain_id,
+                    });
+                },
+                Some(_) => (),
+                None => warn!(
+                    target: COMPONENT,
+                    "The genesis file predates the chain ids, it isn't bound to chain {chain_id}"
+                ),
+            }
+            // the states of the public accounts are published from the genesis block on
+            let account_details: Vec<_> = genesis_state
+                .accounts
+                .iter()
+                .filter(|account| account.id().is_on_chain())
+                .map(|account| (u64::from(account.id()), account.to_bytes()))
+                .collect();
             let (block_header, account_smt) =
                 genesis_state.into_block_parts().map_err(GenesisError::MalformedGenesisState)?;
 
-            (block_header.into(), account_smt)
+            (block_header.into(), account_smt, account_details)
         };
 
         let maybe_block_header_in_store = self
@@ -319,8 +924,12 @@ impl Db {
                             &transaction,
                             &expected_genesis_header,
                             &[],
+                            None,
                             &[],
                             &accounts,
+                            &account_details,
+                            &[],
+                            &[],
                         )?;
 
                         transaction.commit()?;
@@ -336,3 +945,115 @@ impl Db {
         Ok(())
     }
 }
+
+// INSPECTION
+// ================================================================================================
+
+/// State of a database on disk, see [inspect].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseInfo {
+    /// Number of migrations applied to the database, [SCHEMA_VERSION] once up to date.
+    pub schema_version: u32,
+    /// Header of the genesis block, missing if the store never started on this database.
+    pub genesis_header: Option<block_header::BlockHeader>,
+}
+
+/// Reads the state of the database at `database_filepath`, for diagnostics.
+///
+/// Unlike [Db::setup], the database is opened read-only: it is neither migrated nor locked, so it
+/// can be inspected while a store is running on it.
+pub fn inspect(database_filepath: &Path) -> Result<DatabaseInfo> {
+    let mut conn =
+        Connection::open_with_flags(database_filepath, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let schema_version = sql::select_schema_version(&mut conn)?;
+    let genesis_header = if schema_version == 0 {
+        None
+    } else {
+        sql::select_block_header_by_block_num(&mut conn, Some(GENESIS_BLOCK_NUM))?
+    };
+
+    Ok(DatabaseInfo {
+        schema_version,
+        genesis_header,
+    })
+}
+
+// QUERY INTERRUPTION
+// ================================================================================================
+
+/// Interruption state of a single query, shared between the task waiting for the query and the
+/// connection running it.
+#[derive(Clone)]
+struct QueryInterrupt {
+    cancelled: Arc<AtomicBool>,
+    deadline: Instant,
+    timeout: Duration,
+}
+
+impl QueryInterrupt {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            deadline: Instant::now() + timeout,
+            timeout,
+        }
+    }
+
+    /// Returns a guard which cancels the query when dropped.
+    fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(self.cancelled.clone())
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn is_timed_out(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+}
+
+/// Cancels a query once the future waiting for its result is dropped.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Runs `query` on `conn`, making SQLite abort it once `interrupt` is cancelled or timed out.
+fn run_interruptible<F, R, E>(
+    conn: &mut Connection,
+    name: &'static str,
+    interrupt: &QueryInterrupt,
+    query: F,
+) -> Result<R, E>
+where
+    F: FnOnce(&mut Connection) -> Result<R, E>,
+    E: From<DatabaseError>,
+{
+    // The caller is gone, don't bother starting the query
+    if interrupt.is_cancelled() {
+        return Err(DatabaseError::QueryCancelled(name).into());
+    }
+
+    let handler_interrupt = interrupt.clone();
+    conn.progress_handler(
+        QUERY_INTERRUPT_CHECK_PERIOD,
+        Some(move || handler_interrupt.is_cancelled() || handler_interrupt.is_timed_out()),
+    );
+    let result = query(conn);
+    conn.progress_handler(QUERY_INTERRUPT_CHECK_PERIOD, None::<fn() -> bool>);
+
+    match result {
+        Err(_) if interrupt.is_timed_out() => Err(DatabaseError::QueryTimeout {
+            query: name,
+            timeout: interrupt.timeout,
+        }
+        .into()),
+        Err(_) if interrupt.is_cancelled() => Err(DatabaseError::QueryCancelled(name).into()),
+        result => result,
+    }
+}
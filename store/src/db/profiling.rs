@@ -0,0 +1,114 @@
+//! Profiling of the SQL statements run on behalf of the requests.
+//!
+//! Statements are only measured when the `query-profiling` feature is enabled, otherwise
+//! [StatementProfile] is a no-op.
+#[cfg(feature = "query-profiling")]
+pub use enabled::*;
+use rusqlite::ToSql;
+
+/// Measures a statement from its execution until its results are read, i.e. until the profile is
+/// dropped.
+#[cfg(not(feature = "query-profiling"))]
+pub struct StatementProfile;
+
+#[cfg(not(feature = "query-profiling"))]
+impl StatementProfile {
+    #[inline(always)]
+    pub fn start(
+        _sql: &'static str,
+        _params: &[&dyn ToSql],
+    ) -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "query-profiling")]
+mod enabled {
+    use std::time::{Duration, Instant};
+
+    use rusqlite::types::{ToSqlOutput, ValueRef};
+    use tracing::{debug, warn};
+
+    use super::ToSql;
+    use crate::COMPONENT;
+
+    /// Statements running for longer than this are logged as slow.
+    pub const SLOW_STATEMENT_THRESHOLD: Duration = Duration::from_millis(50);
+
+    /// Measures a statement from its execution until its results are read, i.e. until the profile
+    /// is dropped.
+    ///
+    /// Only the shapes of the parameters are kept, e.g. `blob(32)` or `array(12)`, so the logs
+    /// show the size of the requests without leaking which notes or accounts they were about.
+    pub struct StatementProfile {
+        sql: &'static str,
+        parameters: String,
+        start: Instant,
+    }
+
+    impl StatementProfile {
+        pub fn start(
+            sql: &'static str,
+            params: &[&dyn ToSql],
+        ) -> Self {
+            Self {
+                sql,
+                parameters: parameter_shapes(params),
+                start: Instant::now(),
+            }
+        }
+    }
+
+    impl Drop for StatementProfile {
+        fn drop(&mut self) {
+            let elapsed = self.start.elapsed();
+            let elapsed_us = elapsed.as_micros() as u64;
+            let sql = compact_sql(self.sql);
+
+            if elapsed >= SLOW_STATEMENT_THRESHOLD {
+                warn!(target: COMPONENT, elapsed_us, sql = %sql, parameters = %self.parameters, "Slow SQL statement");
+            } else {
+                debug!(target: COMPONENT, elapsed_us, sql = %sql, parameters = %self.parameters, "SQL statement");
+            }
+        }
+    }
+
+    /// Describes the type and size of every bound parameter, e.g. `?1: integer, ?2: array(3)`.
+    pub fn parameter_shapes(params: &[&dyn ToSql]) -> String {
+        params
+            .iter()
+            .enumerate()
+            .map(|(index, param)| format!("?{}: {}", index + 1, parameter_shape(*param)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn parameter_shape(param: &dyn ToSql) -> String {
+        match param.to_sql() {
+            Ok(ToSqlOutput::Borrowed(value)) => value_shape(value),
+            Ok(ToSqlOutput::Owned(value)) => value_shape((&value).into()),
+            Ok(ToSqlOutput::Array(values)) => format!("array({})", values.len()),
+            Ok(_) => "unknown".to_string(),
+            Err(_) => "invalid".to_string(),
+        }
+    }
+
+    fn value_shape(value: ValueRef<'_>) -> String {
+        match value {
+            ValueRef::Null => "null".to_string(),
+            ValueRef::Integer(_) => "integer".to_string(),
+            ValueRef::Real(_) => "real".to_string(),
+            ValueRef::Text(text) => format!("text({})", text.len()),
+            ValueRef::Blob(blob) => format!("blob({})", blob.len()),
+        }
+    }
+
+    /// Puts the statement on a single line, removing its comments.
+    fn compact_sql(sql: &str) -> String {
+        sql.lines()
+            .filter(|line| !line.trim_start().starts_with("--"))
+            .flat_map(str::split_whitespace)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
@@ -1,29 +1,40 @@
 //! Wrapper functions for SQL statements.
-use std::rc::Rc;
+use std::{collections::BTreeMap, rc::Rc};
 
 use miden_crypto::{
     hash::rpo::RpoDigest,
-    utils::{Deserializable, SliceReader},
+    merkle::MerklePath,
+    utils::{ByteReader, Deserializable, DeserializationError, SliceReader},
 };
 use miden_node_proto::{
     account::{self, AccountId as AccountIdProto, AccountInfo},
-    block_header::BlockHeader,
+    block_header::{BlockHeader, BlockSignature, TimestampAttestation},
     digest::Digest,
-    merkle::MerklePath,
-    note::Note,
-    responses::{AccountHashUpdate, NullifierUpdate},
+    note::{Note, NoteCreated, NoteRecoveryRecord},
+    responses::{AccountHashUpdate, ConsumedNoteUpdate, NoteTagStats, NullifierUpdate, TableSize},
 };
+use miden_node_utils::tag_policy::TagRange;
 use prost::Message;
-use rusqlite::{params, types::Value, Connection, Transaction};
+use rusqlite::{params, params_from_iter, types::Value, Connection, Transaction};
 
-use super::{Result, StateSyncUpdate};
+use super::{profiling::StatementProfile, Result, StateSyncUpdate};
 use crate::{
+    backfill::BackfillProgress,
     errors::{ConversionError, DatabaseError, StateSyncError},
+    note_tree::BlockNoteTree,
     types::{AccountId, BlockNumber},
+    NOTE_TAG_STATS_WINDOW,
 };
 
+/// Maximum number of nullifiers inserted by a single statement, keeping the number of parameters
+/// under SQLite's default limit of 999.
+const NULLIFIERS_PER_INSERT: usize = 333;
+
 /// Insert nullifiers to the DB using the given [Transaction].
 ///
+/// The nullifiers are sorted and inserted with a single statement per [NULLIFIERS_PER_INSERT]
+/// nullifiers, so the index of the table is updated in order.
+///
 /// # Returns
 ///
 /// The number of affected rows.
@@ -39,17 +50,24 @@ pub fn insert_nullifiers_for_block(
 ) -> Result<usize> {
     use miden_crypto::StarkField;
 
-    let mut stmt = transaction.prepare(
-        "INSERT INTO nullifiers (nullifier, nullifier_prefix, block_number) VALUES (?1, ?2, ?3);",
-    )?;
+    let mut nullifiers = nullifiers.to_vec();
+    nullifiers.sort_unstable_by_key(|nullifier| nullifier.as_bytes());
 
     let mut count = 0;
-    for nullifier in nullifiers.iter() {
-        count += stmt.execute(params![
-            nullifier.as_bytes(),
-            u64_to_prefix(nullifier[0].as_int()),
-            block_num,
-        ])?
+    for chunk in nullifiers.chunks(NULLIFIERS_PER_INSERT) {
+        let values = vec!["(?, ?, ?)"; chunk.len()].join(", ");
+        let mut stmt = transaction.prepare_cached(&format!(
+            "INSERT INTO nullifiers (nullifier, nullifier_prefix, block_number) VALUES {values};"
+        ))?;
+
+        let params = chunk.iter().flat_map(|nullifier| {
+            [
+                Value::Blob(nullifier.as_bytes().to_vec()),
+                Value::Integer(u64_to_prefix(nullifier[0].as_int()).into()),
+                Value::Integer(block_num.into()),
+            ]
+        });
+        count += stmt.execute(params_from_iter(params))?;
     }
     Ok(count)
 }
@@ -60,9 +78,11 @@ pub fn insert_nullifiers_for_block(
 ///
 /// A vector with nullifiers and the block height at which they where created, or an error.
 pub fn select_nullifiers(conn: &mut Connection) -> Result<Vec<(RpoDigest, BlockNumber)>> {
-    let mut stmt =
-        conn.prepare("SELECT nullifier, block_number FROM nullifiers ORDER BY block_number ASC;")?;
-    let mut rows = stmt.query([])?;
+    let sql = "SELECT nullifier, block_number FROM nullifiers ORDER BY block_number ASC;";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
 
     let mut result = vec![];
     while let Some(row) = rows.next()? {
@@ -74,23 +94,278 @@ pub fn select_nullifiers(conn: &mut Connection) -> Result<Vec<(RpoDigest, BlockN
     Ok(result)
 }
 
+/// Select up to `limit` nullifiers following the nullifier `after`, by their encoding, from the DB
+/// using the given [Connection].
+///
+/// Reads the nullifiers in chunks, starting from the first one if `after` is `None`.
+pub fn select_nullifiers_after(
+    conn: &mut Connection,
+    after: Option<RpoDigest>,
+    limit: usize,
+) -> Result<Vec<(RpoDigest, BlockNumber)>> {
+    let sql = "
+        SELECT nullifier, block_number
+        FROM nullifiers
+        WHERE ?1 IS NULL OR nullifier > ?1
+        ORDER BY nullifier ASC
+        LIMIT ?2;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let after = after.map(|nullifier| nullifier.as_bytes().to_vec());
+    let params = params![after, limit];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let mut result = vec![];
+    while let Some(row) = rows.next()? {
+        let nullifier_data = row.get_ref(0)?.as_blob()?;
+        let nullifier = decode_rpo_digest(nullifier_data)?;
+        let block_number = row.get(1)?;
+        result.push((nullifier, block_number));
+    }
+    Ok(result)
+}
+
+/// Count the nullifiers, the accounts and the block headers the in-memory state is built from,
+/// using the given [Connection].
+pub fn count_startup_rows(conn: &mut Connection) -> Result<u64> {
+    let sql = "
+        SELECT
+            (SELECT COUNT(*) FROM nullifiers)
+            + (SELECT COUNT(*) FROM accounts)
+            + (SELECT COUNT(*) FROM block_headers);
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![];
+    let _profile = StatementProfile::start(sql, params);
+    let count = stmt.query_row(params, |row| row.get(0))?;
+    Ok(count)
+}
+
+/// Count the notes in the DB using the given [Connection].
+pub fn count_notes(conn: &mut Connection) -> Result<u64> {
+    let sql = "SELECT COUNT(*) FROM notes;";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![];
+    let _profile = StatementProfile::start(sql, params);
+    let count = stmt.query_row(params, |row| row.get(0))?;
+    Ok(count)
+}
+
+/// Count the notes, the nullifiers and the consumed notes, up to the block `block_num`, using the
+/// given [Connection].
+///
+/// The consumed notes are the notes created up to the block whose nullifier, linked to them, was
+/// produced up to the block. The links to notes which aren't in the chain are left out.
+pub fn count_state_rows(
+    conn: &mut Connection,
+    block_num: BlockNumber,
+) -> Result<(u64, u64, u64)> {
+    let sql = "
+        SELECT
+            (SELECT COUNT(*) FROM notes WHERE block_num <= ?1),
+            (SELECT COUNT(*) FROM nullifiers WHERE block_number <= ?1),
+            (
+                SELECT COUNT(*)
+                FROM note_nullifiers
+                JOIN nullifiers ON nullifiers.nullifier = note_nullifiers.nullifier
+                JOIN notes ON notes.note_hash = note_nullifiers.note_hash
+                WHERE nullifiers.block_number <= ?1 AND notes.block_num <= ?1
+            );
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![block_num];
+    let _profile = StatementProfile::start(sql, params);
+    let counts = stmt.query_row(params, |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    Ok(counts)
+}
+
+/// Select the version of the schema, the number of migrations applied to the DB, using the given
+/// [Connection].
+pub fn select_schema_version(conn: &mut Connection) -> Result<u32> {
+    let version = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    Ok(version)
+}
+
 /// Select all notes from the DB using the given [Connection].
 ///
+/// The merkle paths of the notes are not stored, they are derived from the block's note tree, see
+/// [select_note_tree].
 ///
 /// # Returns
 ///
-/// A vector with notes, or an error.
+/// A vector with notes without their merkle path, or an error.
 pub fn select_notes(conn: &mut Connection) -> Result<Vec<Note>> {
-    let mut stmt = conn.prepare("SELECT * FROM notes ORDER BY block_num ASC;")?;
-    let mut rows = stmt.query([])?;
+    let sql = "
+        SELECT
+            block_num,
+            note_index,
+            note_hash,
+            sender,
+            tag,
+            private
+        FROM
+            notes
+        ORDER BY
+            block_num ASC;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
 
     let mut notes = vec![];
     while let Some(row) = rows.next()? {
         let note_hash_data = row.get_ref(2)?.as_blob()?;
-        let note_hash = Digest::decode(note_hash_data)?;
+        let note_hash = decode_digest(note_hash_data)?;
+
+        notes.push(Note {
+            block_num: row.get(0)?,
+            note_index: row.get(1)?,
+            note_hash: Some(note_hash),
+            sender: column_value_as_u64(row, 3)?,
+            tag: column_value_as_u64(row, 4)?,
+            merkle_path: None,
+            private: row.get(5)?,
+        })
+    }
+    Ok(notes)
+}
+
+/// Select the notes created from the block `from_block` to the block `to_block` which the 16 high
+/// bits of the tag match `tags`, using the given [Connection].
+///
+/// # Returns
+///
+/// A vector with the notes without their merkle path, ordered by block number and note index, or an
+/// error.
+pub fn select_notes_by_tag_and_block_range(
+    conn: &mut Connection,
+    tags: &[u32],
+    from_block: BlockNumber,
+    to_block: BlockNumber,
+) -> Result<Vec<Note>> {
+    let tags: Vec<Value> = tags.iter().copied().map(u32_to_value).collect();
+
+    let sql = "
+        SELECT
+            block_num,
+            note_index,
+            note_hash,
+            sender,
+            tag,
+            private
+        FROM
+            notes
+        WHERE
+            (tag >> 48) IN rarray(?1) AND
+            block_num >= ?2 AND
+            block_num <= ?3
+        ORDER BY
+            block_num ASC,
+            note_index ASC;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![Rc::new(tags), from_block, to_block];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let mut notes = vec![];
+    while let Some(row) = rows.next()? {
+        let note_hash_data = row.get_ref(2)?.as_blob()?;
+        let note_hash = decode_digest(note_hash_data)?;
+
+        notes.push(Note {
+            block_num: row.get(0)?,
+            note_index: row.get(1)?,
+            note_hash: Some(note_hash),
+            sender: column_value_as_u64(row, 3)?,
+            tag: column_value_as_u64(row, 4)?,
+            merkle_path: None,
+            private: row.get(5)?,
+        })
+    }
+    Ok(notes)
+}
+
+/// Select the notes created from the block `from_block` to the block `to_block` which the 16 high
+/// bits of the tag match `tags`, and which are not linked to a nullifier, using the given
+/// [Connection].
+///
+/// At least one block is listed, and as many whole blocks as `limit` notes allow.
+///
+/// # Returns
+///
+/// A vector with the notes without their merkle path, ordered by block number and note index, and
+/// the number of the first block which wasn't listed, or an error.
+pub fn select_unconsumed_notes_by_tag(
+    conn: &mut Connection,
+    tags: &[u32],
+    from_block: BlockNumber,
+    to_block: BlockNumber,
+    limit: usize,
+) -> Result<(Vec<Note>, BlockNumber)> {
+    let tags = Rc::new(tags.iter().copied().map(u32_to_value).collect::<Vec<Value>>());
 
-        let merkle_path_data = row.get_ref(5)?.as_blob()?;
-        let merkle_path = MerklePath::decode(merkle_path_data)?;
+    // the listed blocks end before the block of the note past the limit
+    let sql = "
+        SELECT
+            block_num
+        FROM
+            notes
+        WHERE
+            (tag >> 48) IN rarray(?1) AND
+            block_num >= ?2 AND
+            block_num <= ?3 AND
+            NOT EXISTS (SELECT 1 FROM note_nullifiers WHERE note_hash = notes.note_hash)
+        ORDER BY
+            block_num ASC,
+            note_index ASC
+        LIMIT 1 OFFSET ?4;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![tags.clone(), from_block, to_block, limit];
+    let _profile = StatementProfile::start(sql, params);
+    let cutoff_block: Option<BlockNumber> = match stmt.query(params)?.next()? {
+        Some(row) => Some(row.get(0)?),
+        None => None,
+    };
+    let (to_block, next_block_num) = match cutoff_block {
+        Some(block_num) if block_num > from_block => (block_num - 1, block_num),
+        Some(_) => (from_block, from_block + 1),
+        None => (to_block, to_block + 1),
+    };
+
+    // the consumed notes are linked to their nullifier by hash, a note created before the digests
+    // were stored natively and consumed since is listed until the backfill rewrote its hash
+    let sql = "
+        SELECT
+            block_num,
+            note_index,
+            note_hash,
+            sender,
+            tag,
+            private
+        FROM
+            notes
+        WHERE
+            (tag >> 48) IN rarray(?1) AND
+            block_num >= ?2 AND
+            block_num <= ?3 AND
+            NOT EXISTS (SELECT 1 FROM note_nullifiers WHERE note_hash = notes.note_hash)
+        ORDER BY
+            block_num ASC,
+            note_index ASC;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![tags, from_block, to_block];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let mut notes = vec![];
+    while let Some(row) = rows.next()? {
+        let note_hash_data = row.get_ref(2)?.as_blob()?;
+        let note_hash = decode_digest(note_hash_data)?;
 
         notes.push(Note {
             block_num: row.get(0)?,
@@ -98,12 +373,262 @@ pub fn select_notes(conn: &mut Connection) -> Result<Vec<Note>> {
             note_hash: Some(note_hash),
             sender: column_value_as_u64(row, 3)?,
             tag: column_value_as_u64(row, 4)?,
-            merkle_path: Some(merkle_path),
+            merkle_path: None,
+            private: row.get(5)?,
+        })
+    }
+    Ok((notes, next_block_num))
+}
+
+/// Select the leaves of the note tree of the block `block_num` using the given [Connection].
+///
+/// Used to rebuild the note tree of the blocks applied before the trees were stored.
+///
+/// # Returns
+///
+/// A vector with the notes created in the block ordered by their index, or an error.
+pub fn select_notes_by_block_num(
+    conn: &mut Connection,
+    block_num: BlockNumber,
+) -> Result<Vec<NoteCreated>> {
+    let sql = "
+        SELECT
+            note_index,
+            note_hash,
+            sender,
+            tag,
+            private
+        FROM
+            notes
+        WHERE
+            block_num = ?1
+        ORDER BY
+            note_index ASC;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![block_num];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let mut notes = vec![];
+    while let Some(row) = rows.next()? {
+        let note_hash_data = row.get_ref(1)?.as_blob()?;
+        let note_hash = decode_digest(note_hash_data)?;
+
+        notes.push(NoteCreated {
+            note_index: row.get(0)?,
+            note_hash: Some(note_hash),
+            sender: column_value_as_u64(row, 2)?,
+            tag: column_value_as_u64(row, 3)?,
+            private: row.get(4)?,
         })
     }
     Ok(notes)
 }
 
+/// Select the note whose hash is `note_hash` using the given [Connection].
+///
+/// # Returns
+///
+/// The earliest note with the hash without its merkle path, or `None` if no block created it.
+pub fn select_note_by_hash(
+    conn: &mut Connection,
+    note_hash: RpoDigest,
+) -> Result<Option<Note>> {
+    let sql = "
+        SELECT
+            block_num,
+            note_index,
+            sender,
+            tag,
+            private
+        FROM
+            notes
+        WHERE
+            note_hash IN (?1, ?2)
+        ORDER BY
+            block_num ASC,
+            note_index ASC
+        LIMIT
+            1;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let [native, protobuf] = digest_lookup_values(&note_hash);
+    let params = params![native, protobuf];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let Some(row) = rows.next()? else {
+        return Ok(None);
+    };
+
+    Ok(Some(Note {
+        block_num: row.get(0)?,
+        note_index: row.get(1)?,
+        note_hash: Some(note_hash.into()),
+        sender: column_value_as_u64(row, 2)?,
+        tag: column_value_as_u64(row, 3)?,
+        merkle_path: None,
+        private: row.get(4)?,
+    }))
+}
+
+/// Insert the recovery record of the `note` to the DB using the given [Transaction].
+///
+/// A note whose record was already inserted keeps its first record.
+///
+/// # Returns
+///
+/// The number of affected rows.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn insert_note_recovery_record(
+    transaction: &Transaction,
+    note: &Note,
+    details: &[u8],
+    merkle_path: &MerklePath,
+) -> Result<usize> {
+    let mut stmt = transaction.prepare_cached(
+        "
+        INSERT OR IGNORE INTO
+        note_recovery_records
+        (
+            note_hash,
+            block_num,
+            note_index,
+            details,
+            merkle_path
+        )
+        VALUES
+        (
+            ?1, ?2, ?3, ?4, ?5
+        );",
+    )?;
+
+    let note_hash =
+        note.note_hash
+            .clone()
+            .ok_or(ConversionError::MissingFieldInProtobufRepresentation {
+                entity: "note",
+                field_name: "note_hash",
+            })?;
+    let merkle_path: Vec<u8> = merkle_path.iter().flat_map(|node| node.as_bytes()).collect();
+    let count = stmt.execute(params![
+        encode_protobuf_digest(&note_hash)?,
+        note.block_num,
+        note.note_index,
+        details,
+        merkle_path,
+    ])?;
+
+    Ok(count)
+}
+
+/// Select the recovery records of the notes `note_hashes` using the given [Connection].
+///
+/// # Returns
+///
+/// The records of the notes which have one, in no particular order.
+pub fn select_note_recovery_records(
+    conn: &mut Connection,
+    note_hashes: &[RpoDigest],
+) -> Result<Vec<NoteRecoveryRecord>> {
+    let note_hashes: Vec<Value> = note_hashes.iter().flat_map(digest_lookup_values).collect();
+
+    let sql = "
+        SELECT
+            note_hash,
+            block_num,
+            note_index,
+            details,
+            merkle_path
+        FROM
+            note_recovery_records
+        WHERE
+            note_hash IN rarray(?1);
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![Rc::new(note_hashes)];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let mut records = Vec::new();
+    while let Some(row) = rows.next()? {
+        let note_hash = decode_digest(row.get_ref(0)?.as_blob()?)?;
+        let merkle_path = row
+            .get_ref(4)?
+            .as_blob()?
+            .chunks(32)
+            .map(decode_rpo_digest)
+            .collect::<Result<Vec<_>>>()?;
+
+        records.push(NoteRecoveryRecord {
+            block_num: row.get(1)?,
+            note_index: row.get(2)?,
+            note_hash: Some(note_hash),
+            details: row.get(3)?,
+            merkle_path: Some(MerklePath::new(merkle_path).into()),
+        });
+    }
+    Ok(records)
+}
+
+/// Delete the recovery records of the notes whose tag is within any of `tags`, using the given
+/// [Transaction].
+///
+/// # Returns
+///
+/// The number of deleted records.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn delete_note_recovery_records_by_tag(
+    transaction: &Transaction,
+    tags: &[TagRange],
+) -> Result<usize> {
+    if tags.is_empty() {
+        return Ok(0);
+    }
+
+    // the tags are stored as signed integers, so the ranges are matched once read back
+    let mut stmt = transaction.prepare_cached(
+        "
+        SELECT
+            notes.block_num,
+            notes.note_index,
+            notes.tag
+        FROM
+            note_recovery_records
+        JOIN
+            notes ON
+                notes.block_num = note_recovery_records.block_num AND
+                notes.note_index = note_recovery_records.note_index;
+        ",
+    )?;
+    let mut rows = stmt.query([])?;
+    let mut notes: Vec<(BlockNumber, u32)> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let tag = column_value_as_u64(row, 2)?;
+        if tags.iter().any(|range| range.contains(tag)) {
+            notes.push((row.get(0)?, row.get(1)?));
+        }
+    }
+
+    let mut stmt = transaction.prepare_cached(
+        "DELETE FROM note_recovery_records WHERE block_num = ?1 AND note_index = ?2;",
+    )?;
+    let mut count = 0;
+    for (block_num, note_index) in notes {
+        count += stmt.execute(params![block_num, note_index])?;
+    }
+    Ok(count)
+}
+
 /// Select all accounts from the DB using the given [Connection].
 ///
 ///
@@ -111,13 +636,16 @@ pub fn select_notes(conn: &mut Connection) -> Result<Vec<Note>> {
 ///
 /// A vector with accounts, or an error.
 pub fn select_accounts(conn: &mut Connection) -> Result<Vec<AccountInfo>> {
-    let mut stmt = conn.prepare("SELECT * FROM accounts ORDER BY block_num ASC;")?;
-    let mut rows = stmt.query([])?;
+    let sql = "SELECT * FROM accounts ORDER BY block_num ASC;";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
 
     let mut accounts = vec![];
     while let Some(row) = rows.next()? {
         let account_hash_data = row.get_ref(1)?.as_blob()?;
-        let account_hash = Digest::decode(account_hash_data)?;
+        let account_hash = decode_digest(account_hash_data)?;
 
         let account_id_data = column_value_as_u64(row, 0)?;
         let account_id = AccountIdProto::from(account_id_data);
@@ -137,6 +665,9 @@ pub fn select_accounts(conn: &mut Connection) -> Result<Vec<AccountInfo>> {
 /// Each value of the `nullifier_prefixes` is only the 16 most significant bits of the nullifier of
 /// interest to the client. This hides the details of the specific nullifier being requested.
 ///
+/// The prefixes are looked up in the index of the nullifiers by prefix and block number, each one
+/// seeking the block range instead of scanning the table.
+///
 /// # Returns
 ///
 /// A vector of [NullifierUpdate] with the nullifiers and the block height at which they where
@@ -150,23 +681,24 @@ pub fn select_nullifiers_by_block_range(
     let nullifier_prefixes: Vec<Value> =
         nullifier_prefixes.iter().copied().map(u32_to_value).collect();
 
-    let mut stmt = conn.prepare(
-        "
+    let sql = "
         SELECT
             nullifier,
             block_number
         FROM
             nullifiers
         WHERE
+            nullifier_prefix IN rarray(?3) AND
             block_number > ?1 AND
-            block_number <= ?2 AND
-            nullifier_prefix IN rarray(?3)
+            block_number <= ?2
         ORDER BY
             block_number ASC
-    ",
-    )?;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
 
-    let mut rows = stmt.query(params![block_start, block_end, Rc::new(nullifier_prefixes)])?;
+    let params = params![block_start, block_end, Rc::new(nullifier_prefixes)];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
 
     let mut result = Vec::new();
     while let Some(row) = rows.next()? {
@@ -178,10 +710,253 @@ pub fn select_nullifiers_by_block_range(
             block_num,
         });
     }
-    Ok(result)
+    Ok(result)
+}
+
+/// Insert the links between consumed notes and their nullifiers to the DB using the given
+/// [Transaction].
+///
+/// Each entry of `consumed_notes` is a pair of note hash and nullifier, the nullifier must be
+/// inserted beforehand.
+///
+/// # Returns
+///
+/// The number of affected rows.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn insert_note_nullifiers(
+    transaction: &Transaction,
+    consumed_notes: &[(RpoDigest, RpoDigest)],
+) -> Result<usize> {
+    let mut stmt = transaction
+        .prepare_cached("INSERT INTO note_nullifiers (note_hash, nullifier) VALUES (?1, ?2);")?;
+
+    let mut count = 0;
+    for (note_hash, nullifier) in consumed_notes.iter() {
+        count += stmt.execute(params![encode_digest(note_hash), nullifier.as_bytes()])?
+    }
+    Ok(count)
+}
+
+/// Select the notes of `note_hashes` consumed within the given block range using the given
+/// [Connection].
+///
+/// Only the notes linked to their nullifier when the consuming block was applied are found.
+///
+/// # Returns
+///
+/// A vector of [ConsumedNoteUpdate] with the notes, their nullifiers, and the block height at
+/// which they were consumed, or an error.
+pub fn select_consumed_notes_by_block_range(
+    conn: &mut Connection,
+    block_start: BlockNumber,
+    block_end: BlockNumber,
+    note_hashes: &[RpoDigest],
+) -> Result<Vec<ConsumedNoteUpdate>> {
+    let note_hashes: Vec<Value> = note_hashes.iter().flat_map(digest_lookup_values).collect();
+
+    let sql = "
+        SELECT
+            note_nullifiers.note_hash,
+            note_nullifiers.nullifier,
+            nullifiers.block_number
+        FROM
+            note_nullifiers
+        JOIN
+            nullifiers ON note_nullifiers.nullifier = nullifiers.nullifier
+        WHERE
+            nullifiers.block_number > ?1 AND
+            nullifiers.block_number <= ?2 AND
+            note_nullifiers.note_hash IN rarray(?3)
+        ORDER BY
+            nullifiers.block_number ASC
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+
+    let params = params![block_start, block_end, Rc::new(note_hashes)];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let mut result = Vec::new();
+    while let Some(row) = rows.next()? {
+        let note_hash_data = row.get_ref(0)?.as_blob()?;
+        let note_hash = decode_digest(note_hash_data)?;
+        let nullifier_data = row.get_ref(1)?.as_blob()?;
+        let nullifier: Digest = decode_rpo_digest(nullifier_data)?.into();
+        let block_num = row.get(2)?;
+        result.push(ConsumedNoteUpdate {
+            note_hash: Some(note_hash),
+            nullifier: Some(nullifier),
+            block_num,
+        });
+    }
+    Ok(result)
+}
+
+/// Insert a [BlockHeader] to the DB using the given [Transaction].
+///
+/// # Returns
+///
+/// The number of affected rows.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn insert_block_header(
+    transaction: &Transaction,
+    block_header: &BlockHeader,
+) -> Result<usize> {
+    let mut stmt = transaction
+        .prepare_cached("INSERT INTO block_headers (block_num, block_header) VALUES (?1, ?2);")?;
+    Ok(stmt.execute(params![block_header.block_num, block_header.encode_to_vec()])?)
+}
+
+/// Insert the versions of the transaction kernels used in the block `block_num` to the DB using the
+/// given [Transaction].
+///
+/// # Returns
+///
+/// The number of affected rows.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn insert_block_tx_kernel_versions(
+    transaction: &Transaction,
+    block_num: BlockNumber,
+    tx_kernel_versions: &[u32],
+) -> Result<usize> {
+    let mut stmt = transaction.prepare_cached(
+        "INSERT OR IGNORE INTO block_tx_kernels (block_num, version) VALUES (?1, ?2);",
+    )?;
+
+    let mut count = 0;
+    for version in tx_kernel_versions {
+        count += stmt.execute(params![block_num, version])?;
+    }
+    Ok(count)
+}
+
+/// Select the versions of the transaction kernels used in the block `block_num` using the given
+/// [Connection].
+///
+/// # Returns
+///
+/// The versions in ascending order, empty for the blocks applied before they were recorded.
+pub fn select_block_tx_kernel_versions(
+    conn: &mut Connection,
+    block_num: BlockNumber,
+) -> Result<Vec<u32>> {
+    let sql = "SELECT version FROM block_tx_kernels WHERE block_num = ?1 ORDER BY version";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![block_num];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let mut result = Vec::new();
+    while let Some(row) = rows.next()? {
+        result.push(row.get(0)?);
+    }
+    Ok(result)
+}
+
+/// Insert the [BlockSignature] of the block `block_num` to the DB using the given [Transaction].
+///
+/// # Returns
+///
+/// The number of affected rows.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn insert_block_signature(
+    transaction: &Transaction,
+    block_num: BlockNumber,
+    signature: &BlockSignature,
+) -> Result<usize> {
+    let mut stmt = transaction
+        .prepare_cached("INSERT INTO block_signatures (block_num, signature) VALUES (?1, ?2);")?;
+    Ok(stmt.execute(params![block_num, signature.encode_to_vec()])?)
+}
+
+/// Select the [BlockSignature] of the block `block_num` using the given [Connection].
+///
+/// # Returns
+///
+/// The signature, `None` if the block was not signed.
+pub fn select_block_signature(
+    conn: &mut Connection,
+    block_num: BlockNumber,
+) -> Result<Option<BlockSignature>> {
+    let sql = "SELECT signature FROM block_signatures WHERE block_num = ?1";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![block_num];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    match rows.next()? {
+        Some(row) => {
+            let data = row.get_ref(0)?.as_blob()?;
+            Ok(Some(BlockSignature::decode(data)?))
+        },
+        None => Ok(None),
+    }
+}
+
+/// Insert the [TimestampAttestation] of the block `block_num` to the DB using the given
+/// [Transaction].
+///
+/// # Returns
+///
+/// The number of affected rows.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn insert_block_timestamp_attestation(
+    transaction: &Transaction,
+    block_num: BlockNumber,
+    attestation: &TimestampAttestation,
+) -> Result<usize> {
+    let mut stmt = transaction.prepare_cached(
+        "INSERT INTO block_timestamp_attestations (block_num, attestation) VALUES (?1, ?2);",
+    )?;
+    Ok(stmt.execute(params![block_num, attestation.encode_to_vec()])?)
+}
+
+/// Select the [TimestampAttestation] of the block `block_num` using the given [Connection].
+///
+/// # Returns
+///
+/// The attestation, `None` if the block was applied without one.
+pub fn select_block_timestamp_attestation(
+    conn: &mut Connection,
+    block_num: BlockNumber,
+) -> Result<Option<TimestampAttestation>> {
+    let sql = "SELECT attestation FROM block_timestamp_attestations WHERE block_num = ?1";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![block_num];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    match rows.next()? {
+        Some(row) => {
+            let data = row.get_ref(0)?.as_blob()?;
+            Ok(Some(TimestampAttestation::decode(data)?))
+        },
+        None => Ok(None),
+    }
 }
 
-/// Insert a [BlockHeader] to the DB using the given [Transaction].
+/// Records that the transactions of the block `block_num` were ordered canonically, using the
+/// given [Transaction].
 ///
 /// # Returns
 ///
@@ -191,13 +966,27 @@ pub fn select_nullifiers_by_block_range(
 ///
 /// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
 /// transaction.
-pub fn insert_block_header(
+pub fn insert_canonically_ordered_block(
     transaction: &Transaction,
-    block_header: &BlockHeader,
+    block_num: BlockNumber,
 ) -> Result<usize> {
     let mut stmt = transaction
-        .prepare("INSERT INTO block_headers (block_num, block_header) VALUES (?1, ?2);")?;
-    Ok(stmt.execute(params![block_header.block_num, block_header.encode_to_vec()])?)
+        .prepare_cached("INSERT INTO canonically_ordered_blocks (block_num) VALUES (?1);")?;
+    Ok(stmt.execute(params![block_num])?)
+}
+
+/// Select whether the transactions of the block `block_num` were ordered canonically using the
+/// given [Connection].
+pub fn select_block_canonical_tx_ordering(
+    conn: &mut Connection,
+    block_num: BlockNumber,
+) -> Result<bool> {
+    let sql = "SELECT EXISTS (SELECT 1 FROM canonically_ordered_blocks WHERE block_num = ?1)";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![block_num];
+    let _profile = StatementProfile::start(sql, params);
+    let canonical = stmt.query_row(params, |row| row.get(0))?;
+    Ok(canonical)
 }
 
 /// Select a [BlockHeader] from the DB by its `block_num` using the given [Connection].
@@ -211,16 +1000,21 @@ pub fn select_block_header_by_block_num(
     block_number: Option<BlockNumber>,
 ) -> Result<Option<BlockHeader>> {
     let mut stmt;
+    let _profile;
     let mut rows = match block_number {
         Some(block_number) => {
-            stmt = conn.prepare("SELECT block_header FROM block_headers WHERE block_num = ?1")?;
-            stmt.query([block_number])?
+            let sql = "SELECT block_header FROM block_headers WHERE block_num = ?1";
+            stmt = conn.prepare_cached(sql)?;
+            let params = params![block_number];
+            _profile = StatementProfile::start(sql, params);
+            stmt.query(params)?
         },
         None => {
-            stmt = conn.prepare(
-                "SELECT block_header FROM block_headers ORDER BY block_num DESC LIMIT 1",
-            )?;
-            stmt.query([])?
+            let sql = "SELECT block_header FROM block_headers ORDER BY block_num DESC LIMIT 1";
+            stmt = conn.prepare_cached(sql)?;
+            let params = params![];
+            _profile = StatementProfile::start(sql, params);
+            stmt.query(params)?
         },
     };
 
@@ -233,15 +1027,26 @@ pub fn select_block_header_by_block_num(
     }
 }
 
-/// Select all block headers from the DB using the given [Connection].
-///
-/// # Returns
+/// Select up to `limit` block headers following the block `after`, ordered by block number, from
+/// the DB using the given [Connection].
 ///
-/// A vector of [BlockHeader] or an error.
-pub fn select_block_headers(conn: &mut Connection) -> Result<Vec<BlockHeader>> {
-    let mut stmt =
-        conn.prepare("SELECT block_header FROM block_headers ORDER BY block_num ASC;")?;
-    let mut rows = stmt.query([])?;
+/// Reads the block headers in chunks, starting from the genesis block if `after` is `None`.
+pub fn select_block_headers_after(
+    conn: &mut Connection,
+    after: Option<BlockNumber>,
+    limit: usize,
+) -> Result<Vec<BlockHeader>> {
+    let sql = "
+        SELECT block_header
+        FROM block_headers
+        WHERE ?1 IS NULL OR block_num > ?1
+        ORDER BY block_num ASC
+        LIMIT ?2;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![after, limit];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
     let mut result = vec![];
     while let Some(row) = rows.next()? {
         let block_header_data = row.get_ref(0)?.as_blob()?;
@@ -266,7 +1071,7 @@ pub fn insert_notes(
     transaction: &Transaction,
     notes: &[Note],
 ) -> Result<usize> {
-    let mut stmt = transaction.prepare(
+    let mut stmt = transaction.prepare_cached(
         "
         INSERT INTO
         notes
@@ -276,11 +1081,11 @@ pub fn insert_notes(
             note_hash,
             sender,
             tag,
-            merkle_path
+            private
         )
         VALUES
         (
-            ?1, ?2, ?3, ?4, ?5, ?6 
+            ?1, ?2, ?3, ?4, ?5, ?6
         );",
     )?;
 
@@ -289,35 +1094,375 @@ pub fn insert_notes(
         count += stmt.execute(params![
             note.block_num,
             note.note_index,
-            note.note_hash
-                .clone()
-                .ok_or(ConversionError::MissingFieldInProtobufRepresentation {
+            encode_protobuf_digest(note.note_hash.as_ref().ok_or(
+                ConversionError::MissingFieldInProtobufRepresentation {
                     entity: "note",
                     field_name: "note_hash"
-                })?
-                .encode_to_vec(),
+                }
+            )?)?,
             u64_to_value(note.sender),
             u64_to_value(note.tag),
-            note.merkle_path
-                .clone()
-                .ok_or(ConversionError::MissingFieldInProtobufRepresentation {
-                    entity: "note",
-                    field_name: "merkle_path"
-                })?
-                .encode_to_vec(),
+            note.private,
         ])?;
     }
 
     Ok(count)
 }
 
+/// Insert the number of notes created with every tag by the block `block_num` to the DB using the
+/// given [Transaction], and drop the counts of the blocks which left the window of
+/// [NOTE_TAG_STATS_WINDOW] blocks.
+///
+/// # Returns
+///
+/// The number of affected rows.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn insert_note_tag_stats(
+    transaction: &Transaction,
+    block_num: BlockNumber,
+    notes: &[Note],
+) -> Result<usize> {
+    let mut num_notes: BTreeMap<u32, u32> = BTreeMap::new();
+    for note in notes {
+        *num_notes.entry(u64_to_prefix(note.tag)).or_default() += 1;
+    }
+
+    let mut stmt = transaction.prepare_cached(
+        "INSERT INTO note_tag_stats (block_num, tag_prefix, num_notes) VALUES (?1, ?2, ?3);",
+    )?;
+    let mut count = 0;
+    for (tag_prefix, num_notes) in num_notes {
+        count += stmt.execute(params![block_num, tag_prefix, num_notes])?;
+    }
+
+    let mut stmt =
+        transaction.prepare_cached("DELETE FROM note_tag_stats WHERE block_num < ?1;")?;
+    count += stmt.execute(params![(block_num + 1).saturating_sub(NOTE_TAG_STATS_WINDOW)])?;
+
+    Ok(count)
+}
+
+/// Select the number of notes created from the block `from_block` with any tag, and with every tag
+/// of `tags`, using the given [Connection].
+///
+/// # Returns
+///
+/// The total number of notes, and the stats of the tags of `tags` with at least one note, ordered
+/// by tag.
+pub fn select_note_tag_stats(
+    conn: &mut Connection,
+    tags: &[u32],
+    from_block: BlockNumber,
+) -> Result<(u32, Vec<NoteTagStats>)> {
+    let sql = "SELECT COALESCE(SUM(num_notes), 0) FROM note_tag_stats WHERE block_num >= ?1;";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![from_block];
+    let _profile = StatementProfile::start(sql, params);
+    let total_notes = stmt.query_row(params, |row| row.get(0))?;
+
+    let tags: Vec<Value> = tags.iter().copied().map(u32_to_value).collect();
+    let sql = "
+        SELECT
+            tag_prefix,
+            SUM(num_notes),
+            COUNT(*)
+        FROM
+            note_tag_stats
+        WHERE
+            tag_prefix IN rarray(?1) AND
+            block_num >= ?2
+        GROUP BY
+            tag_prefix
+        ORDER BY
+            tag_prefix ASC;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![Rc::new(tags), from_block];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let mut stats = Vec::new();
+    while let Some(row) = rows.next()? {
+        stats.push(NoteTagStats {
+            tag: row.get(0)?,
+            num_notes: row.get(1)?,
+            num_blocks: row.get(2)?,
+        });
+    }
+
+    Ok((total_notes, stats))
+}
+
+/// Insert the note tree of the block `block_num` to the DB using the given [Transaction].
+///
+/// # Returns
+///
+/// The number of affected rows.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn insert_note_tree(
+    transaction: &Transaction,
+    block_num: BlockNumber,
+    note_tree: &BlockNoteTree,
+) -> Result<usize> {
+    let mut stmt = transaction.prepare_cached(
+        "INSERT INTO note_trees (block_num, batches, nodes) VALUES (?1, ?2, ?3);",
+    )?;
+
+    // every batch is stored as its index and number of leaves, both fitting in a u32
+    let batches: Vec<u8> = note_tree
+        .batches()
+        .flat_map(|(batch_idx, num_leaves)| {
+            [(batch_idx as u32).to_be_bytes(), (num_leaves as u32).to_be_bytes()]
+        })
+        .flatten()
+        .collect();
+    let nodes: Vec<u8> = note_tree.nodes().flat_map(|node| node.as_bytes()).collect();
+    let count = stmt.execute(params![block_num, batches, nodes])?;
+
+    Ok(count)
+}
+
+/// Select the note tree of the block `block_num` using the given [Connection].
+///
+/// # Returns
+///
+/// The note tree of the block, or `None` if the block has no stored tree, i.e. the block doesn't
+/// exist, or it was applied before the note trees were stored.
+pub fn select_note_tree(
+    conn: &mut Connection,
+    block_num: BlockNumber,
+) -> Result<Option<BlockNoteTree>> {
+    let sql = "SELECT batches, nodes FROM note_trees WHERE block_num = ?1;";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![block_num];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let Some(row) = rows.next()? else {
+        return Ok(None);
+    };
+
+    let batches: Vec<(u64, u64)> = row
+        .get_ref(0)?
+        .as_blob()?
+        .chunks_exact(8)
+        .map(|batch| {
+            let (batch_idx, num_leaves) = batch.split_at(4);
+            let batch_idx = u32::from_be_bytes(batch_idx.try_into().expect("chunk of 4 bytes"));
+            let num_leaves = u32::from_be_bytes(num_leaves.try_into().expect("chunk of 4 bytes"));
+            (batch_idx as u64, num_leaves as u64)
+        })
+        .collect();
+    let nodes = row
+        .get_ref(1)?
+        .as_blob()?
+        .chunks(32)
+        .map(decode_rpo_digest)
+        .collect::<Result<Vec<_>>>()?;
+
+    BlockNoteTree::from_parts(&batches, nodes)
+        .map(Some)
+        .ok_or(DatabaseError::MalformedNoteTree(block_num))
+}
+
+/// Select the numbers of up to `limit` blocks from `block_num` which have notes, but no stored note
+/// tree, using the given [Connection].
+///
+/// # Returns
+///
+/// The block numbers in ascending order.
+pub fn select_blocks_without_note_tree(
+    conn: &mut Connection,
+    block_num: BlockNumber,
+    limit: usize,
+) -> Result<Vec<BlockNumber>> {
+    let sql = "
+        SELECT DISTINCT
+            block_num
+        FROM
+            notes
+        WHERE
+            block_num >= ?1 AND
+            block_num NOT IN (SELECT block_num FROM note_trees)
+        ORDER BY
+            block_num ASC
+        LIMIT
+            ?2;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![block_num, limit];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let mut result = vec![];
+    while let Some(row) = rows.next()? {
+        result.push(row.get(0)?);
+    }
+    Ok(result)
+}
+
+/// Select the progress of all the backfills using the given [Connection].
+pub fn select_backfills(conn: &mut Connection) -> Result<Vec<BackfillProgress>> {
+    let sql = "SELECT name, next_block_num, completed FROM backfills ORDER BY name ASC;";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    let mut result = vec![];
+    while let Some(row) = rows.next()? {
+        result.push(BackfillProgress {
+            name: row.get(0)?,
+            next_block_num: row.get(1)?,
+            completed: row.get(2)?,
+        });
+    }
+    Ok(result)
+}
+
+/// Update the progress of a backfill using the given [Transaction].
+///
+/// # Returns
+///
+/// The number of affected rows.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn update_backfill(
+    transaction: &Transaction,
+    progress: &BackfillProgress,
+) -> Result<usize> {
+    let mut stmt = transaction.prepare_cached(
+        "UPDATE backfills SET next_block_num = ?2, completed = ?3 WHERE name = ?1;",
+    )?;
+    Ok(stmt.execute(params![progress.name, progress.next_block_num, progress.completed])?)
+}
+
+/// Rewrite the protobuf encoded digests of the blocks from `from_block` to `to_block`, excluded,
+/// in miden's native serialization using the given [Transaction].
+///
+/// A note recovery record inserted again since the migration, which is stored twice, keeps its
+/// first record.
+///
+/// # Returns
+///
+/// The number of affected rows.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn rewrite_protobuf_digests(
+    transaction: &Transaction,
+    from_block: BlockNumber,
+    to_block: BlockNumber,
+) -> Result<usize> {
+    // every query selects the key of the rows to rewrite and their digest
+    let rewrites = [
+        (
+            "
+            SELECT
+                note_hash, note_hash
+            FROM
+                notes
+            WHERE
+                block_num >= ?1 AND
+                block_num < ?2 AND
+                length(note_hash) != 32;
+            ",
+            "UPDATE notes SET note_hash = ?2 WHERE note_hash = ?1;",
+        ),
+        (
+            "
+            SELECT
+                note_hash, note_hash
+            FROM
+                note_recovery_records
+            WHERE
+                block_num >= ?1 AND
+                block_num < ?2 AND
+                length(note_hash) != 32;
+            ",
+            "UPDATE OR REPLACE note_recovery_records SET note_hash = ?2 WHERE note_hash = ?1;",
+        ),
+        (
+            "
+            SELECT
+                note_nullifiers.note_hash, note_nullifiers.note_hash
+            FROM
+                note_nullifiers
+            JOIN
+                nullifiers ON note_nullifiers.nullifier = nullifiers.nullifier
+            WHERE
+                nullifiers.block_number >= ?1 AND
+                nullifiers.block_number < ?2 AND
+                length(note_nullifiers.note_hash) != 32;
+            ",
+            "UPDATE note_nullifiers SET note_hash = ?2 WHERE note_hash = ?1;",
+        ),
+        (
+            "
+            SELECT
+                account_id, account_hash
+            FROM
+                accounts
+            WHERE
+                block_num >= ?1 AND
+                block_num < ?2 AND
+                length(account_hash) != 32;
+            ",
+            "UPDATE accounts SET account_hash = ?2 WHERE account_id = ?1;",
+        ),
+        (
+            "
+            SELECT
+                account_hash, account_hash
+            FROM
+                account_updates
+            WHERE
+                block_num >= ?1 AND
+                block_num < ?2 AND
+                length(account_hash) != 32;
+            ",
+            "UPDATE account_updates SET account_hash = ?2 WHERE account_hash = ?1;",
+        ),
+    ];
+
+    let mut count = 0;
+    for (select_sql, update_sql) in rewrites {
+        let mut stmt = transaction.prepare_cached(select_sql)?;
+        let params = params![from_block, to_block];
+        let _profile = StatementProfile::start(select_sql, params);
+        let rows = stmt
+            .query_map(params, |row| Ok((row.get::<_, Value>(0)?, row.get::<_, Vec<u8>>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut stmt = transaction.prepare_cached(update_sql)?;
+        for (key, digest) in rows {
+            count +=
+                stmt.execute(params![key, encode_protobuf_digest(&Digest::decode(&*digest)?)?])?;
+        }
+    }
+    Ok(count)
+}
+
 /// Select notes matching the tag and account_ids search criteria using the given [Connection].
 ///
 /// # Returns
 ///
 /// - Empty vector if no tag created after `block_num` match `tags` or `account_ids`.
 /// - Otherwise, notes which the 16 high bits match `tags`, or the `sender` is one of the
-///   `account_ids`.
+///   `account_ids`, without their merkle path.
 ///
 /// # Note
 ///
@@ -332,15 +1477,14 @@ pub fn select_notes_since_block_by_tag_and_sender(
     let tags: Vec<Value> = tags.iter().copied().map(u32_to_value).collect();
     let account_ids: Vec<Value> = account_ids.iter().copied().map(u64_to_value).collect();
 
-    let mut stmt = conn.prepare(
-        "
+    let sql = "
         SELECT
             block_num,
             note_index,
             note_hash,
             sender,
             tag,
-            merkle_path
+            private
         FROM
             notes
         WHERE
@@ -360,20 +1504,21 @@ pub fn select_notes_since_block_by_tag_and_sender(
             ) AND
             -- filter the block's notes and return only the ones matching the requested tags
             ((tag >> 48) IN rarray(?1) OR sender IN rarray(?2));
-    ",
-    )?;
-    let mut rows = stmt.query(params![Rc::new(tags), Rc::new(account_ids), block_num])?;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![Rc::new(tags), Rc::new(account_ids), block_num];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
 
     let mut res = Vec::new();
     while let Some(row) = rows.next()? {
         let block_num = row.get(0)?;
         let note_index = row.get(1)?;
         let note_hash_data = row.get_ref(2)?.as_blob()?;
-        let note_hash = Some(decode_protobuf_digest(note_hash_data)?);
+        let note_hash = Some(decode_digest(note_hash_data)?);
         let sender = column_value_as_u64(row, 3)?;
         let tag = column_value_as_u64(row, 4)?;
-        let merkle_path_data = row.get_ref(5)?.as_blob()?;
-        let merkle_path = Some(MerklePath::decode(merkle_path_data)?);
+        let private = row.get(5)?;
 
         let note = Note {
             block_num,
@@ -381,18 +1526,20 @@ pub fn select_notes_since_block_by_tag_and_sender(
             note_hash,
             sender,
             tag,
-            merkle_path,
+            merkle_path: None,
+            private,
         };
         res.push(note);
     }
     Ok(res)
 }
 
-/// Inserts or updates accounts to the DB using the given [Transaction].
+/// Inserts or updates accounts to the DB using the given [Transaction], and appends the updates to
+/// the history of the accounts.
 ///
 /// # Returns
 ///
-/// The number of affected rows.
+/// The number of updated accounts.
 ///
 /// # Note
 ///
@@ -403,15 +1550,114 @@ pub fn upsert_accounts_with_blocknum(
     accounts: &[(AccountId, Digest)],
     block_num: BlockNumber,
 ) -> Result<usize> {
-    let mut stmt = transaction.prepare("INSERT OR REPLACE INTO accounts (account_id, account_hash, block_num) VALUES (?1, ?2, ?3);")?;
+    let mut stmt = transaction.prepare_cached("INSERT OR REPLACE INTO accounts (account_id, account_hash, block_num) VALUES (?1, ?2, ?3);")?;
+    let mut history_stmt = transaction.prepare_cached("INSERT OR REPLACE INTO account_updates (account_id, block_num, account_hash) VALUES (?1, ?2, ?3);")?;
 
     let mut count = 0;
     for (account_id, account_hash) in accounts.iter() {
-        count += stmt.execute(params![
-            u64_to_value(*account_id),
-            account_hash.encode_to_vec(),
-            block_num
-        ])?
+        let account_hash = encode_protobuf_digest(account_hash)?;
+        history_stmt.execute(params![u64_to_value(*account_id), block_num, account_hash])?;
+        count += stmt.execute(params![u64_to_value(*account_id), account_hash, block_num])?
+    }
+    Ok(count)
+}
+
+/// Sets the serialized states of the public `accounts` to the DB using the given [Transaction].
+///
+/// The accounts must have been upserted first, see [upsert_accounts_with_blocknum], which clears
+/// the states of the updated accounts.
+///
+/// # Returns
+///
+/// The number of updated accounts.
+pub fn update_account_details(
+    transaction: &Transaction,
+    accounts: &[(AccountId, Vec<u8>)],
+) -> Result<usize> {
+    let mut stmt =
+        transaction.prepare_cached("UPDATE accounts SET details = ?2 WHERE account_id = ?1;")?;
+
+    let mut count = 0;
+    for (account_id, details) in accounts.iter() {
+        count += stmt.execute(params![u64_to_value(*account_id), details])?;
+    }
+    Ok(count)
+}
+
+/// Selects the latest hash of the account `account_id`, along with its serialized state if it is
+/// known, using the given [Connection].
+///
+/// # Returns
+///
+/// `None` if the account doesn't exist.
+pub fn select_account_details(
+    conn: &mut Connection,
+    account_id: AccountId,
+) -> Result<Option<(Digest, Option<Vec<u8>>)>> {
+    let sql = "SELECT account_hash, details FROM accounts WHERE account_id = ?1;";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![u64_to_value(account_id)];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
+
+    match rows.next()? {
+        Some(row) => {
+            let account_hash = decode_digest(row.get_ref(0)?.as_blob()?)?;
+            Ok(Some((account_hash, row.get(1)?)))
+        },
+        None => Ok(None),
+    }
+}
+
+/// Compacts the history of the `accounts` using the given [Transaction], keeping the latest
+/// `keep_latest` updates of every account, and the last update of every window of
+/// `checkpoint_interval` blocks as checkpoint. A `keep_latest` of `0` keeps the full history, a
+/// `checkpoint_interval` of `0` keeps no checkpoint.
+///
+/// # Returns
+///
+/// The number of deleted updates.
+///
+/// # Note
+///
+/// The [Transaction] object is not consumed. It's up to the caller to commit or rollback the
+/// transaction.
+pub fn compact_account_updates(
+    transaction: &Transaction,
+    accounts: &[AccountId],
+    keep_latest: u32,
+    checkpoint_interval: u32,
+) -> Result<usize> {
+    if keep_latest == 0 {
+        return Ok(0);
+    }
+
+    // the windows of a `checkpoint_interval` of `0` are all the same, as SQLite divides by zero
+    // into NULL, so its checkpoint is the latest update which is kept anyway
+    let sql = "
+        DELETE FROM
+            account_updates
+        WHERE
+            account_id = ?1 AND
+            block_num NOT IN (
+                SELECT block_num FROM account_updates
+                WHERE account_id = ?1
+                ORDER BY block_num DESC
+                LIMIT ?2
+            ) AND
+            block_num NOT IN (
+                SELECT MAX(block_num) FROM account_updates
+                WHERE account_id = ?1
+                GROUP BY block_num / ?3
+            );
+    ";
+    let mut stmt = transaction.prepare_cached(sql)?;
+
+    let mut count = 0;
+    for account_id in accounts {
+        let params = params![u64_to_value(*account_id), keep_latest, checkpoint_interval];
+        let _profile = StatementProfile::start(sql, params);
+        count += stmt.execute(params)?;
     }
     Ok(count)
 }
@@ -421,7 +1667,11 @@ pub fn upsert_accounts_with_blocknum(
 ///
 /// # Returns
 ///
-/// The vector of [AccountHashUpdate] with the matching accounts.
+/// The vector of [AccountHashUpdate] with the latest update of the matching accounts within the
+/// range. Once the history of an account is compacted, see [compact_account_updates], the latest
+/// update kept within the range is returned, which is not necessarily the latest state of the
+/// account at `block_end`, and the accounts whose updates within the range were all compacted
+/// away are skipped.
 pub fn select_accounts_by_block_range(
     conn: &mut Connection,
     block_start: BlockNumber,
@@ -430,29 +1680,32 @@ pub fn select_accounts_by_block_range(
 ) -> Result<Vec<AccountHashUpdate>> {
     let account_ids: Vec<Value> = account_ids.iter().copied().map(u64_to_value).collect();
 
-    let mut stmt = conn.prepare(
-        "
+    let sql = "
         SELECT
-            account_id, account_hash, block_num
+            account_id, account_hash, MAX(block_num)
         FROM
-            accounts
+            account_updates
         WHERE
             block_num > ?1 AND
             block_num <= ?2 AND
             account_id IN rarray(?3)
+        GROUP BY
+            account_id
         ORDER BY
-            block_num ASC
-    ",
-    )?;
+            MAX(block_num) ASC
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
 
-    let mut rows = stmt.query(params![block_start, block_end, Rc::new(account_ids)])?;
+    let params = params![block_start, block_end, Rc::new(account_ids)];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
 
     let mut result = Vec::new();
     while let Some(row) = rows.next()? {
         let account_id_data = column_value_as_u64(row, 0)?;
         let account_id: account::AccountId = account_id_data.into();
         let account_hash_data = row.get_ref(1)?.as_blob()?;
-        let account_hash = Digest::decode(account_hash_data)?;
+        let account_hash = decode_digest(account_hash_data)?;
         let block_num = row.get(2)?;
 
         result.push(AccountHashUpdate {
@@ -465,21 +1718,32 @@ pub fn select_accounts_by_block_range(
     Ok(result)
 }
 
-/// Select all account hashes from the DB using the given [Connection].
-///
-/// # Returns
+/// Select up to `limit` account hashes following the account `after`, ordered by account id, from
+/// the DB using the given [Connection].
 ///
-/// The vector with the account id and corresponding hash, or an error.
-pub fn select_account_hashes(conn: &mut Connection) -> Result<Vec<(AccountId, Digest)>> {
-    let mut stmt =
-        conn.prepare("SELECT account_id, account_hash FROM accounts ORDER BY block_num ASC;")?;
-    let mut rows = stmt.query([])?;
+/// Reads the account hashes in chunks, starting from the first account if `after` is `None`.
+pub fn select_account_hashes_after(
+    conn: &mut Connection,
+    after: Option<AccountId>,
+    limit: usize,
+) -> Result<Vec<(AccountId, Digest)>> {
+    let sql = "
+        SELECT account_id, account_hash
+        FROM accounts
+        WHERE ?1 IS NULL OR account_id > ?1
+        ORDER BY account_id ASC
+        LIMIT ?2;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![after.map(u64_to_value), limit];
+    let _profile = StatementProfile::start(sql, params);
+    let mut rows = stmt.query(params)?;
 
     let mut result = Vec::new();
     while let Some(row) = rows.next()? {
         let account_id = column_value_as_u64(row, 0)?;
         let account_hash_data = row.get_ref(1)?.as_blob()?;
-        let account_hash = Digest::decode(account_hash_data)?;
+        let account_hash = decode_digest(account_hash_data)?;
 
         result.push((account_id, account_hash));
     }
@@ -494,6 +1758,7 @@ pub fn get_state_sync(
     account_ids: &[AccountId],
     note_tag_prefixes: &[u32],
     nullifier_prefixes: &[u32],
+    note_hashes: &[RpoDigest],
 ) -> Result<StateSyncUpdate, StateSyncError> {
     let notes = select_notes_since_block_by_tag_and_sender(
         conn,
@@ -527,15 +1792,101 @@ pub fn get_state_sync(
         nullifier_prefixes,
     )?;
 
+    let consumed_notes =
+        select_consumed_notes_by_block_range(conn, block_num, block_header.block_num, note_hashes)?;
+
     Ok(StateSyncUpdate {
         notes,
         block_header,
         chain_tip,
         account_updates,
         nullifiers,
+        consumed_notes,
     })
 }
 
+/// Select the number of rows of every table of the DB, and the bytes used by the table and its
+/// indexes, using the given [Connection].
+///
+/// The rows are counted by scanning the tables. The bytes are read from SQLite's `dbstat` virtual
+/// table, and are `None` if SQLite was built without it.
+///
+/// # Returns
+///
+/// The sizes of the tables ordered by name, or an error.
+pub fn select_table_sizes(conn: &mut Connection) -> Result<Vec<TableSize>> {
+    let sql = "
+        SELECT
+            name
+        FROM
+            sqlite_schema
+        WHERE
+            type = 'table' AND
+            name NOT LIKE 'sqlite_%'
+        ORDER BY
+            name ASC;
+    ";
+    let mut stmt = conn.prepare_cached(sql)?;
+    let params = params![];
+    let _profile = StatementProfile::start(sql, params);
+    let names = stmt
+        .query_map(params, |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let num_bytes = select_table_bytes(conn)?;
+
+    let mut tables = Vec::with_capacity(names.len());
+    for name in names {
+        // the names come from the schema, they are quoted in case one needs it
+        let sql = format!("SELECT COUNT(*) FROM \"{}\";", name.replace('"', "\"\""));
+        let num_rows = conn.query_row(&sql, [], |row| column_value_as_u64(row, 0))?;
+
+        tables.push(TableSize {
+            num_bytes: num_bytes
+                .as_ref()
+                .map(|num_bytes| num_bytes.get(&name).copied().unwrap_or(0)),
+            name,
+            num_rows,
+        });
+    }
+
+    Ok(tables)
+}
+
+/// Select the bytes used by every table and its indexes from SQLite's `dbstat` virtual table, using
+/// the given [Connection].
+///
+/// # Returns
+///
+/// The bytes by table name, or `None` if SQLite was built without `dbstat`.
+fn select_table_bytes(conn: &mut Connection) -> Result<Option<BTreeMap<String, u64>>> {
+    let sql = "
+        SELECT
+            sqlite_schema.tbl_name,
+            SUM(dbstat.pgsize)
+        FROM
+            dbstat
+        JOIN
+            sqlite_schema ON sqlite_schema.name = dbstat.name
+        GROUP BY
+            sqlite_schema.tbl_name;
+    ";
+    let mut stmt = match conn.prepare(sql) {
+        Ok(stmt) => stmt,
+        Err(rusqlite::Error::SqliteFailure(_, Some(message))) if message.contains("dbstat") => {
+            return Ok(None);
+        },
+        Err(err) => return Err(err.into()),
+    };
+    let params = params![];
+    let _profile = StatementProfile::start(sql, params);
+    let num_bytes = stmt
+        .query_map(params, |row| Ok((row.get(0)?, column_value_as_u64(row, 1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(Some(num_bytes))
+}
+
 /// Updates the DB with the state of a new block.
 ///
 ///
@@ -546,29 +1897,82 @@ pub fn apply_block(
     transaction: &Transaction,
     block_header: &BlockHeader,
     notes: &[Note],
+    note_tree: Option<&BlockNoteTree>,
     nullifiers: &[RpoDigest],
     accounts: &[(AccountId, Digest)],
+    account_details: &[(AccountId, Vec<u8>)],
+    consumed_notes: &[(RpoDigest, RpoDigest)],
+    tx_kernel_versions: &[u32],
 ) -> Result<usize> {
     let mut count = 0;
     count += insert_block_header(transaction, block_header)?;
+    count +=
+        insert_block_tx_kernel_versions(transaction, block_header.block_num, tx_kernel_versions)?;
     count += insert_notes(transaction, notes)?;
+    count += insert_note_tag_stats(transaction, block_header.block_num, notes)?;
+    if let Some(note_tree) = note_tree {
+        count += insert_note_tree(transaction, block_header.block_num, note_tree)?;
+    }
     count += upsert_accounts_with_blocknum(transaction, accounts, block_header.block_num)?;
+    count += update_account_details(transaction, account_details)?;
     count += insert_nullifiers_for_block(transaction, nullifiers, block_header.block_num)?;
+    count += insert_note_nullifiers(transaction, consumed_notes)?;
     Ok(count)
 }
 
 // UTILITIES
 // ================================================================================================
 
-/// Decodes a blob from the database into a [Digest].
-fn decode_protobuf_digest(data: &[u8]) -> Result<Digest> {
-    Ok(Digest::decode(data)?)
+/// Size of a digest in miden's native serialization.
+const NATIVE_DIGEST_SIZE: usize = 32;
+
+/// Encodes a [RpoDigest] into the blob stored in the database.
+///
+/// Digests are stored in miden's native serialization. The databases created before hold protobuf
+/// encoded digests until the [crate::backfill::NATIVE_DIGESTS_BACKFILL] rewrote them, see [decode_digest].
+pub fn encode_digest(digest: &RpoDigest) -> Vec<u8> {
+    digest.as_bytes().to_vec()
+}
+
+/// Encodes a protobuf [Digest] into the blob stored in the database, see [encode_digest].
+fn encode_protobuf_digest(digest: &Digest) -> Result<Vec<u8>> {
+    let digest = RpoDigest::try_from(digest).map_err(ConversionError::from)?;
+    Ok(encode_digest(&digest))
+}
+
+/// Decodes a digest blob from the database into a [Digest].
+///
+/// Both the native serialization and the protobuf encoding are read. A protobuf encoded digest is
+/// made of 9 bytes long fields, so it is never as long as a native one.
+pub fn decode_digest(data: &[u8]) -> Result<Digest> {
+    if data.len() == NATIVE_DIGEST_SIZE {
+        Ok(decode_rpo_digest(data)?.into())
+    } else {
+        Ok(Digest::decode(data)?)
+    }
+}
+
+/// Returns the blobs a digest may be stored as, the native one first, to look it up until the
+/// [crate::backfill::NATIVE_DIGESTS_BACKFILL] completed.
+fn digest_lookup_values(digest: &RpoDigest) -> [Value; 2] {
+    [
+        Value::Blob(encode_digest(digest)),
+        Value::Blob(Digest::from(digest).encode_to_vec()),
+    ]
 }
 
 /// Decodes a blob from the database into a [RpoDigest].
-fn decode_rpo_digest(data: &[u8]) -> Result<RpoDigest> {
+///
+/// The blob must hold exactly one digest, a longer blob is corrupted rather than padded.
+pub fn decode_rpo_digest(data: &[u8]) -> Result<RpoDigest> {
     let mut reader = SliceReader::new(data);
-    RpoDigest::read_from(&mut reader).map_err(DatabaseError::NullifierDecodingError)
+    let digest =
+        RpoDigest::read_from(&mut reader).map_err(DatabaseError::NullifierDecodingError)?;
+    if reader.has_more_bytes() {
+        return Err(DatabaseError::NullifierDecodingError(DeserializationError::UnconsumedBytes));
+    }
+
+    Ok(digest)
 }
 
 /// Returns the high bits of the `u64` value used during searches.
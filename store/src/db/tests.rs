@@ -1,17 +1,28 @@
-use miden_crypto::{hash::rpo::RpoDigest, merkle::LeafIndex, StarkField};
+use std::{path::Path, time::Duration};
+
+use figment::Jail;
+use miden_crypto::{hash::rpo::RpoDigest, utils::Serializable, StarkField};
 use miden_node_proto::{
     account::{AccountId, AccountInfo},
-    block_header::BlockHeader as ProtobufBlockHeader,
+    block_header::{BlockHeader as ProtobufBlockHeader, BlockSignature, TimestampAttestation},
     digest::Digest as ProtobufDigest,
     merkle::MerklePath,
-    note::Note,
-    responses::{AccountHashUpdate, NullifierUpdate},
+    note::{Note, NoteCreated, NoteRecoveryRecord},
+    responses::{AccountHashUpdate, ConsumedNoteUpdate, NoteTagStats, NullifierUpdate},
 };
+use miden_node_utils::tag_policy::TagRange;
 use miden_objects::{crypto::merkle::SimpleSmt, notes::NOTE_LEAF_DEPTH, Felt, FieldElement};
-use rusqlite::{vtab::array, Connection};
-
-use super::sql;
-use crate::db::migrations;
+use prost::Message;
+use rusqlite::{params, vtab::array, Connection};
+
+use super::{inspect, run_interruptible, sql, QueryInterrupt};
+use crate::{
+    backfill::{BackfillProgress, NATIVE_DIGESTS_BACKFILL, NOTE_TREES_BACKFILL},
+    db::migrations,
+    errors::DatabaseError,
+    note_tree::{note_leaf_index, BlockNoteTree, MAX_NOTES_PER_BATCH},
+    NOTE_TAG_STATS_WINDOW,
+};
 
 fn create_db() -> Connection {
     let mut conn = Connection::open_in_memory().unwrap();
@@ -62,6 +73,33 @@ fn test_sql_insert_nullifiers_for_block() {
         transaction.commit().unwrap();
         assert_eq!(res.unwrap(), nullifiers.len(), "There should be 10 entries");
     }
+
+    // nullifiers spanning multiple statements are all inserted
+    {
+        let nullifiers: Vec<_> = (100..1100).rev().map(num_to_rpo_digest).collect();
+        let block_num = 2;
+        let transaction = conn.transaction().unwrap();
+        let res = sql::insert_nullifiers_for_block(&transaction, &nullifiers, block_num);
+        transaction.commit().unwrap();
+        assert_eq!(res.unwrap(), nullifiers.len(), "There should be 1000 entries");
+
+        let stored = sql::select_nullifiers(&mut conn).unwrap();
+        let stored_in_block: Vec<_> =
+            stored.iter().filter(|(_, block)| *block == block_num).collect();
+        assert_eq!(stored_in_block.len(), nullifiers.len());
+    }
+}
+
+#[test]
+fn test_decode_rpo_digest() {
+    let digest = num_to_rpo_digest(1 << 48);
+    let bytes = digest.to_bytes();
+    assert_eq!(sql::decode_rpo_digest(&bytes).unwrap(), digest);
+
+    // truncated, padded, and out of the field blobs are rejected
+    assert!(sql::decode_rpo_digest(&bytes[..31]).is_err());
+    assert!(sql::decode_rpo_digest(&[bytes.as_slice(), &[0]].concat()).is_err());
+    assert!(sql::decode_rpo_digest(&[u8::MAX; 32]).is_err());
 }
 
 #[test]
@@ -86,6 +124,20 @@ fn test_sql_select_nullifiers() {
         let nullifiers = sql::select_nullifiers(&mut conn).unwrap();
         assert_eq!(nullifiers, state);
     }
+
+    // test reading the nullifiers in chunks, ordered by their encoding
+    state.sort_unstable_by_key(|(nullifier, _)| nullifier.as_bytes());
+    let mut nullifiers = vec![];
+    loop {
+        let after = nullifiers.last().map(|&(nullifier, _)| nullifier);
+        let chunk = sql::select_nullifiers_after(&mut conn, after, 3).unwrap();
+        assert!(chunk.len() <= 3);
+        if chunk.is_empty() {
+            break;
+        }
+        nullifiers.extend(chunk);
+    }
+    assert_eq!(nullifiers, state);
 }
 
 #[test]
@@ -106,7 +158,8 @@ fn test_sql_select_notes() {
             note_hash: Some(num_to_protobuf_digest(i.into())),
             sender: i.into(),
             tag: i.into(),
-            merkle_path: Some(MerklePath { siblings: vec![] }),
+            merkle_path: None,
+            private: false,
         };
         state.push(note.clone());
 
@@ -277,6 +330,125 @@ fn test_sql_select_nullifiers_by_block_range() {
     assert!(nullifiers.is_empty());
 }
 
+#[test]
+fn test_sql_nullifiers_prefix_index() {
+    // nullifiers stored before the index on the prefix and block number was introduced
+    let mut conn = Connection::open_in_memory().unwrap();
+    array::load_module(&conn).unwrap();
+    migrations::MIGRATIONS.to_version(&mut conn, 12).unwrap();
+
+    let legacy = [(num_to_rpo_digest(1 << 48), 1), (num_to_rpo_digest(2 << 48), 300)];
+    for (nullifier, block_num) in legacy {
+        conn.execute(
+            "INSERT INTO nullifiers (nullifier, nullifier_prefix, block_number) VALUES (?1, ?2, ?3);",
+            params![nullifier.as_bytes(), sql::u64_to_prefix(nullifier[0].as_int()), block_num],
+        )
+        .unwrap();
+    }
+    migrations::MIGRATIONS.to_latest(&mut conn).unwrap();
+
+    let indexes: Vec<String> = conn
+        .prepare("SELECT name FROM pragma_index_list('nullifiers');")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(indexes.contains(&"nullifiers_prefix_block_number".to_string()));
+
+    // a nullifier inserted since the migration
+    let nullifier = num_to_rpo_digest(3 << 48);
+    let block_num = 199;
+    let transaction = conn.transaction().unwrap();
+    sql::insert_nullifiers_for_block(&transaction, &[nullifier], block_num).unwrap();
+    transaction.commit().unwrap();
+
+    let prefixes: Vec<u32> = [legacy[0].0, legacy[1].0, nullifier]
+        .iter()
+        .map(|nullifier| sql::u64_to_prefix(nullifier[0].as_int()))
+        .collect();
+    let mut block_nums = |block_start, block_end| -> Vec<u32> {
+        sql::select_nullifiers_by_block_range(&mut conn, block_start, block_end, &prefixes)
+            .unwrap()
+            .iter()
+            .map(|update| update.block_num)
+            .collect()
+    };
+
+    assert_eq!(block_nums(0, u32::MAX), vec![1, block_num, 300]);
+    assert_eq!(block_nums(1, 300), vec![block_num, 300]);
+    assert_eq!(block_nums(block_num - 1, block_num), vec![block_num]);
+    assert_eq!(block_nums(block_num, 299), Vec::<u32>::new());
+}
+
+#[test]
+fn test_sql_select_consumed_notes_by_block_range() {
+    let mut conn = create_db();
+
+    let note_hash1 = num_to_rpo_digest(1);
+    let note_hash2 = num_to_rpo_digest(2);
+    let nullifier1 = num_to_rpo_digest(1 << 48);
+    let nullifier2 = num_to_rpo_digest(2 << 48);
+
+    // test empty table
+    let consumed_notes =
+        sql::select_consumed_notes_by_block_range(&mut conn, 0, u32::MAX, &[note_hash1]).unwrap();
+    assert!(consumed_notes.is_empty());
+
+    // the notes are consumed in different blocks
+    let transaction = conn.transaction().unwrap();
+    sql::insert_nullifiers_for_block(&transaction, &[nullifier1], 1).unwrap();
+    sql::insert_nullifiers_for_block(&transaction, &[nullifier2], 2).unwrap();
+    let res = sql::insert_note_nullifiers(
+        &transaction,
+        &[(note_hash1, nullifier1), (note_hash2, nullifier2)],
+    );
+    assert_eq!(res.unwrap(), 2, "Two elements must have been inserted");
+    transaction.commit().unwrap();
+
+    // a note can only be consumed once
+    let transaction = conn.transaction().unwrap();
+    let res = sql::insert_note_nullifiers(&transaction, &[(note_hash1, nullifier2)]);
+    assert!(res.is_err(), "Linking the same note twice is an error");
+    drop(transaction);
+
+    let consumed_notes = sql::select_consumed_notes_by_block_range(
+        &mut conn,
+        0,
+        u32::MAX,
+        &[note_hash1, note_hash2],
+    )
+    .unwrap();
+    assert_eq!(
+        consumed_notes,
+        vec![
+            ConsumedNoteUpdate {
+                note_hash: Some(note_hash1.into()),
+                nullifier: Some(nullifier1.into()),
+                block_num: 1,
+            },
+            ConsumedNoteUpdate {
+                note_hash: Some(note_hash2.into()),
+                nullifier: Some(nullifier2.into()),
+                block_num: 2,
+            },
+        ]
+    );
+
+    // the block range is exclusive at the start and inclusive at the end
+    let consumed_notes =
+        sql::select_consumed_notes_by_block_range(&mut conn, 1, 2, &[note_hash1, note_hash2])
+            .unwrap();
+    assert_eq!(consumed_notes.len(), 1);
+    assert_eq!(consumed_notes[0].block_num, 2);
+
+    // only the requested notes are returned
+    let consumed_notes =
+        sql::select_consumed_notes_by_block_range(&mut conn, 0, u32::MAX, &[note_hash2]).unwrap();
+    assert_eq!(consumed_notes.len(), 1);
+    assert_eq!(consumed_notes[0].note_hash, Some(note_hash2.into()));
+}
+
 #[test]
 fn test_db_block_header() {
     let mut conn = create_db();
@@ -289,7 +461,7 @@ fn test_db_block_header() {
     let res = sql::select_block_header_by_block_num(&mut conn, None).unwrap();
     assert!(res.is_none());
 
-    let res = sql::select_block_headers(&mut conn).unwrap();
+    let res = sql::select_block_headers_after(&mut conn, None, 10).unwrap();
     assert!(res.is_empty());
 
     let block_header = ProtobufBlockHeader {
@@ -344,8 +516,52 @@ fn test_db_block_header() {
     let res = sql::select_block_header_by_block_num(&mut conn, None).unwrap();
     assert_eq!(res.unwrap(), block_header2);
 
-    let res = sql::select_block_headers(&mut conn).unwrap();
-    assert_eq!(res, [block_header, block_header2]);
+    let res = sql::select_block_headers_after(&mut conn, None, 10).unwrap();
+    assert_eq!(res, [block_header.clone(), block_header2.clone()]);
+
+    // test fetch the block headers in chunks
+    let res = sql::select_block_headers_after(&mut conn, None, 1).unwrap();
+    assert_eq!(res, [block_header.clone()]);
+    let res = sql::select_block_headers_after(&mut conn, Some(block_header.block_num), 1).unwrap();
+    assert_eq!(res, [block_header2.clone()]);
+    let res = sql::select_block_headers_after(&mut conn, Some(block_header2.block_num), 1).unwrap();
+    assert!(res.is_empty());
+}
+
+#[test]
+fn test_db_inspect() {
+    Jail::expect_with(|_jail| {
+        let database_filepath = Path::new("store.sqlite3");
+
+        // a database the store never started on
+        drop(Connection::open(database_filepath).unwrap());
+        let info = inspect(database_filepath).unwrap();
+        assert_eq!(info.schema_version, 0);
+        assert_eq!(info.genesis_header, None);
+
+        let mut conn = Connection::open(database_filepath).unwrap();
+        migrations::MIGRATIONS.to_latest(&mut conn).unwrap();
+        let info = inspect(database_filepath).unwrap();
+        assert_eq!(info.schema_version, migrations::SCHEMA_VERSION);
+        assert_eq!(info.genesis_header, None);
+
+        let genesis_header = ProtobufBlockHeader {
+            block_num: 0,
+            ..Default::default()
+        };
+        let transaction = conn.transaction().unwrap();
+        sql::insert_block_header(&transaction, &genesis_header).unwrap();
+        transaction.commit().unwrap();
+
+        let info = inspect(database_filepath).unwrap();
+        assert_eq!(info.genesis_header, Some(genesis_header));
+
+        // inspecting never creates a database
+        assert!(inspect(Path::new("missing.sqlite3")).is_err());
+        assert!(!Path::new("missing.sqlite3").exists());
+
+        Ok(())
+    });
 }
 
 #[test]
@@ -395,6 +611,74 @@ fn test_db_account() {
     assert!(res.is_empty());
 }
 
+#[test]
+fn test_db_account_history() {
+    let mut conn = create_db();
+
+    // account 0 is updated at every block, account 1 only at block 3
+    let account_ids = [0, 1];
+    let hash = |block_num: u32| num_to_protobuf_digest(block_num as u64);
+    let update = |account_id: u64, block_num: u32| AccountHashUpdate {
+        account_id: Some(account_id.into()),
+        account_hash: Some(hash(block_num)),
+        block_num,
+    };
+    for block_num in 1..=12 {
+        let mut accounts = vec![(0, hash(block_num))];
+        if block_num == 3 {
+            accounts.push((1, hash(block_num)));
+        }
+
+        let transaction = conn.transaction().unwrap();
+        sql::upsert_accounts_with_blocknum(&transaction, &accounts, block_num).unwrap();
+        transaction.commit().unwrap();
+    }
+
+    // the latest update within the range is returned
+    let res = sql::select_accounts_by_block_range(&mut conn, 0, 4, &account_ids).unwrap();
+    assert_eq!(res, vec![update(1, 3), update(0, 4)]);
+    let res = sql::select_accounts_by_block_range(&mut conn, 4, 12, &account_ids).unwrap();
+    assert_eq!(res, vec![update(0, 12)]);
+
+    // the full history is kept by default
+    let transaction = conn.transaction().unwrap();
+    assert_eq!(sql::compact_account_updates(&transaction, &account_ids, 0, 5).unwrap(), 0);
+
+    // the latest 3 updates are kept, and the last one of every 5 blocks before them
+    assert_eq!(sql::compact_account_updates(&transaction, &account_ids, 3, 5).unwrap(), 7);
+    transaction.commit().unwrap();
+
+    let blocks: Vec<u32> = conn
+        .prepare("SELECT block_num FROM account_updates WHERE account_id = 0 ORDER BY block_num")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(blocks, vec![4, 9, 10, 11, 12]);
+
+    // a range ending in the compacted history is served its latest checkpoint
+    let res = sql::select_accounts_by_block_range(&mut conn, 0, 8, &account_ids).unwrap();
+    assert_eq!(res, vec![update(1, 3), update(0, 4)]);
+
+    // the accounts whose updates within the range were all compacted away are skipped
+    let res = sql::select_accounts_by_block_range(&mut conn, 4, 8, &account_ids).unwrap();
+    assert!(res.is_empty());
+    let res = sql::select_accounts_by_block_range(&mut conn, 2, 8, &account_ids).unwrap();
+    assert_eq!(res, vec![update(1, 3), update(0, 4)]);
+    let res = sql::select_accounts_by_block_range(&mut conn, 5, 7, &account_ids).unwrap();
+    assert!(res.is_empty());
+    let res = sql::select_accounts_by_block_range(&mut conn, 0, 12, &account_ids).unwrap();
+    assert_eq!(res, vec![update(1, 3), update(0, 12)]);
+
+    // without checkpoints, only the latest updates are kept
+    let transaction = conn.transaction().unwrap();
+    assert_eq!(sql::compact_account_updates(&transaction, &account_ids, 1, 0).unwrap(), 4);
+    transaction.commit().unwrap();
+    let res = sql::select_accounts_by_block_range(&mut conn, 0, 11, &account_ids).unwrap();
+    assert_eq!(res, vec![update(1, 3)]);
+}
+
 #[test]
 fn test_notes() {
     let mut conn = create_db();
@@ -411,24 +695,16 @@ fn test_notes() {
     let block_num = 1;
     let note_index = 2u32;
     let tag = 5;
-    let note_hash = num_to_rpo_digest(3);
-    let values = [(note_index as u64, *note_hash)];
-    let notes_db = SimpleSmt::<NOTE_LEAF_DEPTH>::with_leaves(values.iter().cloned()).unwrap();
-    let idx = LeafIndex::<NOTE_LEAF_DEPTH>::new(note_index as u64).unwrap();
-    let merkle_path = notes_db.open(&idx).path;
-
-    let merkle_path: Vec<ProtobufDigest> =
-        merkle_path.nodes().iter().map(|n| (*n).into()).collect();
 
+    // the merkle paths are not stored, they are derived from the block's note tree
     let note = Note {
         block_num,
         note_index,
         note_hash: Some(num_to_protobuf_digest(3)),
         sender: 4,
         tag,
-        merkle_path: Some(MerklePath {
-            siblings: merkle_path.clone(),
-        }),
+        merkle_path: None,
+        private: false,
     };
 
     let transaction = conn.transaction().unwrap();
@@ -466,9 +742,8 @@ fn test_notes() {
         note_hash: Some(num_to_protobuf_digest(3)),
         sender: note.sender,
         tag: note.tag,
-        merkle_path: Some(MerklePath {
-            siblings: merkle_path,
-        }),
+        merkle_path: None,
+        private: false,
     };
 
     let transaction = conn.transaction().unwrap();
@@ -496,6 +771,678 @@ fn test_notes() {
     assert_eq!(res, vec![note2.clone()]);
 }
 
+#[test]
+fn test_sql_select_notes_by_tag_and_block_range() {
+    let mut conn = create_db();
+
+    let matching_tag = 1u64 << 48;
+    let other_tag = 2u64 << 48;
+    let notes: Vec<Note> = [
+        (1, 1, matching_tag),
+        (2, 0, other_tag),
+        (2, 1, matching_tag),
+        (3, 0, matching_tag),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, (block_num, note_index, tag))| Note {
+        block_num,
+        note_index,
+        note_hash: Some(num_to_protobuf_digest(i as u64)),
+        sender: 4,
+        tag,
+        merkle_path: None,
+        private: false,
+    })
+    .collect();
+
+    let transaction = conn.transaction().unwrap();
+    sql::insert_notes(&transaction, &notes).unwrap();
+    transaction.commit().unwrap();
+
+    // both bounds of the range are included, the other tags are filtered out
+    let res = sql::select_notes_by_tag_and_block_range(&mut conn, &[1], 1, 2).unwrap();
+    assert_eq!(res, vec![notes[0].clone(), notes[2].clone()]);
+
+    let res = sql::select_notes_by_tag_and_block_range(&mut conn, &[1, 2], 2, 3).unwrap();
+    assert_eq!(res, vec![notes[1].clone(), notes[2].clone(), notes[3].clone()]);
+
+    let res = sql::select_notes_by_tag_and_block_range(&mut conn, &[], 0, u32::MAX).unwrap();
+    assert!(res.is_empty());
+}
+
+#[test]
+fn test_sql_note_tree() {
+    let mut conn = create_db();
+
+    // test missing tree
+    assert_eq!(sql::select_note_tree(&mut conn, 1).unwrap(), None);
+
+    // notes of the batches 0 and 3
+    let leaves = [
+        (0, *num_to_rpo_digest(1)),
+        (1, *num_to_rpo_digest(2)),
+        (5, *num_to_rpo_digest(3)),
+        (note_leaf_index(3 * MAX_NOTES_PER_BATCH), *num_to_rpo_digest(4)),
+    ];
+    let note_tree =
+        BlockNoteTree::from_tree(&SimpleSmt::<NOTE_LEAF_DEPTH>::with_leaves(leaves).unwrap());
+    let empty_tree = BlockNoteTree::default();
+
+    let transaction = conn.transaction().unwrap();
+    assert_eq!(sql::insert_note_tree(&transaction, 1, &note_tree).unwrap(), 1);
+    assert_eq!(sql::insert_note_tree(&transaction, 2, &empty_tree).unwrap(), 1);
+    transaction.commit().unwrap();
+
+    assert_eq!(sql::select_note_tree(&mut conn, 1).unwrap(), Some(note_tree));
+    assert_eq!(sql::select_note_tree(&mut conn, 2).unwrap(), Some(empty_tree));
+    assert_eq!(sql::select_note_tree(&mut conn, 3).unwrap(), None);
+
+    // a tree can be stored only once per block
+    let transaction = conn.transaction().unwrap();
+    assert!(sql::insert_note_tree(&transaction, 2, &BlockNoteTree::default()).is_err());
+}
+
+#[test]
+fn test_sql_block_tx_kernel_versions() {
+    let mut conn = create_db();
+
+    // blocks applied before the versions were recorded have none
+    assert!(sql::select_block_tx_kernel_versions(&mut conn, 1).unwrap().is_empty());
+
+    let transaction = conn.transaction().unwrap();
+    assert_eq!(sql::insert_block_tx_kernel_versions(&transaction, 1, &[2, 1]).unwrap(), 2);
+    assert_eq!(sql::insert_block_tx_kernel_versions(&transaction, 2, &[1]).unwrap(), 1);
+    transaction.commit().unwrap();
+
+    assert_eq!(sql::select_block_tx_kernel_versions(&mut conn, 1).unwrap(), vec![1, 2]);
+    assert_eq!(sql::select_block_tx_kernel_versions(&mut conn, 2).unwrap(), vec![1]);
+    assert!(sql::select_block_tx_kernel_versions(&mut conn, 3).unwrap().is_empty());
+}
+
+#[test]
+fn test_sql_block_signatures() {
+    let mut conn = create_db();
+
+    // unsigned blocks have no signature
+    assert_eq!(sql::select_block_signature(&mut conn, 1).unwrap(), None);
+
+    let signature = BlockSignature {
+        producer_key: Some(num_to_protobuf_digest(1)),
+        signature: vec![1, 2, 3],
+    };
+    let transaction = conn.transaction().unwrap();
+    assert_eq!(sql::insert_block_signature(&transaction, 1, &signature).unwrap(), 1);
+    transaction.commit().unwrap();
+
+    assert_eq!(sql::select_block_signature(&mut conn, 1).unwrap(), Some(signature.clone()));
+    assert_eq!(sql::select_block_signature(&mut conn, 2).unwrap(), None);
+
+    // a block is signed only once
+    let transaction = conn.transaction().unwrap();
+    assert!(sql::insert_block_signature(&transaction, 1, &signature).is_err());
+}
+
+#[test]
+fn test_sql_block_timestamp_attestations() {
+    let mut conn = create_db();
+
+    // blocks applied without attestation have none
+    assert_eq!(sql::select_block_timestamp_attestation(&mut conn, 1).unwrap(), None);
+
+    let attestation = TimestampAttestation {
+        authority_key: Some(num_to_protobuf_digest(1)),
+        timestamp: 1_700_000_000,
+        signature: vec![1, 2, 3],
+    };
+    let transaction = conn.transaction().unwrap();
+    assert_eq!(
+        sql::insert_block_timestamp_attestation(&transaction, 1, &attestation).unwrap(),
+        1
+    );
+    transaction.commit().unwrap();
+
+    assert_eq!(
+        sql::select_block_timestamp_attestation(&mut conn, 1).unwrap(),
+        Some(attestation.clone())
+    );
+    assert_eq!(sql::select_block_timestamp_attestation(&mut conn, 2).unwrap(), None);
+
+    // a block is attested only once
+    let transaction = conn.transaction().unwrap();
+    assert!(sql::insert_block_timestamp_attestation(&transaction, 1, &attestation).is_err());
+}
+
+#[test]
+fn test_sql_canonically_ordered_blocks() {
+    let mut conn = create_db();
+
+    // the blocks are ordered as the transactions arrived unless recorded otherwise
+    assert!(!sql::select_block_canonical_tx_ordering(&mut conn, 1).unwrap());
+
+    let transaction = conn.transaction().unwrap();
+    assert_eq!(sql::insert_canonically_ordered_block(&transaction, 1).unwrap(), 1);
+    transaction.commit().unwrap();
+
+    assert!(sql::select_block_canonical_tx_ordering(&mut conn, 1).unwrap());
+    assert!(!sql::select_block_canonical_tx_ordering(&mut conn, 2).unwrap());
+}
+
+#[test]
+fn test_sql_note_tag_stats() {
+    let mut conn = create_db();
+
+    let note = |block_num, note_index, tag_prefix: u64| Note {
+        block_num,
+        note_index,
+        note_hash: Some(num_to_protobuf_digest(note_index as u64)),
+        sender: 4,
+        tag: (tag_prefix << 48) + 1,
+        merkle_path: None,
+        private: false,
+    };
+
+    let transaction = conn.transaction().unwrap();
+    sql::insert_note_tag_stats(&transaction, 1, &[note(1, 0, 1), note(1, 1, 1), note(1, 2, 2)])
+        .unwrap();
+    sql::insert_note_tag_stats(&transaction, 2, &[]).unwrap();
+    sql::insert_note_tag_stats(&transaction, 3, &[note(3, 0, 1)]).unwrap();
+    transaction.commit().unwrap();
+
+    let (total_notes, stats) = sql::select_note_tag_stats(&mut conn, &[1, 3], 0).unwrap();
+    assert_eq!(total_notes, 4);
+    assert_eq!(
+        stats,
+        vec![NoteTagStats {
+            tag: 1,
+            num_notes: 3,
+            num_blocks: 2,
+        }]
+    );
+
+    let (total_notes, stats) = sql::select_note_tag_stats(&mut conn, &[2], 2).unwrap();
+    assert_eq!(total_notes, 1);
+    assert!(stats.is_empty());
+
+    // the blocks leaving the window are dropped
+    let transaction = conn.transaction().unwrap();
+    sql::insert_note_tag_stats(&transaction, NOTE_TAG_STATS_WINDOW + 1, &[]).unwrap();
+    transaction.commit().unwrap();
+
+    let (total_notes, stats) = sql::select_note_tag_stats(&mut conn, &[1], 0).unwrap();
+    assert_eq!(total_notes, 1);
+    assert_eq!(
+        stats,
+        vec![NoteTagStats {
+            tag: 1,
+            num_notes: 1,
+            num_blocks: 1,
+        }]
+    );
+}
+
+#[test]
+fn test_sql_unconsumed_notes() {
+    let mut conn = create_db();
+
+    let note = |block_num, note_index, tag_prefix: u64| Note {
+        block_num,
+        note_index,
+        note_hash: Some(num_to_rpo_digest((block_num * 10 + note_index) as u64).into()),
+        sender: 4,
+        tag: (tag_prefix << 48) + 1,
+        merkle_path: None,
+        private: false,
+    };
+
+    let transaction = conn.transaction().unwrap();
+    sql::insert_notes(
+        &transaction,
+        &[
+            note(1, 0, 1),
+            note(1, 1, 2),
+            note(1, 2, 1),
+            note(2, 0, 1),
+            note(2, 1, 1),
+            note(3, 0, 1),
+        ],
+    )
+    .unwrap();
+    let nullifier = num_to_rpo_digest(100);
+    sql::insert_nullifiers_for_block(&transaction, &[nullifier], 3).unwrap();
+    sql::insert_note_nullifiers(&transaction, &[(num_to_rpo_digest(12), nullifier)]).unwrap();
+    transaction.commit().unwrap();
+
+    // the consumed note and the notes of other tags are filtered out
+    let (notes, next_block_num) =
+        sql::select_unconsumed_notes_by_tag(&mut conn, &[1], 0, 3, 10).unwrap();
+    assert_eq!(notes, vec![note(1, 0, 1), note(2, 0, 1), note(2, 1, 1), note(3, 0, 1)]);
+    assert_eq!(next_block_num, 4);
+
+    // whole blocks are listed within the limit
+    let (notes, next_block_num) =
+        sql::select_unconsumed_notes_by_tag(&mut conn, &[1], 0, 3, 2).unwrap();
+    assert_eq!(notes, vec![note(1, 0, 1)]);
+    assert_eq!(next_block_num, 2);
+
+    // and a block past the limit is listed whole
+    let (notes, next_block_num) =
+        sql::select_unconsumed_notes_by_tag(&mut conn, &[1], 2, 3, 1).unwrap();
+    assert_eq!(notes, vec![note(2, 0, 1), note(2, 1, 1)]);
+    assert_eq!(next_block_num, 3);
+
+    let (notes, next_block_num) =
+        sql::select_unconsumed_notes_by_tag(&mut conn, &[1, 2], 4, 3, 10).unwrap();
+    assert!(notes.is_empty());
+    assert_eq!(next_block_num, 4);
+}
+
+#[test]
+fn test_sql_count_state_rows() {
+    let mut conn = create_db();
+
+    let note = |block_num, note_index| Note {
+        block_num,
+        note_index,
+        note_hash: Some(num_to_rpo_digest((block_num * 10 + note_index) as u64).into()),
+        sender: 4,
+        tag: 1,
+        merkle_path: None,
+        private: false,
+    };
+
+    let transaction = conn.transaction().unwrap();
+    sql::insert_notes(&transaction, &[note(1, 0), note(1, 1), note(2, 0), note(3, 0)]).unwrap();
+    sql::insert_nullifiers_for_block(&transaction, &[num_to_rpo_digest(100)], 2).unwrap();
+    sql::insert_nullifiers_for_block(&transaction, &[num_to_rpo_digest(101)], 3).unwrap();
+    sql::insert_nullifiers_for_block(&transaction, &[num_to_rpo_digest(102)], 3).unwrap();
+    sql::insert_note_nullifiers(
+        &transaction,
+        &[
+            (num_to_rpo_digest(10), num_to_rpo_digest(100)),
+            (num_to_rpo_digest(20), num_to_rpo_digest(101)),
+            // a note which isn't in the chain
+            (num_to_rpo_digest(99), num_to_rpo_digest(102)),
+        ],
+    )
+    .unwrap();
+    transaction.commit().unwrap();
+
+    // only the rows up to the block are counted
+    assert_eq!(sql::count_state_rows(&mut conn, 0).unwrap(), (0, 0, 0));
+    assert_eq!(sql::count_state_rows(&mut conn, 1).unwrap(), (2, 0, 0));
+    assert_eq!(sql::count_state_rows(&mut conn, 2).unwrap(), (3, 1, 1));
+    assert_eq!(sql::count_state_rows(&mut conn, 3).unwrap(), (4, 3, 2));
+    assert_eq!(sql::count_state_rows(&mut conn, 10).unwrap(), (4, 3, 2));
+}
+
+#[test]
+fn test_sql_select_table_sizes() {
+    let mut conn = create_db();
+
+    let transaction = conn.transaction().unwrap();
+    sql::insert_block_tx_kernel_versions(&transaction, 1, &[1, 2]).unwrap();
+    transaction.commit().unwrap();
+
+    let tables = sql::select_table_sizes(&mut conn).unwrap();
+
+    let names: Vec<&str> = tables.iter().map(|table| table.name.as_str()).collect();
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names, sorted);
+    assert!(names.contains(&"notes"));
+
+    let table = |name: &str| tables.iter().find(|table| table.name == name).unwrap();
+    assert_eq!(table("block_tx_kernels").num_rows, 2);
+    assert_eq!(table("notes").num_rows, 0);
+
+    // the bytes are reported for every table, or for none if SQLite lacks `dbstat`
+    let with_bytes = tables.iter().filter(|table| table.num_bytes.is_some()).count();
+    assert!(with_bytes == 0 || with_bytes == tables.len());
+}
+
+#[test]
+fn test_sql_select_notes_by_block_num() {
+    let mut conn = create_db();
+
+    // test empty table
+    let res = sql::select_notes_by_block_num(&mut conn, 1).unwrap();
+    assert!(res.is_empty());
+
+    let notes: Vec<Note> = [(1, 2), (1, 0), (2, 0)]
+        .into_iter()
+        .map(|(block_num, note_index)| Note {
+            block_num,
+            note_index,
+            note_hash: Some(num_to_protobuf_digest((block_num * 10 + note_index) as u64)),
+            sender: 4,
+            tag: 5,
+            merkle_path: Some(MerklePath { siblings: vec![] }),
+            private: false,
+        })
+        .collect();
+
+    let transaction = conn.transaction().unwrap();
+    sql::insert_notes(&transaction, &notes).unwrap();
+    transaction.commit().unwrap();
+
+    // only the notes of the requested block are returned, ordered by their index
+    let res = sql::select_notes_by_block_num(&mut conn, 1).unwrap();
+    let expected: Vec<NoteCreated> = [&notes[1], &notes[0]]
+        .into_iter()
+        .map(|note| NoteCreated {
+            note_index: note.note_index,
+            note_hash: note.note_hash.clone(),
+            sender: note.sender,
+            tag: note.tag,
+            private: note.private,
+        })
+        .collect();
+    assert_eq!(res, expected);
+
+    // test block without notes
+    let res = sql::select_notes_by_block_num(&mut conn, 3).unwrap();
+    assert!(res.is_empty());
+}
+
+#[test]
+fn test_sql_note_recovery_records() {
+    let mut conn = create_db();
+
+    let note = Note {
+        block_num: 1,
+        note_index: 3,
+        note_hash: Some(num_to_rpo_digest(13).into()),
+        sender: 4,
+        tag: 5,
+        merkle_path: None,
+        private: false,
+    };
+    let transaction = conn.transaction().unwrap();
+    sql::insert_notes(&transaction, &[note.clone()]).unwrap();
+    transaction.commit().unwrap();
+
+    // the notes are found by hash
+    assert_eq!(
+        sql::select_note_by_hash(&mut conn, num_to_rpo_digest(13)).unwrap(),
+        Some(note.clone())
+    );
+    assert_eq!(sql::select_note_by_hash(&mut conn, num_to_rpo_digest(14)).unwrap(), None);
+
+    // test note without record
+    let res = sql::select_note_recovery_records(&mut conn, &[num_to_rpo_digest(13)]).unwrap();
+    assert!(res.is_empty());
+
+    let siblings = vec![num_to_rpo_digest(1), num_to_rpo_digest(2)];
+    let merkle_path = miden_crypto::merkle::MerklePath::new(siblings.clone());
+    let transaction = conn.transaction().unwrap();
+    let res = sql::insert_note_recovery_record(&transaction, &note, &[1, 2, 3], &merkle_path);
+    assert_eq!(res.unwrap(), 1);
+
+    // the first record of a note is kept
+    let res = sql::insert_note_recovery_record(&transaction, &note, &[4, 5, 6], &merkle_path);
+    assert_eq!(res.unwrap(), 0);
+    transaction.commit().unwrap();
+
+    let res = sql::select_note_recovery_records(
+        &mut conn,
+        &[num_to_rpo_digest(13), num_to_rpo_digest(14)],
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        vec![NoteRecoveryRecord {
+            block_num: 1,
+            note_index: 3,
+            note_hash: note.note_hash,
+            details: vec![1, 2, 3],
+            merkle_path: Some(MerklePath {
+                siblings: siblings.into_iter().map(Into::into).collect(),
+            }),
+        }]
+    );
+
+    // the records are deleted by the tag of their note
+    let transaction = conn.transaction().unwrap();
+    let res = sql::delete_note_recovery_records_by_tag(
+        &transaction,
+        &[TagRange {
+            start: 6,
+            end: u64::MAX,
+        }],
+    );
+    assert_eq!(res.unwrap(), 0);
+    let res =
+        sql::delete_note_recovery_records_by_tag(&transaction, &[TagRange { start: 5, end: 5 }]);
+    assert_eq!(res.unwrap(), 1);
+    transaction.commit().unwrap();
+
+    let res = sql::select_note_recovery_records(&mut conn, &[num_to_rpo_digest(13)]).unwrap();
+    assert!(res.is_empty());
+}
+
+#[test]
+fn test_sql_note_trees_backfill() {
+    let mut conn = create_db();
+
+    // the migrations register the backfills
+    let native_digests = BackfillProgress {
+        name: NATIVE_DIGESTS_BACKFILL.to_string(),
+        next_block_num: 0,
+        completed: false,
+    };
+    let progress = BackfillProgress {
+        name: NOTE_TREES_BACKFILL.to_string(),
+        next_block_num: 0,
+        completed: false,
+    };
+    assert_eq!(
+        sql::select_backfills(&mut conn).unwrap(),
+        vec![native_digests.clone(), progress.clone()]
+    );
+
+    let notes: Vec<Note> = [1, 1, 2, 4, 5]
+        .into_iter()
+        .enumerate()
+        .map(|(index, block_num)| Note {
+            block_num,
+            note_index: index as u32,
+            note_hash: Some(num_to_protobuf_digest(index as u64)),
+            sender: 4,
+            tag: 5,
+            merkle_path: Some(MerklePath { siblings: vec![] }),
+            private: false,
+        })
+        .collect();
+
+    let transaction = conn.transaction().unwrap();
+    sql::insert_notes(&transaction, &notes).unwrap();
+    sql::insert_note_tree(&transaction, 4, &BlockNoteTree::default()).unwrap();
+    transaction.commit().unwrap();
+
+    // blocks with notes and no tree are listed once, in order
+    assert_eq!(sql::select_blocks_without_note_tree(&mut conn, 0, 2).unwrap(), vec![1, 2]);
+    assert_eq!(sql::select_blocks_without_note_tree(&mut conn, 3, 10).unwrap(), vec![5]);
+    assert!(sql::select_blocks_without_note_tree(&mut conn, 6, 10).unwrap().is_empty());
+
+    let progress = BackfillProgress {
+        next_block_num: 6,
+        completed: true,
+        ..progress
+    };
+    let transaction = conn.transaction().unwrap();
+    assert_eq!(sql::update_backfill(&transaction, &progress).unwrap(), 1);
+    transaction.commit().unwrap();
+    assert_eq!(sql::select_backfills(&mut conn).unwrap(), vec![native_digests, progress]);
+}
+
+#[test]
+fn test_sql_native_digests_backfill() {
+    let mut conn = create_db();
+
+    let legacy_note = num_to_rpo_digest(1);
+    let legacy_account = num_to_rpo_digest(2);
+    let nullifier = num_to_rpo_digest(3);
+    let note = num_to_rpo_digest(4);
+    let protobuf = |digest: RpoDigest| ProtobufDigest::from(digest).encode_to_vec();
+
+    // rows of block 1 written before the migration, with protobuf encoded digests
+    conn.execute(
+        "INSERT INTO notes (block_num, note_index, note_hash, sender, tag) VALUES (1, 0, ?1, 5, 6);",
+        [protobuf(legacy_note)],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO note_recovery_records (note_hash, block_num, note_index, details, merkle_path)
+         VALUES (?1, 1, 0, x'01', x'');",
+        [protobuf(legacy_note)],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO accounts (account_id, account_hash, block_num) VALUES (7, ?1, 1);",
+        [protobuf(legacy_account)],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO account_updates (account_id, block_num, account_hash) VALUES (7, 1, ?1);",
+        [protobuf(legacy_account)],
+    )
+    .unwrap();
+    let transaction = conn.transaction().unwrap();
+    sql::insert_nullifiers_for_block(&transaction, &[nullifier], 1).unwrap();
+    transaction.commit().unwrap();
+    conn.execute(
+        "INSERT INTO note_nullifiers (note_hash, nullifier) VALUES (?1, ?2);",
+        [protobuf(legacy_note), nullifier.as_bytes().to_vec()],
+    )
+    .unwrap();
+
+    // a note of block 2, written since the migration
+    let transaction = conn.transaction().unwrap();
+    sql::insert_notes(
+        &transaction,
+        &[Note {
+            block_num: 2,
+            note_index: 0,
+            note_hash: Some(note.into()),
+            sender: 5,
+            tag: 6,
+            merkle_path: None,
+            private: false,
+        }],
+    )
+    .unwrap();
+    transaction.commit().unwrap();
+
+    // both encodings are read
+    let check_reads = |conn: &mut Connection| {
+        let note_hashes: Vec<_> = sql::select_notes(conn)
+            .unwrap()
+            .into_iter()
+            .map(|note| note.note_hash.unwrap())
+            .collect();
+        assert_eq!(note_hashes, vec![legacy_note.into(), note.into()]);
+        assert_eq!(sql::select_note_by_hash(conn, legacy_note).unwrap().unwrap().block_num, 1);
+        assert_eq!(sql::select_note_by_hash(conn, note).unwrap().unwrap().block_num, 2);
+        assert_eq!(sql::select_note_recovery_records(conn, &[legacy_note]).unwrap().len(), 1);
+        assert_eq!(
+            sql::select_consumed_notes_by_block_range(conn, 0, 1, &[legacy_note]).unwrap(),
+            vec![ConsumedNoteUpdate {
+                note_hash: Some(legacy_note.into()),
+                nullifier: Some(nullifier.into()),
+                block_num: 1,
+            }]
+        );
+        assert_eq!(
+            sql::select_account_hashes_after(conn, None, 10).unwrap(),
+            vec![(7, legacy_account.into())]
+        );
+        assert_eq!(
+            sql::select_accounts_by_block_range(conn, 0, 1, &[7]).unwrap(),
+            vec![AccountHashUpdate {
+                account_id: Some(7u64.into()),
+                account_hash: Some(legacy_account.into()),
+                block_num: 1,
+            }]
+        );
+    };
+    check_reads(&mut conn);
+
+    // the rows of block 1 are rewritten, the ones of block 2 were native already
+    let transaction = conn.transaction().unwrap();
+    assert_eq!(sql::rewrite_protobuf_digests(&transaction, 0, 3).unwrap(), 5);
+    transaction.commit().unwrap();
+
+    let num_protobuf_digests: u32 = conn
+        .query_row(
+            "SELECT
+                (SELECT COUNT(*) FROM notes WHERE length(note_hash) != 32) +
+                (SELECT COUNT(*) FROM note_recovery_records WHERE length(note_hash) != 32) +
+                (SELECT COUNT(*) FROM note_nullifiers WHERE length(note_hash) != 32) +
+                (SELECT COUNT(*) FROM accounts WHERE length(account_hash) != 32) +
+                (SELECT COUNT(*) FROM account_updates WHERE length(account_hash) != 32);",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(num_protobuf_digests, 0);
+    check_reads(&mut conn);
+}
+
+#[test]
+fn test_query_interruption() {
+    let mut conn = create_db();
+
+    // a query which takes long enough to trigger the progress handler
+    let count_rows = |conn: &mut Connection| -> Result<i64, DatabaseError> {
+        Ok(conn.query_row(
+            "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 100000)
+             SELECT count(*) FROM c;",
+            [],
+            |row| row.get(0),
+        )?)
+    };
+
+    // queries within the timeout complete
+    let interrupt = QueryInterrupt::new(Duration::from_secs(60));
+    let res = run_interruptible(&mut conn, "count", &interrupt, count_rows);
+    assert_eq!(res.unwrap(), 100000);
+
+    // queries exceeding the timeout are interrupted
+    let interrupt = QueryInterrupt::new(Duration::ZERO);
+    let res = run_interruptible(&mut conn, "count", &interrupt, count_rows);
+    assert!(matches!(res, Err(DatabaseError::QueryTimeout { query: "count", .. })));
+
+    // queries of dropped callers are not run
+    let interrupt = QueryInterrupt::new(Duration::from_secs(60));
+    drop(interrupt.cancel_on_drop());
+    let res = run_interruptible(&mut conn, "count", &interrupt, count_rows);
+    assert!(matches!(res, Err(DatabaseError::QueryCancelled("count"))));
+
+    // the progress handler is removed once the query completes
+    let res = count_rows(&mut conn);
+    assert_eq!(res.unwrap(), 100000);
+}
+
+#[cfg(feature = "query-profiling")]
+#[test]
+fn test_statement_parameter_shapes() {
+    use std::rc::Rc;
+
+    use rusqlite::{params, types::Value};
+
+    use super::profiling::parameter_shapes;
+
+    let prefixes: Vec<Value> = vec![Value::Integer(1), Value::Integer(2)];
+    let nullifier = num_to_rpo_digest(1);
+
+    assert_eq!(parameter_shapes(params![]), "");
+    assert_eq!(
+        parameter_shapes(params![7u32, nullifier.as_bytes(), "tag", Rc::new(prefixes)]),
+        "?1: integer, ?2: blob(32), ?3: text(3), ?4: array(2)"
+    );
+}
+
 // UTILITIES
 // -------------------------------------------------------------------------------------------
 fn num_to_rpo_digest(n: u64) -> RpoDigest {
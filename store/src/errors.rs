@@ -1,4 +1,4 @@
-use std::io;
+use std::{io, path::PathBuf, time::Duration};
 
 use deadpool_sqlite::PoolError;
 use miden_crypto::{
@@ -7,6 +7,7 @@ use miden_crypto::{
     utils::DeserializationError,
 };
 use miden_node_proto::{block_header::BlockHeader, errors::ParseError};
+use miden_node_utils::tag_policy::TagPolicyError;
 use prost::DecodeError;
 use rusqlite::types::FromSqlError;
 use thiserror::Error;
@@ -49,6 +50,15 @@ pub enum DatabaseError {
     NullifierDecodingError(DeserializationError),
     #[error("Block applying was broken because of closed channel on state side: {0}")]
     ApplyBlockFailedClosedChannel(RecvError),
+    #[error("{query} query interrupted after exceeding the timeout of {timeout:?}")]
+    QueryTimeout {
+        query: &'static str,
+        timeout: Duration,
+    },
+    #[error("{0} query cancelled by the caller")]
+    QueryCancelled(&'static str),
+    #[error("Malformed note tree stored for block {0}")]
+    MalformedNoteTree(u32),
 }
 
 // INITIALIZATION ERRORS
@@ -60,16 +70,39 @@ pub enum StateInitializationError {
     DatabaseError(#[from] DatabaseError),
     #[error("Conversion error: {0}")]
     ConversionError(#[from] ConversionError),
-    #[error("Failed to create nullifiers tree: {0}")]
-    FailedToCreateNullifiersTree(MerkleError),
-    #[error("Failed to create accounts tree: {0}")]
-    FailedToCreateAccountsTree(MerkleError),
     #[error("Failed to create chain MMR: {0}")]
     FailedToCreateChainMmr(ParseError),
+    #[error("Genesis block header is missing from the database")]
+    GenesisBlockHeaderMissing,
+}
+
+#[derive(Debug, Error)]
+pub enum DataDirectoryError {
+    #[error("Failed to create directory {path:?}: {error}")]
+    CreateFailed { path: PathBuf, error: io::Error },
+    #[error("Failed to lock data directory with {path:?}: {error}")]
+    LockFailed { path: PathBuf, error: io::Error },
+    #[error("Data directory is already in use by another store instance (pid {owner_pid}), locked with {path:?}")]
+    AlreadyInUse { path: PathBuf, owner_pid: String },
+}
+
+#[derive(Debug, Error)]
+pub enum DiskLimitError {
+    #[error("Dataset of {size_mb}MB exceeds the limit of {max_size_mb}MB")]
+    DatasetTooLarge { size_mb: u64, max_size_mb: u64 },
+    #[error("Only {free_space_mb}MB left on disk, under the limit of {min_free_space_mb}MB")]
+    NotEnoughFreeSpace {
+        free_space_mb: u64,
+        min_free_space_mb: u64,
+    },
+    #[error("Failed to measure the disk usage: {0}")]
+    MeasurementFailed(#[from] io::Error),
 }
 
 #[derive(Debug, Error)]
 pub enum DatabaseSetupError {
+    #[error("Data directory error: {0}")]
+    DataDirectoryError(#[from] DataDirectoryError),
     #[error("Database error: {0}")]
     DatabaseError(#[from] DatabaseError),
     #[error("Genesis block error: {0}")]
@@ -100,6 +133,11 @@ pub enum GenesisError {
     },
     #[error("Malformed genesis state: {0}")]
     MalformedGenesisState(MerkleError),
+    #[error("Genesis file defines chain id {genesis_chain_id}, but the store is configured for chain id {expected}")]
+    ChainIdMismatch {
+        expected: u32,
+        genesis_chain_id: u32,
+    },
     #[error("Retrieving genesis block header failed: {0}")]
     SelectBlockHeaderByBlockNumError(Box<DatabaseError>),
 }
@@ -125,12 +163,65 @@ pub enum ApplyBlockError {
     NewBlockInvalidAccountRoot,
     #[error("Received invalid note root")]
     NewBlockInvalidNoteRoot,
+    #[error("Received invalid batch root, not committing to the proofs of the batches")]
+    NewBlockInvalidBatchRoot,
+    #[error("Received invalid proof hash, not committing to the proofs of the batches")]
+    NewBlockInvalidProofHash,
+    #[error("Proof of batch {batch_index} of the block is invalid: {error}")]
+    InvalidBatchProof {
+        batch_index: u32,
+        error: BatchProofError,
+    },
+    #[error("Proof of batch {batch_index} is out of order, or beyond the batches of the block")]
+    BatchProofOutOfOrder { batch_index: u32 },
+    #[error("Batch {batch_index} of the block is not proven")]
+    UnprovenBatch { batch_index: u32 },
+    #[error("Block updates {num_accounts} accounts, more than its {num_txs} proven transactions")]
+    UnprovenTransactions { num_accounts: usize, num_txs: usize },
+    #[error("Proof of batch {batch_index} is of an empty batch")]
+    EmptyProvenBatch { batch_index: u32 },
+    #[error("Notes created by batch {batch_index} are not the notes of its proof")]
+    BatchProofNotesMismatch { batch_index: u32 },
+    #[error("Block is of chain {block_chain_id}, but the store is of chain {expected}")]
+    ChainIdMismatch { expected: u32, block_chain_id: u32 },
+    #[error("Batch proof verification task failed: {0}")]
+    BatchProofVerificationTaskFailed(String),
+    #[error("Block staging task failed: {0}")]
+    BlockStagingTaskFailed(String),
     #[error("Duplicated nullifiers {0:?}")]
     DuplicatedNullifiers(Vec<RpoDigest>),
+    #[error("State of account {0:#018x} is malformed, or doesn't match its hash in the block")]
+    InvalidAccountDetails(u64),
+    #[error(
+        "New block timestamp {timestamp} is before the genesis block timestamp {genesis_timestamp}"
+    )]
+    NewBlockTimestampBeforeGenesis {
+        timestamp: u64,
+        genesis_timestamp: u64,
+    },
+    #[error("New block timestamp {timestamp} is more than {max_skew_s}s ahead of the store's clock ({now})")]
+    NewBlockTimestampInFuture {
+        timestamp: u64,
+        now: u64,
+        max_skew_s: u64,
+    },
+    #[error("Note {note_index} of the block is invalid: {error}")]
+    InvalidNoteTag {
+        note_index: u32,
+        error: TagPolicyError,
+    },
+    #[error("Block signature is malformed, or is not a signature of the block")]
+    InvalidBlockSignature,
+    #[error("Timestamp attestation is malformed, or is not an attestation of the block")]
+    InvalidTimestampAttestation,
+    #[error("Consumed note linked to nullifier {0} which is not produced by the block")]
+    ConsumedNoteNullifierNotInBlock(RpoDigest),
     #[error("Unable to create proof for note: {0}")]
     UnableToCreateProofForNote(MerkleError),
     #[error("Block applying was broken because of closed channel on database side: {0}")]
     BlockApplyingBrokenBecauseOfClosedChannel(RecvError),
+    #[error("Database update task failed: {0}")]
+    DbUpdateTaskFailed(String),
     #[error("Failed to create notes tree: {0}")]
     FailedToCreateNotesTree(MerkleError),
     #[error("Received invalid account id")]
@@ -139,6 +230,25 @@ pub enum ApplyBlockError {
     DbBlockHeaderEmpty,
     #[error("Failed to get MMR peaks for forest ({forest}): {error}")]
     FailedToGetMmrPeaksForForest { forest: usize, error: MmrError },
+    #[error("Block {0} is already proposed and waiting to be committed")]
+    ProposalPending(RpoDigest),
+    #[error("Block {0} was not proposed, or was aborted")]
+    ProposalNotFound(RpoDigest),
+    #[error("Block {0} was not committed within the proposal timeout, and was aborted")]
+    ProposalExpired(RpoDigest),
+    #[error("Store is read-only until its disk usage is back under the limits: {0}")]
+    DiskLimitExceeded(#[from] DiskLimitError),
+}
+
+// BATCH PROOF ERRORS
+// =================================================================================================
+
+#[derive(Error, Debug)]
+pub enum BatchProofError {
+    #[error("Malformed batch proof: {0}")]
+    MalformedProof(DeserializationError),
+    #[error("Batch proof verification failed: {0}")]
+    VerificationFailed(String),
 }
 
 impl From<ParseError> for ApplyBlockError {
@@ -147,6 +257,14 @@ impl From<ParseError> for ApplyBlockError {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum GetBlockHeaderError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] DatabaseError),
+    #[error("Failed to create MMR proof of the block header: {0}")]
+    FailedToCreateMmrProof(MmrError),
+}
+
 #[derive(Error, Debug)]
 pub enum GetBlockInputsError {
     #[error("Database error: {0}")]
@@ -157,6 +275,46 @@ pub enum GetBlockInputsError {
     FailedToGetMmrPeaksForForest { forest: usize, error: MmrError },
     #[error("Chain MMR forest expected to be 1 less than latest header's block num. Chain MMR forest: {forest}, block num: {block_num}")]
     IncorrectChainMmrForestNumber { forest: usize, block_num: u32 },
+    #[error("Failed to create account proofs: {0}")]
+    FailedToCreateAccountProofs(MerkleError),
+}
+
+#[derive(Error, Debug)]
+pub enum GetNoteAuthenticationPathError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] DatabaseError),
+    #[error("Conversion error: {0}")]
+    ConversionError(#[from] ConversionError),
+    #[error("Block {0} not found")]
+    BlockNotFound(u32),
+    #[error("Note with index {note_index} not found in block {block_num}")]
+    NoteNotFound { block_num: u32, note_index: u32 },
+    #[error("Failed to rebuild notes tree: {0}")]
+    FailedToRebuildNotesTree(String),
+    #[error("Rebuilt notes tree root doesn't match the note root of block {0}")]
+    NoteRootMismatch(u32),
+    #[error("Unable to create proof for note: {0}")]
+    UnableToCreateProofForNote(MerkleError),
+}
+
+impl From<ParseError> for GetNoteAuthenticationPathError {
+    fn from(err: ParseError) -> Self {
+        GetNoteAuthenticationPathError::ConversionError(err.into())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum NoteRecoveryError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] DatabaseError),
+    #[error("Note {0} was not created by any block")]
+    NoteNotFound(RpoDigest),
+    #[error("Metadata of note {0} doesn't match the note created by the chain")]
+    MetadataMismatch(RpoDigest),
+    #[error("Note {0} is private, its details are not stored")]
+    PrivateNote(RpoDigest),
+    #[error("Failed to derive the note authentication path: {0}")]
+    NoteAuthenticationPathError(#[from] GetNoteAuthenticationPathError),
 }
 
 #[derive(Error, Debug)]
@@ -167,4 +325,26 @@ pub enum StateSyncError {
     EmptyBlockHeadersTable,
     #[error("Failed to build MMR delta: {0}")]
     FailedToBuildMmrDelta(MmrError),
+    #[error("Failed to derive note authentication paths: {0}")]
+    NoteAuthenticationPathError(#[from] GetNoteAuthenticationPathError),
+    #[error("Conversion error: {0}")]
+    ConversionError(#[from] ConversionError),
+    #[error("Resumption token was issued for other filters")]
+    SyncFiltersMismatch,
+    #[error("Block {0} of the resumption token is no longer part of the chain")]
+    SyncBlockNotInChain(u32),
+}
+
+#[derive(Debug, Error)]
+pub enum SyncTokenError {
+    #[error("Malformed resumption token")]
+    Malformed,
+    #[error("Resumption token version {0} is not supported")]
+    UnsupportedVersion(u8),
+}
+
+impl From<ParseError> for StateSyncError {
+    fn from(err: ParseError) -> Self {
+        StateSyncError::ConversionError(err.into())
+    }
 }
@@ -1,3 +1,5 @@
+use std::{fs, path::Path};
+
 use miden_crypto::merkle::{EmptySubtreeRoots, MerkleError, MmrPeaks, SimpleSmt, TieredSmt};
 use miden_objects::{
     accounts::Account,
@@ -6,6 +8,8 @@ use miden_objects::{
     BlockHeader, Digest, ACCOUNT_TREE_DEPTH,
 };
 
+use crate::errors::GenesisError;
+
 pub const GENESIS_BLOCK_NUM: u32 = 0;
 
 /// Represents the state at genesis, which will be used to derive the genesis block.
@@ -14,6 +18,10 @@ pub struct GenesisState {
     pub accounts: Vec<Account>,
     pub version: u64,
     pub timestamp: u64,
+    /// Identifier of the chain, used to prevent components of different networks from being
+    /// connected to each other. `None` if the genesis file was written before the chain ids, such
+    /// a file is still read but isn't bound to a chain.
+    pub chain_id: Option<u32>,
 }
 
 impl GenesisState {
@@ -21,14 +29,27 @@ impl GenesisState {
         accounts: Vec<Account>,
         version: u64,
         timestamp: u64,
+        chain_id: u32,
     ) -> Self {
         Self {
             accounts,
             version,
             timestamp,
+            chain_id: Some(chain_id),
         }
     }
 
+    /// Reads the genesis state from the genesis file at `genesis_filepath`.
+    pub fn read_from_file(genesis_filepath: &Path) -> Result<Self, GenesisError> {
+        let file_contents =
+            fs::read(genesis_filepath).map_err(|error| GenesisError::FailedToReadGenesisFile {
+                genesis_filepath: genesis_filepath.display().to_string(),
+                error,
+            })?;
+
+        Self::read_from_bytes(&file_contents).map_err(GenesisError::GenesisFileDeserializationError)
+    }
+
     /// Returns the block header and the account SMT
     pub fn into_block_parts(
         self
@@ -73,17 +94,50 @@ impl Serializable for GenesisState {
 
         target.write_u64(self.version);
         target.write_u64(self.timestamp);
+        if let Some(chain_id) = self.chain_id {
+            target.write_u32(chain_id);
+        }
     }
 }
 
 impl Deserializable for GenesisState {
+    /// Reads a genesis state, either with its chain id, or as written before the chain ids, which
+    /// ends with the timestamp.
     fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
         let num_accounts = source.read_u64()? as usize;
         let accounts = Account::read_batch_from(source, num_accounts)?;
 
         let version = source.read_u64()?;
         let timestamp = source.read_u64()?;
+        let chain_id = if source.has_more_bytes() {
+            Some(source.read_u32()?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            accounts,
+            version,
+            timestamp,
+            chain_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genesis_state_without_chain_id() {
+        let genesis = GenesisState::new(Vec::new(), 1, 1_700_000_000, 7);
+        let bytes = genesis.to_bytes();
+        assert_eq!(GenesisState::read_from_bytes(&bytes).unwrap(), genesis);
 
-        Ok(Self::new(accounts, version, timestamp))
+        // the files written before the chain ids end with the timestamp
+        let legacy = GenesisState::read_from_bytes(&bytes[..bytes.len() - 4]).unwrap();
+        assert_eq!(legacy.chain_id, None);
+        assert_eq!(legacy.timestamp, genesis.timestamp);
+        assert_eq!(legacy.to_bytes(), bytes[..bytes.len() - 4]);
     }
 }
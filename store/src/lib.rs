@@ -1,11 +1,95 @@
+use std::time::Duration;
+
+pub mod backfill;
+pub mod batch_kernel;
+pub mod commitment;
 pub mod config;
+pub mod consistency;
+pub mod data_directory;
 pub mod db;
 pub mod errors;
 pub mod genesis;
+pub mod note_tree;
 pub mod server;
+pub mod startup;
 pub mod state;
+pub mod subscription;
+pub mod sync_token;
 pub mod types;
 
 // CONSTANTS
 // =================================================================================================
 pub const COMPONENT: &str = "miden-store";
+
+/// Number of nullifiers the chain may have beyond its number of notes before a block is flagged as
+/// suspicious, to account for the notes which are not known to the store
+pub const SUSPICIOUS_NULLIFIER_ALLOWANCE: u64 = 1000;
+
+/// Number of blocks processed by a single batch of a backfill
+const BACKFILL_BATCH_SIZE: usize = 100;
+
+/// Time between two batches of a backfill, leaving the database to the requests
+const BACKFILL_BATCH_PAUSE: Duration = Duration::from_millis(100);
+
+/// Time before a backfill batch which failed is retried
+const BACKFILL_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Number of applied blocks buffered for the nullifier subscriptions, a subscription falling
+/// further behind catches up from the nullifier tree
+const NULLIFIER_SUBSCRIPTION_BUFFER: usize = 64;
+
+/// Number of notifications buffered for a client of a nullifier subscription
+const NULLIFIER_SUBSCRIPTION_CHANNEL_SIZE: usize = 16;
+
+/// Maximum number of nullifiers watched by a single subscription
+pub const MAX_SUBSCRIBED_NULLIFIERS: usize = 1000;
+
+/// Number of latest blocks whose notes are counted by tag, to report the recent activity of the
+/// tags. The migration introducing the counts fills the same window from the existing notes
+pub const NOTE_TAG_STATS_WINDOW: u32 = 256;
+
+/// Maximum number of tags whose activity is requested at once
+pub const MAX_NOTE_TAG_STATS_TAGS: usize = 1000;
+
+/// Maximum number of notes listed at once by `GetUnconsumedNotes`, unless a single block holds more
+pub const MAX_UNCONSUMED_NOTES: usize = 1000;
+
+/// Maximum number of tags whose unconsumed notes are listed at once
+pub const MAX_UNCONSUMED_NOTES_TAGS: usize = 1000;
+
+/// Maximum number of notes requested at once from the proposed block by `GetProposedNotes`
+pub const MAX_PROPOSED_NOTES_HASHES: usize = 1000;
+
+/// Maximum number of accounts synced at once by `SyncAccounts`
+pub const MAX_SYNCED_ACCOUNTS: usize = 1000;
+
+/// Maximum number of blocks applied at once by `ApplyBlocks`
+pub const MAX_APPLY_BLOCKS: usize = 1000;
+
+/// Operation signed by an operator of the store to apply blocks with `ApplyBlocks`, see
+/// [miden_node_utils::operator_auth].
+pub const APPLY_BLOCKS_OPERATION: &str = "ApplyBlocks";
+
+/// Operation signed by an operator of the store to read its disk usage with `GetDatabaseSize`.
+pub const GET_DATABASE_SIZE_OPERATION: &str = "GetDatabaseSize";
+
+/// Operation signed by an operator of the store to export notes with `ExportNotes`.
+pub const EXPORT_NOTES_OPERATION: &str = "ExportNotes";
+
+/// Operation signed by an operator of the store to read the progress of the backfills with
+/// `GetBackfillStatus`.
+pub const GET_BACKFILL_STATUS_OPERATION: &str = "GetBackfillStatus";
+
+/// Operation signed by an operator of the store to read the counters of the chain with
+/// `GetChainCounters`.
+pub const GET_CHAIN_COUNTERS_OPERATION: &str = "GetChainCounters";
+
+/// Maximum size in bytes of a serialized note submitted as a recovery record
+pub const MAX_NOTE_RECOVERY_RECORD_SIZE: usize = 16 * 1024;
+
+/// Maximum number of recovery records requested at once
+pub const MAX_NOTE_RECOVERY_RECORDS: usize = 1000;
+
+/// Number of rows inserted into the trees of the state between two updates of the startup
+/// progress, see [startup]
+const STARTUP_CHUNK_SIZE: usize = 10_000;
@@ -1,36 +1,154 @@
 mod cli;
-use anyhow::{anyhow, Result};
+use std::{fs, path::Path, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
 use clap::Parser;
-use cli::{Cli, Command, Query};
+use cli::{Cli, Command, ExportNotesArgs, Query};
 use hex::ToHex;
-use miden_crypto::merkle::{path_to_text, TieredSmtProof};
+use miden_crypto::{
+    dsa::rpo_falcon512::KeyPair,
+    merkle::{path_to_text, TieredSmtProof},
+};
 use miden_node_proto::{
     account::AccountId,
+    chain_id::ChainIdInterceptor,
     requests::{
-        CheckNullifiersRequest, GetBlockHeaderByNumberRequest, GetBlockInputsRequest,
+        CheckNullifiersRequest, ExportNotesRequest, GetBlockHeaderByNumberRequest,
+        GetBlockInputsRequest, GetNodeInfoRequest, GetNoteAuthenticationPathRequest,
         GetTransactionInputsRequest, ListAccountsRequest, ListNotesRequest, ListNullifiersRequest,
         SyncStateRequest,
     },
     store::api_client,
     tsmt::NullifierProof,
 };
-use miden_node_store::{config::StoreTopLevelConfig, db::Db, server};
-use miden_node_utils::config::load_config;
+use miden_node_store::{
+    backfill,
+    config::{StoreConfig, StoreTopLevelConfig},
+    db::Db,
+    server,
+    state::State,
+    EXPORT_NOTES_OPERATION,
+};
+use miden_node_utils::{config::load_config, grpc, operator_auth::sign_request};
 use miden_objects::BlockHeader;
+use prost::Message;
+use tonic::{service::interceptor::InterceptedService, transport::Channel};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     miden_node_utils::logging::setup_logging()?;
 
     let cli = Cli::parse();
-    let config: StoreTopLevelConfig = load_config(cli.config.as_path()).extract()?;
-    let db = Db::setup(config.store.clone()).await?;
+    let mut config: StoreTopLevelConfig = load_config(cli.config.as_path()).extract()?;
+    if let Some(data_directory) = cli.data_directory {
+        config.store.data_directory = data_directory;
+    }
 
     match cli.command {
         Command::Serve { .. } => {
+            let db = Db::setup(config.store.clone()).await?;
             server::serve(config.store, db).await?;
         },
         Command::Query(command) => query(config, command).await?,
+        Command::ExportNotes(args) => export_notes(config, args).await?,
+        Command::Backfill => run_backfills(config.store).await?,
+    }
+
+    Ok(())
+}
+
+/// Exports the notes specified by `args` from the store at the endpoint defined in `config`, and
+/// writes the archive to `args.output`, or to the exports directory of the store's data directory.
+async fn export_notes(
+    config: StoreTopLevelConfig,
+    args: ExportNotesArgs,
+) -> Result<()> {
+    // the archive of a large range of blocks exceeds the default limit of the messages size
+    let mut client = connect(&config.store).await?.max_decoding_message_size(usize::MAX);
+
+    let mut request = tonic::Request::new(ExportNotesRequest {
+        note_tags: args.note_tags,
+        from_block: args.from_block,
+        to_block: args.to_block,
+    });
+    let operator = load_key(&args.operator_key_file)?;
+    sign_request(&mut request, &operator, config.store.chain_id, EXPORT_NOTES_OPERATION)?;
+    let archive = client
+        .export_notes(request)
+        .await?
+        .into_inner()
+        .archive
+        .ok_or(anyhow!("Store returned no archive"))?;
+
+    let output = match args.output {
+        Some(output) => output,
+        None => {
+            let exports_dir = config.store.data_directory().exports_dir();
+            fs::create_dir_all(&exports_dir).with_context(|| {
+                format!("Failed to create exports directory {}", exports_dir.display())
+            })?;
+            exports_dir.join(format!("notes-{}-{}.bin", args.from_block, args.to_block))
+        },
+    };
+    fs::write(&output, archive.encode_to_vec())
+        .with_context(|| format!("Failed to write archive to {}", output.display()))?;
+    println!(
+        "Exported {} notes of {} blocks to {}",
+        archive.notes.len(),
+        archive.block_headers.len(),
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Connects to the store at the endpoint defined in `config`, attaching its chain id to every
+/// request.
+async fn connect(
+    config: &StoreConfig
+) -> Result<api_client::ApiClient<InterceptedService<Channel, ChainIdInterceptor>>> {
+    let channel = grpc::connect(&config.as_url()).await?;
+
+    Ok(api_client::ApiClient::with_interceptor(
+        channel,
+        ChainIdInterceptor::new(config.chain_id),
+    ))
+}
+
+/// Loads a signing key from `key_file`, which holds the hex encoded seed of the key.
+fn load_key(key_file: &Path) -> Result<KeyPair> {
+    let seed = fs::read_to_string(key_file)
+        .with_context(|| format!("Failed to read key file {}", key_file.display()))?;
+    let seed = seed.trim();
+    let seed = hex::decode(seed.strip_prefix("0x").unwrap_or(seed))
+        .with_context(|| format!("Key file {} is not valid hex", key_file.display()))?;
+
+    KeyPair::from_seed(&seed)
+        .map_err(|err| anyhow!("Invalid key seed in {}: {err:?}", key_file.display()))
+}
+
+/// Runs the backfills of the store's database to completion, instead of in the background of a
+/// running store.
+async fn run_backfills(config: StoreConfig) -> Result<()> {
+    config.tag_policy.validate()?;
+    config.note_visibility.validate()?;
+
+    let db = Db::setup(config.clone()).await?;
+    let state = Arc::new(
+        State::load(
+            db,
+            config.max_block_timestamp_skew_s,
+            Duration::from_millis(config.proposal_timeout_ms),
+            config.tag_policy.clone(),
+            config.note_visibility.clone(),
+            config.verify_batch_proofs,
+        )
+        .await?,
+    );
+    backfill::run(state.clone(), Duration::ZERO).await;
+
+    for progress in state.backfills().await? {
+        println!("{}: completed {}", progress.name, progress.completed);
     }
 
     Ok(())
@@ -43,18 +161,23 @@ async fn query(
     config: StoreTopLevelConfig,
     command: Query,
 ) -> Result<()> {
-    let mut client = api_client::ApiClient::connect(config.store.endpoint.to_string()).await?;
+    let mut client = connect(&config.store).await?;
 
     match command {
         Query::GetBlockHeaderByNumber(args) => {
             let request = tonic::Request::new(GetBlockHeaderByNumberRequest {
                 block_num: args.block_num,
+                include_mmr_proof: Some(args.include_mmr_proof),
+                if_none_match: None,
             });
             let response = client.get_block_header_by_number(request).await?.into_inner();
             match response.block_header {
                 Some(block_header) => {
                     let block_header: BlockHeader = block_header.try_into()?;
                     println!("{block_header:?}");
+                    if let Some(chain_length) = response.chain_length {
+                        println!("chain_length: {chain_length} mmr_path: {:?}", response.mmr_path);
+                    }
                 },
                 None => match args.block_num {
                     Some(block_num) => {
@@ -70,6 +193,7 @@ async fn query(
         Query::CheckNullifiers(args) => {
             let request = tonic::Request::new(CheckNullifiersRequest {
                 nullifiers: args.nullifiers.clone(),
+                nullifiers_without_proofs: args.nullifiers_without_proofs.clone(),
             });
             let response = client.check_nullifiers(request).await?.into_inner();
             let proofs = response
@@ -90,6 +214,11 @@ async fn query(
                     Err(e) => println!("{} {:?}", nullifier.encode_hex::<String>(), e),
                 }
             }
+            for (block_num, nullifier) in
+                response.block_nums.iter().zip(args.nullifiers_without_proofs.iter())
+            {
+                println!("{} block_num: {}", nullifier.encode_hex::<String>(), block_num);
+            }
             Ok(())
         },
         Query::SyncState(args) => {
@@ -98,11 +227,22 @@ async fn query(
                 account_ids: args.account_ids.iter().map(|&id| AccountId { id }).collect(),
                 note_tags: args.note_tags.clone(),
                 nullifiers: args.nullifiers.clone(),
+                note_hashes: args.note_hashes.clone(),
+                resume_token: Vec::new(),
             });
             let response = client.sync_state(request).await?.into_inner();
             println!("{:?}", response);
             Ok(())
         },
+        Query::GetNoteAuthenticationPath(args) => {
+            let request = tonic::Request::new(GetNoteAuthenticationPathRequest {
+                block_num: args.block_num,
+                note_index: args.note_index,
+            });
+            let response = client.get_note_authentication_path(request).await?.into_inner();
+            println!("{:?}", response);
+            Ok(())
+        },
         Query::GetBlockInputs(args) => {
             let request = tonic::Request::new(GetBlockInputsRequest {
                 account_ids: args.account_ids.iter().map(|&id| AccountId { id }).collect(),
@@ -118,11 +258,18 @@ async fn query(
                     id: args.account_id,
                 }),
                 nullifiers: args.nullifiers.clone(),
+                block_ref: None,
             });
             let response = client.get_transaction_inputs(request).await?.into_inner();
             println!("{:?}", response);
             Ok(())
         },
+        Query::GetNodeInfo => {
+            let request = tonic::Request::new(GetNodeInfoRequest {});
+            let response = client.get_node_info(request).await?.into_inner();
+            println!("{:?}", response);
+            Ok(())
+        },
         Query::ListNullifiers => {
             let request = tonic::Request::new(ListNullifiersRequest {});
             let response = client.list_nullifiers(request).await?.into_inner();
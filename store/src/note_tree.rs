@@ -0,0 +1,350 @@
+//! Compact storage of the note trees of the blocks.
+//!
+//! Instead of keeping a full merkle path per note, the store keeps every block's note tree once,
+//! and derives the paths of the notes from it on demand.
+//!
+//! The note tree of a block is made of the note trees of its batches: the tree of the batch
+//! `batch_idx` is the subtree rooted at depth [BATCH_TREE_DEPTH], at index `batch_idx`. The note
+//! `i` of the batch has the index `batch_idx * MAX_NOTES_PER_BATCH + i` in the block, and its hash
+//! and metadata are the leaves `2 * index` and `2 * index + 1` of the tree, see [note_leaf_index].
+use std::{collections::BTreeMap, iter};
+
+use miden_crypto::{
+    hash::rpo::{Rpo256, RpoDigest},
+    merkle::{EmptySubtreeRoots, MerklePath, NodeIndex, SimpleSmt},
+};
+use miden_objects::notes::NOTE_LEAF_DEPTH;
+
+/// Depth of the roots of the note trees of the batches within the note tree of a block, i.e. a
+/// block has at most `2^BATCH_TREE_DEPTH` batches.
+pub const BATCH_TREE_DEPTH: u8 = 8;
+
+/// Depth of the note tree of a batch.
+const BATCH_NOTE_TREE_DEPTH: u8 = NOTE_LEAF_DEPTH - BATCH_TREE_DEPTH;
+
+/// Maximum number of notes created by a batch, each one taking two leaves of its tree.
+pub const MAX_NOTES_PER_BATCH: u32 = 1 << (BATCH_NOTE_TREE_DEPTH - 1);
+
+/// Returns the index of the leaf holding the hash of the note at `note_index` in its block, the
+/// metadata of the note being the next leaf.
+pub fn note_leaf_index(note_index: u32) -> u64 {
+    2 * note_index as u64
+}
+
+// BLOCK NOTE TREE
+// ================================================================================================
+
+/// The note tree of a block, keeping only the nodes above the populated leaves of its batches.
+///
+/// The notes of a batch occupy the leftmost leaves of its tree, every node to the right of the
+/// last populated leaf of a batch is the root of an empty subtree and is not stored, nor are the
+/// trees of the batches which created no note. A batch with `n` leaves keeps at most
+/// `2n + BATCH_NOTE_TREE_DEPTH` nodes, regardless of the number of paths requested.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockNoteTree {
+    /// The trees of the batches which created notes, by batch index.
+    batches: BTreeMap<u64, BatchNoteTree>,
+    /// The nodes above the roots of the batch trees, by depth and index, derived from the roots.
+    upper_nodes: BTreeMap<(u8, u64), RpoDigest>,
+}
+
+/// The note tree of a batch, see [BlockNoteTree].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BatchNoteTree {
+    /// Number of leaves covered by the stored nodes, i.e. the index of the last populated leaf of
+    /// the batch plus one.
+    num_leaves: u64,
+    /// The stored nodes, level by level from the leaves up to the root of the batch tree, left to
+    /// right.
+    nodes: Vec<RpoDigest>,
+}
+
+impl BlockNoteTree {
+    /// Keeps the nodes of `tree` above the populated leaves of its batches.
+    pub fn from_tree(tree: &SimpleSmt<NOTE_LEAF_DEPTH>) -> Self {
+        let mut batch_leaves: BTreeMap<u64, u64> = BTreeMap::new();
+        for (index, _) in tree.leaves() {
+            let (batch_idx, leaf_idx) = split_leaf_index(index);
+            let num_leaves = batch_leaves.entry(batch_idx).or_default();
+            *num_leaves = (*num_leaves).max(leaf_idx + 1);
+        }
+
+        let batches = batch_leaves
+            .into_iter()
+            .map(|(batch_idx, num_leaves)| {
+                let mut nodes = Vec::new();
+                for (depth, width) in level_widths(num_leaves) {
+                    let first = batch_idx << (depth - BATCH_TREE_DEPTH);
+                    nodes.extend((first..first + width).map(|index| {
+                        let index =
+                            NodeIndex::new(depth, index).expect("node index is within the tree");
+                        tree.get_node(index).expect("node index is within the tree")
+                    }));
+                }
+
+                (batch_idx, BatchNoteTree { num_leaves, nodes })
+            })
+            .collect();
+
+        Self::from_batches(batches)
+    }
+
+    /// Reassembles a tree from its parts, as returned by [Self::batches] and [Self::nodes].
+    ///
+    /// Returns `None` if the batches are not sorted by index, or if the number of nodes doesn't
+    /// match their number of leaves.
+    pub fn from_parts(
+        batches: &[(u64, u64)],
+        nodes: Vec<RpoDigest>,
+    ) -> Option<Self> {
+        let mut nodes = nodes.into_iter();
+        let mut batch_trees = BTreeMap::new();
+        for &(batch_idx, num_leaves) in batches {
+            let unsorted = batch_trees.last_key_value().is_some_and(|(&last, _)| last >= batch_idx);
+            if unsorted
+                || batch_idx >= 1 << BATCH_TREE_DEPTH
+                || num_leaves == 0
+                || num_leaves > 1 << BATCH_NOTE_TREE_DEPTH
+            {
+                return None;
+            }
+
+            let num_nodes: u64 = level_widths(num_leaves).map(|(_, width)| width).sum();
+            let batch_nodes: Vec<RpoDigest> = nodes.by_ref().take(num_nodes as usize).collect();
+            if batch_nodes.len() as u64 != num_nodes {
+                return None;
+            }
+            batch_trees.insert(
+                batch_idx,
+                BatchNoteTree {
+                    num_leaves,
+                    nodes: batch_nodes,
+                },
+            );
+        }
+
+        nodes.next().is_none().then(|| Self::from_batches(batch_trees))
+    }
+
+    /// Returns the index and the number of leaves covered by the stored nodes of every batch which
+    /// created notes, by batch index.
+    pub fn batches(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.batches.iter().map(|(&batch_idx, batch)| (batch_idx, batch.num_leaves))
+    }
+
+    /// Returns the stored nodes of every batch, by batch index, each one level by level from the
+    /// leaves up to the root of the batch tree.
+    pub fn nodes(&self) -> impl Iterator<Item = &RpoDigest> {
+        self.batches.values().flat_map(|batch| batch.nodes.iter())
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> RpoDigest {
+        self.upper_node(0, 0)
+    }
+
+    /// Returns `true` if the leaf at `index` is populated.
+    pub fn contains_leaf(
+        &self,
+        index: u64,
+    ) -> bool {
+        let (batch_idx, leaf_idx) = split_leaf_index(index);
+        self.batches.get(&batch_idx).is_some_and(|batch| {
+            leaf_idx < batch.num_leaves
+                && batch.nodes[leaf_idx as usize]
+                    != *EmptySubtreeRoots::entry(NOTE_LEAF_DEPTH, NOTE_LEAF_DEPTH)
+        })
+    }
+
+    /// Returns the authentication path of the leaf at `index`, or `None` if `index` is outside the
+    /// tree.
+    pub fn open(
+        &self,
+        index: u64,
+    ) -> Option<MerklePath> {
+        if index >= 1 << NOTE_LEAF_DEPTH {
+            return None;
+        }
+
+        let (batch_idx, leaf_idx) = split_leaf_index(index);
+        let mut siblings = Vec::with_capacity(NOTE_LEAF_DEPTH as usize);
+
+        // the path within the tree of the batch
+        let mut offset = 0;
+        let mut position = leaf_idx;
+        let batch = self.batches.get(&batch_idx);
+        let num_leaves = batch.map_or(0, |batch| batch.num_leaves);
+        for (depth, width) in level_widths(num_leaves).take(BATCH_NOTE_TREE_DEPTH as usize) {
+            let sibling = position ^ 1;
+            siblings.push(match batch {
+                Some(batch) if sibling < width => batch.nodes[(offset + sibling) as usize],
+                _ => *EmptySubtreeRoots::entry(NOTE_LEAF_DEPTH, depth),
+            });
+
+            offset += width;
+            position >>= 1;
+        }
+
+        // the path from the root of the batch tree up to the root of the block tree
+        let mut position = batch_idx;
+        for depth in (1..=BATCH_TREE_DEPTH).rev() {
+            siblings.push(self.upper_node(depth, position ^ 1));
+            position >>= 1;
+        }
+
+        Some(MerklePath::new(siblings))
+    }
+
+    /// Computes the nodes above the roots of the `batches` trees.
+    fn from_batches(batches: BTreeMap<u64, BatchNoteTree>) -> Self {
+        let mut upper_nodes: BTreeMap<(u8, u64), RpoDigest> = batches
+            .iter()
+            .map(|(&batch_idx, batch)| {
+                let root = *batch.nodes.last().expect("a batch tree has a root");
+                ((BATCH_TREE_DEPTH, batch_idx), root)
+            })
+            .collect();
+
+        let mut level: Vec<u64> = batches.keys().copied().collect();
+        for depth in (1..=BATCH_TREE_DEPTH).rev() {
+            let empty = *EmptySubtreeRoots::entry(NOTE_LEAF_DEPTH, depth);
+            let mut parents: Vec<u64> = level.iter().map(|index| index >> 1).collect();
+            parents.dedup();
+
+            for &parent in &parents {
+                let left = upper_nodes.get(&(depth, 2 * parent)).copied().unwrap_or(empty);
+                let right = upper_nodes.get(&(depth, 2 * parent + 1)).copied().unwrap_or(empty);
+                upper_nodes.insert((depth - 1, parent), Rpo256::merge(&[left, right]));
+            }
+            level = parents;
+        }
+
+        Self {
+            batches,
+            upper_nodes,
+        }
+    }
+
+    /// Returns the node at `depth` and `index`, with `depth` at most [BATCH_TREE_DEPTH].
+    fn upper_node(
+        &self,
+        depth: u8,
+        index: u64,
+    ) -> RpoDigest {
+        self.upper_nodes
+            .get(&(depth, index))
+            .copied()
+            .unwrap_or(*EmptySubtreeRoots::entry(NOTE_LEAF_DEPTH, depth))
+    }
+}
+
+/// Splits the index of a leaf of the tree of a block into the index of its batch, and the index
+/// of the leaf in the tree of the batch.
+fn split_leaf_index(index: u64) -> (u64, u64) {
+    (index >> BATCH_NOTE_TREE_DEPTH, index & ((1 << BATCH_NOTE_TREE_DEPTH) - 1))
+}
+
+/// Returns the depth and the number of stored nodes of every level of the tree of a batch with
+/// `num_leaves` leaves, from the leaves up to the root of the batch tree.
+fn level_widths(num_leaves: u64) -> impl Iterator<Item = (u8, u64)> {
+    let widths = iter::successors(Some(num_leaves), |width| Some(width.div_ceil(2)));
+    (BATCH_TREE_DEPTH..=NOTE_LEAF_DEPTH).rev().zip(widths)
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_crypto::{
+        merkle::{LeafIndex, SimpleSmt},
+        Felt, FieldElement, Word,
+    };
+    use miden_objects::notes::NOTE_LEAF_DEPTH;
+
+    use super::{note_leaf_index, BlockNoteTree, MAX_NOTES_PER_BATCH};
+
+    fn leaf(value: u64) -> Word {
+        [Felt::new(value), Felt::ZERO, Felt::ZERO, Felt::ZERO]
+    }
+
+    /// Returns the index of the leaf of the hash of the note `note_idx` of the batch `batch_idx`.
+    fn batch_leaf(
+        batch_idx: u32,
+        note_idx: u32,
+    ) -> u64 {
+        note_leaf_index(batch_idx * MAX_NOTES_PER_BATCH + note_idx)
+    }
+
+    #[test]
+    fn test_paths_match_full_tree() {
+        let batch_0 = vec![0, 1, 2, 3, 4];
+        let batches_0_1 = vec![0, 1, batch_leaf(1, 0), batch_leaf(1, 0) + 1];
+        let batches_1_3_255 = vec![
+            batch_leaf(1, 1),
+            batch_leaf(1, 1) + 1,
+            batch_leaf(3, 0),
+            batch_leaf(255, MAX_NOTES_PER_BATCH - 1) + 1,
+        ];
+        for indices in [vec![], vec![0], vec![1, 6, 9], batch_0, batches_0_1, batches_1_3_255] {
+            let tree = SimpleSmt::<NOTE_LEAF_DEPTH>::with_leaves(
+                indices.iter().map(|&index| (index, leaf(index + 1))),
+            )
+            .unwrap();
+            let note_tree = BlockNoteTree::from_tree(&tree);
+
+            assert_eq!(note_tree.root(), tree.root());
+
+            // populated leaves, empty leaves around them, and the leaves of the empty batches
+            let mut opened: Vec<u64> = indices
+                .iter()
+                .flat_map(|&index| [index.saturating_sub(1), index, index + 1, index + 2])
+                .chain([0, 1, batch_leaf(2, 0), batch_leaf(254, 3), (1 << NOTE_LEAF_DEPTH) - 1])
+                .filter(|&index| index < 1 << NOTE_LEAF_DEPTH)
+                .collect();
+            opened.sort();
+            opened.dedup();
+            for index in opened {
+                let expected = tree.open(&LeafIndex::new(index).unwrap()).path;
+                assert_eq!(note_tree.open(index), Some(expected), "path of leaf {index}");
+                assert_eq!(note_tree.contains_leaf(index), indices.contains(&index));
+            }
+
+            let batches: Vec<_> = note_tree.batches().collect();
+            let round_trip =
+                BlockNoteTree::from_parts(&batches, note_tree.nodes().copied().collect());
+            assert_eq!(round_trip, Some(note_tree));
+        }
+    }
+
+    #[test]
+    fn test_sparse_batches_keep_few_nodes() {
+        let leaves = [(batch_leaf(0, 0), leaf(1)), (batch_leaf(200, 0), leaf(2))];
+        let tree = SimpleSmt::<NOTE_LEAF_DEPTH>::with_leaves(leaves).unwrap();
+        let note_tree = BlockNoteTree::from_tree(&tree);
+
+        assert_eq!(note_tree.batches().collect::<Vec<_>>(), vec![(0, 1), (200, 1)]);
+        assert_eq!(note_tree.nodes().count(), 2 * (NOTE_LEAF_DEPTH as usize - 8 + 1));
+    }
+
+    #[test]
+    fn test_from_parts_rejects_malformed_trees() {
+        let leaves = [(2, leaf(1)), (batch_leaf(3, 0), leaf(2))];
+        let tree = SimpleSmt::<NOTE_LEAF_DEPTH>::with_leaves(leaves).unwrap();
+        let note_tree = BlockNoteTree::from_tree(&tree);
+        let batches: Vec<_> = note_tree.batches().collect();
+        let nodes: Vec<_> = note_tree.nodes().copied().collect();
+
+        let mut missing_node = nodes.clone();
+        missing_node.pop();
+        assert_eq!(BlockNoteTree::from_parts(&batches, missing_node), None);
+
+        let mut extra_node = nodes.clone();
+        extra_node.push(nodes[0]);
+        assert_eq!(BlockNoteTree::from_parts(&batches, extra_node), None);
+
+        let unsorted: Vec<_> = batches.iter().rev().copied().collect();
+        assert_eq!(BlockNoteTree::from_parts(&unsorted, nodes.clone()), None);
+        assert_eq!(BlockNoteTree::from_parts(&[(256, 1)], vec![]), None);
+        assert_eq!(BlockNoteTree::from_parts(&[(0, 0)], vec![]), None);
+        assert_eq!(BlockNoteTree::from_parts(&[], vec![]), Some(BlockNoteTree::default()));
+        assert!(note_tree.open(1 << NOTE_LEAF_DEPTH).is_none());
+    }
+}
@@ -1,45 +1,255 @@
-use std::sync::Arc;
+use std::{
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use miden_crypto::hash::rpo::RpoDigest;
 use miden_node_proto::{
     conversion::convert,
     digest::Digest,
     errors::ParseError,
+    note::NoteArchive,
     requests::{
-        ApplyBlockRequest, CheckNullifiersRequest, GetBlockHeaderByNumberRequest,
-        GetBlockInputsRequest, GetTransactionInputsRequest, ListAccountsRequest, ListNotesRequest,
-        ListNullifiersRequest, SyncStateRequest,
+        ApplyBlockRequest, ApplyBlocksRequest, CheckNullifiersRequest, CommitBlockRequest,
+        ExportNotesRequest, GetBackfillStatusRequest, GetBlockHeaderByNumberRequest,
+        GetBlockInputsRequest, GetChainCountersRequest, GetDatabaseSizeRequest,
+        GetGenesisBlockRequest, GetNodeInfoRequest, GetNoteAuthenticationPathRequest,
+        GetNoteRecoveryRecordsRequest, GetNoteTagStatsRequest, GetProposedNotesRequest,
+        GetPruningHorizonRequest, GetStartupProgressRequest, GetStateCommitmentRequest,
+        GetTransactionInputsRequest, GetUnconsumedNotesRequest, ListAccountsRequest,
+        ListNotesRequest, ListNullifiersRequest, SubmitNoteRecoveryRecordRequest,
+        SubscribeChainTipRequest, SubscribeNullifiersRequest, SyncAccountsRequest,
+        SyncStateRequest,
     },
     responses::{
-        ApplyBlockResponse, CheckNullifiersResponse, GetBlockHeaderByNumberResponse,
-        GetBlockInputsResponse, GetTransactionInputsResponse, ListAccountsResponse,
-        ListNotesResponse, ListNullifiersResponse, SyncStateResponse,
+        ApplyBlockResponse, ApplyBlocksResponse, BackfillStatus, CheckNullifiersResponse,
+        CommitBlockResponse, ExportNotesResponse, GetBackfillStatusResponse,
+        GetBlockHeaderByNumberResponse, GetBlockInputsResponse, GetChainCountersResponse,
+        GetDatabaseSizeResponse, GetGenesisBlockResponse, GetNodeInfoResponse,
+        GetNoteAuthenticationPathResponse, GetNoteRecoveryRecordsResponse, GetNoteTagStatsResponse,
+        GetProposedNotesResponse, GetPruningHorizonResponse, GetStartupProgressResponse,
+        GetStateCommitmentResponse, GetTransactionInputsResponse, GetUnconsumedNotesResponse,
+        ListAccountsResponse, ListNotesResponse, ListNullifiersResponse, ProposeBlockResponse,
+        SubmitNoteRecoveryRecordResponse, SubscribeChainTipResponse, SubscribeNullifiersResponse,
+        SyncAccountsResponse, SyncStateResponse,
     },
     store::api_server,
     tsmt::NullifierLeaf,
+    PROTOCOL_VERSION,
+};
+use miden_node_utils::{operator_auth::require_operator, tag_policy::TagPolicy};
+use miden_objects::{
+    notes::Note,
+    utils::serde::{Deserializable, Serializable},
+    BlockHeader,
 };
-use miden_objects::BlockHeader;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Response, Status};
 use tracing::{debug, info, instrument};
 
-use crate::{state::State, COMPONENT};
+use crate::{
+    backfill,
+    batch_kernel::BatchProof,
+    commitment,
+    config::StoreConfig,
+    data_directory::DataDirectory,
+    db::Db,
+    errors::{ApplyBlockError, GetNoteAuthenticationPathError, NoteRecoveryError, StateSyncError},
+    genesis::{GenesisState, GENESIS_BLOCK_NUM},
+    startup::StartupProgress,
+    state::{ChainTip, NewBlock, State},
+    subscription::{self, NullifierSubscription},
+    sync_token::SyncToken,
+    types::BlockNumber,
+    APPLY_BLOCKS_OPERATION, BACKFILL_BATCH_PAUSE, COMPONENT, EXPORT_NOTES_OPERATION,
+    GET_BACKFILL_STATUS_OPERATION, GET_CHAIN_COUNTERS_OPERATION, GET_DATABASE_SIZE_OPERATION,
+    MAX_APPLY_BLOCKS, MAX_NOTE_RECOVERY_RECORDS, MAX_NOTE_RECOVERY_RECORD_SIZE,
+    MAX_NOTE_TAG_STATS_TAGS, MAX_PROPOSED_NOTES_HASHES, MAX_SUBSCRIBED_NULLIFIERS,
+    MAX_SYNCED_ACCOUNTS, MAX_UNCONSUMED_NOTES_TAGS, NULLIFIER_SUBSCRIPTION_CHANNEL_SIZE,
+};
 
 // STORE API
 // ================================================================================================
 
+/// The endpoints of the store.
+///
+/// Served over gRPC by [super::serve_api], and called directly by a block producer running in the
+/// same process.
 pub struct StoreApi {
-    pub(super) state: Arc<State>,
+    /// State of the store, set once loaded by [StoreApi::finish_loading]
+    state: OnceLock<Arc<State>>,
+    /// Database of the store, read by the endpoints served while the state is loaded
+    db: Arc<Db>,
+    /// Progress of the state being loaded
+    progress: StartupProgress,
+    pub(super) chain_id: u32,
+    /// Number of blocks below the chain tip after which blocks are reported as final
+    pub(super) safe_depth: u32,
+    /// Data directory of the store, whose disk usage is reported to operators
+    pub(super) data_directory: DataDirectory,
+    /// Policy of the tags of the notes created by new blocks, reported while the state is loaded
+    tag_policy: TagPolicy,
+}
+
+impl StoreApi {
+    /// Loads the state of the store from `db`, and starts the backfills of the tables introduced
+    /// by the latest migrations, and the publication of the state commitments.
+    pub async fn load(
+        config: &StoreConfig,
+        db: Db,
+    ) -> Result<Self> {
+        let store = Self::new(config, db)?;
+        store.finish_loading(config).await?;
+
+        Ok(store)
+    }
+
+    /// Returns the store of `db` whose state is not loaded yet, see [StoreApi::finish_loading].
+    ///
+    /// Until then the block headers are read from the database, and the endpoints which need the
+    /// state answer `UNAVAILABLE` along with the progress of the startup.
+    pub fn new(
+        config: &StoreConfig,
+        db: Db,
+    ) -> Result<Self> {
+        config.tag_policy.validate()?;
+        config.note_visibility.validate()?;
+
+        Ok(Self {
+            state: OnceLock::new(),
+            db: Arc::new(db),
+            progress: StartupProgress::new(config.startup.progress_interval()),
+            chain_id: config.chain_id,
+            safe_depth: config.safe_depth,
+            data_directory: config.data_directory(),
+            tag_policy: config.tag_policy.clone(),
+        })
+    }
+
+    /// Loads the state of the store, then starts the backfills of the tables introduced by the
+    /// latest migrations, and the publication of the state commitments.
+    pub async fn finish_loading(
+        &self,
+        config: &StoreConfig,
+    ) -> Result<()> {
+        let state = Arc::new(
+            State::load_with_progress(
+                self.db.clone(),
+                config.max_block_timestamp_skew_s,
+                Duration::from_millis(config.proposal_timeout_ms),
+                config.tag_policy.clone(),
+                config.note_visibility.clone(),
+                config.verify_batch_proofs,
+                &self.progress,
+            )
+            .await?,
+        );
+        if self.state.set(state.clone()).is_err() {
+            bail!("The state of the store was already loaded");
+        }
+
+        tokio::spawn(backfill::run(state.clone(), BACKFILL_BATCH_PAUSE));
+        if config.state_commitment_interval > 0 {
+            tokio::spawn(commitment::publish(state, config.state_commitment_interval));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the state of the store, or `UNAVAILABLE` along with the progress of the startup
+    /// while it is loaded.
+    fn state(&self) -> Result<&Arc<State>, Status> {
+        self.state.get().ok_or_else(|| {
+            Status::unavailable(format!(
+                "the store is loading its state: {}",
+                self.progress.status()
+            ))
+        })
+    }
+
+    /// Returns the counters of the chain, as served by `GetChainCounters`.
+    ///
+    /// Unlike the endpoint, doesn't require an operator signature: it is called by the block
+    /// producer running in the same process as the store.
+    pub async fn chain_counters(&self) -> Result<GetChainCountersResponse, Status> {
+        let state = self.state()?;
+        let counters = state.chain_counters().await;
+        let published = state.commitment_metrics().published();
+
+        Ok(GetChainCountersResponse {
+            block_num: counters.chain_tip.block_num,
+            block_hash: Some(counters.chain_tip.block_hash.into()),
+            account_root: Some(counters.account_root.into()),
+            num_accounts: counters.num_accounts,
+            num_notes: counters.stats.num_notes,
+            num_nullifiers: counters.stats.num_nullifiers,
+            num_suspicious_blocks: state.consistency().suspicious_blocks(),
+            num_block_anomalies: state.consistency().anomalies(),
+            commitment_block_num: published.map_or(0, |(block_num, _)| block_num),
+            commitment: published.map(|(_, digest)| digest.into()),
+            num_diverged_commitments: state.commitment_metrics().diverged(),
+        })
+    }
+
+    /// Returns the latest block of the chain, read from the database while the state is loaded.
+    async fn latest_chain_tip(&self) -> Result<ChainTip, Status> {
+        if let Some(state) = self.state.get() {
+            return Ok(state.latest_chain_tip());
+        }
+
+        let block_header = self
+            .db
+            .select_block_header_by_block_num(None)
+            .await
+            .map_err(internal_error)?
+            .ok_or_else(|| Status::internal("the store holds no block"))?;
+
+        Ok(ChainTip {
+            block_num: block_header.block_num,
+            block_hash: BlockHeader::try_from(&block_header).map_err(internal_error)?.hash(),
+        })
+    }
+
+    /// Returns the hash of the genesis block, read from the database while the state is loaded.
+    async fn genesis_hash(&self) -> Result<RpoDigest, Status> {
+        if let Some(state) = self.state.get() {
+            return Ok(state.genesis_hash());
+        }
+
+        let genesis_header = self
+            .db
+            .select_block_header_by_block_num(Some(GENESIS_BLOCK_NUM))
+            .await
+            .map_err(internal_error)?
+            .ok_or_else(|| Status::internal("the store holds no genesis block"))?;
+
+        Ok(BlockHeader::try_from(&genesis_header).map_err(internal_error)?.hash())
+    }
+
+    /// Returns the number of the latest block reported as final given the `chain_tip`.
+    fn safe_chain_tip(
+        &self,
+        chain_tip: BlockNumber,
+    ) -> BlockNumber {
+        chain_tip.saturating_sub(self.safe_depth)
+    }
 }
 
 #[tonic::async_trait]
 impl api_server::Api for StoreApi {
+    type SubscribeChainTipStream = ReceiverStream<Result<SubscribeChainTipResponse, Status>>;
+    type SubscribeNullifiersStream = ReceiverStream<Result<SubscribeNullifiersResponse, Status>>;
+
     // CLIENT ENDPOINTS
     // --------------------------------------------------------------------------------------------
 
     /// Returns block header for the specified block number.
     ///
-    /// If the block number is not provided, block header for the latest block is returned.
+    /// If the block number is not provided, block header for the latest block is returned. If
+    /// requested, the header's authentication path in the chain MMR of the latest block is also
+    /// returned. The numbers of the chain tip and of the latest final block are always returned.
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(
         target = "miden-store",
@@ -55,16 +265,81 @@ impl api_server::Api for StoreApi {
         info!(target: COMPONENT, ?request);
 
         let request = request.into_inner();
-        let block_header =
-            self.state.get_block_header(request.block_num).await.map_err(internal_error)?;
+        let include_mmr_proof = request.include_mmr_proof.unwrap_or_default();
+        let (block_header, mmr_proof) = match self.state.get() {
+            // the headers are served from the database while the state is loaded, the MMR proofs
+            // need the chain MMR
+            None if !include_mmr_proof => (
+                self.db
+                    .select_block_header_by_block_num(request.block_num)
+                    .await
+                    .map_err(internal_error)?,
+                None,
+            ),
+            _ => self
+                .state()?
+                .get_block_header(request.block_num, include_mmr_proof)
+                .await
+                .map_err(internal_error)?,
+        };
 
-        Ok(Response::new(GetBlockHeaderByNumberResponse { block_header }))
+        let (tx_kernel_versions, signature, canonical_tx_ordering, timestamp_attestation) =
+            match &block_header {
+                Some(block_header) => (
+                    self.db
+                        .select_block_tx_kernel_versions(block_header.block_num)
+                        .await
+                        .map_err(internal_error)?,
+                    self.db
+                        .select_block_signature(block_header.block_num)
+                        .await
+                        .map_err(internal_error)?,
+                    self.db
+                        .select_block_canonical_tx_ordering(block_header.block_num)
+                        .await
+                        .map_err(internal_error)?,
+                    self.db
+                        .select_block_timestamp_attestation(block_header.block_num)
+                        .await
+                        .map_err(internal_error)?,
+                ),
+                None => (Vec::new(), None, false, None),
+            };
+
+        // the tip may have moved since the header was read, the response must agree with both the
+        // header and the MMR proof
+        let chain_tip = match (&mmr_proof, request.block_num, &block_header) {
+            (Some(mmr_proof), ..) => ChainTip {
+                block_num: mmr_proof.chain_length,
+                block_hash: mmr_proof.chain_tip_hash,
+            },
+            (None, None, Some(block_header)) => ChainTip {
+                block_num: block_header.block_num,
+                block_hash: BlockHeader::try_from(block_header).map_err(internal_error)?.hash(),
+            },
+            _ => self.latest_chain_tip().await?,
+        };
+
+        Ok(Response::new(GetBlockHeaderByNumberResponse {
+            block_header,
+            chain_length: mmr_proof.as_ref().map(|proof| proof.chain_length),
+            mmr_path: mmr_proof.and_then(|proof| proof.mmr_path).map(Into::into),
+            not_modified: false,
+            tx_kernel_versions,
+            chain_tip: chain_tip.block_num,
+            safe_chain_tip: self.safe_chain_tip(chain_tip.block_num),
+            chain_tip_hash: Some(chain_tip.block_hash.into()),
+            signature,
+            canonical_tx_ordering,
+            timestamp_attestation,
+        }))
     }
 
     /// Returns info on whether the specified nullifiers have been consumed.
     ///
-    /// This endpoint also returns Merkle authentication path for each requested nullifier which can
-    /// be verified against the latest root of the nullifier database.
+    /// This endpoint also returns Merkle authentication path for each nullifier in `nullifiers`
+    /// which can be verified against the latest root of the nullifier database. For the
+    /// `nullifiers_without_proofs` only the block number at which they were consumed is returned.
     #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
     #[instrument(
         target = "miden-store",
@@ -80,12 +355,15 @@ impl api_server::Api for StoreApi {
         // Validate the nullifiers and convert them to RpoDigest values. Stop on first error.
         let request = request.into_inner();
         let nullifiers = validate_nullifiers(&request.nullifiers)?;
+        let nullifiers_without_proofs = validate_nullifiers(&request.nullifiers_without_proofs)?;
 
         // Query the state for the request's nullifiers
-        let proofs = self.state.check_nullifiers(&nullifiers).await;
+        let (proofs, block_nums) =
+            self.state()?.check_nullifiers(&nullifiers, &nullifiers_without_proofs).await;
 
         Ok(Response::new(CheckNullifiersResponse {
             proofs: convert(proofs),
+            block_nums,
         }))
     }
 
@@ -106,20 +384,337 @@ impl api_server::Api for StoreApi {
         let request = request.into_inner();
 
         let account_ids: Vec<u64> = request.account_ids.iter().map(|e| e.id).collect();
+        let note_hashes = request
+            .note_hashes
+            .iter()
+            .map(RpoDigest::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid_argument)?;
+
+        let block_num = if request.resume_token.is_empty() {
+            request.block_num
+        } else {
+            let token = SyncToken::from_bytes(&request.resume_token).map_err(invalid_argument)?;
+            self.state()?
+                .resume_sync(
+                    &token,
+                    &account_ids,
+                    &request.note_tags,
+                    &request.nullifiers,
+                    &note_hashes,
+                )
+                .await
+                .map_err(|err| match err {
+                    StateSyncError::SyncFiltersMismatch => {
+                        Status::invalid_argument(err.to_string())
+                    },
+                    StateSyncError::SyncBlockNotInChain(_) => {
+                        Status::failed_precondition(err.to_string())
+                    },
+                    err => internal_error(err),
+                })?
+        };
 
-        let (state, delta) = self
-            .state
-            .sync_state(request.block_num, &account_ids, &request.note_tags, &request.nullifiers)
+        let (state, delta, token) = self
+            .state()?
+            .sync_state(
+                block_num,
+                &account_ids,
+                &request.note_tags,
+                &request.nullifiers,
+                &note_hashes,
+            )
             .await
             .map_err(internal_error)?;
 
         Ok(Response::new(SyncStateResponse {
             chain_tip: state.chain_tip,
+            safe_chain_tip: self.safe_chain_tip(state.chain_tip),
             block_header: Some(state.block_header),
             mmr_delta: Some(delta.into()),
             accounts: state.account_updates,
             notes: convert(state.notes),
             nullifiers: state.nullifiers,
+            consumed_notes: state.consumed_notes,
+            resume_token: token.to_bytes(),
+        }))
+    }
+
+    /// Returns the latest states of the requested accounts updated after the client's block, with
+    /// their inclusion proofs against the chain tip.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:sync_accounts",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn sync_accounts(
+        &self,
+        request: tonic::Request<SyncAccountsRequest>,
+    ) -> Result<Response<SyncAccountsResponse>, Status> {
+        let request = request.into_inner();
+
+        debug!(target: COMPONENT, ?request);
+
+        if request.account_ids.len() > MAX_SYNCED_ACCOUNTS {
+            return Err(Status::invalid_argument(format!(
+                "At most {MAX_SYNCED_ACCOUNTS} accounts can be synced"
+            )));
+        }
+        let account_ids: Vec<u64> = request.account_ids.iter().map(|e| e.id).collect();
+
+        let sync = self
+            .state()?
+            .sync_accounts(request.block_num, &account_ids)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(Response::new(SyncAccountsResponse {
+            block_header: Some(sync.block_header),
+            accounts: sync.accounts,
+        }))
+    }
+
+    /// Streams the blocks consuming the requested nullifiers, starting at `from_block`, until
+    /// every nullifier was consumed.
+    ///
+    /// See [crate::subscription] for the delivery guarantees.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", name = "store:subscribe_nullifiers", skip_all, err)]
+    async fn subscribe_nullifiers(
+        &self,
+        request: tonic::Request<SubscribeNullifiersRequest>,
+    ) -> Result<Response<Self::SubscribeNullifiersStream>, Status> {
+        let request = request.into_inner();
+
+        debug!(target: COMPONENT, ?request);
+
+        let nullifiers = validate_nullifiers(&request.nullifiers)?;
+        if nullifiers.is_empty() || nullifiers.len() > MAX_SUBSCRIBED_NULLIFIERS {
+            return Err(Status::invalid_argument(format!(
+                "Expected between 1 and {MAX_SUBSCRIBED_NULLIFIERS} nullifiers, got {}",
+                nullifiers.len()
+            )));
+        }
+
+        let (sender, receiver) = mpsc::channel(NULLIFIER_SUBSCRIPTION_CHANNEL_SIZE);
+        tokio::spawn(subscription::run(
+            self.state()?.clone(),
+            NullifierSubscription::new(nullifiers, request.from_block),
+            sender,
+        ));
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    /// Streams the latest block of the chain, starting with the current one, so caches of the
+    /// chain tip learn about new blocks as soon as they are applied.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", name = "store:subscribe_chain_tip", skip_all, err)]
+    async fn subscribe_chain_tip(
+        &self,
+        _request: tonic::Request<SubscribeChainTipRequest>,
+    ) -> Result<Response<Self::SubscribeChainTipStream>, Status> {
+        let (sender, receiver) = mpsc::channel(1);
+        tokio::spawn(subscription::run_chain_tip(self.state()?.subscribe_chain_tip(), sender));
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    /// Returns the inclusion path of a note, regenerated from the notes of the block in which it
+    /// was created.
+    ///
+    /// The path can be verified against the `note_root` of the block's header.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_note_authentication_path",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_note_authentication_path(
+        &self,
+        request: tonic::Request<GetNoteAuthenticationPathRequest>,
+    ) -> Result<Response<GetNoteAuthenticationPathResponse>, Status> {
+        let request = request.into_inner();
+
+        debug!(target: COMPONENT, ?request);
+
+        let merkle_path = self
+            .state()?
+            .get_note_authentication_path(request.block_num, request.note_index)
+            .await
+            .map_err(|err| match err {
+                GetNoteAuthenticationPathError::BlockNotFound(_)
+                | GetNoteAuthenticationPathError::NoteNotFound { .. } => {
+                    Status::not_found(err.to_string())
+                },
+                err => internal_error(err),
+            })?;
+
+        Ok(Response::new(GetNoteAuthenticationPathResponse {
+            merkle_path: Some(merkle_path.into()),
+        }))
+    }
+
+    /// Retains the details of a note created by the chain along with its inclusion path, so its
+    /// owner can still consume it once the path is no longer served otherwise.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:submit_note_recovery_record",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn submit_note_recovery_record(
+        &self,
+        request: tonic::Request<SubmitNoteRecoveryRecordRequest>,
+    ) -> Result<Response<SubmitNoteRecoveryRecordResponse>, Status> {
+        let request = request.into_inner();
+
+        if request.note.len() > MAX_NOTE_RECOVERY_RECORD_SIZE {
+            return Err(Status::invalid_argument(format!(
+                "Serialized note exceeds {MAX_NOTE_RECOVERY_RECORD_SIZE} bytes"
+            )));
+        }
+        let note = Note::read_from_bytes(&request.note)
+            .map_err(|err| Status::invalid_argument(format!("Invalid note: {err}")))?;
+
+        debug!(target: COMPONENT, note_id = %note.id().inner());
+
+        let record =
+            self.state()?
+                .submit_note_recovery_record(&note)
+                .await
+                .map_err(|err| match err {
+                    NoteRecoveryError::NoteNotFound(_) => Status::not_found(err.to_string()),
+                    NoteRecoveryError::MetadataMismatch(_) | NoteRecoveryError::PrivateNote(_) => {
+                        Status::invalid_argument(err.to_string())
+                    },
+                    err => internal_error(err),
+                })?;
+
+        Ok(Response::new(SubmitNoteRecoveryRecordResponse {
+            record: Some(record),
+        }))
+    }
+
+    /// Returns the recovery records of the requested notes, see
+    /// [Self::submit_note_recovery_record].
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_note_recovery_records",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_note_recovery_records(
+        &self,
+        request: tonic::Request<GetNoteRecoveryRecordsRequest>,
+    ) -> Result<Response<GetNoteRecoveryRecordsResponse>, Status> {
+        let request = request.into_inner();
+
+        debug!(target: COMPONENT, ?request);
+
+        if request.note_hashes.len() > MAX_NOTE_RECOVERY_RECORDS {
+            return Err(Status::invalid_argument(format!(
+                "At most {MAX_NOTE_RECOVERY_RECORDS} records can be requested"
+            )));
+        }
+        let note_hashes = request
+            .note_hashes
+            .iter()
+            .map(RpoDigest::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid_argument)?;
+
+        let records = self
+            .state()?
+            .get_note_recovery_records(note_hashes)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(Response::new(GetNoteRecoveryRecordsResponse { records }))
+    }
+
+    /// Returns the number of notes created over the latest blocks, with any tag and with the
+    /// requested tags, for the clients to estimate the congestion of the chain.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_note_tag_stats",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_note_tag_stats(
+        &self,
+        request: tonic::Request<GetNoteTagStatsRequest>,
+    ) -> Result<Response<GetNoteTagStatsResponse>, Status> {
+        let request = request.into_inner();
+
+        debug!(target: COMPONENT, ?request);
+
+        if request.note_tags.len() > MAX_NOTE_TAG_STATS_TAGS {
+            return Err(Status::invalid_argument(format!(
+                "At most {MAX_NOTE_TAG_STATS_TAGS} tags can be requested"
+            )));
+        }
+
+        let activity = self
+            .state()?
+            .get_note_tag_stats(request.note_tags, request.num_blocks)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(Response::new(GetNoteTagStatsResponse {
+            chain_tip: activity.chain_tip,
+            from_block: activity.from_block,
+            total_notes: activity.total_notes,
+            tags: activity.tags,
+            queue_depth: None,
+        }))
+    }
+
+    /// Returns the notes matching some tags which weren't consumed, e.g. for dapps to list their
+    /// open orders, over as many blocks as fit in a response.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_unconsumed_notes",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_unconsumed_notes(
+        &self,
+        request: tonic::Request<GetUnconsumedNotesRequest>,
+    ) -> Result<Response<GetUnconsumedNotesResponse>, Status> {
+        let request = request.into_inner();
+
+        debug!(target: COMPONENT, ?request);
+
+        if request.note_tags.len() > MAX_UNCONSUMED_NOTES_TAGS {
+            return Err(Status::invalid_argument(format!(
+                "At most {MAX_UNCONSUMED_NOTES_TAGS} tags can be requested"
+            )));
+        }
+
+        let unconsumed = self
+            .state()?
+            .get_unconsumed_notes(request.note_tags, request.from_block)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(Response::new(GetUnconsumedNotesResponse {
+            chain_tip: unconsumed.chain_tip,
+            notes: unconsumed.notes,
+            next_block_num: unconsumed.next_block_num,
         }))
     }
 
@@ -142,36 +737,143 @@ impl api_server::Api for StoreApi {
         let request = request.into_inner();
 
         debug!(target: COMPONENT, ?request);
-        let block = request.block.ok_or(invalid_argument("Apply block missing block header"))?;
-        let header_base: BlockHeader = block
-            .clone()
-            .try_into()
-            .map_err(|err: ParseError| Status::invalid_argument(err.to_string()))?;
+        let block = parse_block(request)?;
 
-        info!(target: COMPONENT, block_num = block.block_num, block_hash = %header_base.hash());
+        let _ = self.state()?.apply_block(block).await;
 
-        let nullifiers = validate_nullifiers(&request.nullifiers)?;
-        let accounts = request
-            .accounts
-            .iter()
-            .map(|account_update| {
-                let account_id = account_update
-                    .account_id
-                    .clone()
-                    .ok_or(invalid_argument("Account update missing account id"))?;
-                let account_hash = account_update
-                    .account_hash
-                    .clone()
-                    .ok_or(invalid_argument("Account update missing account hash"))?;
-                Ok((account_id.id, account_hash))
-            })
+        Ok(Response::new(ApplyBlockResponse {}))
+    }
+
+    /// Applies a contiguous run of blocks in a single transaction, e.g. to ingest an archive.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:apply_blocks",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn apply_blocks(
+        &self,
+        request: tonic::Request<ApplyBlocksRequest>,
+    ) -> Result<Response<ApplyBlocksResponse>, Status> {
+        let operator = require_operator(&request, APPLY_BLOCKS_OPERATION)?;
+        let request = request.into_inner();
+
+        let num_blocks = request.blocks.len();
+        if num_blocks == 0 || num_blocks > MAX_APPLY_BLOCKS {
+            return Err(Status::invalid_argument(format!(
+                "Expected between 1 and {MAX_APPLY_BLOCKS} blocks, got {num_blocks}"
+            )));
+        }
+
+        let blocks = request
+            .blocks
+            .into_iter()
+            .map(parse_block)
             .collect::<Result<Vec<_>, Status>>()?;
 
-        let notes = request.notes;
+        let chain_tip = self.state()?.apply_blocks(blocks).await.map_err(apply_block_error)?;
 
-        let _ = self.state.apply_block(block, nullifiers, accounts, notes).await;
+        info!(target: COMPONENT, num_blocks, chain_tip, operator = %operator.key.to_hex(), "Blocks applied");
 
-        Ok(Response::new(ApplyBlockResponse {}))
+        Ok(Response::new(ApplyBlocksResponse { chain_tip }))
+    }
+
+    /// Validates a new block and stages it, to be applied by `CommitBlock`.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:propose_block",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn propose_block(
+        &self,
+        request: tonic::Request<ApplyBlockRequest>,
+    ) -> Result<Response<ProposeBlockResponse>, Status> {
+        let request = request.into_inner();
+
+        debug!(target: COMPONENT, ?request);
+        let block = parse_block(request)?;
+
+        let block_hash = self.state()?.propose_block(block).await.map_err(apply_block_error)?;
+
+        info!(target: COMPONENT, %block_hash, "Block proposed");
+
+        Ok(Response::new(ProposeBlockResponse {
+            block_hash: Some(block_hash.into()),
+        }))
+    }
+
+    /// Applies the block staged by `ProposeBlock`.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:commit_block",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn commit_block(
+        &self,
+        request: tonic::Request<CommitBlockRequest>,
+    ) -> Result<Response<CommitBlockResponse>, Status> {
+        let block_hash: RpoDigest = request
+            .into_inner()
+            .block_hash
+            .ok_or(invalid_argument("Commit block missing block hash"))?
+            .try_into()
+            .map_err(invalid_argument)?;
+
+        let block_num = self.state()?.commit_block(block_hash).await.map_err(apply_block_error)?;
+
+        info!(target: COMPONENT, block_num, %block_hash, "Block committed");
+
+        Ok(Response::new(CommitBlockResponse { block_num }))
+    }
+
+    /// Returns the notes created by the block staged by `ProposeBlock`, with their provisional
+    /// inclusion paths, so they can be consumed before the block is committed.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_proposed_notes",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_proposed_notes(
+        &self,
+        request: tonic::Request<GetProposedNotesRequest>,
+    ) -> Result<Response<GetProposedNotesResponse>, Status> {
+        let request = request.into_inner();
+
+        debug!(target: COMPONENT, ?request);
+
+        if request.note_hashes.len() > MAX_PROPOSED_NOTES_HASHES {
+            return Err(Status::invalid_argument(format!(
+                "At most {MAX_PROPOSED_NOTES_HASHES} notes can be requested"
+            )));
+        }
+        let note_hashes = request
+            .note_hashes
+            .iter()
+            .map(RpoDigest::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid_argument)?;
+
+        let proposed =
+            self.state()?.get_proposed_notes(&note_hashes).await.map_err(internal_error)?;
+
+        Ok(Response::new(match proposed {
+            Some(proposed) => GetProposedNotesResponse {
+                block_header: Some(proposed.block_header),
+                notes: proposed.notes,
+            },
+            None => GetProposedNotesResponse::default(),
+        }))
     }
 
     /// Returns data needed by the block producer to construct and prove the next block.
@@ -192,8 +894,8 @@ impl api_server::Api for StoreApi {
         let nullifiers = validate_nullifiers(&request.nullifiers)?;
         let account_ids: Vec<u64> = request.account_ids.iter().map(|e| e.id).collect();
 
-        let (latest, accumulator, account_states) = self
-            .state
+        let (latest, accumulator, account_states, account_proofs) = self
+            .state()?
             .get_block_inputs(&account_ids, &nullifiers)
             .await
             .map_err(internal_error)?;
@@ -204,6 +906,44 @@ impl api_server::Api for StoreApi {
             account_states: convert(account_states),
             // TODO: nullifiers blocked by changes in crypto repo
             nullifiers: vec![],
+            account_proofs: Some(account_proofs.into()),
+        }))
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_state_commitment",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_state_commitment(
+        &self,
+        request: tonic::Request<GetStateCommitmentRequest>,
+    ) -> Result<Response<GetStateCommitmentResponse>, Status> {
+        let request = request.into_inner();
+        let commitment = self
+            .state()?
+            .state_commitment(request.block_num)
+            .await
+            .map_err(internal_error)?
+            .ok_or_else(|| Status::not_found(format!("Block {:?} not found", request.block_num)))?;
+        let (block_header, _) = self
+            .state()?
+            .get_block_header(Some(commitment.block_num), false)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(Response::new(GetStateCommitmentResponse {
+            block_header,
+            num_notes: commitment.num_notes,
+            num_nullifiers: commitment.num_nullifiers,
+            num_consumed_notes: commitment.num_consumed_notes,
+            commitment: Some(commitment.digest().into()),
+            chain_root: Some(commitment.chain_root.into()),
+            account_root: Some(commitment.account_root.into()),
+            nullifier_root: commitment.nullifier_root.map(Into::into),
         }))
     }
 
@@ -225,13 +965,258 @@ impl api_server::Api for StoreApi {
 
         let nullifiers = validate_nullifiers(&request.nullifiers)?;
         let account_id = request.account_id.ok_or(invalid_argument("Account_id missing"))?.id;
+        let block_ref = request
+            .block_ref
+            .map(RpoDigest::try_from)
+            .transpose()
+            .map_err(|_| invalid_argument("Digest field is not in the modulus range"))?;
 
-        let (account, nullifiers_blocks) =
-            self.state.get_transaction_inputs(account_id, &nullifiers).await;
+        let (account, nullifiers_blocks, block_ref_num) = self
+            .state()?
+            .get_transaction_inputs(account_id, &nullifiers, block_ref)
+            .await
+            .map_err(internal_error)?;
 
         Ok(Response::new(GetTransactionInputsResponse {
             account_state: Some(account.into()),
             nullifiers: convert(nullifiers_blocks),
+            block_ref_num,
+        }))
+    }
+
+    /// Returns the oldest block whose notes are retained by the store, used by the block producer
+    /// to reject the transactions consuming older notes.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_pruning_horizon",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_pruning_horizon(
+        &self,
+        _request: tonic::Request<GetPruningHorizonRequest>,
+    ) -> Result<Response<GetPruningHorizonResponse>, Status> {
+        Ok(Response::new(GetPruningHorizonResponse {
+            block_num: self.state()?.pruning_horizon(),
+        }))
+    }
+
+    // NODE ENDPOINTS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns the progress of the backfills populating the tables introduced by migrations.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_backfill_status",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_backfill_status(
+        &self,
+        request: tonic::Request<GetBackfillStatusRequest>,
+    ) -> Result<Response<GetBackfillStatusResponse>, Status> {
+        require_operator(&request, GET_BACKFILL_STATUS_OPERATION)?;
+
+        let backfills = self
+            .state()?
+            .backfills()
+            .await
+            .map_err(internal_error)?
+            .into_iter()
+            .map(|progress| BackfillStatus {
+                name: progress.name,
+                next_block_num: progress.next_block_num,
+                completed: progress.completed,
+            })
+            .collect();
+        let (chain_tip, _) =
+            self.state()?.get_block_header(None, false).await.map_err(internal_error)?;
+
+        Ok(Response::new(GetBackfillStatusResponse {
+            backfills,
+            chain_tip: chain_tip.map(|header| header.block_num).unwrap_or_default(),
+        }))
+    }
+
+    /// Returns the progress of the state being loaded, served while it is.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_startup_progress",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_startup_progress(
+        &self,
+        _request: tonic::Request<GetStartupProgressRequest>,
+    ) -> Result<Response<GetStartupProgressResponse>, Status> {
+        let status = self.progress.status();
+
+        Ok(Response::new(GetStartupProgressResponse {
+            stage: status.stage.to_string(),
+            rows_read: status.rows_read,
+            rows_loaded: status.rows_loaded,
+            total_rows: status.total_rows,
+            percent_complete: status.percent_complete(),
+            eta_s: status.eta().map(|eta| eta.as_secs()),
+            elapsed_s: status.elapsed.as_secs(),
+        }))
+    }
+
+    /// Returns the number of accounts, notes and nullifiers of the chain, along with the latest
+    /// block and account root they were counted at, so the block producer can audit the state of
+    /// the store before producing blocks on top of it.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_chain_counters",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_chain_counters(
+        &self,
+        request: tonic::Request<GetChainCountersRequest>,
+    ) -> Result<Response<GetChainCountersResponse>, Status> {
+        require_operator(&request, GET_CHAIN_COUNTERS_OPERATION)?;
+
+        Ok(Response::new(self.chain_counters().await?))
+    }
+
+    /// Returns the size of the database and of its tables, for operators to plan
+    /// the retention of the data before the disk fills up.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_database_size",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_database_size(
+        &self,
+        request: tonic::Request<GetDatabaseSizeRequest>,
+    ) -> Result<Response<GetDatabaseSizeResponse>, Status> {
+        require_operator(&request, GET_DATABASE_SIZE_OPERATION)?;
+
+        let data_directory = self.data_directory.clone();
+        let disk_usage = tokio::task::spawn_blocking(move || data_directory.disk_usage())
+            .await
+            .map_err(internal_error)?
+            .map_err(internal_error)?;
+        let tables = self.state()?.table_sizes().await.map_err(internal_error)?;
+
+        Ok(Response::new(GetDatabaseSizeResponse {
+            database_size: disk_usage.database_size,
+            wal_size: disk_usage.wal_size,
+            tables,
+        }))
+    }
+
+    /// Returns the header of the genesis block and its accounts, as defined by the genesis file of
+    /// the store, so tools can learn the initial state of the chain.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", name = "store:get_genesis_block", skip_all, err)]
+    async fn get_genesis_block(
+        &self,
+        _request: tonic::Request<GetGenesisBlockRequest>,
+    ) -> Result<Response<GetGenesisBlockResponse>, Status> {
+        let genesis_filepath = self.data_directory.genesis_filepath();
+        let genesis_state =
+            tokio::task::spawn_blocking(move || GenesisState::read_from_file(&genesis_filepath))
+                .await
+                .map_err(internal_error)?
+                .map_err(internal_error)?;
+        let accounts = genesis_state.accounts.iter().map(Serializable::to_bytes).collect();
+
+        let (block_header, _) = self
+            .state()?
+            .get_block_header(Some(GENESIS_BLOCK_NUM), false)
+            .await
+            .map_err(internal_error)?;
+        let block_header = block_header.ok_or(Status::internal("Genesis block header missing"))?;
+
+        // the genesis file was checked against the database on startup, but may have been
+        // replaced since
+        let (expected_header, _) = genesis_state.into_block_parts().map_err(internal_error)?;
+        if block_header != miden_node_proto::block_header::BlockHeader::from(expected_header) {
+            return Err(Status::failed_precondition(
+                "The genesis file doesn't match the genesis block of the database",
+            ));
+        }
+
+        Ok(Response::new(GetGenesisBlockResponse {
+            block_header: Some(block_header),
+            accounts,
+        }))
+    }
+
+    /// Returns the chain id, protocol version and genesis block hash, used by the other components
+    /// to verify they operate on the same chain as the store.
+    ///
+    /// Served while the state is loaded, so the other components may start meanwhile.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(
+        target = "miden-store",
+        name = "store:get_node_info",
+        skip_all,
+        ret(level = "debug"),
+        err
+    )]
+    async fn get_node_info(
+        &self,
+        _request: tonic::Request<GetNodeInfoRequest>,
+    ) -> Result<Response<GetNodeInfoResponse>, Status> {
+        Ok(Response::new(GetNodeInfoResponse {
+            protocol_version: PROTOCOL_VERSION,
+            genesis_hash: Some(self.genesis_hash().await?.into()),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            chain_id: self.chain_id,
+            tag_policy: Some((&self.tag_policy).into()),
+            safe_depth: self.safe_depth,
+        }))
+    }
+
+    /// Exports the notes created over a range of blocks which match the requested tags, with
+    /// their merkle paths and the headers of their blocks, as a self-contained archive.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", name = "store:export_notes", skip_all, err)]
+    async fn export_notes(
+        &self,
+        request: tonic::Request<ExportNotesRequest>,
+    ) -> Result<Response<ExportNotesResponse>, Status> {
+        require_operator(&request, EXPORT_NOTES_OPERATION)?;
+        let request = request.into_inner();
+
+        debug!(target: COMPONENT, ?request);
+
+        if request.from_block > request.to_block {
+            return Err(Status::invalid_argument(format!(
+                "from_block {} is after to_block {}",
+                request.from_block, request.to_block
+            )));
+        }
+
+        let (block_headers, notes) = self
+            .state()?
+            .export_notes(request.note_tags.clone(), request.from_block, request.to_block)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(Response::new(ExportNotesResponse {
+            archive: Some(NoteArchive {
+                chain_id: self.chain_id,
+                from_block: request.from_block,
+                to_block: request.to_block,
+                note_tags: request.note_tags,
+                block_headers,
+                notes,
+            }),
         }))
     }
 
@@ -251,7 +1236,7 @@ impl api_server::Api for StoreApi {
         &self,
         _request: tonic::Request<ListNullifiersRequest>,
     ) -> Result<Response<ListNullifiersResponse>, Status> {
-        let raw_nullifiers = self.state.list_nullifiers().await.map_err(internal_error)?;
+        let raw_nullifiers = self.state()?.list_nullifiers().await.map_err(internal_error)?;
         let nullifiers = raw_nullifiers
             .into_iter()
             .map(|(key, block_num)| NullifierLeaf {
@@ -275,7 +1260,7 @@ impl api_server::Api for StoreApi {
         &self,
         _request: tonic::Request<ListNotesRequest>,
     ) -> Result<Response<ListNotesResponse>, Status> {
-        let notes = self.state.list_notes().await.map_err(internal_error)?;
+        let notes = self.state()?.list_notes().await.map_err(internal_error)?;
         Ok(Response::new(ListNotesResponse { notes }))
     }
 
@@ -292,7 +1277,7 @@ impl api_server::Api for StoreApi {
         &self,
         _request: tonic::Request<ListAccountsRequest>,
     ) -> Result<Response<ListAccountsResponse>, Status> {
-        let accounts = self.state.list_accounts().await.map_err(internal_error)?;
+        let accounts = self.state()?.list_accounts().await.map_err(internal_error)?;
         Ok(Response::new(ListAccountsResponse { accounts }))
     }
 }
@@ -301,6 +1286,121 @@ impl api_server::Api for StoreApi {
 // ================================================================================================
 
 /// Formats an error
+/// Parses a block sent to `ApplyBlock`, `ApplyBlocks` or `ProposeBlock`.
+fn parse_block(request: ApplyBlockRequest) -> Result<NewBlock, Status> {
+    let block = request.block.ok_or(invalid_argument("Apply block missing block header"))?;
+    let header_base: BlockHeader = block
+        .clone()
+        .try_into()
+        .map_err(|err: ParseError| Status::invalid_argument(err.to_string()))?;
+
+    info!(target: COMPONENT, block_num = block.block_num, block_hash = %header_base.hash());
+
+    let nullifiers = validate_nullifiers(&request.nullifiers)?;
+    let accounts = request
+        .accounts
+        .iter()
+        .map(|account_update| {
+            let account_id = account_update
+                .account_id
+                .clone()
+                .ok_or(invalid_argument("Account update missing account id"))?;
+            let account_hash = account_update
+                .account_hash
+                .clone()
+                .ok_or(invalid_argument("Account update missing account hash"))?;
+            Ok((account_id.id, account_hash))
+        })
+        .collect::<Result<Vec<_>, Status>>()?;
+    let account_details = request
+        .accounts
+        .iter()
+        .filter(|account_update| !account_update.details.is_empty())
+        .filter_map(|account_update| {
+            let account_id = account_update.account_id.as_ref()?;
+            Some((account_id.id, account_update.details.clone()))
+        })
+        .collect();
+
+    let notes = request.notes;
+    let consumed_notes = request
+        .consumed_notes
+        .iter()
+        .map(|consumed_note| {
+            let note_hash = consumed_note
+                .note_hash
+                .as_ref()
+                .ok_or(invalid_argument("Consumed note missing note hash"))?;
+            let nullifier = consumed_note
+                .nullifier
+                .as_ref()
+                .ok_or(invalid_argument("Consumed note missing nullifier"))?;
+            Ok((
+                note_hash.try_into().map_err(invalid_argument)?,
+                nullifier.try_into().map_err(invalid_argument)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, Status>>()?;
+
+    let batch_proofs = request
+        .batch_proofs
+        .into_iter()
+        .map(|batch_proof| {
+            let commitment = batch_proof
+                .commitment
+                .as_ref()
+                .ok_or(invalid_argument("Batch proof missing commitment"))?;
+            let created_notes_root = batch_proof
+                .created_notes_root
+                .as_ref()
+                .ok_or(invalid_argument("Batch proof missing created notes root"))?;
+            Ok(BatchProof {
+                commitment: commitment.try_into().map_err(invalid_argument)?,
+                num_txs: batch_proof.num_txs as usize,
+                created_notes_root: created_notes_root.try_into().map_err(invalid_argument)?,
+                proof: batch_proof.proof,
+                batch_index: batch_proof.batch_index,
+            })
+        })
+        .collect::<Result<Vec<_>, Status>>()?;
+
+    Ok(NewBlock {
+        block_header: block,
+        nullifiers,
+        accounts,
+        account_details,
+        notes,
+        consumed_notes,
+        tx_kernel_versions: request.tx_kernel_versions,
+        signature: request.signature,
+        canonical_tx_ordering: request.canonical_tx_ordering,
+        timestamp_attestation: request.timestamp_attestation,
+        batch_proofs,
+        chain_id: request.chain_id,
+    })
+}
+
+/// Maps a failure to apply a block to a status, telling blocks rejected by the store apart from
+/// failures of the store.
+fn apply_block_error(err: ApplyBlockError) -> Status {
+    match err {
+        ApplyBlockError::DatabaseError(_)
+        | ApplyBlockError::BlockApplyingBrokenBecauseOfClosedChannel(_)
+        | ApplyBlockError::DbUpdateTaskFailed(_)
+        | ApplyBlockError::BatchProofVerificationTaskFailed(_)
+        | ApplyBlockError::BlockStagingTaskFailed(_)
+        | ApplyBlockError::DbBlockHeaderEmpty
+        | ApplyBlockError::FailedToGetMmrPeaksForForest { .. } => Status::internal(err.to_string()),
+        ApplyBlockError::ConcurrentWrite | ApplyBlockError::ProposalPending(_) => {
+            Status::aborted(err.to_string())
+        },
+        ApplyBlockError::ProposalNotFound(_) => Status::not_found(err.to_string()),
+        ApplyBlockError::ProposalExpired(_) => Status::deadline_exceeded(err.to_string()),
+        ApplyBlockError::DiskLimitExceeded(_) => Status::resource_exhausted(err.to_string()),
+        _ => Status::invalid_argument(err.to_string()),
+    }
+}
+
 fn internal_error<E: core::fmt::Debug>(err: E) -> Status {
     Status::internal(format!("{:?}", err))
 }
@@ -1,35 +1,140 @@
-use std::{net::ToSocketAddrs, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
 
-use anyhow::{anyhow, Result};
-use miden_node_proto::store::api_server;
-use tonic::transport::Server;
+use anyhow::{bail, Result};
+use miden_node_proto::{chain_id::ChainIdValidator, store::api_server};
+use miden_node_utils::operator_auth::{OperatorAuthenticator, OperatorKeys};
+use tokio::task::JoinSet;
+use tonic::service::{interceptor::InterceptedService, Interceptor};
 use tracing::{info, instrument};
 
-use crate::{config::StoreConfig, db::Db, state::State, COMPONENT};
+use crate::{config::StoreConfig, db::Db, COMPONENT};
 
 mod api;
+pub use api::StoreApi;
+
+mod tenants;
+use tenants::TenantRouter;
 
 // STORE INITIALIZER
 // ================================================================================================
 
-#[instrument(target = "miden-store", name = "store", skip_all)]
+/// Loads the store from `db`, and serves it.
+///
+/// With partial availability enabled, the store is served while its state is loaded, see
+/// [crate::startup].
 pub async fn serve(
     config: StoreConfig,
     db: Db,
+) -> Result<()> {
+    if !config.startup.partial_availability {
+        let store = Arc::new(StoreApi::load(&config, db).await?);
+        return serve_api(config, store).await;
+    }
+
+    let store = Arc::new(StoreApi::new(&config, db)?);
+    tokio::try_join!(serve_api(config.clone(), store.clone()), store.finish_loading(&config))?;
+
+    Ok(())
+}
+
+/// Serves `store`, which may be shared with a block producer running in the same process, along
+/// with the tenants of the store.
+///
+/// The listeners are bound first, then the databases of the tenants are set up and their states
+/// loaded concurrently, so a slow tenant doesn't hold up the others. With partial availability, the
+/// tenants are served as soon as their databases are set up, their endpoints which need the state
+/// answering `UNAVAILABLE` until it is loaded, see [StoreApi::new]. Otherwise, the server starts
+/// once every tenant is loaded.
+#[instrument(target = "miden-store", name = "store", skip_all)]
+pub async fn serve_api(
+    config: StoreConfig,
+    store: Arc<StoreApi>,
 ) -> Result<()> {
     info!(target: COMPONENT, %config, "Initializing server");
 
-    let state = Arc::new(State::load(db).await?);
-    let store = api_server::ApiServer::new(api::StoreApi { state });
+    let operator_keys = OperatorKeys::from_hex(&config.operator_keys)?;
+    if operator_keys.is_empty() {
+        info!(target: COMPONENT, "No operator key configured, admin operations are disabled");
+    }
+
+    let incoming = config.grpc.incoming(&config.endpoint, &config.listen)?;
 
+    let mut chain_ids = BTreeSet::from([config.chain_id]);
+    let mut setups = JoinSet::new();
+    for tenant in &config.tenants {
+        if !chain_ids.insert(tenant.chain_id) {
+            bail!("Chain {} is hosted more than once", tenant.chain_id);
+        }
+
+        let tenant_config = config.tenant_config(tenant);
+        setups.spawn(async move {
+            let db = Db::setup(tenant_config.clone()).await?;
+            let tenant_store = Arc::new(StoreApi::new(&tenant_config, db)?);
+            if !tenant_config.startup.partial_availability {
+                load_tenant(&tenant_config, &tenant_store).await?;
+            }
+            Ok::<_, anyhow::Error>((tenant_config, tenant_store))
+        });
+    }
+
+    let mut tenants = BTreeMap::new();
+    let mut loading = JoinSet::new();
+    while let Some(setup) = setups.join_next().await {
+        let (tenant_config, tenant_store) = setup??;
+        let chain_id = tenant_config.chain_id;
+        tenants.insert(chain_id, chain_service(chain_id, &operator_keys, tenant_store.clone()));
+
+        if tenant_config.startup.partial_availability {
+            loading.spawn(async move { load_tenant(&tenant_config, &tenant_store).await });
+        }
+    }
+    let store = TenantRouter::new(chain_service(config.chain_id, &operator_keys, store), tenants);
     info!(target: COMPONENT, "Server initialized");
 
-    let addr = config
-        .endpoint
-        .to_socket_addrs()?
-        .next()
-        .ok_or(anyhow!("Couldn't resolve server address"))?;
-    Server::builder().add_service(store).serve(addr).await?;
+    let serve = async {
+        config
+            .grpc
+            .server_builder()
+            .add_service(store)
+            .serve_with_incoming(incoming)
+            .await?;
+        Ok::<_, anyhow::Error>(())
+    };
+    let load_tenants = async {
+        while let Some(loaded) = loading.join_next().await {
+            loaded??;
+        }
+        Ok::<_, anyhow::Error>(())
+    };
+    tokio::try_join!(serve, load_tenants)?;
+
+    Ok(())
+}
+
+/// Loads the state of the tenant `store`, configured by `config`.
+async fn load_tenant(
+    config: &StoreConfig,
+    store: &StoreApi,
+) -> Result<()> {
+    store.finish_loading(config).await?;
+    info!(target: COMPONENT, chain_id = config.chain_id, "Tenant loaded");
 
     Ok(())
 }
+
+/// Serves `store` to the requests for its chain `chain_id`, authenticating the admin operations
+/// with the `operator_keys`.
+fn chain_service(
+    chain_id: u32,
+    operator_keys: &OperatorKeys,
+    store: Arc<StoreApi>,
+) -> InterceptedService<api_server::ApiServer<StoreApi>, impl Interceptor + Clone> {
+    let mut chain_id_validator = ChainIdValidator::new(chain_id);
+    let mut operator_authenticator = OperatorAuthenticator::new(chain_id, operator_keys.clone());
+    InterceptedService::new(api_server::ApiServer::from_arc(store), move |request| {
+        operator_authenticator.call(chain_id_validator.call(request)?)
+    })
+}
@@ -0,0 +1,112 @@
+//! Routing of the requests to the chains hosted by the store.
+//!
+//! Besides its own chain, the store can host the chains of tenants, each one with its own data
+//! directory and state. Every component attaches its chain id to its requests, so the requests are
+//! routed by their chain id metadata. Requests without chain id and requests for unknown chains are
+//! routed to the store's own chain, whose chain id validator rejects them.
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use miden_node_proto::chain_id::CHAIN_ID_METADATA_KEY;
+use tonic::{
+    codegen::{http, Service},
+    server::NamedService,
+};
+
+/// Routes the requests to the service of the chain named by their chain id metadata.
+#[derive(Debug, Clone)]
+pub(super) struct TenantRouter<S> {
+    /// Service of the store's own chain
+    default: S,
+    /// Services of the tenants' chains, by chain id
+    tenants: Arc<BTreeMap<u32, S>>,
+}
+
+impl<S> TenantRouter<S> {
+    pub(super) fn new(
+        default: S,
+        tenants: BTreeMap<u32, S>,
+    ) -> Self {
+        Self {
+            default,
+            tenants: Arc::new(tenants),
+        }
+    }
+
+    /// Returns the service of the chain the request with `headers` is sent to.
+    fn route(
+        &self,
+        headers: &http::HeaderMap,
+    ) -> &S {
+        headers
+            .get(CHAIN_ID_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u32>().ok())
+            .and_then(|chain_id| self.tenants.get(&chain_id))
+            .unwrap_or(&self.default)
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for TenantRouter<S>
+where
+    S: Service<http::Request<B>> + Clone,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(
+        &mut self,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        // the gRPC services of the chains are always ready
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(
+        &mut self,
+        request: http::Request<B>,
+    ) -> Self::Future {
+        self.route(request.headers()).clone().call(request)
+    }
+}
+
+impl<S: NamedService> NamedService for TenantRouter<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use miden_node_proto::chain_id::CHAIN_ID_METADATA_KEY;
+    use tonic::codegen::http::{HeaderMap, HeaderValue};
+
+    use super::TenantRouter;
+
+    #[test]
+    fn test_tenant_routing() {
+        let router =
+            TenantRouter::new("default", BTreeMap::from([(2, "devnet-2"), (3, "devnet-3")]));
+        let route = |chain_id: Option<&'static str>| {
+            let mut headers = HeaderMap::new();
+            if let Some(chain_id) = chain_id {
+                headers.insert(CHAIN_ID_METADATA_KEY, HeaderValue::from_static(chain_id));
+            }
+            *router.route(&headers)
+        };
+
+        assert_eq!(route(Some("2")), "devnet-2");
+        assert_eq!(route(Some("3")), "devnet-3");
+
+        // the requests without chain id, or for another chain, are routed to the store's own chain,
+        // which serves only its own
+        assert_eq!(route(None), "default");
+        assert_eq!(route(Some("1")), "default");
+        assert_eq!(route(Some("4")), "default");
+        assert_eq!(route(Some("not a chain id")), "default");
+    }
+}
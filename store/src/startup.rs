@@ -0,0 +1,323 @@
+//! Progress of the store's startup, while its in-memory trees are rebuilt from the database.
+//!
+//! The nullifiers, account hashes and block headers are counted and read from the database in
+//! chunks, then inserted into the nullifier tree, the account tree and the chain MMR, which takes a
+//! while on a long chain. The progress covers both the rows read and the rows inserted, it is logged
+//! periodically, and reported by the `GetStartupProgress` endpoint.
+//!
+//! With partial availability enabled, the store serves its endpoints while the trees are built:
+//! the block headers are read from the database, the endpoints which need the trees answer
+//! `UNAVAILABLE` along with the progress until the state is loaded.
+use std::{
+    fmt::{Display, Formatter},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use tracing::info;
+
+use crate::COMPONENT;
+
+// STARTUP STAGE
+// ================================================================================================
+
+/// Stage of the store's startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupStage {
+    /// The rows the trees are built from are counted, then read from the database
+    ReadingDatabase,
+    NullifierTree,
+    AccountTree,
+    ChainMmr,
+    /// The state is loaded, every endpoint is served
+    Loaded,
+}
+
+impl Display for StartupStage {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            StartupStage::ReadingDatabase => f.write_str("reading_database"),
+            StartupStage::NullifierTree => f.write_str("nullifier_tree"),
+            StartupStage::AccountTree => f.write_str("account_tree"),
+            StartupStage::ChainMmr => f.write_str("chain_mmr"),
+            StartupStage::Loaded => f.write_str("loaded"),
+        }
+    }
+}
+
+// STARTUP STATUS
+// ================================================================================================
+
+/// Progress of the startup at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartupStatus {
+    pub stage: StartupStage,
+
+    /// Number of rows read from the database so far
+    pub rows_read: u64,
+
+    /// Number of rows inserted into the trees so far
+    pub rows_loaded: u64,
+
+    /// Number of rows to read from the database and insert into the trees, `0` until they are
+    /// counted
+    pub total_rows: u64,
+
+    /// Time spent reading the rows and building the trees so far
+    pub elapsed: Duration,
+}
+
+impl StartupStatus {
+    /// Returns the percentage of the work done, every row being read then inserted into the trees.
+    pub fn percent_complete(&self) -> u32 {
+        match (self.stage, self.total_work()) {
+            (StartupStage::Loaded, _) => 100,
+            (_, 0) => 0,
+            (_, total_work) => (self.work_done().min(total_work) * 100 / total_work) as u32,
+        }
+    }
+
+    /// Returns the time left until the trees are built, extrapolated from the pace of the rows
+    /// read and inserted so far, `None` until a row was read.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.stage == StartupStage::Loaded {
+            return Some(Duration::ZERO);
+        }
+        let work_done = self.work_done();
+        if work_done == 0 {
+            return None;
+        }
+
+        let work_left = self.total_work().saturating_sub(work_done);
+        let eta_ms = self.elapsed.as_millis() as u64 * work_left / work_done;
+
+        Some(Duration::from_millis(eta_ms))
+    }
+
+    /// Returns the number of rows read plus the number of rows inserted so far.
+    fn work_done(&self) -> u64 {
+        self.rows_read + self.rows_loaded
+    }
+
+    /// Returns the number of rows to read plus the number of rows to insert.
+    fn total_work(&self) -> u64 {
+        self.total_rows * 2
+    }
+}
+
+impl Display for StartupStatus {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{}, {} read and {} loaded of {} rows ({}%)",
+            self.stage,
+            self.rows_read,
+            self.rows_loaded,
+            self.total_rows,
+            self.percent_complete()
+        ))?;
+        match self.eta() {
+            Some(eta) if self.stage != StartupStage::Loaded => {
+                f.write_fmt(format_args!(", eta {}s", eta.as_secs()))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+// STARTUP PROGRESS
+// ================================================================================================
+
+/// Tracker of the progress of the startup, updated while the state is loaded.
+pub struct StartupProgress {
+    /// Time between two logs of the progress, the progress is not logged if zero
+    log_interval: Duration,
+    inner: Mutex<ProgressState>,
+}
+
+struct ProgressState {
+    stage: StartupStage,
+    rows_read: u64,
+    rows_loaded: u64,
+    total_rows: u64,
+    /// Time the rows started being read
+    started_at: Option<Instant>,
+    logged_at: Instant,
+}
+
+impl StartupProgress {
+    pub fn new(log_interval: Duration) -> Self {
+        Self {
+            log_interval,
+            inner: Mutex::new(ProgressState {
+                stage: StartupStage::ReadingDatabase,
+                rows_read: 0,
+                rows_loaded: 0,
+                total_rows: 0,
+                started_at: None,
+                logged_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Returns the progress of the startup so far.
+    pub fn status(&self) -> StartupStatus {
+        let inner = self.inner.lock().expect("startup progress lock poisoned");
+
+        StartupStatus {
+            stage: inner.stage,
+            rows_read: inner.rows_read,
+            rows_loaded: inner.rows_loaded,
+            total_rows: inner.total_rows,
+            elapsed: inner.started_at.map(|started_at| started_at.elapsed()).unwrap_or_default(),
+        }
+    }
+
+    /// Records that the trees are built from `total_rows` rows, counted in the database before
+    /// they are read.
+    pub(crate) fn rows_counted(
+        &self,
+        total_rows: u64,
+    ) {
+        let mut inner = self.inner.lock().expect("startup progress lock poisoned");
+        inner.total_rows = total_rows;
+        inner.started_at = Some(Instant::now());
+        drop(inner);
+
+        info!(target: COMPONENT, total_rows, "Reading the rows of the state");
+    }
+
+    /// Records that `num_rows` more rows were read from the database, and logs the progress once
+    /// the log interval elapsed since the last log.
+    pub(crate) fn rows_read(
+        &self,
+        num_rows: usize,
+    ) {
+        let mut inner = self.inner.lock().expect("startup progress lock poisoned");
+        inner.rows_read += num_rows as u64;
+        self.log(inner);
+    }
+
+    /// Records that the trees are built up to `stage`.
+    pub(crate) fn set_stage(
+        &self,
+        stage: StartupStage,
+    ) {
+        self.inner.lock().expect("startup progress lock poisoned").stage = stage;
+        if stage == StartupStage::Loaded {
+            let status = self.status();
+            info!(target: COMPONENT, rows_loaded = status.rows_loaded, elapsed_s = status.elapsed.as_secs(), "State loaded");
+        }
+    }
+
+    /// Records that `num_rows` more rows were inserted into the trees, and logs the progress once
+    /// the log interval elapsed since the last log.
+    pub(crate) fn rows_loaded(
+        &self,
+        num_rows: usize,
+    ) {
+        let mut inner = self.inner.lock().expect("startup progress lock poisoned");
+        inner.rows_loaded += num_rows as u64;
+        self.log(inner);
+    }
+
+    /// Logs the progress once the log interval elapsed since the last log.
+    fn log(
+        &self,
+        mut inner: MutexGuard<'_, ProgressState>,
+    ) {
+        if self.log_interval.is_zero() || inner.logged_at.elapsed() < self.log_interval {
+            return;
+        }
+        inner.logged_at = Instant::now();
+        drop(inner);
+
+        let status = self.status();
+        info!(
+            target: COMPONENT,
+            stage = %status.stage,
+            rows_read = status.rows_read,
+            rows_loaded = status.rows_loaded,
+            total_rows = status.total_rows,
+            percent_complete = status.percent_complete(),
+            eta_s = status.eta().map(|eta| eta.as_secs()),
+            "Loading the state"
+        );
+    }
+}
+
+impl Default for StartupProgress {
+    fn default() -> Self {
+        Self::new(Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{StartupStage, StartupStatus};
+
+    #[test]
+    fn test_startup_status() {
+        let status = StartupStatus {
+            stage: StartupStage::ReadingDatabase,
+            rows_read: 0,
+            rows_loaded: 0,
+            total_rows: 0,
+            elapsed: Duration::ZERO,
+        };
+        assert_eq!(status.percent_complete(), 0);
+        assert_eq!(status.eta(), None);
+
+        // the rows being read count toward the progress, before any of them is inserted
+        let status = StartupStatus {
+            rows_read: 400,
+            total_rows: 1000,
+            elapsed: Duration::from_secs(4),
+            ..status
+        };
+        assert_eq!(status.percent_complete(), 20);
+        assert_eq!(status.eta(), Some(Duration::from_secs(16)));
+        assert_eq!(
+            status.to_string(),
+            "reading_database, 400 read and 0 loaded of 1000 rows (20%), eta 16s"
+        );
+
+        // half of the work took 10 seconds, the other half takes 10 seconds more
+        let status = StartupStatus {
+            stage: StartupStage::AccountTree,
+            rows_read: 1000,
+            rows_loaded: 0,
+            total_rows: 1000,
+            elapsed: Duration::from_secs(10),
+        };
+        assert_eq!(status.percent_complete(), 50);
+        assert_eq!(status.eta(), Some(Duration::from_secs(10)));
+
+        // a quarter of the rows were inserted after they were all read
+        let status = StartupStatus {
+            rows_loaded: 250,
+            elapsed: Duration::from_secs(15),
+            ..status
+        };
+        assert_eq!(status.percent_complete(), 62);
+        assert_eq!(status.eta(), Some(Duration::from_secs(9)));
+        assert_eq!(
+            status.to_string(),
+            "account_tree, 1000 read and 250 loaded of 1000 rows (62%), eta 9s"
+        );
+
+        let status = StartupStatus {
+            stage: StartupStage::Loaded,
+            ..status
+        };
+        assert_eq!(status.percent_complete(), 100);
+        assert_eq!(status.eta(), Some(Duration::ZERO));
+    }
+}
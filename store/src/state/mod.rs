@@ -0,0 +1,2167 @@
+//! Abstraction to synchornize state modifications.
+//!
+//! The [State] provides data access and modifications methods, its main purpose is to ensure that
+//! data is atomically written, and that reads are consistent.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Debug, Display, Formatter},
+    mem,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use miden_crypto::{
+    hash::rpo::RpoDigest,
+    merkle::{
+        LeafIndex, MerklePath, Mmr, MmrDelta, MmrPeaks, SimpleSmt, TieredSmt, TieredSmtProof,
+    },
+    Felt, FieldElement, StarkField, Word, EMPTY_WORD,
+};
+use miden_node_proto::{
+    account::AccountInfo,
+    block_header,
+    conversion::nullifier_value_to_blocknum,
+    digest::Digest,
+    domain::{BlockSignature, MerkleMultiproof, TimestampAttestation},
+    note::{Note, NoteCreated, NoteRecoveryRecord},
+    requests::AccountUpdate,
+    responses::{
+        AccountBlockInputRecord, AccountSyncRecord, AccountTransactionInputRecord, NoteTagStats,
+        NullifierTransactionInputRecord, TableSize,
+    },
+};
+use miden_node_utils::{
+    formatting::{format_account_id, format_array},
+    tag_policy::TagPolicy,
+};
+use miden_objects::{
+    accounts::Account as ObjectAccount,
+    notes::{Note as ObjectNote, NoteMetadata, NOTE_LEAF_DEPTH},
+    utils::serde::{Deserializable, Serializable},
+    BlockHeader, ACCOUNT_TREE_DEPTH,
+};
+use tokio::{
+    sync::{broadcast, oneshot, watch, Mutex, RwLock},
+    task,
+    time::Instant,
+};
+use tracing::{info, info_span, instrument, warn};
+
+use crate::{
+    backfill::{BackfillProgress, NATIVE_DIGESTS_BACKFILL, NOTE_TREES_BACKFILL},
+    batch_kernel::{verify_batch_contents, BatchKernelVerifier, BatchProof},
+    commitment::{CommitmentMetrics, StateCommitment},
+    config::NoteVisibilityConfig,
+    consistency::{check_block, ChainStats, ConsistencyMetrics},
+    db::{BlockUpdate, Db, StateSyncUpdate},
+    errors::{
+        ApplyBlockError, ConversionError, DatabaseError, GetBlockHeaderError, GetBlockInputsError,
+        GetNoteAuthenticationPathError, NoteRecoveryError, StateInitializationError,
+        StateSyncError,
+    },
+    genesis::GENESIS_BLOCK_NUM,
+    note_tree::{note_leaf_index, BlockNoteTree},
+    startup::{StartupProgress, StartupStage},
+    subscription::AppliedNullifiers,
+    sync_token::{sync_filters_digest, SyncToken},
+    types::{AccountId, BlockNumber},
+    COMPONENT, MAX_UNCONSUMED_NOTES, NOTE_TAG_STATS_WINDOW, NULLIFIER_SUBSCRIPTION_BUFFER,
+    STARTUP_CHUNK_SIZE,
+};
+
+#[cfg(test)]
+mod tests;
+
+// STRUCTURES
+// ================================================================================================
+
+/// Container for state that needs to be updated atomically.
+struct InnerState {
+    nullifier_tree: TieredSmt,
+    chain_mmr: Mmr,
+    account_tree: SimpleSmt<ACCOUNT_TREE_DEPTH>,
+    /// Number of every block of the chain, by block hash.
+    block_nums: BTreeMap<RpoDigest, BlockNumber>,
+    /// Number of notes and nullifiers of the chain.
+    stats: ChainStats,
+}
+
+/// A new block, as sent by the block producer.
+#[derive(Debug, Clone)]
+pub struct NewBlock {
+    pub block_header: block_header::BlockHeader,
+    pub nullifiers: Vec<RpoDigest>,
+    pub accounts: Vec<(AccountId, Digest)>,
+    /// Serialized states of the public accounts of `accounts` known to the block producer
+    pub account_details: Vec<(AccountId, Vec<u8>)>,
+    pub notes: Vec<NoteCreated>,
+    /// Links between the public notes consumed in the block and their nullifiers
+    pub consumed_notes: Vec<(RpoDigest, RpoDigest)>,
+    pub tx_kernel_versions: Vec<u32>,
+    /// Signature of the block by the block producer, if signed
+    pub signature: Option<block_header::BlockSignature>,
+    /// Whether the transactions of the block were ordered canonically by the block producer
+    pub canonical_tx_ordering: bool,
+    /// Attestation of the time the previous block was produced by a time authority, if attested.
+    /// It is kept along the previous block, which it attests.
+    pub timestamp_attestation: Option<block_header::TimestampAttestation>,
+    /// Proofs of the proven batches of the block, which the block commits to in its header
+    pub batch_proofs: Vec<BatchProof>,
+    /// Identifier of the chain of the block, which must be the chain of the store
+    pub chain_id: u32,
+}
+
+/// A new block validated against the state, and the data written to the DB to apply it.
+struct StagedBlock {
+    block: BlockHeader,
+    update: BlockUpdate,
+}
+
+/// Copies of the in-memory structures, updated with the staged blocks.
+struct StagedTrees {
+    account_tree: SimpleSmt<ACCOUNT_TREE_DEPTH>,
+    chain_mmr: Mmr,
+    nullifier_tree: TieredSmt,
+}
+
+/// The rules the new blocks are validated against, on top of the state they follow.
+struct BlockRules {
+    /// Identifier of the chain, which the signed messages are bound to.
+    chain_id: u32,
+
+    /// Timestamp of the genesis block, in seconds since the UNIX epoch. No block can precede it.
+    genesis_timestamp: u64,
+
+    /// Maximum number of seconds the timestamp of a new block may be ahead of the store's clock.
+    max_block_timestamp_skew_s: u64,
+
+    /// Policy of the tags of the notes created by new blocks.
+    tag_policy: TagPolicy,
+
+    /// Visibility of the notes, on top of the one of their metadata. The details of the private
+    /// notes are never stored.
+    note_visibility: NoteVisibilityConfig,
+
+    /// Whether every batch of the new blocks must be proven, the proofs being verified, see
+    /// [crate::batch_kernel].
+    verify_batch_proofs: bool,
+}
+
+/// A block staged by [State::propose_block], waiting to be committed.
+struct BlockProposal {
+    block: StagedBlock,
+    trees: StagedTrees,
+    proposed_at: Instant,
+}
+
+/// The rollup state
+pub struct State {
+    db: Arc<Db>,
+
+    /// Read-write lock used to prevent writing to a structure while it is being used.
+    ///
+    /// The lock is writer-preferring, meaning the writer won't be starved.
+    inner: RwLock<InnerState>,
+
+    /// To allow readers to access the tree data while an update in being performed, and prevent
+    /// TOCTOU issues, there must be no concurrent writers. This locks to serialize the writers.
+    writer: Mutex<()>,
+
+    /// Hash of the genesis block, it never changes once the state is loaded.
+    genesis_hash: RpoDigest,
+
+    /// Rules the new blocks are validated against, shared with the tasks staging them.
+    block_rules: Arc<BlockRules>,
+
+    /// The block waiting to be committed, see [State::propose_block].
+    proposal: Mutex<Option<BlockProposal>>,
+
+    /// Time after which a proposed block which was not committed is aborted.
+    proposal_timeout: Duration,
+
+    /// Counters of the suspicious blocks applied, see [crate::consistency].
+    consistency: ConsistencyMetrics,
+
+    /// The latest state commitment published, see [crate::commitment].
+    commitment_metrics: CommitmentMetrics,
+
+    /// Publishes the nullifiers of every applied block, see [crate::subscription].
+    applied_nullifiers: broadcast::Sender<AppliedNullifiers>,
+
+    /// Publishes the latest block of the chain, updated along with the in-memory state.
+    chain_tip: watch::Sender<ChainTip>,
+
+    /// Verifies the proofs of the batches of the new blocks, see [crate::batch_kernel].
+    batch_verifier: Arc<BatchKernelVerifier>,
+}
+
+/// The latest block of the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainTip {
+    pub block_num: BlockNumber,
+    pub block_hash: RpoDigest,
+}
+
+/// Totals of the chain, as of its latest block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainCounters {
+    pub chain_tip: ChainTip,
+    /// Root of the in-memory account tree.
+    pub account_root: RpoDigest,
+    pub num_accounts: u64,
+    pub stats: ChainStats,
+}
+
+/// Authentication of a block header against the chain root of a reference block.
+#[derive(Debug)]
+pub struct BlockHeaderMmrProof {
+    /// Number of the reference block, whose chain root commits to the `chain_length` blocks before
+    /// it.
+    pub chain_length: BlockNumber,
+
+    /// Hash of the reference block, the chain tip when the proof was made.
+    pub chain_tip_hash: RpoDigest,
+
+    /// Path of the block header in the chain MMR with `chain_length` leaves. `None` if the header
+    /// is the reference block itself, which its own chain root doesn't commit to.
+    pub mmr_path: Option<MerklePath>,
+}
+
+/// Notes created over the latest blocks, see [State::get_note_tag_stats].
+#[derive(Debug)]
+pub struct NoteTagActivity {
+    /// Last block covered by the stats.
+    pub chain_tip: BlockNumber,
+
+    /// First block covered by the stats.
+    pub from_block: BlockNumber,
+
+    /// Number of notes created from `from_block` to `chain_tip`, with any tag.
+    pub total_notes: u32,
+
+    /// Activity of every requested tag, in the order of the request.
+    pub tags: Vec<NoteTagStats>,
+}
+
+/// Notes which weren't consumed, see [State::get_unconsumed_notes].
+#[derive(Debug)]
+pub struct UnconsumedNotes {
+    /// Latest block at which the notes weren't consumed.
+    pub chain_tip: BlockNumber,
+
+    /// Notes matching the requested tags, ordered by block number and note index.
+    pub notes: Vec<Note>,
+
+    /// First block whose notes weren't listed.
+    pub next_block_num: BlockNumber,
+}
+
+/// Latest states of some accounts, proven against the chain tip, see [State::sync_accounts].
+#[derive(Debug)]
+pub struct AccountsSync {
+    /// Header of the chain tip, the paths of the accounts are against its `account_root`.
+    pub block_header: block_header::BlockHeader,
+
+    /// Requested accounts updated after the client's block, ordered by their latest update.
+    pub accounts: Vec<AccountSyncRecord>,
+}
+
+/// Notes of the block waiting to be committed, see [State::get_proposed_notes].
+#[derive(Debug)]
+pub struct ProposedNotes {
+    /// Header of the proposed block, the paths of the notes are against its `note_root`.
+    pub block_header: block_header::BlockHeader,
+
+    /// Notes created by the proposed block, with their merkle paths, ordered by note index.
+    pub notes: Vec<Note>,
+}
+
+pub struct AccountState {
+    account_id: AccountId,
+    account_hash: Word,
+    /// Serialized state of the account, if it is public and known to the store
+    details: Option<Vec<u8>>,
+}
+
+impl Debug for AccountState {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ account_id: {}, account_hash: {} }}",
+            format_account_id(self.account_id),
+            RpoDigest::from(self.account_hash),
+        ))
+    }
+}
+
+impl Display for AccountState {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+impl From<AccountState> for AccountTransactionInputRecord {
+    fn from(value: AccountState) -> Self {
+        Self {
+            account_id: Some(value.account_id.into()),
+            account_hash: Some(value.account_hash.into()),
+            details: value.details.unwrap_or_default(),
+        }
+    }
+}
+
+/// The account's authentication path is sent separately, as part of a multiproof.
+impl From<AccountState> for AccountBlockInputRecord {
+    fn from(value: AccountState) -> Self {
+        Self {
+            account_id: Some(value.account_id.into()),
+            account_hash: Some(value.account_hash.into()),
+            proof: None,
+        }
+    }
+}
+
+impl TryFrom<AccountUpdate> for AccountState {
+    type Error = ConversionError;
+
+    fn try_from(value: AccountUpdate) -> Result<Self, Self::Error> {
+        Ok(Self {
+            account_id: value
+                .account_id
+                .ok_or(ConversionError::MissingFieldInProtobufRepresentation {
+                    entity: "account update",
+                    field_name: "account_id",
+                })?
+                .into(),
+            account_hash: value
+                .account_hash
+                .ok_or(ConversionError::MissingFieldInProtobufRepresentation {
+                    entity: "account update",
+                    field_name: "account_hash",
+                })?
+                .try_into()?,
+            details: (!value.details.is_empty()).then_some(value.details),
+        })
+    }
+}
+
+impl TryFrom<&AccountUpdate> for AccountState {
+    type Error = ConversionError;
+
+    fn try_from(value: &AccountUpdate) -> Result<Self, Self::Error> {
+        value.clone().try_into()
+    }
+}
+
+#[derive(Debug)]
+pub struct NullifierStateForTransactionInput {
+    nullifier: RpoDigest,
+    block_num: u32,
+}
+
+impl Display for NullifierStateForTransactionInput {
+    fn fmt(
+        &self,
+        formatter: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        formatter.write_fmt(format_args!(
+            "{{ nullifier: {}, block_num: {} }}",
+            self.nullifier, self.block_num
+        ))
+    }
+}
+
+impl From<NullifierStateForTransactionInput> for NullifierTransactionInputRecord {
+    fn from(value: NullifierStateForTransactionInput) -> Self {
+        Self {
+            nullifier: Some(value.nullifier.into()),
+            block_num: value.block_num,
+        }
+    }
+}
+
+impl State {
+    /// Loads the state from the `db`.
+    pub async fn load(
+        db: Db,
+        max_block_timestamp_skew_s: u64,
+        proposal_timeout: Duration,
+        tag_policy: TagPolicy,
+        note_visibility: NoteVisibilityConfig,
+        verify_batch_proofs: bool,
+    ) -> Result<Self, StateInitializationError> {
+        Self::load_with_progress(
+            Arc::new(db),
+            max_block_timestamp_skew_s,
+            proposal_timeout,
+            tag_policy,
+            note_visibility,
+            verify_batch_proofs,
+            &StartupProgress::default(),
+        )
+        .await
+    }
+
+    /// Loads the state from the `db`, reporting the progress of the trees being built to
+    /// `progress`.
+    ///
+    /// The rows are read from the database, then inserted into the trees, in chunks of
+    /// [STARTUP_CHUNK_SIZE], yielding to the other tasks in between, so the store may serve
+    /// requests from the database meanwhile.
+    #[instrument(target = "miden-store", skip_all)]
+    pub async fn load_with_progress(
+        db: Arc<Db>,
+        max_block_timestamp_skew_s: u64,
+        proposal_timeout: Duration,
+        tag_policy: TagPolicy,
+        note_visibility: NoteVisibilityConfig,
+        verify_batch_proofs: bool,
+        progress: &StartupProgress,
+    ) -> Result<Self, StateInitializationError> {
+        // the records stored before their notes were made private are dropped
+        let num_private_records = db
+            .delete_note_recovery_records_by_tag(note_visibility.private_tags.clone())
+            .await?;
+        if num_private_records > 0 {
+            info!(
+                target: COMPONENT,
+                num_private_records, "Deleted the recovery records of private notes"
+            );
+        }
+
+        progress.rows_counted(db.count_startup_rows().await?);
+        let nullifiers = load_nullifiers(&db, progress).await?;
+        let account_hashes = load_account_hashes(&db, progress).await?;
+        let block_headers = load_block_headers(&db, progress).await?;
+        let genesis_header = load_genesis_header(&db).await?;
+        let num_nullifiers = nullifiers.len();
+
+        let nullifier_tree = build_nullifier_tree(nullifiers, progress).await;
+        let account_tree = build_account_tree(account_hashes, progress).await;
+        let (chain_mmr, block_nums) = build_chain_mmr(block_headers, progress).await;
+        let chain_tip = block_nums
+            .iter()
+            .max_by_key(|(_, &block_num)| block_num)
+            .map(|(&block_hash, &block_num)| ChainTip {
+                block_num,
+                block_hash,
+            })
+            .expect("the chain contains at least the genesis block");
+        let stats = ChainStats {
+            num_notes: db.count_notes().await?,
+            num_nullifiers: num_nullifiers as u64,
+        };
+
+        let inner = RwLock::new(InnerState {
+            nullifier_tree,
+            chain_mmr,
+            account_tree,
+            block_nums,
+            stats,
+        });
+
+        let writer = Mutex::new(());
+        progress.set_stage(StartupStage::Loaded);
+        Ok(Self {
+            db,
+            inner,
+            writer,
+            genesis_hash: genesis_header.hash(),
+            block_rules: Arc::new(BlockRules {
+                chain_id: db.chain_id(),
+                genesis_timestamp: genesis_header.timestamp().as_int(),
+                max_block_timestamp_skew_s,
+                tag_policy,
+                note_visibility,
+                verify_batch_proofs,
+            }),
+            proposal: Mutex::new(None),
+            proposal_timeout,
+            consistency: ConsistencyMetrics::default(),
+            commitment_metrics: CommitmentMetrics::default(),
+            applied_nullifiers: broadcast::channel(NULLIFIER_SUBSCRIPTION_BUFFER).0,
+            chain_tip: watch::channel(chain_tip).0,
+            batch_verifier: Arc::new(BatchKernelVerifier::new()),
+        })
+    }
+
+    /// Returns the hash of the genesis block of the chain.
+    pub fn genesis_hash(&self) -> RpoDigest {
+        self.genesis_hash
+    }
+
+    /// Returns the policy of the tags of the notes created by new blocks.
+    pub fn tag_policy(&self) -> &TagPolicy {
+        &self.block_rules.tag_policy
+    }
+
+    /// Returns the number of the latest block of the chain.
+    pub async fn chain_tip(&self) -> BlockNumber {
+        // the chain MMR contains every block, starting with the genesis block
+        (self.inner.read().await.chain_mmr.forest() - 1) as BlockNumber
+    }
+
+    /// Returns the number and hash of the latest block of the chain, read at once.
+    pub fn latest_chain_tip(&self) -> ChainTip {
+        *self.chain_tip.borrow()
+    }
+
+    /// Returns the totals of the chain, all read at the same block.
+    ///
+    /// Counting the accounts walks the account tree, this is meant for occasional audits rather
+    /// than for every request.
+    pub async fn chain_counters(&self) -> ChainCounters {
+        let inner = self.inner.read().await;
+
+        ChainCounters {
+            // the tip is published while the lock is held
+            chain_tip: self.latest_chain_tip(),
+            account_root: inner.account_tree.root(),
+            num_accounts: inner.account_tree.leaves().count() as u64,
+            stats: inner.stats,
+        }
+    }
+
+    /// Returns a receiver of the latest block of the chain, which is notified of every change.
+    ///
+    /// Blocks applied in quick succession may be notified at once, only the latest one is kept.
+    pub fn subscribe_chain_tip(&self) -> watch::Receiver<ChainTip> {
+        self.chain_tip.subscribe()
+    }
+
+    /// Returns a receiver of the nullifiers of the blocks applied from now on.
+    ///
+    /// A receiver which falls more than [NULLIFIER_SUBSCRIPTION_BUFFER] blocks behind misses blocks,
+    /// see [State::nullifier_blocks] to catch up.
+    pub fn subscribe_nullifiers(&self) -> broadcast::Receiver<AppliedNullifiers> {
+        self.applied_nullifiers.subscribe()
+    }
+
+    /// Returns the chain tip, and the block in which each of the `nullifiers` was consumed, zero if
+    /// not consumed yet.
+    ///
+    /// Both are read at once, so every nullifier consumed up to the returned chain tip is reported.
+    pub async fn nullifier_blocks(
+        &self,
+        nullifiers: &[RpoDigest],
+    ) -> (BlockNumber, Vec<BlockNumber>) {
+        let inner = self.inner.read().await;
+
+        let chain_tip = (inner.chain_mmr.forest() - 1) as BlockNumber;
+        let block_nums = nullifiers
+            .iter()
+            .map(|n| nullifier_value_to_blocknum(inner.nullifier_tree.get_value(*n)))
+            .collect();
+
+        (chain_tip, block_nums)
+    }
+
+    /// Returns the counters of the suspicious blocks applied since the state was loaded.
+    pub fn consistency(&self) -> &ConsistencyMetrics {
+        &self.consistency
+    }
+
+    /// Returns the latest state commitment published, and the number of diverged commitments.
+    pub fn commitment_metrics(&self) -> &CommitmentMetrics {
+        &self.commitment_metrics
+    }
+
+    /// Apply changes of a new block to the DB and in-memory data structures.
+    ///
+    /// ## Note on state consistency
+    ///
+    /// The server contains in-memory representations of the existing trees, the in-memory
+    /// representation must be kept consistent with the committed data, this is necessary so to
+    /// provide consistent results for all endpoints. In order to achieve consistency, the
+    /// following steps are used:
+    ///
+    /// - the request data is validated, prior to starting any modifications.
+    /// - a transaction is open in the DB and the writes are started.
+    /// - while the transaction is not committed, concurrent reads are allowed, both the DB and
+    ///   the in-memory representations, which are consistent at this stage.
+    /// - prior to committing the changes to the DB, an exclusive lock to the in-memory data is
+    ///   acquired, preventing concurrent reads to the in-memory data, since that will be
+    ///   out-of-sync w.r.t. the DB.
+    /// - the DB transaction is committed, and requests that read only from the DB can proceed to
+    ///   use the fresh data.
+    /// - the in-memory structures are updated, and the lock is released. If the transaction failed
+    ///   to commit, the lock is released without any change.
+    ///
+    /// The block is validated and applied at once, see [State::propose_block] and
+    /// [State::commit_block] to apply it in two steps.
+    // TODO: This span is logged in a root span, we should connect it to the parent span.
+    #[instrument(target = "miden-store", skip_all, err)]
+    pub async fn apply_block(
+        &self,
+        block: NewBlock,
+    ) -> Result<(), ApplyBlockError> {
+        self.verify_batch_proofs(&block).await?;
+
+        let _writer = self.writer.try_lock().map_err(|_| ApplyBlockError::ConcurrentWrite)?;
+        self.db.check_disk_limits().await?;
+
+        let (staged, trees) = self.stage_block(block).await?;
+        self.commit_staged_blocks(vec![staged], trees).await
+    }
+
+    /// Applies a contiguous run of blocks, the first one following the chain tip, and returns the
+    /// new chain tip.
+    ///
+    /// Every block is validated against the state updated with the blocks before it, the in-memory
+    /// structures are updated in a single pass and the blocks are written in a single DB
+    /// transaction, following the same steps as [State::apply_block]. Either every block is
+    /// applied, or none of them.
+    #[instrument(target = "miden-store", skip_all, err)]
+    pub async fn apply_blocks(
+        &self,
+        blocks: Vec<NewBlock>,
+    ) -> Result<BlockNumber, ApplyBlockError> {
+        for block in blocks.iter() {
+            self.verify_batch_proofs(block).await?;
+        }
+
+        let _writer = self.writer.try_lock().map_err(|_| ApplyBlockError::ConcurrentWrite)?;
+        self.db.check_disk_limits().await?;
+
+        let (staged, trees) = self.stage_blocks(blocks).await?;
+        let Some(chain_tip) = staged.last().map(|staged| staged.block.block_num()) else {
+            return Ok(self.latest_chain_tip().block_num);
+        };
+        self.commit_staged_blocks(staged, trees).await?;
+
+        Ok(chain_tip)
+    }
+
+    /// Validates a new block against the current state, and stages it to be applied by
+    /// [State::commit_block]. Returns the hash of the block.
+    ///
+    /// A single block is staged at a time. Proposing the staged block again replaces it, while
+    /// proposing a different block fails until the staged one is committed, or is aborted for not
+    /// being committed within the proposal timeout.
+    #[instrument(target = "miden-store", skip_all, err)]
+    pub async fn propose_block(
+        &self,
+        block: NewBlock,
+    ) -> Result<RpoDigest, ApplyBlockError> {
+        self.verify_batch_proofs(&block).await?;
+
+        let _writer = self.writer.try_lock().map_err(|_| ApplyBlockError::ConcurrentWrite)?;
+        self.db.check_disk_limits().await?;
+
+        let block_hash = BlockHeader::try_from(block.block_header.clone())?.hash();
+
+        let mut proposal = self.proposal.lock().await;
+        if let Some(pending) = proposal.as_ref() {
+            let pending_hash = pending.block.block.hash();
+            if pending.proposed_at.elapsed() >= self.proposal_timeout {
+                info!(target: COMPONENT, block_hash = %pending_hash, "Stale block proposal aborted");
+                *proposal = None;
+            } else if pending_hash != block_hash {
+                return Err(ApplyBlockError::ProposalPending(pending_hash));
+            }
+        }
+
+        let (staged, trees) = self.stage_block(block).await?;
+        *proposal = Some(BlockProposal {
+            block: staged,
+            trees,
+            proposed_at: Instant::now(),
+        });
+
+        Ok(block_hash)
+    }
+
+    /// Applies the block `block_hash` staged by [State::propose_block], and returns its number.
+    ///
+    /// Committing a block which is already part of the chain succeeds without any change, so a
+    /// caller unsure whether its commit succeeded can safely retry it.
+    #[instrument(target = "miden-store", skip_all, err)]
+    pub async fn commit_block(
+        &self,
+        block_hash: RpoDigest,
+    ) -> Result<BlockNumber, ApplyBlockError> {
+        let _writer = self.writer.try_lock().map_err(|_| ApplyBlockError::ConcurrentWrite)?;
+
+        if let Some(&block_num) = self.inner.read().await.block_nums.get(&block_hash) {
+            return Ok(block_num);
+        }
+
+        let pending = {
+            let mut proposal = self.proposal.lock().await;
+            match proposal.take() {
+                Some(pending) if pending.block.block.hash() == block_hash => {
+                    if pending.proposed_at.elapsed() >= self.proposal_timeout {
+                        return Err(ApplyBlockError::ProposalExpired(block_hash));
+                    }
+                    pending
+                },
+                pending => {
+                    *proposal = pending;
+                    return Err(ApplyBlockError::ProposalNotFound(block_hash));
+                },
+            }
+        };
+
+        // a block may have been applied directly since the proposal, making it stale
+        let block_num = pending.block.block.block_num();
+        if self.inner.read().await.chain_mmr.forest() != block_num as usize {
+            return Err(ApplyBlockError::NewBlockInvalidBlockNum);
+        }
+
+        self.commit_staged_blocks(vec![pending.block], pending.trees).await?;
+
+        Ok(block_num)
+    }
+
+    /// Verifies the proofs of the batches of `block`, and that its header commits to them, see
+    /// [crate::batch_kernel]. Nothing is verified unless enabled in the configuration.
+    ///
+    /// The proofs only depend on the block, they are verified before the writer lock is taken and
+    /// off the async runtime.
+    async fn verify_batch_proofs(
+        &self,
+        block: &NewBlock,
+    ) -> Result<(), ApplyBlockError> {
+        if !self.block_rules.verify_batch_proofs {
+            return Ok(());
+        }
+
+        let header = BlockHeader::try_from(block.block_header.clone())?;
+        let batch_proofs = block.batch_proofs.clone();
+        let batch_verifier = self.batch_verifier.clone();
+
+        task::spawn_blocking(move || {
+            batch_verifier.verify_block(header.batch_root(), header.proof_hash(), &batch_proofs)
+        })
+        .await
+        .map_err(|err| ApplyBlockError::BatchProofVerificationTaskFailed(err.to_string()))?
+    }
+
+    /// Validates a new block against the current state, and computes the in-memory structures
+    /// updated with it.
+    ///
+    /// The caller must hold the writer lock, so the state doesn't change until the block is
+    /// committed.
+    async fn stage_block(
+        &self,
+        block: NewBlock,
+    ) -> Result<(StagedBlock, StagedTrees), ApplyBlockError> {
+        let (mut staged, trees) = self.stage_blocks(vec![block]).await?;
+        let staged = staged.pop().expect("a block was staged");
+
+        Ok((staged, trees))
+    }
+
+    /// Validates a contiguous run of new blocks, the first one following the chain tip, and
+    /// computes the in-memory structures updated with them, see [State::stage_block].
+    ///
+    /// The trees are updated off the async runtime, the caller holding the writer lock meanwhile.
+    async fn stage_blocks(
+        &self,
+        blocks: Vec<NewBlock>,
+    ) -> Result<(Vec<StagedBlock>, StagedTrees), ApplyBlockError> {
+        let prev_block = self.latest_block_header().await?;
+        let trees = self.staged_trees().await;
+        let block_rules = self.block_rules.clone();
+
+        task::spawn_blocking(move || {
+            let _span = info_span!(target: COMPONENT, "update_in_memory_structs").entered();
+            block_rules.stage_blocks_onto(trees, prev_block, blocks)
+        })
+        .await
+        .map_err(|err| ApplyBlockError::BlockStagingTaskFailed(err.to_string()))?
+    }
+
+    /// Returns the header of the latest block, which new blocks must follow.
+    async fn latest_block_header(&self) -> Result<BlockHeader, ApplyBlockError> {
+        Ok(self
+            .db
+            .select_block_header_by_block_num(None)
+            .await?
+            .ok_or(ApplyBlockError::DbBlockHeaderEmpty)?
+            .try_into()?)
+    }
+
+    /// Returns copies of the in-memory structures, to be updated with new blocks.
+    async fn staged_trees(&self) -> StagedTrees {
+        let inner = self.inner.read().await;
+
+        StagedTrees {
+            account_tree: inner.account_tree.clone(),
+            chain_mmr: inner.chain_mmr.clone(),
+            nullifier_tree: inner.nullifier_tree.clone(),
+        }
+    }
+
+    /// Writes staged blocks to the DB in a single transaction, and swaps in the in-memory
+    /// structures updated with them.
+    ///
+    /// The caller must hold the writer lock, see [State::stage_block].
+    async fn commit_staged_blocks(
+        &self,
+        blocks: Vec<StagedBlock>,
+        trees: StagedTrees,
+    ) -> Result<(), ApplyBlockError> {
+        let mut applied = Vec::with_capacity(blocks.len());
+        let mut updates = Vec::with_capacity(blocks.len());
+        for StagedBlock { block, update } in blocks {
+            let applied_nullifiers = AppliedNullifiers {
+                block_num: block.block_num(),
+                nullifiers: Arc::new(update.nullifiers.clone()),
+            };
+            applied.push((block, update.notes.len(), applied_nullifiers));
+            updates.push(update);
+        }
+
+        // signals the transaction is ready to be committed, and the write lock can be acquired
+        let (allow_acquire, acquired_allowed) = oneshot::channel::<()>();
+        // signals the write lock has been acquired, and the transaction can be committed
+        let (inform_acquire_done, acquire_done) = oneshot::channel::<()>();
+
+        // The DB and in-memory state updates need to be synchronized and are partially
+        // overlapping. Namely, the DB transaction only proceeds after this task acquires the
+        // in-memory write lock. This requires the DB update to run concurrently, so a new task is
+        // spawned.
+        let db = self.db.clone();
+        let db_update_task =
+            tokio::spawn(
+                async move { db.apply_blocks(allow_acquire, acquire_done, updates).await },
+            );
+
+        acquired_allowed
+            .await
+            .map_err(ApplyBlockError::BlockApplyingBrokenBecauseOfClosedChannel)?;
+
+        // scope to update the in-memory data
+        {
+            let mut inner = self.inner.write().await;
+            let _ = inform_acquire_done.send(());
+
+            // the in-memory structures are only swapped once the transaction is committed, the
+            // readers waiting on the lock must not observe blocks missing from the DB
+            db_update_task
+                .await
+                .map_err(|err| ApplyBlockError::DbUpdateTaskFailed(err.to_string()))??;
+
+            let StagedTrees {
+                account_tree,
+                chain_mmr,
+                nullifier_tree,
+            } = trees;
+            let _ = mem::replace(&mut inner.chain_mmr, chain_mmr);
+            let _ = mem::replace(&mut inner.nullifier_tree, nullifier_tree);
+            let _ = mem::replace(&mut inner.account_tree, account_tree);
+
+            for (block, num_notes, applied_nullifiers) in applied {
+                inner.block_nums.insert(block.hash(), block.block_num());
+
+                // suspicious blocks are flagged, not rejected, see [crate::consistency]
+                let num_nullifiers = applied_nullifiers.nullifiers.len();
+                let anomalies = check_block(&inner.stats, num_notes, num_nullifiers);
+                inner.stats.add_block(num_notes, num_nullifiers);
+                for anomaly in anomalies.iter() {
+                    warn!(target: COMPONENT, block_num = block.block_num(), %anomaly, "Suspicious block applied");
+                }
+                self.consistency.record(&anomalies);
+
+                // published while the lock is held, so the subscribers catching up with the
+                // in-memory state either see the block in the nullifier tree, or receive it
+                let _ = self.applied_nullifiers.send(applied_nullifiers);
+                self.chain_tip.send_replace(ChainTip {
+                    block_num: block.block_num(),
+                    block_hash: block.hash(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Queries a [BlockHeader] from the database, and optionally its proof in the chain MMR.
+    ///
+    /// If [None] is given as the value of `block_num`, the latest [BlockHeader] is returned.
+    ///
+    /// If `include_mmr_proof` is set, the header is authenticated against the chain root of the
+    /// latest block, see [BlockHeaderMmrProof].
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn get_block_header(
+        &self,
+        block_num: Option<BlockNumber>,
+        include_mmr_proof: bool,
+    ) -> Result<(Option<block_header::BlockHeader>, Option<BlockHeaderMmrProof>), GetBlockHeaderError>
+    {
+        if !include_mmr_proof {
+            return Ok((self.db.select_block_header_by_block_num(block_num).await?, None));
+        }
+
+        // the lock is held while reading the header, so the chain MMR matches the database
+        let inner = self.inner.read().await;
+
+        let Some(block_header) = self.db.select_block_header_by_block_num(block_num).await? else {
+            return Ok((None, None));
+        };
+
+        // the MMR contains every block up to and including the tip, and the tip's chain root
+        // commits to all blocks before it
+        let chain_length = (inner.chain_mmr.forest() - 1) as BlockNumber;
+        // the tip is published while the lock is held, it is the block `chain_length`
+        let chain_tip_hash = self.latest_chain_tip().block_hash;
+        let mmr_path = if block_header.block_num < chain_length {
+            let proof = inner
+                .chain_mmr
+                .open(block_header.block_num as usize, chain_length as usize)
+                .map_err(GetBlockHeaderError::FailedToCreateMmrProof)?;
+            Some(proof.merkle_path)
+        } else {
+            None
+        };
+
+        Ok((
+            Some(block_header),
+            Some(BlockHeaderMmrProof {
+                chain_length,
+                chain_tip_hash,
+                mmr_path,
+            }),
+        ))
+    }
+
+    /// Returns the commitment to the state at the block `block_num`, at the chain tip if [None],
+    /// `None` if the block is not known.
+    ///
+    /// At the chain tip, the roots and the totals of the chain are read from the in-memory
+    /// structures, and a commitment which doesn't match the database is logged and counted, see
+    /// [crate::commitment].
+    pub async fn state_commitment(
+        &self,
+        block_num: Option<BlockNumber>,
+    ) -> Result<Option<StateCommitment>, DatabaseError> {
+        // the lock is held so the in-memory structures stay at the chain tip
+        let inner = self.inner.read().await;
+        let chain_tip = self.latest_chain_tip();
+        let block_num = block_num.unwrap_or(chain_tip.block_num);
+
+        let Some(mut commitment) = self.db.select_state_commitment(block_num).await? else {
+            return Ok(None);
+        };
+        if block_num != chain_tip.block_num {
+            return Ok(Some(commitment));
+        }
+
+        let stored = commitment;
+        commitment.chain_root = inner
+            .chain_mmr
+            .peaks(block_num as usize)
+            .expect("the chain MMR contains the blocks before the chain tip")
+            .hash_peaks();
+        commitment.account_root = inner.account_tree.root();
+        commitment.nullifier_root = Some(inner.nullifier_tree.root());
+        commitment.num_notes = inner.stats.num_notes;
+        commitment.num_nullifiers = inner.stats.num_nullifiers;
+
+        if commitment.digest() != stored.digest() {
+            warn!(
+                target: COMPONENT,
+                block_num,
+                chain_root = %commitment.chain_root,
+                account_root = %commitment.account_root,
+                num_notes = commitment.num_notes,
+                num_nullifiers = commitment.num_nullifiers,
+                stored_chain_root = %stored.chain_root,
+                stored_account_root = %stored.account_root,
+                stored_num_notes = stored.num_notes,
+                stored_num_nullifiers = stored.num_nullifiers,
+                "In-memory state diverged from the database"
+            );
+            self.commitment_metrics.record_divergence();
+        }
+
+        Ok(Some(commitment))
+    }
+
+    /// Generates membership proofs for each one of the `nullifiers` against the latest nullifier
+    /// tree, and looks up the block at which each one of the `nullifiers_without_proofs` has been
+    /// consumed, zero if not consumed.
+    ///
+    /// Both lists are answered from the same version of the nullifier tree. Looking up the block
+    /// number is much cheaper than generating a proof, so callers which don't need the proofs
+    /// should use `nullifiers_without_proofs`.
+    ///
+    /// Note: these proofs are invalidated once the nullifier tree is modified, i.e. on a new block.
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"))]
+    pub async fn check_nullifiers(
+        &self,
+        nullifiers: &[RpoDigest],
+        nullifiers_without_proofs: &[RpoDigest],
+    ) -> (Vec<TieredSmtProof>, Vec<BlockNumber>) {
+        let inner = self.inner.read().await;
+
+        let proofs = nullifiers.iter().map(|n| inner.nullifier_tree.prove(*n)).collect();
+        let block_nums = nullifiers_without_proofs
+            .iter()
+            .map(|n| nullifier_value_to_blocknum(inner.nullifier_tree.get_value(*n)))
+            .collect();
+
+        (proofs, block_nums)
+    }
+
+    /// Loads data to synchronize a client.
+    ///
+    /// The client's request contains a list of tag prefixes, this method will return the first
+    /// block with a matching tag, or the chain tip. All the other values are filter based on this
+    /// block range.
+    ///
+    /// # Arguments
+    ///
+    /// - `block_num`: The last block *know* by the client, updates start from the next block.
+    /// - `account_ids`: Include the account's hash if their _last change_ was in the result's block
+    ///   range.
+    /// - `note_tag_prefixes`: Only the 16 high bits of the tags the client is interested in, result
+    ///   will include notes with matching prefixes, the first block with a matching note determines
+    ///   the block range.
+    /// - `nullifier_prefixes`: Only the 16 high bits of the nullifiers the client is interested in,
+    ///   results will include nullifiers matching prefixes produced in the given block range.
+    /// - `note_hashes`: Notes tracked by the client, results will include the ones consumed in the
+    ///   given block range, if they were linked to their nullifier when the block was applied.
+    ///
+    /// Along with the update, returns the token resuming the sync after the update's block, see
+    /// [State::resume_sync].
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn sync_state(
+        &self,
+        block_num: BlockNumber,
+        account_ids: &[AccountId],
+        note_tag_prefixes: &[u32],
+        nullifier_prefixes: &[u32],
+        note_hashes: &[RpoDigest],
+    ) -> Result<(StateSyncUpdate, MmrDelta, SyncToken), StateSyncError> {
+        let inner = self.inner.read().await;
+
+        let mut state_sync = self
+            .db
+            .get_state_sync(
+                block_num,
+                account_ids,
+                note_tag_prefixes,
+                nullifier_prefixes,
+                note_hashes,
+            )
+            .await?;
+
+        // all the notes belong to the returned block
+        if !state_sync.notes.is_empty() {
+            let note_tree = self.get_note_tree(state_sync.block_header.block_num).await?;
+            add_note_paths(&mut state_sync.notes, &note_tree)?;
+        }
+
+        let delta = if block_num == state_sync.block_header.block_num {
+            // The client is in sync with the chain tip.
+            MmrDelta {
+                forest: block_num as usize,
+                data: vec![],
+            }
+        } else {
+            // Important notes about the boundary conditions:
+            //
+            // - The Mmr forest is 1-indexed whereas the block number is 0-indexed. The Mmr root
+            // contained in the block header always lag behind by one block, this is because the Mmr
+            // leaves are hashes of block headers, and we can't have self-referential hashes. These two
+            // points cancel out and don't require adjusting.
+            // - Mmr::get_delta is inclusive, whereas the sync_state request block_num is defined to be
+            // exclusive, so the from_forest has to be adjusted with a +1
+            let from_forest = (block_num + 1) as usize;
+            let to_forest = state_sync.block_header.block_num as usize;
+            inner
+                .chain_mmr
+                .get_delta(from_forest, to_forest)
+                .map_err(StateSyncError::FailedToBuildMmrDelta)?
+        };
+
+        let block_header: BlockHeader = state_sync.block_header.clone().try_into()?;
+        let token = SyncToken {
+            block_num: block_header.block_num(),
+            block_hash: block_header.hash(),
+            filters: sync_filters_digest(
+                account_ids,
+                note_tag_prefixes,
+                nullifier_prefixes,
+                note_hashes,
+            ),
+        };
+
+        Ok((state_sync, delta, token))
+    }
+
+    /// Returns the latest states of the accounts of `account_ids` updated after the block
+    /// `block_num`, with their paths in the account tree of the chain tip, and the chain tip's
+    /// header.
+    ///
+    /// Unlike [State::sync_state], the accounts are synced up to the chain tip at once, and their
+    /// hashes are proven, so a light client can track its accounts trusting only the header.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn sync_accounts(
+        &self,
+        block_num: BlockNumber,
+        account_ids: &[AccountId],
+    ) -> Result<AccountsSync, StateSyncError> {
+        // the lock is held while reading the DB, so the account tree matches the chain tip
+        let inner = self.inner.read().await;
+        let chain_tip = (inner.chain_mmr.forest() - 1) as BlockNumber;
+
+        let block_header = self
+            .db
+            .select_block_header_by_block_num(Some(chain_tip))
+            .await?
+            .ok_or(StateSyncError::EmptyBlockHeadersTable)?;
+        let updates = self
+            .db
+            .select_account_updates(block_num, chain_tip, account_ids.to_vec())
+            .await?;
+
+        let accounts = updates
+            .into_iter()
+            .filter_map(|update| {
+                let account_id: AccountId = update.account_id?.into();
+                let opening = inner.account_tree.open(&LeafIndex::new_max_depth(account_id));
+
+                Some(AccountSyncRecord {
+                    account_id: Some(account_id.into()),
+                    account_hash: Some(RpoDigest::from(opening.value).into()),
+                    block_num: update.block_num,
+                    proof: Some(opening.path.into()),
+                })
+            })
+            .collect();
+
+        Ok(AccountsSync {
+            block_header,
+            accounts,
+        })
+    }
+
+    /// Returns the last block known by a client resuming its sync with `token`.
+    ///
+    /// The filters must be the ones of the sync which returned the token, and its block must still
+    /// be part of the chain, otherwise the client would miss updates.
+    pub async fn resume_sync(
+        &self,
+        token: &SyncToken,
+        account_ids: &[AccountId],
+        note_tag_prefixes: &[u32],
+        nullifier_prefixes: &[u32],
+        note_hashes: &[RpoDigest],
+    ) -> Result<BlockNumber, StateSyncError> {
+        let filters =
+            sync_filters_digest(account_ids, note_tag_prefixes, nullifier_prefixes, note_hashes);
+        if token.filters != filters {
+            return Err(StateSyncError::SyncFiltersMismatch);
+        }
+
+        let inner = self.inner.read().await;
+        if inner.block_nums.get(&token.block_hash) != Some(&token.block_num) {
+            return Err(StateSyncError::SyncBlockNotInChain(token.block_num));
+        }
+
+        Ok(token.block_num)
+    }
+
+    /// Returns data needed by the block producer to construct and prove the next block.
+    pub async fn get_block_inputs(
+        &self,
+        account_ids: &[AccountId],
+        _nullifiers: &[RpoDigest],
+    ) -> Result<
+        (block_header::BlockHeader, MmrPeaks, Vec<AccountState>, MerkleMultiproof),
+        GetBlockInputsError,
+    > {
+        let inner = self.inner.read().await;
+
+        let latest = self
+            .db
+            .select_block_header_by_block_num(None)
+            .await?
+            .ok_or(GetBlockInputsError::DbBlockHeaderEmpty)?;
+
+        // sanity check
+        if inner.chain_mmr.forest() != latest.block_num as usize + 1 {
+            return Err(GetBlockInputsError::IncorrectChainMmrForestNumber {
+                forest: inner.chain_mmr.forest(),
+                block_num: latest.block_num,
+            });
+        }
+
+        // using current block number gets us the peaks of the chain MMR as of one block ago;
+        // this is done so that latest.chain_root matches the returned peaks
+        let peaks = inner.chain_mmr.peaks(latest.block_num as usize).map_err(|error| {
+            GetBlockInputsError::FailedToGetMmrPeaksForForest {
+                forest: latest.block_num as usize,
+                error,
+            }
+        })?;
+        let account_states = account_ids
+            .iter()
+            .cloned()
+            .map(|account_id| AccountState {
+                account_id,
+                account_hash: inner.account_tree.get_leaf(&LeafIndex::new_max_depth(account_id)),
+                details: None,
+            })
+            .collect();
+
+        // the authentication paths of all accounts are generated at once, sharing common nodes
+        let account_proofs =
+            MerkleMultiproof::from_tree(&inner.account_tree, account_ids.iter().cloned())
+                .map_err(GetBlockInputsError::FailedToCreateAccountProofs)?;
+
+        // TODO: add nullifiers
+        Ok((latest, peaks, account_states, account_proofs))
+    }
+
+    /// Returns data needed by the block producer to verify transactions validity.
+    ///
+    /// Along the account and nullifiers states, returns the number of the block `block_ref`, if it
+    /// is part of the chain. The state of a public account is returned along its hash, for the
+    /// block producer to check the account deltas of the transactions against it.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret, err)]
+    pub async fn get_transaction_inputs(
+        &self,
+        account_id: AccountId,
+        nullifiers: &[RpoDigest],
+        block_ref: Option<RpoDigest>,
+    ) -> Result<
+        (AccountState, Vec<NullifierStateForTransactionInput>, Option<BlockNumber>),
+        DatabaseError,
+    > {
+        info!(target: COMPONENT, account_id = %format_account_id(account_id), nullifiers = %format_array(nullifiers));
+
+        // the DB is read before the in-memory structures, the state of the account is dropped if a
+        // block updating it was applied in between
+        let details = self.db.select_account_details(account_id).await?;
+
+        let inner = self.inner.read().await;
+
+        let account_hash: Word =
+            inner.account_tree.open(&LeafIndex::new_max_depth(account_id)).value.into();
+        let details = details.and_then(|(details_hash, details)| {
+            details.filter(|_| details_hash == Digest::from(account_hash))
+        });
+        let account = AccountState {
+            account_id,
+            account_hash,
+            details,
+        };
+
+        let nullifier_blocks = nullifiers
+            .iter()
+            .cloned()
+            .map(|nullifier| {
+                let value = inner.nullifier_tree.get_value(nullifier);
+                let block_num = nullifier_value_to_blocknum(value);
+
+                NullifierStateForTransactionInput {
+                    nullifier,
+                    block_num,
+                }
+            })
+            .collect();
+
+        let block_ref_num =
+            block_ref.and_then(|block_ref| inner.block_nums.get(&block_ref).copied());
+
+        Ok((account, nullifier_blocks, block_ref_num))
+    }
+
+    /// Returns the oldest block whose notes are retained.
+    ///
+    /// Transactions consuming notes of older blocks can't be verified, because the notes can no
+    /// longer be authenticated. The store doesn't prune any data yet, so the whole chain is
+    /// retained.
+    pub fn pruning_horizon(&self) -> BlockNumber {
+        GENESIS_BLOCK_NUM
+    }
+
+    /// Derives the inclusion path of the note at `note_index` in the block `block_num` from the
+    /// block's note tree.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn get_note_authentication_path(
+        &self,
+        block_num: BlockNumber,
+        note_index: u32,
+    ) -> Result<MerklePath, GetNoteAuthenticationPathError> {
+        let note_tree = self.get_note_tree(block_num).await?;
+
+        note_path(&note_tree, block_num, note_index)
+    }
+
+    /// Returns the notes of `note_hashes` created by the block waiting to be committed, see
+    /// [State::propose_block], along with their inclusion paths in the block. All the notes of the
+    /// block are returned if `note_hashes` is empty.
+    ///
+    /// The inclusion data is provisional: the proposed block may be aborted or replaced, the
+    /// paths only hold once the block of the returned header is committed. `None` is returned if
+    /// no block is waiting to be committed.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn get_proposed_notes(
+        &self,
+        note_hashes: &[RpoDigest],
+    ) -> Result<Option<ProposedNotes>, GetNoteAuthenticationPathError> {
+        let proposal = self.proposal.lock().await;
+        let Some(pending) = proposal
+            .as_ref()
+            .filter(|pending| pending.proposed_at.elapsed() < self.proposal_timeout)
+        else {
+            return Ok(None);
+        };
+
+        let update = &pending.block.update;
+        let note_hashes: BTreeSet<Digest> = note_hashes.iter().map(Digest::from).collect();
+        let mut notes: Vec<Note> = update
+            .notes
+            .iter()
+            .filter(|note| {
+                note_hashes.is_empty()
+                    || note.note_hash.as_ref().is_some_and(|hash| note_hashes.contains(hash))
+            })
+            .cloned()
+            .collect();
+        add_note_paths(&mut notes, &update.note_tree)?;
+
+        Ok(Some(ProposedNotes {
+            block_header: update.block_header.clone(),
+            notes,
+        }))
+    }
+
+    /// Retains the details of a `note` created by the chain along with its inclusion path, so the
+    /// note can still be consumed once its path is no longer derivable from the note trees.
+    ///
+    /// A note which already has a record keeps it, the record of a note is fully determined by
+    /// the note. The details of a private note are refused, see [NoteVisibilityConfig].
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn submit_note_recovery_record(
+        &self,
+        note: &ObjectNote,
+    ) -> Result<NoteRecoveryRecord, NoteRecoveryError> {
+        let note_hash = note.id().inner();
+        let created_note = self
+            .db
+            .select_note_by_hash(note_hash)
+            .await?
+            .ok_or(NoteRecoveryError::NoteNotFound(note_hash))?;
+
+        // the note id doesn't commit to the metadata, which is authenticated by the note tree
+        if u64::from(note.metadata().sender()) != created_note.sender
+            || note.metadata().tag().as_int() != created_note.tag
+        {
+            return Err(NoteRecoveryError::MetadataMismatch(note_hash));
+        }
+        if created_note.private || self.block_rules.note_visibility.is_private(created_note.tag) {
+            return Err(NoteRecoveryError::PrivateNote(note_hash));
+        }
+
+        let merkle_path = self
+            .get_note_authentication_path(created_note.block_num, created_note.note_index)
+            .await?;
+        let details = note.to_bytes();
+
+        self.db
+            .insert_note_recovery_record(created_note.clone(), details.clone(), merkle_path.clone())
+            .await?;
+
+        Ok(NoteRecoveryRecord {
+            block_num: created_note.block_num,
+            note_index: created_note.note_index,
+            note_hash: created_note.note_hash,
+            details,
+            merkle_path: Some(merkle_path.into()),
+        })
+    }
+
+    /// Returns the recovery records of the notes `note_hashes`, the notes without a record are
+    /// omitted.
+    pub async fn get_note_recovery_records(
+        &self,
+        note_hashes: Vec<RpoDigest>,
+    ) -> Result<Vec<NoteRecoveryRecord>, DatabaseError> {
+        self.db.select_note_recovery_records(note_hashes).await
+    }
+
+    /// Loads the note tree of the block `block_num`.
+    ///
+    /// The trees of the blocks applied before the note trees were stored are rebuilt from the note
+    /// leaves stored in the database, and checked against the `note_root` of the block header.
+    async fn get_note_tree(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<BlockNoteTree, GetNoteAuthenticationPathError> {
+        if let Some(note_tree) = self.db.select_note_tree(block_num).await? {
+            return Ok(note_tree);
+        }
+
+        self.rebuild_note_tree(block_num).await
+    }
+
+    /// Rebuilds the note tree of the block `block_num` from the note leaves stored in the
+    /// database, and checks it against the `note_root` of the block header.
+    async fn rebuild_note_tree(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<BlockNoteTree, GetNoteAuthenticationPathError> {
+        let block_header: BlockHeader = self
+            .db
+            .select_block_header_by_block_num(Some(block_num))
+            .await?
+            .ok_or(GetNoteAuthenticationPathError::BlockNotFound(block_num))?
+            .try_into()?;
+
+        let notes = self.db.select_notes_by_block_num(block_num).await?;
+        let note_tree = build_notes_tree(&notes).map_err(|err| {
+            GetNoteAuthenticationPathError::FailedToRebuildNotesTree(err.to_string())
+        })?;
+        if note_tree.root() != block_header.note_root() {
+            return Err(GetNoteAuthenticationPathError::NoteRootMismatch(block_num));
+        }
+
+        Ok(BlockNoteTree::from_tree(&note_tree))
+    }
+
+    /// Returns the number of rows and the bytes used by every table of the database.
+    pub async fn table_sizes(&self) -> Result<Vec<TableSize>, DatabaseError> {
+        self.db.select_table_sizes().await
+    }
+
+    /// Returns the progress of all the backfills, see [crate::backfill].
+    pub async fn backfills(&self) -> Result<Vec<BackfillProgress>, DatabaseError> {
+        self.db.select_backfills().await
+    }
+
+    /// Stores the note trees of up to `batch_size` blocks from `progress`, which were applied
+    /// before the note trees were stored, and returns the progress of the backfill after the
+    /// batch.
+    ///
+    /// Blocks whose note tree can't be rebuilt are skipped, their notes can't be authenticated
+    /// either way.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn backfill_note_trees(
+        &self,
+        progress: &BackfillProgress,
+        batch_size: usize,
+    ) -> Result<BackfillProgress, DatabaseError> {
+        let block_nums = self
+            .db
+            .select_blocks_without_note_tree(progress.next_block_num, batch_size)
+            .await?;
+
+        let mut note_trees = Vec::with_capacity(block_nums.len());
+        for &block_num in &block_nums {
+            match self.rebuild_note_tree(block_num).await {
+                Ok(note_tree) => note_trees.push((block_num, note_tree)),
+                Err(GetNoteAuthenticationPathError::DatabaseError(err)) => return Err(err),
+                Err(err) => {
+                    warn!(target: COMPONENT, block_num, %err, "Skipping note tree backfill of block")
+                },
+            }
+        }
+
+        let next = BackfillProgress {
+            name: NOTE_TREES_BACKFILL.to_string(),
+            next_block_num: block_nums.last().map_or(progress.next_block_num, |last| last + 1),
+            completed: block_nums.len() < batch_size,
+        };
+        self.db.insert_backfilled_note_trees(note_trees, next.clone()).await?;
+
+        Ok(next)
+    }
+
+    /// Rewrites the protobuf encoded digests of up to `batch_size` blocks from `progress` in
+    /// miden's native serialization, and returns the progress of the backfill after the batch.
+    ///
+    /// The blocks applied since the migration store native digests, so the backfill completes
+    /// once it went past the chain tip.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn backfill_native_digests(
+        &self,
+        progress: &BackfillProgress,
+        batch_size: usize,
+    ) -> Result<BackfillProgress, DatabaseError> {
+        let chain_tip = self.chain_tip().await;
+        let batch_size = BlockNumber::try_from(batch_size).unwrap_or(BlockNumber::MAX);
+        let to_block = progress.next_block_num.saturating_add(batch_size);
+
+        let next = BackfillProgress {
+            name: NATIVE_DIGESTS_BACKFILL.to_string(),
+            next_block_num: to_block,
+            completed: to_block > chain_tip,
+        };
+        self.db
+            .rewrite_protobuf_digests(progress.next_block_num, to_block, next.clone())
+            .await?;
+
+        Ok(next)
+    }
+
+    /// Lists all known nullifiers with their inclusion blocks, intended for testing.
+    pub async fn list_nullifiers(&self) -> Result<Vec<(RpoDigest, u32)>, DatabaseError> {
+        self.db.select_nullifiers().await
+    }
+
+    /// Lists all known accounts, with their ids, latest state hash, and block at which the account was last
+    /// modified, intended for testing.
+    pub async fn list_accounts(&self) -> Result<Vec<AccountInfo>, DatabaseError> {
+        self.db.select_accounts().await
+    }
+
+    /// Lists all known notes, intended for testing.
+    pub async fn list_notes(&self) -> Result<Vec<Note>, GetNoteAuthenticationPathError> {
+        let mut notes = self.db.select_notes().await?;
+        self.add_block_note_paths(&mut notes).await?;
+
+        Ok(notes)
+    }
+
+    /// Returns the notes created from the block `from_block` to the block `to_block` which the 16
+    /// high bits of the tag match `tags`, with their merkle paths, and the headers of the blocks
+    /// which created them.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, err)]
+    pub async fn export_notes(
+        &self,
+        tags: Vec<u32>,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+    ) -> Result<(Vec<block_header::BlockHeader>, Vec<Note>), GetNoteAuthenticationPathError> {
+        let mut notes =
+            self.db.select_notes_by_tag_and_block_range(tags, from_block, to_block).await?;
+        let block_nums = self.add_block_note_paths(&mut notes).await?;
+
+        let mut block_headers = Vec::with_capacity(block_nums.len());
+        for block_num in block_nums {
+            let block_header = self
+                .db
+                .select_block_header_by_block_num(Some(block_num))
+                .await?
+                .ok_or(GetNoteAuthenticationPathError::BlockNotFound(block_num))?;
+            block_headers.push(block_header);
+        }
+
+        Ok((block_headers, notes))
+    }
+
+    /// Returns the notes created from the block `from_block` which the 16 high bits of the tag
+    /// match `tags`, and which weren't consumed as of the chain tip, without their merkle paths.
+    ///
+    /// Only the consumption of the notes linked to their nullifier, i.e. public notes, is known,
+    /// so the private notes are always listed. Whole blocks are listed, as many as
+    /// [MAX_UNCONSUMED_NOTES] notes allow, the following ones being listed from the returned
+    /// `next_block_num`.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn get_unconsumed_notes(
+        &self,
+        tags: Vec<u32>,
+        from_block: BlockNumber,
+    ) -> Result<UnconsumedNotes, DatabaseError> {
+        let chain_tip = self.chain_tip().await;
+        let (notes, next_block_num) = self
+            .db
+            .select_unconsumed_notes_by_tag(tags, from_block, chain_tip, MAX_UNCONSUMED_NOTES)
+            .await?;
+
+        Ok(UnconsumedNotes {
+            chain_tip,
+            notes,
+            next_block_num,
+        })
+    }
+
+    /// Returns the number of notes created over the latest `num_blocks` blocks with any tag, and
+    /// with every tag of `tags`, given as the 16 high bits of the tags.
+    ///
+    /// At most the latest [NOTE_TAG_STATS_WINDOW] blocks are covered, all of them if `num_blocks`
+    /// is zero.
+    #[allow(clippy::blocks_in_conditions)] // Workaround of `instrument` issue
+    #[instrument(target = "miden-store", skip_all, ret(level = "debug"), err)]
+    pub async fn get_note_tag_stats(
+        &self,
+        tags: Vec<u32>,
+        num_blocks: u32,
+    ) -> Result<NoteTagActivity, DatabaseError> {
+        let num_blocks = match num_blocks {
+            0 => NOTE_TAG_STATS_WINDOW,
+            num_blocks => num_blocks.min(NOTE_TAG_STATS_WINDOW),
+        };
+        let chain_tip = self.chain_tip().await;
+        let from_block = (chain_tip + 1).saturating_sub(num_blocks);
+
+        let (total_notes, stats) = self.db.select_note_tag_stats(tags.clone(), from_block).await?;
+        let stats: BTreeMap<u32, NoteTagStats> =
+            stats.into_iter().map(|stats| (stats.tag, stats)).collect();
+        let tags = tags
+            .into_iter()
+            .map(|tag| {
+                stats.get(&tag).cloned().unwrap_or(NoteTagStats {
+                    tag,
+                    num_notes: 0,
+                    num_blocks: 0,
+                })
+            })
+            .collect();
+
+        Ok(NoteTagActivity {
+            chain_tip,
+            from_block,
+            total_notes,
+            tags,
+        })
+    }
+
+    /// Adds their merkle paths to the `notes`, which are ordered by block, and returns the numbers
+    /// of the blocks which created them.
+    async fn add_block_note_paths(
+        &self,
+        notes: &mut [Note],
+    ) -> Result<Vec<BlockNumber>, GetNoteAuthenticationPathError> {
+        // the paths are added one block at a time
+        let mut block_nums = Vec::new();
+        let mut start = 0;
+        while start < notes.len() {
+            let block_num = notes[start].block_num;
+            let end = notes[start..]
+                .iter()
+                .position(|note| note.block_num != block_num)
+                .map_or(notes.len(), |len| start + len);
+
+            let note_tree = self.get_note_tree(block_num).await?;
+            add_note_paths(&mut notes[start..end], &note_tree)?;
+            block_nums.push(block_num);
+            start = end;
+        }
+
+        Ok(block_nums)
+    }
+}
+
+impl BlockRules {
+    /// Validates the `blocks`, a contiguous run following `prev_block`, against the in-memory
+    /// structures `trees`, and updates them with the blocks.
+    ///
+    /// Either every block is staged, or none of them.
+    fn stage_blocks_onto(
+        &self,
+        mut trees: StagedTrees,
+        mut prev_block: BlockHeader,
+        blocks: Vec<NewBlock>,
+    ) -> Result<(Vec<StagedBlock>, StagedTrees), ApplyBlockError> {
+        let mut staged = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let block = self.stage_block_onto(&mut trees, &prev_block, block)?;
+            prev_block = block.block;
+            staged.push(block);
+        }
+
+        Ok((staged, trees))
+    }
+
+    /// Validates `block` as the successor of `prev_block`, against the in-memory structures
+    /// `trees`, and updates them with it.
+    ///
+    /// The `trees` are left partially updated if the block is invalid, and must be discarded.
+    fn stage_block_onto(
+        &self,
+        trees: &mut StagedTrees,
+        prev_block: &BlockHeader,
+        block: NewBlock,
+    ) -> Result<StagedBlock, ApplyBlockError> {
+        let NewBlock {
+            block_header,
+            nullifiers,
+            accounts,
+            account_details,
+            notes,
+            consumed_notes,
+            tx_kernel_versions,
+            signature,
+            canonical_tx_ordering,
+            timestamp_attestation,
+            batch_proofs,
+            chain_id,
+        } = block;
+        let new_block: BlockHeader = block_header.clone().try_into()?;
+
+        // a misconfigured block producer mustn't extend the chain of another network
+        if chain_id != self.chain_id {
+            return Err(ApplyBlockError::ChainIdMismatch {
+                expected: self.chain_id,
+                block_chain_id: chain_id,
+            });
+        }
+
+        // ensures the right block header is being processed
+        if new_block.block_num() != prev_block.block_num() + 1 {
+            return Err(ApplyBlockError::NewBlockInvalidBlockNum);
+        }
+        if new_block.prev_hash() != prev_block.hash() {
+            return Err(ApplyBlockError::NewBlockInvalidPrevHash);
+        }
+        self.validate_block_timestamp(new_block.timestamp().as_int())?;
+
+        // a signed block must be signed by the key it claims, which key is trusted is up to the
+        // consumers of the chain
+        if let Some(signature) = &signature {
+            let is_valid = BlockSignature::try_from(signature)
+                .is_ok_and(|signature| signature.verify(self.chain_id, new_block.hash()));
+            if !is_valid {
+                return Err(ApplyBlockError::InvalidBlockSignature);
+            }
+        }
+
+        // likewise, which time authority is trusted is up to the consumers, the attested time is
+        // not compared to the block timestamp taken from the block producer's clock. The
+        // attestation of a block is only received once the block is applied, with the next one
+        if let Some(attestation) = &timestamp_attestation {
+            let is_valid = TimestampAttestation::try_from(attestation)
+                .is_ok_and(|attestation| attestation.verify(self.chain_id, new_block.prev_hash()));
+            if !is_valid {
+                return Err(ApplyBlockError::InvalidTimestampAttestation);
+            }
+        }
+
+        // notes are indexed by tag, only the tags of the policy are accepted
+        for note in notes.iter() {
+            self.tag_policy
+                .check(note.tag)
+                .map_err(|error| ApplyBlockError::InvalidNoteTag {
+                    note_index: note.note_index,
+                    error,
+                })?;
+        }
+
+        // the published state of a public account must be the state the block commits to
+        for (account_id, details) in account_details.iter() {
+            let is_valid = ObjectAccount::read_from_bytes(details).is_ok_and(|account| {
+                account.id().is_on_chain()
+                    && u64::from(account.id()) == *account_id
+                    && accounts
+                        .iter()
+                        .any(|(id, hash)| id == account_id && *hash == Digest::from(account.hash()))
+            });
+            if !is_valid {
+                return Err(ApplyBlockError::InvalidAccountDetails(*account_id));
+            }
+        }
+
+        // consumed notes can only be linked to the nullifiers produced by the block
+        if let Some((_, nullifier)) =
+            consumed_notes.iter().find(|(_, nullifier)| !nullifiers.contains(nullifier))
+        {
+            return Err(ApplyBlockError::ConsumedNoteNullifierNotInBlock(*nullifier));
+        }
+
+        // nullifiers can be produced only once, the insertions into the nullifier tree are
+        // computed at once and checked before any structure is updated
+        let nullifier_mutations =
+            NullifierMutations::compute(&trees.nullifier_tree, &nullifiers, new_block.block_num())
+                .map_err(ApplyBlockError::DuplicatedNullifiers)?;
+
+        // update the in-memory data structures and compute the new block header. Important, the
+        // structures are not yet committed
+
+        // update chain MMR, new_block.chain_root must be equal to the chain MMR root prior to the
+        // update
+        let peaks = trees.chain_mmr.peaks(trees.chain_mmr.forest()).map_err(|error| {
+            ApplyBlockError::FailedToGetMmrPeaksForForest {
+                forest: trees.chain_mmr.forest(),
+                error,
+            }
+        })?;
+        if peaks.hash_peaks() != new_block.chain_root() {
+            return Err(ApplyBlockError::NewBlockInvalidChainRoot);
+        }
+        trees.chain_mmr.add(new_block.hash());
+
+        // update nullifier tree
+        nullifier_mutations.apply(&mut trees.nullifier_tree);
+        // FIXME: Re-add when nullifiers start getting updated
+        // if trees.nullifier_tree.root() != new_block.nullifier_root() {
+        //     return Err(StateError::NewBlockInvalidNullifierRoot);
+        // }
+
+        // update account tree
+        for (account_id, account_hash) in accounts.iter() {
+            trees
+                .account_tree
+                .insert(LeafIndex::new_max_depth(*account_id), account_hash.try_into()?);
+        }
+        if trees.account_tree.root() != new_block.account_root() {
+            return Err(ApplyBlockError::NewBlockInvalidAccountRoot);
+        }
+
+        // build notes tree
+        let note_tree = build_notes_tree(&notes)?;
+        if note_tree.root() != new_block.note_root() {
+            return Err(ApplyBlockError::NewBlockInvalidNoteRoot);
+        }
+
+        // the proven batches must cover the contents of the block, the proofs themselves were
+        // verified against the header before staging
+        if self.verify_batch_proofs {
+            verify_batch_contents(&note_tree, accounts.len(), &batch_proofs)?;
+        }
+
+        // the merkle paths of the notes are derived from the note tree when requested
+        let note_tree = BlockNoteTree::from_tree(&note_tree);
+
+        let notes = notes
+            .iter()
+            .map(|note| Note {
+                block_num: new_block.block_num(),
+                note_hash: note.note_hash.clone(),
+                sender: note.sender,
+                note_index: note.note_index,
+                tag: note.tag,
+                merkle_path: None,
+                private: note.private || self.note_visibility.is_private(note.tag),
+            })
+            .collect::<Vec<_>>();
+
+        Ok(StagedBlock {
+            block: new_block,
+            update: BlockUpdate {
+                block_header,
+                notes,
+                note_tree,
+                nullifiers,
+                accounts,
+                account_details,
+                consumed_notes,
+                tx_kernel_versions,
+                signature,
+                canonical_tx_ordering,
+                timestamp_attestation,
+            },
+        })
+    }
+
+    /// Ensures the `timestamp` of a new block, in seconds since the UNIX epoch, is not before the
+    /// genesis block, nor further ahead of the store's clock than the configured skew.
+    fn validate_block_timestamp(
+        &self,
+        timestamp: u64,
+    ) -> Result<(), ApplyBlockError> {
+        if timestamp < self.genesis_timestamp {
+            return Err(ApplyBlockError::NewBlockTimestampBeforeGenesis {
+                timestamp,
+                genesis_timestamp: self.genesis_timestamp,
+            });
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("today is expected to be after 1970")
+            .as_secs();
+        if timestamp > now.saturating_add(self.max_block_timestamp_skew_s) {
+            return Err(ApplyBlockError::NewBlockTimestampInFuture {
+                timestamp,
+                now,
+                max_skew_s: self.max_block_timestamp_skew_s,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// UTILITIES
+// ================================================================================================
+
+/// Returns the nullifier's block number given its leaf value in the TSMT.
+fn block_to_nullifier_data(block: BlockNumber) -> Word {
+    [Felt::new(block as u64), Felt::ZERO, Felt::ZERO, Felt::ZERO]
+}
+
+/// The new leaves of the nullifier tree for the nullifiers produced by a block, computed once
+/// against the tree and applied to it in a single batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NullifierMutations {
+    /// The new leaves, in the order of their position in the tree
+    leaves: Vec<(RpoDigest, Word)>,
+}
+
+impl NullifierMutations {
+    /// Computes the leaves inserting the `nullifiers` produced by the block `block_num` into
+    /// `tree`, without updating it.
+    ///
+    /// Returns the nullifiers which are already in `tree`, or which are produced several times by
+    /// the block, if any: a nullifier can be produced only once.
+    pub fn compute(
+        tree: &TieredSmt,
+        nullifiers: &[RpoDigest],
+        block_num: BlockNumber,
+    ) -> Result<Self, Vec<RpoDigest>> {
+        let nullifier_data = block_to_nullifier_data(block_num);
+
+        // the tree derives the position of a leaf from the most significant element of its key
+        let mut leaves: Vec<(RpoDigest, Word)> =
+            nullifiers.iter().map(|&nullifier| (nullifier, nullifier_data)).collect();
+        leaves.sort_unstable_by_key(|(nullifier, _)| (nullifier[3].as_int(), *nullifier));
+
+        let mut duplicates: Vec<RpoDigest> = leaves
+            .windows(2)
+            .filter(|pair| pair[0].0 == pair[1].0)
+            .map(|pair| pair[0].0)
+            .chain(
+                leaves
+                    .iter()
+                    .filter(|(nullifier, _)| tree.get_value(*nullifier) != EMPTY_WORD)
+                    .map(|(nullifier, _)| *nullifier),
+            )
+            .collect();
+        if !duplicates.is_empty() {
+            duplicates.sort();
+            duplicates.dedup();
+            return Err(duplicates);
+        }
+
+        Ok(Self { leaves })
+    }
+
+    /// Returns the number of leaves inserted.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Applies the mutations to `tree`, which must be the tree they were computed against.
+    ///
+    /// The leaves are inserted in the order of their position, so consecutive insertions update
+    /// overlapping paths of the tree, which hashes each path once per leaf: [TieredSmt] has no
+    /// batched update.
+    pub fn apply(
+        self,
+        tree: &mut TieredSmt,
+    ) {
+        for (nullifier, value) in self.leaves {
+            tree.insert(nullifier, value);
+        }
+    }
+}
+
+/// Returns the inclusion path of the note at `note_index` in `note_tree`, the tree of the block
+/// `block_num`.
+fn note_path(
+    note_tree: &BlockNoteTree,
+    block_num: BlockNumber,
+    note_index: u32,
+) -> Result<MerklePath, GetNoteAuthenticationPathError> {
+    let note_not_found = GetNoteAuthenticationPathError::NoteNotFound {
+        block_num,
+        note_index,
+    };
+    let leaf_index = note_leaf_index(note_index);
+    if !note_tree.contains_leaf(leaf_index) {
+        return Err(note_not_found);
+    }
+
+    note_tree.open(leaf_index).ok_or(note_not_found)
+}
+
+/// Sets the merkle path of the `notes`, which all belong to the block of `note_tree`.
+fn add_note_paths(
+    notes: &mut [Note],
+    note_tree: &BlockNoteTree,
+) -> Result<(), GetNoteAuthenticationPathError> {
+    for note in notes {
+        let merkle_path = note_path(note_tree, note.block_num, note.note_index)?;
+        note.merkle_path = Some(merkle_path.into());
+    }
+
+    Ok(())
+}
+
+/// Creates a [SimpleSmt] tree from the `notes`, each one placed at the leaves of its index in the
+/// block, see [note_leaf_index].
+#[instrument(target = "miden-store", skip_all)]
+pub fn build_notes_tree(
+    notes: &[NoteCreated]
+) -> Result<SimpleSmt<NOTE_LEAF_DEPTH>, ApplyBlockError> {
+    // TODO: create SimpleSmt without this allocation
+    let mut entries: Vec<(u64, Word)> = Vec::with_capacity(notes.len() * 2);
+
+    for note in notes.iter() {
+        let note_hash = note.note_hash.clone().ok_or(
+            ConversionError::MissingFieldInProtobufRepresentation {
+                entity: "note",
+                field_name: "note_hash",
+            },
+        )?;
+        let account_id = note.sender.try_into().or(Err(ApplyBlockError::InvalidAccountId))?;
+        let note_metadata = NoteMetadata::new(account_id, note.tag.into());
+        let index = note_leaf_index(note.note_index);
+        entries.push((index, note_hash.try_into()?));
+        entries.push((index + 1, note_metadata.into()));
+    }
+
+    SimpleSmt::with_leaves(entries).map_err(ApplyBlockError::FailedToCreateNotesTree)
+}
+
+/// Builds the nullifier tree out of the `nullifiers` and the blocks which consumed them.
+#[instrument(target = "miden-store", skip_all)]
+async fn build_nullifier_tree(
+    mut nullifiers: Vec<(RpoDigest, BlockNumber)>,
+    progress: &StartupProgress,
+) -> TieredSmt {
+    progress.set_stage(StartupStage::NullifierTree);
+
+    // inserted in the order of their leaves, see [NullifierMutations]
+    nullifiers.sort_unstable_by_key(|(nullifier, _)| nullifier[3].as_int());
+
+    let mut nullifier_tree = TieredSmt::default();
+    for chunk in nullifiers.chunks(STARTUP_CHUNK_SIZE) {
+        for &(nullifier, block_num) in chunk {
+            nullifier_tree.insert(nullifier, block_to_nullifier_data(block_num));
+        }
+        progress.rows_loaded(chunk.len());
+        task::yield_now().await;
+    }
+
+    nullifier_tree
+}
+
+/// Builds the account tree out of the latest hash of every account.
+#[instrument(target = "miden-store", skip_all)]
+async fn build_account_tree(
+    account_hashes: Vec<(AccountId, Word)>,
+    progress: &StartupProgress,
+) -> SimpleSmt<ACCOUNT_TREE_DEPTH> {
+    progress.set_stage(StartupStage::AccountTree);
+
+    let mut account_tree = SimpleSmt::new().expect("the depth of the account tree is valid");
+    for chunk in account_hashes.chunks(STARTUP_CHUNK_SIZE) {
+        for &(account_id, account_hash) in chunk {
+            account_tree.insert(LeafIndex::new_max_depth(account_id), account_hash);
+        }
+        progress.rows_loaded(chunk.len());
+        task::yield_now().await;
+    }
+
+    account_tree
+}
+
+/// Builds the chain MMR out of the `block_headers`, ordered by block number, along with the number
+/// of every block by hash.
+#[instrument(target = "miden-store", skip_all)]
+async fn build_chain_mmr(
+    block_headers: Vec<BlockHeader>,
+    progress: &StartupProgress,
+) -> (Mmr, BTreeMap<RpoDigest, BlockNumber>) {
+    progress.set_stage(StartupStage::ChainMmr);
+
+    let mut chain_mmr = Mmr::new();
+    let mut block_nums = BTreeMap::new();
+    for chunk in block_headers.chunks(STARTUP_CHUNK_SIZE) {
+        for block_header in chunk {
+            let block_hash = block_header.hash();
+            chain_mmr.add(block_hash);
+            block_nums.insert(block_hash, block_header.block_num());
+        }
+        progress.rows_loaded(chunk.len());
+        task::yield_now().await;
+    }
+
+    (chain_mmr, block_nums)
+}
+
+/// Reads the nullifiers and the blocks which consumed them, in chunks of [STARTUP_CHUNK_SIZE].
+#[instrument(target = "miden-store", skip_all)]
+async fn load_nullifiers(
+    db: &Db,
+    progress: &StartupProgress,
+) -> Result<Vec<(RpoDigest, BlockNumber)>, StateInitializationError> {
+    let mut nullifiers = Vec::new();
+    loop {
+        let after = nullifiers.last().map(|&(nullifier, _)| nullifier);
+        let chunk = db.select_nullifiers_after(after, STARTUP_CHUNK_SIZE).await?;
+        let num_rows = chunk.len();
+        progress.rows_read(num_rows);
+        nullifiers.extend(chunk);
+        if num_rows < STARTUP_CHUNK_SIZE {
+            return Ok(nullifiers);
+        }
+    }
+}
+
+/// Reads the block headers, ordered by block number, in chunks of [STARTUP_CHUNK_SIZE].
+#[instrument(target = "miden-store", skip_all)]
+async fn load_block_headers(
+    db: &Db,
+    progress: &StartupProgress,
+) -> Result<Vec<BlockHeader>, StateInitializationError> {
+    let mut block_headers: Vec<BlockHeader> = Vec::new();
+    loop {
+        let after = block_headers.last().map(BlockHeader::block_num);
+        let chunk = db.select_block_headers_after(after, STARTUP_CHUNK_SIZE).await?;
+        let num_rows = chunk.len();
+        progress.rows_read(num_rows);
+        for block_header in chunk {
+            block_headers.push(
+                block_header
+                    .try_into()
+                    .map_err(StateInitializationError::FailedToCreateChainMmr)?,
+            );
+        }
+        if num_rows < STARTUP_CHUNK_SIZE {
+            return Ok(block_headers);
+        }
+    }
+}
+
+#[instrument(target = "miden-store", skip_all)]
+async fn load_genesis_header(db: &Db) -> Result<BlockHeader, StateInitializationError> {
+    let genesis_header = db
+        .select_block_header_by_block_num(Some(GENESIS_BLOCK_NUM))
+        .await?
+        .ok_or(StateInitializationError::GenesisBlockHeaderMissing)?
+        .try_into()
+        .map_err(ConversionError::ParseError)?;
+
+    Ok(genesis_header)
+}
+
+/// Reads the latest hash of every account, in chunks of [STARTUP_CHUNK_SIZE].
+#[instrument(target = "miden-store", skip_all)]
+async fn load_account_hashes(
+    db: &Db,
+    progress: &StartupProgress,
+) -> Result<Vec<(AccountId, Word)>, StateInitializationError> {
+    let mut account_hashes = Vec::new();
+    loop {
+        let after = account_hashes.last().map(|&(account_id, _)| account_id);
+        let chunk = db.select_account_hashes_after(after, STARTUP_CHUNK_SIZE).await?;
+        let num_rows = chunk.len();
+        progress.rows_read(num_rows);
+        for (account_id, account_hash) in chunk {
+            account_hashes
+                .push((account_id, account_hash.try_into().map_err(ConversionError::from)?));
+        }
+        if num_rows < STARTUP_CHUNK_SIZE {
+            return Ok(account_hashes);
+        }
+    }
+}
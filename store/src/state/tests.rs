@@ -0,0 +1,1395 @@
+//! Concurrency of the writer of the state with its readers.
+//!
+//! Blocks are applied while readers query the state the way the RPC does. Every read must observe
+//! whole blocks, and the in-memory structures must never be out of sync with the DB, see
+//! [State::apply_block]. This also holds when the writer fails or waits for a connection.
+//!
+//! The interleavings of the writer with the readers are explored by pausing the writer at each of
+//! its steps, see [crate::db::faults].
+use std::{fs, future::Future};
+
+use figment::Jail;
+use miden_crypto::{dsa::rpo_falcon512::KeyPair, merkle::EmptySubtreeRoots, rand::RpoRandomCoin};
+use miden_lib::notes::create_p2id_note;
+use miden_node_utils::{
+    config::{Endpoint, GrpcServerConfig},
+    tag_policy::TagRange,
+};
+use miden_objects::accounts::AccountId as ObjectAccountId;
+use miden_vm::{prove, AdviceInputs, DefaultHost, MemAdviceProvider, ProvingOptions};
+use tokio::time::timeout;
+
+use super::*;
+use crate::{
+    batch_kernel,
+    config::{DiskLimitsConfig, NoteVisibilityConfig, StartupConfig, StoreConfig},
+    db::faults::{WriteFaults, WritePause, WriteStep},
+    errors::DiskLimitError,
+    genesis::GenesisState,
+    note_tree::{BATCH_TREE_DEPTH, MAX_NOTES_PER_BATCH},
+};
+
+// HELPERS
+// ================================================================================================
+
+const CHAIN_ID: u32 = 1;
+
+/// The account updated by every block.
+const ACCOUNT_ID: AccountId = 0x8000_0000_0000_0001;
+
+const NULLIFIERS_PER_BLOCK: usize = 3;
+
+/// Time after which a read which didn't complete is considered blocked.
+const BLOCKED_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Loads a state from a new data directory, and runs `test` against it on a multi-threaded
+/// runtime, so the writer and the readers run in parallel.
+fn with_state<F, Fut>(test: F)
+where
+    F: FnOnce(Arc<State>) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = Arc::new(load_state().await);
+            test(state).await
+        });
+
+        Ok(())
+    });
+}
+
+async fn load_state() -> State {
+    load_state_with_config(store_config()).await
+}
+
+fn store_config() -> StoreConfig {
+    StoreConfig {
+        endpoint: Endpoint {
+            host: "127.0.0.1".to_string(),
+            port: 28943,
+        },
+        listen: Vec::new(),
+        data_directory: "data".into(),
+        chain_id: CHAIN_ID,
+        query_timeout_ms: 5000,
+        max_block_timestamp_skew_s: 60,
+        proposal_timeout_ms: 30000,
+        operator_keys: Vec::new(),
+        tag_policy: TagPolicy::default(),
+        safe_depth: 0,
+        tenants: Vec::new(),
+        account_history: Default::default(),
+        state_commitment_interval: 0,
+        disk_limits: DiskLimitsConfig::default(),
+        note_visibility: NoteVisibilityConfig::default(),
+        verify_batch_proofs: false,
+        grpc: GrpcServerConfig::default(),
+        startup: StartupConfig::default(),
+    }
+}
+
+/// Loads a state from the data directory of `config`, created along with its genesis file if
+/// missing.
+async fn load_state_with_config(config: StoreConfig) -> State {
+    // the blocks are timestamped a second apart from the genesis block, and stay in the past
+    let data_directory = config.data_directory();
+    if !data_directory.genesis_filepath().exists() {
+        let genesis_timestamp =
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() - 3600;
+        let genesis = GenesisState::new(Vec::new(), 1, genesis_timestamp, CHAIN_ID);
+        fs::create_dir_all(data_directory.root()).unwrap();
+        fs::write(data_directory.genesis_filepath(), genesis.to_bytes()).unwrap();
+    }
+
+    let note_visibility = config.note_visibility.clone();
+    let verify_batch_proofs = config.verify_batch_proofs;
+    let proposal_timeout = Duration::from_millis(config.proposal_timeout_ms);
+    let db = Db::setup(config).await.unwrap();
+    State::load(
+        db,
+        60,
+        proposal_timeout,
+        TagPolicy::default(),
+        note_visibility,
+        verify_batch_proofs,
+    )
+    .await
+    .unwrap()
+}
+
+fn account_hash(block_num: BlockNumber) -> RpoDigest {
+    RpoDigest::new([Felt::from(block_num), Felt::ZERO, Felt::ZERO, Felt::ONE])
+}
+
+fn block_nullifiers(block_num: BlockNumber) -> Vec<RpoDigest> {
+    (0..NULLIFIERS_PER_BLOCK)
+        .map(|index| {
+            RpoDigest::new([Felt::new(index as u64), Felt::ZERO, Felt::ZERO, Felt::from(block_num)])
+        })
+        .collect()
+}
+
+/// Builds the blocks following the chain tip of a state, every block updates [ACCOUNT_ID] and
+/// produces [NULLIFIERS_PER_BLOCK] nullifiers.
+struct TestChain {
+    prev_block: BlockHeader,
+    chain_mmr: Mmr,
+    account_tree: SimpleSmt<ACCOUNT_TREE_DEPTH>,
+}
+
+impl TestChain {
+    async fn new(state: &State) -> Self {
+        let prev_block = state.latest_block_header().await.unwrap();
+        let inner = state.inner.read().await;
+
+        Self {
+            prev_block,
+            chain_mmr: inner.chain_mmr.clone(),
+            account_tree: inner.account_tree.clone(),
+        }
+    }
+
+    /// Returns the next block of the chain, the chain moves on as if the block was applied.
+    fn next_block(&mut self) -> NewBlock {
+        self.next_block_with_notes(Vec::new())
+    }
+
+    /// Returns the next block of the chain creating `notes`, see [Self::next_block].
+    fn next_block_with_notes(
+        &mut self,
+        notes: Vec<NoteCreated>,
+    ) -> NewBlock {
+        self.next_block_with(notes, Vec::new())
+    }
+
+    /// Returns the next block of the chain creating `notes`, whose header commits to
+    /// `batch_proofs`, see [Self::next_block].
+    fn next_block_with(
+        &mut self,
+        notes: Vec<NoteCreated>,
+        batch_proofs: Vec<BatchProof>,
+    ) -> NewBlock {
+        let timestamp = self.prev_block.timestamp().as_int() + 1;
+        self.build_block(notes, batch_proofs, timestamp)
+    }
+
+    /// Returns the next block of the chain timestamped `timestamp`, in seconds since the UNIX
+    /// epoch, see [Self::next_block].
+    fn next_block_at(
+        &mut self,
+        timestamp: u64,
+    ) -> NewBlock {
+        self.build_block(Vec::new(), Vec::new(), timestamp)
+    }
+
+    fn build_block(
+        &mut self,
+        notes: Vec<NoteCreated>,
+        batch_proofs: Vec<BatchProof>,
+        timestamp: u64,
+    ) -> NewBlock {
+        let block_num = self.prev_block.block_num() + 1;
+        let chain_root = self.chain_mmr.peaks(self.chain_mmr.forest()).unwrap().hash_peaks();
+        self.account_tree
+            .insert(LeafIndex::new_max_depth(ACCOUNT_ID), account_hash(block_num).into());
+
+        let header = BlockHeader::new(
+            self.prev_block.hash(),
+            block_num,
+            chain_root,
+            self.account_tree.root(),
+            RpoDigest::default(),
+            build_notes_tree(&notes).unwrap().root(),
+            batch_kernel::batch_root(
+                batch_proofs.iter().map(|batch| (batch.commitment, batch.proof_hash())),
+            ),
+            batch_kernel::proof_hash(batch_proofs.iter().map(BatchProof::proof_hash)),
+            Felt::ONE,
+            timestamp.into(),
+        );
+        self.chain_mmr.add(header.hash());
+        self.prev_block = header;
+
+        NewBlock {
+            block_header: header.into(),
+            nullifiers: block_nullifiers(block_num),
+            accounts: vec![(ACCOUNT_ID, account_hash(block_num).into())],
+            account_details: Vec::new(),
+            notes,
+            consumed_notes: Vec::new(),
+            tx_kernel_versions: Vec::new(),
+            signature: None,
+            canonical_tx_ordering: false,
+            timestamp_attestation: None,
+            batch_proofs,
+            chain_id: CHAIN_ID,
+        }
+    }
+}
+
+/// Returns the proof of the first batch of a block, made of `num_txs` transactions creating no
+/// note, proven with the batch kernel as by the block producer.
+fn prove_batch(num_txs: usize) -> BatchProof {
+    let created_notes_root = *EmptySubtreeRoots::entry(NOTE_LEAF_DEPTH, BATCH_TREE_DEPTH);
+    let advice_stack: Vec<Felt> = (0..num_txs)
+        .flat_map(|tx| [Felt::new(tx as u64), Felt::ZERO, Felt::ZERO, Felt::ONE])
+        .collect();
+    let host =
+        DefaultHost::new(MemAdviceProvider::from(AdviceInputs::default().with_stack(advice_stack)));
+
+    let (stack_outputs, proof) = prove(
+        &batch_kernel::batch_kernel(),
+        batch_kernel::kernel_stack_inputs(num_txs, created_notes_root),
+        host,
+        ProvingOptions::default(),
+    )
+    .unwrap();
+
+    BatchProof {
+        commitment: stack_outputs.get_stack_word(0).unwrap().into(),
+        num_txs,
+        created_notes_root,
+        proof: proof.to_bytes(),
+        batch_index: 0,
+    }
+}
+
+/// Returns a note sent by [ACCOUNT_ID] to the account `target`, whose tag is derived from
+/// `target`.
+fn p2id_note(target: u64) -> ObjectNote {
+    let sender = ObjectAccountId::new_unchecked(Felt::new(ACCOUNT_ID));
+    let target = ObjectAccountId::new_unchecked(Felt::new(target));
+    let rng = RpoRandomCoin::new([Felt::new(target.into()); 4]);
+
+    create_p2id_note(sender, target, Vec::new(), rng).unwrap()
+}
+
+/// Returns the leaf of `note` at `note_index` of the note tree of its block.
+fn note_created(
+    note_index: u32,
+    note: &ObjectNote,
+) -> NoteCreated {
+    NoteCreated {
+        note_index,
+        note_hash: Some(note.id().inner().into()),
+        sender: note.metadata().sender().into(),
+        tag: note.metadata().tag().as_int(),
+        private: false,
+    }
+}
+
+/// Queries the state the way the RPC does, asserting every read observed whole blocks of a chain
+/// of `num_blocks` blocks, and returns the chain tip observed by the first read.
+///
+/// The reads are not atomic with each other, the chain may grow in between.
+async fn read_chain_tip(
+    state: &State,
+    num_blocks: BlockNumber,
+) -> BlockNumber {
+    // the chain MMR authenticates the latest header of the DB
+    let (header, proof) = state.get_block_header(None, true).await.unwrap();
+    let header: BlockHeader = header.unwrap().try_into().unwrap();
+    let proof = proof.unwrap();
+    assert_eq!(proof.chain_length, header.block_num(), "chain MMR out of sync with the DB");
+    assert_eq!(proof.chain_tip_hash, header.hash());
+    let chain_tip = header.block_num();
+
+    // the delta of the chain MMR reaches the block returned from the DB, along with the latest
+    // update of the account
+    let (update, delta, _) = state
+        .sync_state(GENESIS_BLOCK_NUM, &[ACCOUNT_ID], &[], &[], &[])
+        .await
+        .expect("chain MMR out of sync with the DB");
+    let sync_tip = update.block_header.block_num;
+    assert!(sync_tip >= chain_tip, "chain tip went back from {chain_tip} to {sync_tip}");
+    assert_eq!(delta.forest, sync_tip as usize);
+    if sync_tip != GENESIS_BLOCK_NUM {
+        assert!(
+            update
+                .account_updates
+                .iter()
+                .any(|account_update| account_update.block_num == sync_tip
+                    && account_update.account_hash == Some(account_hash(sync_tip).into())),
+            "account update of block {sync_tip} missing: {:?}",
+            update.account_updates
+        );
+    }
+
+    // the nullifiers of the applied blocks, and only them, are consumed
+    let nullifiers: Vec<_> = (1..=num_blocks).flat_map(block_nullifiers).collect();
+    let (_, block_nums) = state.check_nullifiers(&[], &nullifiers).await;
+    let mut nullifier_tip = GENESIS_BLOCK_NUM;
+    for (block_num, consumed_at) in (1..=num_blocks).zip(block_nums.chunks(NULLIFIERS_PER_BLOCK)) {
+        if consumed_at.iter().all(|&consumed_at| consumed_at == block_num) {
+            assert_eq!(nullifier_tip + 1, block_num, "block {block_num} applied out of order");
+            nullifier_tip = block_num;
+        } else {
+            assert!(
+                consumed_at.iter().all(|&consumed_at| consumed_at == 0),
+                "block {block_num} partially applied to the nullifier tree: {consumed_at:?}"
+            );
+        }
+    }
+    assert!(
+        nullifier_tip >= sync_tip,
+        "chain tip went back from {sync_tip} to {nullifier_tip}"
+    );
+
+    chain_tip
+}
+
+// TESTS
+// ================================================================================================
+
+/// Tests that the readers concurrent to the writer observe whole blocks, and never see the chain
+/// tip go back
+#[test]
+fn test_state_concurrent_apply_block_and_reads() {
+    const NUM_BLOCKS: BlockNumber = 20;
+    const NUM_READERS: usize = 4;
+
+    with_state(|state| async move {
+        let mut chain = TestChain::new(&state).await;
+        let blocks: Vec<_> = (0..NUM_BLOCKS).map(|_| chain.next_block()).collect();
+
+        let writer = tokio::spawn({
+            let state = state.clone();
+            async move {
+                for block in blocks {
+                    state.apply_block(block).await.unwrap();
+                }
+            }
+        });
+        let readers: Vec<_> = (0..NUM_READERS)
+            .map(|_| {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    let mut chain_tip = GENESIS_BLOCK_NUM;
+                    while chain_tip < NUM_BLOCKS {
+                        let observed = read_chain_tip(&state, NUM_BLOCKS).await;
+                        assert!(observed >= chain_tip, "chain tip went back to {observed}");
+                        chain_tip = observed;
+                    }
+                })
+            })
+            .collect();
+
+        writer.await.unwrap();
+        for reader in readers {
+            reader.await.unwrap();
+        }
+        assert_eq!(read_chain_tip(&state, NUM_BLOCKS).await, NUM_BLOCKS);
+    });
+}
+
+/// Tests that while a block is written to the DB, the readers observe the previous block, and the
+/// other writers are rejected
+#[test]
+fn test_state_reads_during_block_write() {
+    with_state(|state| async move {
+        let block = TestChain::new(&state).await.next_block();
+
+        let (pause, mut handle) = WritePause::new();
+        state.db.inject_faults(WriteFaults {
+            pause: Some((WriteStep::BlocksWritten, pause)),
+            ..Default::default()
+        });
+        let writer = tokio::spawn({
+            let state = state.clone();
+            let block = block.clone();
+            async move { state.apply_block(block).await }
+        });
+        handle.reached().await;
+
+        // the transaction is not committed, the readers observe the genesis block
+        assert_eq!(read_chain_tip(&state, 1).await, GENESIS_BLOCK_NUM);
+
+        // the writer lock is held, the other writers can't race with the writer
+        assert!(matches!(
+            state.apply_block(block.clone()).await,
+            Err(ApplyBlockError::ConcurrentWrite)
+        ));
+        assert!(matches!(
+            state.propose_block(block).await,
+            Err(ApplyBlockError::ConcurrentWrite)
+        ));
+
+        handle.resume();
+        writer.await.unwrap().unwrap();
+        assert_eq!(read_chain_tip(&state, 1).await, 1);
+    });
+}
+
+/// Tests that once the in-memory structures are locked, the readers wait for the transaction to be
+/// committed
+#[test]
+fn test_state_reads_wait_for_commit() {
+    with_state(|state| async move {
+        let block = TestChain::new(&state).await.next_block();
+
+        let (pause, mut handle) = WritePause::new();
+        state.db.inject_faults(WriteFaults {
+            pause: Some((WriteStep::LockAcquired, pause)),
+            ..Default::default()
+        });
+        let writer = tokio::spawn({
+            let state = state.clone();
+            async move { state.apply_block(block).await }
+        });
+        handle.reached().await;
+
+        let reader = tokio::spawn({
+            let state = state.clone();
+            async move { read_chain_tip(&state, 1).await }
+        });
+        assert!(
+            timeout(BLOCKED_READ_TIMEOUT, state.check_nullifiers(&[], &block_nullifiers(1)))
+                .await
+                .is_err(),
+            "the in-memory structures must be locked until the transaction is committed"
+        );
+
+        handle.resume();
+        writer.await.unwrap().unwrap();
+        assert_eq!(reader.await.unwrap(), 1);
+    });
+}
+
+/// Tests that a writer failing at any step leaves the state unchanged, and the block can be applied
+/// again
+#[test]
+fn test_state_writer_failures() {
+    with_state(|state| async move {
+        let block = TestChain::new(&state).await.next_block();
+
+        let faults = [
+            WriteFaults {
+                panic_at: Some(WriteStep::BlocksWritten),
+                ..Default::default()
+            },
+            WriteFaults {
+                fail_at: Some(WriteStep::BlocksWritten),
+                ..Default::default()
+            },
+            WriteFaults {
+                panic_at: Some(WriteStep::LockAcquired),
+                ..Default::default()
+            },
+            WriteFaults {
+                fail_at: Some(WriteStep::LockAcquired),
+                ..Default::default()
+            },
+        ];
+        for faults in faults {
+            let description = format!("{faults:?}");
+            state.db.inject_faults(faults);
+
+            assert!(state.apply_block(block.clone()).await.is_err(), "{description}");
+            assert_eq!(read_chain_tip(&state, 1).await, GENESIS_BLOCK_NUM, "{description}");
+            assert_eq!(state.latest_chain_tip().block_num, GENESIS_BLOCK_NUM, "{description}");
+        }
+
+        state.apply_block(block).await.unwrap();
+        assert_eq!(read_chain_tip(&state, 1).await, 1);
+    });
+}
+
+/// Tests that a writer waiting for a connection keeps the other writers out, without blocking the
+/// reads of the in-memory structures
+#[test]
+fn test_state_pool_exhaustion() {
+    with_state(|state| async move {
+        let block = TestChain::new(&state).await.next_block();
+
+        let connections = state.db.exhaust_pool().await;
+        let writer = tokio::spawn({
+            let state = state.clone();
+            let block = block.clone();
+            async move { state.apply_block(block).await }
+        });
+
+        // the DB reads wait for a connection, like the writer
+        assert!(timeout(BLOCKED_READ_TIMEOUT, state.get_block_header(None, false))
+            .await
+            .is_err());
+        assert!(matches!(state.apply_block(block).await, Err(ApplyBlockError::ConcurrentWrite)));
+
+        // the in-memory structures don't need a connection, and are unchanged
+        let (_, block_nums) = state.check_nullifiers(&[], &block_nullifiers(1)).await;
+        assert!(block_nums.iter().all(|&block_num| block_num == 0));
+
+        drop(connections);
+        writer.await.unwrap().unwrap();
+        assert_eq!(read_chain_tip(&state, 1).await, 1);
+    });
+}
+
+/// Tests that the notes of a proposed block are served with their paths in the block until the
+/// block is committed, and that the proposal doesn't change what the readers observe
+#[test]
+fn test_state_proposed_notes() {
+    with_state(|state| async move {
+        assert!(state.get_proposed_notes(&[]).await.unwrap().is_none());
+
+        let notes = [p2id_note(0x9000_0000_0000_0001), p2id_note(0x9000_0000_0000_0002)];
+        let block = TestChain::new(&state)
+            .await
+            .next_block_with_notes(vec![note_created(0, &notes[0]), note_created(1, &notes[1])]);
+        let block_header = block.block_header.clone();
+        let block_hash = state.propose_block(block).await.unwrap();
+
+        let proposed = state.get_proposed_notes(&[]).await.unwrap().unwrap();
+        assert_eq!(proposed.block_header, block_header);
+        let note_hashes: Vec<_> =
+            proposed.notes.iter().map(|note| note.note_hash.clone()).collect();
+        assert_eq!(
+            note_hashes,
+            notes.iter().map(|note| Some(note.id().inner().into())).collect::<Vec<_>>()
+        );
+        assert_eq!(read_chain_tip(&state, 1).await, GENESIS_BLOCK_NUM);
+
+        // the paths of the proposed notes are the ones served once the block is committed
+        let proposed = state.get_proposed_notes(&[notes[1].id().inner()]).await.unwrap().unwrap();
+        assert_eq!(proposed.notes.len(), 1);
+        let proposed_path =
+            MerklePath::try_from(proposed.notes[0].merkle_path.clone().unwrap()).unwrap();
+
+        state.commit_block(block_hash).await.unwrap();
+        assert!(state.get_proposed_notes(&[]).await.unwrap().is_none());
+        assert_eq!(read_chain_tip(&state, 1).await, 1);
+
+        let committed_path = state.get_note_authentication_path(1, 1).await.unwrap();
+        assert_eq!(proposed_path, committed_path);
+    });
+}
+
+/// Tests that the notes of a proposed block are no longer served once the proposal expired
+#[test]
+fn test_state_proposed_notes_expire() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state_with_config(StoreConfig {
+                proposal_timeout_ms: 50,
+                ..store_config()
+            })
+            .await;
+
+            let note = p2id_note(0x9000_0000_0000_0001);
+            let block =
+                TestChain::new(&state).await.next_block_with_notes(vec![note_created(0, &note)]);
+            state.propose_block(block).await.unwrap();
+            let proposed = state.get_proposed_notes(&[]).await.unwrap().unwrap();
+            assert_eq!(proposed.notes.len(), 1);
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert!(state.get_proposed_notes(&[]).await.unwrap().is_none());
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that a block can't be proposed while another one is pending, unlike the pending block
+/// itself, and that only the pending block can be committed
+#[test]
+fn test_state_proposal_pending() {
+    with_state(|state| async move {
+        let note = p2id_note(0x9000_0000_0000_0001);
+        let block = TestChain::new(&state).await.next_block();
+        let other_block =
+            TestChain::new(&state).await.next_block_with_notes(vec![note_created(0, &note)]);
+        let other_hash = BlockHeader::try_from(other_block.block_header.clone()).unwrap().hash();
+
+        let block_hash = state.propose_block(block.clone()).await.unwrap();
+        assert!(matches!(
+            state.propose_block(other_block).await,
+            Err(ApplyBlockError::ProposalPending(pending_hash)) if pending_hash == block_hash
+        ));
+        assert!(matches!(
+            state.commit_block(other_hash).await,
+            Err(ApplyBlockError::ProposalNotFound(hash)) if hash == other_hash
+        ));
+
+        // proposing the pending block again replaces it
+        assert_eq!(state.propose_block(block).await.unwrap(), block_hash);
+        assert_eq!(state.commit_block(block_hash).await.unwrap(), 1);
+        assert_eq!(read_chain_tip(&state, 1).await, 1);
+    });
+}
+
+/// Tests that a proposal which wasn't committed within the proposal timeout can't be committed,
+/// and no longer prevents another block from being proposed
+#[test]
+fn test_state_proposal_expired() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state_with_config(StoreConfig {
+                proposal_timeout_ms: 50,
+                ..store_config()
+            })
+            .await;
+
+            let note = p2id_note(0x9000_0000_0000_0001);
+            let block = TestChain::new(&state).await.next_block();
+            let other_block =
+                TestChain::new(&state).await.next_block_with_notes(vec![note_created(0, &note)]);
+
+            let block_hash = state.propose_block(block).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert!(matches!(
+                state.commit_block(block_hash).await,
+                Err(ApplyBlockError::ProposalExpired(hash)) if hash == block_hash
+            ));
+            assert_eq!(read_chain_tip(&state, 1).await, GENESIS_BLOCK_NUM);
+
+            let other_hash = state.propose_block(other_block).await.unwrap();
+            assert_eq!(state.commit_block(other_hash).await.unwrap(), 1);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that committing a block which is already part of the chain succeeds without any change,
+/// so the block producer can retry a commit whose outcome it doesn't know
+#[test]
+fn test_state_recommit() {
+    with_state(|state| async move {
+        let block = TestChain::new(&state).await.next_block();
+        let block_hash = state.propose_block(block).await.unwrap();
+        assert_eq!(state.commit_block(block_hash).await.unwrap(), 1);
+
+        assert_eq!(state.commit_block(block_hash).await.unwrap(), 1);
+        assert_eq!(read_chain_tip(&state, 1).await, 1);
+
+        // an older block of the chain is committed as well
+        let next_hash =
+            state.propose_block(TestChain::new(&state).await.next_block()).await.unwrap();
+        assert_eq!(state.commit_block(next_hash).await.unwrap(), 2);
+        assert_eq!(state.commit_block(block_hash).await.unwrap(), 1);
+    });
+}
+
+/// Tests that the synced accounts are authenticated by the account root of the chain tip, and
+/// only returned once updated after the client's block
+#[test]
+fn test_state_sync_accounts() {
+    with_state(|state| async move {
+        let mut chain = TestChain::new(&state).await;
+        for _ in 0..3 {
+            state.apply_block(chain.next_block()).await.unwrap();
+        }
+
+        let sync = state.sync_accounts(GENESIS_BLOCK_NUM, &[ACCOUNT_ID]).await.unwrap();
+        let block_header: BlockHeader = sync.block_header.try_into().unwrap();
+        assert_eq!(block_header.block_num(), 3);
+        assert_eq!(sync.accounts.len(), 1);
+
+        let record = &sync.accounts[0];
+        assert_eq!(record.block_num, 3);
+        assert_eq!(record.account_hash, Some(account_hash(3).into()));
+        let path: MerklePath = record.proof.clone().unwrap().try_into().unwrap();
+        assert_eq!(
+            path.compute_root(ACCOUNT_ID, account_hash(3)).unwrap(),
+            block_header.account_root()
+        );
+
+        // nothing changed after the chain tip
+        let sync = state.sync_accounts(3, &[ACCOUNT_ID]).await.unwrap();
+        assert!(sync.accounts.is_empty());
+    });
+}
+
+/// Tests that the blocks are refused while the disk limits are exceeded, and that the reads are
+/// still served
+#[test]
+fn test_state_disk_limits() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            // no disk has this much free space
+            let state = load_state_with_config(StoreConfig {
+                disk_limits: DiskLimitsConfig {
+                    max_dataset_size_mb: 0,
+                    min_free_space_mb: u64::MAX,
+                },
+                ..store_config()
+            })
+            .await;
+
+            let block = TestChain::new(&state).await.next_block();
+            assert!(matches!(
+                state.apply_block(block.clone()).await,
+                Err(ApplyBlockError::DiskLimitExceeded(DiskLimitError::NotEnoughFreeSpace { .. }))
+            ));
+            assert!(matches!(
+                state.propose_block(block).await,
+                Err(ApplyBlockError::DiskLimitExceeded(_))
+            ));
+            assert_eq!(read_chain_tip(&state, 1).await, GENESIS_BLOCK_NUM);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that the details of the private notes are neither accepted nor kept once their tag is
+/// made private, while their hash and metadata are still synced like the public notes'
+#[test]
+fn test_state_private_notes() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let public_note = p2id_note(0x8000_0000_0000_0002);
+            let private_note = p2id_note(0x9000_0000_0000_0003);
+            let private_tag = private_note.metadata().tag().as_int();
+            assert_ne!(public_note.metadata().tag().as_int(), private_tag);
+            let note_hashes = vec![public_note.id().inner(), private_note.id().inner()];
+
+            // both notes are public so far, and both records are stored
+            let state = load_state().await;
+            let block = TestChain::new(&state).await.next_block_with_notes(vec![
+                note_created(0, &public_note),
+                note_created(1, &private_note),
+            ]);
+            state.apply_block(block).await.unwrap();
+            state.submit_note_recovery_record(&public_note).await.unwrap();
+            state.submit_note_recovery_record(&private_note).await.unwrap();
+            assert_eq!(
+                state.get_note_recovery_records(note_hashes.clone()).await.unwrap().len(),
+                2
+            );
+            drop(state);
+
+            // the record of the note made private is deleted when the store restarts
+            let state = load_state_with_config(StoreConfig {
+                note_visibility: NoteVisibilityConfig {
+                    private_tags: vec![TagRange {
+                        start: private_tag,
+                        end: private_tag,
+                    }],
+                },
+                ..store_config()
+            })
+            .await;
+            let records = state.get_note_recovery_records(note_hashes).await.unwrap();
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].note_hash, Some(public_note.id().inner().into()));
+
+            // and its details are refused from now on
+            assert!(matches!(
+                state.submit_note_recovery_record(&private_note).await,
+                Err(NoteRecoveryError::PrivateNote(_))
+            ));
+            state.submit_note_recovery_record(&public_note).await.unwrap();
+
+            // the chain still commits to the private note, which is synced without its details
+            let (update, ..) = state
+                .sync_state(GENESIS_BLOCK_NUM, &[], &[(private_tag >> 48) as u32], &[], &[])
+                .await
+                .unwrap();
+            let synced = update
+                .notes
+                .iter()
+                .find(|note| note.note_hash == Some(private_note.id().inner().into()))
+                .expect("private note not synced");
+            assert_eq!(synced.tag, private_tag);
+            assert_eq!(synced.sender, ACCOUNT_ID);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that the notes flagged private by their block are synced as such, and that their details
+/// are refused whatever the configured tags
+#[test]
+fn test_state_private_note_metadata() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let public_note = p2id_note(0x8000_0000_0000_0002);
+            let private_note = p2id_note(0x9000_0000_0000_0003);
+
+            let state = load_state().await;
+            let block = TestChain::new(&state).await.next_block_with_notes(vec![
+                note_created(0, &public_note),
+                NoteCreated {
+                    private: true,
+                    ..note_created(1, &private_note)
+                },
+            ]);
+            state.apply_block(block).await.unwrap();
+
+            state.submit_note_recovery_record(&public_note).await.unwrap();
+            assert!(matches!(
+                state.submit_note_recovery_record(&private_note).await,
+                Err(NoteRecoveryError::PrivateNote(_))
+            ));
+
+            let tags = [public_note.metadata().tag(), private_note.metadata().tag()]
+                .map(|tag| (tag.as_int() >> 48) as u32);
+            let (update, ..) =
+                state.sync_state(GENESIS_BLOCK_NUM, &[], &tags, &[], &[]).await.unwrap();
+            let synced: BTreeSet<_> = update
+                .notes
+                .iter()
+                .map(|note| (note.note_hash.clone().unwrap(), note.private))
+                .collect();
+            assert_eq!(
+                synced,
+                BTreeSet::from([
+                    (public_note.id().inner().into(), false),
+                    (private_note.id().inner().into(), true)
+                ])
+            );
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that the state of an account published by a block must be the state the block commits to
+#[test]
+fn test_state_account_details() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state().await;
+
+            let mut block = TestChain::new(&state).await.next_block();
+            block.account_details = vec![(ACCOUNT_ID, vec![1, 2, 3])];
+            assert!(matches!(
+                state.apply_block(block).await,
+                Err(ApplyBlockError::InvalidAccountDetails(ACCOUNT_ID))
+            ));
+
+            // the state of an account which isn't updated by the block can't be published either
+            let mut block = TestChain::new(&state).await.next_block();
+            block.account_details = vec![(ACCOUNT_ID + 1, vec![1, 2, 3])];
+            assert!(matches!(
+                state.apply_block(block).await,
+                Err(ApplyBlockError::InvalidAccountDetails(id)) if id == ACCOUNT_ID + 1
+            ));
+            assert_eq!(read_chain_tip(&state, 0).await, 0);
+
+            // the account is only tracked by its hash without its state
+            let block = TestChain::new(&state).await.next_block();
+            state.apply_block(block).await.unwrap();
+            let (account, ..) = state.get_transaction_inputs(ACCOUNT_ID, &[], None).await.unwrap();
+            assert_eq!(account.details, None);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that a block of another chain is rejected
+#[test]
+fn test_state_block_chain_id() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state().await;
+
+            let mut block = TestChain::new(&state).await.next_block();
+            block.chain_id = CHAIN_ID + 1;
+            assert!(matches!(
+                state.apply_block(block).await,
+                Err(ApplyBlockError::ChainIdMismatch { expected: CHAIN_ID, block_chain_id })
+                    if block_chain_id == CHAIN_ID + 1
+            ));
+            assert_eq!(read_chain_tip(&state, 0).await, 0);
+
+            let block = TestChain::new(&state).await.next_block();
+            state.apply_block(block).await.unwrap();
+            assert_eq!(read_chain_tip(&state, 1).await, 1);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that with the batch proofs verified, a block is applied only if every batch is proven, the
+/// proofs verify, its header commits to them, and the proven batches created the notes of the block
+#[test]
+fn test_state_batch_proofs() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state_with_config(StoreConfig {
+                data_directory: "batch-proofs".into(),
+                verify_batch_proofs: true,
+                ..store_config()
+            })
+            .await;
+            let batch_proof = prove_batch(2);
+
+            // a block can't skip the proofs of its batches by committing to none
+            let block = TestChain::new(&state).await.next_block();
+            assert!(matches!(
+                state.apply_block(block).await,
+                Err(ApplyBlockError::UnprovenTransactions {
+                    num_accounts: 1,
+                    num_txs: 0
+                })
+            ));
+
+            // the header must commit to the proofs of the batches
+            let mut block = TestChain::new(&state).await.next_block();
+            block.batch_proofs = vec![batch_proof.clone()];
+            assert!(matches!(
+                state.apply_block(block).await,
+                Err(ApplyBlockError::NewBlockInvalidBatchRoot)
+            ));
+
+            // a proof of another commitment doesn't verify, even if the header commits to it
+            let tampered = BatchProof {
+                commitment: prove_batch(3).commitment,
+                ..batch_proof.clone()
+            };
+            let block = TestChain::new(&state).await.next_block_with(Vec::new(), vec![tampered]);
+            assert!(matches!(
+                state.apply_block(block).await,
+                Err(ApplyBlockError::InvalidBatchProof { batch_index: 0, .. })
+            ));
+            assert_eq!(state.chain_tip().await, GENESIS_BLOCK_NUM);
+
+            // the proven batch created no note, while the block has a note in its subtree
+            let note = note_created(0, &p2id_note(ACCOUNT_ID + 1));
+            let block = TestChain::new(&state)
+                .await
+                .next_block_with(vec![note], vec![batch_proof.clone()]);
+            assert!(matches!(
+                state.apply_block(block).await,
+                Err(ApplyBlockError::BatchProofNotesMismatch { batch_index: 0 })
+            ));
+
+            // the note is in the subtree of the next batch, which isn't proven
+            let note = note_created(MAX_NOTES_PER_BATCH, &p2id_note(ACCOUNT_ID + 1));
+            let block = TestChain::new(&state)
+                .await
+                .next_block_with(vec![note], vec![batch_proof.clone()]);
+            assert!(matches!(
+                state.apply_block(block).await,
+                Err(ApplyBlockError::UnprovenBatch { batch_index: 1 })
+            ));
+
+            // a batch can't be proven twice
+            let block = TestChain::new(&state)
+                .await
+                .next_block_with(Vec::new(), vec![batch_proof.clone(), batch_proof.clone()]);
+            assert!(matches!(
+                state.apply_block(block).await,
+                Err(ApplyBlockError::BatchProofOutOfOrder { batch_index: 0 })
+            ));
+            assert_eq!(state.chain_tip().await, GENESIS_BLOCK_NUM);
+
+            let block = TestChain::new(&state)
+                .await
+                .next_block_with(Vec::new(), vec![batch_proof.clone()]);
+            state.apply_block(block).await.unwrap();
+            assert_eq!(read_chain_tip(&state, 1).await, 1);
+
+            // the proofs are not verified by default
+            let state = load_state().await;
+            let tampered = BatchProof {
+                commitment: prove_batch(3).commitment,
+                ..batch_proof
+            };
+            let block = TestChain::new(&state).await.next_block_with(Vec::new(), vec![tampered]);
+            state.apply_block(block).await.unwrap();
+            assert_eq!(read_chain_tip(&state, 1).await, 1);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that the timestamp attestation carried by a block must attest its parent on the chain of
+/// the store, and is kept along the parent
+#[test]
+fn test_state_timestamp_attestation() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state().await;
+            let keypair = KeyPair::new().unwrap();
+            let genesis_hash = state.genesis_hash();
+            let attest = |chain_id, block_hash| {
+                let attestation =
+                    TimestampAttestation::sign(&keypair, chain_id, block_hash, 1_700_000_000)
+                        .unwrap();
+                Some(block_header::TimestampAttestation::from(&attestation))
+            };
+
+            // an attestation of the block itself, or of the parent on another chain, is rejected
+            let mut block = TestChain::new(&state).await.next_block();
+            let block_hash = BlockHeader::try_from(&block.block_header).unwrap().hash();
+            for timestamp_attestation in
+                [attest(CHAIN_ID, block_hash), attest(CHAIN_ID + 1, genesis_hash)]
+            {
+                block.timestamp_attestation = timestamp_attestation;
+                assert!(matches!(
+                    state.apply_block(block.clone()).await,
+                    Err(ApplyBlockError::InvalidTimestampAttestation)
+                ));
+            }
+            assert_eq!(state.chain_tip().await, GENESIS_BLOCK_NUM);
+
+            block.timestamp_attestation = attest(CHAIN_ID, genesis_hash);
+            state.apply_block(block.clone()).await.unwrap();
+            assert_eq!(
+                state.db.select_block_timestamp_attestation(GENESIS_BLOCK_NUM).await.unwrap(),
+                block.timestamp_attestation
+            );
+            assert_eq!(state.db.select_block_timestamp_attestation(1).await.unwrap(), None);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that the blocks dated before the genesis block, or too far ahead of the store's clock, are
+/// rejected, and that a block may share the timestamp of the previous one
+#[test]
+fn test_state_block_timestamps() {
+    with_state(|state| async move {
+        let genesis_timestamp = state.latest_block_header().await.unwrap().timestamp().as_int();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        // a rejected block moves the test chain on, every block is built on a new one
+        let block = TestChain::new(&state).await.next_block_at(genesis_timestamp - 1);
+        assert!(matches!(
+            state.apply_block(block).await,
+            Err(ApplyBlockError::NewBlockTimestampBeforeGenesis { timestamp, .. })
+                if timestamp == genesis_timestamp - 1
+        ));
+
+        // the store's config allows 60 seconds of skew
+        let block = TestChain::new(&state).await.next_block_at(now + 3600);
+        assert!(matches!(
+            state.apply_block(block).await,
+            Err(ApplyBlockError::NewBlockTimestampInFuture { max_skew_s: 60, .. })
+        ));
+        assert_eq!(state.chain_tip().await, GENESIS_BLOCK_NUM);
+
+        let mut chain = TestChain::new(&state).await;
+        state.apply_block(chain.next_block_at(genesis_timestamp)).await.unwrap();
+        state.apply_block(chain.next_block_at(genesis_timestamp)).await.unwrap();
+        assert_eq!(state.chain_tip().await, 2);
+    });
+}
+
+/// Tests that a run of blocks is applied entirely or not at all, a single invalid block rejecting
+/// the whole run
+#[test]
+fn test_state_apply_blocks_atomicity() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state().await;
+            let mut chain = TestChain::new(&state).await;
+            let blocks: Vec<NewBlock> = (0..3).map(|_| chain.next_block()).collect();
+
+            // the last block produces the nullifiers of the first one
+            let mut invalid = blocks.clone();
+            invalid[2].nullifiers = block_nullifiers(1);
+            assert!(matches!(
+                state.apply_blocks(invalid).await,
+                Err(ApplyBlockError::DuplicatedNullifiers(_))
+            ));
+            assert_eq!(state.chain_tip().await, GENESIS_BLOCK_NUM);
+            let (_, block_nums) = state.check_nullifiers(&[], &block_nullifiers(1)).await;
+            assert!(block_nums.iter().all(|&block_num| block_num == 0), "{block_nums:?}");
+
+            assert_eq!(state.apply_blocks(blocks).await.unwrap(), 3);
+            assert_eq!(read_chain_tip(&state, 3).await, 3);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that a run of blocks is rejected unless it follows the chain tip without gaps
+#[test]
+fn test_state_apply_blocks_contiguity() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state().await;
+            let mut chain = TestChain::new(&state).await;
+            let blocks: Vec<NewBlock> = (0..3).map(|_| chain.next_block()).collect();
+
+            // a gap within the run
+            assert!(matches!(
+                state.apply_blocks(vec![blocks[0].clone(), blocks[2].clone()]).await,
+                Err(ApplyBlockError::NewBlockInvalidBlockNum)
+            ));
+            // a gap after the chain tip
+            assert!(matches!(
+                state.apply_blocks(blocks[1..].to_vec()).await,
+                Err(ApplyBlockError::NewBlockInvalidBlockNum)
+            ));
+            assert_eq!(state.chain_tip().await, GENESIS_BLOCK_NUM);
+
+            assert_eq!(state.apply_blocks(blocks[..1].to_vec()).await.unwrap(), 1);
+            assert_eq!(state.apply_blocks(blocks[1..].to_vec()).await.unwrap(), 3);
+            assert_eq!(read_chain_tip(&state, 3).await, 3);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that the public notes consumed by an applied block are no longer listed as unconsumed,
+/// while the other notes of their tag still are
+#[test]
+fn test_state_unconsumed_notes() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state().await;
+            let target = 0x8000_0000_0000_0002;
+            let notes = [p2id_note(target), p2id_note(target + 1)];
+            let tag = (notes[0].metadata().tag().as_int() >> 48) as u32;
+            assert_eq!((notes[1].metadata().tag().as_int() >> 48) as u32, tag);
+            let note_hashes = |unconsumed: &UnconsumedNotes| -> Vec<Option<Digest>> {
+                unconsumed.notes.iter().map(|note| note.note_hash.clone()).collect()
+            };
+
+            let block = TestChain::new(&state).await.next_block_with_notes(vec![
+                note_created(0, &notes[0]),
+                note_created(1, &notes[1]),
+            ]);
+            state.apply_block(block).await.unwrap();
+            let unconsumed =
+                state.get_unconsumed_notes(vec![tag], GENESIS_BLOCK_NUM).await.unwrap();
+            assert_eq!(
+                note_hashes(&unconsumed),
+                vec![Some(notes[0].id().inner().into()), Some(notes[1].id().inner().into())]
+            );
+
+            // the next block consumes the first note, linked to one of its nullifiers
+            let mut block = TestChain::new(&state).await.next_block();
+            block.consumed_notes = vec![(notes[0].id().inner(), block.nullifiers[0])];
+            state.apply_block(block).await.unwrap();
+
+            let unconsumed =
+                state.get_unconsumed_notes(vec![tag], GENESIS_BLOCK_NUM).await.unwrap();
+            assert_eq!(unconsumed.chain_tip, 2);
+            assert_eq!(note_hashes(&unconsumed), vec![Some(notes[1].id().inner().into())]);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that the notes of a block are placed at the offset of their batch in its note tree, as
+/// by the block producer, and that their paths authenticate them against the note root
+#[test]
+fn test_state_note_paths_across_batches() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state().await;
+
+            // two notes of the first batch, and one of the second and the fourth batches each
+            let notes: Vec<(u32, ObjectNote)> = [(0, 0), (0, 1), (1, 0), (3, 2)]
+                .into_iter()
+                .enumerate()
+                .map(|(i, (batch_idx, note_idx))| {
+                    let note_index = batch_idx * MAX_NOTES_PER_BATCH + note_idx;
+                    (note_index, p2id_note(0x8000_0000_0000_0002 + i as u64))
+                })
+                .collect();
+
+            // the tree of the block, each batch tree being the subtree at its index
+            let batch_tree_width = 1 << (NOTE_LEAF_DEPTH - BATCH_TREE_DEPTH);
+            let leaves = notes.iter().flat_map(|(note_index, note)| {
+                let batch_idx = (note_index / MAX_NOTES_PER_BATCH) as u64;
+                let note_idx = (note_index % MAX_NOTES_PER_BATCH) as u64;
+                let leaf = batch_idx * batch_tree_width + 2 * note_idx;
+                [(leaf, Word::from(note.id().inner())), (leaf + 1, Word::from(*note.metadata()))]
+            });
+            let expected_tree = SimpleSmt::<NOTE_LEAF_DEPTH>::with_leaves(leaves).unwrap();
+
+            let block = TestChain::new(&state).await.next_block_with_notes(
+                notes.iter().map(|(note_index, note)| note_created(*note_index, note)).collect(),
+            );
+            let header = BlockHeader::try_from(block.block_header.clone()).unwrap();
+            assert_eq!(header.note_root(), expected_tree.root());
+            state.apply_block(block).await.unwrap();
+
+            for (note_index, _) in &notes {
+                let path = state.get_note_authentication_path(1, *note_index).await.unwrap();
+                let leaf = LeafIndex::new(note_leaf_index(*note_index)).unwrap();
+                assert_eq!(path, expected_tree.open(&leaf).path, "path of note {note_index}");
+            }
+
+            // the batches which created no note have no note
+            assert!(matches!(
+                state.get_note_authentication_path(1, 2 * MAX_NOTES_PER_BATCH).await,
+                Err(GetNoteAuthenticationPathError::NoteNotFound { .. })
+            ));
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that the inclusion paths regenerated from the note leaves of a block whose note tree isn't
+/// stored, e.g. a block applied before the note trees were stored, verify against its note root
+#[test]
+fn test_state_regenerated_note_paths() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state().await;
+
+            let notes: Vec<(u32, ObjectNote)> = [0, 1, MAX_NOTES_PER_BATCH]
+                .into_iter()
+                .enumerate()
+                .map(|(i, note_index)| (note_index, p2id_note(0x8000_0000_0000_0002 + i as u64)))
+                .collect();
+            let block = TestChain::new(&state).await.next_block_with_notes(
+                notes.iter().map(|(note_index, note)| note_created(*note_index, note)).collect(),
+            );
+            let header = BlockHeader::try_from(block.block_header.clone()).unwrap();
+            state.apply_block(block).await.unwrap();
+
+            // the note tree of the block is dropped, its paths are regenerated from its notes
+            let connections = state.db.exhaust_pool().await;
+            connections[0]
+                .interact(|conn| conn.execute("DELETE FROM note_trees WHERE block_num = 1;", []))
+                .await
+                .unwrap()
+                .unwrap();
+            drop(connections);
+            assert!(state.db.select_note_tree(1).await.unwrap().is_none());
+
+            for (note_index, note) in &notes {
+                let path = state.get_note_authentication_path(1, *note_index).await.unwrap();
+                let root = path.compute_root(note_leaf_index(*note_index), note.id().inner());
+                assert_eq!(root.unwrap(), header.note_root(), "path of note {note_index}");
+            }
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that the commitment at the chain tip is read from the in-memory state, matches the one
+/// read from the database once the chain moved on, and that a diverged in-memory state is counted
+#[test]
+fn test_state_commitment() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state().await;
+            let note = p2id_note(0x8000_0000_0000_0002);
+
+            let block =
+                TestChain::new(&state).await.next_block_with_notes(vec![note_created(0, &note)]);
+            state.apply_block(block).await.unwrap();
+            let mut block = TestChain::new(&state).await.next_block();
+            block.consumed_notes = vec![(note.id().inner(), block.nullifiers[0])];
+            state.apply_block(block).await.unwrap();
+
+            let at_chain_tip = state.state_commitment(None).await.unwrap().unwrap();
+            assert_eq!(at_chain_tip.block_num, 2);
+            assert_eq!(
+                at_chain_tip.nullifier_root,
+                Some(state.inner.read().await.nullifier_tree.root())
+            );
+            assert_eq!(
+                (
+                    at_chain_tip.num_notes,
+                    at_chain_tip.num_nullifiers,
+                    at_chain_tip.num_consumed_notes
+                ),
+                (1, 2 * NULLIFIERS_PER_BLOCK as u64, 1)
+            );
+            assert_eq!(state.commitment_metrics().diverged(), 0);
+
+            let block = TestChain::new(&state).await.next_block();
+            state.apply_block(block).await.unwrap();
+            let stored = state.state_commitment(Some(2)).await.unwrap().unwrap();
+            assert_eq!(stored.nullifier_root, None);
+            assert_eq!(stored.digest(), at_chain_tip.digest());
+
+            // the in-memory account tree diverged from the database
+            state
+                .inner
+                .write()
+                .await
+                .account_tree
+                .insert(LeafIndex::new_max_depth(ACCOUNT_ID), account_hash(100).into());
+            let diverged = state.state_commitment(None).await.unwrap().unwrap();
+            assert_ne!(
+                diverged.account_root,
+                TestChain::new(&state).await.prev_block.account_root()
+            );
+            assert_eq!(state.commitment_metrics().diverged(), 1);
+        });
+
+        Ok(())
+    });
+}
+
+/// Tests that the rows the state is built from are counted, read and inserted into the trees, and
+/// that the reloaded state matches the one it was saved by
+#[test]
+fn test_state_load_progress() {
+    Jail::expect_with(|_jail| {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let state = load_state().await;
+            for _ in 0..3 {
+                let block = TestChain::new(&state).await.next_block();
+                state.apply_block(block).await.unwrap();
+            }
+            let chain_tip = state.latest_chain_tip();
+            let (nullifier_root, account_root) = {
+                let inner = state.inner.read().await;
+                (inner.nullifier_tree.root(), inner.account_tree.root())
+            };
+            drop(state);
+
+            let progress = StartupProgress::default();
+            let db = Db::setup(store_config()).await.unwrap();
+            let state = State::load_with_progress(
+                Arc::new(db),
+                60,
+                Duration::from_millis(30000),
+                TagPolicy::default(),
+                NoteVisibilityConfig::default(),
+                false,
+                &progress,
+            )
+            .await
+            .unwrap();
+
+            // the nullifiers of the blocks, the account they update and the headers of the chain
+            let total_rows = (3 * NULLIFIERS_PER_BLOCK + 1 + 4) as u64;
+            let status = progress.status();
+            assert_eq!(status.stage, StartupStage::Loaded);
+            assert_eq!(
+                (status.rows_read, status.rows_loaded, status.total_rows),
+                (total_rows, total_rows, total_rows)
+            );
+            assert_eq!(status.percent_complete(), 100);
+
+            assert_eq!(state.latest_chain_tip(), chain_tip);
+            let inner = state.inner.read().await;
+            assert_eq!(inner.nullifier_tree.root(), nullifier_root);
+            assert_eq!(inner.account_tree.root(), account_root);
+        });
+
+        Ok(())
+    });
+}
@@ -0,0 +1,333 @@
+//! Subscriptions to the consumption of nullifiers.
+//!
+//! Bridges finalize a withdrawal once the note it created is consumed, and need to learn it as soon
+//! as the consuming block is applied. A subscription watches a set of nullifiers in full, and
+//! notifies every block consuming some of them, starting at a given block.
+//!
+//! The delivery is at-least-once: a client whose stream was interrupted resumes it from the last
+//! block it was notified of, which is then notified again. The blocks applied before the
+//! subscription, and the blocks missed by a subscription which fell behind, are recovered from
+//! the nullifier tree.
+//!
+//! Caches of the chain tip, e.g. the RPC's, subscribe to the chain tip instead, and are notified of
+//! its latest value whenever it changes.
+use std::{collections::BTreeSet, sync::Arc};
+
+use miden_crypto::hash::rpo::RpoDigest;
+use miden_node_proto::responses::{SubscribeChainTipResponse, SubscribeNullifiersResponse};
+use tokio::sync::{broadcast::error::RecvError, mpsc, watch};
+use tonic::Status;
+use tracing::{debug, warn};
+
+use crate::{
+    state::{ChainTip, State},
+    types::BlockNumber,
+    COMPONENT,
+};
+
+// APPLIED NULLIFIERS
+// ================================================================================================
+
+/// The nullifiers of a block, published by the [State] once the block is applied.
+#[derive(Debug, Clone)]
+pub struct AppliedNullifiers {
+    pub block_num: BlockNumber,
+    pub nullifiers: Arc<Vec<RpoDigest>>,
+}
+
+// NULLIFIER SUBSCRIPTION
+// ================================================================================================
+
+/// A block consuming some of the watched nullifiers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NullifierNotification {
+    pub block_num: BlockNumber,
+    pub nullifiers: Vec<RpoDigest>,
+}
+
+impl From<NullifierNotification> for SubscribeNullifiersResponse {
+    fn from(notification: NullifierNotification) -> Self {
+        Self {
+            block_num: notification.block_num,
+            nullifiers: notification.nullifiers.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// The nullifiers watched by a subscription, which were not notified yet.
+#[derive(Debug)]
+pub struct NullifierSubscription {
+    pending: BTreeSet<RpoDigest>,
+
+    /// First block whose nullifiers are notified, the nullifiers consumed before it are dropped
+    from_block: BlockNumber,
+
+    /// Last block whose nullifiers were checked
+    last_block: Option<BlockNumber>,
+}
+
+impl NullifierSubscription {
+    pub fn new(
+        nullifiers: impl IntoIterator<Item = RpoDigest>,
+        from_block: BlockNumber,
+    ) -> Self {
+        Self {
+            pending: nullifiers.into_iter().collect(),
+            from_block,
+            last_block: None,
+        }
+    }
+
+    /// Returns the watched nullifiers which were not notified yet.
+    pub fn pending(&self) -> Vec<RpoDigest> {
+        self.pending.iter().copied().collect()
+    }
+
+    /// Whether every watched nullifier was notified.
+    pub fn is_completed(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Returns the notifications of the blocks up to `chain_tip`, given the block which consumed
+    /// each of the [NullifierSubscription::pending] nullifiers, zero if not consumed.
+    ///
+    /// Following blocks are notified by [NullifierSubscription::apply], until the subscription
+    /// falls behind and has to catch up again.
+    pub fn catch_up(
+        &mut self,
+        chain_tip: BlockNumber,
+        block_nums: &[BlockNumber],
+    ) -> Vec<NullifierNotification> {
+        let mut notifications: Vec<NullifierNotification> = Vec::new();
+
+        let mut consumed: Vec<(BlockNumber, RpoDigest)> = self
+            .pending
+            .iter()
+            .zip(block_nums)
+            .filter(|&(_, &block_num)| block_num != 0)
+            .map(|(&nullifier, &block_num)| (block_num, nullifier))
+            .collect();
+        consumed.sort();
+
+        for (block_num, nullifier) in consumed {
+            self.pending.remove(&nullifier);
+            if block_num < self.from_block {
+                continue;
+            }
+            match notifications.last_mut() {
+                Some(last) if last.block_num == block_num => last.nullifiers.push(nullifier),
+                _ => notifications.push(NullifierNotification {
+                    block_num,
+                    nullifiers: vec![nullifier],
+                }),
+            }
+        }
+
+        self.last_block = Some(chain_tip);
+        notifications
+    }
+
+    /// Returns the notification of the `block` applied after the last checked block, if it
+    /// consumes some of the pending nullifiers.
+    ///
+    /// The blocks which were already checked are ignored.
+    pub fn apply(
+        &mut self,
+        block: &AppliedNullifiers,
+    ) -> Option<NullifierNotification> {
+        if self.last_block.is_some_and(|last_block| block.block_num <= last_block) {
+            return None;
+        }
+        self.last_block = Some(block.block_num);
+
+        let nullifiers: Vec<RpoDigest> = block
+            .nullifiers
+            .iter()
+            .filter(|nullifier| self.pending.remove(nullifier))
+            .copied()
+            .collect();
+
+        // a subscription may start after the chain tip
+        if block.block_num < self.from_block {
+            return None;
+        }
+
+        (!nullifiers.is_empty()).then_some(NullifierNotification {
+            block_num: block.block_num,
+            nullifiers,
+        })
+    }
+
+    /// Notifies the blocks following the last checked block from now on, the subscription fell
+    /// behind the applied blocks and must catch up.
+    fn resume_after_last_block(&mut self) {
+        if let Some(last_block) = self.last_block {
+            self.from_block = self.from_block.max(last_block + 1);
+        }
+    }
+}
+
+// SUBSCRIPTION TASK
+// ================================================================================================
+
+/// Sends the notifications of `subscription` to `sender`, until every watched nullifier was
+/// notified, or the client went away.
+pub async fn run(
+    state: Arc<State>,
+    mut subscription: NullifierSubscription,
+    sender: mpsc::Sender<Result<SubscribeNullifiersResponse, Status>>,
+) {
+    loop {
+        // subscribing before catching up, so no block is missed in between
+        let mut applied = state.subscribe_nullifiers();
+
+        let (chain_tip, block_nums) = state.nullifier_blocks(&subscription.pending()).await;
+        for notification in subscription.catch_up(chain_tip, &block_nums) {
+            if sender.send(Ok(notification.into())).await.is_err() {
+                return;
+            }
+        }
+
+        while !subscription.is_completed() {
+            let block = tokio::select! {
+                _ = sender.closed() => return,
+                block = applied.recv() => block,
+            };
+
+            match block {
+                Ok(block) => {
+                    if let Some(notification) = subscription.apply(&block) {
+                        if sender.send(Ok(notification.into())).await.is_err() {
+                            return;
+                        }
+                    }
+                },
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(target: COMPONENT, skipped, "Nullifier subscription fell behind, catching up");
+                    subscription.resume_after_last_block();
+                    break;
+                },
+                Err(RecvError::Closed) => return,
+            }
+        }
+
+        if subscription.is_completed() {
+            debug!(target: COMPONENT, "Every watched nullifier was notified");
+            return;
+        }
+    }
+}
+
+// CHAIN TIP SUBSCRIPTION
+// ================================================================================================
+
+impl From<ChainTip> for SubscribeChainTipResponse {
+    fn from(chain_tip: ChainTip) -> Self {
+        Self {
+            block_num: chain_tip.block_num,
+            block_hash: Some(chain_tip.block_hash.into()),
+        }
+    }
+}
+
+/// Sends the current chain tip to `sender`, and then every new one, until the client went away.
+///
+/// A client slower than the chain skips the intermediate tips, and only receives the latest one.
+pub async fn run_chain_tip(
+    mut chain_tip: watch::Receiver<ChainTip>,
+    sender: mpsc::Sender<Result<SubscribeChainTipResponse, Status>>,
+) {
+    loop {
+        let tip = *chain_tip.borrow_and_update();
+        if sender.send(Ok(tip.into())).await.is_err() {
+            return;
+        }
+
+        tokio::select! {
+            _ = sender.closed() => return,
+            changed = chain_tip.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use miden_crypto::{hash::rpo::RpoDigest, Felt};
+
+    use super::{AppliedNullifiers, NullifierNotification, NullifierSubscription};
+
+    fn nullifier(value: u64) -> RpoDigest {
+        RpoDigest::new([Felt::new(value), Felt::new(0), Felt::new(0), Felt::new(0)])
+    }
+
+    fn block(
+        block_num: u32,
+        nullifiers: &[RpoDigest],
+    ) -> AppliedNullifiers {
+        AppliedNullifiers {
+            block_num,
+            nullifiers: Arc::new(nullifiers.to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_nullifier_subscription_catch_up() {
+        let nullifiers = [nullifier(1), nullifier(2), nullifier(3), nullifier(4)];
+        let mut subscription = NullifierSubscription::new(nullifiers, 5);
+
+        // consumed before the first notified block, twice in block 6, and not consumed
+        let notifications = subscription.catch_up(8, &[3, 6, 6, 0]);
+        assert_eq!(
+            notifications,
+            vec![NullifierNotification {
+                block_num: 6,
+                nullifiers: vec![nullifier(2), nullifier(3)],
+            }]
+        );
+        assert_eq!(subscription.pending(), vec![nullifier(4)]);
+
+        // the blocks covered by the catch up are not notified again
+        assert_eq!(subscription.apply(&block(8, &[nullifier(4)])), None);
+        assert!(!subscription.is_completed());
+
+        assert_eq!(
+            subscription.apply(&block(9, &[nullifier(4), nullifier(5)])),
+            Some(NullifierNotification {
+                block_num: 9,
+                nullifiers: vec![nullifier(4)],
+            })
+        );
+        assert!(subscription.is_completed());
+
+        // the blocks before the first notified block are not notified
+        let mut subscription = NullifierSubscription::new([nullifier(1)], 10);
+        assert!(subscription.catch_up(8, &[0]).is_empty());
+        assert_eq!(subscription.apply(&block(9, &[nullifier(1)])), None);
+        assert!(subscription.is_completed());
+    }
+
+    #[test]
+    fn test_nullifier_subscription_resume() {
+        let mut subscription = NullifierSubscription::new([nullifier(1), nullifier(2)], 0);
+        assert!(subscription.catch_up(2, &[0, 0]).is_empty());
+        assert!(subscription.apply(&block(3, &[nullifier(1)])).is_some());
+
+        // after falling behind, the nullifiers consumed since the last block are caught up
+        subscription.resume_after_last_block();
+        let notifications = subscription.catch_up(5, &[4]);
+        assert_eq!(
+            notifications,
+            vec![NullifierNotification {
+                block_num: 4,
+                nullifiers: vec![nullifier(2)],
+            }]
+        );
+        assert!(subscription.is_completed());
+    }
+}
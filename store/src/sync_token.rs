@@ -0,0 +1,163 @@
+//! Resumption tokens of the state sync.
+//!
+//! Every `SyncState` response carries an opaque token resuming the sync after the block it
+//! returned. A client interrupted in the middle of a long sync sends the token back instead of a
+//! block number. The token names the last block the client processed along with its hash, and a
+//! digest of the filters of the request which returned it, so the store rejects the tokens resumed
+//! with other filters or after the chain was rolled back past their block, which would otherwise
+//! skip updates silently.
+use miden_crypto::{
+    hash::rpo::{Rpo256, RpoDigest},
+    utils::{Deserializable, Serializable},
+};
+
+use crate::{
+    errors::SyncTokenError,
+    types::{AccountId, BlockNumber},
+};
+
+/// Version of the encoding of the tokens, bumped whenever their content changes.
+const SYNC_TOKEN_VERSION: u8 = 1;
+
+/// Length of an encoded token: the version, block number, block hash and filters digest.
+const SYNC_TOKEN_LEN: usize = 1 + 4 + 32 + 32;
+
+// SYNC TOKEN
+// ================================================================================================
+
+/// Resumes a state sync after the block `block_num`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncToken {
+    /// Last block processed by the client.
+    pub block_num: BlockNumber,
+
+    /// Hash of the block `block_num`, to detect the chain was rolled back past it.
+    pub block_hash: RpoDigest,
+
+    /// Digest of the filters of the sync, see [sync_filters_digest].
+    pub filters: RpoDigest,
+}
+
+impl SyncToken {
+    /// Encodes the token, as sent to the clients.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SYNC_TOKEN_LEN);
+        bytes.push(SYNC_TOKEN_VERSION);
+        bytes.extend_from_slice(&self.block_num.to_le_bytes());
+        bytes.extend_from_slice(&self.block_hash.to_bytes());
+        bytes.extend_from_slice(&self.filters.to_bytes());
+        bytes
+    }
+
+    /// Decodes a token sent back by a client.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SyncTokenError> {
+        let Some(&version) = bytes.first() else {
+            return Err(SyncTokenError::Malformed);
+        };
+        if version != SYNC_TOKEN_VERSION {
+            return Err(SyncTokenError::UnsupportedVersion(version));
+        }
+        if bytes.len() != SYNC_TOKEN_LEN {
+            return Err(SyncTokenError::Malformed);
+        }
+
+        let block_num = BlockNumber::from_le_bytes(bytes[1..5].try_into().expect("4 bytes"));
+        let block_hash =
+            RpoDigest::read_from_bytes(&bytes[5..37]).map_err(|_| SyncTokenError::Malformed)?;
+        let filters =
+            RpoDigest::read_from_bytes(&bytes[37..]).map_err(|_| SyncTokenError::Malformed)?;
+
+        Ok(Self {
+            block_num,
+            block_hash,
+            filters,
+        })
+    }
+}
+
+/// Returns the digest of the filters of a state sync.
+///
+/// The filters are sorted first, so the digest doesn't depend on the order the client lists them.
+pub fn sync_filters_digest(
+    account_ids: &[AccountId],
+    note_tag_prefixes: &[u32],
+    nullifier_prefixes: &[u32],
+    note_hashes: &[RpoDigest],
+) -> RpoDigest {
+    let mut account_ids = account_ids.to_vec();
+    account_ids.sort_unstable();
+    let mut note_tag_prefixes = note_tag_prefixes.to_vec();
+    note_tag_prefixes.sort_unstable();
+    let mut nullifier_prefixes = nullifier_prefixes.to_vec();
+    nullifier_prefixes.sort_unstable();
+    let mut note_hashes = note_hashes.to_vec();
+    note_hashes.sort_unstable();
+
+    // every list is prefixed by its length, so the boundaries between them are unambiguous
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(account_ids.len() as u64).to_le_bytes());
+    for account_id in account_ids {
+        bytes.extend_from_slice(&account_id.to_le_bytes());
+    }
+    for prefixes in [note_tag_prefixes, nullifier_prefixes] {
+        bytes.extend_from_slice(&(prefixes.len() as u64).to_le_bytes());
+        for prefix in prefixes {
+            bytes.extend_from_slice(&prefix.to_le_bytes());
+        }
+    }
+    bytes.extend_from_slice(&(note_hashes.len() as u64).to_le_bytes());
+    for note_hash in note_hashes {
+        bytes.extend_from_slice(&note_hash.as_bytes());
+    }
+
+    Rpo256::hash(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_crypto::hash::rpo::RpoDigest;
+    use miden_objects::Felt;
+
+    use super::{sync_filters_digest, SyncToken};
+    use crate::errors::SyncTokenError;
+
+    #[test]
+    fn test_sync_token_encoding() {
+        let token = SyncToken {
+            block_num: 42,
+            block_hash: RpoDigest::new([Felt::new(1), Felt::new(2), Felt::new(3), Felt::new(4)]),
+            filters: sync_filters_digest(&[1, 2], &[3], &[], &[]),
+        };
+
+        let bytes = token.to_bytes();
+        assert_eq!(SyncToken::from_bytes(&bytes).unwrap(), token);
+
+        assert!(matches!(SyncToken::from_bytes(&[]), Err(SyncTokenError::Malformed)));
+        assert!(matches!(
+            SyncToken::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(SyncTokenError::Malformed)
+        ));
+
+        let mut other_version = bytes.clone();
+        other_version[0] = 0;
+        assert!(matches!(
+            SyncToken::from_bytes(&other_version),
+            Err(SyncTokenError::UnsupportedVersion(0))
+        ));
+    }
+
+    #[test]
+    fn test_sync_filters_digest() {
+        // the order of the filters doesn't matter
+        assert_eq!(
+            sync_filters_digest(&[1, 2], &[3, 4], &[5], &[]),
+            sync_filters_digest(&[2, 1], &[4, 3], &[5], &[])
+        );
+
+        // a filter can't be moved to another list
+        assert_ne!(
+            sync_filters_digest(&[], &[3], &[], &[]),
+            sync_filters_digest(&[], &[], &[3], &[])
+        );
+    }
+}
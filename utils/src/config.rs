@@ -2,7 +2,8 @@ use std::{
     fmt::{Display, Formatter},
     io,
     net::{SocketAddr, ToSocketAddrs},
-    path::Path,
+    path::{Path, PathBuf},
+    time::Duration,
     vec,
 };
 
@@ -10,15 +11,31 @@ use figment::{
     providers::{Format, Toml},
     Figment,
 };
+use futures::{stream, Stream, StreamExt};
+use miden_crypto::{hash::rpo::RpoDigest, utils::Deserializable};
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tonic::transport::{
+    server::{Connected, TcpConnectInfo, TcpIncoming},
+    Server,
+};
 
-/// The `(host, port)` pair for the server's listening socket.
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
-pub struct Endpoint {
-    /// Host used by the store.
-    pub host: String,
-    /// Port number used by the store.
-    pub port: u16,
+#[cfg(unix)]
+use crate::listener::unix_incoming;
+use crate::{
+    documented_config,
+    listener::{limit_connections, Connection},
+};
+
+documented_config! {
+    /// The `(host, port)` pair for the server's listening socket.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+    pub struct Endpoint {
+        /// Host name or IP address the server listens on.
+        pub host: String,
+        /// Port the server listens on.
+        pub port: u16,
+    }
 }
 
 impl ToSocketAddrs for Endpoint {
@@ -33,10 +50,177 @@ impl Display for Endpoint {
         &self,
         f: &mut Formatter<'_>,
     ) -> std::fmt::Result {
-        f.write_fmt(format_args!("http://{}:{}", self.host, self.port))
+        // IPv6 addresses are enclosed in brackets in URLs
+        if self.host.contains(':') && !self.host.starts_with('[') {
+            f.write_fmt(format_args!("http://[{}]:{}", self.host, self.port))
+        } else {
+            f.write_fmt(format_args!("http://{}:{}", self.host, self.port))
+        }
     }
 }
 
+/// An address a gRPC server listens on in addition to its endpoint.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ListenAddress {
+    /// A TCP socket, e.g. `{ host = "::1", port = 57291 }` to listen on IPv6 as well.
+    Tcp(Endpoint),
+
+    /// A Unix domain socket, e.g. `{ unix_socket = "/run/miden/store.sock" }`, for the components
+    /// running on the same host. Only supported on Unix.
+    Unix { unix_socket: PathBuf },
+}
+
+impl Display for ListenAddress {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            ListenAddress::Tcp(endpoint) => endpoint.fmt(f),
+            ListenAddress::Unix { unix_socket } => {
+                f.write_fmt(format_args!("unix://{}", unix_socket.display()))
+            },
+        }
+    }
+}
+
+documented_config! {
+    /// Tuning of the gRPC server of a component, every option left to `0` keeps the default of
+    /// tonic
+    ///
+    /// The defaults suit a handful of clients, a server facing thousands of wallet connections
+    /// should at least bound the number of connections, and enable the keepalives so the
+    /// connections of the clients which went away are closed.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct GrpcServerConfig {
+        /// Maximum number of open connections, new connections wait in the listen backlog until
+        /// an open one is closed. `0` disables the limit.
+        pub max_connections: usize,
+        /// Maximum number of concurrent HTTP/2 streams, i.e. requests, a connection may open. `0`
+        /// keeps the default of tonic.
+        pub max_concurrent_streams: u32,
+        /// Maximum number of requests of a connection served concurrently, the others wait. `0`
+        /// disables the limit.
+        pub concurrency_limit_per_connection: usize,
+        /// Interval in seconds of the TCP keepalive probes, `0` disables them.
+        pub tcp_keepalive_s: u64,
+        /// Whether Nagle's algorithm is disabled on the accepted connections.
+        pub tcp_nodelay: bool,
+        /// Interval in seconds of the HTTP/2 pings sent to idle connections, `0` disables them.
+        pub http2_keepalive_interval_s: u64,
+        /// Time in seconds after which a connection whose ping wasn't acknowledged is closed. `0`
+        /// keeps the default of tonic.
+        pub http2_keepalive_timeout_s: u64,
+        /// Initial HTTP/2 flow control window of a stream, in bytes. `0` keeps the default of
+        /// tonic.
+        pub initial_stream_window_size: u32,
+        /// Initial HTTP/2 flow control window of a connection, in bytes. `0` keeps the default of
+        /// tonic.
+        pub initial_connection_window_size: u32,
+    }
+}
+
+impl GrpcServerConfig {
+    /// Returns a server builder applying the HTTP/2 options.
+    ///
+    /// The TCP options are applied by the listener returned by [GrpcServerConfig::incoming], which
+    /// must be served with `serve_with_incoming`.
+    pub fn server_builder(&self) -> Server {
+        let mut builder = Server::builder()
+            .max_concurrent_streams(non_zero(self.max_concurrent_streams))
+            .http2_keepalive_interval(
+                non_zero(self.http2_keepalive_interval_s).map(Duration::from_secs),
+            )
+            .http2_keepalive_timeout(
+                non_zero(self.http2_keepalive_timeout_s).map(Duration::from_secs),
+            )
+            .initial_stream_window_size(non_zero(self.initial_stream_window_size))
+            .initial_connection_window_size(non_zero(self.initial_connection_window_size));
+        if self.concurrency_limit_per_connection != 0 {
+            builder =
+                builder.concurrency_limit_per_connection(self.concurrency_limit_per_connection);
+        }
+        builder
+    }
+
+    /// Binds the listening sockets at `endpoint` and at the `listen` addresses, accepting at most
+    /// `max_connections` connections at a time over all of them.
+    pub fn incoming(
+        &self,
+        endpoint: &Endpoint,
+        listen: &[ListenAddress],
+    ) -> io::Result<
+        impl Stream<
+                Item = io::Result<
+                    impl AsyncRead
+                        + AsyncWrite
+                        + Connected<ConnectInfo = TcpConnectInfo>
+                        + Unpin
+                        + Send
+                        + 'static,
+                >,
+            > + Send
+            + 'static,
+    > {
+        let mut listeners =
+            vec![self.tcp_incoming(endpoint)?.map(|io| io.map(Connection::Tcp)).boxed()];
+        for address in listen {
+            let listener = match address {
+                ListenAddress::Tcp(other_endpoint) => {
+                    self.tcp_incoming(other_endpoint)?.map(|io| io.map(Connection::Tcp)).boxed()
+                },
+                #[cfg(unix)]
+                ListenAddress::Unix { unix_socket } => {
+                    unix_incoming(unix_socket)?.map(|io| io.map(Connection::Unix)).boxed()
+                },
+                #[cfg(not(unix))]
+                ListenAddress::Unix { .. } => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        "Unix domain sockets are only supported on Unix",
+                    ))
+                },
+            };
+            listeners.push(listener);
+        }
+
+        Ok(limit_connections(stream::select_all(listeners), self.max_connections))
+    }
+
+    /// Binds a TCP socket at the first address `endpoint` resolves to.
+    fn tcp_incoming(
+        &self,
+        endpoint: &Endpoint,
+    ) -> io::Result<TcpIncoming> {
+        let addr: SocketAddr = endpoint.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("Couldn't resolve {endpoint}"))
+        })?;
+        let tcp_keepalive = non_zero(self.tcp_keepalive_s).map(Duration::from_secs);
+
+        TcpIncoming::new(addr, self.tcp_nodelay, tcp_keepalive)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+    }
+}
+
+impl Display for GrpcServerConfig {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{{ max_connections: {}, max_concurrent_streams: {}, concurrency_limit_per_connection: {}, tcp_keepalive_s: {}, tcp_nodelay: {}, http2_keepalive_interval_s: {}, http2_keepalive_timeout_s: {}, initial_stream_window_size: {}, initial_connection_window_size: {} }}",
+            self.max_connections, self.max_concurrent_streams, self.concurrency_limit_per_connection, self.tcp_keepalive_s, self.tcp_nodelay, self.http2_keepalive_interval_s, self.http2_keepalive_timeout_s, self.initial_stream_window_size, self.initial_connection_window_size
+        ))
+    }
+}
+
+/// Maps the `0` of an option to `None`, keeping the default of tonic.
+fn non_zero<T: Default + PartialEq>(value: T) -> Option<T> {
+    (value != T::default()).then_some(value)
+}
+
 /// Loads the user configuration.
 ///
 /// This function will look for the configuration file at the provided path. If the path is
@@ -46,3 +230,16 @@ impl Display for Endpoint {
 pub fn load_config(config_file: &Path) -> Figment {
     Figment::from(Toml::file(config_file))
 }
+
+/// Parses the hex encoded hash of the genesis block set in a configuration, with or without the
+/// `0x` prefix, `None` if it isn't set.
+pub fn parse_genesis_hash(genesis_hash: Option<&str>) -> anyhow::Result<Option<RpoDigest>> {
+    let Some(genesis_hash) = genesis_hash else {
+        return Ok(None);
+    };
+    hex::decode(genesis_hash.strip_prefix("0x").unwrap_or(genesis_hash))
+        .ok()
+        .and_then(|bytes| RpoDigest::read_from_bytes(&bytes).ok())
+        .map(Some)
+        .ok_or_else(|| anyhow::anyhow!("Invalid genesis hash {genesis_hash}"))
+}
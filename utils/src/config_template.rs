@@ -0,0 +1,409 @@
+//! Config files generated from a default config, with every option commented with its
+//! documentation, e.g. by `miden-node config init`.
+//!
+//! The options are enumerated by serializing the config, so a field added to a config struct
+//! appears in the generated files without further changes. Its documentation is looked up by the
+//! path of the option, e.g. `block_producer.batching.batch_size`, among the [OptionDoc]s of the
+//! config, which [documented_config](crate::documented_config) generates from the doc comments of
+//! the fields. The options missing from the serialized config, e.g. the optional ones which are
+//! unset, are written commented out, along with their example value if they have one.
+use std::fmt::Write;
+
+use serde::{ser::Error as _, Serialize};
+use toml::{Table, Value};
+
+/// Width of the generated files, beyond which the documentation of an option is wrapped.
+const MAX_LINE_WIDTH: usize = 100;
+
+// OPTION DOC
+// ================================================================================================
+
+/// Documentation of an option of a config, or of one of its tables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OptionDoc {
+    /// Path of the option within its config, e.g. `batch_size`
+    pub path: &'static str,
+
+    /// Documentation of the option, wrapped when written
+    pub doc: &'static str,
+
+    /// Value of the option written commented out if the option is unset by default
+    pub example: Option<&'static str>,
+}
+
+impl OptionDoc {
+    pub const fn new(
+        path: &'static str,
+        doc: &'static str,
+    ) -> Self {
+        Self {
+            path,
+            doc,
+            example: None,
+        }
+    }
+
+    pub const fn with_example(
+        self,
+        example: &'static str,
+    ) -> Self {
+        Self {
+            example: Some(example),
+            ..self
+        }
+    }
+}
+
+/// A config whose options are documented, see the [module](self) documentation.
+pub trait DocumentedConfig {
+    /// Adds the documentation of the options of the config, found at `prefix`, to `template`.
+    fn document(
+        template: ConfigTemplate,
+        prefix: &str,
+    ) -> ConfigTemplate;
+}
+
+// DOCUMENTED CONFIG
+// ================================================================================================
+
+/// Declares a config struct, implementing [DocumentedConfig] with the doc comments of its fields
+/// as the documentation of its options.
+///
+/// The example value of an optional field follows its type, e.g.
+/// `pub url: Option<String> => "\"http://localhost:8080\"",`, and the fields holding a nested
+/// config, itself documented, are marked with `=> table`.
+#[macro_export]
+macro_rules! documented_config {
+    (
+        $(#[$struct_attr:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$($field_attr:tt)*])*
+                $field_vis:vis $field:ident: $ty:ty $(=> $marker:tt)?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$struct_attr])*
+        $vis struct $name {
+            $(
+                $(#[$($field_attr)*])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl $crate::config_template::DocumentedConfig for $name {
+            #[allow(clippy::let_and_return)]
+            fn document(
+                template: $crate::config_template::ConfigTemplate,
+                prefix: &str,
+            ) -> $crate::config_template::ConfigTemplate {
+                let template = template.with_docs(
+                    prefix,
+                    &[$(
+                        $crate::__option_doc!(
+                            stringify!($field), [] $(#[$($field_attr)*])* $(=> $marker)?
+                        ),
+                    )*],
+                );
+                $(
+                    $crate::__document_table!(template, prefix, $field: $ty $(=> $marker)?);
+                )*
+                template
+            }
+        }
+    };
+}
+
+/// Returns the [OptionDoc] of a field of [documented_config](crate::documented_config), joining
+/// the lines of its doc comments and skipping its other attributes.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __option_doc {
+    ($path:expr, [$($doc:literal)*] #[doc = $line:literal] $($rest:tt)*) => {
+        $crate::__option_doc!($path, [$($doc)* $line] $($rest)*)
+    };
+    ($path:expr, [$($doc:literal)*] #[$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__option_doc!($path, [$($doc)*] $($rest)*)
+    };
+    ($path:expr, [$($doc:literal)*] => table) => {
+        $crate::__option_doc!($path, [$($doc)*])
+    };
+    ($path:expr, [$($doc:literal)*] => $example:literal) => {
+        $crate::__option_doc!($path, [$($doc)*]).with_example($example)
+    };
+    ($path:expr, [$($doc:literal)*]) => {
+        $crate::config_template::OptionDoc::new($path, concat!($($doc, " ",)*))
+    };
+}
+
+/// Documents the nested config of a field of [documented_config](crate::documented_config) marked
+/// with `=> table`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __document_table {
+    ($template:ident, $prefix:ident, $field:ident: $ty:ty => table) => {
+        let $template = $template
+            .with_config::<$ty>(&$crate::config_template::option_path($prefix, stringify!($field)));
+    };
+    ($template:ident, $prefix:ident, $field:ident: $ty:ty $(=> $example:literal)?) => {};
+}
+
+// CONFIG TEMPLATE
+// ================================================================================================
+
+/// Generator of the config files, see the [module](self) documentation.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigTemplate {
+    /// Comment written at the top of the generated files
+    header: String,
+
+    /// Documentation of the options by their full path, in the order the options are written
+    docs: Vec<(String, OptionDoc)>,
+}
+
+impl ConfigTemplate {
+    pub fn new(header: &str) -> Self {
+        Self {
+            header: header.to_string(),
+            docs: Vec::new(),
+        }
+    }
+
+    /// Documents the options `docs`, whose paths are relative to `prefix`.
+    ///
+    /// The options whose documentation is empty, e.g. the fields without doc comments, are left
+    /// undocumented.
+    pub fn with_docs(
+        mut self,
+        prefix: &str,
+        docs: &[OptionDoc],
+    ) -> Self {
+        self.docs.extend(
+            docs.iter()
+                .filter(|doc| !doc.doc.trim().is_empty())
+                .map(|doc| (option_path(prefix, doc.path), *doc)),
+        );
+        self
+    }
+
+    /// Documents the options of the config `T`, found at `prefix`.
+    pub fn with_config<T: DocumentedConfig>(
+        self,
+        prefix: &str,
+    ) -> Self {
+        T::document(self, prefix)
+    }
+
+    /// Returns the TOML file setting every option of `config`, each one preceded by its
+    /// documentation.
+    ///
+    /// The options of a table are written in the order they are documented, followed by the
+    /// undocumented ones, and then by the nested tables.
+    pub fn render<T: Serialize>(
+        &self,
+        config: &T,
+    ) -> Result<String, toml::ser::Error> {
+        let table = config_table(config)?;
+
+        let mut file = String::new();
+        for line in self.header.lines() {
+            writeln!(file, "{}", format!("# {line}").trim_end())
+                .expect("writing to a string can't fail");
+        }
+        self.write_table(&mut file, "", &table);
+
+        Ok(file)
+    }
+
+    /// Returns the paths of the options of `config`, and of its tables, which are not documented.
+    pub fn undocumented_options<T: Serialize>(
+        &self,
+        config: &T,
+    ) -> Result<Vec<String>, toml::ser::Error> {
+        let mut options = Vec::new();
+        collect_options(&mut options, "", &config_table(config)?);
+
+        Ok(options.into_iter().filter(|path| self.doc(path).is_none()).collect())
+    }
+
+    fn doc(
+        &self,
+        path: &str,
+    ) -> Option<&OptionDoc> {
+        self.docs.iter().find(|(doc_path, _)| doc_path == path).map(|(_, doc)| doc)
+    }
+
+    /// Writes the options of the table at `path`, then its nested tables.
+    fn write_table(
+        &self,
+        file: &mut String,
+        path: &str,
+        table: &Table,
+    ) {
+        let mut keys: Vec<&str> = self
+            .docs
+            .iter()
+            .filter_map(|(doc_path, _)| {
+                let (parent, key) = doc_path.rsplit_once('.').unwrap_or(("", doc_path));
+                (parent == path).then_some(key)
+            })
+            .collect();
+        keys.extend(
+            table
+                .keys()
+                .map(String::as_str)
+                .filter(|key| self.doc(&option_path(path, key)).is_none()),
+        );
+
+        for key in &keys {
+            let option = option_path(path, key);
+            match (table.get(*key), self.doc(&option)) {
+                (Some(Value::Table(_)), _) => continue,
+                (Some(value), _) => {
+                    self.write_doc(file, &option);
+                    writeln!(file, "{key} = {value}").expect("writing to a string can't fail");
+                },
+                (None, Some(doc)) => {
+                    self.write_doc(file, &option);
+                    let line = format!("# {key} = {}", doc.example.unwrap_or_default());
+                    writeln!(file, "{}", line.trim_end()).expect("writing to a string can't fail");
+                },
+                (None, None) => {},
+            }
+        }
+
+        for key in &keys {
+            if let Some(Value::Table(nested)) = table.get(*key) {
+                let option = option_path(path, key);
+                file.push('\n');
+                self.write_doc(file, &option);
+                writeln!(file, "[{option}]").expect("writing to a string can't fail");
+                self.write_table(file, &option, nested);
+            }
+        }
+    }
+
+    /// Writes the documentation of the option at `path`, wrapped to the width of the file.
+    fn write_doc(
+        &self,
+        file: &mut String,
+        path: &str,
+    ) {
+        let Some(doc) = self.doc(path) else {
+            return;
+        };
+
+        let mut line = String::from("#");
+        for word in doc.doc.split_whitespace() {
+            if line.len() > 1 && line.len() + 1 + word.len() > MAX_LINE_WIDTH {
+                file.push_str(&line);
+                file.push('\n');
+                line = String::from("#");
+            }
+            line.push(' ');
+            line.push_str(word);
+        }
+        file.push_str(&line);
+        file.push('\n');
+    }
+}
+
+// HELPERS
+// ================================================================================================
+
+/// Returns the path of the option `key` of the table at `prefix`.
+pub fn option_path(
+    prefix: &str,
+    key: &str,
+) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+fn config_table<T: Serialize>(config: &T) -> Result<Table, toml::ser::Error> {
+    match Value::try_from(config)? {
+        Value::Table(table) => Ok(table),
+        _ => Err(toml::ser::Error::custom("the config is not a table")),
+    }
+}
+
+/// Appends the paths of the options of `table`, and of its nested tables, to `options`.
+fn collect_options(
+    options: &mut Vec<String>,
+    prefix: &str,
+    table: &Table,
+) {
+    for (key, value) in table {
+        let path = option_path(prefix, key);
+        if let Value::Table(nested) = value {
+            collect_options(options, &path, nested);
+        }
+        options.push(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::ConfigTemplate;
+    use crate::documented_config;
+
+    documented_config! {
+        #[derive(Serialize)]
+        struct Config {
+            /// Number of retries.
+            retries: u32,
+            /// Name of the service, as reported in the
+            /// logs.
+            name: String,
+            /// URL of the service.
+            url: Option<String> => "\"http://localhost:8080\"",
+            /// Time in seconds after which a request fails, never if missing.
+            timeout_s: Option<u64>,
+            /// Limits of the service.
+            limits: Limits => table,
+        }
+    }
+
+    documented_config! {
+        #[derive(Serialize)]
+        struct Limits {
+            max_size_mb: u64,
+        }
+    }
+
+    #[test]
+    fn test_config_template() {
+        let config = Config {
+            retries: 3,
+            name: "service".to_string(),
+            url: None,
+            timeout_s: None,
+            limits: Limits { max_size_mb: 512 },
+        };
+        let template = ConfigTemplate::new("Config of the service").with_config::<Config>("");
+
+        assert_eq!(
+            template.render(&config).unwrap(),
+            "# Config of the service
+# Number of retries.
+retries = 3
+# Name of the service, as reported in the logs.
+name = \"service\"
+# URL of the service.
+# url = \"http://localhost:8080\"
+# Time in seconds after which a request fails, never if missing.
+# timeout_s =
+
+# Limits of the service.
+[limits]
+max_size_mb = 512
+"
+        );
+        assert_eq!(template.undocumented_options(&config).unwrap(), vec!["limits.max_size_mb"]);
+    }
+}
@@ -7,7 +7,8 @@ use miden_crypto::{
 };
 use miden_objects::{
     notes::{NoteEnvelope, Nullifier},
-    transaction::{InputNotes, OutputNotes},
+    transaction::{InputNotes, OutputNotes, ProvenTransaction},
+    BlockHeader,
 };
 
 pub fn format_account_id(id: u64) -> String {
@@ -34,6 +35,37 @@ pub fn format_output_notes(notes: &OutputNotes<NoteEnvelope>) -> String {
     }))
 }
 
+pub fn format_proven_transaction(tx: &ProvenTransaction) -> String {
+    format!(
+        "{{ tx_id: {}, account_id: {}, initial_account_hash: {}, final_account_hash: {}, input_notes: {}, output_notes: {}, tx_script_root: {}, block_ref: {} }}",
+        tx.id().to_hex(),
+        tx.account_id().to_hex(),
+        tx.initial_account_hash(),
+        tx.final_account_hash(),
+        format_input_notes(tx.input_notes()),
+        format_output_notes(tx.output_notes()),
+        format_opt(tx.tx_script_root().as_ref()),
+        tx.block_ref(),
+    )
+}
+
+pub fn format_block_header(header: &BlockHeader) -> String {
+    format!(
+        "{{ block_num: {}, hash: {}, prev_hash: {}, chain_root: {}, account_root: {}, nullifier_root: {}, note_root: {}, batch_root: {}, proof_hash: {}, version: {}, timestamp: {} }}",
+        header.block_num(),
+        header.hash(),
+        header.prev_hash(),
+        header.chain_root(),
+        header.account_root(),
+        header.nullifier_root(),
+        header.note_root(),
+        header.batch_root(),
+        header.proof_hash(),
+        header.version(),
+        header.timestamp(),
+    )
+}
+
 pub fn format_map<'a, K: Display + 'a, V: Display + 'a>(
     map: impl IntoIterator<Item = (&'a K, &'a V)>
 ) -> String {
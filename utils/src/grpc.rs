@@ -0,0 +1,44 @@
+//! Connection of the components to the gRPC servers of the other components.
+use tonic::transport::{Channel, Endpoint, Error};
+
+/// Scheme of the URLs of the gRPC servers listening on a Unix domain socket, followed by the path
+/// of the socket, e.g. `unix:///run/miden/store.sock`.
+pub const UNIX_SOCKET_SCHEME: &str = "unix://";
+
+/// Connects to the gRPC server at `url`, either `http://<host>[:<port>]`, or the path of a Unix
+/// domain socket prefixed with [UNIX_SOCKET_SCHEME].
+pub async fn connect(url: &str) -> Result<Channel, Error> {
+    #[cfg(unix)]
+    if let Some(path) = url.strip_prefix(UNIX_SOCKET_SCHEME) {
+        use tokio::net::UnixStream;
+        use tonic::transport::Uri;
+        use tower::service_fn;
+
+        let path = path.to_string();
+        // the URI is required by tonic but unused, the connector connects to the socket
+        return Endpoint::from_static("http://[::]:50051")
+            .connect_with_connector(service_fn(move |_: Uri| UnixStream::connect(path.clone())))
+            .await;
+    }
+
+    Endpoint::new(url.to_string())?.connect().await
+}
+
+/// Returns a channel to the gRPC server at `url`, see [connect], which connects on its first
+/// request rather than right away, so the server may be unreachable when the channel is created.
+pub fn connect_lazy(url: &str) -> Result<Channel, Error> {
+    #[cfg(unix)]
+    if let Some(path) = url.strip_prefix(UNIX_SOCKET_SCHEME) {
+        use tokio::net::UnixStream;
+        use tonic::transport::Uri;
+        use tower::service_fn;
+
+        let path = path.to_string();
+        // the URI is required by tonic but unused, the connector connects to the socket
+        return Ok(Endpoint::from_static("http://[::]:50051").connect_with_connector_lazy(
+            service_fn(move |_: Uri| UnixStream::connect(path.clone())),
+        ));
+    }
+
+    Ok(Endpoint::new(url.to_string())?.connect_lazy())
+}
@@ -1,3 +1,8 @@
 pub mod config;
+pub mod config_template;
 pub mod formatting;
+pub mod grpc;
+pub mod listener;
 pub mod logging;
+pub mod operator_auth;
+pub mod tag_policy;
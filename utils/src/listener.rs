@@ -0,0 +1,347 @@
+//! Listening sockets and connection limit of the gRPC servers.
+//!
+//! A server listens on its endpoint, and on any number of additional TCP sockets or Unix domain
+//! sockets, the connections of all of them being served alike, see [Connection].
+//!
+//! Every open connection holds buffers and a file descriptor of the server, whether or not it
+//! sends requests. Beyond the maximum number of connections of a server, no new connection is
+//! accepted until an open one is closed: the new connections wait in the listen backlog of the
+//! socket, and are eventually refused by the operating system once it is full.
+#[cfg(unix)]
+use std::path::Path;
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures::{stream, Stream, StreamExt};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    sync::{OwnedSemaphorePermit, Semaphore},
+};
+use tonic::transport::server::{Connected, TcpConnectInfo};
+#[cfg(unix)]
+use tracing::warn;
+
+/// Pause after a failure to accept a connection, e.g. when the server is out of file descriptors,
+/// so the listener doesn't spin until a connection is closed.
+#[cfg(unix)]
+const ACCEPT_ERROR_PAUSE: std::time::Duration = std::time::Duration::from_millis(100);
+
+// CONNECTION
+// ================================================================================================
+
+/// A connection accepted on a TCP socket, `T` being the connections of tonic's `TcpIncoming`, or
+/// on a Unix domain socket.
+///
+/// The connections of a Unix domain socket have neither a local nor a remote address, so
+/// `Request::remote_addr` returns `None` for their requests.
+#[derive(Debug)]
+pub enum Connection<T> {
+    Tcp(T),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl<T: Connected<ConnectInfo = TcpConnectInfo>> Connected for Connection<T> {
+    type ConnectInfo = TcpConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        match self {
+            Connection::Tcp(io) => io.connect_info(),
+            #[cfg(unix)]
+            Connection::Unix(_) => TcpConnectInfo {
+                local_addr: None,
+                remote_addr: None,
+            },
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Connection<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Tcp(io) => Pin::new(io).poll_read(cx, buf),
+            #[cfg(unix)]
+            Connection::Unix(io) => Pin::new(io).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Connection<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Connection::Tcp(io) => Pin::new(io).poll_write(cx, buf),
+            #[cfg(unix)]
+            Connection::Unix(io) => Pin::new(io).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Connection::Tcp(io) => Pin::new(io).poll_write_vectored(cx, bufs),
+            #[cfg(unix)]
+            Connection::Unix(io) => Pin::new(io).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            Connection::Tcp(io) => io.is_write_vectored(),
+            #[cfg(unix)]
+            Connection::Unix(io) => io.is_write_vectored(),
+        }
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Tcp(io) => Pin::new(io).poll_flush(cx),
+            #[cfg(unix)]
+            Connection::Unix(io) => Pin::new(io).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Tcp(io) => Pin::new(io).poll_shutdown(cx),
+            #[cfg(unix)]
+            Connection::Unix(io) => Pin::new(io).poll_shutdown(cx),
+        }
+    }
+}
+
+// UNIX DOMAIN SOCKET
+// ================================================================================================
+
+/// Binds a Unix domain socket at `path`, and returns the stream of the accepted connections.
+///
+/// A socket left at `path` by a previous run is replaced, while a socket still accepting
+/// connections, e.g. the one of another instance, or any other file at `path` is an error. The
+/// failures to accept a connection are logged, and don't end the stream.
+#[cfg(unix)]
+pub fn unix_incoming(
+    path: &Path
+) -> io::Result<impl Stream<Item = io::Result<UnixStream>> + Send + 'static> {
+    use std::{fs, os::unix::fs::FileTypeExt};
+
+    if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        remove_stale_socket(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    let path = path.to_path_buf();
+
+    Ok(stream::unfold(listener, move |listener| {
+        let path = path.clone();
+        async move {
+            loop {
+                match listener.accept().await {
+                    Ok((io, _)) => return Some((Ok(io), listener)),
+                    Err(err) => {
+                        warn!(path = %path.display(), %err, "Failed to accept a connection");
+                        tokio::time::sleep(ACCEPT_ERROR_PAUSE).await;
+                    },
+                }
+            }
+        }
+    }))
+}
+
+/// Removes the socket at `path` if no process accepts connections on it anymore.
+#[cfg(unix)]
+fn remove_stale_socket(path: &Path) -> io::Result<()> {
+    use std::{fs, os::unix::net::UnixStream as StdUnixStream};
+
+    match StdUnixStream::connect(path) {
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("{} is the socket of a running server", path.display()),
+        )),
+        Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => fs::remove_file(path),
+        Err(err) => Err(err),
+    }
+}
+
+// LIMITED CONNECTION
+// ================================================================================================
+
+/// An accepted connection, counted against the maximum number of connections until it is dropped.
+#[derive(Debug)]
+pub struct LimitedConnection<IO> {
+    io: IO,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl<IO: Connected> Connected for LimitedConnection<IO> {
+    type ConnectInfo = IO::ConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.io.connect_info()
+    }
+}
+
+impl<IO: AsyncRead + Unpin> AsyncRead for LimitedConnection<IO> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl<IO: AsyncWrite + Unpin> AsyncWrite for LimitedConnection<IO> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
+
+// CONNECTION LIMIT
+// ================================================================================================
+
+/// Accepts the connections of `incoming`, at most `max_connections` of them being open at a time.
+///
+/// A `max_connections` of `0` disables the limit.
+pub fn limit_connections<S, IO>(
+    incoming: S,
+    max_connections: usize,
+) -> impl Stream<Item = io::Result<LimitedConnection<IO>>> + Send + 'static
+where
+    S: Stream<Item = io::Result<IO>> + Unpin + Send + 'static,
+    IO: Send + 'static,
+{
+    let permits = (max_connections != 0).then(|| Arc::new(Semaphore::new(max_connections)));
+
+    stream::unfold((incoming, permits), |(mut incoming, permits)| async move {
+        // the next connection is only accepted once a permit is available, the connections waiting
+        // for it stay in the listen backlog
+        let permit = match &permits {
+            Some(permits) => {
+                Some(permits.clone().acquire_owned().await.expect("semaphore is never closed"))
+            },
+            None => None,
+        };
+        let connection = incoming.next().await?;
+        let connection = connection.map(|io| LimitedConnection {
+            io,
+            _permit: permit,
+        });
+
+        Some((connection, (incoming, permits)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use futures::{stream, FutureExt, StreamExt};
+
+    use super::limit_connections;
+
+    #[tokio::test]
+    async fn test_limit_connections() {
+        let incoming = stream::iter((0..3).map(io::Result::Ok));
+        let mut connections = Box::pin(limit_connections(incoming, 2));
+
+        let first = connections.next().await.unwrap().unwrap();
+        let second = connections.next().await.unwrap().unwrap();
+        assert_eq!((first.io, second.io), (0, 1));
+
+        // a third connection is only accepted once one of the open connections is closed
+        assert!(connections.next().now_or_never().is_none());
+        drop(first);
+        let third = connections.next().await.unwrap().unwrap();
+        assert_eq!(third.io, 2);
+
+        // the listener is exhausted
+        drop(second);
+        assert!(connections.next().await.is_none());
+
+        // without limit, every connection is accepted right away
+        let incoming = stream::iter((0..3).map(io::Result::Ok));
+        let connections: Vec<_> = limit_connections(incoming, 0).collect().await;
+        assert_eq!(connections.len(), 3);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_incoming() {
+        use std::{env, fs, os::unix::net::UnixListener, process};
+
+        use tokio::net::UnixStream;
+
+        use super::unix_incoming;
+
+        let path = env::temp_dir().join(format!("miden-listener-{}.sock", process::id()));
+
+        // the socket left by a previous run is replaced
+        drop(UnixListener::bind(&path).unwrap());
+        let mut incoming = Box::pin(unix_incoming(&path).unwrap());
+
+        let _client = UnixStream::connect(&path).await.unwrap();
+        assert!(incoming.next().await.unwrap().is_ok());
+
+        // the socket of a running server is left alone
+        let err = unix_incoming(&path).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        drop(incoming);
+
+        // any other file is left alone
+        fs::remove_file(&path).unwrap();
+        fs::write(&path, b"not a socket").unwrap();
+        assert!(unix_incoming(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
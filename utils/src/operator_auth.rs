@@ -0,0 +1,427 @@
+//! Authentication of the node operators.
+//!
+//! Destructive admin operations, e.g. rolling back, pruning or restoring the chain, must be signed
+//! by one of the operator keys listed in the component's configuration, so that network access to
+//! a component alone isn't enough to destroy its chain.
+//!
+//! A signed request carries as gRPC metadata the operation it authorizes, the time it was signed
+//! at, a nonce, the hash of the request, the public key of the operator, and a signature of them
+//! by that key. The [OperatorAuthenticator] verifies the signature of every signed request, and
+//! rejects the nonces it already saw within [MAX_SIGNATURE_AGE], so that a signed request can't be
+//! replayed. The admin handlers call [require_operator] to reject the requests which were not
+//! signed for their operation, or whose hash isn't the one signed.
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use miden_crypto::{
+    dsa::rpo_falcon512::{KeyPair, PublicKey, Signature},
+    hash::rpo::{Rpo256, RpoDigest},
+    utils::{Deserializable, Serializable},
+    Word,
+};
+use prost::Message;
+use thiserror::Error;
+use tonic::{metadata::MetadataMap, service::Interceptor, Request, Status};
+
+/// The gRPC metadata key under which the public key of the operator is given.
+pub const OPERATOR_KEY_METADATA_KEY: &str = "miden-operator-key";
+
+/// The gRPC metadata key under which the authorized operation is given.
+pub const OPERATOR_OPERATION_METADATA_KEY: &str = "miden-operator-operation";
+
+/// The gRPC metadata key under which the signing time, in seconds since the UNIX epoch, is given.
+pub const OPERATOR_TIMESTAMP_METADATA_KEY: &str = "miden-operator-timestamp";
+
+/// The gRPC metadata key under which the nonce of the request, unique to every signed request, is
+/// given.
+pub const OPERATOR_NONCE_METADATA_KEY: &str = "miden-operator-nonce";
+
+/// The gRPC metadata key under which the hex encoded hash of the signed request is given.
+pub const OPERATOR_REQUEST_HASH_METADATA_KEY: &str = "miden-operator-request-hash";
+
+/// The gRPC metadata key under which the signature of the operator is given.
+pub const OPERATOR_SIGNATURE_METADATA_KEY: &str = "miden-operator-signature";
+
+/// Maximum difference between the signing time of a request and the time it is verified at.
+pub const MAX_SIGNATURE_AGE: Duration = Duration::from_secs(60);
+
+// OPERATOR KEYS
+// ================================================================================================
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum OperatorKeyError {
+    #[error("operator key {0} is not valid hex")]
+    InvalidHex(String),
+    #[error("operator key {0} is not a valid public key")]
+    InvalidKey(String),
+}
+
+/// The set of operator public keys allowed to sign admin operations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OperatorKeys(BTreeSet<RpoDigest>);
+
+impl OperatorKeys {
+    /// Parses the hex encoded public keys of the operators, as listed in the configuration.
+    pub fn from_hex<S: AsRef<str>>(keys: &[S]) -> Result<Self, OperatorKeyError> {
+        keys.iter()
+            .map(|key| parse_key(key.as_ref()))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Returns `true` if no operator key is configured, in which case no admin operation can be
+    /// authorized.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns `true` if `key` is one of the operator keys.
+    pub fn contains(
+        &self,
+        key: &RpoDigest,
+    ) -> bool {
+        self.0.contains(key)
+    }
+}
+
+fn parse_key(key: &str) -> Result<RpoDigest, OperatorKeyError> {
+    let bytes = hex::decode(key.strip_prefix("0x").unwrap_or(key))
+        .map_err(|_| OperatorKeyError::InvalidHex(key.to_string()))?;
+    RpoDigest::read_from_bytes(&bytes).map_err(|_| OperatorKeyError::InvalidKey(key.to_string()))
+}
+
+/// Returns the hex encoding of `key`, as listed in the configuration.
+pub fn format_key(key: PublicKey) -> String {
+    format!("0x{}", hex::encode(RpoDigest::from(Word::from(key)).to_bytes()))
+}
+
+// SIGNING
+// ================================================================================================
+
+/// Number of requests signed by this process, making the nonces of the requests it signs within
+/// the same nanosecond distinct.
+static NUM_SIGNED_REQUESTS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the message signed by an operator to authorize `operation` on chain `chain_id` at
+/// `timestamp`, for the request whose hash is `request_hash`.
+pub fn operation_message(
+    chain_id: u32,
+    operation: &str,
+    timestamp: u64,
+    nonce: u64,
+    request_hash: RpoDigest,
+) -> Word {
+    let mut bytes = Vec::with_capacity(operation.len() + 52);
+    bytes.extend(chain_id.to_le_bytes());
+    bytes.extend(timestamp.to_le_bytes());
+    bytes.extend(nonce.to_le_bytes());
+    bytes.extend(request_hash.as_bytes());
+    bytes.extend(operation.as_bytes());
+    Rpo256::hash(&bytes).into()
+}
+
+/// Returns the hash of `request`, as signed by an operator.
+pub fn request_hash<T: Message>(request: &T) -> RpoDigest {
+    Rpo256::hash(&request.encode_to_vec())
+}
+
+/// Signs `request` with `keypair`, authorizing `operation` on chain `chain_id`.
+///
+/// The signature covers the content of the request, which must not be changed once signed, and a
+/// new nonce: a request is signed anew every time it is sent.
+pub fn sign_request<T: Message>(
+    request: &mut Request<T>,
+    keypair: &KeyPair,
+    chain_id: u32,
+    operation: &str,
+) -> anyhow::Result<()> {
+    let timestamp = now();
+    let nonce = new_nonce();
+    let request_hash = request_hash(request.get_ref());
+    let signature =
+        keypair.sign(operation_message(chain_id, operation, timestamp, nonce, request_hash))?;
+
+    let metadata = request.metadata_mut();
+    metadata.insert(OPERATOR_KEY_METADATA_KEY, format_key(keypair.public_key()).parse()?);
+    metadata.insert(OPERATOR_OPERATION_METADATA_KEY, operation.parse()?);
+    metadata.insert(OPERATOR_TIMESTAMP_METADATA_KEY, timestamp.into());
+    metadata.insert(OPERATOR_NONCE_METADATA_KEY, nonce.into());
+    metadata.insert(
+        OPERATOR_REQUEST_HASH_METADATA_KEY,
+        hex::encode(request_hash.as_bytes()).parse()?,
+    );
+    metadata.insert(OPERATOR_SIGNATURE_METADATA_KEY, hex::encode(signature.to_bytes()).parse()?);
+
+    Ok(())
+}
+
+/// Returns a nonce distinct from the ones of the other requests signed by the operator, derived
+/// from the current time and the number of requests signed by this process.
+fn new_nonce() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("current time is after the UNIX epoch")
+        .as_nanos() as u64;
+    nanos.wrapping_add(NUM_SIGNED_REQUESTS.fetch_add(1, Ordering::Relaxed))
+}
+
+// VERIFICATION
+// ================================================================================================
+
+/// An admin operation authorized by an operator, attached to the request by the
+/// [OperatorAuthenticator].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperatorAuthorization {
+    /// Public key of the operator who signed the request.
+    pub key: RpoDigest,
+    /// The authorized operation.
+    pub operation: String,
+    /// Hash of the signed request, see [request_hash].
+    pub request_hash: RpoDigest,
+}
+
+/// Server side interceptor which verifies the signature of the requests signed by an operator.
+///
+/// Requests which are not signed are accepted, it's up to the admin handlers to reject them with
+/// [require_operator]. Requests with an invalid signature, signed by an unknown key, or whose
+/// nonce was already seen, are rejected.
+#[derive(Debug, Clone)]
+pub struct OperatorAuthenticator {
+    chain_id: u32,
+    keys: Arc<OperatorKeys>,
+    /// The `(timestamp, key, nonce)` of the requests verified within [MAX_SIGNATURE_AGE], shared
+    /// by the clones of the authenticator
+    seen_nonces: Arc<Mutex<BTreeSet<(u64, RpoDigest, u64)>>>,
+}
+
+impl OperatorAuthenticator {
+    pub fn new(
+        chain_id: u32,
+        keys: OperatorKeys,
+    ) -> Self {
+        Self {
+            chain_id,
+            keys: Arc::new(keys),
+            seen_nonces: Arc::default(),
+        }
+    }
+
+    fn authenticate(
+        &self,
+        metadata: &MetadataMap,
+        now: u64,
+    ) -> Result<Option<OperatorAuthorization>, Status> {
+        let Some(signature) = metadata.get(OPERATOR_SIGNATURE_METADATA_KEY) else {
+            return Ok(None);
+        };
+
+        let read = |key: &str| {
+            metadata
+                .get(key)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| Status::unauthenticated(format!("Missing or malformed {key}")))
+        };
+
+        let key = parse_key(read(OPERATOR_KEY_METADATA_KEY)?)
+            .map_err(|err| Status::unauthenticated(err.to_string()))?;
+        let operation = read(OPERATOR_OPERATION_METADATA_KEY)?;
+        let timestamp: u64 = read(OPERATOR_TIMESTAMP_METADATA_KEY)?
+            .parse()
+            .map_err(|_| Status::unauthenticated("Malformed operator timestamp"))?;
+        let nonce: u64 = read(OPERATOR_NONCE_METADATA_KEY)?
+            .parse()
+            .map_err(|_| Status::unauthenticated("Malformed operator nonce"))?;
+        let request_hash = hex::decode(read(OPERATOR_REQUEST_HASH_METADATA_KEY)?)
+            .ok()
+            .and_then(|bytes| RpoDigest::read_from_bytes(&bytes).ok())
+            .ok_or_else(|| Status::unauthenticated("Malformed operator request hash"))?;
+        let signature = signature
+            .to_str()
+            .ok()
+            .and_then(|signature| hex::decode(signature).ok())
+            .and_then(|bytes| Signature::read_from_bytes(&bytes).ok())
+            .ok_or_else(|| Status::unauthenticated("Malformed operator signature"))?;
+
+        if !self.keys.contains(&key) {
+            return Err(Status::permission_denied("Request signed by an unknown operator key"));
+        }
+        if now.abs_diff(timestamp) > MAX_SIGNATURE_AGE.as_secs() {
+            return Err(Status::unauthenticated("Operator signature expired"));
+        }
+
+        let message = operation_message(self.chain_id, operation, timestamp, nonce, request_hash);
+        if !PublicKey::new(key.into()).verify(message, &signature) {
+            return Err(Status::unauthenticated("Invalid operator signature"));
+        }
+
+        // the nonces of the expired signatures are forgotten, the signatures are rejected anyway
+        let mut seen_nonces = self.seen_nonces.lock().expect("seen nonces lock poisoned");
+        let oldest = now.saturating_sub(MAX_SIGNATURE_AGE.as_secs());
+        *seen_nonces = seen_nonces.split_off(&(oldest, RpoDigest::default(), 0));
+        if !seen_nonces.insert((timestamp, key, nonce)) {
+            return Err(Status::unauthenticated("Operator signature replayed"));
+        }
+
+        Ok(Some(OperatorAuthorization {
+            key,
+            operation: operation.to_string(),
+            request_hash,
+        }))
+    }
+}
+
+impl Interceptor for OperatorAuthenticator {
+    fn call(
+        &mut self,
+        mut request: Request<()>,
+    ) -> Result<Request<()>, Status> {
+        if let Some(authorization) = self.authenticate(request.metadata(), now())? {
+            request.extensions_mut().insert(authorization);
+        }
+        Ok(request)
+    }
+}
+
+/// Verifies `request` was signed by an operator to authorize `operation`, as it was received.
+pub fn require_operator<T: Message>(
+    request: &Request<T>,
+    operation: &str,
+) -> Result<OperatorAuthorization, Status> {
+    match request.extensions().get::<OperatorAuthorization>() {
+        Some(authorization) if authorization.operation != operation => {
+            Err(Status::permission_denied(format!(
+                "Request authorizes {}, not {operation}",
+                authorization.operation
+            )))
+        },
+        Some(authorization) if authorization.request_hash != request_hash(request.get_ref()) => {
+            Err(Status::unauthenticated("Operator signature doesn't cover the request"))
+        },
+        Some(authorization) => Ok(authorization.clone()),
+        None => Err(Status::unauthenticated(format!("{operation} requires an operator signature"))),
+    }
+}
+
+/// Returns the current time, in seconds since the UNIX epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("current time is after the UNIX epoch")
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_crypto::dsa::rpo_falcon512::KeyPair;
+    use tonic::{service::Interceptor, Request};
+
+    use super::{
+        format_key, require_operator, sign_request, OperatorAuthenticator, OperatorKeys,
+        MAX_SIGNATURE_AGE,
+    };
+
+    fn signed_request(
+        keypair: &KeyPair,
+        chain_id: u32,
+        operation: &str,
+    ) -> Request<()> {
+        let mut request = Request::new(());
+        sign_request(&mut request, keypair, chain_id, operation).unwrap();
+        request
+    }
+
+    /// Returns the request as received by a handler, once it passed the interceptor.
+    fn intercepted<T>(
+        authenticator: &mut OperatorAuthenticator,
+        request: &Request<T>,
+        body: T,
+    ) -> Result<Request<T>, tonic::Status> {
+        let mut intercepted = Request::new(());
+        *intercepted.metadata_mut() = request.metadata().clone();
+        let (metadata, extensions, _) = authenticator.call(intercepted)?.into_parts();
+        Ok(Request::from_parts(metadata, extensions, body))
+    }
+
+    #[test]
+    fn test_operator_signature() {
+        let operator = KeyPair::new().unwrap();
+        let keys = OperatorKeys::from_hex(&[format_key(operator.public_key())]).unwrap();
+        let mut authenticator = OperatorAuthenticator::new(1, keys);
+
+        let request = authenticator.call(signed_request(&operator, 1, "Rollback")).unwrap();
+        assert!(require_operator(&request, "Rollback").is_ok());
+        assert!(require_operator(&request, "Prune").is_err());
+
+        // unsigned requests pass the interceptor, but can't be authorized
+        let request = authenticator.call(Request::new(())).unwrap();
+        assert!(require_operator(&request, "Rollback").is_err());
+
+        // signatures of unknown keys, or for another chain, are rejected
+        let stranger = KeyPair::new().unwrap();
+        assert!(authenticator.call(signed_request(&stranger, 1, "Rollback")).is_err());
+        assert!(authenticator.call(signed_request(&operator, 2, "Rollback")).is_err());
+    }
+
+    #[test]
+    fn test_operator_signature_expires() {
+        let operator = KeyPair::new().unwrap();
+        let keys = OperatorKeys::from_hex(&[format_key(operator.public_key())]).unwrap();
+        let authenticator = OperatorAuthenticator::new(1, keys);
+
+        let request = signed_request(&operator, 1, "Rollback");
+        let now = super::now();
+        assert!(authenticator.authenticate(request.metadata(), now).unwrap().is_some());
+        assert!(authenticator
+            .authenticate(request.metadata(), now + MAX_SIGNATURE_AGE.as_secs() + 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_operator_signature_replayed() {
+        let operator = KeyPair::new().unwrap();
+        let keys = OperatorKeys::from_hex(&[format_key(operator.public_key())]).unwrap();
+        let mut authenticator = OperatorAuthenticator::new(1, keys);
+
+        let request = signed_request(&operator, 1, "Rollback");
+        assert!(intercepted(&mut authenticator, &request, ()).is_ok());
+
+        // the clones of the authenticator, e.g. of other connections, share the seen nonces
+        let mut clone = authenticator.clone();
+        assert!(clone.call(request).is_err());
+
+        // a request signed anew is accepted
+        assert!(authenticator.call(signed_request(&operator, 1, "Rollback")).is_ok());
+    }
+
+    #[test]
+    fn test_operator_signature_covers_request() {
+        let operator = KeyPair::new().unwrap();
+        let keys = OperatorKeys::from_hex(&[format_key(operator.public_key())]).unwrap();
+        let mut authenticator = OperatorAuthenticator::new(1, keys);
+
+        let mut request = Request::new("block 10".to_string());
+        sign_request(&mut request, &operator, 1, "Rollback").unwrap();
+
+        let received = intercepted(&mut authenticator, &request, "block 10".to_string()).unwrap();
+        assert!(require_operator(&received, "Rollback").is_ok());
+
+        // the signature can't authorize another request with the same metadata
+        let mut request = Request::new("block 10".to_string());
+        sign_request(&mut request, &operator, 1, "Rollback").unwrap();
+        let tampered = intercepted(&mut authenticator, &request, "block 0".to_string()).unwrap();
+        assert!(require_operator(&tampered, "Rollback").is_err());
+    }
+
+    #[test]
+    fn test_operator_keys_from_hex() {
+        assert!(OperatorKeys::from_hex::<&str>(&[]).unwrap().is_empty());
+        assert!(OperatorKeys::from_hex(&["0xzz"]).is_err());
+        assert!(OperatorKeys::from_hex(&["0x00"]).is_err());
+    }
+}
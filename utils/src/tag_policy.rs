@@ -0,0 +1,184 @@
+//! Validation of the note tags.
+//!
+//! The tags of the notes are split in kinds by the ranges they fall in: tags of notes consumed by
+//! the network, tags matched locally by the clients, and tags of notes targeting an account. The
+//! policy is configured on the store, which indexes the notes by tag, and the block producer adopts
+//! the store's policy when connecting to it, so both components agree on which tags are valid.
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::documented_config;
+
+// TAG RANGE
+// ================================================================================================
+
+/// A range of note tags, both bounds included.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+pub struct TagRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl TagRange {
+    /// Returns `true` if `tag` is within the range.
+    pub fn contains(
+        &self,
+        tag: u64,
+    ) -> bool {
+        self.start <= tag && tag <= self.end
+    }
+
+    fn overlaps(
+        &self,
+        other: &TagRange,
+    ) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+impl Display for TagRange {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}..={}", self.start, self.end))
+    }
+}
+
+// TAG POLICY
+// ================================================================================================
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TagPolicyError {
+    #[error("tag range {0} is empty")]
+    EmptyRange(TagRange),
+    #[error("tag ranges {0} and {1} overlap")]
+    OverlappingRanges(TagRange, TagRange),
+    #[error("note tag {0} is not allowed by the tag policy")]
+    TagNotAllowed(u64),
+}
+
+/// The kind of a note tag, see [TagPolicy].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagKind {
+    Network,
+    Local,
+    Account,
+}
+
+documented_config! {
+    /// The ranges of the note tags accepted by the node, by kind of tag.
+    ///
+    /// A policy without any range accepts every tag.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct TagPolicy {
+        /// Tags of the notes consumed by the network.
+        pub network: Vec<TagRange>,
+        /// Tags matched locally by the clients.
+        pub local: Vec<TagRange>,
+        /// Tags of the notes targeting an account.
+        pub account: Vec<TagRange>,
+    }
+}
+
+impl TagPolicy {
+    /// Returns `true` if the policy accepts every tag.
+    pub fn is_unrestricted(&self) -> bool {
+        self.network.is_empty() && self.local.is_empty() && self.account.is_empty()
+    }
+
+    /// Ensures the ranges of the policy are not empty, and don't overlap.
+    pub fn validate(&self) -> Result<(), TagPolicyError> {
+        let ranges: Vec<TagRange> = self.ranges().map(|(_, range)| *range).collect();
+        for (index, range) in ranges.iter().enumerate() {
+            if range.start > range.end {
+                return Err(TagPolicyError::EmptyRange(*range));
+            }
+            if let Some(other) = ranges[..index].iter().find(|other| other.overlaps(range)) {
+                return Err(TagPolicyError::OverlappingRanges(*other, *range));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the kind of `tag`, or `None` if it is outside of every range.
+    pub fn kind(
+        &self,
+        tag: u64,
+    ) -> Option<TagKind> {
+        self.ranges().find(|(_, range)| range.contains(tag)).map(|(kind, _)| kind)
+    }
+
+    /// Ensures `tag` is accepted by the policy.
+    pub fn check(
+        &self,
+        tag: u64,
+    ) -> Result<(), TagPolicyError> {
+        if self.is_unrestricted() || self.kind(tag).is_some() {
+            Ok(())
+        } else {
+            Err(TagPolicyError::TagNotAllowed(tag))
+        }
+    }
+
+    fn ranges(&self) -> impl Iterator<Item = (TagKind, &TagRange)> {
+        let network = self.network.iter().map(|range| (TagKind::Network, range));
+        let local = self.local.iter().map(|range| (TagKind::Local, range));
+        let account = self.account.iter().map(|range| (TagKind::Account, range));
+        network.chain(local).chain(account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TagKind, TagPolicy, TagPolicyError, TagRange};
+
+    fn range(
+        start: u64,
+        end: u64,
+    ) -> TagRange {
+        TagRange { start, end }
+    }
+
+    #[test]
+    fn test_tag_policy() {
+        let policy = TagPolicy {
+            network: vec![range(0, 9)],
+            local: vec![range(10, 19), range(100, 100)],
+            account: vec![range(20, 29)],
+        };
+        assert_eq!(policy.validate(), Ok(()));
+
+        assert_eq!(policy.kind(0), Some(TagKind::Network));
+        assert_eq!(policy.kind(100), Some(TagKind::Local));
+        assert_eq!(policy.kind(29), Some(TagKind::Account));
+        assert_eq!(policy.check(15), Ok(()));
+        assert_eq!(policy.check(30), Err(TagPolicyError::TagNotAllowed(30)));
+
+        // a policy without ranges accepts every tag
+        assert_eq!(TagPolicy::default().check(u64::MAX), Ok(()));
+    }
+
+    #[test]
+    fn test_tag_policy_validation() {
+        let overlapping = TagPolicy {
+            network: vec![range(0, 10)],
+            account: vec![range(10, 20)],
+            ..Default::default()
+        };
+        assert_eq!(
+            overlapping.validate(),
+            Err(TagPolicyError::OverlappingRanges(range(0, 10), range(10, 20)))
+        );
+
+        let empty = TagPolicy {
+            local: vec![range(5, 4)],
+            ..Default::default()
+        };
+        assert_eq!(empty.validate(), Err(TagPolicyError::EmptyRange(range(5, 4))));
+    }
+}